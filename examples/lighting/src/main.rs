@@ -95,6 +95,7 @@ pub async fn run() {
             constant: 0.1,
             linear: 0.001,
             quadratic: 0.0001,
+            ..Default::default()
         },
     );
     let mut point0 = PointLight::new(
@@ -106,6 +107,7 @@ pub async fn run() {
             constant: 0.5,
             linear: 0.05,
             quadratic: 0.005,
+            ..Default::default()
         },
     );
     let mut point1 = PointLight::new(
@@ -117,6 +119,7 @@ pub async fn run() {
             constant: 0.5,
             linear: 0.05,
             quadratic: 0.005,
+            ..Default::default()
         },
     );
 
@@ -94,7 +94,13 @@ pub fn main() {
                     let distance = camera.position().z.abs();
                     let mut target = camera.position_at_pixel(position);
                     target.z = 0.0;
-                    camera.zoom_towards(&target, distance * 0.05 * delta.1, 0.00001, 10.0);
+                    three_d_asset::Camera::zoom_towards(
+                        &mut camera,
+                        &target,
+                        distance * 0.05 * delta.1,
+                        0.00001,
+                        10.0,
+                    );
                     redraw = true;
                 }
                 _ => {}
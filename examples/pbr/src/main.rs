@@ -121,6 +121,9 @@ pub async fn run() {
                     } else {
                         None
                     },
+                    height_texture: model.material.height_texture.clone(),
+                    height_scale: model.material.height_scale,
+                    parallax_steps: model.material.parallax_steps,
                     occlusion_strength: model.material.occlusion_strength,
                     occlusion_texture: if occlusion_map_enabled {
                         model.material.occlusion_texture.clone()
@@ -139,10 +142,13 @@ pub async fn run() {
                     },
                     render_states: model.material.render_states,
                     is_transparent: model.material.is_transparent,
+                    order_independent_transparency: model.material.order_independent_transparency,
                     lighting_model: LightingModel::Cook(
                         NormalDistributionFunction::TrowbridgeReitzGGX,
                         GeometryFunction::SmithSchlickGGX,
                     ),
+                    shading_mode: model.material.shading_mode,
+                    alpha_cutout: model.material.alpha_cutout,
                 };
                 model.render_with_material(&material, &camera, &[&light]);
                 gui.render()
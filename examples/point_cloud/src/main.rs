@@ -33,15 +33,11 @@ pub async fn run() {
         .unwrap();
     let cpu_point_cloud: PointCloud = loaded.deserialize("hand.pcd").unwrap();
 
-    let mut point_mesh = CpuMesh::sphere(4);
-    point_mesh.transform(&Mat4::from_scale(0.001)).unwrap();
-
-    let mut point_cloud = Gm {
-        geometry: InstancedMesh::new(&context, &cpu_point_cloud.into(), &point_mesh),
+    // The points are stored relative to the center of the point cloud, so the point cloud is centered at the origin
+    let point_cloud = Gm {
+        geometry: Points::new(&context, &cpu_point_cloud, 0.002),
         material: ColorMaterial::default(),
     };
-    let c = -point_cloud.aabb().center();
-    point_cloud.set_transformation(Mat4::from_translation(c));
 
     // main loop
     window.render_loop(move |mut frame_input| {
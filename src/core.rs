@@ -28,6 +28,34 @@ mod program;
 #[doc(inline)]
 pub use program::*;
 
+mod capabilities;
+#[doc(inline)]
+pub use capabilities::*;
+
+mod shader_dialect;
+#[doc(inline)]
+pub use shader_dialect::*;
+
+mod query;
+#[doc(inline)]
+pub use query::*;
+
+mod render_profiler;
+#[doc(inline)]
+pub use render_profiler::*;
+
+mod frame_graph;
+#[doc(inline)]
+pub use frame_graph::*;
+
+mod color_space_validation;
+#[doc(inline)]
+pub use color_space_validation::*;
+
+mod upload_queue;
+#[doc(inline)]
+pub use upload_queue::*;
+
 mod scissor_box;
 #[doc(inline)]
 pub use scissor_box::*;
@@ -59,6 +87,10 @@ pub enum CoreError {
     ShaderCompilation(String, String, String),
     #[error("failed to link shader program: {0}")]
     ShaderLink(String),
+    #[error("the compressed texture format {0:?} cannot be read back")]
+    CompressedTextureRead(CompressedFormat),
+    #[error("failed creating texture array: {0}")]
+    TextureArrayCreation(String),
 }
 
 pub(crate) fn full_screen_draw(
@@ -112,11 +144,24 @@ fn from_byte_slice<T: DataType>(data: &[u8]) -> &[T] {
 }
 
 fn format_from_data_type<T: DataType>() -> u32 {
-    match T::size() {
-        1 => crate::context::RED,
-        2 => crate::context::RG,
-        3 => crate::context::RGB,
-        4 => crate::context::RGBA,
+    // Integer formats, in contrast to normalized unsigned bytes, must be transferred with the integer formats
+    let integer = matches!(
+        T::data_type(),
+        crate::context::BYTE
+            | crate::context::SHORT
+            | crate::context::UNSIGNED_SHORT
+            | crate::context::INT
+            | crate::context::UNSIGNED_INT
+    );
+    match (T::size(), integer) {
+        (1, false) => crate::context::RED,
+        (2, false) => crate::context::RG,
+        (3, false) => crate::context::RGB,
+        (4, false) => crate::context::RGBA,
+        (1, true) => crate::context::RED_INTEGER,
+        (2, true) => crate::context::RG_INTEGER,
+        (3, true) => crate::context::RGB_INTEGER,
+        (4, true) => crate::context::RGBA_INTEGER,
         _ => unreachable!(),
     }
 }
@@ -9,6 +9,10 @@ mod vertex_buffer;
 #[doc(inline)]
 pub use vertex_buffer::*;
 
+mod vertex_attributes;
+#[doc(inline)]
+pub use vertex_attributes::*;
+
 mod instance_buffer;
 #[doc(inline)]
 pub use instance_buffer::*;
@@ -17,6 +21,10 @@ mod uniform_buffer;
 #[doc(inline)]
 pub use uniform_buffer::*;
 
+mod ping_pong_buffer;
+#[doc(inline)]
+pub use ping_pong_buffer::*;
+
 use crate::core::*;
 use data_type::*;
 
@@ -71,6 +79,24 @@ impl Buffer {
         buffer
     }
 
+    pub fn new_with_capacity<T: BufferDataType>(context: &Context, count: u32) -> Self {
+        let mut buffer = Self::new(context);
+        buffer.bind();
+        unsafe {
+            context.buffer_data_size(
+                crate::context::ARRAY_BUFFER,
+                (count as usize * std::mem::size_of::<T>()) as i32,
+                crate::context::DYNAMIC_COPY,
+            );
+            context.bind_buffer(crate::context::ARRAY_BUFFER, None);
+        }
+        buffer.attribute_count = count;
+        buffer.data_type = T::data_type();
+        buffer.data_size = T::size();
+        buffer.normalized = T::normalized();
+        buffer
+    }
+
     pub fn fill<T: BufferDataType>(&mut self, data: &[T]) {
         self.bind();
         unsafe {
@@ -91,6 +117,26 @@ impl Buffer {
         self.normalized = T::normalized();
     }
 
+    pub fn fill_subset<T: BufferDataType>(&mut self, offset: u32, data: &[T]) {
+        if offset as usize + data.len() > self.attribute_count as usize {
+            panic!(
+                "the subset at offset {} with {} values is outside the buffer which contains {} values",
+                offset,
+                data.len(),
+                self.attribute_count
+            );
+        }
+        self.bind();
+        unsafe {
+            self.context.buffer_sub_data_u8_slice(
+                crate::context::ARRAY_BUFFER,
+                (offset as usize * std::mem::size_of::<T>()) as i32,
+                to_byte_slice(data),
+            );
+            self.context.bind_buffer(crate::context::ARRAY_BUFFER, None);
+        }
+    }
+
     pub fn attribute_count(&self) -> u32 {
         self.attribute_count
     }
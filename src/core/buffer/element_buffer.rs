@@ -60,6 +60,25 @@ impl ElementBuffer {
         buffer
     }
 
+    pub(in crate::core) fn new_with_capacity<T: ElementBufferDataType>(
+        context: &Context,
+        count: usize,
+    ) -> Self {
+        let mut buffer = Self::new(context);
+        buffer.bind();
+        unsafe {
+            context.buffer_data_size(
+                crate::context::ELEMENT_ARRAY_BUFFER,
+                (count * std::mem::size_of::<T>()) as i32,
+                crate::context::STATIC_DRAW,
+            );
+            context.bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
+        }
+        buffer.count = count;
+        buffer.data_type = T::data_type();
+        buffer
+    }
+
     ///
     /// Fills the buffer with the given indices which must be divisable by 3.
     ///
@@ -92,6 +111,10 @@ impl ElementBuffer {
         self.count / 3
     }
 
+    pub(in crate::core) fn id(&self) -> crate::context::Buffer {
+        self.id
+    }
+
     pub(crate) fn bind(&self) {
         unsafe {
             self.context
@@ -0,0 +1,55 @@
+use crate::core::*;
+
+///
+/// Two [VertexBuffer]s where one is read from (the source) and the other is written to (the target), for example using [Program::transform_feedback].
+/// After each update, call [PingPongBuffer::swap] to use the result of the update as the source of the next update.
+/// This is needed since a buffer cannot be read from and written to in the same draw call.
+///
+pub struct PingPongBuffer {
+    buffers: [VertexBuffer; 2],
+    current: usize,
+}
+
+impl PingPongBuffer {
+    ///
+    /// Creates a new ping-pong buffer where both buffers are filled with the given data.
+    ///
+    pub fn new_with_data<T: BufferDataType>(context: &Context, data: &[T]) -> Self {
+        Self {
+            buffers: [
+                VertexBuffer::new_with_data(context, data),
+                VertexBuffer::new_with_data(context, data),
+            ],
+            current: 0,
+        }
+    }
+
+    ///
+    /// Returns the buffer containing the result of the latest update, ie. the buffer to read from.
+    ///
+    pub fn source(&self) -> &VertexBuffer {
+        &self.buffers[self.current]
+    }
+
+    ///
+    /// Returns the buffer to write the next update to.
+    ///
+    pub fn target(&self) -> &VertexBuffer {
+        &self.buffers[1 - self.current]
+    }
+
+    ///
+    /// Swaps the source and target buffers.
+    ///
+    pub fn swap(&mut self) {
+        self.current = 1 - self.current;
+    }
+
+    ///
+    /// Fills both buffers with the given data.
+    ///
+    pub fn fill<T: BufferDataType>(&mut self, data: &[T]) {
+        self.buffers[0].fill(data);
+        self.buffers[1].fill(data);
+    }
+}
@@ -1,14 +1,198 @@
 use crate::core::*;
 
+///
+/// The type of a variable in a uniform block, see [UniformBlockLayout].
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UniformBlockType {
+    /// A `float`.
+    Float,
+    /// A `vec2`.
+    Vec2,
+    /// A `vec3`.
+    Vec3,
+    /// A `vec4`.
+    Vec4,
+    /// A `mat3`.
+    Mat3,
+    /// A `mat4`.
+    Mat4,
+}
+
+impl UniformBlockType {
+    /// The number of elements of this type.
+    fn element_count(&self) -> usize {
+        match self {
+            Self::Float => 1,
+            Self::Vec2 => 2,
+            Self::Vec3 => 3,
+            Self::Vec4 => 4,
+            Self::Mat3 => 9,
+            Self::Mat4 => 16,
+        }
+    }
+
+    /// The std140 base alignment in floats.
+    fn alignment(&self) -> usize {
+        match self {
+            Self::Float => 1,
+            Self::Vec2 => 2,
+            _ => 4,
+        }
+    }
+
+    /// The number of floats a single value of this type occupies in std140 layout.
+    fn std140_size(&self) -> usize {
+        match self {
+            Self::Float => 1,
+            Self::Vec2 => 2,
+            Self::Vec3 => 3,
+            Self::Vec4 => 4,
+            Self::Mat3 => 12,
+            Self::Mat4 => 16,
+        }
+    }
+
+    /// The number of elements in each column (or for non-matrix types, in the whole value) and the number of columns.
+    fn columns(&self) -> (usize, usize) {
+        match self {
+            Self::Mat3 => (3, 3),
+            Self::Mat4 => (4, 4),
+            _ => (self.element_count(), 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct UniformBlockVariable {
+    name: String,
+    data_type: UniformBlockType,
+    array_length: Option<usize>,
+    offset: usize,
+    packed_offset: usize,
+}
+
+impl UniformBlockVariable {
+    fn element_count(&self) -> usize {
+        self.data_type.element_count() * self.array_length.unwrap_or(1)
+    }
+
+    fn stride(&self) -> usize {
+        if self.array_length.is_some() {
+            // Array elements are rounded up to the size of a vec4 in std140 layout
+            (self.data_type.std140_size() + 3) / 4 * 4
+        } else {
+            self.data_type.std140_size()
+        }
+    }
+}
+
+///
+/// Describes the variables in a uniform block in the order they are declared in the shader, so that the std140 offsets can be computed.
+/// For example, the following builder
+/// ```
+/// # use three_d::*;
+/// let layout = UniformBlockLayout::new()
+///     .add("viewProjection", UniformBlockType::Mat4)
+///     .add("cameraPosition", UniformBlockType::Vec3)
+///     .add_array("weights", UniformBlockType::Float, 4);
+/// ```
+/// describes the uniform block
+/// ```no_rust
+/// layout (std140) uniform Block
+/// {
+///     mat4 viewProjection;
+///     vec3 cameraPosition;
+///     float weights[4];
+/// };
+/// ```
+///
+#[derive(Debug, Clone, Default)]
+pub struct UniformBlockLayout {
+    variables: Vec<UniformBlockVariable>,
+    size: usize,
+    packed_size: usize,
+}
+
+impl UniformBlockLayout {
+    ///
+    /// Creates a new empty layout.
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Adds a variable with the given name and type to the end of the layout.
+    ///
+    pub fn add(self, name: &str, data_type: UniformBlockType) -> Self {
+        self.add_variable(name, data_type, None)
+    }
+
+    ///
+    /// Adds an array variable with the given name, element type and length to the end of the layout.
+    ///
+    pub fn add_array(self, name: &str, data_type: UniformBlockType, length: u32) -> Self {
+        self.add_variable(name, data_type, Some(length as usize))
+    }
+
+    ///
+    /// Returns the byte offset of the variable with the given name if it is part of the layout.
+    ///
+    pub fn offset(&self, name: &str) -> Option<usize> {
+        self.variables
+            .iter()
+            .find(|v| v.name == name)
+            .map(|v| v.offset * std::mem::size_of::<f32>())
+    }
+
+    ///
+    /// Returns the size of the uniform block in bytes.
+    ///
+    pub fn size(&self) -> usize {
+        (self.size + 3) / 4 * 4 * std::mem::size_of::<f32>()
+    }
+
+    fn add_variable(
+        mut self,
+        name: &str,
+        data_type: UniformBlockType,
+        array_length: Option<usize>,
+    ) -> Self {
+        let alignment = if array_length.is_some() {
+            4
+        } else {
+            data_type.alignment()
+        };
+        let offset = (self.size + alignment - 1) / alignment * alignment;
+        let variable = UniformBlockVariable {
+            name: name.to_string(),
+            data_type,
+            array_length,
+            offset,
+            packed_offset: self.packed_size,
+        };
+        self.size = offset + variable.stride() * array_length.unwrap_or(1);
+        self.packed_size += variable.element_count();
+        self.variables.push(variable);
+        self
+    }
+}
+
 ///
 /// A buffer for transferring a set of uniform variables to the shader program
 /// (see also [use_uniform_block](crate::core::Program::use_uniform_block)).
+/// The data is laid out according to the std140 layout rules, so the uniform block in the shader must be declared with `layout (std140)`.
+///
+/// A uniform buffer can be shared between several programs, for example to send the camera data once per frame instead of once per program.
 ///
 pub struct UniformBuffer {
     context: Context,
     id: crate::context::Buffer,
-    offsets: Vec<usize>,
+    layout: UniformBlockLayout,
     data: Vec<f32>,
+    // The values of all variables tightly packed, so that they can be returned as a slice by UniformBuffer::get
+    values: Vec<f32>,
 }
 
 impl UniformBuffer {
@@ -16,27 +200,63 @@ impl UniformBuffer {
     /// Creates a new uniform buffer with room for a set of variables of varying length defined by the `sizes` argument.
     /// So for example if you create a uniform buffer with `&[3, 1, 4, 16]` as the `sizes` argument, you will have a uniform buffer that has four variables:
     /// The first with 3 elements (a [Vec3]), the second with 1 element (a `f32`), the third with four elements (a [Vec4]) and the last with 16 elements (a [Mat4]).
+    /// A size of 9 is interpreted as a [Mat3] and any other size than 1, 2, 3, 4, 9 and 16 as an array of `f32`.
     /// The variables are initialized to 0.
     ///
+    /// Use [UniformBuffer::new_with_layout] to give the variables names.
+    ///
     pub fn new(context: &Context, sizes: &[u32]) -> UniformBuffer {
-        let id = unsafe { context.create_buffer().expect("Failed creating buffer") };
-
-        let mut offsets = Vec::new();
-        let mut length = 0;
-        for size in sizes {
-            offsets.push(length);
-            length += *size as usize;
+        let mut layout = UniformBlockLayout::new();
+        for (index, size) in sizes.iter().enumerate() {
+            let name = index.to_string();
+            layout = match size {
+                1 => layout.add(&name, UniformBlockType::Float),
+                2 => layout.add(&name, UniformBlockType::Vec2),
+                3 => layout.add(&name, UniformBlockType::Vec3),
+                4 => layout.add(&name, UniformBlockType::Vec4),
+                9 => layout.add(&name, UniformBlockType::Mat3),
+                16 => layout.add(&name, UniformBlockType::Mat4),
+                _ => layout.add_array(&name, UniformBlockType::Float, *size),
+            };
         }
+        Self::new_with_layout(context, layout)
+    }
+
+    ///
+    /// Creates a new uniform buffer with the variables described by the given layout. The variables are initialized to 0.
+    ///
+    pub fn new_with_layout(context: &Context, layout: UniformBlockLayout) -> UniformBuffer {
+        let id = unsafe { context.create_buffer().expect("Failed creating buffer") };
         let buffer = UniformBuffer {
             context: context.clone(),
             id,
-            offsets,
-            data: vec![0.0; length],
+            data: vec![0.0; layout.size() / std::mem::size_of::<f32>()],
+            values: vec![0.0; layout.packed_size],
+            layout,
         };
-        buffer.send();
+        unsafe {
+            buffer
+                .context
+                .bind_buffer(crate::context::UNIFORM_BUFFER, Some(buffer.id));
+            buffer.context.buffer_data_u8_slice(
+                crate::context::UNIFORM_BUFFER,
+                to_byte_slice(&buffer.data),
+                crate::context::DYNAMIC_DRAW,
+            );
+            buffer
+                .context
+                .bind_buffer(crate::context::UNIFORM_BUFFER, None);
+        }
         buffer
     }
 
+    ///
+    /// Returns the layout of this uniform buffer.
+    ///
+    pub fn layout(&self) -> &UniformBlockLayout {
+        &self.layout
+    }
+
     pub(crate) fn bind(&self, id: u32) {
         unsafe {
             self.context
@@ -46,64 +266,80 @@ impl UniformBuffer {
 
     ///
     /// Update the values of the variable at the given index with the given data.
+    /// The data is tightly packed, for example a [Mat3] is given as 9 values and an array of [Vec3] as 3 values per element.
     ///
     /// # Panic
-    /// Will panic if the index is not in the range `[0-max]` where `max` is the length of the `sizes` argument given at construction.
+    /// Will panic if the index is not in the range `[0-max]` where `max` is the number of variables in the layout.
     /// Will panic if the data length does not match the element count of the variable (defined at construction) at the given index.
     ///
     pub fn update(&mut self, index: u32, data: &[f32]) {
-        if let Some((offset, length)) = self.offset_length(index as usize) {
-            if data.len() != length {
-                panic!(
-                    "data for element at index {0} has length {1} but a length of {2} was expected",
-                    index,
-                    data.len(),
-                    length,
-                );
-            }
-            self.data
-                .splice(offset..offset + length, data.iter().cloned());
-            self.send();
-        } else {
+        if index as usize >= self.layout.variables.len() {
             panic!(
                 "the index {} is outside the expected range [0, {}]",
                 index,
-                self.offsets.len() - 1
+                self.layout.variables.len() as i32 - 1
+            );
+        }
+        let variable = &self.layout.variables[index as usize];
+        if data.len() != variable.element_count() {
+            panic!(
+                "data for element at index {0} has length {1} but a length of {2} was expected",
+                index,
+                data.len(),
+                variable.element_count(),
             );
         }
-        //TODO: Send to GPU (contextBufferSubData)
+        self.values[variable.packed_offset..variable.packed_offset + data.len()]
+            .copy_from_slice(data);
+        let (column_length, column_count) = variable.data_type.columns();
+        let column_stride = if column_count > 1 { 4 } else { column_length };
+        let stride = variable.stride();
+        for (element, values) in data.chunks(variable.data_type.element_count()).enumerate() {
+            for (column, values) in values.chunks(column_length).enumerate() {
+                let offset = variable.offset + element * stride + column * column_stride;
+                self.data[offset..offset + column_length].copy_from_slice(values);
+            }
+        }
+        let start = variable.offset;
+        let end = start + stride * variable.array_length.unwrap_or(1);
+        self.send(start, end.min(self.data.len()));
     }
 
     ///
-    /// Returns the values of the variable at the given index if inside the range of variables, otherwise `None`.
+    /// Update the values of the variable with the given name with the given data, see [UniformBuffer::update].
     ///
-    pub fn get(&self, index: u32) -> Option<&[f32]> {
-        self.offset_length(index as usize)
-            .map(|(offset, length)| &self.data[offset..offset + length])
+    /// # Panic
+    /// Will panic if the variable is not part of the layout.
+    /// Will panic if the data length does not match the element count of the variable.
+    ///
+    pub fn update_by_name(&mut self, name: &str, data: &[f32]) {
+        let index = self
+            .layout
+            .variables
+            .iter()
+            .position(|v| v.name == name)
+            .unwrap_or_else(|| panic!("the variable {} is not part of the uniform block", name));
+        self.update(index as u32, data);
     }
 
-    fn offset_length(&self, index: usize) -> Option<(usize, usize)> {
-        if index >= self.offsets.len() {
-            None
-        } else {
-            let offset = self.offsets[index];
-            let length = if index + 1 == self.offsets.len() {
-                self.data.len()
-            } else {
-                self.offsets[index + 1]
-            } - offset;
-            Some((offset, length))
-        }
+    ///
+    /// Returns the values of the variable at the given index if inside the range of variables, otherwise `None`.
+    /// The values are returned tightly packed in the same way as given to [UniformBuffer::update].
+    ///
+    pub fn get(&self, index: u32) -> Option<&[f32]> {
+        self.layout.variables.get(index as usize).map(|variable| {
+            &self.values[variable.packed_offset..variable.packed_offset + variable.element_count()]
+        })
     }
 
-    fn send(&self) {
+    fn send(&self, start: usize, end: usize) {
         unsafe {
             self.context
                 .bind_buffer(crate::context::UNIFORM_BUFFER, Some(self.id));
-            self.context.buffer_data_u8_slice(
+            self.context.buffer_sub_data_u8_slice(
                 crate::context::UNIFORM_BUFFER,
-                to_byte_slice(&self.data),
-                crate::context::STATIC_DRAW,
+                (start * std::mem::size_of::<f32>()) as i32,
+                to_byte_slice(&self.data[start..end]),
             );
             self.context
                 .bind_buffer(crate::context::UNIFORM_BUFFER, None);
@@ -0,0 +1,140 @@
+use crate::core::*;
+use std::collections::HashMap;
+
+///
+/// The semantic of a vertex attribute, ie. what the data in a [VertexBuffer] represents.
+/// Each semantic corresponds to an attribute name in the shaders, see [VertexAttribute::name],
+/// which makes it possible for custom shaders to bind vertex buffers by semantic instead of by a hard coded name.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VertexAttribute {
+    /// The position of the vertex, `vec3`.
+    Position,
+    /// The normal of the vertex, `vec3`.
+    Normal,
+    /// The tangent of the vertex where the fourth component is the handedness of the tangent space, `vec4`.
+    Tangent,
+    /// The first set of uv coordinates, `vec2`.
+    Uv0,
+    /// The second set of uv coordinates, for example used for light maps, `vec2`.
+    Uv1,
+    /// The color of the vertex in linear sRGB, `vec4`.
+    Color,
+    /// The indices of the joints affecting the vertex when skinning, `vec4`.
+    Joints,
+    /// The weights of the joints affecting the vertex when skinning, `vec4`.
+    Weights,
+    /// A custom attribute with the given attribute name.
+    Custom(String),
+}
+
+impl VertexAttribute {
+    ///
+    /// Returns the name of the attribute in the shaders.
+    ///
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Position => "position",
+            Self::Normal => "normal",
+            Self::Tangent => "tangent",
+            Self::Uv0 => "uv_coordinates",
+            Self::Uv1 => "uv_coordinates_1",
+            Self::Color => "color",
+            Self::Joints => "joints",
+            Self::Weights => "weights",
+            Self::Custom(name) => name,
+        }
+    }
+}
+
+impl std::fmt::Display for VertexAttribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+///
+/// A set of [VertexBuffer]s identified by their [VertexAttribute] semantic.
+/// Use [VertexAttributes::use_attributes] to send all the attributes required by a [Program] to the program.
+///
+#[derive(Default)]
+pub struct VertexAttributes {
+    buffers: HashMap<VertexAttribute, VertexBuffer>,
+}
+
+impl VertexAttributes {
+    ///
+    /// Creates a new empty set of vertex attributes.
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Inserts the vertex buffer with the given semantic and returns the vertex buffer previously inserted with that semantic, if any.
+    ///
+    pub fn insert(
+        &mut self,
+        attribute: VertexAttribute,
+        buffer: VertexBuffer,
+    ) -> Option<VertexBuffer> {
+        self.buffers.insert(attribute, buffer)
+    }
+
+    ///
+    /// Removes and returns the vertex buffer with the given semantic.
+    ///
+    pub fn remove(&mut self, attribute: &VertexAttribute) -> Option<VertexBuffer> {
+        self.buffers.remove(attribute)
+    }
+
+    ///
+    /// Returns the vertex buffer with the given semantic.
+    ///
+    pub fn get(&self, attribute: &VertexAttribute) -> Option<&VertexBuffer> {
+        self.buffers.get(attribute)
+    }
+
+    ///
+    /// Returns the vertex buffer with the given semantic.
+    ///
+    pub fn get_mut(&mut self, attribute: &VertexAttribute) -> Option<&mut VertexBuffer> {
+        self.buffers.get_mut(attribute)
+    }
+
+    ///
+    /// Returns whether or not this set contains a vertex buffer with the given semantic.
+    ///
+    pub fn contains(&self, attribute: &VertexAttribute) -> bool {
+        self.buffers.contains_key(attribute)
+    }
+
+    ///
+    /// Returns an iterator over the semantics and vertex buffers in this set.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (&VertexAttribute, &VertexBuffer)> {
+        self.buffers.iter()
+    }
+
+    ///
+    /// Returns whether or not this set is empty.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    ///
+    /// Sends each vertex buffer in this set, which is required by the given program, to the program.
+    /// Returns the semantics of the vertex buffers that were sent.
+    ///
+    pub fn use_attributes(&self, program: &Program) -> Vec<VertexAttribute> {
+        let mut used = Vec::new();
+        for (attribute, buffer) in self.buffers.iter() {
+            if program.requires_attribute(attribute.name()) {
+                program.use_vertex_attribute(attribute.name(), buffer);
+                used.push(attribute.clone());
+            }
+        }
+        used
+    }
+}
@@ -29,6 +29,16 @@ impl VertexBuffer {
         }
     }
 
+    ///
+    /// Creates a new vertex buffer with room for `count` values of type `T` which are left uninitialized.
+    /// This is useful for a buffer which is written to on the GPU, for example using [Program::transform_feedback].
+    ///
+    pub fn new_with_capacity<T: BufferDataType>(context: &Context, count: u32) -> Self {
+        Self {
+            buffer: Buffer::new_with_capacity::<T>(context, count),
+        }
+    }
+
     ///
     /// Fills the vertex buffer with the given data. The data should be in the same format as specified in the shader.
     /// As an example, if specified as `vec3` in the shader it needs to be specified as an array of `Vector3<T>` where `T` is a primitive type that implements [BufferDataType], for example can be f16 or f32.
@@ -37,6 +47,19 @@ impl VertexBuffer {
         self.buffer.fill(data);
     }
 
+    ///
+    /// Replaces the values starting at the given offset, measured in number of values of type `T`, with the given data without reallocating the buffer.
+    /// This is much cheaper than [VertexBuffer::fill] when only a small part of the buffer changes, for example when painting vertex colors.
+    /// The data must have the same type as the data the buffer was filled with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subset is outside the buffer.
+    ///
+    pub fn fill_subset<T: BufferDataType>(&mut self, offset: u32, data: &[T]) {
+        self.buffer.fill_subset(offset, data);
+    }
+
     ///
     /// The number of values in the buffer.
     ///
@@ -55,6 +78,10 @@ impl VertexBuffer {
         self.buffer.bind();
     }
 
+    pub(in crate::core) fn id(&self) -> crate::context::Buffer {
+        self.buffer.id
+    }
+
     pub(in crate::core) fn data_type(&self) -> u32 {
         self.buffer.data_type
     }
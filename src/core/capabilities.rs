@@ -0,0 +1,201 @@
+use std::collections::HashSet;
+
+///
+/// The limits and optional features of the graphics backend, queried once when the [Context](crate::core::Context) is created, see [Context::capabilities](crate::core::Context::capabilities).
+/// Use this to choose quality settings, for example the shadow map resolution or whether to use HDR render targets, instead of assuming that a feature is available.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    /// Whether or not this is an OpenGL ES 2.0 or WebGL 1 context, see [Context::is_legacy](crate::core::Context::is_legacy).
+    pub legacy: bool,
+    /// The maximum width and height of a 2D texture.
+    pub max_texture_size: u32,
+    /// The maximum width and height of a cube map texture.
+    pub max_cube_map_texture_size: u32,
+    /// The maximum width, height and depth of a 3D texture. Zero if 3D textures are not supported.
+    pub max_3d_texture_size: u32,
+    /// The maximum number of layers in a texture array. Zero if texture arrays are not supported.
+    pub max_array_texture_layers: u32,
+    /// The maximum number of color attachments of a render target.
+    pub max_color_attachments: u32,
+    /// The maximum number of color attachments that can be written to in one render pass.
+    pub max_draw_buffers: u32,
+    /// The maximum number of samples of a multisample render target. Zero if multisampling is not supported.
+    pub max_samples: u32,
+    /// The maximum number of textures that can be sampled in a fragment shader.
+    pub max_texture_image_units: u32,
+    /// The maximum number of vertex attributes.
+    pub max_vertex_attributes: u32,
+    /// The maximum anisotropy of texture filtering, or `None` if anisotropic filtering is not supported.
+    pub max_anisotropy: Option<f32>,
+    /// Whether or not it is possible to render to 32 bit floating point textures.
+    pub float_render_targets: bool,
+    /// Whether or not it is possible to render to 16 bit floating point textures.
+    pub half_float_render_targets: bool,
+    /// Whether or not blending is supported when rendering to 32 bit floating point textures, which requires the `EXT_float_blend` extension on OpenGL ES and web.
+    pub float_blend: bool,
+    /// Whether or not 32 bit floating point textures can be sampled with linear interpolation.
+    pub float_linear_filtering: bool,
+    /// Whether or not 32 bit floating point textures are supported.
+    pub float_textures: bool,
+    /// Whether or not 16 bit floating point textures are supported, which requires the `OES_texture_half_float` extension on legacy contexts.
+    pub half_float_textures: bool,
+    /// Whether or not textures with one or two channels are supported.
+    pub rg_textures: bool,
+    /// Whether or not mip maps can be generated for 16 and 32 bit floating point textures with three channels, which requires these formats to be color-renderable, which is only the case on desktop.
+    pub rgb_float_mip_maps: bool,
+    /// Whether or not cube map array textures are supported.
+    pub cube_map_arrays: bool,
+    /// Whether or not timer queries are supported, see [TimerQuery](crate::core::TimerQuery).
+    pub timer_queries: bool,
+    /// Whether or not user clip planes can be evaluated in hardware using `gl_ClipDistance`, otherwise they are evaluated by discarding fragments, see [ClipPlanes](crate::core::ClipPlanes).
+    pub clip_distances: bool,
+    /// Whether or not cube maps are filtered across the edges between the sides, which is not the case on legacy contexts (OpenGL ES 2.0 and WebGL 1).
+    /// Without seamless filtering, seams can be visible at the edges of blurry cube maps, see [EnvironmentOptions::edge_fixup](crate::renderer::EnvironmentOptions::edge_fixup).
+    pub seamless_cube_maps: bool,
+    /// Whether or not the S3TC compressed formats BC1, BC2 and BC3 are supported, see [CompressedFormat](crate::core::CompressedFormat).
+    pub s3tc_textures: bool,
+    /// Whether or not the RGTC compressed formats BC4 and BC5 are supported, see [CompressedFormat](crate::core::CompressedFormat).
+    pub rgtc_textures: bool,
+    /// Whether or not the BPTC compressed formats BC6H and BC7 are supported, see [CompressedFormat](crate::core::CompressedFormat).
+    pub bptc_textures: bool,
+    /// Whether or not the ETC2 and EAC compressed formats are supported, see [CompressedFormat](crate::core::CompressedFormat).
+    pub etc2_textures: bool,
+    /// Whether or not the ASTC compressed formats are supported, see [CompressedFormat](crate::core::CompressedFormat).
+    pub astc_textures: bool,
+}
+
+impl Capabilities {
+    pub(in crate::core) fn new(context: &crate::context::Context) -> Self {
+        use crate::context::HasContext;
+        let version = context.version();
+        let legacy = version.is_embedded && version.major < 3;
+        let extensions = context.supported_extensions();
+        let get = |parameter: u32| unsafe { context.get_parameter_i32(parameter).max(0) as u32 };
+        let desktop = !version.is_embedded && !cfg!(target_arch = "wasm32");
+        Self {
+            legacy,
+            max_texture_size: get(crate::context::MAX_TEXTURE_SIZE),
+            max_cube_map_texture_size: get(crate::context::MAX_CUBE_MAP_TEXTURE_SIZE),
+            max_3d_texture_size: if legacy {
+                0
+            } else {
+                get(crate::context::MAX_3D_TEXTURE_SIZE)
+            },
+            max_array_texture_layers: if legacy {
+                0
+            } else {
+                get(crate::context::MAX_ARRAY_TEXTURE_LAYERS)
+            },
+            max_color_attachments: if legacy {
+                1
+            } else {
+                get(crate::context::MAX_COLOR_ATTACHMENTS)
+            },
+            max_draw_buffers: if legacy {
+                1
+            } else {
+                get(crate::context::MAX_DRAW_BUFFERS)
+            },
+            max_samples: if legacy {
+                0
+            } else {
+                get(crate::context::MAX_SAMPLES)
+            },
+            max_texture_image_units: get(crate::context::MAX_TEXTURE_IMAGE_UNITS),
+            max_vertex_attributes: get(crate::context::MAX_VERTEX_ATTRIBS),
+            max_anisotropy: if has_extension(
+                extensions,
+                &[
+                    "EXT_texture_filter_anisotropic",
+                    "ARB_texture_filter_anisotropic",
+                ],
+            ) {
+                Some(unsafe {
+                    context.get_parameter_f32(crate::context::MAX_TEXTURE_MAX_ANISOTROPY_EXT)
+                })
+            } else {
+                None
+            },
+            float_render_targets: desktop || has_extension(extensions, &["EXT_color_buffer_float"]),
+            half_float_render_targets: desktop
+                || has_extension(
+                    extensions,
+                    &["EXT_color_buffer_float", "EXT_color_buffer_half_float"],
+                ),
+            float_blend: desktop || has_extension(extensions, &["EXT_float_blend"]),
+            float_linear_filtering: desktop
+                || has_extension(extensions, &["OES_texture_float_linear"]),
+            float_textures: !legacy || has_extension(extensions, &["OES_texture_float"]),
+            half_float_textures: !legacy || has_extension(extensions, &["OES_texture_half_float"]),
+            rg_textures: !legacy || has_extension(extensions, &["EXT_texture_rg"]),
+            rgb_float_mip_maps: desktop,
+            cube_map_arrays: if version.is_embedded || cfg!(target_arch = "wasm32") {
+                (version.major > 3 || (version.major == 3 && version.minor >= 2))
+                    || has_extension(extensions, &["EXT_texture_cube_map_array"])
+            } else {
+                version.major >= 4 || has_extension(extensions, &["ARB_texture_cube_map_array"])
+            },
+            timer_queries: if cfg!(target_arch = "wasm32") {
+                extensions.contains("EXT_disjoint_timer_query_webgl2")
+            } else if version.is_embedded {
+                extensions.contains("GL_EXT_disjoint_timer_query")
+            } else {
+                true
+            },
+            clip_distances: if cfg!(target_arch = "wasm32") {
+                false
+            } else if version.is_embedded {
+                !legacy && has_extension(extensions, &["EXT_clip_cull_distance"])
+            } else {
+                true
+            },
+            seamless_cube_maps: !legacy,
+            s3tc_textures: has_extension(
+                extensions,
+                &[
+                    "EXT_texture_compression_s3tc",
+                    "WEBGL_compressed_texture_s3tc",
+                ],
+            ),
+            rgtc_textures: desktop && version.major >= 3
+                || has_extension(
+                    extensions,
+                    &[
+                        "EXT_texture_compression_rgtc",
+                        "ARB_texture_compression_rgtc",
+                    ],
+                ),
+            bptc_textures: desktop
+                && (version.major > 4 || (version.major == 4 && version.minor >= 2))
+                || has_extension(
+                    extensions,
+                    &[
+                        "EXT_texture_compression_bptc",
+                        "ARB_texture_compression_bptc",
+                    ],
+                ),
+            // ETC2 is a core feature of OpenGL ES 3.0 and OpenGL 4.3, but not of WebGL 2
+            etc2_textures: (version.is_embedded && !legacy && !cfg!(target_arch = "wasm32"))
+                || (desktop && (version.major > 4 || (version.major == 4 && version.minor >= 3)))
+                || has_extension(
+                    extensions,
+                    &["WEBGL_compressed_texture_etc", "ARB_ES3_compatibility"],
+                ),
+            astc_textures: has_extension(
+                extensions,
+                &[
+                    "KHR_texture_compression_astc_ldr",
+                    "WEBGL_compressed_texture_astc",
+                ],
+            ),
+        }
+    }
+}
+
+// Extensions are listed with a GL_ prefix on native platforms and without on web.
+fn has_extension(extensions: &HashSet<String>, names: &[&str]) -> bool {
+    names
+        .iter()
+        .any(|name| extensions.contains(*name) || extensions.contains(&format!("GL_{}", name)))
+}
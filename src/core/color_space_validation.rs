@@ -0,0 +1,197 @@
+use crate::core::*;
+use std::collections::HashMap;
+
+///
+/// Describes how the colors written by a shader program are encoded, ie. which of the color space conversions at the end of a render pipeline have been applied.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ColorEncoding {
+    /// Whether or not tone mapping has been applied, ie. the colors are mapped from HDR to the `[0,1]` range.
+    pub tone_mapped: bool,
+    /// Whether or not the colors are encoded in the non-linear sRGB color space.
+    pub srgb: bool,
+}
+
+impl ColorEncoding {
+    ///
+    /// Linear colors without tone mapping, which is the expected input of lighting calculations and post effects.
+    ///
+    pub const LINEAR: Self = Self {
+        tone_mapped: false,
+        srgb: false,
+    };
+
+    ///
+    /// Returns true if neither tone mapping nor sRGB encoding has been applied.
+    ///
+    pub fn is_linear(&self) -> bool {
+        !self.tone_mapped && !self.srgb
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self {
+            tone_mapped: self.tone_mapped || other.tone_mapped,
+            srgb: self.srgb || other.srgb,
+        }
+    }
+}
+
+///
+/// A color space mistake detected when color space validation is enabled, see [Context::set_color_space_validation].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorSpaceIssue {
+    /// A texture containing sRGB encoded colors is sampled by a program that applies tone mapping, ie. the program treats sRGB colors as linear HDR colors.
+    SrgbSampledAsLinear,
+    /// A texture containing sRGB encoded colors is sampled by a program that encodes the colors to sRGB again.
+    DoubleGamma,
+    /// A texture containing tone mapped colors is sampled by a program that applies tone mapping again.
+    DoubleToneMapping,
+    /// Tone mapped or sRGB encoded colors are blended into a texture, which means the blending does not happen in linear space.
+    /// Render into the texture without tone mapping and color mapping and apply them when copying the result to the screen instead.
+    BlendingAfterToneMapping,
+}
+
+impl std::fmt::Display for ColorSpaceIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Self::SrgbSampledAsLinear => "sRGB encoded texture sampled as linear",
+            Self::DoubleGamma => "sRGB encoding applied twice",
+            Self::DoubleToneMapping => "tone mapping applied twice",
+            Self::BlendingAfterToneMapping => "blending after tone mapping into a non-sRGB target",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+///
+/// A warning produced by the color space validation, see [Context::set_color_space_validation] and [Context::take_color_space_warnings].
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorSpaceWarning {
+    /// The detected mistake.
+    pub issue: ColorSpaceIssue,
+    /// The identifier of the offending resource, ie. the sampled texture or the blend target, see [FrameResource::id].
+    pub resource: String,
+    /// The render targets written to when the mistake was detected.
+    pub targets: Vec<String>,
+}
+
+impl std::fmt::Display for ColorSpaceWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} (writing to {})",
+            self.issue,
+            self.resource,
+            self.targets.join(", ")
+        )
+    }
+}
+
+struct ColorSpaceDraw {
+    encoding: ColorEncoding,
+    blend: bool,
+    inputs: Vec<String>,
+}
+
+#[derive(Default)]
+pub(in crate::core) struct ColorSpaceValidator {
+    encodings: HashMap<String, ColorEncoding>,
+    inputs: Vec<String>,
+    draws: Vec<ColorSpaceDraw>,
+    warnings: Vec<ColorSpaceWarning>,
+}
+
+impl ColorSpaceValidator {
+    pub(in crate::core) fn add_input(&mut self, input: &FrameResource) {
+        if !self.inputs.contains(&input.id) {
+            self.inputs.push(input.id.clone());
+        }
+    }
+
+    pub(in crate::core) fn add_draw(&mut self, encoding: ColorEncoding, blend: bool) {
+        self.draws.push(ColorSpaceDraw {
+            encoding,
+            blend,
+            inputs: std::mem::take(&mut self.inputs),
+        });
+    }
+
+    pub(in crate::core) fn add_pass(&mut self, kind: FramePassKind, targets: &[FrameResource]) {
+        match kind {
+            FramePassKind::Clear => {
+                for target in targets {
+                    self.encodings.remove(&target.id);
+                }
+            }
+            FramePassKind::Write => {
+                let target_ids = targets.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+                for draw in std::mem::take(&mut self.draws) {
+                    let mut encoding = draw.encoding;
+                    for input in draw.inputs.iter() {
+                        let input_encoding = self
+                            .encodings
+                            .get(input)
+                            .copied()
+                            .unwrap_or(ColorEncoding::LINEAR);
+                        if draw.encoding.srgb && input_encoding.srgb {
+                            self.warn(ColorSpaceIssue::DoubleGamma, input, &target_ids);
+                        } else if draw.encoding.tone_mapped && input_encoding.srgb {
+                            self.warn(ColorSpaceIssue::SrgbSampledAsLinear, input, &target_ids);
+                        }
+                        if draw.encoding.tone_mapped && input_encoding.tone_mapped {
+                            self.warn(ColorSpaceIssue::DoubleToneMapping, input, &target_ids);
+                        }
+                        // Copying without conversion keeps the encoding of the input
+                        encoding = encoding.union(input_encoding);
+                    }
+                    for target in targets.iter().filter(|t| t.kind != "Screen") {
+                        if draw.blend && !draw.encoding.is_linear() {
+                            self.warn(
+                                ColorSpaceIssue::BlendingAfterToneMapping,
+                                &target.id,
+                                &target_ids,
+                            );
+                        }
+                        let target_encoding = self
+                            .encodings
+                            .entry(target.id.clone())
+                            .or_insert(ColorEncoding::LINEAR);
+                        *target_encoding = target_encoding.union(encoding);
+                    }
+                }
+            }
+            FramePassKind::Blit => {
+                let encoding = self.inputs.iter().fold(ColorEncoding::LINEAR, |e, input| {
+                    e.union(
+                        self.encodings
+                            .get(input)
+                            .copied()
+                            .unwrap_or(ColorEncoding::LINEAR),
+                    )
+                });
+                for target in targets.iter().filter(|t| t.kind != "Screen") {
+                    self.encodings.insert(target.id.clone(), encoding);
+                }
+            }
+            FramePassKind::Read => {}
+        }
+        self.inputs.clear();
+    }
+
+    pub(in crate::core) fn take_warnings(&mut self) -> Vec<ColorSpaceWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn warn(&mut self, issue: ColorSpaceIssue, resource: &str, targets: &[String]) {
+        let warning = ColorSpaceWarning {
+            issue,
+            resource: resource.to_string(),
+            targets: targets.to_vec(),
+        };
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+}
@@ -1,11 +1,17 @@
 use super::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::sync::RwLock;
+use std::sync::{RwLock, RwLockWriteGuard};
 
 #[doc(hidden)]
 pub use crate::context::HasContext;
 
+///
+/// The maximum number of format fallbacks which are kept until they are taken with [Context::take_format_fallbacks].
+///
+pub const MAX_FORMAT_FALLBACKS: usize = 256;
+
 ///
 /// Contains the low-level OpenGL/WebGL graphics context as well as other "global" variables.
 /// Implements Deref with the low-level graphics context as target, so you can call low-level functionality
@@ -17,6 +23,17 @@ pub struct Context {
     pub(super) vao: crate::context::VertexArray,
     /// A cache of programs to avoid recompiling a [Program] every frame.
     pub programs: Arc<RwLock<HashMap<Vec<u8>, Program>>>,
+    queries: Arc<RwLock<HashMap<u32, Vec<crate::context::Query>>>>,
+    active_queries: Arc<RwLock<HashSet<u32>>>,
+    frame_capture: Arc<RwLock<Option<FrameCapture>>>,
+    capturing_frame: Arc<AtomicBool>,
+    color_space_validator: Arc<RwLock<Option<ColorSpaceValidator>>>,
+    validating_color_space: Arc<AtomicBool>,
+    color_precision: Arc<RwLock<ColorPrecision>>,
+    time: Arc<RwLock<f64>>,
+    format_fallbacks: Arc<RwLock<Vec<FormatFallback>>>,
+    depth_range: Arc<RwLock<DepthRange>>,
+    capabilities: Arc<Capabilities>,
 }
 
 impl Context {
@@ -29,12 +46,13 @@ impl Context {
     pub fn from_gl_context(context: Arc<crate::context::Context>) -> Result<Self, CoreError> {
         unsafe {
             if !context.version().is_embedded {
-                // Enable seamless cube map textures - not available on OpenGL ES and WebGL
+                // Enable seamless cube map textures - always enabled on OpenGL ES 3.0 and WebGL 2 and not available on OpenGL ES 2.0 and WebGL 1
                 context.enable(crate::context::TEXTURE_CUBE_MAP_SEAMLESS);
             }
             context.pixel_store_i32(crate::context::UNPACK_ALIGNMENT, 1);
             context.pixel_store_i32(crate::context::PACK_ALIGNMENT, 1);
         };
+        let capabilities = Arc::new(Capabilities::new(&context));
         let c = unsafe {
             // Create one Vertex Array Object which is then reused all the time.
             let vao = context
@@ -44,11 +62,332 @@ impl Context {
                 context,
                 vao,
                 programs: Arc::new(RwLock::new(HashMap::new())),
+                queries: Arc::new(RwLock::new(HashMap::new())),
+                active_queries: Arc::new(RwLock::new(HashSet::new())),
+                frame_capture: Arc::new(RwLock::new(None)),
+                capturing_frame: Arc::new(AtomicBool::new(false)),
+                color_space_validator: Arc::new(RwLock::new(None)),
+                validating_color_space: Arc::new(AtomicBool::new(false)),
+                color_precision: Arc::new(RwLock::new(ColorPrecision::default())),
+                time: Arc::new(RwLock::new(0.0)),
+                format_fallbacks: Arc::new(RwLock::new(Vec::new())),
+                depth_range: Arc::new(RwLock::new(DepthRange::FULL)),
+                capabilities,
             }
         };
         Ok(c)
     }
 
+    ///
+    /// Returns a query object for the given target, either a previously released one or a newly created one.
+    /// The query objects are pooled per target, since a query object cannot be used with another target than the one it was first used with.
+    ///
+    pub(super) fn acquire_query(&self, target: u32) -> crate::context::Query {
+        self.queries
+            .write()
+            .unwrap()
+            .get_mut(&target)
+            .and_then(|queries| queries.pop())
+            .unwrap_or_else(|| unsafe { self.create_query().expect("Failed creating query") })
+    }
+
+    ///
+    /// Releases the query object used with the given target so that it can be reused, instead of deleting it.
+    ///
+    pub(super) fn release_query(&self, target: u32, query: crate::context::Query) {
+        self.queries
+            .write()
+            .unwrap()
+            .entry(target)
+            .or_default()
+            .push(query);
+    }
+
+    ///
+    /// Marks a query with the given target as active and returns whether or not another query with the same target was already active,
+    /// since only one query per target can be active at a time.
+    ///
+    pub(super) fn activate_query(&self, target: u32) -> bool {
+        !self.active_queries.write().unwrap().insert(target)
+    }
+
+    ///
+    /// Marks the active query with the given target as ended.
+    ///
+    pub(super) fn deactivate_query(&self, target: u32) {
+        self.active_queries.write().unwrap().remove(&target);
+    }
+
+    ///
+    /// Starts recording the clear, write, read and blit operations on render targets together with the textures sampled in each of them.
+    /// Call [Context::end_frame_capture] to stop the recording and get the resulting [FrameGraph].
+    /// Starting a new capture discards any capture in progress.
+    ///
+    pub fn begin_frame_capture(&self) {
+        *self.frame_capture.write().unwrap() = Some(FrameCapture::default());
+        self.capturing_frame.store(true, Ordering::Release);
+    }
+
+    ///
+    /// Stops the recording started with [Context::begin_frame_capture] and returns the recorded [FrameGraph].
+    /// Returns `None` if no capture is in progress.
+    ///
+    pub fn end_frame_capture(&self) -> Option<FrameGraph> {
+        self.capturing_frame.store(false, Ordering::Release);
+        self.frame_capture
+            .write()
+            .unwrap()
+            .take()
+            .map(|capture| capture.finish())
+    }
+
+    ///
+    /// Returns whether or not a frame capture is in progress, see [Context::begin_frame_capture].
+    ///
+    pub fn is_capturing_frame(&self) -> bool {
+        self.capturing_frame.load(Ordering::Acquire)
+    }
+
+    ///
+    /// Sets the label of the passes recorded from now on, for example the name of the effect or render pass, which makes the [FrameGraph] easier to read.
+    /// Use `None` to remove the label. Does nothing if no frame capture is in progress.
+    ///
+    pub fn set_frame_pass_label(&self, label: Option<&str>) {
+        if let Some(capture) = self.frame_capture().as_mut().and_then(|c| c.as_mut()) {
+            capture.set_label(label.map(|l| l.to_string()));
+        }
+    }
+
+    ///
+    /// Enables or disables validation of the color spaces used when rendering.
+    /// When enabled, the encoding of the colors written to each texture is tracked and common color space mistakes are detected,
+    /// for example sampling an sRGB encoded texture as linear, applying sRGB encoding twice or blending tone mapped colors into a texture.
+    /// Use [Context::take_color_space_warnings] to get the detected mistakes.
+    ///
+    /// **Note:** The validation has a small CPU overhead, so it should only be enabled while debugging.
+    ///
+    pub fn set_color_space_validation(&self, enabled: bool) {
+        *self.color_space_validator.write().unwrap() = if enabled {
+            Some(ColorSpaceValidator::default())
+        } else {
+            None
+        };
+        self.validating_color_space
+            .store(enabled, Ordering::Release);
+    }
+
+    ///
+    /// Returns whether or not color space validation is enabled, see [Context::set_color_space_validation].
+    ///
+    pub fn is_validating_color_space(&self) -> bool {
+        self.validating_color_space.load(Ordering::Acquire)
+    }
+
+    ///
+    /// Returns the color space mistakes detected since the last call to this method, see [Context::set_color_space_validation].
+    /// Each mistake is only reported once per resource and render target.
+    ///
+    pub fn take_color_space_warnings(&self) -> Vec<ColorSpaceWarning> {
+        self.color_space_validator
+            .write()
+            .unwrap()
+            .as_mut()
+            .map(|validator| validator.take_warnings())
+            .unwrap_or_default()
+    }
+
+    ///
+    /// Sets the precision of the color textures used for intermediate results, ie. when rendering in HDR using the `RenderPipeline`
+    /// and in post-processing using for example the `ImageProcessor`, `TemporalUpsampler` and `PlanarReflection`.
+    /// A lower precision trades more banding for less memory and bandwidth, which is especially important on mobile GPUs.
+    /// The textures are recreated with the new precision the next time they are resized or, for the `RenderPipeline`, the next time it renders.
+    /// The default is [ColorPrecision::Rgba16f].
+    ///
+    pub fn set_color_precision(&self, precision: ColorPrecision) {
+        *self.color_precision.write().unwrap() = precision;
+    }
+
+    ///
+    /// Returns the precision of the color textures used for intermediate results, see [Context::set_color_precision].
+    /// If the chosen precision cannot be rendered to, the closest supported precision is returned instead,
+    /// ie. [ColorPrecision::Rgba16f] if it is supported and otherwise [ColorPrecision::Rgba8].
+    ///
+    pub fn color_precision(&self) -> ColorPrecision {
+        let precision = *self.color_precision.read().unwrap();
+        match precision {
+            ColorPrecision::Rg11b10f if self.capabilities.float_render_targets => precision,
+            ColorPrecision::Rgba8 => precision,
+            _ if self.capabilities.half_float_render_targets => ColorPrecision::Rgba16f,
+            _ => ColorPrecision::Rgba8,
+        }
+    }
+
+    ///
+    /// Sets the time in seconds used by time driven shader effects, for example the sway of a `Wind` geometry, so all effects are driven by the same clock.
+    /// When using a `Window` or a `FrameInputGenerator`, this is set to the accumulated time at the beginning of each frame.
+    ///
+    pub fn set_time(&self, time: f64) {
+        *self.time.write().unwrap() = time;
+    }
+
+    ///
+    /// Returns the time in seconds used by time driven shader effects, see [Context::set_time].
+    ///
+    pub fn time(&self) -> f64 {
+        *self.time.read().unwrap()
+    }
+
+    // Returns the frame capture, without taking the lock when no capture is in progress since this is called for every texture binding.
+    fn frame_capture(&self) -> Option<RwLockWriteGuard<'_, Option<FrameCapture>>> {
+        self.is_capturing_frame()
+            .then(|| self.frame_capture.write().unwrap())
+    }
+
+    // Returns the color space validator, without taking the lock when the validation is disabled since this is called for every draw call.
+    fn color_space_validator(&self) -> Option<RwLockWriteGuard<'_, Option<ColorSpaceValidator>>> {
+        self.is_validating_color_space()
+            .then(|| self.color_space_validator.write().unwrap())
+    }
+
+    pub(super) fn record_frame_input(&self, input: impl FnOnce() -> FrameResource) {
+        let mut capture_guard = self.frame_capture();
+        let mut capture = capture_guard.as_mut().and_then(|c| c.as_mut());
+        let mut validator_guard = self.color_space_validator();
+        let mut validator = validator_guard.as_mut().and_then(|v| v.as_mut());
+        if capture.is_some() || validator.is_some() {
+            let input = input();
+            if let Some(validator) = validator.as_mut() {
+                validator.add_input(&input);
+            }
+            if let Some(capture) = capture.as_mut() {
+                capture.add_input(input);
+            }
+        }
+    }
+
+    pub(super) fn record_color_space_draw(
+        &self,
+        encoding: impl FnOnce() -> ColorEncoding,
+        blend: Blend,
+    ) {
+        if let Some(validator) = self
+            .color_space_validator()
+            .as_mut()
+            .and_then(|v| v.as_mut())
+        {
+            validator.add_draw(encoding(), blend != Blend::Disabled);
+        }
+    }
+
+    pub(super) fn record_frame_pass(
+        &self,
+        kind: FramePassKind,
+        targets: impl FnOnce() -> Vec<FrameResource>,
+        mip_level: u32,
+        scissor_box: ScissorBox,
+    ) {
+        let mut capture_guard = self.frame_capture();
+        let mut capture = capture_guard.as_mut().and_then(|c| c.as_mut());
+        let mut validator_guard = self.color_space_validator();
+        let mut validator = validator_guard.as_mut().and_then(|v| v.as_mut());
+        if capture.is_some() || validator.is_some() {
+            let targets = targets();
+            if let Some(validator) = validator.as_mut() {
+                validator.add_pass(kind, &targets);
+            }
+            if let Some(capture) = capture.as_mut() {
+                capture.add_pass(kind, targets, mip_level, scissor_box);
+            }
+        }
+    }
+
+    ///
+    /// Returns the limits and optional features of the graphics backend, see [Capabilities].
+    ///
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    ///
+    /// Returns whether or not this and the other context wrap the same low-level graphics context, in which case resources created with one of them can be used with the other.
+    ///
+    pub(in crate::core) fn is_same_gl_context(&self, other: &Context) -> bool {
+        Arc::ptr_eq(&self.context, &other.context)
+    }
+
+    ///
+    /// Returns whether or not this is an OpenGL ES 2.0 or WebGL 1 context, which is supported in a compatibility mode with reduced functionality:
+    /// - The shaders are translated to GLSL ES 1.00, see [ShaderDialect].
+    /// - Only the first color attachment is written to, see [Context::supports_multiple_render_targets].
+    /// - 3D textures and texture arrays are not available, see [Context::supports_3d_textures].
+    /// - Mip maps and repeating wrapping are only available for textures with power-of-two sizes, see [Context::supports_npot_mip_maps].
+    /// - Depth textures require the `OES_depth_texture` or `WEBGL_depth_texture` extension and 16 bit float textures require the `OES_texture_half_float` extension.
+    /// - Shaders cannot use unsigned integers, bit operations, `textureSize` or `texelFetch`, so clustered lighting, skinning and filtering of shadows are not available.
+    ///
+    /// Use these queries to select content that degrades gracefully on old devices.
+    ///
+    pub fn is_legacy(&self) -> bool {
+        self.capabilities.legacy
+    }
+
+    ///
+    /// Returns whether or not it is possible to write to multiple color attachments in one render pass.
+    /// If not, only the first color attachment of a [ColorTarget] is written to.
+    ///
+    pub fn supports_multiple_render_targets(&self) -> bool {
+        self.capabilities.max_draw_buffers > 1
+    }
+
+    ///
+    /// Returns whether or not [Texture3D], [Texture2DArray] and the other array textures are available.
+    ///
+    pub fn supports_3d_textures(&self) -> bool {
+        self.capabilities.max_3d_texture_size > 0
+    }
+
+    ///
+    /// Returns whether or not textures with a width or height that is not a power of two can have mip maps and repeating wrapping.
+    /// If not, the mip map filter is ignored and the wrapping is set to [Wrapping::ClampToEdge] for such textures.
+    ///
+    pub fn supports_npot_mip_maps(&self) -> bool {
+        !self.is_legacy()
+    }
+
+    ///
+    /// Returns whether or not textures in the given compressed format can be created, see [Texture2D::new_compressed].
+    ///
+    pub fn supports_compressed_format(&self, format: CompressedFormat) -> bool {
+        format.is_supported(&self.capabilities)
+    }
+
+    ///
+    /// Returns the closest supported format which the given texture is created with when using [Texture2D::new], [Texture2DArray::new] or [TextureCubeMap::new],
+    /// or `None` if the format of the data is supported as it is.
+    /// For example, a 32 bit float texture with linear interpolation is created as a 16 bit float texture if 32 bit float textures cannot be filtered,
+    /// and a texture with one or two channels is created with three channels if such textures are not supported.
+    ///
+    pub fn texture_format_fallback(&self, cpu_texture: &CpuTexture) -> Option<FormatFallback> {
+        FormatFallback::negotiate(&self.capabilities, cpu_texture)
+    }
+
+    ///
+    /// Returns the textures which have been created with another format than the format of the data since the last call to this method, see [Context::texture_format_fallback].
+    /// Identical reports are only returned once and only the latest [MAX_FORMAT_FALLBACKS] reports are kept.
+    ///
+    pub fn take_format_fallbacks(&self) -> Vec<FormatFallback> {
+        std::mem::take(&mut *self.format_fallbacks.write().unwrap())
+    }
+
+    pub(in crate::core) fn record_format_fallback(&self, fallback: FormatFallback) {
+        let mut fallbacks = self.format_fallbacks.write().unwrap();
+        if !fallbacks.contains(&fallback) {
+            if fallbacks.len() >= MAX_FORMAT_FALLBACKS {
+                fallbacks.remove(0);
+            }
+            fallbacks.push(fallback);
+        }
+    }
+
     ///
     /// Set the scissor test for this context (see [ScissorBox]).
     ///
@@ -157,6 +496,111 @@ impl Context {
         }
     }
 
+    ///
+    /// Set the depth range for this context (see [DepthRange]).
+    /// The depth range is only changed if it is different from the depth range set the last time, since it is set for every render call.
+    ///
+    pub fn set_depth_range(&self, depth_range: DepthRange) {
+        let depth_range = DepthRange::new(
+            depth_range.near.clamp(0.0, 1.0),
+            depth_range.far.clamp(0.0, 1.0),
+        );
+        let mut current = self.depth_range.write().unwrap();
+        if *current != depth_range {
+            unsafe {
+                self.depth_range_f32(depth_range.near, depth_range.far);
+            }
+            *current = depth_range;
+        }
+    }
+
+    ///
+    /// Enables or disables alpha-to-coverage for this context (see [RenderStates::alpha_to_coverage]).
+    ///
+    pub fn set_alpha_to_coverage(&self, alpha_to_coverage: bool) {
+        unsafe {
+            if alpha_to_coverage {
+                self.enable(crate::context::SAMPLE_ALPHA_TO_COVERAGE);
+            } else {
+                self.disable(crate::context::SAMPLE_ALPHA_TO_COVERAGE);
+            }
+        }
+    }
+
+    ///
+    /// Enables the hardware clip distances needed for the given clip planes for this context (see [ClipPlanes]).
+    /// Does nothing if hardware clip distances are not supported, in which case the planes are only evaluated in the fragment shader.
+    ///
+    pub fn set_clip_planes(&self, clip_planes: ClipPlanes) {
+        if !self.capabilities.clip_distances {
+            return;
+        }
+        unsafe {
+            for i in 0..ClipPlanes::MAX {
+                if i < clip_planes.planes().len() {
+                    self.enable(crate::context::CLIP_DISTANCE0 + i as u32);
+                } else {
+                    self.disable(crate::context::CLIP_DISTANCE0 + i as u32);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Set the stencil test and stencil operations for this context (see [Stencil]).
+    ///
+    pub fn set_stencil(&self, stencil: Stencil) {
+        unsafe {
+            if let Stencil::Enabled {
+                test,
+                reference,
+                read_mask,
+                write_mask,
+                stencil_fail,
+                depth_fail,
+                pass,
+            } = stencil
+            {
+                self.enable(crate::context::STENCIL_TEST);
+                self.stencil_func(
+                    match test {
+                        StencilTest::Never => crate::context::NEVER,
+                        StencilTest::Less => crate::context::LESS,
+                        StencilTest::Equal => crate::context::EQUAL,
+                        StencilTest::LessOrEqual => crate::context::LEQUAL,
+                        StencilTest::Greater => crate::context::GREATER,
+                        StencilTest::NotEqual => crate::context::NOTEQUAL,
+                        StencilTest::GreaterOrEqual => crate::context::GEQUAL,
+                        StencilTest::Always => crate::context::ALWAYS,
+                    },
+                    reference as i32,
+                    read_mask as u32,
+                );
+                self.stencil_op(
+                    Self::stencil_const_from_operation(stencil_fail),
+                    Self::stencil_const_from_operation(depth_fail),
+                    Self::stencil_const_from_operation(pass),
+                );
+                self.stencil_mask(write_mask as u32);
+            } else {
+                self.disable(crate::context::STENCIL_TEST);
+            }
+        }
+    }
+
+    fn stencil_const_from_operation(operation: StencilOperation) -> u32 {
+        match operation {
+            StencilOperation::Keep => crate::context::KEEP,
+            StencilOperation::Zero => crate::context::ZERO,
+            StencilOperation::Replace => crate::context::REPLACE,
+            StencilOperation::Increment => crate::context::INCR,
+            StencilOperation::IncrementWrap => crate::context::INCR_WRAP,
+            StencilOperation::Decrement => crate::context::DECR,
+            StencilOperation::DecrementWrap => crate::context::DECR_WRAP,
+            StencilOperation::Invert => crate::context::INVERT,
+        }
+    }
+
     ///
     /// Set the blend state for this context (see [Blend]).
     ///
@@ -224,7 +668,11 @@ impl Context {
         } else {
             self.set_depth_test(render_states.depth_test);
         }
+        self.set_depth_range(render_states.depth_range);
         self.set_blend(render_states.blend);
+        self.set_alpha_to_coverage(render_states.alpha_to_coverage);
+        self.set_stencil(render_states.stencil);
+        self.set_clip_planes(render_states.clip_planes);
     }
 
     ///
@@ -299,6 +747,9 @@ impl std::fmt::Debug for Context {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut d = f.debug_struct("Context");
         d.field("programs", &self.programs.read().unwrap().len());
+        d.field("validating_color_space", &self.is_validating_color_space());
+        d.field("queries", &self.queries.read().unwrap().len());
+        d.field("capturing_frame", &self.is_capturing_frame());
         d.finish()
     }
 }
@@ -394,6 +394,25 @@ impl DataType for f32 {
     }
 }
 
+impl DataType for r11g11b10f {
+    fn internal_format() -> u32 {
+        crate::context::R11F_G11F_B10F
+    }
+
+    fn data_type() -> u32 {
+        crate::context::UNSIGNED_INT_10F_11F_11F_REV
+    }
+
+    fn size() -> u32 {
+        3
+    }
+
+    fn send_uniform(context: &Context, location: &UniformLocation, data: &[Self]) {
+        let data = data.iter().map(|v| v.0).collect::<Vec<_>>();
+        u32::send_uniform_with_type(context, location, &data, UniformType::Value)
+    }
+}
+
 impl<T: PrimitiveDataType> DataType for Vector2<T> {
     fn internal_format() -> u32 {
         T::internal_format_with_size(Self::size())
@@ -611,6 +630,10 @@ impl<T: PrimitiveDataType> DataType for Matrix4<T> {
 
 pub trait DepthDataType {
     fn internal_format() -> u32;
+
+    fn attachment() -> u32 {
+        crate::context::DEPTH_ATTACHMENT
+    }
 }
 
 impl DepthDataType for f16 {
@@ -628,3 +651,21 @@ impl DepthDataType for f32 {
         crate::context::DEPTH_COMPONENT32F
     }
 }
+impl DepthDataType for f24s8 {
+    fn internal_format() -> u32 {
+        crate::context::DEPTH24_STENCIL8
+    }
+
+    fn attachment() -> u32 {
+        crate::context::DEPTH_STENCIL_ATTACHMENT
+    }
+}
+impl DepthDataType for f32s8 {
+    fn internal_format() -> u32 {
+        crate::context::DEPTH32F_STENCIL8
+    }
+
+    fn attachment() -> u32 {
+        crate::context::DEPTH_STENCIL_ATTACHMENT
+    }
+}
@@ -0,0 +1,288 @@
+use crate::core::*;
+
+///
+/// The type of operation performed in a [FramePass].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FramePassKind {
+    /// The targets are cleared.
+    Clear,
+    /// Render calls write to the targets.
+    Write,
+    /// The targets are read back to the CPU.
+    Read,
+    /// The content of another render target is copied into the targets.
+    Blit,
+}
+
+impl std::fmt::Display for FramePassKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Clear => "clear",
+            Self::Write => "write",
+            Self::Read => "read",
+            Self::Blit => "blit",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+///
+/// A GPU resource, for example a [Texture2D] or the screen, that is written to or read from in a [FramePass].
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameResource {
+    /// The type of the resource, for example `Texture2D` or `Screen`.
+    pub kind: String,
+    /// An identifier which is unique among the resources that are alive.
+    pub id: String,
+    /// The part of the resource that is used, for example the layer of an array texture or the side of a cube map, or `None` if the entire resource is used.
+    pub subresource: Option<String>,
+    /// The width of the resource in texels.
+    pub width: u32,
+    /// The height of the resource in texels.
+    pub height: u32,
+    /// The number of layers of the resource, 1 if the resource is not an array or 3D texture.
+    pub layers: u32,
+    /// A description of the format of the resource, for example the number of bytes per texel.
+    pub format: String,
+}
+
+impl FrameResource {
+    pub(in crate::core) fn texture(
+        kind: &str,
+        id: impl std::fmt::Debug,
+        width: u32,
+        height: u32,
+        layers: u32,
+        format: String,
+    ) -> Self {
+        Self {
+            kind: kind.to_string(),
+            id: format!("{}{:?}", kind, id),
+            subresource: None,
+            width,
+            height,
+            layers,
+            format,
+        }
+    }
+
+    pub(in crate::core) fn screen(width: u32, height: u32) -> Self {
+        Self {
+            kind: "Screen".to_string(),
+            id: "Screen".to_string(),
+            subresource: None,
+            width,
+            height,
+            layers: 1,
+            format: "default framebuffer".to_string(),
+        }
+    }
+
+    pub(in crate::core) fn color_format(data_byte_size: usize) -> String {
+        format!("{} bytes per texel", data_byte_size)
+    }
+
+    pub(in crate::core) fn depth_format(attachment: u32) -> String {
+        if attachment == crate::context::DEPTH_STENCIL_ATTACHMENT {
+            "depth-stencil".to_string()
+        } else {
+            "depth".to_string()
+        }
+    }
+}
+
+impl std::fmt::Display for FrameResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)?;
+        if let Some(subresource) = &self.subresource {
+            write!(f, " {}", subresource)?;
+        }
+        write!(f, " {}x{}", self.width, self.height)?;
+        if self.layers > 1 {
+            write!(f, "x{}", self.layers)?;
+        }
+        write!(f, " ({})", self.format)
+    }
+}
+
+///
+/// A single pass in a [FrameGraph], ie. one clear, write, read or blit operation on a [RenderTarget], [ColorTarget] or [DepthTarget].
+///
+#[derive(Clone, Debug)]
+pub struct FramePass {
+    /// The label set with [Context::set_frame_pass_label] when the pass was recorded, if any.
+    pub label: Option<String>,
+    /// The type of operation.
+    pub kind: FramePassKind,
+    /// The resources written to by this pass or, in case of a [FramePassKind::Read], the resources read from.
+    pub targets: Vec<FrameResource>,
+    /// The textures sampled by the programs used in this pass or, in case of a [FramePassKind::Blit], the resources that are copied.
+    pub inputs: Vec<FrameResource>,
+    /// The mip level of the targets.
+    pub mip_level: u32,
+    /// The part of the targets that is affected by this pass.
+    pub scissor_box: ScissorBox,
+}
+
+impl FramePass {
+    fn name(&self, index: usize) -> String {
+        match &self.label {
+            Some(label) => format!("#{} {} [{}]", index, label, self.kind),
+            None => format!("#{} [{}]", index, self.kind),
+        }
+    }
+}
+
+///
+/// A record of the passes executed while capturing a frame, see [Context::begin_frame_capture] and [Context::end_frame_capture].
+/// Use [FrameGraph::to_text] or [FrameGraph::to_dot] to get a human readable dump of the passes, their targets and their dependencies,
+/// for example when debugging a post-processing stack or reporting an issue.
+///
+#[derive(Clone, Debug, Default)]
+pub struct FrameGraph {
+    /// The recorded passes in the order they were executed.
+    pub passes: Vec<FramePass>,
+}
+
+impl FrameGraph {
+    ///
+    /// Returns the dependencies between the passes as pairs of indices `(from, to)` into [FrameGraph::passes],
+    /// meaning that the pass `to` samples a texture that was last written by the pass `from`.
+    ///
+    pub fn dependencies(&self) -> Vec<(usize, usize)> {
+        let mut dependencies = Vec::new();
+        for (to, pass) in self.passes.iter().enumerate() {
+            for input in pass.inputs.iter() {
+                if let Some(from) = self.last_writer(input, to) {
+                    if !dependencies.contains(&(from, to)) {
+                        dependencies.push((from, to));
+                    }
+                }
+            }
+        }
+        dependencies
+    }
+
+    ///
+    /// Returns a plain text dump of the passes, their targets and inputs.
+    ///
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (index, pass) in self.passes.iter().enumerate() {
+            text.push_str(&format!(
+                "{} mip {} scissor ({}, {}, {}x{})\n",
+                pass.name(index),
+                pass.mip_level,
+                pass.scissor_box.x,
+                pass.scissor_box.y,
+                pass.scissor_box.width,
+                pass.scissor_box.height
+            ));
+            for target in pass.targets.iter() {
+                text.push_str(&format!("    target: {}\n", target));
+            }
+            for input in pass.inputs.iter() {
+                match self.last_writer(input, index) {
+                    Some(from) => {
+                        text.push_str(&format!("    input: {} <- pass #{}\n", input, from))
+                    }
+                    None => text.push_str(&format!("    input: {}\n", input)),
+                }
+            }
+        }
+        text
+    }
+
+    ///
+    /// Returns the frame graph in the [DOT](https://graphviz.org/doc/info/lang.html) graph description language,
+    /// which can be visualized with for example Graphviz.
+    /// Each pass is a node and each edge is a dependency labeled with the texture that connects the two passes.
+    ///
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph frame {\n    node [shape=box];\n".to_string();
+        for (index, pass) in self.passes.iter().enumerate() {
+            let mut label = pass.name(index);
+            for target in pass.targets.iter() {
+                label.push_str(&format!("\\n{}", target));
+            }
+            dot.push_str(&format!(
+                "    pass{} [label=\"{}\"];\n",
+                index,
+                label.replace('"', "\\\"")
+            ));
+        }
+        for (to, pass) in self.passes.iter().enumerate() {
+            let mut seen = Vec::new();
+            for input in pass.inputs.iter() {
+                if let Some(from) = self.last_writer(input, to) {
+                    if !seen.contains(&(from, &input.id)) {
+                        seen.push((from, &input.id));
+                        dot.push_str(&format!(
+                            "    pass{} -> pass{} [label=\"{}\"];\n",
+                            from, to, input.id
+                        ));
+                    }
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn last_writer(&self, resource: &FrameResource, before: usize) -> Option<usize> {
+        self.passes[..before].iter().rposition(|pass| {
+            pass.kind != FramePassKind::Read && pass.targets.iter().any(|t| t.id == resource.id)
+        })
+    }
+}
+
+impl std::fmt::Display for FrameGraph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+#[derive(Default)]
+pub(in crate::core) struct FrameCapture {
+    graph: FrameGraph,
+    label: Option<String>,
+    inputs: Vec<FrameResource>,
+}
+
+impl FrameCapture {
+    pub(in crate::core) fn set_label(&mut self, label: Option<String>) {
+        self.label = label;
+    }
+
+    pub(in crate::core) fn add_input(&mut self, input: FrameResource) {
+        if !self.inputs.contains(&input) {
+            self.inputs.push(input);
+        }
+    }
+
+    pub(in crate::core) fn add_pass(
+        &mut self,
+        kind: FramePassKind,
+        targets: Vec<FrameResource>,
+        mip_level: u32,
+        scissor_box: ScissorBox,
+    ) {
+        self.graph.passes.push(FramePass {
+            label: self.label.clone(),
+            kind,
+            targets,
+            inputs: match kind {
+                FramePassKind::Write | FramePassKind::Blit => std::mem::take(&mut self.inputs),
+                FramePassKind::Clear | FramePassKind::Read => Vec::new(),
+            },
+            mip_level,
+            scissor_box,
+        });
+    }
+
+    pub(in crate::core) fn finish(self) -> FrameGraph {
+        self.graph
+    }
+}
@@ -15,16 +15,68 @@ pub struct Program {
     textures: RwLock<HashMap<String, u32>>,
     uniforms: HashMap<String, crate::context::UniformLocation>,
     uniform_blocks: RwLock<HashMap<String, (u32, u32)>>,
+    varyings_count: usize,
+    color_encoding: RwLock<ColorEncoding>,
 }
 
 impl Program {
     ///
     /// Creates a new shader program from the given vertex and fragment glsl shader source.
+    /// The shader source is written in GLSL 3.30 style and is translated to the shader dialect of the graphics backend, see [ShaderDialect].
     ///
     pub fn from_source(
         context: &Context,
         vertex_shader_source: &str,
         fragment_shader_source: &str,
+    ) -> Result<Self, CoreError> {
+        Self::from_source_with_varyings(context, vertex_shader_source, fragment_shader_source, &[])
+    }
+
+    ///
+    /// Creates a new shader program from the given vertex and fragment glsl shader source
+    /// where the output variables of the vertex shader with the given names (the varyings) can be captured into vertex buffers using [Program::transform_feedback].
+    ///
+    pub fn from_source_with_transform_feedback(
+        context: &Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        varyings: &[&str],
+    ) -> Result<Self, CoreError> {
+        Self::from_source_with_varyings(
+            context,
+            vertex_shader_source,
+            fragment_shader_source,
+            varyings,
+        )
+    }
+
+    ///
+    /// Creates a new shader program from the given vertex glsl shader source
+    /// where the output variables of the vertex shader with the given names (the varyings) can be captured into vertex buffers using [Program::transform_feedback].
+    /// Since the program is only used for transform feedback, a fragment shader is not needed.
+    ///
+    pub fn from_vertex_source_with_transform_feedback(
+        context: &Context,
+        vertex_shader_source: &str,
+        varyings: &[&str],
+    ) -> Result<Self, CoreError> {
+        Self::from_source_with_varyings(
+            context,
+            vertex_shader_source,
+            "layout (location = 0) out vec4 outColor;
+            void main()
+            {
+                outColor = vec4(0.0);
+            }",
+            varyings,
+        )
+    }
+
+    fn from_source_with_varyings(
+        context: &Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        varyings: &[&str],
     ) -> Result<Self, CoreError> {
         unsafe {
             let vert_shader = context
@@ -34,24 +86,20 @@ impl Program {
                 .create_shader(crate::context::FRAGMENT_SHADER)
                 .expect("Failed creating fragment shader");
 
-            let header: &str = if context.version().is_embedded {
-                "#version 300 es
-                    #ifdef GL_FRAGMENT_PRECISION_HIGH
-                        precision highp float;
-                        precision highp int;
-                        precision highp sampler2DArray;
-                        precision highp sampler3D;
-                    #else
-                        precision mediump float;
-                        precision mediump int;
-                        precision mediump sampler2DArray;
-                        precision mediump sampler3D;
-                    #endif\n"
-            } else {
-                "#version 330 core\n"
-            };
-            let vertex_shader_source = format!("{}{}", header, vertex_shader_source);
-            let fragment_shader_source = format!("{}{}", header, fragment_shader_source);
+            let dialect = ShaderDialect::from_context(context);
+            let clip_distances = context.capabilities().clip_distances;
+            let vertex_shader_source = dialect.translate(
+                &dialect.add_clip_planes(vertex_shader_source, ShaderType::Vertex, clip_distances),
+                ShaderType::Vertex,
+            );
+            let fragment_shader_source = dialect.translate(
+                &dialect.add_clip_planes(
+                    fragment_shader_source,
+                    ShaderType::Fragment,
+                    clip_distances,
+                ),
+                ShaderType::Fragment,
+            );
 
             context.shader_source(vert_shader, &vertex_shader_source);
             context.shader_source(frag_shader, &fragment_shader_source);
@@ -61,6 +109,9 @@ impl Program {
             let id = context.create_program().expect("Failed creating program");
             context.attach_shader(id, vert_shader);
             context.attach_shader(id, frag_shader);
+            if !varyings.is_empty() {
+                context.transform_feedback_varyings(id, varyings, crate::context::SEPARATE_ATTRIBS);
+            }
             context.link_program(id);
 
             if !context.get_program_link_status(id) {
@@ -128,6 +179,8 @@ impl Program {
                 uniforms,
                 uniform_blocks: RwLock::new(HashMap::new()),
                 textures: RwLock::new(HashMap::new()),
+                varyings_count: varyings.len(),
+                color_encoding: RwLock::new(ColorEncoding::LINEAR),
             })
         }
     }
@@ -192,6 +245,7 @@ impl Program {
     pub fn use_texture(&self, name: &str, texture: &Texture2D) {
         self.use_texture_internal(name);
         texture.bind();
+        self.context.record_frame_input(|| texture.frame_resource());
     }
 
     ///
@@ -205,6 +259,7 @@ impl Program {
     pub fn use_depth_texture(&self, name: &str, texture: &DepthTexture2D) {
         self.use_texture_internal(name);
         texture.bind();
+        self.context.record_frame_input(|| texture.frame_resource());
     }
 
     ///
@@ -218,6 +273,7 @@ impl Program {
     pub fn use_texture_array(&self, name: &str, texture: &Texture2DArray) {
         self.use_texture_internal(name);
         texture.bind();
+        self.context.record_frame_input(|| texture.frame_resource());
     }
 
     ///
@@ -231,6 +287,7 @@ impl Program {
     pub fn use_depth_texture_array(&self, name: &str, texture: &DepthTexture2DArray) {
         self.use_texture_internal(name);
         texture.bind();
+        self.context.record_frame_input(|| texture.frame_resource());
     }
 
     ///
@@ -244,6 +301,7 @@ impl Program {
     pub fn use_texture_cube(&self, name: &str, texture: &TextureCubeMap) {
         self.use_texture_internal(name);
         texture.bind();
+        self.context.record_frame_input(|| texture.frame_resource());
     }
 
     ///
@@ -257,6 +315,35 @@ impl Program {
     pub fn use_depth_texture_cube(&self, name: &str, texture: &DepthTextureCubeMap) {
         self.use_texture_internal(name);
         texture.bind();
+        self.context.record_frame_input(|| texture.frame_resource());
+    }
+
+    ///
+    /// Use the given cube map array texture in this shader program and associate it with the given named variable.
+    /// The glsl shader variable must be of type `uniform samplerCubeArray` and can only be accessed in the fragment shader.
+    ///
+    /// # Panic
+    /// Will panic if the texture is not defined in the shader code or not used.
+    /// In the latter case the variable is removed by the shader compiler.
+    ///
+    pub fn use_texture_cube_map_array(&self, name: &str, texture: &TextureCubeMapArray) {
+        self.use_texture_internal(name);
+        texture.bind();
+        self.context.record_frame_input(|| texture.frame_resource());
+    }
+
+    ///
+    /// Use the given depth cube map array texture in this shader program and associate it with the given named variable.
+    /// The glsl shader variable must be of type `uniform samplerCubeArray` and can only be accessed in the fragment shader.
+    ///
+    /// # Panic
+    /// Will panic if the texture is not defined in the shader code or not used.
+    /// In the latter case the variable is removed by the shader compiler.
+    ///
+    pub fn use_depth_texture_cube_map_array(&self, name: &str, texture: &DepthTextureCubeMapArray) {
+        self.use_texture_internal(name);
+        texture.bind();
+        self.context.record_frame_input(|| texture.frame_resource());
     }
 
     ///
@@ -270,6 +357,7 @@ impl Program {
     pub fn use_texture_3d(&self, name: &str, texture: &Texture3D) {
         self.use_texture_internal(name);
         texture.bind();
+        self.context.record_frame_input(|| texture.frame_resource());
     }
 
     ///
@@ -332,6 +420,25 @@ impl Program {
     /// In the latter case the variable is removed by the shader compiler.
     ///
     pub fn use_vertex_attribute(&self, name: &str, buffer: &VertexBuffer) {
+        self.use_vertex_attribute_with_divisor(name, buffer, 0);
+    }
+
+    ///
+    /// Uses the given [VertexBuffer] data in this shader program and associates it with the given named variable.
+    /// A divisor of zero means that each value is used for one vertex, exactly like [Program::use_vertex_attribute],
+    /// otherwise each value is used when rendering `divisor` number of instances, like [Program::use_instance_attribute_with_divisor].
+    /// This is for example useful when the per instance data is computed on the GPU using [Program::transform_feedback].
+    ///
+    /// # Panic
+    /// Will panic if the attribute is not defined in the shader code or not used.
+    /// In the latter case the variable is removed by the shader compiler.
+    ///
+    pub fn use_vertex_attribute_with_divisor(
+        &self,
+        name: &str,
+        buffer: &VertexBuffer,
+        divisor: u32,
+    ) {
         if buffer.count() > 0 {
             buffer.bind();
             let loc = self.location(name);
@@ -363,7 +470,7 @@ impl Program {
                         0,
                     );
                 }
-                self.context.vertex_attrib_divisor(loc, 0);
+                self.context.vertex_attrib_divisor(loc, divisor);
                 self.context.bind_buffer(crate::context::ARRAY_BUFFER, None);
             }
             self.unuse_program();
@@ -380,6 +487,29 @@ impl Program {
     /// In the latter case the variable is removed by the shader compiler.
     ///
     pub fn use_instance_attribute(&self, name: &str, buffer: &InstanceBuffer) {
+        self.use_instance_attribute_with_divisor(name, buffer, 1);
+    }
+
+    ///
+    /// Uses the given [InstanceBuffer] data in this shader program and associates it with the given named variable.
+    /// Each value in the buffer is used when rendering `divisor` number of instances using the [Program::draw_arrays_instanced] or [Program::draw_elements_instanced] methods.
+    /// Therefore the buffer must contain at least the number of instances specified in those draw calls divided by the divisor.
+    /// For example, a divisor of 2 means that the first value is used for the first two instances, the second value for the next two instances and so on.
+    ///
+    /// # Panic
+    /// Will panic if the attribute is not defined in the shader code or not used.
+    /// In the latter case the variable is removed by the shader compiler.
+    /// Will panic if the divisor is zero.
+    ///
+    pub fn use_instance_attribute_with_divisor(
+        &self,
+        name: &str,
+        buffer: &InstanceBuffer,
+        divisor: u32,
+    ) {
+        if divisor == 0 {
+            panic!("the divisor of the instance attribute {} must be larger than zero, use a vertex attribute instead", name);
+        }
         if buffer.count() > 0 {
             buffer.bind();
             let loc = self.location(name);
@@ -411,13 +541,69 @@ impl Program {
                         0,
                     );
                 }
-                self.context.vertex_attrib_divisor(loc, 1);
+                self.context.vertex_attrib_divisor(loc, divisor);
                 self.context.bind_buffer(crate::context::ARRAY_BUFFER, None);
             }
             self.unuse_program();
         }
     }
 
+    ///
+    /// Runs the vertex shader of this program on `count` vertices and captures the varyings given at construction
+    /// (see [Program::from_source_with_transform_feedback]) into the given vertex buffers, one buffer per varying in the same order.
+    /// Nothing is rasterized, so the fragment shader is not executed.
+    /// The vertex buffers must have room for at least `count` values, see for example [VertexBuffer::new_with_capacity],
+    /// and cannot be used as vertex attributes in the same call, see [PingPongBuffer].
+    ///
+    /// **Note:** The vertex attributes must be defined using the use_attribute methods before calling this method.
+    ///
+    /// # Panic
+    /// Will panic if the number of vertex buffers does not match the number of varyings.
+    ///
+    pub fn transform_feedback(&self, count: u32, outputs: &[&VertexBuffer]) {
+        if outputs.len() != self.varyings_count {
+            panic!(
+                "the number of transform feedback buffers ({}) must match the number of varyings ({})",
+                outputs.len(),
+                self.varyings_count
+            );
+        }
+        self.use_program();
+        unsafe {
+            self.context.enable(crate::context::RASTERIZER_DISCARD);
+            for (index, buffer) in outputs.iter().enumerate() {
+                self.context.bind_buffer_base(
+                    crate::context::TRANSFORM_FEEDBACK_BUFFER,
+                    index as u32,
+                    Some(buffer.id()),
+                );
+            }
+            self.context
+                .begin_transform_feedback(crate::context::POINTS);
+            self.context
+                .draw_arrays(crate::context::POINTS, 0, count as i32);
+            self.context.end_transform_feedback();
+            for index in 0..outputs.len() {
+                self.context.bind_buffer_base(
+                    crate::context::TRANSFORM_FEEDBACK_BUFFER,
+                    index as u32,
+                    None,
+                );
+            }
+            self.context.disable(crate::context::RASTERIZER_DISCARD);
+            for location in self.attributes.values() {
+                self.context.disable_vertex_attrib_array(*location);
+            }
+            self.context.bind_vertex_array(None);
+        }
+        self.unuse_program();
+
+        #[cfg(debug_assertions)]
+        self.context
+            .error_check()
+            .expect("Unexpected rendering error occured")
+    }
+
     ///
     /// Draws `count` number of triangles with the given render states and viewport using this shader program.
     /// Requires that all attributes and uniforms have been defined using the use_attribute and use_uniform methods.
@@ -427,6 +613,9 @@ impl Program {
     pub fn draw_arrays(&self, render_states: RenderStates, viewport: Viewport, count: u32) {
         self.context.set_viewport(viewport);
         self.context.set_render_states(render_states);
+        self.use_clip_planes(render_states.clip_planes);
+        self.context
+            .record_color_space_draw(|| *self.color_encoding.read().unwrap(), render_states.blend);
         self.use_program();
         unsafe {
             self.context
@@ -457,6 +646,9 @@ impl Program {
     ) {
         self.context.set_viewport(viewport);
         self.context.set_render_states(render_states);
+        self.use_clip_planes(render_states.clip_planes);
+        self.context
+            .record_color_space_draw(|| *self.color_encoding.read().unwrap(), render_states.blend);
         self.use_program();
         unsafe {
             self.context.draw_arrays_instanced(
@@ -515,6 +707,9 @@ impl Program {
     ) {
         self.context.set_viewport(viewport);
         self.context.set_render_states(render_states);
+        self.use_clip_planes(render_states.clip_planes);
+        self.context
+            .record_color_space_draw(|| *self.color_encoding.read().unwrap(), render_states.blend);
         self.use_program();
         element_buffer.bind();
         unsafe {
@@ -576,6 +771,9 @@ impl Program {
     ) {
         self.context.set_viewport(viewport);
         self.context.set_render_states(render_states);
+        self.use_clip_planes(render_states.clip_planes);
+        self.context
+            .record_color_space_draw(|| *self.color_encoding.read().unwrap(), render_states.blend);
         self.use_program();
         element_buffer.bind();
         unsafe {
@@ -601,6 +799,37 @@ impl Program {
             .expect("Unexpected rendering error occured")
     }
 
+    ///
+    /// Returns how the colors written by this program are encoded, which is used by the color space validation, see [Context::set_color_space_validation].
+    ///
+    pub fn color_encoding(&self) -> ColorEncoding {
+        *self.color_encoding.read().unwrap()
+    }
+
+    ///
+    /// Specifies whether the colors written by this program are tone mapped, which is used by the color space validation.
+    /// This is called automatically when sending the tone mapping uniforms to the program.
+    ///
+    pub fn set_tone_mapped(&self, tone_mapped: bool) {
+        self.color_encoding.write().unwrap().tone_mapped = tone_mapped;
+    }
+
+    ///
+    /// Specifies whether the colors written by this program are sRGB encoded, which is used by the color space validation.
+    /// This is called automatically when sending the color mapping uniforms to the program.
+    ///
+    pub fn set_srgb_encoded(&self, srgb: bool) {
+        self.color_encoding.write().unwrap().srgb = srgb;
+    }
+
+    // Sends the user clip planes to the code added by ShaderDialect::add_clip_planes.
+    fn use_clip_planes(&self, clip_planes: ClipPlanes) {
+        self.use_uniform_if_required("userClipPlaneCount", clip_planes.planes().len() as i32);
+        if !clip_planes.is_empty() && self.requires_uniform("userClipPlanes") {
+            self.use_uniform_array("userClipPlanes", clip_planes.planes());
+        }
+    }
+
     ///
     /// Returns true if this program uses the uniform with the given name.
     ///
@@ -0,0 +1,378 @@
+use crate::core::*;
+use std::collections::VecDeque;
+
+///
+/// An occlusion query which determines whether any samples passed the depth and stencil test in the render calls issued between [OcclusionQuery::begin] and [OcclusionQuery::end].
+/// This can for example be used to skip rendering an object when its bounding box was hidden behind other objects in the previous frame.
+///
+/// The result is computed asynchronously on the GPU, so use [OcclusionQuery::result] to poll for the result without blocking.
+/// Up to [OcclusionQuery::MAX_PENDING_QUERIES] queries can be pending at the same time, so it is possible to begin a new query each frame
+/// even though the GPU is a few frames behind, without losing the results of the previous queries.
+///
+/// **Note:** Not supported on OpenGL ES 2.0 and WebGL 1 contexts, use [OcclusionQuery::is_supported] to check whether occlusion queries are supported.
+///
+pub struct OcclusionQuery {
+    context: Context,
+    active: Option<Query>,
+    pending: VecDeque<Query>,
+    available: Vec<Query>,
+    result: Option<bool>,
+}
+
+impl OcclusionQuery {
+    /// The maximum number of queries which are pending on the GPU at the same time.
+    /// When beginning a query while this number of queries are pending, the CPU waits for the oldest query to finish.
+    pub const MAX_PENDING_QUERIES: usize = 4;
+
+    ///
+    /// Creates a new occlusion query.
+    ///
+    /// # Panic
+    /// Will panic if occlusion queries are not supported, see [OcclusionQuery::is_supported].
+    ///
+    pub fn new(context: &Context) -> Self {
+        if !Self::is_supported(context) {
+            panic!("occlusion queries are not supported by the graphics backend");
+        }
+        Self {
+            context: context.clone(),
+            active: None,
+            pending: VecDeque::new(),
+            available: Vec::new(),
+            result: None,
+        }
+    }
+
+    ///
+    /// Returns whether or not occlusion queries are supported by the graphics backend, which is not the case on OpenGL ES 2.0 and WebGL 1 contexts.
+    ///
+    pub fn is_supported(context: &Context) -> bool {
+        !context.is_legacy()
+    }
+
+    ///
+    /// Begins the occlusion query. All render calls until [OcclusionQuery::end] is called contributes to the result of the query.
+    /// Usually, the objects are rendered without writing to the color and depth buffer, see [WriteMask::NONE].
+    ///
+    /// # Panic
+    /// Will panic if the query is already active or if another occlusion query is active.
+    ///
+    pub fn begin(&mut self) {
+        if self.active.is_some() {
+            panic!("cannot begin an occlusion query that is already active");
+        }
+        self.poll();
+        let mut query = if let Some(query) = self.available.pop() {
+            query
+        } else if self.pending.len() < Self::MAX_PENDING_QUERIES {
+            Query::new(&self.context, crate::context::ANY_SAMPLES_PASSED)
+        } else {
+            // Wait for the oldest query instead of discarding its result
+            let mut query = self.pending.pop_front().unwrap();
+            self.result = query.wait_for_result().map(|r| r != 0);
+            query
+        };
+        query.begin();
+        self.active = Some(query);
+    }
+
+    ///
+    /// Ends the occlusion query. The result is available some time after this call, see [OcclusionQuery::result].
+    ///
+    /// # Panic
+    /// Will panic if the query is not active.
+    ///
+    pub fn end(&mut self) {
+        let Some(mut query) = self.active.take() else {
+            panic!("cannot end an occlusion query that is not active");
+        };
+        query.end();
+        self.pending.push_back(query);
+    }
+
+    ///
+    /// Begins the query, calls the `render` closure and ends the query.
+    ///
+    pub fn measure(&mut self, render: impl FnOnce()) {
+        self.begin();
+        render();
+        self.end();
+    }
+
+    ///
+    /// Returns whether or not any samples passed in the most recent query that has finished on the GPU, without waiting for the GPU.
+    /// Returns `None` if no query has finished yet.
+    ///
+    pub fn result(&mut self) -> Option<bool> {
+        self.poll();
+        self.result
+    }
+
+    ///
+    /// Returns whether or not any samples passed in the most recently issued query and waits for the GPU to finish the query if necessary.
+    /// Returns `None` if no query has been issued.
+    ///
+    /// **Note:** This stalls the CPU until the GPU has finished the query, so prefer [OcclusionQuery::result] if possible.
+    ///
+    pub fn wait_for_result(&mut self) -> Option<bool> {
+        while let Some(mut query) = self.pending.pop_front() {
+            self.result = query.wait_for_result().map(|r| r != 0);
+            self.available.push(query);
+        }
+        self.result
+    }
+
+    ///
+    /// Returns whether or not any issued query has not yet finished on the GPU.
+    ///
+    pub fn is_pending(&mut self) -> bool {
+        self.poll();
+        !self.pending.is_empty()
+    }
+
+    // Moves the queries that have finished on the GPU, which finish in the order they were issued, to the available queries.
+    fn poll(&mut self) {
+        while let Some(query) = self.pending.front_mut() {
+            if query.is_pending() {
+                break;
+            }
+            self.result = query.result().map(|r| r != 0);
+            let query = self.pending.pop_front().unwrap();
+            self.available.push(query);
+        }
+    }
+}
+
+///
+/// A timer query which measures the time it takes the GPU to execute the render calls issued between [TimerQuery::begin] and [TimerQuery::end].
+/// See [RenderProfiler] for measuring several named render passes.
+///
+/// The result is computed asynchronously on the GPU, so use [TimerQuery::result] to poll for the result without blocking.
+/// On OpenGL ES and web, a result is discarded if a disjoint operation, for example a change of the GPU frequency, happened during the query,
+/// in which case the previous result is returned.
+///
+/// **Note:** Requires OpenGL 3.3, the `GL_EXT_disjoint_timer_query` extension on OpenGL ES or the `EXT_disjoint_timer_query_webgl2` extension on web,
+/// use [TimerQuery::is_supported] to check whether timer queries are supported.
+///
+pub struct TimerQuery {
+    query: Query,
+}
+
+impl TimerQuery {
+    ///
+    /// Creates a new timer query.
+    ///
+    /// # Panic
+    /// Will panic if timer queries are not supported, see [TimerQuery::is_supported].
+    ///
+    pub fn new(context: &Context) -> Self {
+        if !Self::is_supported(context) {
+            panic!("timer queries are not supported by the graphics backend");
+        }
+        Self {
+            query: Query::new(context, crate::context::TIME_ELAPSED),
+        }
+    }
+
+    ///
+    /// Returns whether or not timer queries are supported by the graphics backend.
+    ///
+    pub fn is_supported(context: &Context) -> bool {
+        context.capabilities().timer_queries
+    }
+
+    ///
+    /// Begins the timer query. The GPU time of all render calls until [TimerQuery::end] is called contributes to the result of the query.
+    ///
+    /// # Panic
+    /// Will panic if the query is already active or if another timer query is active.
+    ///
+    pub fn begin(&mut self) {
+        self.query.begin();
+    }
+
+    ///
+    /// Ends the timer query. The result is available some time after this call, see [TimerQuery::result].
+    ///
+    /// # Panic
+    /// Will panic if the query is not active.
+    ///
+    pub fn end(&mut self) {
+        self.query.end();
+    }
+
+    ///
+    /// Begins the query, calls the `render` closure and ends the query.
+    ///
+    pub fn measure(&mut self, render: impl FnOnce()) {
+        self.begin();
+        render();
+        self.end();
+    }
+
+    ///
+    /// Returns the GPU time in milliseconds of the most recent query that has finished on the GPU, without waiting for the GPU.
+    /// Returns `None` if no query has finished yet.
+    ///
+    pub fn result(&mut self) -> Option<f64> {
+        self.query.result().map(nanoseconds_to_milliseconds)
+    }
+
+    ///
+    /// Returns the GPU time in milliseconds of the most recently issued query and waits for the GPU to finish the query if necessary.
+    /// Returns `None` if no query has been issued.
+    ///
+    /// **Note:** This stalls the CPU until the GPU has finished the query, so prefer [TimerQuery::result] if possible.
+    ///
+    pub fn wait_for_result(&mut self) -> Option<f64> {
+        self.query
+            .wait_for_result()
+            .map(nanoseconds_to_milliseconds)
+    }
+
+    ///
+    /// Returns whether or not the most recently issued query has not yet finished on the GPU.
+    ///
+    pub fn is_pending(&mut self) -> bool {
+        self.query.is_pending()
+    }
+}
+
+fn nanoseconds_to_milliseconds(nanoseconds: u64) -> f64 {
+    nanoseconds as f64 * 1.0e-6
+}
+
+// Defined by the EXT_disjoint_timer_query extension.
+const GPU_DISJOINT_EXT: u32 = 0x8FBB;
+
+struct Query {
+    context: Context,
+    id: crate::context::Query,
+    target: u32,
+    active: bool,
+    issued: bool,
+    result: Option<u64>,
+}
+
+impl Query {
+    fn new(context: &Context, target: u32) -> Self {
+        Self {
+            context: context.clone(),
+            id: context.acquire_query(target),
+            target,
+            active: false,
+            issued: false,
+            result: None,
+        }
+    }
+
+    // Timer queries on OpenGL ES and WebGL are invalid if a disjoint operation, for example a change of the GPU frequency, happened while they were active.
+    fn has_disjoint_results(&self) -> bool {
+        self.target == crate::context::TIME_ELAPSED
+            && (cfg!(target_arch = "wasm32") || self.context.version().is_embedded)
+    }
+
+    fn begin(&mut self) {
+        if self.active {
+            panic!("cannot begin a query that is already active");
+        }
+        if self.context.activate_query(self.target) {
+            panic!("cannot begin a query while another query of the same type is active");
+        }
+        self.poll();
+        if self.has_disjoint_results() {
+            // Reading the disjoint state resets it, so only disjoint operations during this query are reported afterwards
+            unsafe {
+                self.context.get_parameter_i32(GPU_DISJOINT_EXT);
+            }
+        }
+        unsafe {
+            self.context.begin_query(self.target, self.id);
+        }
+        self.active = true;
+    }
+
+    fn end(&mut self) {
+        if !self.active {
+            panic!("cannot end a query that is not active");
+        }
+        unsafe {
+            self.context.end_query(self.target);
+        }
+        self.context.deactivate_query(self.target);
+        self.active = false;
+        self.issued = true;
+    }
+
+    fn result(&mut self) -> Option<u64> {
+        self.poll();
+        self.result
+    }
+
+    fn wait_for_result(&mut self) -> Option<u64> {
+        if self.issued && !self.active {
+            let result = self.read_result();
+            let disjoint = self.has_disjoint_results()
+                && unsafe { self.context.get_parameter_i32(GPU_DISJOINT_EXT) != 0 };
+            // The result of a query during a disjoint operation is discarded, so the previous result is kept
+            if !disjoint {
+                self.result = Some(result);
+            }
+            self.issued = false;
+        }
+        self.result
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_result(&self) -> u64 {
+        // Read the 64 bit result into client memory, since the time in nanoseconds overflows 32 bits after a few seconds
+        let mut result = 0u64;
+        unsafe {
+            self.context.get_query_parameter_u64_with_offset(
+                self.id,
+                crate::context::QUERY_RESULT,
+                &mut result as *mut u64 as usize,
+            );
+        }
+        result
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read_result(&self) -> u64 {
+        // WebGL does not support 64 bit query results
+        unsafe {
+            self.context
+                .get_query_parameter_u32(self.id, crate::context::QUERY_RESULT) as u64
+        }
+    }
+
+    fn is_pending(&mut self) -> bool {
+        self.poll();
+        self.issued
+    }
+
+    fn poll(&mut self) {
+        if self.issued && !self.active {
+            let available = unsafe {
+                self.context
+                    .get_query_parameter_u32(self.id, crate::context::QUERY_RESULT_AVAILABLE)
+                    != 0
+            };
+            if available {
+                self.wait_for_result();
+            }
+        }
+    }
+}
+
+impl Drop for Query {
+    fn drop(&mut self) {
+        if self.active {
+            unsafe {
+                self.context.end_query(self.target);
+            }
+            self.context.deactivate_query(self.target);
+        }
+        self.context.release_query(self.target, self.id);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::core::*;
+use std::collections::VecDeque;
+
+///
+/// Measures the GPU time of named render passes using [TimerQuery]s.
+/// Wrap each render pass in a call to [RenderProfiler::measure] every frame and use [RenderProfiler::results] to get the most recent GPU time of each pass.
+/// Since the results are computed asynchronously on the GPU, they lag a few frames behind the current frame.
+///
+/// If timer queries are not supported (see [TimerQuery::is_supported]), the render passes are still executed, but no results are reported.
+///
+pub struct RenderProfiler {
+    context: Context,
+    supported: bool,
+    passes: Vec<ProfiledPass>,
+}
+
+struct ProfiledPass {
+    name: String,
+    pending: VecDeque<TimerQuery>,
+    available: Vec<TimerQuery>,
+    time: Option<f64>,
+}
+
+impl ProfiledPass {
+    fn poll(&mut self) {
+        while let Some(query) = self.pending.front_mut() {
+            if query.is_pending() {
+                break;
+            }
+            self.time = query.result();
+            let query = self.pending.pop_front().unwrap();
+            self.available.push(query);
+        }
+    }
+}
+
+impl RenderProfiler {
+    ///
+    /// Creates a new render profiler.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            supported: TimerQuery::is_supported(context),
+            passes: Vec::new(),
+        }
+    }
+
+    ///
+    /// Measures the GPU time of the render calls issued in the `render` closure and reports it as the render pass with the given name.
+    /// Render passes cannot be nested since only one timer query can be active at a time.
+    ///
+    /// # Panic
+    /// Will panic if called from within the `render` closure of another render pass.
+    ///
+    pub fn measure<T>(&mut self, name: &str, render: impl FnOnce() -> T) -> T {
+        if !self.supported {
+            return render();
+        }
+        let index = if let Some(index) = self.passes.iter().position(|p| p.name == name) {
+            index
+        } else {
+            self.passes.push(ProfiledPass {
+                name: name.to_string(),
+                pending: VecDeque::new(),
+                available: Vec::new(),
+                time: None,
+            });
+            self.passes.len() - 1
+        };
+        let pass = &mut self.passes[index];
+        pass.poll();
+        let mut query = pass
+            .available
+            .pop()
+            .unwrap_or_else(|| TimerQuery::new(&self.context));
+        query.begin();
+        let result = render();
+        query.end();
+        pass.pending.push_back(query);
+        result
+    }
+
+    ///
+    /// Returns the name and the most recent GPU time in milliseconds of each render pass in the order the passes were first measured.
+    /// Render passes where no result is available yet are not included.
+    ///
+    pub fn results(&mut self) -> Vec<(String, f64)> {
+        self.passes
+            .iter_mut()
+            .filter_map(|pass| {
+                pass.poll();
+                pass.time.map(|time| (pass.name.clone(), time))
+            })
+            .collect()
+    }
+
+    ///
+    /// Returns the sum of the most recent GPU times in milliseconds of all render passes.
+    ///
+    pub fn total(&mut self) -> f64 {
+        self.results().iter().map(|(_, time)| time).sum()
+    }
+
+    ///
+    /// Removes all render passes and their results.
+    ///
+    pub fn clear(&mut self) {
+        self.passes.clear();
+    }
+}
@@ -2,6 +2,8 @@
 //! Definitions of the input state needed for any draw call.
 //!
 
+use crate::core::*;
+
 ///
 /// A set of render specific states that has to be specified at each render call.
 ///
@@ -19,6 +21,12 @@ pub struct RenderStates {
     ///
     pub depth_test: DepthTest,
 
+    ///
+    /// Defines the mapping of the depth from normalized device coordinates to the depth stored in the depth buffer in a render call.
+    /// This can for example be used to render an overlay in front of the rest of the scene or to partition the depth buffer between several ranges of a large scene.
+    ///
+    pub depth_range: DepthRange,
+
     ///
     /// Defines which type of blending to use for a render call.
     /// Blending allows combining each color channel of a render call with the color already in the
@@ -27,10 +35,30 @@ pub struct RenderStates {
     ///
     pub blend: Blend,
 
+    ///
+    /// Whether or not the alpha value of the output color is used to compute a coverage mask when rendering to a multisample render target, see for example [RenderTargetMultisample].
+    /// This gives antialiased edges on alpha tested geometry, for example leaves and fences, instead of the hard edges produced by discarding fragments.
+    /// Has no effect when rendering to a render target without multisampling.
+    ///
+    pub alpha_to_coverage: bool,
+
     ///
     /// Defines whether the triangles that are backfacing, frontfacing or both should be skipped in a render call.
     ///
     pub cull: Cull,
+
+    ///
+    /// Defines the stencil test and how to update the stencil buffer in a render call.
+    /// The stencil test determines whether or not a fragment from the current render call should be discarded
+    /// when comparing a reference value with the value in the stencil buffer.
+    ///
+    pub stencil: Stencil,
+
+    ///
+    /// Defines user clip planes which cut away the parts of the geometry on the negative side of the planes in a render call,
+    /// which can for example be used to make section cuts through a model.
+    ///
+    pub clip_planes: ClipPlanes,
 }
 
 ///
@@ -79,6 +107,280 @@ impl Default for DepthTest {
     }
 }
 
+///
+/// Defines the mapping of the depth from normalized device coordinates (from -1 to 1) to window coordinates,
+/// ie. the depth that is stored in the depth buffer and compared in the depth test (see [DepthTest]).
+/// Both values are clamped to the range from 0 to 1 and the near value can be larger than the far value, which inverts the depth.
+///
+/// The depth can be reversed by using [DepthRange::REVERSED] together with [DepthTest::Greater] and clearing the depth to 0.0 instead of 1.0.
+/// Note that this does not improve the depth precision, since the depth in normalized device coordinates is from -1 to 1 on all supported platforms
+/// and the precision is therefore already lost before the depth range is applied. The well-known precision gain of reversed-Z requires the
+/// depth in normalized device coordinates to be from 0 to 1 (`glClipControl`) together with a floating point depth buffer, which is not available in WebGL and OpenGL ES.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DepthRange {
+    /// The depth in the depth buffer of the near plane.
+    pub near: f32,
+    /// The depth in the depth buffer of the far plane.
+    pub far: f32,
+}
+
+impl DepthRange {
+    ///
+    /// The entire depth range where the near plane is mapped to 0 and the far plane to 1.
+    ///
+    pub const FULL: Self = Self {
+        near: 0.0,
+        far: 1.0,
+    };
+
+    ///
+    /// The entire depth range, but reversed so the near plane is mapped to 1 and the far plane to 0.
+    ///
+    pub const REVERSED: Self = Self {
+        near: 1.0,
+        far: 0.0,
+    };
+
+    ///
+    /// Creates a new depth range which maps the near plane to `near` and the far plane to `far`.
+    ///
+    pub const fn new(near: f32, far: f32) -> Self {
+        Self { near, far }
+    }
+}
+
+impl Default for DepthRange {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+///
+/// A set of at most [ClipPlanes::MAX] user clip planes, each given as `(normal, distance)`,
+/// where only the points `p` with `normal.dot(p) + distance >= 0` for all planes are rendered.
+///
+/// When drawing with a [Program](crate::core::Program) directly, the planes are given in clip space, ie. in the same space as the `gl_Position` output of the vertex shader.
+/// When rendering with a material or an effect in the renderer module, the planes are given in world space and are transformed to clip space using the camera.
+/// Use [ClipPlanes::transformed] to transform the planes between spaces.
+///
+/// The clipping is added automatically to the shaders when creating a [Program](crate::core::Program).
+/// It is done in hardware using `gl_ClipDistance` if supported, see [Capabilities::clip_distances](crate::core::Capabilities::clip_distances),
+/// and otherwise by discarding fragments, which disables early depth testing and therefore is slower.
+/// To avoid this for programs which are never rendered with clip planes, the fragments are only discarded if the fragment shader defines [ClipPlanes::SHADER_DEFINE],
+/// otherwise the clip planes are ignored when clip distances are not supported.
+/// The materials and effects in the renderer module are compiled with this define when they are rendered with clip planes.
+/// Clip planes are not supported on OpenGL ES 2.0 and WebGL 1 contexts.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ClipPlanes {
+    planes: [Vec4; ClipPlanes::MAX],
+    count: usize,
+}
+
+impl ClipPlanes {
+    ///
+    /// The maximum number of clip planes.
+    ///
+    pub const MAX: usize = 6;
+
+    ///
+    /// The name of the define, ie. `#define USE_CLIP_PLANES`, which a fragment shader must contain for the clip planes to be evaluated
+    /// when hardware clip distances are not supported, see [ClipPlanes].
+    ///
+    pub const SHADER_DEFINE: &'static str = "USE_CLIP_PLANES";
+
+    ///
+    /// Creates a new set of clip planes, each given as `(normal, distance)`.
+    ///
+    /// # Panic
+    /// Will panic if more than [ClipPlanes::MAX] planes are given.
+    ///
+    pub fn new(planes: &[Vec4]) -> Self {
+        if planes.len() > Self::MAX {
+            panic!(
+                "the number of clip planes ({}) must be at most {}",
+                planes.len(),
+                Self::MAX
+            );
+        }
+        let mut clip_planes = Self::default();
+        clip_planes.planes[..planes.len()].copy_from_slice(planes);
+        clip_planes.count = planes.len();
+        clip_planes
+    }
+
+    ///
+    /// Returns the clip planes.
+    ///
+    pub fn planes(&self) -> &[Vec4] {
+        &self.planes[..self.count]
+    }
+
+    ///
+    /// Returns whether or not there are any clip planes.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    ///
+    /// Returns the clip planes transformed by the given transformation, ie. a point `p` is on the positive side of a returned plane
+    /// if `transformation.inverse() * p` is on the positive side of the original plane.
+    /// For example, use the view projection matrix of a camera to transform planes from world space to clip space.
+    ///
+    pub fn transformed(&self, transformation: Mat4) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        let Some(inverse) = transformation.invert() else {
+            return *self;
+        };
+        // Planes are transformed by the inverse transpose, ie. multiplied from the right by the inverse
+        let mut clip_planes = *self;
+        for plane in clip_planes.planes[..self.count].iter_mut() {
+            *plane = Vec4::new(
+                plane.dot(inverse.x),
+                plane.dot(inverse.y),
+                plane.dot(inverse.z),
+                plane.dot(inverse.w),
+            );
+        }
+        clip_planes
+    }
+}
+
+impl Default for ClipPlanes {
+    fn default() -> Self {
+        Self {
+            planes: [Vec4::new(0.0, 0.0, 0.0, 0.0); Self::MAX],
+            count: 0,
+        }
+    }
+}
+
+///
+/// Defines the stencil test and how to update the stencil buffer in a render call.
+///
+/// **Note:** Stencil test has no effect if the render call is not writing to a render target with a stencil buffer,
+/// ie. a depth texture with a [DepthStencilFormat](crate::core::DepthStencilFormat) or a screen with a stencil buffer.
+///
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Stencil {
+    Enabled {
+        /// The comparison between the reference value and the value in the stencil buffer.
+        test: StencilTest,
+        /// The reference value used in the stencil test and by [StencilOperation::Replace].
+        reference: u8,
+        /// Bitwise mask applied to both the reference value and the value in the stencil buffer before the stencil test.
+        read_mask: u8,
+        /// Bitwise mask that determines which bits of the stencil buffer are updated.
+        write_mask: u8,
+        /// The operation applied when the stencil test fails.
+        stencil_fail: StencilOperation,
+        /// The operation applied when the stencil test passes but the depth test fails.
+        depth_fail: StencilOperation,
+        /// The operation applied when both the stencil and the depth test passes.
+        pass: StencilOperation,
+    },
+    Disabled,
+}
+
+impl Stencil {
+    ///
+    /// Always passes the stencil test and writes the reference value to the stencil buffer where the depth test passes.
+    /// This is typically used to mark an area, for example a portal or an object that should be outlined.
+    ///
+    pub const fn write(reference: u8) -> Self {
+        Self::Enabled {
+            test: StencilTest::Always,
+            reference,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+            stencil_fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Replace,
+        }
+    }
+
+    ///
+    /// Only renders where the value in the stencil buffer is equal to the reference value and does not change the stencil buffer.
+    ///
+    pub const fn equal(reference: u8) -> Self {
+        Self::Enabled {
+            test: StencilTest::Equal,
+            reference,
+            read_mask: 0xFF,
+            write_mask: 0x00,
+            stencil_fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Keep,
+        }
+    }
+
+    ///
+    /// Only renders where the value in the stencil buffer is not equal to the reference value and does not change the stencil buffer.
+    ///
+    pub const fn not_equal(reference: u8) -> Self {
+        Self::Enabled {
+            test: StencilTest::NotEqual,
+            reference,
+            read_mask: 0xFF,
+            write_mask: 0x00,
+            stencil_fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Keep,
+        }
+    }
+}
+
+impl Default for Stencil {
+    fn default() -> Self {
+        Self::Disabled
+    }
+}
+
+///
+/// Determines whether or not a fragment/pixel from the current render call should be discarded
+/// when comparing the reference value in [Stencil] with the value in the stencil buffer.
+///
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StencilTest {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always,
+}
+
+///
+/// Defines how the value in the stencil buffer is updated in [Stencil].
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StencilOperation {
+    /// Keeps the current value.
+    Keep,
+    /// Sets the value to 0.
+    Zero,
+    /// Sets the value to the reference value.
+    Replace,
+    /// Increments the value and clamps to the maximum value.
+    Increment,
+    /// Increments the value and wraps to 0 when exceeding the maximum value.
+    IncrementWrap,
+    /// Decrements the value and clamps to 0.
+    Decrement,
+    /// Decrements the value and wraps to the maximum value when going below 0.
+    DecrementWrap,
+    /// Bitwise inverts the value.
+    Invert,
+}
+
 ///
 /// Defines which channels (red, green, blue, alpha and depth) to write to in a render call.
 ///
@@ -190,6 +492,31 @@ impl Blend {
         alpha_equation: BlendEquationType::Add,
     };
 
+    ///
+    /// Transparency blending parameters for colors with premultiplied alpha, ie. where the color channels have already been multiplied with the alpha channel.
+    ///
+    pub const PREMULTIPLIED_ALPHA: Self = Self::Enabled {
+        source_rgb_multiplier: BlendMultiplierType::One,
+        source_alpha_multiplier: BlendMultiplierType::One,
+        destination_rgb_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+        destination_alpha_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+        rgb_equation: BlendEquationType::Add,
+        alpha_equation: BlendEquationType::Add,
+    };
+
+    ///
+    /// Blending parameters for the accumulation pass of weighted blended order-independent transparency.
+    /// The weighted premultiplied colors are added together while the alpha channel accumulates the product of one minus the alpha values, ie. the revealage.
+    ///
+    pub const WEIGHTED_BLENDED_ACCUMULATION: Self = Self::Enabled {
+        source_rgb_multiplier: BlendMultiplierType::One,
+        source_alpha_multiplier: BlendMultiplierType::Zero,
+        destination_rgb_multiplier: BlendMultiplierType::One,
+        destination_alpha_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+        rgb_equation: BlendEquationType::Add,
+        alpha_equation: BlendEquationType::Add,
+    };
+
     ///
     /// Adds the color of the render target with the output color of the render call.
     ///
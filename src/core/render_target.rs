@@ -26,6 +26,10 @@ mod depth_target_multisample;
 #[doc(inline)]
 pub use depth_target_multisample::*;
 
+mod pixel_readback;
+#[doc(inline)]
+pub use pixel_readback::*;
+
 use crate::core::*;
 
 use crate::context::Framebuffer;
@@ -100,6 +104,7 @@ impl<'a> RenderTarget<'a> {
         self.context.set_scissor(scissor_box);
         self.bind(crate::context::DRAW_FRAMEBUFFER);
         clear_state.apply(&self.context);
+        self.record_frame_pass(FramePassKind::Clear, scissor_box);
         self
     }
 
@@ -124,6 +129,7 @@ impl<'a> RenderTarget<'a> {
         self.context.set_scissor(scissor_box);
         self.bind(crate::context::DRAW_FRAMEBUFFER);
         render()?;
+        self.record_frame_pass(FramePassKind::Write, scissor_box);
         if let Some(ref color) = self.color {
             color.generate_mip_maps();
         }
@@ -159,20 +165,8 @@ impl<'a> RenderTarget<'a> {
     /// - 32-bit float RGBA (Specify `T` as either `Vec4<f32>` or `[f32; 4]`) which works with any render target using `f16` or `f32` as its base type.
     ///
     pub fn read_color_partially<T: TextureDataType>(&self, scissor_box: ScissorBox) -> Vec<T> {
-        if self.id.is_some() && self.color.is_none() {
-            panic!("Cannot read color from a render target without a color target");
-        }
-        let format = format_from_data_type::<T>();
-        let data_type = T::data_type();
-
-        // On web, the read format needs to be RGBA and f16 is not supported (see https://webglfundamentals.org/webgl/lessons/webgl-readpixels.html).
-        #[cfg(target_arch = "wasm32")]
-        if format != crate::context::RGBA
-            || !(data_type == crate::context::UNSIGNED_BYTE || data_type == crate::context::FLOAT)
-        {
-            panic!("Only the texture data types `Vec4<T>` and `[T; 4]` where `T` is either `u8` or `f32` are supported when reading color from a render target on web.");
-        }
-
+        let (format, data_type) = self.color_read_format::<T>();
+        self.record_frame_pass(FramePassKind::Read, scissor_box);
         self.bind(crate::context::DRAW_FRAMEBUFFER);
         self.bind(crate::context::READ_FRAMEBUFFER);
         let data_size = std::mem::size_of::<T>();
@@ -198,6 +192,48 @@ impl<'a> RenderTarget<'a> {
         pixels
     }
 
+    ///
+    /// Reads the colors of this render target back into a [CpuTexture], for example to take a screenshot of the [RenderTarget::screen] render target
+    /// or to compare the result of rendering to a reference image in a test.
+    /// If the color target of this render target is a [Texture2D], the result has the same format as the texture, see [ColorTarget::read_to_cpu_texture],
+    /// otherwise the result is unsigned byte RGBA data with the rows ordered from top to bottom.
+    ///
+    pub fn read_to_cpu_texture(&self) -> CpuTexture {
+        if let Some(color) = &self.color {
+            color.read_to_cpu_texture()
+        } else {
+            CpuTexture {
+                data: TextureData::RgbaU8(self.read_color::<[u8; 4]>()),
+                width: self.width,
+                height: self.height,
+                ..Default::default()
+            }
+        }
+    }
+
+    ///
+    /// Starts an asynchronous read of the colors of the pixels in this render target, see [PixelReadback].
+    /// The same restrictions on the generic parameter `T` as for [RenderTarget::read_color] applies.
+    ///
+    pub fn read_color_async<T: TextureDataType>(&self) -> PixelReadback<T> {
+        self.read_color_partially_async(self.scissor_box())
+    }
+
+    ///
+    /// Starts an asynchronous read of the colors of the pixels in this render target inside the given scissor box, see [PixelReadback].
+    /// The same restrictions on the generic parameter `T` as for [RenderTarget::read_color_partially] applies.
+    ///
+    pub fn read_color_partially_async<T: TextureDataType>(
+        &self,
+        scissor_box: ScissorBox,
+    ) -> PixelReadback<T> {
+        let (format, data_type) = self.color_read_format::<T>();
+        self.record_frame_pass(FramePassKind::Read, scissor_box);
+        self.bind(crate::context::DRAW_FRAMEBUFFER);
+        self.bind(crate::context::READ_FRAMEBUFFER);
+        PixelReadback::new(&self.context, scissor_box, format, data_type, true)
+    }
+
     ///
     /// Returns the depth values in this render target.
     ///
@@ -214,6 +250,7 @@ impl<'a> RenderTarget<'a> {
         if self.id.is_some() && self.depth.is_none() {
             panic!("cannot read depth from a render target without a depth target");
         }
+        self.record_frame_pass(FramePassKind::Read, scissor_box);
         self.bind(crate::context::DRAW_FRAMEBUFFER);
         self.bind(crate::context::READ_FRAMEBUFFER);
         let mut pixels = vec![0u8; scissor_box.width as usize * scissor_box.height as usize * 4];
@@ -231,6 +268,34 @@ impl<'a> RenderTarget<'a> {
         from_byte_slice(&pixels).to_vec()
     }
 
+    ///
+    /// Starts an asynchronous read of the depth values in this render target, see [PixelReadback].
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_depth_async(&self) -> PixelReadback<f32> {
+        self.read_depth_partially_async(self.scissor_box())
+    }
+
+    ///
+    /// Starts an asynchronous read of the depth values in this render target inside the given scissor box, see [PixelReadback].
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_depth_partially_async(&self, scissor_box: ScissorBox) -> PixelReadback<f32> {
+        if self.id.is_some() && self.depth.is_none() {
+            panic!("cannot read depth from a render target without a depth target");
+        }
+        self.record_frame_pass(FramePassKind::Read, scissor_box);
+        self.bind(crate::context::DRAW_FRAMEBUFFER);
+        self.bind(crate::context::READ_FRAMEBUFFER);
+        PixelReadback::new(
+            &self.context,
+            scissor_box,
+            crate::context::DEPTH_COMPONENT,
+            crate::context::FLOAT,
+            false,
+        )
+    }
+
     ///
     /// Creates a [RenderTarget] with the given low-level [Framebuffer]. Should only be used if the [Framebuffer] is used for something else, ie. to be able
     /// to combine this crate with functionality of another crate. Also see [Self::into_framebuffer].
@@ -291,6 +356,12 @@ impl<'a> RenderTarget<'a> {
                 crate::context::NEAREST,
             );
         }
+        if self.context.is_capturing_frame() || self.context.is_validating_color_space() {
+            for resource in self.frame_resources() {
+                self.context.record_frame_input(|| resource);
+            }
+            target.record_frame_pass(FramePassKind::Blit, target.scissor_box());
+        }
     }
 
     fn new_color(color: ColorTarget<'a>) -> Self {
@@ -319,6 +390,56 @@ impl<'a> RenderTarget<'a> {
         }
     }
 
+    fn color_read_format<T: TextureDataType>(&self) -> (u32, u32) {
+        if self.id.is_some() && self.color.is_none() {
+            panic!("Cannot read color from a render target without a color target");
+        }
+        let format = format_from_data_type::<T>();
+        let data_type = data_type_for_context(&self.context, T::data_type());
+
+        // On web, the read format needs to be RGBA and f16 is not supported (see https://webglfundamentals.org/webgl/lessons/webgl-readpixels.html).
+        #[cfg(target_arch = "wasm32")]
+        if format != crate::context::RGBA
+            || !(data_type == crate::context::UNSIGNED_BYTE || data_type == crate::context::FLOAT)
+        {
+            panic!("Only the texture data types `Vec4<T>` and `[T; 4]` where `T` is either `u8` or `f32` are supported when reading color from a render target on web.");
+        }
+        (format, data_type)
+    }
+
+    fn record_frame_pass(&self, kind: FramePassKind, scissor_box: ScissorBox) {
+        self.context.record_frame_pass(
+            kind,
+            || self.frame_resources(),
+            self.color.as_ref().map(|c| c.mip_level()).unwrap_or(0),
+            scissor_box,
+        );
+    }
+
+    fn frame_resources(&self) -> Vec<FrameResource> {
+        let mut resources = Vec::new();
+        if let Some(ref color) = self.color {
+            resources.extend(color.frame_resources());
+        }
+        if let Some(ref depth) = self.depth {
+            resources.push(depth.frame_resource());
+        }
+        if resources.is_empty() {
+            resources.push(match self.id {
+                Some(id) => FrameResource::texture(
+                    "Framebuffer",
+                    id,
+                    self.width,
+                    self.height,
+                    1,
+                    "external".to_string(),
+                ),
+                None => FrameResource::screen(self.width, self.height),
+            });
+        }
+        resources
+    }
+
     fn bind(&self, target: u32) {
         unsafe {
             self.context.bind_framebuffer(target, self.id);
@@ -360,12 +481,7 @@ fn new_framebuffer(context: &Context) -> crate::context::Framebuffer {
 
 #[cfg(debug_assertions)]
 fn multisample_sanity_check(context: &Context, number_of_samples: u32) {
-    let max_samples: u32 = unsafe {
-        context
-            .get_parameter_i32(crate::context::MAX_SAMPLES)
-            .try_into()
-            .unwrap()
-    };
+    let max_samples = context.capabilities().max_samples;
     if number_of_samples > max_samples {
         panic!("number_of_samples ({}) for multisample target is larger than supported number of samples: {}", number_of_samples, max_samples);
     }
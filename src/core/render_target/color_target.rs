@@ -22,6 +22,10 @@ impl<'a> ColorTarget<'a> {
         texture: &'a Texture2D,
         mip_level: Option<u32>,
     ) -> Self {
+        assert!(
+            texture.compressed_format().is_none(),
+            "a compressed texture cannot be used as a color target"
+        );
         ColorTarget {
             context: context.clone(),
             mip_level,
@@ -44,6 +48,25 @@ impl<'a> ColorTarget<'a> {
         }
     }
 
+    pub(in crate::core) fn new_texture_cube_map_array(
+        context: &Context,
+        texture: &'a TextureCubeMapArray,
+        layer: u32,
+        sides: &'a [CubeMapSide],
+        mip_level: Option<u32>,
+    ) -> Self {
+        ColorTarget {
+            context: context.clone(),
+            mip_level,
+            target: Some(ColorTexture::CubeMapArray {
+                texture,
+                layer,
+                sides,
+            }),
+            multisample_target: None,
+        }
+    }
+
     pub(in crate::core) fn new_texture_2d_array(
         context: &Context,
         texture: &'a Texture2DArray,
@@ -85,6 +108,7 @@ impl<'a> ColorTarget<'a> {
             scissor_box,
             ClearState {
                 depth: None,
+                stencil: None,
                 ..clear_state
             },
         );
@@ -130,6 +154,24 @@ impl<'a> ColorTarget<'a> {
         self.read_partially(self.scissor_box())
     }
 
+    ///
+    /// Reads the content of this color target back into a [CpuTexture].
+    /// If this color target is a [Texture2D], the result has the same format, filtering and wrapping as the texture, see [Texture2D::read_to_cpu_texture],
+    /// otherwise the result is unsigned byte RGBA data with linear filtering.
+    ///
+    pub fn read_to_cpu_texture(&self) -> CpuTexture {
+        if let Some(ColorTexture::Single(texture)) = self.target {
+            texture.read_mip_to_cpu_texture(self.mip_level)
+        } else {
+            CpuTexture {
+                data: TextureData::RgbaU8(self.read::<[u8; 4]>()),
+                width: self.width(),
+                height: self.height(),
+                ..Default::default()
+            }
+        }
+    }
+
     ///
     /// Returns the colors of the pixels in this color target inside the given scissor box.
     /// The number of channels per pixel and the data format for each channel returned from this function is specified by the generic parameter `T`.
@@ -146,6 +188,26 @@ impl<'a> ColorTarget<'a> {
         self.as_render_target().read_color_partially(scissor_box)
     }
 
+    ///
+    /// Starts an asynchronous read of the colors of the pixels in this color target, see [PixelReadback].
+    /// The same restrictions on the generic parameter `T` as for [ColorTarget::read] applies.
+    ///
+    pub fn read_async<T: TextureDataType>(&self) -> PixelReadback<T> {
+        self.read_partially_async(self.scissor_box())
+    }
+
+    ///
+    /// Starts an asynchronous read of the colors of the pixels in this color target inside the given scissor box, see [PixelReadback].
+    /// The same restrictions on the generic parameter `T` as for [ColorTarget::read_partially] applies.
+    ///
+    pub fn read_partially_async<T: TextureDataType>(
+        &self,
+        scissor_box: ScissorBox,
+    ) -> PixelReadback<T> {
+        self.as_render_target()
+            .read_color_partially_async(scissor_box)
+    }
+
     ///
     /// Returns the width of the color target in texels.
     /// If using the zero mip level of the underlying texture, then this is simply the width of that texture, otherwise it is the width of the given mip level.
@@ -160,6 +222,9 @@ impl<'a> ColorTarget<'a> {
                 ColorTexture::CubeMap { texture, .. } => {
                     size_with_mip(texture.width(), self.mip_level)
                 }
+                ColorTexture::CubeMapArray { texture, .. } => {
+                    size_with_mip(texture.width(), self.mip_level)
+                }
             }
         } else {
             self.multisample_target.as_ref().unwrap().width()
@@ -180,13 +245,67 @@ impl<'a> ColorTarget<'a> {
                 ColorTexture::CubeMap { texture, .. } => {
                     size_with_mip(texture.height(), self.mip_level)
                 }
+                ColorTexture::CubeMapArray { texture, .. } => {
+                    size_with_mip(texture.height(), self.mip_level)
+                }
             }
         } else {
             self.multisample_target.as_ref().unwrap().height()
         }
     }
 
-    pub(super) fn as_render_target(&self) -> RenderTarget<'a> {
+    pub(super) fn mip_level(&self) -> u32 {
+        self.mip_level.unwrap_or(0)
+    }
+
+    pub(super) fn frame_resources(&self) -> Vec<FrameResource> {
+        if let Some(target) = self.target {
+            match target {
+                ColorTexture::Single(texture) => vec![texture.frame_resource()],
+                ColorTexture::Array { texture, layers } => {
+                    let resource = texture.frame_resource();
+                    layers
+                        .iter()
+                        .map(|layer| FrameResource {
+                            subresource: Some(format!("layer {}", layer)),
+                            layers: 1,
+                            ..resource.clone()
+                        })
+                        .collect()
+                }
+                ColorTexture::CubeMap { texture, sides } => {
+                    let resource = texture.frame_resource();
+                    sides
+                        .iter()
+                        .map(|side| FrameResource {
+                            subresource: Some(format!("{:?}", side)),
+                            layers: 1,
+                            ..resource.clone()
+                        })
+                        .collect()
+                }
+                ColorTexture::CubeMapArray {
+                    texture,
+                    layer,
+                    sides,
+                } => {
+                    let resource = texture.frame_resource();
+                    sides
+                        .iter()
+                        .map(|side| FrameResource {
+                            subresource: Some(format!("layer {} {:?}", layer, side)),
+                            layers: 1,
+                            ..resource.clone()
+                        })
+                        .collect()
+                }
+            }
+        } else {
+            vec![self.multisample_target.as_ref().unwrap().frame_resource()]
+        }
+    }
+
+    pub(in crate::core) fn as_render_target(&self) -> RenderTarget<'a> {
         RenderTarget::new_color(self.clone())
     }
 
@@ -208,6 +327,11 @@ impl<'a> ColorTarget<'a> {
                         texture.generate_mip_maps()
                     }
                 }
+                ColorTexture::CubeMapArray { texture, .. } => {
+                    if self.mip_level.is_none() {
+                        texture.generate_mip_maps()
+                    }
+                }
             }
         }
     }
@@ -215,47 +339,66 @@ impl<'a> ColorTarget<'a> {
     pub(super) fn bind(&self, context: &Context) {
         if let Some(target) = self.target {
             match target {
-                ColorTexture::Single(texture) => unsafe {
-                    context.draw_buffers(&[crate::context::COLOR_ATTACHMENT0]);
+                ColorTexture::Single(texture) => {
+                    set_draw_buffers(context, 1);
                     texture.bind_as_color_target(0, self.mip_level.unwrap_or(0));
-                },
-                ColorTexture::Array { texture, layers } => unsafe {
-                    context.draw_buffers(
-                        &(0..layers.len())
-                            .map(|i| crate::context::COLOR_ATTACHMENT0 + i as u32)
-                            .collect::<Vec<u32>>(),
-                    );
-                    (0..layers.len()).for_each(|channel| {
+                }
+                ColorTexture::Array { texture, layers } => {
+                    (0..set_draw_buffers(context, layers.len())).for_each(|channel| {
                         texture.bind_as_color_target(
                             layers[channel],
                             channel as u32,
                             self.mip_level.unwrap_or(0),
                         );
                     });
-                },
-                ColorTexture::CubeMap { texture, sides } => unsafe {
-                    context.draw_buffers(
-                        &(0..sides.len())
-                            .map(|i| crate::context::COLOR_ATTACHMENT0 + i as u32)
-                            .collect::<Vec<u32>>(),
-                    );
-                    (0..sides.len()).for_each(|channel| {
+                }
+                ColorTexture::CubeMap { texture, sides } => {
+                    (0..set_draw_buffers(context, sides.len())).for_each(|channel| {
+                        texture.bind_as_color_target(
+                            sides[channel],
+                            channel as u32,
+                            self.mip_level.unwrap_or(0),
+                        );
+                    });
+                }
+                ColorTexture::CubeMapArray {
+                    texture,
+                    layer,
+                    sides,
+                } => {
+                    (0..set_draw_buffers(context, sides.len())).for_each(|channel| {
                         texture.bind_as_color_target(
+                            layer,
                             sides[channel],
                             channel as u32,
                             self.mip_level.unwrap_or(0),
                         );
                     });
-                },
+                }
             }
         } else {
-            unsafe {
-                context.draw_buffers(&[crate::context::COLOR_ATTACHMENT0]);
-                self.multisample_target
-                    .as_ref()
-                    .unwrap()
-                    .bind_as_color_target(0);
-            }
+            set_draw_buffers(context, 1);
+            self.multisample_target
+                .as_ref()
+                .unwrap()
+                .bind_as_color_target(0);
+        }
+    }
+}
+
+// Enables writing to the given number of color attachments and returns the number of color attachments that are written to,
+// which is only the first one if multiple render targets are not supported.
+fn set_draw_buffers(context: &Context, count: usize) -> usize {
+    if context.supports_multiple_render_targets() {
+        unsafe {
+            context.draw_buffers(
+                &(0..count)
+                    .map(|i| crate::context::COLOR_ATTACHMENT0 + i as u32)
+                    .collect::<Vec<u32>>(),
+            );
         }
+        count
+    } else {
+        count.min(1)
     }
 }
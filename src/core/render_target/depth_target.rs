@@ -34,6 +34,23 @@ impl<'a> DepthTarget<'a> {
         }
     }
 
+    pub(in crate::core) fn new_texture_cube_map_array(
+        context: &Context,
+        texture: &'a DepthTextureCubeMapArray,
+        layer: u32,
+        side: CubeMapSide,
+    ) -> Self {
+        Self {
+            context: context.clone(),
+            target: Some(DepthTexture::CubeMapArray {
+                texture,
+                layer,
+                side,
+            }),
+            multisample_target: None,
+        }
+    }
+
     pub(in crate::core) fn new_texture_2d_array(
         context: &Context,
         texture: &'a DepthTexture2DArray,
@@ -72,6 +89,7 @@ impl<'a> DepthTarget<'a> {
             scissor_box,
             ClearState {
                 depth: clear_state.depth,
+                stencil: clear_state.stencil,
                 ..ClearState::none()
             },
         );
@@ -117,6 +135,23 @@ impl<'a> DepthTarget<'a> {
         self.as_render_target().read_depth_partially(scissor_box)
     }
 
+    ///
+    /// Starts an asynchronous read of the depth values in this depth target, see [PixelReadback].
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_async(&self) -> PixelReadback<f32> {
+        self.read_partially_async(self.scissor_box())
+    }
+
+    ///
+    /// Starts an asynchronous read of the depth values in this depth target inside the given scissor box, see [PixelReadback].
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_partially_async(&self, scissor_box: ScissorBox) -> PixelReadback<f32> {
+        self.as_render_target()
+            .read_depth_partially_async(scissor_box)
+    }
+
     pub(super) fn as_render_target(&self) -> RenderTarget<'a> {
         RenderTarget::new_depth(self.clone())
     }
@@ -130,6 +165,7 @@ impl<'a> DepthTarget<'a> {
                 DepthTexture::Single(texture) => texture.width(),
                 DepthTexture::Array { texture, .. } => texture.width(),
                 DepthTexture::CubeMap { texture, .. } => texture.width(),
+                DepthTexture::CubeMapArray { texture, .. } => texture.width(),
             }
         } else {
             self.multisample_target.as_ref().unwrap().width()
@@ -145,12 +181,42 @@ impl<'a> DepthTarget<'a> {
                 DepthTexture::Single(texture) => texture.height(),
                 DepthTexture::Array { texture, .. } => texture.height(),
                 DepthTexture::CubeMap { texture, .. } => texture.height(),
+                DepthTexture::CubeMapArray { texture, .. } => texture.height(),
             }
         } else {
             self.multisample_target.as_ref().unwrap().height()
         }
     }
 
+    pub(super) fn frame_resource(&self) -> FrameResource {
+        if let Some(target) = &self.target {
+            match target {
+                DepthTexture::Single(texture) => texture.frame_resource(),
+                DepthTexture::Array { texture, layer } => FrameResource {
+                    subresource: Some(format!("layer {}", layer)),
+                    layers: 1,
+                    ..texture.frame_resource()
+                },
+                DepthTexture::CubeMap { texture, side } => FrameResource {
+                    subresource: Some(format!("{:?}", side)),
+                    layers: 1,
+                    ..texture.frame_resource()
+                },
+                DepthTexture::CubeMapArray {
+                    texture,
+                    layer,
+                    side,
+                } => FrameResource {
+                    subresource: Some(format!("layer {} {:?}", layer, side)),
+                    layers: 1,
+                    ..texture.frame_resource()
+                },
+            }
+        } else {
+            self.multisample_target.as_ref().unwrap().frame_resource()
+        }
+    }
+
     pub(super) fn bind(&self) {
         if let Some(target) = &self.target {
             match target {
@@ -163,6 +229,13 @@ impl<'a> DepthTarget<'a> {
                 DepthTexture::CubeMap { texture, side } => {
                     texture.bind_as_depth_target(*side);
                 }
+                DepthTexture::CubeMapArray {
+                    texture,
+                    layer,
+                    side,
+                } => {
+                    texture.bind_as_depth_target(*layer, *side);
+                }
             }
         } else {
             self.multisample_target
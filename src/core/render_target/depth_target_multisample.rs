@@ -44,6 +44,7 @@ impl<D: DepthTextureDataType> DepthTargetMultisample<D> {
             scissor_box,
             ClearState {
                 depth: clear_state.depth,
+                stencil: clear_state.stencil,
                 ..ClearState::none()
             },
         );
@@ -0,0 +1,170 @@
+use super::*;
+
+///
+/// A handle to pixel data that is being copied from a render target into a pixel buffer object on the GPU,
+/// see for example [RenderTarget::read_color_async] and [ColorTarget::read_async].
+///
+/// In contrast to the synchronous read methods, starting the copy does not stall the CPU until the GPU has finished rendering.
+/// Instead, poll [PixelReadback::is_ready] or [PixelReadback::try_read] in the following frames and use the data once it is available.
+/// This avoids frame hitches when for example taking screenshots or doing GPU picking.
+///
+pub struct PixelReadback<T: TextureDataType> {
+    context: Context,
+    buffer: crate::context::Buffer,
+    fence: Option<crate::context::Fence>,
+    width: u32,
+    height: u32,
+    flip_y: bool,
+    _data_type: std::marker::PhantomData<T>,
+}
+
+impl<T: TextureDataType> PixelReadback<T> {
+    pub(in crate::core) fn new(
+        context: &Context,
+        scissor_box: ScissorBox,
+        format: u32,
+        data_type: u32,
+        flip_y: bool,
+    ) -> Self {
+        let byte_size =
+            scissor_box.width as usize * scissor_box.height as usize * std::mem::size_of::<T>();
+        unsafe {
+            let buffer = context.create_buffer().expect("Failed creating buffer");
+            context.bind_buffer(crate::context::PIXEL_PACK_BUFFER, Some(buffer));
+            context.buffer_data_size(
+                crate::context::PIXEL_PACK_BUFFER,
+                byte_size as i32,
+                crate::context::STREAM_READ,
+            );
+            context.read_pixels(
+                scissor_box.x,
+                scissor_box.y,
+                scissor_box.width as i32,
+                scissor_box.height as i32,
+                format,
+                data_type,
+                crate::context::PixelPackData::BufferOffset(0),
+            );
+            context.bind_buffer(crate::context::PIXEL_PACK_BUFFER, None);
+            let fence = context
+                .fence_sync(crate::context::SYNC_GPU_COMMANDS_COMPLETE, 0)
+                .ok();
+            // Make sure the commands, including the fence, are sent to the GPU so the fence is eventually signaled.
+            context.flush();
+            Self {
+                context: context.clone(),
+                buffer,
+                fence,
+                width: scissor_box.width,
+                height: scissor_box.height,
+                flip_y,
+                _data_type: std::marker::PhantomData,
+            }
+        }
+    }
+
+    ///
+    /// The width of the area that is read, in texels.
+    ///
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    ///
+    /// The height of the area that is read, in texels.
+    ///
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    ///
+    /// Returns whether or not the GPU has finished copying the pixel data, in which case [PixelReadback::try_read] returns the data without stalling.
+    ///
+    pub fn is_ready(&self) -> bool {
+        if let Some(fence) = self.fence {
+            let status = unsafe { self.context.client_wait_sync(fence, 0, 0) };
+            status == crate::context::ALREADY_SIGNALED
+                || status == crate::context::CONDITION_SATISFIED
+        } else {
+            true
+        }
+    }
+
+    ///
+    /// Returns the pixel data if the GPU has finished copying it, otherwise `None` is returned without waiting for the GPU.
+    /// The data has the same layout as the data returned from the synchronous read methods.
+    ///
+    pub fn try_read(&self) -> Option<Vec<T>> {
+        if self.is_ready() {
+            Some(self.read_buffer())
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Waits for the GPU to finish copying the pixel data and returns it.
+    /// The data has the same layout as the data returned from the synchronous read methods.
+    ///
+    /// **Note:** This stalls the CPU until the GPU has finished, so prefer [PixelReadback::try_read] if possible.
+    ///
+    pub fn wait(self) -> Vec<T> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(fence) = self.fence {
+            loop {
+                let status = unsafe {
+                    self.context.client_wait_sync(
+                        fence,
+                        crate::context::SYNC_FLUSH_COMMANDS_BIT,
+                        1_000_000,
+                    )
+                };
+                if status != crate::context::TIMEOUT_EXPIRED {
+                    break;
+                }
+            }
+        }
+        self.read_buffer()
+    }
+
+    fn read_buffer(&self) -> Vec<T> {
+        let byte_size = self.width as usize * self.height as usize * std::mem::size_of::<T>();
+        let mut bytes = vec![0u8; byte_size];
+        unsafe {
+            self.context
+                .bind_buffer(crate::context::PIXEL_PACK_BUFFER, Some(self.buffer));
+            if self.context.version().is_embedded && !cfg!(target_arch = "wasm32") {
+                // Reading buffer data directly is not supported on OpenGL ES, so map the buffer instead.
+                let data = self.context.map_buffer_range(
+                    crate::context::PIXEL_PACK_BUFFER,
+                    0,
+                    byte_size as i32,
+                    crate::context::MAP_READ_BIT,
+                );
+                std::ptr::copy_nonoverlapping(data, bytes.as_mut_ptr(), byte_size);
+                self.context.unmap_buffer(crate::context::PIXEL_PACK_BUFFER);
+            } else {
+                self.context
+                    .get_buffer_sub_data(crate::context::PIXEL_PACK_BUFFER, 0, &mut bytes);
+            }
+            self.context
+                .bind_buffer(crate::context::PIXEL_PACK_BUFFER, None);
+        }
+        let mut pixels = from_byte_slice(&bytes).to_vec();
+        if self.flip_y {
+            flip_y(&mut pixels, self.width as usize, self.height as usize);
+        }
+        pixels
+    }
+}
+
+impl<T: TextureDataType> Drop for PixelReadback<T> {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(fence) = self.fence {
+                self.context.delete_sync(fence);
+            }
+            self.context.delete_buffer(self.buffer);
+        }
+    }
+}
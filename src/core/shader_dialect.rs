@@ -0,0 +1,360 @@
+use crate::core::*;
+
+///
+/// The type of a shader stage.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShaderType {
+    /// A vertex shader.
+    Vertex,
+    /// A fragment shader.
+    Fragment,
+}
+
+///
+/// The GLSL dialect supported by a graphics backend.
+/// Shaders are written once in GLSL 3.30 style without a version header and then translated to the dialect of the current backend
+/// using [ShaderDialect::translate], which is done automatically when creating a [Program].
+///
+/// The translation
+/// - replaces any `#version` directive with the version header of the dialect,
+/// - adds default precision qualifiers on OpenGL ES and WebGL,
+/// - enables extensions needed for features that are not part of the core specification of the dialect, for example cube map arrays,
+/// - converts legacy GLSL (`attribute`, `varying`, `gl_FragColor`, `texture2D`, `textureCube` etc.) to GLSL 3.30 style.
+///
+/// On OpenGL ES 2.0 and WebGL 1 contexts, see [Context::is_legacy], the shaders are instead downgraded to GLSL ES 1.00,
+/// which converts `in`/`out` declarations to `attribute`/`varying`, maps the first fragment output to `gl_FragColor`,
+/// turns any other fragment outputs into ordinary global variables which are not written anywhere and adds `texture` and `textureLod` functions for 2D and cube map samplers.
+/// Features without an equivalent in GLSL ES 1.00, for example integer bit operations, `textureSize` and texture arrays, cannot be translated.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShaderDialect {
+    /// GLSL ES 1.00, used on OpenGL ES 2.0 and WebGL 1.
+    GlslEs100,
+    /// GLSL 3.30 core, used on OpenGL 3.3 and above.
+    Glsl330,
+    /// GLSL 4.00 core, used on OpenGL 4.0 and above.
+    Glsl400,
+    /// GLSL ES 3.00, used on OpenGL ES 3.0 and 3.1 and WebGL 2.
+    GlslEs300,
+    /// GLSL ES 3.20, used on OpenGL ES 3.2 and above.
+    GlslEs320,
+}
+
+impl ShaderDialect {
+    ///
+    /// Returns the dialect supported by the given context.
+    ///
+    pub fn from_context(context: &Context) -> Self {
+        let version = context.version();
+        if context.is_legacy() {
+            Self::GlslEs100
+        } else if cfg!(target_arch = "wasm32") {
+            Self::GlslEs300
+        } else if version.is_embedded {
+            if version.major > 3 || (version.major == 3 && version.minor >= 2) {
+                Self::GlslEs320
+            } else {
+                Self::GlslEs300
+            }
+        } else if version.major >= 4 {
+            Self::Glsl400
+        } else {
+            Self::Glsl330
+        }
+    }
+
+    ///
+    /// Returns whether or not this is an OpenGL ES or WebGL dialect.
+    ///
+    pub fn is_embedded(&self) -> bool {
+        matches!(self, Self::GlslEs100 | Self::GlslEs300 | Self::GlslEs320)
+    }
+
+    ///
+    /// Returns the version directive of this dialect.
+    ///
+    pub fn version_directive(&self) -> &'static str {
+        match self {
+            Self::GlslEs100 => "#version 100\n",
+            Self::Glsl330 => "#version 330 core\n",
+            Self::Glsl400 => "#version 400 core\n",
+            Self::GlslEs300 => "#version 300 es\n",
+            Self::GlslEs320 => "#version 320 es\n",
+        }
+    }
+
+    ///
+    /// Translates the given shader source to this dialect, see [ShaderDialect] for a description of the translation.
+    ///
+    pub fn translate(&self, source: &str, shader_type: ShaderType) -> String {
+        let mut body = String::with_capacity(source.len());
+        let mut extensions = String::new();
+        for line in source.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("#version") {
+                continue;
+            }
+            if trimmed.starts_with("#extension") {
+                extensions.push_str(trimmed);
+                extensions.push('\n');
+                continue;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+
+        let mut body = self.translate_legacy(&body, shader_type);
+        if *self == Self::GlslEs100 {
+            body = downgrade_to_es100(&body, shader_type, &mut extensions);
+        }
+
+        let uses_cube_map_array = contains_identifier(&body, "samplerCubeArray")
+            || contains_identifier(&body, "samplerCubeArrayShadow");
+        if uses_cube_map_array {
+            match self {
+                Self::Glsl330 => {
+                    extensions.push_str("#extension GL_ARB_texture_cube_map_array : enable\n")
+                }
+                Self::GlslEs300 => {
+                    extensions.push_str("#extension GL_EXT_texture_cube_map_array : enable\n")
+                }
+                _ => {}
+            }
+        }
+
+        if self.is_embedded() {
+            let mut precision = "#ifdef GL_FRAGMENT_PRECISION_HIGH\n".to_string();
+            for qualifier in ["highp", "mediump"] {
+                precision.push_str(&format!(
+                    "precision {q} float;\nprecision {q} int;\n",
+                    q = qualifier
+                ));
+                if *self != Self::GlslEs100 {
+                    precision.push_str(&format!(
+                        "precision {q} sampler2DArray;\nprecision {q} sampler3D;\n",
+                        q = qualifier
+                    ));
+                }
+                if uses_cube_map_array {
+                    precision.push_str(&format!("precision {} samplerCubeArray;\n", qualifier));
+                }
+                if qualifier == "highp" {
+                    precision.push_str("#else\n");
+                }
+            }
+            precision.push_str("#endif\n");
+            body = precision + &body;
+        }
+        format!("{}{}{}", self.version_directive(), extensions, body)
+    }
+
+    ///
+    /// Adds the evaluation of the user clip planes (see [ClipPlanes]) to the given shader source by wrapping the `main` function.
+    /// If hardware clip distances are supported, the vertex shader writes `gl_ClipDistance`,
+    /// otherwise the vertex shader passes on the clip space position and the fragment shader discards the fragments on the negative side of the planes.
+    /// Since discarding fragments disables early depth testing, the fragment shader is only changed if it defines `USE_CLIP_PLANES`,
+    /// see [ClipPlanes::SHADER_DEFINE].
+    /// The source is returned unchanged for GLSL ES 1.00, which does not support clip planes.
+    ///
+    pub(in crate::core) fn add_clip_planes(
+        &self,
+        source: &str,
+        shader_type: ShaderType,
+        clip_distances: bool,
+    ) -> String {
+        if *self == Self::GlslEs100
+            || (shader_type == ShaderType::Fragment
+                && (clip_distances || !contains_identifier(source, ClipPlanes::SHADER_DEFINE)))
+            || !contains_identifier(source, "main")
+        {
+            return source.to_string();
+        }
+        let mut source = replace_identifier(source, "main", "user_clip_planes_main");
+        source.push_str(&format!(
+            "\nuniform vec4 userClipPlanes[{}];\nuniform int userClipPlaneCount;\n",
+            ClipPlanes::MAX
+        ));
+        match shader_type {
+            ShaderType::Vertex if clip_distances => {
+                if self.is_embedded() {
+                    source.push_str("#extension GL_EXT_clip_cull_distance : enable\n");
+                }
+                source.push_str("void main()\n{\n    user_clip_planes_main();\n");
+                for i in 0..ClipPlanes::MAX {
+                    source.push_str(&format!(
+                        "    gl_ClipDistance[{i}] = {i} < userClipPlaneCount ? dot(userClipPlanes[{i}], gl_Position) : 1.0;\n"
+                    ));
+                }
+                source.push_str("}\n");
+            }
+            ShaderType::Vertex => {
+                source.push_str(
+                    "out vec4 userClipPosition;\nvoid main()\n{\n    user_clip_planes_main();\n    userClipPosition = gl_Position;\n}\n",
+                );
+            }
+            ShaderType::Fragment => {
+                source.push_str(&format!(
+                    "in vec4 userClipPosition;\nvoid main()\n{{\n    for (int i = 0; i < {}; i++) {{\n        if (i < userClipPlaneCount && dot(userClipPlanes[i], userClipPosition) < 0.0) discard;\n    }}\n    user_clip_planes_main();\n}}\n",
+                    ClipPlanes::MAX
+                ));
+            }
+        }
+        source
+    }
+
+    fn translate_legacy(&self, source: &str, shader_type: ShaderType) -> String {
+        let mut source = replace_identifier(source, "texture2D", "texture");
+        for (from, to) in [
+            ("texture2DLod", "textureLod"),
+            ("texture2DProj", "textureProj"),
+            ("textureCube", "texture"),
+            ("textureCubeLod", "textureLod"),
+            ("texture3D", "texture"),
+        ] {
+            source = replace_identifier(&source, from, to);
+        }
+        match shader_type {
+            ShaderType::Vertex => {
+                source = replace_identifier(&source, "attribute", "in");
+                source = replace_identifier(&source, "varying", "out");
+            }
+            ShaderType::Fragment => {
+                source = replace_identifier(&source, "varying", "in");
+                if contains_identifier(&source, "gl_FragColor") {
+                    source = replace_identifier(&source, "gl_FragColor", "outFragColor");
+                    source = format!("layout (location = 0) out vec4 outFragColor;\n{}", source);
+                }
+            }
+        }
+        source
+    }
+}
+
+// Converts GLSL 3.30 style declarations to GLSL ES 1.00 and adds the texture lookup functions that are missing in GLSL ES 1.00.
+fn downgrade_to_es100(source: &str, shader_type: ShaderType, extensions: &mut String) -> String {
+    let mut body = String::with_capacity(source.len());
+    let mut has_fragment_color = false;
+    for line in source.lines() {
+        let indentation = &line[..line.len() - line.trim_start().len()];
+        let mut declaration = line.trim_start();
+        let mut location = None;
+        if declaration.starts_with("layout") {
+            if let Some(end) = declaration.find(')') {
+                location = declaration[..end]
+                    .split("location")
+                    .nth(1)
+                    .and_then(|l| l.trim_start_matches([' ', '=']).trim().parse::<u32>().ok());
+                declaration = declaration[end + 1..].trim_start();
+            }
+        }
+        for qualifier in ["flat ", "smooth "] {
+            declaration = declaration.strip_prefix(qualifier).unwrap_or(declaration);
+        }
+        let converted = match shader_type {
+            ShaderType::Vertex => declaration
+                .strip_prefix("in ")
+                .map(|d| format!("attribute {}", d))
+                .or_else(|| {
+                    declaration
+                        .strip_prefix("out ")
+                        .map(|d| format!("varying {}", d))
+                }),
+            ShaderType::Fragment => declaration
+                .strip_prefix("in ")
+                .map(|d| format!("varying {}", d))
+                .or_else(|| {
+                    declaration.strip_prefix("out ").map(|d| {
+                        let name = d
+                            .trim_end_matches(';')
+                            .split_whitespace()
+                            .last()
+                            .unwrap_or("")
+                            .to_string();
+                        if !has_fragment_color && location.unwrap_or(0) == 0 {
+                            has_fragment_color = true;
+                            format!("#define {} gl_FragColor", name)
+                        } else {
+                            d.to_string()
+                        }
+                    })
+                }),
+        };
+        match converted {
+            Some(converted) => {
+                body.push_str(indentation);
+                body.push_str(&converted);
+            }
+            None if location.is_some() => body.push_str(declaration),
+            None => body.push_str(line),
+        }
+        body.push('\n');
+    }
+
+    let mut functions = String::new();
+    if contains_identifier(&body, "texture") {
+        functions.push_str(
+            "vec4 texture(sampler2D s, vec2 uv) { return texture2D(s, uv); }\n\
+             vec4 texture(samplerCube s, vec3 dir) { return textureCube(s, dir); }\n",
+        );
+        if shader_type == ShaderType::Fragment {
+            functions.push_str(
+                "vec4 texture(sampler2D s, vec2 uv, float bias) { return texture2D(s, uv, bias); }\n\
+                 vec4 texture(samplerCube s, vec3 dir, float bias) { return textureCube(s, dir, bias); }\n",
+            );
+        }
+    }
+    if contains_identifier(&body, "textureLod") {
+        match shader_type {
+            ShaderType::Vertex => functions.push_str(
+                "vec4 textureLod(sampler2D s, vec2 uv, float lod) { return texture2DLod(s, uv, lod); }\n\
+                 vec4 textureLod(samplerCube s, vec3 dir, float lod) { return textureCubeLod(s, dir, lod); }\n",
+            ),
+            ShaderType::Fragment => {
+                extensions.push_str("#extension GL_EXT_shader_texture_lod : enable\n");
+                functions.push_str(
+                    "vec4 textureLod(sampler2D s, vec2 uv, float lod) { return texture2DLodEXT(s, uv, lod); }\n\
+                     vec4 textureLod(samplerCube s, vec3 dir, float lod) { return textureCubeLodEXT(s, dir, lod); }\n",
+                );
+            }
+        }
+    }
+    functions + &body
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn find_identifier(source: &str, identifier: &str, from: usize) -> Option<usize> {
+    let mut start = from;
+    while let Some(i) = source[start..].find(identifier) {
+        let begin = start + i;
+        let end = begin + identifier.len();
+        let before = source[..begin].chars().next_back();
+        let after = source[end..].chars().next();
+        if !before.map(is_identifier_char).unwrap_or(false)
+            && !after.map(is_identifier_char).unwrap_or(false)
+        {
+            return Some(begin);
+        }
+        start = end;
+    }
+    None
+}
+
+fn contains_identifier(source: &str, identifier: &str) -> bool {
+    find_identifier(source, identifier, 0).is_some()
+}
+
+fn replace_identifier(source: &str, identifier: &str, replacement: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+    while let Some(i) = find_identifier(source, identifier, last) {
+        result.push_str(&source[last..i]);
+        result.push_str(replacement);
+        last = i + identifier.len();
+    }
+    result.push_str(&source[last..]);
+    result
+}
@@ -14,9 +14,11 @@ vec3 world_pos_from_depth(mat4 viewProjectionInverse, float depth, vec2 uv) {
 }
 
 // http://holger.dammertz.org/stuff/notes_HammersleyOnHemisphere.html
+#if __VERSION__ > 100
 // efficient VanDerCorpus calculation.
-float RadicalInverse_VdC(uint bits) 
+float RadicalInverse_VdC(int i) 
 {
+     uint bits = uint(i);
      bits = (bits << 16u) | (bits >> 16u);
      bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
      bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
@@ -24,8 +26,24 @@ float RadicalInverse_VdC(uint bits)
      bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
      return float(bits) * 2.3283064365386963e-10; // / 0x100000000
 }
+#else
+// GLSL ES 1.00 has no unsigned integers and bit operations, so the bits are reversed one at a time.
+float RadicalInverse_VdC(int i)
+{
+    float n = float(i);
+    float result = 0.0;
+    float base = 0.5;
+    for (int k = 0; k < 16; k++) {
+        if (n < 1.0) break;
+        result += base * mod(n, 2.0);
+        n = floor(n * 0.5);
+        base *= 0.5;
+    }
+    return result;
+}
+#endif
 
-vec2 Hammersley(uint i, uint N)
+vec2 Hammersley(int i, int N)
 {
 	return vec2(float(i)/float(N), RadicalInverse_VdC(i));
 }
@@ -33,10 +33,26 @@ mod depth_texture_cube_map;
 #[doc(inline)]
 pub use depth_texture_cube_map::*;
 
+mod texture_cube_map_array;
+#[doc(inline)]
+pub use texture_cube_map_array::*;
+
+mod depth_texture_cube_map_array;
+#[doc(inline)]
+pub use depth_texture_cube_map_array::*;
+
 mod depth_texture2d_multisample;
 #[doc(inline)]
 pub(in crate::core) use depth_texture2d_multisample::*;
 
+mod compressed;
+#[doc(inline)]
+pub use compressed::*;
+
+mod format_fallback;
+#[doc(inline)]
+pub use format_fallback::*;
+
 use data_type::*;
 pub use three_d_asset::texture::{
     Interpolation, Texture2D as CpuTexture, Texture3D as CpuTexture3D, TextureData, Wrapping,
@@ -56,6 +72,7 @@ impl<T: TextureDataType + PrimitiveDataType> TextureDataType for [T; 3] {}
 impl<T: TextureDataType + PrimitiveDataType> TextureDataType for [T; 4] {}
 
 impl TextureDataType for Quat {}
+impl TextureDataType for r11g11b10f {}
 
 impl<T: TextureDataType + ?Sized> TextureDataType for &T {}
 
@@ -71,6 +88,59 @@ impl DepthTextureDataType for f16 {}
 impl DepthTextureDataType for f24 {}
 impl DepthTextureDataType for f32 {}
 
+///
+/// The basic data type used for each pixel in a depth texture which also contains a stencil value.
+/// Use one of these types as the [DepthTextureDataType] when creating a depth texture to be able to use [Stencil] in a render call.
+///
+pub trait DepthStencilFormat: DepthTextureDataType {}
+
+/// 24 bit float depth and 8 bit unsigned integer stencil which can be used as [DepthTextureDataType] and [DepthStencilFormat].
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct f24s8 {}
+
+/// 32 bit float depth and 8 bit unsigned integer stencil which can be used as [DepthTextureDataType] and [DepthStencilFormat].
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct f32s8 {}
+
+impl DepthTextureDataType for f24s8 {}
+impl DepthTextureDataType for f32s8 {}
+impl DepthStencilFormat for f24s8 {}
+impl DepthStencilFormat for f32s8 {}
+
+///
+/// Packed 32 bit unsigned float color with 11 bits for the red and green channels and 10 bits for the blue channel which can be used as [TextureDataType].
+/// It has the same size as `[u8; 4]` but can contain HDR colors, however, it cannot contain negative values and has no alpha channel.
+///
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct r11g11b10f(pub u32);
+
+///
+/// The format of the color textures used for intermediate results, for example when rendering in HDR and when post-processing, see [Context::set_color_precision].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColorPrecision {
+    /// 8 bit unsigned normalized integer per channel, ie. `[u8; 4]`. Uses the least memory and bandwidth, but cannot contain HDR colors, so tone mapping is applied before writing the colors.
+    Rgba8,
+    /// Packed 11 bit float for the red and green channels and 10 bit float for the blue channel, ie. [r11g11b10f].
+    /// Can contain HDR colors using the same memory and bandwidth as [ColorPrecision::Rgba8], but has no alpha channel and less precision than [ColorPrecision::Rgba16f].
+    Rg11b10f,
+    /// 16 bit float per channel, ie. `[f16; 4]`. Uses twice the memory and bandwidth of the other formats, but has the least banding.
+    #[default]
+    Rgba16f,
+}
+
+impl ColorPrecision {
+    ///
+    /// Returns whether or not colors outside the range `[0, 1]` can be stored in textures with this precision.
+    ///
+    pub fn is_hdr(&self) -> bool {
+        *self != Self::Rgba8
+    }
+}
+
 ///
 /// A reference to some type of texture containing colors.
 ///
@@ -89,6 +159,12 @@ pub enum ColorTexture<'a> {
         texture: &'a TextureCubeMap,
         sides: &'a [CubeMapSide],
     },
+    /// A cube map array texture, an index into the array and a set of [CubeMapSide]s indicating the sides to use.
+    CubeMapArray {
+        texture: &'a TextureCubeMapArray,
+        layer: u32,
+        sides: &'a [CubeMapSide],
+    },
 }
 
 impl ColorTexture<'_> {
@@ -100,6 +176,7 @@ impl ColorTexture<'_> {
             ColorTexture::Single(texture) => texture.width(),
             ColorTexture::Array { texture, .. } => texture.width(),
             ColorTexture::CubeMap { texture, .. } => texture.width(),
+            ColorTexture::CubeMapArray { texture, .. } => texture.width(),
         }
     }
 
@@ -111,6 +188,7 @@ impl ColorTexture<'_> {
             ColorTexture::Single(texture) => texture.height(),
             ColorTexture::Array { texture, .. } => texture.height(),
             ColorTexture::CubeMap { texture, .. } => texture.height(),
+            ColorTexture::CubeMapArray { texture, .. } => texture.height(),
         }
     }
 
@@ -138,7 +216,35 @@ impl ColorTexture<'_> {
                     return texture(colorMap, vec3(uv, colorLayers[index]));
                 }"
             .to_owned(),
-            Self::CubeMap { .. } => todo!(),
+            Self::CubeMap { .. } => format!(
+                "{}
+                uniform samplerCube colorMap;
+                uniform int colorSides[6];
+                vec4 sample_color(vec2 uv)
+                {{
+                    return texture(colorMap, cube_map_direction(uv, colorSides[0]));
+                }}
+                vec4 sample_layer(vec2 uv, int index)
+                {{
+                    return texture(colorMap, cube_map_direction(uv, colorSides[index]));
+                }}",
+                CUBE_MAP_DIRECTION_SOURCE
+            ),
+            Self::CubeMapArray { .. } => format!(
+                "{}
+                uniform samplerCubeArray colorMap;
+                uniform int colorLayer;
+                uniform int colorSides[6];
+                vec4 sample_color(vec2 uv)
+                {{
+                    return texture(colorMap, vec4(cube_map_direction(uv, colorSides[0]), colorLayer));
+                }}
+                vec4 sample_layer(vec2 uv, int index)
+                {{
+                    return texture(colorMap, vec4(cube_map_direction(uv, colorSides[index]), colorLayer));
+                }}",
+                CUBE_MAP_DIRECTION_SOURCE
+            ),
         }
     }
 
@@ -149,9 +255,8 @@ impl ColorTexture<'_> {
         match self {
             Self::Single { .. } => 1u16 << 3,
             Self::Array { .. } => 10u16 << 3,
-            Self::CubeMap { .. } => {
-                todo!()
-            }
+            Self::CubeMap { .. } => 3u16 << 3,
+            Self::CubeMapArray { .. } => 4u16 << 3,
         }
     }
 
@@ -170,7 +275,19 @@ impl ColorTexture<'_> {
                 program.use_uniform_array("colorLayers", &la);
                 program.use_texture_array("colorMap", texture);
             }
-            Self::CubeMap { .. } => todo!(),
+            Self::CubeMap { texture, sides } => {
+                program.use_uniform_array("colorSides", &side_indices(sides));
+                program.use_texture_cube("colorMap", texture);
+            }
+            Self::CubeMapArray {
+                texture,
+                layer,
+                sides,
+            } => {
+                program.use_uniform("colorLayer", *layer as i32);
+                program.use_uniform_array("colorSides", &side_indices(sides));
+                program.use_texture_cube_map_array("colorMap", texture);
+            }
         }
     }
 }
@@ -193,6 +310,12 @@ pub enum DepthTexture<'a> {
         texture: &'a DepthTextureCubeMap,
         side: CubeMapSide,
     },
+    /// A cube map array texture, an index into the array and a [CubeMapSide] indicating the side to use.
+    CubeMapArray {
+        texture: &'a DepthTextureCubeMapArray,
+        layer: u32,
+        side: CubeMapSide,
+    },
 }
 
 impl DepthTexture<'_> {
@@ -204,6 +327,7 @@ impl DepthTexture<'_> {
             DepthTexture::Single(texture) => texture.width(),
             DepthTexture::Array { texture, .. } => texture.width(),
             DepthTexture::CubeMap { texture, .. } => texture.width(),
+            DepthTexture::CubeMapArray { texture, .. } => texture.width(),
         }
     }
 
@@ -215,6 +339,7 @@ impl DepthTexture<'_> {
             DepthTexture::Single(texture) => texture.height(),
             DepthTexture::Array { texture, .. } => texture.height(),
             DepthTexture::CubeMap { texture, .. } => texture.height(),
+            DepthTexture::CubeMapArray { texture, .. } => texture.height(),
         }
     }
     ///
@@ -237,9 +362,27 @@ impl DepthTexture<'_> {
                     return texture(depthMap, vec3(uv, depthLayer)).x;
                 }"
             .to_owned(),
-            Self::CubeMap { .. } => {
-                todo!()
-            }
+            Self::CubeMap { .. } => format!(
+                "{}
+                uniform samplerCube depthMap;
+                uniform int depthSide;
+                float sample_depth(vec2 uv)
+                {{
+                    return texture(depthMap, cube_map_direction(uv, depthSide)).x;
+                }}",
+                CUBE_MAP_DIRECTION_SOURCE
+            ),
+            Self::CubeMapArray { .. } => format!(
+                "{}
+                uniform samplerCubeArray depthMap;
+                uniform int depthLayer;
+                uniform int depthSide;
+                float sample_depth(vec2 uv)
+                {{
+                    return texture(depthMap, vec4(cube_map_direction(uv, depthSide), depthLayer)).x;
+                }}",
+                CUBE_MAP_DIRECTION_SOURCE
+            ),
         }
     }
 
@@ -250,9 +393,8 @@ impl DepthTexture<'_> {
         match self {
             Self::Single { .. } => 1u16,
             Self::Array { .. } => 10u16,
-            Self::CubeMap { .. } => {
-                todo!()
-            }
+            Self::CubeMap { .. } => 3u16,
+            Self::CubeMapArray { .. } => 4u16,
         }
     }
 
@@ -266,11 +408,46 @@ impl DepthTexture<'_> {
                 program.use_uniform("depthLayer", layer);
                 program.use_depth_texture_array("depthMap", texture);
             }
-            Self::CubeMap { .. } => todo!(),
+            Self::CubeMap { texture, side } => {
+                program.use_uniform("depthSide", side.index() as i32);
+                program.use_depth_texture_cube("depthMap", texture);
+            }
+            Self::CubeMapArray {
+                texture,
+                layer,
+                side,
+            } => {
+                program.use_uniform("depthLayer", *layer as i32);
+                program.use_uniform("depthSide", side.index() as i32);
+                program.use_depth_texture_cube_map_array("depthMap", texture);
+            }
         }
     }
 }
 
+// Returns the direction for looking up the texel at the given uv coordinates on the cube map side with the given index,
+// where the sides are in the OpenGL order, ie. positive x, negative x, positive y, negative y, positive z and negative z.
+const CUBE_MAP_DIRECTION_SOURCE: &str = "
+    vec3 cube_map_direction(vec2 uv, int side)
+    {
+        vec2 st = 2.0 * uv - 1.0;
+        if (side == 0) return vec3(1.0, -st.y, -st.x);
+        if (side == 1) return vec3(-1.0, -st.y, st.x);
+        if (side == 2) return vec3(st.x, 1.0, st.y);
+        if (side == 3) return vec3(st.x, -1.0, -st.y);
+        if (side == 4) return vec3(st.x, -st.y, 1.0);
+        return vec3(-st.x, -st.y, -1.0);
+    }";
+
+fn side_indices(sides: &[CubeMapSide]) -> [i32; 6] {
+    let mut indices = [0; 6];
+    sides
+        .iter()
+        .enumerate()
+        .for_each(|(i, side)| indices[i] = side.index() as i32);
+    indices
+}
+
 use crate::core::*;
 
 // COMMON TEXTURE FUNCTIONS
@@ -350,14 +527,16 @@ fn set_parameters(
 }
 
 fn calculate_number_of_mip_maps<T: TextureDataType>(
+    context: &Context,
     mip_map_filter: Option<Interpolation>,
     width: u32,
     height: u32,
     depth: Option<u32>,
 ) -> u32 {
-    // Cannot generate mip maps for RGB16F or RGB32F textures on web (https://registry.khronos.org/webgl/extensions/EXT_color_buffer_float/)
+    // Cannot generate mip maps for RGB16F or RGB32F textures on web and OpenGL ES (https://registry.khronos.org/webgl/extensions/EXT_color_buffer_float/)
     if (T::data_type() == crate::context::FLOAT || T::data_type() == crate::context::HALF_FLOAT)
         && T::size() == 3
+        && !context.capabilities().rgb_float_mip_maps
     {
         return 1;
     }
@@ -404,6 +583,60 @@ fn check_data_length<T: TextureDataType>(
     }
 }
 
+fn check_3d_texture_support(context: &Context) {
+    if !context.supports_3d_textures() {
+        panic!("3D textures and texture arrays are not supported by the graphics backend")
+    }
+}
+
+// The 16 bit float data type has another value on legacy contexts, where it is given by the OES_texture_half_float extension
+const HALF_FLOAT_OES: u32 = 0x8D61;
+
+pub(in crate::core) fn data_type_for_context(context: &Context, data_type: u32) -> u32 {
+    if context.is_legacy() && data_type == crate::context::HALF_FLOAT {
+        HALF_FLOAT_OES
+    } else {
+        data_type
+    }
+}
+
+// Allocates a depth texture image on a legacy context, which does not support immutable texture storage and sized depth formats.
+// The depth is stored as normalized integers, since floating point depth formats are not available.
+unsafe fn allocate_legacy_depth_image<T: DepthTextureDataType>(
+    context: &Context,
+    target: u32,
+    width: u32,
+    height: u32,
+) {
+    let (format, data_type) = if T::attachment() == crate::context::DEPTH_STENCIL_ATTACHMENT {
+        (
+            crate::context::DEPTH_STENCIL,
+            crate::context::UNSIGNED_INT_24_8,
+        )
+    } else if T::internal_format() == crate::context::DEPTH_COMPONENT16 {
+        (
+            crate::context::DEPTH_COMPONENT,
+            crate::context::UNSIGNED_SHORT,
+        )
+    } else {
+        (
+            crate::context::DEPTH_COMPONENT,
+            crate::context::UNSIGNED_INT,
+        )
+    };
+    context.tex_image_2d(
+        target,
+        0,
+        format as i32,
+        width as i32,
+        height as i32,
+        0,
+        format,
+        data_type,
+        None,
+    );
+}
+
 fn ru8_data(t: &CpuTexture) -> &[u8] {
     if let TextureData::RU8(data) = &t.data {
         data
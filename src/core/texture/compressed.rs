@@ -0,0 +1,127 @@
+use crate::core::*;
+
+///
+/// A block compressed texture format, where each block of 4x4 pixels is stored in a fixed number of bytes and decompressed by the GPU when sampling.
+/// Whether or not a format is available depends on the graphics backend, see [Context::supports_compressed_format].
+///
+/// **Note:** The colors are sampled as they are stored, also when the data is given in an sRGB format,
+/// since the materials convert the colors to linear color space in the shader, just as for uncompressed textures.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompressedFormat {
+    /// BC1 (DXT1) with RGB colors.
+    Bc1Rgb,
+    /// BC1 (DXT1) with RGB colors and one bit alpha.
+    Bc1Rgba,
+    /// BC2 (DXT3) with RGB colors and explicit four bit alpha.
+    Bc2Rgba,
+    /// BC3 (DXT5) with RGB colors and interpolated alpha.
+    Bc3Rgba,
+    /// BC4 with one channel.
+    Bc4R,
+    /// BC5 with two channels, for example used for normal maps.
+    Bc5Rg,
+    /// BC6H with unsigned half float RGB colors.
+    Bc6hRgbUfloat,
+    /// BC7 with high quality RGBA colors.
+    Bc7Rgba,
+    /// ETC2 with RGB colors, which is also able to decode ETC1.
+    Etc2Rgb,
+    /// ETC2 with RGB colors and EAC alpha.
+    Etc2Rgba,
+    /// EAC with one channel.
+    EacR11,
+    /// EAC with two channels.
+    EacRg11,
+    /// ASTC with RGBA colors and blocks of 4x4 pixels.
+    Astc4x4Rgba,
+}
+
+impl CompressedFormat {
+    ///
+    /// Returns the width and height in pixels of each block.
+    ///
+    pub fn block_size(&self) -> (u32, u32) {
+        (4, 4)
+    }
+
+    ///
+    /// Returns the number of bytes in each block.
+    ///
+    pub fn bytes_per_block(&self) -> usize {
+        match self {
+            Self::Bc1Rgb | Self::Bc1Rgba | Self::Bc4R | Self::Etc2Rgb | Self::EacR11 => 8,
+            _ => 16,
+        }
+    }
+
+    ///
+    /// Returns the number of bytes of an image with the given size in pixels in this format.
+    ///
+    pub fn byte_size(&self, width: u32, height: u32) -> usize {
+        let (block_width, block_height) = self.block_size();
+        let blocks_x = ((width + block_width - 1) / block_width).max(1) as usize;
+        let blocks_y = ((height + block_height - 1) / block_height).max(1) as usize;
+        blocks_x * blocks_y * self.bytes_per_block()
+    }
+
+    pub(in crate::core) fn internal_format(&self) -> u32 {
+        match self {
+            Self::Bc1Rgb => 0x83F0,
+            Self::Bc1Rgba => 0x83F1,
+            Self::Bc2Rgba => 0x83F2,
+            Self::Bc3Rgba => 0x83F3,
+            Self::Bc4R => 0x8DBB,
+            Self::Bc5Rg => 0x8DBD,
+            Self::Bc6hRgbUfloat => 0x8E8F,
+            Self::Bc7Rgba => 0x8E8C,
+            Self::Etc2Rgb => 0x9274,
+            Self::Etc2Rgba => 0x9278,
+            Self::EacR11 => 0x9270,
+            Self::EacRg11 => 0x9272,
+            Self::Astc4x4Rgba => 0x93B0,
+        }
+    }
+
+    pub(in crate::core) fn is_supported(&self, capabilities: &Capabilities) -> bool {
+        match self {
+            Self::Bc1Rgb | Self::Bc1Rgba | Self::Bc2Rgba | Self::Bc3Rgba => {
+                capabilities.s3tc_textures
+            }
+            Self::Bc4R | Self::Bc5Rg => capabilities.rgtc_textures,
+            Self::Bc6hRgbUfloat | Self::Bc7Rgba => capabilities.bptc_textures,
+            Self::Etc2Rgb | Self::Etc2Rgba | Self::EacR11 | Self::EacRg11 => {
+                capabilities.etc2_textures
+            }
+            Self::Astc4x4Rgba => capabilities.astc_textures,
+        }
+    }
+}
+
+///
+/// A block compressed 2D texture stored in CPU memory, which can be uploaded to the GPU using [Texture2D::new_compressed].
+///
+#[derive(Clone, Debug)]
+pub struct CpuCompressedTexture {
+    /// Name of this texture.
+    pub name: String,
+    /// The compressed format of the data.
+    pub format: CompressedFormat,
+    /// The width of the largest mip level in pixels.
+    pub width: u32,
+    /// The height of the largest mip level in pixels.
+    pub height: u32,
+    /// The compressed data of each mip level, starting with the largest. The size of each level is half the size of the previous level, rounded down and at least one pixel.
+    /// Mip maps cannot be generated from compressed data, so the texture is only mip mapped if the mip levels are given.
+    pub mip_levels: Vec<Vec<u8>>,
+    /// The way the pixel data is interpolated when the texture is far away.
+    pub min_filter: Interpolation,
+    /// The way the pixel data is interpolated when the texture is close.
+    pub mag_filter: Interpolation,
+    /// Specifies which type of interpolation to use between the mip levels, if more than one mip level is given.
+    pub mip_map_filter: Option<Interpolation>,
+    /// Determines how the texture is sampled outside the [0..1] s coordinate range (the first value of the uv coordinates).
+    pub wrap_s: Wrapping,
+    /// Determines how the texture is sampled outside the [0..1] t coordinate range (the second value of the uv coordinates).
+    pub wrap_t: Wrapping,
+}
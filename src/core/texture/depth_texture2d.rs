@@ -8,6 +8,7 @@ pub struct DepthTexture2D {
     id: crate::context::Texture,
     width: u32,
     height: u32,
+    attachment: u32,
 }
 
 impl DepthTexture2D {
@@ -27,6 +28,7 @@ impl DepthTexture2D {
             id,
             width,
             height,
+            attachment: T::attachment(),
         };
         texture.bind();
         set_parameters(
@@ -40,13 +42,22 @@ impl DepthTexture2D {
             None,
         );
         unsafe {
-            context.tex_storage_2d(
-                crate::context::TEXTURE_2D,
-                1,
-                T::internal_format(),
-                width as i32,
-                height as i32,
-            );
+            if context.is_legacy() {
+                allocate_legacy_depth_image::<T>(
+                    context,
+                    crate::context::TEXTURE_2D,
+                    width,
+                    height,
+                );
+            } else {
+                context.tex_storage_2d(
+                    crate::context::TEXTURE_2D,
+                    1,
+                    T::internal_format(),
+                    width as i32,
+                    height as i32,
+                );
+            }
         }
         texture
     }
@@ -73,7 +84,7 @@ impl DepthTexture2D {
         unsafe {
             self.context.framebuffer_texture_2d(
                 crate::context::FRAMEBUFFER,
-                crate::context::DEPTH_ATTACHMENT,
+                self.attachment,
                 crate::context::TEXTURE_2D,
                 Some(self.id),
                 0,
@@ -81,6 +92,17 @@ impl DepthTexture2D {
         }
     }
 
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "DepthTexture2D",
+            self.id,
+            self.width,
+            self.height,
+            1,
+            FrameResource::depth_format(self.attachment),
+        )
+    }
+
     pub(in crate::core) fn bind(&self) {
         unsafe {
             self.context
@@ -9,12 +9,16 @@ pub struct DepthTexture2DArray {
     width: u32,
     height: u32,
     depth: u32,
+    attachment: u32,
 }
 
 impl DepthTexture2DArray {
     ///
     /// Creates a new array of depth textures.
     ///
+    /// # Panic
+    /// Will panic if texture arrays are not supported by the context, see [Context::supports_3d_textures].
+    ///
     pub fn new<T: DepthTextureDataType>(
         context: &Context,
         width: u32,
@@ -23,6 +27,7 @@ impl DepthTexture2DArray {
         wrap_s: Wrapping,
         wrap_t: Wrapping,
     ) -> Self {
+        check_3d_texture_support(context);
         let id = generate(context);
         let texture = Self {
             context: context.clone(),
@@ -30,6 +35,7 @@ impl DepthTexture2DArray {
             width,
             height,
             depth,
+            attachment: T::attachment(),
         };
         texture.bind();
         set_parameters(
@@ -82,7 +88,7 @@ impl DepthTexture2DArray {
         unsafe {
             self.context.framebuffer_texture_layer(
                 crate::context::DRAW_FRAMEBUFFER,
-                crate::context::DEPTH_ATTACHMENT,
+                self.attachment,
                 Some(self.id),
                 0,
                 layer as i32,
@@ -90,6 +96,17 @@ impl DepthTexture2DArray {
         }
     }
 
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "DepthTexture2DArray",
+            self.id,
+            self.width,
+            self.height,
+            self.depth,
+            FrameResource::depth_format(self.attachment),
+        )
+    }
+
     pub(in crate::core) fn bind(&self) {
         unsafe {
             self.context
@@ -8,6 +8,7 @@ pub struct DepthTextureCubeMap {
     id: crate::context::Texture,
     width: u32,
     height: u32,
+    attachment: u32,
 }
 
 impl DepthTextureCubeMap {
@@ -28,6 +29,7 @@ impl DepthTextureCubeMap {
             id,
             width,
             height,
+            attachment: T::attachment(),
         };
         texture.bind();
         set_parameters(
@@ -41,13 +43,19 @@ impl DepthTextureCubeMap {
             Some(wrap_r),
         );
         unsafe {
-            context.tex_storage_2d(
-                crate::context::TEXTURE_CUBE_MAP,
-                1,
-                T::internal_format(),
-                width as i32,
-                height as i32,
-            );
+            if context.is_legacy() {
+                for side in CubeMapSide::iter() {
+                    allocate_legacy_depth_image::<T>(context, side.to_const(), width, height);
+                }
+            } else {
+                context.tex_storage_2d(
+                    crate::context::TEXTURE_CUBE_MAP,
+                    1,
+                    T::internal_format(),
+                    width as i32,
+                    height as i32,
+                );
+            }
         }
         texture
     }
@@ -74,7 +82,7 @@ impl DepthTextureCubeMap {
         unsafe {
             self.context.framebuffer_texture_2d(
                 crate::context::DRAW_FRAMEBUFFER,
-                crate::context::DEPTH_ATTACHMENT,
+                self.attachment,
                 side.to_const(),
                 Some(self.id),
                 0,
@@ -82,6 +90,17 @@ impl DepthTextureCubeMap {
         }
     }
 
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "DepthTextureCubeMap",
+            self.id,
+            self.width,
+            self.height,
+            6,
+            FrameResource::depth_format(self.attachment),
+        )
+    }
+
     pub(in crate::core) fn bind(&self) {
         unsafe {
             self.context
@@ -0,0 +1,128 @@
+use crate::core::texture::*;
+
+///
+/// An array of depth cube map textures that can be rendered into and read from. See also [RenderTarget] and [DepthTarget].
+/// Useful for storing the shadow maps of many point lights in a single resource.
+///
+/// **Note:** Requires OpenGL 4.0 or OpenGL ES 3.2 and is therefore not supported on web.
+///
+pub struct DepthTextureCubeMapArray {
+    context: Context,
+    id: crate::context::Texture,
+    width: u32,
+    height: u32,
+    depth: u32,
+    attachment: u32,
+}
+
+impl DepthTextureCubeMapArray {
+    ///
+    /// Creates a new array of depth cube map textures with `depth` number of cube maps.
+    ///
+    /// # Panic
+    /// Will panic if cube map array textures are not supported by the graphics backend.
+    ///
+    pub fn new<T: DepthTextureDataType>(
+        context: &Context,
+        width: u32,
+        height: u32,
+        depth: u32,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+        wrap_r: Wrapping,
+    ) -> Self {
+        check_cube_map_array_support(context);
+        let id = generate(context);
+        let texture = Self {
+            context: context.clone(),
+            id,
+            width,
+            height,
+            depth,
+            attachment: T::attachment(),
+        };
+        texture.bind();
+        set_parameters(
+            context,
+            crate::context::TEXTURE_CUBE_MAP_ARRAY,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            wrap_s,
+            wrap_t,
+            Some(wrap_r),
+        );
+        unsafe {
+            context.tex_storage_3d(
+                crate::context::TEXTURE_CUBE_MAP_ARRAY,
+                1,
+                T::internal_format(),
+                width as i32,
+                height as i32,
+                6 * depth as i32,
+            );
+        }
+        texture
+    }
+
+    ///
+    /// Returns a [DepthTarget] which can be used to clear, write to and read from the given side of the cube map at the given layer of this texture.
+    /// Combine this together with a [ColorTarget] with [RenderTarget::new] to be able to write to both a depth and color target at the same time.
+    ///
+    pub fn as_depth_target(&mut self, layer: u32, side: CubeMapSide) -> DepthTarget<'_> {
+        DepthTarget::new_texture_cube_map_array(&self.context, self, layer, side)
+    }
+
+    /// The width of this texture.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of this texture.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of cube maps in the array.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub(in crate::core) fn bind_as_depth_target(&self, layer: u32, side: CubeMapSide) {
+        unsafe {
+            self.context.framebuffer_texture_layer(
+                crate::context::DRAW_FRAMEBUFFER,
+                self.attachment,
+                Some(self.id),
+                0,
+                (6 * layer + side.index()) as i32,
+            );
+        }
+    }
+
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "DepthTextureCubeMapArray",
+            self.id,
+            self.width,
+            self.height,
+            6 * self.depth,
+            FrameResource::depth_format(self.attachment),
+        )
+    }
+
+    pub(in crate::core) fn bind(&self) {
+        unsafe {
+            self.context
+                .bind_texture(crate::context::TEXTURE_CUBE_MAP_ARRAY, Some(self.id));
+        }
+    }
+}
+
+impl Drop for DepthTextureCubeMapArray {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.delete_texture(self.id);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use crate::core::*;
+
+///
+/// The number of channels and the data type of the pixels of a texture, which corresponds to the variants of [TextureData].
+///
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    RU8,
+    RgU8,
+    RgbU8,
+    RgbaU8,
+    RF16,
+    RgF16,
+    RgbF16,
+    RgbaF16,
+    RF32,
+    RgF32,
+    RgbF32,
+    RgbaF32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Precision {
+    U8,
+    F16,
+    F32,
+}
+
+impl TextureFormat {
+    ///
+    /// Returns the format of the given texture data.
+    ///
+    pub fn from_data(data: &TextureData) -> Self {
+        match data {
+            TextureData::RU8(_) => Self::RU8,
+            TextureData::RgU8(_) => Self::RgU8,
+            TextureData::RgbU8(_) => Self::RgbU8,
+            TextureData::RgbaU8(_) => Self::RgbaU8,
+            TextureData::RF16(_) => Self::RF16,
+            TextureData::RgF16(_) => Self::RgF16,
+            TextureData::RgbF16(_) => Self::RgbF16,
+            TextureData::RgbaF16(_) => Self::RgbaF16,
+            TextureData::RF32(_) => Self::RF32,
+            TextureData::RgF32(_) => Self::RgF32,
+            TextureData::RgbF32(_) => Self::RgbF32,
+            TextureData::RgbaF32(_) => Self::RgbaF32,
+        }
+    }
+
+    ///
+    /// Returns the number of channels of each pixel.
+    ///
+    pub fn channel_count(&self) -> u32 {
+        match self {
+            Self::RU8 | Self::RF16 | Self::RF32 => 1,
+            Self::RgU8 | Self::RgF16 | Self::RgF32 => 2,
+            Self::RgbU8 | Self::RgbF16 | Self::RgbF32 => 3,
+            Self::RgbaU8 | Self::RgbaF16 | Self::RgbaF32 => 4,
+        }
+    }
+
+    fn precision(&self) -> Precision {
+        match self {
+            Self::RU8 | Self::RgU8 | Self::RgbU8 | Self::RgbaU8 => Precision::U8,
+            Self::RF16 | Self::RgF16 | Self::RgbF16 | Self::RgbaF16 => Precision::F16,
+            _ => Precision::F32,
+        }
+    }
+
+    fn with(channel_count: u32, precision: Precision) -> Self {
+        match (precision, channel_count) {
+            (Precision::U8, 1) => Self::RU8,
+            (Precision::U8, 2) => Self::RgU8,
+            (Precision::U8, 3) => Self::RgbU8,
+            (Precision::U8, _) => Self::RgbaU8,
+            (Precision::F16, 1) => Self::RF16,
+            (Precision::F16, 2) => Self::RgF16,
+            (Precision::F16, 3) => Self::RgbF16,
+            (Precision::F16, _) => Self::RgbaF16,
+            (Precision::F32, 1) => Self::RF32,
+            (Precision::F32, 2) => Self::RgF32,
+            (Precision::F32, 3) => Self::RgbF32,
+            (Precision::F32, _) => Self::RgbaF32,
+        }
+    }
+}
+
+///
+/// The reason why a texture is created with another format than the format of the data, see [FormatFallback].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FallbackReason {
+    /// 16 bit float textures are not supported, see [Capabilities::half_float_textures].
+    HalfFloatTextures,
+    /// 32 bit float textures are not supported, see [Capabilities::float_textures].
+    FloatTextures,
+    /// 32 bit float textures cannot be sampled with linear interpolation, see [Capabilities::float_linear_filtering].
+    FloatLinearFiltering,
+    /// Textures with one or two channels are not supported, see [Capabilities::rg_textures].
+    RgTextures,
+    /// Mip maps cannot be generated for float textures with three channels, see [Capabilities::rgb_float_mip_maps], so an alpha channel is added.
+    RgbFloatMipMaps,
+}
+
+impl std::fmt::Display for FallbackReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Self::HalfFloatTextures => "16 bit float textures are not supported",
+            Self::FloatTextures => "32 bit float textures are not supported",
+            Self::FloatLinearFiltering => {
+                "32 bit float textures cannot be sampled with linear interpolation"
+            }
+            Self::RgTextures => "textures with one or two channels are not supported",
+            Self::RgbFloatMipMaps => "mip maps cannot be generated for RGB float textures",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+///
+/// A report of a texture which is created with the closest supported format instead of the format of the data,
+/// see [Context::texture_format_fallback], [Context::take_format_fallbacks] and [Texture2D::format_fallback].
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatFallback {
+    /// The name of the texture.
+    pub name: String,
+    /// The format of the data.
+    pub requested: TextureFormat,
+    /// The format the texture is created with.
+    pub used: TextureFormat,
+    /// Whether or not the texture is sampled with nearest interpolation instead of linear interpolation.
+    pub nearest_filtering: bool,
+    /// The reasons for choosing another format.
+    pub reasons: Vec<FallbackReason>,
+}
+
+impl std::fmt::Display for FormatFallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "texture '{}' is created as {:?} instead of {:?}{} since {}",
+            self.name,
+            self.used,
+            self.requested,
+            if self.nearest_filtering {
+                " with nearest interpolation"
+            } else {
+                ""
+            },
+            self.reasons
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<_>>()
+                .join(" and ")
+        )
+    }
+}
+
+impl FormatFallback {
+    ///
+    /// Returns the closest format of the given texture which is supported with the given capabilities or `None` if the format is supported as it is.
+    ///
+    pub(in crate::core) fn negotiate(
+        capabilities: &Capabilities,
+        cpu_texture: &CpuTexture,
+    ) -> Option<Self> {
+        Self::negotiate_data(
+            capabilities,
+            &cpu_texture.name,
+            &cpu_texture.data,
+            cpu_texture.min_filter,
+            cpu_texture.mag_filter,
+            cpu_texture.mip_map_filter,
+        )
+    }
+
+    ///
+    /// Returns the closest format of the given 3D texture which is supported with the given capabilities or `None` if the format is supported as it is.
+    ///
+    pub(in crate::core) fn negotiate_3d(
+        capabilities: &Capabilities,
+        cpu_texture: &CpuTexture3D,
+    ) -> Option<Self> {
+        Self::negotiate_data(
+            capabilities,
+            &cpu_texture.name,
+            &cpu_texture.data,
+            cpu_texture.min_filter,
+            cpu_texture.mag_filter,
+            cpu_texture.mip_map_filter,
+        )
+    }
+
+    fn negotiate_data(
+        capabilities: &Capabilities,
+        name: &str,
+        data: &TextureData,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+    ) -> Option<Self> {
+        let requested = TextureFormat::from_data(data);
+        let mut precision = requested.precision();
+        let mut channel_count = requested.channel_count();
+        let mut nearest_filtering = false;
+        let mut reasons = Vec::new();
+
+        if precision == Precision::F16 && !capabilities.half_float_textures {
+            reasons.push(FallbackReason::HalfFloatTextures);
+            precision = if capabilities.float_textures {
+                Precision::F32
+            } else {
+                Precision::U8
+            };
+        }
+        if precision == Precision::F32 && !capabilities.float_textures {
+            reasons.push(FallbackReason::FloatTextures);
+            precision = if capabilities.half_float_textures {
+                Precision::F16
+            } else {
+                Precision::U8
+            };
+        }
+        let linear_filtering = min_filter == Interpolation::Linear
+            || mag_filter == Interpolation::Linear
+            || mip_map_filter == Some(Interpolation::Linear);
+        if precision == Precision::F32 && linear_filtering && !capabilities.float_linear_filtering {
+            reasons.push(FallbackReason::FloatLinearFiltering);
+            if capabilities.half_float_textures {
+                precision = Precision::F16;
+            } else {
+                nearest_filtering = true;
+            }
+        }
+        if channel_count < 3 && !capabilities.rg_textures {
+            reasons.push(FallbackReason::RgTextures);
+            channel_count = 3;
+        }
+        if channel_count == 3
+            && precision != Precision::U8
+            && mip_map_filter.is_some()
+            && !capabilities.rgb_float_mip_maps
+        {
+            reasons.push(FallbackReason::RgbFloatMipMaps);
+            channel_count = 4;
+        }
+
+        (!reasons.is_empty()).then(|| Self {
+            name: name.to_owned(),
+            requested,
+            used: TextureFormat::with(channel_count, precision),
+            nearest_filtering,
+            reasons,
+        })
+    }
+
+    ///
+    /// Converts the given texture to the format chosen by this fallback.
+    /// Missing color channels are set to zero and a missing alpha channel to one, while float values are clamped to the range [0, 1] when converted to 8 bit.
+    ///
+    pub(in crate::core) fn apply(&self, cpu_texture: &CpuTexture) -> CpuTexture {
+        let (min_filter, mag_filter, mip_map_filter) = self.filters(
+            cpu_texture.min_filter,
+            cpu_texture.mag_filter,
+            cpu_texture.mip_map_filter,
+        );
+        CpuTexture {
+            data: self.convert(&cpu_texture.data),
+            min_filter,
+            mag_filter,
+            mip_map_filter,
+            ..cpu_texture.clone()
+        }
+    }
+
+    ///
+    /// Converts the given 3D texture to the format chosen by this fallback, see [FormatFallback::apply].
+    ///
+    pub(in crate::core) fn apply_3d(&self, cpu_texture: &CpuTexture3D) -> CpuTexture3D {
+        let (min_filter, mag_filter, mip_map_filter) = self.filters(
+            cpu_texture.min_filter,
+            cpu_texture.mag_filter,
+            cpu_texture.mip_map_filter,
+        );
+        CpuTexture3D {
+            data: self.convert(&cpu_texture.data),
+            min_filter,
+            mag_filter,
+            mip_map_filter,
+            ..cpu_texture.clone()
+        }
+    }
+
+    fn convert(&self, data: &TextureData) -> TextureData {
+        let pixels = rgba_pixels(data);
+        let to_u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        match self.used {
+            TextureFormat::RU8 => TextureData::RU8(pixels.map(|p| to_u8(p[0])).collect()),
+            TextureFormat::RgU8 => {
+                TextureData::RgU8(pixels.map(|p| [to_u8(p[0]), to_u8(p[1])]).collect())
+            }
+            TextureFormat::RgbU8 => TextureData::RgbU8(
+                pixels
+                    .map(|p| [to_u8(p[0]), to_u8(p[1]), to_u8(p[2])])
+                    .collect(),
+            ),
+            TextureFormat::RgbaU8 => TextureData::RgbaU8(pixels.map(|p| p.map(to_u8)).collect()),
+            TextureFormat::RF16 => TextureData::RF16(pixels.map(|p| f16::from_f32(p[0])).collect()),
+            TextureFormat::RgF16 => TextureData::RgF16(
+                pixels
+                    .map(|p| [f16::from_f32(p[0]), f16::from_f32(p[1])])
+                    .collect(),
+            ),
+            TextureFormat::RgbF16 => TextureData::RgbF16(
+                pixels
+                    .map(|p| [p[0], p[1], p[2]].map(f16::from_f32))
+                    .collect(),
+            ),
+            TextureFormat::RgbaF16 => {
+                TextureData::RgbaF16(pixels.map(|p| p.map(f16::from_f32)).collect())
+            }
+            TextureFormat::RF32 => TextureData::RF32(pixels.map(|p| p[0]).collect()),
+            TextureFormat::RgF32 => TextureData::RgF32(pixels.map(|p| [p[0], p[1]]).collect()),
+            TextureFormat::RgbF32 => {
+                TextureData::RgbF32(pixels.map(|p| [p[0], p[1], p[2]]).collect())
+            }
+            TextureFormat::RgbaF32 => TextureData::RgbaF32(pixels.collect()),
+        }
+    }
+
+    fn filters(
+        &self,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+    ) -> (Interpolation, Interpolation, Option<Interpolation>) {
+        if self.nearest_filtering {
+            (
+                Interpolation::Nearest,
+                Interpolation::Nearest,
+                mip_map_filter.map(|_| Interpolation::Nearest),
+            )
+        } else {
+            (min_filter, mag_filter, mip_map_filter)
+        }
+    }
+}
+
+// Returns the pixels as RGBA float values, where 8 bit values are normalized to the range [0, 1].
+fn rgba_pixels(data: &TextureData) -> Box<dyn Iterator<Item = [f32; 4]> + '_> {
+    let n = |v: u8| v as f32 / 255.0;
+    let h = |v: f16| v.to_f32();
+    match data {
+        TextureData::RU8(d) => Box::new(d.iter().map(move |v| [n(*v), 0.0, 0.0, 1.0])),
+        TextureData::RgU8(d) => Box::new(d.iter().map(move |v| [n(v[0]), n(v[1]), 0.0, 1.0])),
+        TextureData::RgbU8(d) => Box::new(d.iter().map(move |v| [n(v[0]), n(v[1]), n(v[2]), 1.0])),
+        TextureData::RgbaU8(d) => Box::new(d.iter().map(move |v| v.map(n))),
+        TextureData::RF16(d) => Box::new(d.iter().map(move |v| [h(*v), 0.0, 0.0, 1.0])),
+        TextureData::RgF16(d) => Box::new(d.iter().map(move |v| [h(v[0]), h(v[1]), 0.0, 1.0])),
+        TextureData::RgbF16(d) => Box::new(d.iter().map(move |v| [h(v[0]), h(v[1]), h(v[2]), 1.0])),
+        TextureData::RgbaF16(d) => Box::new(d.iter().map(move |v| v.map(h))),
+        TextureData::RF32(d) => Box::new(d.iter().map(|v| [*v, 0.0, 0.0, 1.0])),
+        TextureData::RgF32(d) => Box::new(d.iter().map(|v| [v[0], v[1], 0.0, 1.0])),
+        TextureData::RgbF32(d) => Box::new(d.iter().map(|v| [v[0], v[1], v[2], 1.0])),
+        TextureData::RgbaF32(d) => Box::new(d.iter().copied()),
+    }
+}
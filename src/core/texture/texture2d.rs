@@ -10,15 +10,35 @@ pub struct Texture2D {
     height: u32,
     number_of_mip_maps: u32,
     data_byte_size: usize,
+    data_type: u32,
+    channel_count: u32,
+    min_filter: Interpolation,
+    mag_filter: Interpolation,
+    mip_map_filter: Option<Interpolation>,
+    wrap_s: Wrapping,
+    wrap_t: Wrapping,
+    compressed_format: Option<CompressedFormat>,
+    format_fallback: Option<FormatFallback>,
 }
 
 impl Texture2D {
     ///
     /// Construcs a new texture with the given data.
     ///
-    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format, even if `mip_map_filter` is specified.
+    /// If the format of the data is not supported by the context, the texture is created with the closest supported format instead,
+    /// see [Context::texture_format_fallback] and [Texture2D::format_fallback].
     ///
     pub fn new(context: &Context, cpu_texture: &CpuTexture) -> Self {
+        if let Some(fallback) = context.texture_format_fallback(cpu_texture) {
+            let mut texture = Self::new_with_format(context, &fallback.apply(cpu_texture));
+            context.record_format_fallback(fallback.clone());
+            texture.format_fallback = Some(fallback);
+            return texture;
+        }
+        Self::new_with_format(context, cpu_texture)
+    }
+
+    fn new_with_format(context: &Context, cpu_texture: &CpuTexture) -> Self {
         match cpu_texture.data {
             TextureData::RU8(ref data) => Self::new_with_data(context, cpu_texture, data),
             TextureData::RgU8(ref data) => Self::new_with_data(context, cpu_texture, data),
@@ -59,7 +79,7 @@ impl Texture2D {
     /// The format is determined by the generic [TextureDataType] parameter
     /// (for example, if [u8; 4] is specified, the format is RGBA and the data type is byte).
     ///
-    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format, even if `mip_map_filter` is specified.
+    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format on web and OpenGL ES, even if `mip_map_filter` is specified, see [Capabilities::rgb_float_mip_maps].
     ///
     pub fn new_empty<T: TextureDataType>(
         context: &Context,
@@ -71,9 +91,21 @@ impl Texture2D {
         wrap_s: Wrapping,
         wrap_t: Wrapping,
     ) -> Self {
+        let (mip_map_filter, wrap_s, wrap_t) = if !context.supports_npot_mip_maps()
+            && (!width.is_power_of_two() || !height.is_power_of_two())
+        {
+            (None, Wrapping::ClampToEdge, Wrapping::ClampToEdge)
+        } else {
+            (mip_map_filter, wrap_s, wrap_t)
+        };
         let id = generate(context);
         let number_of_mip_maps =
-            calculate_number_of_mip_maps::<T>(mip_map_filter, width, height, None);
+            calculate_number_of_mip_maps::<T>(context, mip_map_filter, width, height, None);
+        let mip_map_filter = if number_of_mip_maps == 1 {
+            None
+        } else {
+            mip_map_filter
+        };
         let texture = Self {
             context: context.clone(),
             id,
@@ -81,6 +113,15 @@ impl Texture2D {
             height,
             number_of_mip_maps,
             data_byte_size: std::mem::size_of::<T>(),
+            data_type: T::data_type(),
+            channel_count: T::size(),
+            min_filter,
+            mag_filter,
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+            compressed_format: None,
+            format_fallback: None,
         };
         texture.bind();
         set_parameters(
@@ -88,28 +129,212 @@ impl Texture2D {
             crate::context::TEXTURE_2D,
             min_filter,
             mag_filter,
-            if number_of_mip_maps == 1 {
-                None
-            } else {
-                mip_map_filter
-            },
+            mip_map_filter,
             wrap_s,
             wrap_t,
             None,
         );
         unsafe {
-            context.tex_storage_2d(
-                crate::context::TEXTURE_2D,
-                number_of_mip_maps as i32,
-                T::internal_format(),
-                width as i32,
-                height as i32,
-            );
+            if context.is_legacy() {
+                // Immutable texture storage and sized internal formats are not available, the mip levels are allocated when generating mip maps
+                context.tex_image_2d(
+                    crate::context::TEXTURE_2D,
+                    0,
+                    format_from_data_type::<T>() as i32,
+                    width as i32,
+                    height as i32,
+                    0,
+                    format_from_data_type::<T>(),
+                    data_type_for_context(context, T::data_type()),
+                    None,
+                );
+            } else {
+                context.tex_storage_2d(
+                    crate::context::TEXTURE_2D,
+                    number_of_mip_maps as i32,
+                    T::internal_format(),
+                    width as i32,
+                    height as i32,
+                );
+            }
         }
         texture.generate_mip_maps();
         texture
     }
 
+    ///
+    /// Constructs a new texture from the given block compressed data including the given mip levels, which are used instead of generating mip maps.
+    /// The compressed data is uploaded as it is, so the rows are not flipped as when constructing a texture from a [CpuTexture].
+    /// A compressed texture cannot be filled with new data or be used as a [ColorTarget].
+    ///
+    /// # Panic
+    /// Will panic if the format is not supported by the context, see [Context::supports_compressed_format],
+    /// or if no mip levels are given or the length of the data of a mip level does not correspond to the size and format.
+    ///
+    pub fn new_compressed(context: &Context, cpu_texture: &CpuCompressedTexture) -> Self {
+        let format = cpu_texture.format;
+        assert!(
+            context.supports_compressed_format(format),
+            "the compressed texture format {:?} is not supported",
+            format
+        );
+        assert!(
+            !cpu_texture.mip_levels.is_empty(),
+            "a compressed texture must have at least one mip level"
+        );
+        let (width, height) = (cpu_texture.width, cpu_texture.height);
+        let mut number_of_mip_maps = cpu_texture.mip_levels.len() as u32;
+        let (mut mip_map_filter, mut wrap_s, mut wrap_t) = (
+            cpu_texture.mip_map_filter,
+            cpu_texture.wrap_s,
+            cpu_texture.wrap_t,
+        );
+        if !context.supports_npot_mip_maps()
+            && (!width.is_power_of_two() || !height.is_power_of_two())
+        {
+            number_of_mip_maps = 1;
+            mip_map_filter = None;
+            wrap_s = Wrapping::ClampToEdge;
+            wrap_t = Wrapping::ClampToEdge;
+        }
+        // Legacy contexts cannot limit the number of mip levels, so an incomplete mip chain cannot be sampled
+        if context.is_legacy() && number_of_mip_maps < 32 - width.max(height).leading_zeros() {
+            number_of_mip_maps = 1;
+        }
+        if number_of_mip_maps == 1 {
+            mip_map_filter = None;
+        }
+        let texture = Self {
+            context: context.clone(),
+            id: generate(context),
+            width,
+            height,
+            number_of_mip_maps,
+            data_byte_size: 4,
+            data_type: crate::context::UNSIGNED_BYTE,
+            channel_count: 4,
+            min_filter: cpu_texture.min_filter,
+            mag_filter: cpu_texture.mag_filter,
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+            compressed_format: Some(format),
+            format_fallback: None,
+        };
+        texture.bind();
+        set_parameters(
+            context,
+            crate::context::TEXTURE_2D,
+            cpu_texture.min_filter,
+            cpu_texture.mag_filter,
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+            None,
+        );
+        unsafe {
+            if !context.is_legacy() {
+                context.tex_storage_2d(
+                    crate::context::TEXTURE_2D,
+                    number_of_mip_maps as i32,
+                    format.internal_format(),
+                    width as i32,
+                    height as i32,
+                );
+            }
+            for (level, data) in cpu_texture
+                .mip_levels
+                .iter()
+                .take(number_of_mip_maps as usize)
+                .enumerate()
+            {
+                let level_width = (width >> level).max(1);
+                let level_height = (height >> level).max(1);
+                let expected_bytes = format.byte_size(level_width, level_height);
+                if data.len() != expected_bytes {
+                    panic!(
+                        "invalid size of compressed data of mip level {} (expected {} bytes but got {} bytes)",
+                        level,
+                        expected_bytes,
+                        data.len()
+                    );
+                }
+                if context.is_legacy() {
+                    context.compressed_tex_image_2d(
+                        crate::context::TEXTURE_2D,
+                        level as i32,
+                        format.internal_format() as i32,
+                        level_width as i32,
+                        level_height as i32,
+                        0,
+                        data.len() as i32,
+                        data,
+                    );
+                } else {
+                    context.compressed_tex_sub_image_2d(
+                        crate::context::TEXTURE_2D,
+                        level as i32,
+                        0,
+                        0,
+                        level_width as i32,
+                        level_height as i32,
+                        format.internal_format(),
+                        crate::context::CompressedPixelUnpackData::Slice(data),
+                    );
+                }
+            }
+        }
+        texture
+    }
+
+    ///
+    /// Constructs a new empty 2D color texture with the format given by the [ColorPrecision], see [Texture2D::new_empty].
+    ///
+    pub fn new_empty_with_precision(
+        context: &Context,
+        precision: ColorPrecision,
+        width: u32,
+        height: u32,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+    ) -> Self {
+        match precision {
+            ColorPrecision::Rgba8 => Self::new_empty::<[u8; 4]>(
+                context,
+                width,
+                height,
+                min_filter,
+                mag_filter,
+                mip_map_filter,
+                wrap_s,
+                wrap_t,
+            ),
+            ColorPrecision::Rg11b10f => Self::new_empty::<r11g11b10f>(
+                context,
+                width,
+                height,
+                min_filter,
+                mag_filter,
+                mip_map_filter,
+                wrap_s,
+                wrap_t,
+            ),
+            ColorPrecision::Rgba16f => Self::new_empty::<[f16; 4]>(
+                context,
+                width,
+                height,
+                min_filter,
+                mag_filter,
+                mip_map_filter,
+                wrap_s,
+                wrap_t,
+            ),
+        }
+    }
+
     ///
     /// Fills this texture with the given data.
     ///
@@ -118,6 +343,10 @@ impl Texture2D {
     /// It is therefore necessary to create a new texture if the texture size or format has changed.
     ///
     pub fn fill<T: TextureDataType>(&mut self, data: &[T]) {
+        assert!(
+            self.compressed_format.is_none(),
+            "a compressed texture cannot be filled with new data"
+        );
         check_data_length::<T>(self.width, self.height, 1, self.data_byte_size, data.len());
         self.bind();
         let mut data = data.to_owned();
@@ -131,7 +360,7 @@ impl Texture2D {
                 self.width as i32,
                 self.height as i32,
                 format_from_data_type::<T>(),
-                T::data_type(),
+                data_type_for_context(&self.context, T::data_type()),
                 crate::context::PixelUnpackData::Slice(to_byte_slice(&data)),
             );
         }
@@ -146,10 +375,125 @@ impl Texture2D {
     ///
     /// **Note:** [DepthTest] is disabled if not also writing to a depth texture.
     ///
+    /// # Panic
+    /// Will panic if this is a compressed texture, see [Texture2D::new_compressed].
+    ///
     pub fn as_color_target(&mut self, mip_level: Option<u32>) -> ColorTarget<'_> {
         ColorTarget::new_texture2d(&self.context, self, mip_level)
     }
 
+    ///
+    /// Copies the content of the given texture, which can belong to another [Context], into this texture.
+    /// This makes it possible to display the same render result in several windows, each with its own context, without rendering the scene in each of them.
+    /// The source texture must have the same size as this texture.
+    ///
+    /// If the two textures belong to the same low-level graphics context, the content is copied on the GPU and the closures are not called.
+    /// Otherwise, the content is read from the source texture into CPU memory and then written to this texture.
+    /// On native, where only one context is current at a time, the closures must make the context of the source texture and of this texture current respectively,
+    /// for example using `WindowedContext::make_current`. On web, the closures can be empty.
+    /// The generic parameter `T` specifies the format used when copying through CPU memory, which has the same restrictions as for [ColorTarget::read].
+    ///
+    /// # Panic
+    /// Will panic if the size of the source texture is different from the size of this texture.
+    ///
+    pub fn copy_from_other_context<T: TextureDataType>(
+        &mut self,
+        source: &Texture2D,
+        make_source_current: impl FnOnce(),
+        make_destination_current: impl FnOnce(),
+    ) {
+        assert!(
+            source.width == self.width && source.height == self.height,
+            "the size of the source texture ({}x{}) must be the same as the size of the destination texture ({}x{})",
+            source.width,
+            source.height,
+            self.width,
+            self.height
+        );
+        if self.context.is_same_gl_context(&source.context) && !self.context.is_legacy() {
+            ColorTarget::new_texture2d(&source.context, source, Some(0))
+                .as_render_target()
+                .blit_to(
+                    &ColorTarget::new_texture2d(&self.context, self, Some(0)).as_render_target(),
+                );
+            self.generate_mip_maps();
+        } else {
+            make_source_current();
+            let data = ColorTarget::new_texture2d(&source.context, source, Some(0)).read::<T>();
+            make_destination_current();
+            self.fill(&data);
+        }
+    }
+
+    ///
+    /// Reads the content of this texture back into a [CpuTexture] with the same size, number of channels and base type as this texture
+    /// and with the filtering and wrapping used by this texture,
+    /// so that the result can be saved or used to construct an identical texture with [Texture2D::new].
+    /// Only the 0 mip level is read, the mip maps are generated again when constructing a new texture.
+    ///
+    /// **Note:** On web, the data is read as RGBA and converted to the format of this texture.
+    ///
+    /// # Errors
+    /// Returns an error if this is a compressed texture, see [Texture2D::new_compressed], since it cannot be read back.
+    ///
+    pub fn read_to_cpu_texture(&self) -> Result<CpuTexture, CoreError> {
+        if let Some(format) = self.compressed_format {
+            return Err(CoreError::CompressedTextureRead(format));
+        }
+        Ok(self.read_mip_to_cpu_texture(None))
+    }
+
+    pub(in crate::core) fn read_mip_to_cpu_texture(&self, mip_level: Option<u32>) -> CpuTexture {
+        let target = ColorTarget::new_texture2d(&self.context, self, mip_level);
+        // Read as RGBA, which is supported on all platforms, and then remove the unused channels
+        let data = if self.data_type == crate::context::UNSIGNED_BYTE {
+            let data = target.read::<[u8; 4]>();
+            match self.channel_count {
+                1 => TextureData::RU8(data.into_iter().map(|c| c[0]).collect()),
+                2 => TextureData::RgU8(data.into_iter().map(|c| [c[0], c[1]]).collect()),
+                3 => TextureData::RgbU8(data.into_iter().map(|c| [c[0], c[1], c[2]]).collect()),
+                _ => TextureData::RgbaU8(data),
+            }
+        } else if self.data_type == crate::context::HALF_FLOAT {
+            // Legacy contexts can only read half float render targets as half floats
+            let data: Vec<[f16; 4]> = if self.context.is_legacy() {
+                target.read::<[f16; 4]>()
+            } else {
+                target
+                    .read::<[f32; 4]>()
+                    .into_iter()
+                    .map(|c| c.map(f16::from_f32))
+                    .collect()
+            };
+            let data = data.into_iter();
+            match self.channel_count {
+                1 => TextureData::RF16(data.map(|c| c[0]).collect()),
+                2 => TextureData::RgF16(data.map(|c| [c[0], c[1]]).collect()),
+                3 => TextureData::RgbF16(data.map(|c| [c[0], c[1], c[2]]).collect()),
+                _ => TextureData::RgbaF16(data.collect()),
+            }
+        } else {
+            let data = target.read::<[f32; 4]>();
+            match self.channel_count {
+                1 => TextureData::RF32(data.into_iter().map(|c| c[0]).collect()),
+                2 => TextureData::RgF32(data.into_iter().map(|c| [c[0], c[1]]).collect()),
+                3 => TextureData::RgbF32(data.into_iter().map(|c| [c[0], c[1], c[2]]).collect()),
+                _ => TextureData::RgbaF32(data),
+            }
+        };
+        CpuTexture {
+            data,
+            width: target.width(),
+            height: target.height(),
+            min_filter: self.min_filter,
+            mag_filter: self.mag_filter,
+            mip_map_filter: self.mip_map_filter,
+            wrap_s: self.wrap_s,
+            wrap_t: self.wrap_t,
+            ..Default::default()
+        }
+    }
+
     /// The width of this texture.
     pub fn width(&self) -> u32 {
         self.width
@@ -160,8 +504,43 @@ impl Texture2D {
         self.height
     }
 
+    /// How the format of this texture differs from the format of the data it was constructed from, or `None` if the format of the data is used, see [Texture2D::new].
+    pub fn format_fallback(&self) -> Option<&FormatFallback> {
+        self.format_fallback.as_ref()
+    }
+
+    /// The compressed format of this texture or `None` if it is not compressed, see [Texture2D::new_compressed].
+    pub fn compressed_format(&self) -> Option<CompressedFormat> {
+        self.compressed_format
+    }
+
+    pub(in crate::core) fn fill_rows(
+        &self,
+        y: u32,
+        row_count: u32,
+        format: u32,
+        data_type: u32,
+        pixels: crate::context::PixelUnpackData,
+    ) {
+        self.bind();
+        unsafe {
+            self.context.tex_sub_image_2d(
+                crate::context::TEXTURE_2D,
+                0,
+                0,
+                y as i32,
+                self.width as i32,
+                row_count as i32,
+                format,
+                data_type,
+                pixels,
+            );
+        }
+    }
+
     pub(crate) fn generate_mip_maps(&self) {
-        if self.number_of_mip_maps > 1 {
+        // Mip maps of compressed textures are given at construction and cannot be generated
+        if self.number_of_mip_maps > 1 && self.compressed_format.is_none() {
             self.bind();
             unsafe {
                 self.context.generate_mipmap(crate::context::TEXTURE_2D);
@@ -180,6 +559,18 @@ impl Texture2D {
             );
         }
     }
+
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "Texture2D",
+            self.id,
+            self.width,
+            self.height,
+            1,
+            FrameResource::color_format(self.data_byte_size),
+        )
+    }
+
     pub(in crate::core) fn bind(&self) {
         unsafe {
             self.context
@@ -14,6 +14,7 @@ pub struct Texture2DArray {
     depth: u32,
     number_of_mip_maps: u32,
     data_byte_size: usize,
+    format_fallback: Option<FormatFallback>,
 }
 
 impl Texture2DArray {
@@ -21,9 +22,49 @@ impl Texture2DArray {
     /// Creates a new texture array from the given [CpuTexture]s.
     /// All of the cpu textures must contain data with the same [TextureDataType] and the same width and height.
     ///
-    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format, even if `mip_map_filter` is specified.
+    /// If the format of the data is not supported by the context, the texture is created with the closest supported format instead,
+    /// see [Context::texture_format_fallback] and [Texture2DArray::format_fallback].
     ///
-    pub fn new(context: &Context, cpu_textures: &[&CpuTexture]) -> Self {
+    /// # Errors
+    ///
+    /// Returns an error if no textures are given or if the textures do not have the same width, height and [TextureData] format.
+    ///
+    pub fn new(context: &Context, cpu_textures: &[&CpuTexture]) -> Result<Self, CoreError> {
+        let first = cpu_textures.first().ok_or_else(|| {
+            CoreError::TextureArrayCreation("at least one texture is required".to_string())
+        })?;
+        for (layer, cpu_texture) in cpu_textures.iter().enumerate() {
+            if cpu_texture.width != first.width || cpu_texture.height != first.height {
+                return Err(CoreError::TextureArrayCreation(format!(
+                    "the size {}x{} of layer {} is different from the size {}x{} of layer 0",
+                    cpu_texture.width, cpu_texture.height, layer, first.width, first.height
+                )));
+            }
+            if std::mem::discriminant(&cpu_texture.data) != std::mem::discriminant(&first.data) {
+                return Err(CoreError::TextureArrayCreation(format!(
+                    "the format of layer {} is different from the format of layer 0",
+                    layer
+                )));
+            }
+        }
+        if let Some(fallback) = cpu_textures
+            .first()
+            .and_then(|t| context.texture_format_fallback(t))
+        {
+            let cpu_textures = cpu_textures
+                .iter()
+                .map(|t| fallback.apply(t))
+                .collect::<Vec<_>>();
+            let mut texture =
+                Self::new_with_format(context, &cpu_textures.iter().collect::<Vec<_>>());
+            context.record_format_fallback(fallback.clone());
+            texture.format_fallback = Some(fallback);
+            return Ok(texture);
+        }
+        Ok(Self::new_with_format(context, cpu_textures))
+    }
+
+    fn new_with_format(context: &Context, cpu_textures: &[&CpuTexture]) -> Self {
         let cpu_texture = cpu_textures
             .get(0)
             .expect("Expect at least one texture in a texture array");
@@ -147,7 +188,10 @@ impl Texture2DArray {
     ///
     /// Creates a new array of 2D textures.
     ///
-    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format, even if `mip_map_filter` is specified.
+    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format on web and OpenGL ES, even if `mip_map_filter` is specified, see [Capabilities::rgb_float_mip_maps].
+    ///
+    /// # Panic
+    /// Will panic if texture arrays are not supported by the context, see [Context::supports_3d_textures].
     ///
     pub fn new_empty<T: TextureDataType>(
         context: &Context,
@@ -160,9 +204,10 @@ impl Texture2DArray {
         wrap_s: Wrapping,
         wrap_t: Wrapping,
     ) -> Self {
+        check_3d_texture_support(context);
         let id = generate(context);
         let number_of_mip_maps =
-            calculate_number_of_mip_maps::<T>(mip_map_filter, width, height, None);
+            calculate_number_of_mip_maps::<T>(context, mip_map_filter, width, height, None);
         let texture = Self {
             context: context.clone(),
             id,
@@ -171,6 +216,7 @@ impl Texture2DArray {
             depth,
             number_of_mip_maps,
             data_byte_size: std::mem::size_of::<T>(),
+            format_fallback: None,
         };
         texture.bind();
         set_parameters(
@@ -281,6 +327,11 @@ impl Texture2DArray {
         self.depth
     }
 
+    /// How the format of this texture differs from the format of the data it was constructed from, or `None` if the format of the data is used, see [Texture2DArray::new].
+    pub fn format_fallback(&self) -> Option<&FormatFallback> {
+        self.format_fallback.as_ref()
+    }
+
     pub(in crate::core) fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
             self.bind();
@@ -303,6 +354,17 @@ impl Texture2DArray {
         }
     }
 
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "Texture2DArray",
+            self.id,
+            self.width,
+            self.height,
+            self.depth,
+            FrameResource::color_format(self.data_byte_size),
+        )
+    }
+
     pub(in crate::core) fn bind(&self) {
         unsafe {
             self.context
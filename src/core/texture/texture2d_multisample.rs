@@ -55,6 +55,17 @@ impl Texture2DMultisample {
         self.number_of_samples
     }
 
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "Texture2DMultisample",
+            self.id,
+            self.width,
+            self.height,
+            1,
+            format!("{} samples", self.number_of_samples),
+        )
+    }
+
     pub(in crate::core) fn bind_as_color_target(&self, channel: u32) {
         unsafe {
             self.context.framebuffer_renderbuffer(
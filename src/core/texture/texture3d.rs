@@ -10,15 +10,27 @@ pub struct Texture3D {
     depth: u32,
     number_of_mip_maps: u32,
     data_byte_size: usize,
+    format_fallback: Option<FormatFallback>,
 }
 
 impl Texture3D {
     ///
     /// Construcs a new 3D texture with the given data.
     ///
-    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format, even if `mip_map_filter` is specified.
+    /// If the format of the data is not supported by the context, the texture is created with the closest supported format instead,
+    /// see [Context::texture_format_fallback] and [Texture3D::format_fallback].
     ///
     pub fn new(context: &Context, cpu_texture: &CpuTexture3D) -> Self {
+        if let Some(fallback) = FormatFallback::negotiate_3d(context.capabilities(), cpu_texture) {
+            let mut texture = Self::new_with_format(context, &fallback.apply_3d(cpu_texture));
+            context.record_format_fallback(fallback.clone());
+            texture.format_fallback = Some(fallback);
+            return texture;
+        }
+        Self::new_with_format(context, cpu_texture)
+    }
+
+    fn new_with_format(context: &Context, cpu_texture: &CpuTexture3D) -> Self {
         match cpu_texture.data {
             TextureData::RU8(ref data) => Self::new_with_data(context, cpu_texture, data),
             TextureData::RgU8(ref data) => Self::new_with_data(context, cpu_texture, data),
@@ -59,7 +71,10 @@ impl Texture3D {
     ///
     /// Creates a new empty 3D color texture.
     ///
-    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format, even if `mip_map_filter` is specified.
+    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format on web and OpenGL ES, even if `mip_map_filter` is specified, see [Capabilities::rgb_float_mip_maps].
+    ///
+    /// # Panic
+    /// Will panic if 3D textures are not supported by the context, see [Context::supports_3d_textures].
     ///
     pub fn new_empty<T: TextureDataType>(
         context: &Context,
@@ -73,9 +88,10 @@ impl Texture3D {
         wrap_t: Wrapping,
         wrap_r: Wrapping,
     ) -> Self {
+        check_3d_texture_support(context);
         let id = generate(context);
         let number_of_mip_maps =
-            calculate_number_of_mip_maps::<T>(mip_map_filter, width, height, Some(depth));
+            calculate_number_of_mip_maps::<T>(context, mip_map_filter, width, height, Some(depth));
         let texture = Self {
             context: context.clone(),
             id,
@@ -84,6 +100,7 @@ impl Texture3D {
             depth,
             number_of_mip_maps,
             data_byte_size: std::mem::size_of::<T>(),
+            format_fallback: None,
         };
         texture.bind();
         set_parameters(
@@ -163,6 +180,11 @@ impl Texture3D {
         self.depth
     }
 
+    /// How the format of this texture differs from the format of the data it was constructed from, or `None` if the format of the data is used, see [Texture3D::new].
+    pub fn format_fallback(&self) -> Option<&FormatFallback> {
+        self.format_fallback.as_ref()
+    }
+
     fn generate_mip_maps(&self) {
         if self.number_of_mip_maps > 1 {
             self.bind();
@@ -171,6 +193,18 @@ impl Texture3D {
             }
         }
     }
+
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "Texture3D",
+            self.id,
+            self.width,
+            self.height,
+            self.depth,
+            FrameResource::color_format(self.data_byte_size),
+        )
+    }
+
     pub(in crate::core) fn bind(&self) {
         unsafe {
             self.context
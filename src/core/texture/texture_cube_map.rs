@@ -76,6 +76,10 @@ impl CubeMapSide {
         }
     }
 
+    pub(in crate::core) fn index(self) -> u32 {
+        self.to_const() - crate::context::TEXTURE_CUBE_MAP_POSITIVE_X
+    }
+
     /// The up direction that should be used when rendering into this cube map side.
     pub fn up(&self) -> Vec3 {
         match self {
@@ -111,6 +115,8 @@ pub struct TextureCubeMap {
     height: u32,
     number_of_mip_maps: u32,
     data_byte_size: usize,
+    compressed_format: Option<CompressedFormat>,
+    format_fallback: Option<FormatFallback>,
 }
 
 impl TextureCubeMap {
@@ -118,7 +124,8 @@ impl TextureCubeMap {
     /// Creates a new cube map texture from the given [CpuTexture]s.
     /// All of the cpu textures must contain data with the same [TextureDataType].
     ///
-    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format, even if `mip_map_filter` is specified.
+    /// If the format of the data is not supported by the context, the texture is created with the closest supported format instead,
+    /// see [Context::texture_format_fallback] and [TextureCubeMap::format_fallback].
     ///
     pub fn new(
         context: &Context,
@@ -128,6 +135,32 @@ impl TextureCubeMap {
         bottom: &CpuTexture,
         front: &CpuTexture,
         back: &CpuTexture,
+    ) -> Self {
+        if let Some(fallback) = context.texture_format_fallback(front) {
+            let mut texture = Self::new_with_format(
+                context,
+                &fallback.apply(right),
+                &fallback.apply(left),
+                &fallback.apply(top),
+                &fallback.apply(bottom),
+                &fallback.apply(front),
+                &fallback.apply(back),
+            );
+            context.record_format_fallback(fallback.clone());
+            texture.format_fallback = Some(fallback);
+            return texture;
+        }
+        Self::new_with_format(context, right, left, top, bottom, front, back)
+    }
+
+    fn new_with_format(
+        context: &Context,
+        right: &CpuTexture,
+        left: &CpuTexture,
+        top: &CpuTexture,
+        bottom: &CpuTexture,
+        front: &CpuTexture,
+        back: &CpuTexture,
     ) -> Self {
         match &front.data {
             TextureData::RU8(front_data) => Self::new_with_data(
@@ -301,7 +334,7 @@ impl TextureCubeMap {
     ///
     /// Creates a new texture cube map.
     ///
-    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format, even if `mip_map_filter` is specified.
+    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format on web and OpenGL ES, even if `mip_map_filter` is specified, see [Capabilities::rgb_float_mip_maps].
     ///
     pub fn new_empty<T: TextureDataType>(
         context: &Context,
@@ -316,7 +349,7 @@ impl TextureCubeMap {
     ) -> Self {
         let id = generate(context);
         let number_of_mip_maps =
-            calculate_number_of_mip_maps::<T>(mip_map_filter, width, height, None);
+            calculate_number_of_mip_maps::<T>(context, mip_map_filter, width, height, None);
         let texture = Self {
             context: context.clone(),
             id,
@@ -324,6 +357,8 @@ impl TextureCubeMap {
             height,
             number_of_mip_maps,
             data_byte_size: std::mem::size_of::<T>(),
+            compressed_format: None,
+            format_fallback: None,
         };
         texture.bind();
         set_parameters(
@@ -341,18 +376,173 @@ impl TextureCubeMap {
             Some(wrap_r),
         );
         unsafe {
-            context.tex_storage_2d(
-                crate::context::TEXTURE_CUBE_MAP,
-                number_of_mip_maps as i32,
-                T::internal_format(),
-                width as i32,
-                height as i32,
-            );
+            if context.is_legacy() {
+                // Immutable texture storage and sized internal formats are not available, the mip levels are allocated when generating mip maps
+                for side in CubeMapSide::iter() {
+                    context.tex_image_2d(
+                        side.to_const(),
+                        0,
+                        format_from_data_type::<T>() as i32,
+                        width as i32,
+                        height as i32,
+                        0,
+                        format_from_data_type::<T>(),
+                        data_type_for_context(context, T::data_type()),
+                        None,
+                    );
+                }
+            } else {
+                context.tex_storage_2d(
+                    crate::context::TEXTURE_CUBE_MAP,
+                    number_of_mip_maps as i32,
+                    T::internal_format(),
+                    width as i32,
+                    height as i32,
+                );
+            }
         }
         texture.generate_mip_maps();
         texture
     }
 
+    ///
+    /// Creates a new cube map texture from the given block compressed data of the 6 sides including the given mip levels,
+    /// which are used instead of generating mip maps. The sampling parameters are given by the right side.
+    /// A compressed cube map cannot be filled with new data or be used as a [ColorTarget].
+    ///
+    /// # Panic
+    /// Will panic if the format is not supported by the context, see [Context::supports_compressed_format],
+    /// if the sides do not have the same format, size and number of mip levels,
+    /// or if the length of the data of a mip level does not correspond to the size and format.
+    ///
+    pub fn new_compressed(
+        context: &Context,
+        right: &CpuCompressedTexture,
+        left: &CpuCompressedTexture,
+        top: &CpuCompressedTexture,
+        bottom: &CpuCompressedTexture,
+        front: &CpuCompressedTexture,
+        back: &CpuCompressedTexture,
+    ) -> Self {
+        let format = right.format;
+        let (width, height) = (right.width, right.height);
+        let mip_level_count = right.mip_levels.len() as u32;
+        assert!(
+            context.supports_compressed_format(format),
+            "the compressed texture format {:?} is not supported",
+            format
+        );
+        assert!(
+            mip_level_count > 0,
+            "a compressed texture must have at least one mip level"
+        );
+        let sides = [right, left, top, bottom, front, back];
+        for side in sides.iter() {
+            assert!(
+                side.format == format
+                    && side.width == width
+                    && side.height == height
+                    && side.mip_levels.len() as u32 == mip_level_count,
+                "all sides of a compressed cube map must have the same format, size and number of mip levels"
+            );
+        }
+        let mut number_of_mip_maps = mip_level_count;
+        let (mut mip_map_filter, mut wrap_s, mut wrap_t) =
+            (right.mip_map_filter, right.wrap_s, right.wrap_t);
+        if !context.supports_npot_mip_maps()
+            && (!width.is_power_of_two() || !height.is_power_of_two())
+        {
+            number_of_mip_maps = 1;
+            mip_map_filter = None;
+            wrap_s = Wrapping::ClampToEdge;
+            wrap_t = Wrapping::ClampToEdge;
+        }
+        // Legacy contexts cannot limit the number of mip levels, so an incomplete mip chain cannot be sampled
+        if context.is_legacy() && number_of_mip_maps < 32 - width.max(height).leading_zeros() {
+            number_of_mip_maps = 1;
+        }
+        if number_of_mip_maps == 1 {
+            mip_map_filter = None;
+        }
+        let texture = Self {
+            context: context.clone(),
+            id: generate(context),
+            width,
+            height,
+            number_of_mip_maps,
+            data_byte_size: 4,
+            compressed_format: Some(format),
+            format_fallback: None,
+        };
+        texture.bind();
+        set_parameters(
+            context,
+            crate::context::TEXTURE_CUBE_MAP,
+            right.min_filter,
+            right.mag_filter,
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+            Some(wrap_s),
+        );
+        unsafe {
+            if !context.is_legacy() {
+                context.tex_storage_2d(
+                    crate::context::TEXTURE_CUBE_MAP,
+                    number_of_mip_maps as i32,
+                    format.internal_format(),
+                    width as i32,
+                    height as i32,
+                );
+            }
+            for (i, side) in sides.iter().enumerate() {
+                let target = crate::context::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32;
+                for (level, data) in side
+                    .mip_levels
+                    .iter()
+                    .take(number_of_mip_maps as usize)
+                    .enumerate()
+                {
+                    let level_width = (width >> level).max(1);
+                    let level_height = (height >> level).max(1);
+                    let expected_bytes = format.byte_size(level_width, level_height);
+                    if data.len() != expected_bytes {
+                        panic!(
+                            "invalid size of compressed data of mip level {} (expected {} bytes but got {} bytes)",
+                            level,
+                            expected_bytes,
+                            data.len()
+                        );
+                    }
+                    if context.is_legacy() {
+                        context.compressed_tex_image_2d(
+                            target,
+                            level as i32,
+                            format.internal_format() as i32,
+                            level_width as i32,
+                            level_height as i32,
+                            0,
+                            data.len() as i32,
+                            data,
+                        );
+                    } else {
+                        context.compressed_tex_sub_image_2d(
+                            target,
+                            level as i32,
+                            0,
+                            0,
+                            level_width as i32,
+                            level_height as i32,
+                            format.internal_format(),
+                            crate::context::CompressedPixelUnpackData::Slice(data),
+                        );
+                    }
+                }
+            }
+        }
+        texture
+    }
+
     ///
     /// Fills the cube map texture with the given pixel data for the 6 images.
     ///
@@ -369,6 +559,10 @@ impl TextureCubeMap {
         front_data: &[T],
         back_data: &[T],
     ) {
+        assert!(
+            self.compressed_format.is_none(),
+            "a compressed texture cannot be filled with new data"
+        );
         check_data_length::<T>(
             self.width,
             self.height,
@@ -431,7 +625,7 @@ impl TextureCubeMap {
                     self.width as i32,
                     self.height as i32,
                     format_from_data_type::<T>(),
-                    T::data_type(),
+                    data_type_for_context(&self.context, T::data_type()),
                     crate::context::PixelUnpackData::Slice(to_byte_slice(data)),
                 );
             }
@@ -446,7 +640,20 @@ impl TextureCubeMap {
         context: &Context,
         cpu_texture: &CpuTexture,
     ) -> Self {
-        let texture_size = cpu_texture.width / 4;
+        Self::from_equirectangular::<T>(context, &Texture2D::new(context, cpu_texture))
+    }
+
+    ///
+    /// Creates a new cube texture by rendering the six sides from the equirectangular texture given as input on the GPU,
+    /// which is much faster than doing the conversion on the CPU, especially for large HDR panoramas.
+    /// Each side of the cube texture has a width and height of a quarter of the width of the equirectangular texture
+    /// and the texel data type is four channels of type `T`.
+    ///
+    pub fn from_equirectangular<T: PrimitiveDataType + TextureDataType>(
+        context: &Context,
+        equirectangular: &Texture2D,
+    ) -> Self {
+        let texture_size = (equirectangular.width() / 4).max(1);
         let mut texture = Self::new_empty::<[T; 4]>(
             context,
             texture_size,
@@ -459,8 +666,9 @@ impl TextureCubeMap {
             Wrapping::ClampToEdge,
         );
 
-        {
-            let map = Texture2D::new(context, cpu_texture);
+        let key = b"equirectangular_to_cube_map".to_vec();
+        let mut programs = context.programs.write().unwrap();
+        let program = programs.entry(key).or_insert_with(|| {
             let fragment_shader_source = "
             uniform sampler2D equirectangularMap;
             uniform vec3 direction;
@@ -477,28 +685,27 @@ impl TextureCubeMap {
                 vec2 uv = vec2(0.1591 * atan(dir.z, dir.x) + 0.5, 0.3183 * asin(dir.y) + 0.5);
                 outColor = texture(equirectangularMap, uv);
             }";
-
-            let program = Program::from_source(
+            Program::from_source(
                 context,
                 full_screen_vertex_shader_source(),
-                &fragment_shader_source,
+                fragment_shader_source,
             )
-            .expect("Failed compiling shader");
-
-            for side in CubeMapSide::iter() {
-                let viewport = Viewport::new_at_origo(texture_size, texture_size);
-                texture
-                    .as_color_target(&[side], None)
-                    .clear(ClearState::default())
-                    .write::<CoreError>(|| {
-                        program.use_texture("equirectangularMap", &map);
-                        program.use_uniform("direction", side.direction());
-                        program.use_uniform("up", side.up());
-                        full_screen_draw(context, &program, RenderStates::default(), viewport);
-                        Ok(())
-                    })
-                    .unwrap();
-            }
+            .expect("Failed compiling shader")
+        });
+
+        let viewport = Viewport::new_at_origo(texture_size, texture_size);
+        for side in CubeMapSide::iter() {
+            texture
+                .as_color_target(&[side], None)
+                .clear(ClearState::default())
+                .write::<CoreError>(|| {
+                    program.use_texture("equirectangularMap", equirectangular);
+                    program.use_uniform("direction", side.direction());
+                    program.use_uniform("up", side.up());
+                    full_screen_draw(context, program, RenderStates::default(), viewport);
+                    Ok(())
+                })
+                .unwrap();
         }
         texture
     }
@@ -529,8 +736,19 @@ impl TextureCubeMap {
         self.height
     }
 
+    /// How the format of this texture differs from the format of the data it was constructed from, or `None` if the format of the data is used, see [TextureCubeMap::new].
+    pub fn format_fallback(&self) -> Option<&FormatFallback> {
+        self.format_fallback.as_ref()
+    }
+
+    /// The compressed format of this texture or `None` if it is not compressed, see [TextureCubeMap::new_compressed].
+    pub fn compressed_format(&self) -> Option<CompressedFormat> {
+        self.compressed_format
+    }
+
     pub(in crate::core) fn generate_mip_maps(&self) {
-        if self.number_of_mip_maps > 1 {
+        // Mip maps of compressed textures are given at construction and cannot be generated
+        if self.number_of_mip_maps > 1 && self.compressed_format.is_none() {
             self.bind();
             unsafe {
                 self.context
@@ -556,6 +774,17 @@ impl TextureCubeMap {
         }
     }
 
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "TextureCubeMap",
+            self.id,
+            self.width,
+            self.height,
+            6,
+            FrameResource::color_format(self.data_byte_size),
+        )
+    }
+
     pub(in crate::core) fn bind(&self) {
         unsafe {
             self.context
@@ -0,0 +1,240 @@
+use crate::core::texture::*;
+
+///
+/// An array of cube map textures, ie. a number of layers where each layer covers all 6 sides of a cube.
+/// Useful for binding many environment probes as a single resource.
+///
+/// **Note:** Requires OpenGL 4.0 or OpenGL ES 3.2 and is therefore not supported on web.
+///
+pub struct TextureCubeMapArray {
+    context: Context,
+    id: crate::context::Texture,
+    width: u32,
+    height: u32,
+    depth: u32,
+    number_of_mip_maps: u32,
+    data_byte_size: usize,
+}
+
+impl TextureCubeMapArray {
+    ///
+    /// Creates a new array of cube map textures with `depth` number of cube maps.
+    ///
+    /// **Note:** Mip maps will not be generated for RGB16F and RGB32F format on web and OpenGL ES, even if `mip_map_filter` is specified, see [Capabilities::rgb_float_mip_maps].
+    ///
+    /// # Panic
+    /// Will panic if cube map array textures are not supported by the graphics backend.
+    ///
+    pub fn new_empty<T: TextureDataType>(
+        context: &Context,
+        width: u32,
+        height: u32,
+        depth: u32,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+        wrap_r: Wrapping,
+    ) -> Self {
+        check_cube_map_array_support(context);
+        let id = generate(context);
+        let number_of_mip_maps =
+            calculate_number_of_mip_maps::<T>(context, mip_map_filter, width, height, None);
+        let texture = Self {
+            context: context.clone(),
+            id,
+            width,
+            height,
+            depth,
+            number_of_mip_maps,
+            data_byte_size: std::mem::size_of::<T>(),
+        };
+        texture.bind();
+        set_parameters(
+            context,
+            crate::context::TEXTURE_CUBE_MAP_ARRAY,
+            min_filter,
+            mag_filter,
+            if number_of_mip_maps == 1 {
+                None
+            } else {
+                mip_map_filter
+            },
+            wrap_s,
+            wrap_t,
+            Some(wrap_r),
+        );
+        unsafe {
+            context.tex_storage_3d(
+                crate::context::TEXTURE_CUBE_MAP_ARRAY,
+                number_of_mip_maps as i32,
+                T::internal_format(),
+                width as i32,
+                height as i32,
+                6 * depth as i32,
+            );
+        }
+        texture.generate_mip_maps();
+        texture
+    }
+
+    ///
+    /// Fills the cube map at the given layer with the given pixel data for the 6 images.
+    ///
+    /// # Panic
+    /// Will panic if the layer number is bigger than the number of layers or if the length of the data for all 6 images does not correspond to the width, height and format specified at construction.
+    /// It is therefore necessary to create a new texture if the texture size or format has changed.
+    ///
+    pub fn fill_layer<T: TextureDataType>(
+        &mut self,
+        layer: u32,
+        right_data: &[T],
+        left_data: &[T],
+        top_data: &[T],
+        bottom_data: &[T],
+        front_data: &[T],
+        back_data: &[T],
+    ) {
+        if layer >= self.depth {
+            panic!(
+                "cannot fill the layer {} with data, since there are only {} layers in the cube map array",
+                layer, self.depth
+            )
+        }
+        let data = [
+            right_data,
+            left_data,
+            top_data,
+            bottom_data,
+            front_data,
+            back_data,
+        ];
+        for side_data in data.iter() {
+            check_data_length::<T>(
+                self.width,
+                self.height,
+                1,
+                self.data_byte_size,
+                side_data.len(),
+            );
+        }
+        self.bind();
+        for (i, side_data) in data.iter().enumerate() {
+            unsafe {
+                self.context.tex_sub_image_3d(
+                    crate::context::TEXTURE_CUBE_MAP_ARRAY,
+                    0,
+                    0,
+                    0,
+                    (6 * layer + i as u32) as i32,
+                    self.width as i32,
+                    self.height as i32,
+                    1,
+                    format_from_data_type::<T>(),
+                    T::data_type(),
+                    crate::context::PixelUnpackData::Slice(to_byte_slice(side_data)),
+                );
+            }
+        }
+        self.generate_mip_maps();
+    }
+
+    ///
+    /// Returns a [ColorTarget] which can be used to clear, write to and read from the given sides of the cube map at the given layer and mip level of this texture.
+    /// Combine this together with a [DepthTarget] with [RenderTarget::new] to be able to write to both a depth and color target at the same time.
+    /// If `None` is specified as the mip level, the 0 level mip level is used and mip maps are generated after a write operation if a mip map filter is specified.
+    /// Otherwise, the given mip level is used and no mip maps are generated.
+    ///
+    /// **Note:** [DepthTest] is disabled if not also writing to a depth texture.
+    ///
+    pub fn as_color_target<'a>(
+        &'a mut self,
+        layer: u32,
+        sides: &'a [CubeMapSide],
+        mip_level: Option<u32>,
+    ) -> ColorTarget<'a> {
+        ColorTarget::new_texture_cube_map_array(&self.context, self, layer, sides, mip_level)
+    }
+
+    /// The width of this texture.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of this texture.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of cube maps in the array.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub(in crate::core) fn generate_mip_maps(&self) {
+        if self.number_of_mip_maps > 1 {
+            self.bind();
+            unsafe {
+                self.context
+                    .generate_mipmap(crate::context::TEXTURE_CUBE_MAP_ARRAY);
+            }
+        }
+    }
+
+    pub(in crate::core) fn bind_as_color_target(
+        &self,
+        layer: u32,
+        side: CubeMapSide,
+        channel: u32,
+        mip_level: u32,
+    ) {
+        unsafe {
+            self.context.framebuffer_texture_layer(
+                crate::context::DRAW_FRAMEBUFFER,
+                crate::context::COLOR_ATTACHMENT0 + channel,
+                Some(self.id),
+                mip_level as i32,
+                (6 * layer + side.index()) as i32,
+            );
+        }
+    }
+
+    pub(in crate::core) fn frame_resource(&self) -> FrameResource {
+        FrameResource::texture(
+            "TextureCubeMapArray",
+            self.id,
+            self.width,
+            self.height,
+            6 * self.depth,
+            FrameResource::color_format(self.data_byte_size),
+        )
+    }
+
+    pub(in crate::core) fn bind(&self) {
+        unsafe {
+            self.context
+                .bind_texture(crate::context::TEXTURE_CUBE_MAP_ARRAY, Some(self.id));
+        }
+    }
+}
+
+impl Drop for TextureCubeMapArray {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.delete_texture(self.id);
+        }
+    }
+}
+
+pub(in crate::core) fn check_cube_map_array_support(context: &Context) {
+    let version = context.version();
+    let supported = if version.is_embedded {
+        version.major > 3 || (version.major == 3 && version.minor >= 2)
+    } else {
+        version.major >= 4
+    };
+    if !supported || cfg!(target_arch = "wasm32") {
+        panic!("cube map array textures are not supported by the graphics backend")
+    }
+}
@@ -0,0 +1,458 @@
+use crate::core::*;
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+///
+/// A queue which splits large uploads of textures and buffers to the GPU into chunks and only uploads a limited number of bytes each frame,
+/// see [UploadQueue::bytes_per_frame].
+/// This avoids long frame stalls when for example loading a large model while the application is running.
+///
+/// The GPU resources are created immediately when an upload is queued and can be used right away, but their content is undefined
+/// until [Upload::is_complete] returns true. Call [UploadQueue::update] once each frame to upload the next chunks.
+///
+pub struct UploadQueue {
+    context: Context,
+    jobs: VecDeque<UploadJob>,
+    pixel_buffer: Option<crate::context::Buffer>,
+    /// The maximum number of bytes uploaded in each call to [UploadQueue::update].
+    /// At least one chunk (one texture row or part of a buffer) is uploaded in each update, even if it is larger than this budget.
+    pub bytes_per_frame: usize,
+    /// Whether or not to upload the texture data through a pixel buffer object, which allows the driver to copy the data to the texture asynchronously.
+    /// Default is false.
+    pub use_pixel_buffer: bool,
+}
+
+impl UploadQueue {
+    ///
+    /// Creates a new empty upload queue which uploads at most the given number of bytes each frame.
+    ///
+    pub fn new(context: &Context, bytes_per_frame: usize) -> Self {
+        Self {
+            context: context.clone(),
+            jobs: VecDeque::new(),
+            pixel_buffer: None,
+            bytes_per_frame,
+            use_pixel_buffer: false,
+        }
+    }
+
+    ///
+    /// Queues the upload of the given [CpuTexture] and returns the [Texture2D] that the data is uploaded to.
+    /// The texture is uploaded in chunks of rows and the mip maps are generated when all rows have been uploaded.
+    ///
+    pub fn upload_texture(&mut self, cpu_texture: &CpuTexture) -> Upload<Texture2D> {
+        let (texture, bytes, format, data_type) = match cpu_texture.data {
+            TextureData::RU8(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RgU8(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RgbU8(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RgbaU8(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RF16(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RgF16(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RgbF16(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RgbaF16(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RF32(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RgF32(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RgbF32(ref data) => texture_upload(&self.context, cpu_texture, data),
+            TextureData::RgbaF32(ref data) => texture_upload(&self.context, cpu_texture, data),
+        };
+        let texture = Arc::new(texture);
+        let upload = Upload::new(texture.clone(), bytes.len());
+        self.jobs.push_back(UploadJob {
+            target: UploadTarget::Texture {
+                texture,
+                format,
+                data_type,
+            },
+            context: self.context.clone(),
+            bytes,
+            offset: 0,
+            finished: false,
+            fence: None,
+            progress: upload.progress.clone(),
+        });
+        upload
+    }
+
+    ///
+    /// Queues the upload of the given data and returns the [VertexBuffer] that the data is uploaded to.
+    ///
+    pub fn upload_vertex_buffer<T: BufferDataType>(&mut self, data: &[T]) -> Upload<VertexBuffer> {
+        let buffer = Arc::new(VertexBuffer::new_with_capacity::<T>(
+            &self.context,
+            data.len() as u32,
+        ));
+        let bytes = to_byte_slice(data).to_vec();
+        let upload = Upload::new(buffer.clone(), bytes.len());
+        self.jobs.push_back(UploadJob {
+            target: UploadTarget::VertexBuffer(buffer),
+            context: self.context.clone(),
+            bytes,
+            offset: 0,
+            finished: false,
+            fence: None,
+            progress: upload.progress.clone(),
+        });
+        upload
+    }
+
+    ///
+    /// Queues the upload of the given indices and returns the [ElementBuffer] that the indices are uploaded to.
+    ///
+    pub fn upload_element_buffer<T: ElementBufferDataType>(
+        &mut self,
+        data: &[T],
+    ) -> Upload<ElementBuffer> {
+        let buffer = Arc::new(ElementBuffer::new_with_capacity::<T>(
+            &self.context,
+            data.len(),
+        ));
+        let bytes = to_byte_slice(data).to_vec();
+        let upload = Upload::new(buffer.clone(), bytes.len());
+        self.jobs.push_back(UploadJob {
+            target: UploadTarget::ElementBuffer(buffer),
+            context: self.context.clone(),
+            bytes,
+            offset: 0,
+            finished: false,
+            fence: None,
+            progress: upload.progress.clone(),
+        });
+        upload
+    }
+
+    ///
+    /// Uploads the next chunks of the queued uploads, at most [UploadQueue::bytes_per_frame] bytes, and marks the uploads that the GPU has finished as complete.
+    /// Should be called once each frame.
+    ///
+    pub fn update(&mut self) {
+        let pixel_buffer = self.pixel_buffer();
+        let mut budget = self.bytes_per_frame;
+        let mut uploaded_chunk = false;
+        for job in self.jobs.iter_mut() {
+            while job.offset < job.bytes.len() && (budget > 0 || !uploaded_chunk) {
+                let size = job.upload_chunk(budget, pixel_buffer);
+                budget = budget.saturating_sub(size);
+                uploaded_chunk = true;
+            }
+            if job.offset >= job.bytes.len() && !job.finished {
+                job.finish();
+            }
+            if budget == 0 {
+                break;
+            }
+        }
+        self.jobs.retain(|job| !job.poll());
+    }
+
+    ///
+    /// Uploads all queued data and waits for the GPU to finish, ignoring the budget.
+    ///
+    pub fn flush(&mut self) {
+        let pixel_buffer = self.pixel_buffer();
+        for job in self.jobs.iter_mut() {
+            while job.offset < job.bytes.len() {
+                job.upload_chunk(usize::MAX, pixel_buffer);
+            }
+            if !job.finished {
+                job.finish();
+            }
+        }
+        unsafe {
+            self.context.finish();
+        }
+        self.jobs.retain(|job| !job.poll());
+    }
+
+    ///
+    /// Returns whether or not all queued uploads are complete.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    ///
+    /// Returns the number of bytes that still needs to be uploaded.
+    ///
+    pub fn pending_bytes(&self) -> usize {
+        self.jobs
+            .iter()
+            .map(|job| job.bytes.len() - job.offset)
+            .sum()
+    }
+
+    fn pixel_buffer(&mut self) -> Option<crate::context::Buffer> {
+        if self.use_pixel_buffer && self.pixel_buffer.is_none() {
+            self.pixel_buffer = Some(unsafe {
+                self.context
+                    .create_buffer()
+                    .expect("Failed creating buffer")
+            });
+        }
+        self.pixel_buffer.filter(|_| self.use_pixel_buffer)
+    }
+}
+
+impl Drop for UploadQueue {
+    fn drop(&mut self) {
+        if let Some(pixel_buffer) = self.pixel_buffer {
+            unsafe {
+                self.context.delete_buffer(pixel_buffer);
+            }
+        }
+    }
+}
+
+///
+/// A GPU resource that is being uploaded by an [UploadQueue].
+///
+pub struct Upload<T> {
+    resource: Arc<T>,
+    progress: Arc<RwLock<UploadProgress>>,
+}
+
+impl<T> Upload<T> {
+    fn new(resource: Arc<T>, total_bytes: usize) -> Self {
+        Self {
+            resource,
+            progress: Arc::new(RwLock::new(UploadProgress {
+                uploaded_bytes: 0,
+                total_bytes,
+                complete: false,
+            })),
+        }
+    }
+
+    ///
+    /// Returns the resource that the data is uploaded to. The content of the resource is undefined until the upload is complete.
+    ///
+    pub fn resource(&self) -> &Arc<T> {
+        &self.resource
+    }
+
+    ///
+    /// Returns whether or not all of the data has been uploaded and the GPU has finished processing it.
+    ///
+    pub fn is_complete(&self) -> bool {
+        self.progress.read().unwrap().complete
+    }
+
+    ///
+    /// Returns the fraction of the data that has been uploaded, a value between 0 and 1.
+    ///
+    pub fn progress(&self) -> f32 {
+        let progress = self.progress.read().unwrap();
+        if progress.total_bytes == 0 {
+            1.0
+        } else {
+            progress.uploaded_bytes as f32 / progress.total_bytes as f32
+        }
+    }
+}
+
+impl<T> Clone for Upload<T> {
+    fn clone(&self) -> Self {
+        Self {
+            resource: self.resource.clone(),
+            progress: self.progress.clone(),
+        }
+    }
+}
+
+struct UploadProgress {
+    uploaded_bytes: usize,
+    total_bytes: usize,
+    complete: bool,
+}
+
+enum UploadTarget {
+    Texture {
+        texture: Arc<Texture2D>,
+        format: u32,
+        data_type: u32,
+    },
+    VertexBuffer(Arc<VertexBuffer>),
+    ElementBuffer(Arc<ElementBuffer>),
+}
+
+struct UploadJob {
+    context: Context,
+    target: UploadTarget,
+    bytes: Vec<u8>,
+    offset: usize,
+    finished: bool,
+    fence: Option<crate::context::Fence>,
+    progress: Arc<RwLock<UploadProgress>>,
+}
+
+impl UploadJob {
+    fn upload_chunk(
+        &mut self,
+        budget: usize,
+        pixel_buffer: Option<crate::context::Buffer>,
+    ) -> usize {
+        let remaining = self.bytes.len() - self.offset;
+        let size = match &self.target {
+            UploadTarget::Texture {
+                texture,
+                format,
+                data_type,
+            } => {
+                // Only whole rows can be uploaded
+                let row_size = self.bytes.len() / texture.height().max(1) as usize;
+                let rows = (budget / row_size.max(1)).max(1);
+                let size = (rows * row_size).min(remaining);
+                let y = (self.offset / row_size.max(1)) as u32;
+                let row_count = (size / row_size.max(1)) as u32;
+                let bytes = &self.bytes[self.offset..self.offset + size];
+                if let Some(pixel_buffer) = pixel_buffer {
+                    unsafe {
+                        self.context
+                            .bind_buffer(crate::context::PIXEL_UNPACK_BUFFER, Some(pixel_buffer));
+                        // Respecifying the data orphans the previous storage, so the upload does not wait for the previous copy to finish
+                        self.context.buffer_data_u8_slice(
+                            crate::context::PIXEL_UNPACK_BUFFER,
+                            bytes,
+                            crate::context::STREAM_DRAW,
+                        );
+                    }
+                    texture.fill_rows(
+                        y,
+                        row_count,
+                        *format,
+                        *data_type,
+                        crate::context::PixelUnpackData::BufferOffset(0),
+                    );
+                    unsafe {
+                        self.context
+                            .bind_buffer(crate::context::PIXEL_UNPACK_BUFFER, None);
+                    }
+                } else {
+                    texture.fill_rows(
+                        y,
+                        row_count,
+                        *format,
+                        *data_type,
+                        crate::context::PixelUnpackData::Slice(bytes),
+                    );
+                }
+                size
+            }
+            UploadTarget::VertexBuffer(buffer) => {
+                let size = budget.max(1).min(remaining);
+                buffer_sub_data(
+                    &self.context,
+                    crate::context::ARRAY_BUFFER,
+                    buffer.id(),
+                    self.offset,
+                    &self.bytes[self.offset..self.offset + size],
+                );
+                size
+            }
+            UploadTarget::ElementBuffer(buffer) => {
+                let size = budget.max(1).min(remaining);
+                buffer_sub_data(
+                    &self.context,
+                    crate::context::ELEMENT_ARRAY_BUFFER,
+                    buffer.id(),
+                    self.offset,
+                    &self.bytes[self.offset..self.offset + size],
+                );
+                size
+            }
+        };
+        self.offset += size;
+        self.progress.write().unwrap().uploaded_bytes = self.offset;
+        size
+    }
+
+    fn finish(&mut self) {
+        if let UploadTarget::Texture { texture, .. } = &self.target {
+            texture.generate_mip_maps();
+        }
+        // The data is no longer needed, so release the memory
+        self.bytes = Vec::new();
+        self.offset = 0;
+        self.finished = true;
+        unsafe {
+            self.fence = self
+                .context
+                .fence_sync(crate::context::SYNC_GPU_COMMANDS_COMPLETE, 0)
+                .ok();
+            self.context.flush();
+        }
+    }
+
+    ///
+    /// Returns true if the upload is complete, ie. all data is uploaded and the GPU has finished processing it.
+    ///
+    fn poll(&self) -> bool {
+        if !self.finished {
+            return false;
+        }
+        let complete = match self.fence {
+            Some(fence) => {
+                let status = unsafe { self.context.client_wait_sync(fence, 0, 0) };
+                status == crate::context::ALREADY_SIGNALED
+                    || status == crate::context::CONDITION_SATISFIED
+            }
+            None => true,
+        };
+        if complete {
+            self.progress.write().unwrap().complete = true;
+        }
+        complete
+    }
+}
+
+impl Drop for UploadJob {
+    fn drop(&mut self) {
+        if let Some(fence) = self.fence {
+            unsafe {
+                self.context.delete_sync(fence);
+            }
+        }
+    }
+}
+
+fn texture_upload<T: TextureDataType>(
+    context: &Context,
+    cpu_texture: &CpuTexture,
+    data: &[T],
+) -> (Texture2D, Vec<u8>, u32, u32) {
+    let texture = Texture2D::new_empty::<T>(
+        context,
+        cpu_texture.width,
+        cpu_texture.height,
+        cpu_texture.min_filter,
+        cpu_texture.mag_filter,
+        cpu_texture.mip_map_filter,
+        cpu_texture.wrap_s,
+        cpu_texture.wrap_t,
+    );
+    let mut data = data.to_owned();
+    flip_y(
+        &mut data,
+        cpu_texture.width as usize,
+        cpu_texture.height as usize,
+    );
+    (
+        texture,
+        to_byte_slice(&data).to_vec(),
+        format_from_data_type::<T>(),
+        T::data_type(),
+    )
+}
+
+fn buffer_sub_data(
+    context: &Context,
+    target: u32,
+    buffer: crate::context::Buffer,
+    offset: usize,
+    bytes: &[u8],
+) {
+    unsafe {
+        context.bind_buffer(target, Some(buffer));
+        context.buffer_sub_data_u8_slice(target, offset as i32, bytes);
+        context.bind_buffer(target, None);
+    }
+}
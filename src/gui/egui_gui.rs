@@ -189,6 +189,13 @@ impl GUI {
                             None
                         }
                     }
+                    Event::Pinch { delta, handled, .. } => {
+                        if !handled {
+                            Some(egui::Event::Zoom((delta / 200.0).exp()))
+                        } else {
+                            None
+                        }
+                    }
                     _ => None,
                 })
                 .collect::<Vec<_>>(),
@@ -220,6 +227,11 @@ impl GUI {
                     } => {
                         *handled = true;
                     }
+                    Event::Pinch {
+                        ref mut handled, ..
+                    } => {
+                        *handled = true;
+                    }
                     Event::MouseMotion {
                         ref mut handled, ..
                     } => {
@@ -0,0 +1,51 @@
+//!
+//! Loading and saving of assets which are not covered by [three_d_asset], for example the node hierarchy, skins and animations of a glTF file,
+//! OBJ files with several groups and materials, STL and PLY files used for 3D printing and scanning, LAS, PCD and XYZ point clouds, OpenEXR images with the full dynamic range,
+//! KTX2 textures with block compressed and Basis Universal data or DDS textures and cube maps with block compressed data.
+//! The raw bytes are loaded and saved using [three_d_asset::io], so the functionality in this module works on all platforms, including web,
+//! except for saving screenshots with [save_screenshot] which is only available on native.
+//!
+
+#[cfg(feature = "gltf")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gltf")))]
+mod gltf_loader;
+#[cfg(feature = "gltf")]
+pub use gltf_loader::*;
+
+#[cfg(feature = "gltf")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gltf")))]
+mod gltf_exporter;
+#[cfg(feature = "gltf")]
+pub use gltf_exporter::*;
+
+#[cfg(feature = "exr")]
+#[cfg_attr(docsrs, doc(cfg(feature = "exr")))]
+mod exr_loader;
+#[cfg(feature = "exr")]
+pub use exr_loader::*;
+
+#[cfg(feature = "ktx2")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ktx2")))]
+mod ktx2_loader;
+#[cfg(feature = "ktx2")]
+pub use ktx2_loader::*;
+
+mod obj_loader;
+pub use obj_loader::*;
+
+mod stl;
+pub use stl::*;
+
+mod ply;
+pub use ply::*;
+
+mod point_cloud_loader;
+pub use point_cloud_loader::*;
+
+mod dds_loader;
+pub use dds_loader::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod screenshot;
+#[cfg(not(target_arch = "wasm32"))]
+pub use screenshot::*;
@@ -0,0 +1,213 @@
+use crate::renderer::*;
+use std::path::Path;
+use three_d_asset::io::RawAssets;
+
+///
+/// Deserializes the block compressed 2D texture in the DirectDraw Surface file (`.dds`) at the given path from the given raw assets
+/// and uploads it to a [Texture2D], including the mip levels stored in the file.
+///
+/// The BC1-BC5 formats are supported with both the legacy four character codes (`DXT1`-`DXT5`, `ATI1`, `ATI2`, `BC4U` and `BC5U`) and the DX10 header,
+/// while BC6H (unsigned) and BC7 require the DX10 header. The format must be supported by the context, see [Context::supports_compressed_format].
+/// The returned [Texture2DRef] has a uv transformation which makes the texture appear with the same orientation as a texture loaded from a PNG or JPEG image,
+/// since the compressed data cannot be flipped when uploading it.
+///
+pub fn deserialize_dds(
+    context: &Context,
+    raw_assets: &RawAssets,
+    path: impl AsRef<Path>,
+) -> Result<Texture2DRef, RendererError> {
+    let dds = Dds::parse(raw_assets, path.as_ref(), context)?;
+    if dds.is_cube_map {
+        return Err(dds_error(
+            "the file contains a cube map, use deserialize_dds_cube_map instead",
+        ));
+    }
+    let mut texture = Texture2DRef::from_texture(Texture2D::new_compressed(context, &dds.side(0)));
+    // The rows are stored from top to bottom
+    texture.transformation = Mat3::from_cols(
+        vec3(1.0, 0.0, 0.0),
+        vec3(0.0, -1.0, 0.0),
+        vec3(0.0, 1.0, 1.0),
+    );
+    Ok(texture)
+}
+
+///
+/// Deserializes the block compressed cube map in the DirectDraw Surface file (`.dds`) at the given path from the given raw assets
+/// and uploads it to a [TextureCubeMap], including the mip levels stored in the file.
+/// All six sides must be present. The supported formats are the same as for [deserialize_dds].
+///
+pub fn deserialize_dds_cube_map(
+    context: &Context,
+    raw_assets: &RawAssets,
+    path: impl AsRef<Path>,
+) -> Result<TextureCubeMap, RendererError> {
+    let dds = Dds::parse(raw_assets, path.as_ref(), context)?;
+    if !dds.is_cube_map {
+        return Err(dds_error(
+            "the file does not contain a cube map, use deserialize_dds instead",
+        ));
+    }
+    // The sides are stored in the order +x, -x, +y, -y, +z, -z
+    Ok(TextureCubeMap::new_compressed(
+        context,
+        &dds.side(0),
+        &dds.side(1),
+        &dds.side(2),
+        &dds.side(3),
+        &dds.side(4),
+        &dds.side(5),
+    ))
+}
+
+fn dds_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::DdsDeserialization(error.to_string())
+}
+
+struct Dds {
+    name: String,
+    format: CompressedFormat,
+    width: u32,
+    height: u32,
+    is_cube_map: bool,
+    // The mip levels of each side, starting with the largest
+    sides: Vec<Vec<Vec<u8>>>,
+}
+
+impl Dds {
+    fn parse(
+        raw_assets: &RawAssets,
+        path: &Path,
+        context: &Context,
+    ) -> Result<Self, RendererError> {
+        let bytes = raw_assets.get(path).map_err(dds_error)?;
+        if !bytes.starts_with(b"DDS ") || read_u32(bytes, 4)? != 124 {
+            return Err(dds_error("the file does not start with a DDS header"));
+        }
+        let flags = read_u32(bytes, 8)?;
+        let height = read_u32(bytes, 12)?;
+        let width = read_u32(bytes, 16)?;
+        let mip_map_count = if flags & 0x20000 != 0 {
+            read_u32(bytes, 28)?.max(1)
+        } else {
+            1
+        };
+        let pixel_format_flags = read_u32(bytes, 80)?;
+        let four_cc = bytes
+            .get(84..88)
+            .ok_or_else(|| dds_error("unexpected end of file"))?;
+        let caps2 = read_u32(bytes, 112)?;
+        if width == 0 || height == 0 {
+            return Err(dds_error("the texture has zero size"));
+        }
+        if caps2 & 0x200000 != 0 {
+            return Err(dds_error("volume textures are not supported"));
+        }
+        if pixel_format_flags & 0x4 == 0 {
+            return Err(dds_error("only block compressed formats are supported"));
+        }
+
+        let (format, is_cube_map, mut offset) = if four_cc == b"DX10" {
+            let dxgi_format = read_u32(bytes, 128)?;
+            let misc_flags = read_u32(bytes, 136)?;
+            if read_u32(bytes, 140)? > 1 {
+                return Err(dds_error("texture arrays are not supported"));
+            }
+            let format = match dxgi_format {
+                71 | 72 => CompressedFormat::Bc1Rgba,
+                74 | 75 => CompressedFormat::Bc2Rgba,
+                77 | 78 => CompressedFormat::Bc3Rgba,
+                80 => CompressedFormat::Bc4R,
+                83 => CompressedFormat::Bc5Rg,
+                95 => CompressedFormat::Bc6hRgbUfloat,
+                98 | 99 => CompressedFormat::Bc7Rgba,
+                _ => {
+                    return Err(dds_error(format!(
+                        "unsupported format (DXGI format {})",
+                        dxgi_format
+                    )))
+                }
+            };
+            (format, misc_flags & 0x4 != 0, 148)
+        } else {
+            let format = match four_cc {
+                b"DXT1" => CompressedFormat::Bc1Rgba,
+                b"DXT2" | b"DXT3" => CompressedFormat::Bc2Rgba,
+                b"DXT4" | b"DXT5" => CompressedFormat::Bc3Rgba,
+                b"ATI1" | b"BC4U" => CompressedFormat::Bc4R,
+                b"ATI2" | b"BC5U" => CompressedFormat::Bc5Rg,
+                _ => {
+                    return Err(dds_error(format!(
+                        "unsupported format '{}'",
+                        String::from_utf8_lossy(four_cc)
+                    )))
+                }
+            };
+            (format, caps2 & 0x200 != 0, 128)
+        };
+        if is_cube_map && four_cc != b"DX10" && caps2 & 0xFC00 != 0xFC00 {
+            return Err(dds_error(
+                "cube maps without all six sides are not supported",
+            ));
+        }
+        if !context.supports_compressed_format(format) {
+            return Err(dds_error(format!(
+                "the compressed format {:?} is not supported by the graphics backend",
+                format
+            )));
+        }
+
+        let mut sides = Vec::new();
+        for _ in 0..if is_cube_map { 6 } else { 1 } {
+            let mut mip_levels = Vec::new();
+            for level in 0..mip_map_count {
+                let size = format.byte_size((width >> level).max(1), (height >> level).max(1));
+                let data = bytes
+                    .get(offset..offset + size)
+                    .ok_or_else(|| dds_error("unexpected end of file"))?;
+                mip_levels.push(data.to_vec());
+                offset += size;
+            }
+            sides.push(mip_levels);
+        }
+        Ok(Self {
+            name: path.to_string_lossy().to_string(),
+            format,
+            width,
+            height,
+            is_cube_map,
+            sides,
+        })
+    }
+
+    fn side(&self, index: usize) -> CpuCompressedTexture {
+        let mip_levels = self.sides[index].clone();
+        CpuCompressedTexture {
+            name: self.name.clone(),
+            format: self.format,
+            width: self.width,
+            height: self.height,
+            mip_map_filter: (mip_levels.len() > 1).then_some(Interpolation::Linear),
+            mip_levels,
+            min_filter: Interpolation::Linear,
+            mag_filter: Interpolation::Linear,
+            wrap_s: if self.is_cube_map {
+                Wrapping::ClampToEdge
+            } else {
+                Wrapping::Repeat
+            },
+            wrap_t: if self.is_cube_map {
+                Wrapping::ClampToEdge
+            } else {
+                Wrapping::Repeat
+            },
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, RendererError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| dds_error("unexpected end of file"))
+}
@@ -0,0 +1,48 @@
+use crate::renderer::*;
+use std::path::Path;
+use three_d_asset::io::RawAssets;
+
+///
+/// Deserializes the first layer of the OpenEXR image (`.exr`) at the given path from the given raw assets into a [CpuTexture] with 32 bit float colors ([TextureData::RgbaF32]),
+/// so the full dynamic range is kept, for example for environment lighting or lightmaps.
+///
+/// All compression methods supported by the [exr](https://crates.io/crates/exr) crate are supported and half and unsigned integer channels are converted to float.
+/// The `R`, `G` and `B` channels are required, while the alpha is one if the `A` channel is missing.
+/// Radiance HDR images (`.hdr`) are deserialized with the full dynamic range by [three_d_asset], for example using `raw_assets.deserialize("image.hdr")`.
+///
+pub fn deserialize_exr(
+    raw_assets: &RawAssets,
+    path: impl AsRef<Path>,
+) -> Result<CpuTexture, RendererError> {
+    use exr::prelude::*;
+    let path = path.as_ref();
+    let bytes = raw_assets.get(path).map_err(exr_error)?;
+    let image = read()
+        .no_deep_data()
+        .largest_resolution_level()
+        .rgba_channels(
+            |resolution, _| (resolution.width(), vec![[0.0f32; 4]; resolution.area()]),
+            |(width, pixels): &mut (usize, Vec<[f32; 4]>),
+             position,
+             (r, g, b, a): (f32, f32, f32, f32)| {
+                pixels[position.y() * *width + position.x()] = [r, g, b, a];
+            },
+        )
+        .first_valid_layer()
+        .all_attributes()
+        .from_buffered(std::io::Cursor::new(bytes))
+        .map_err(exr_error)?;
+    let size = image.layer_data.size;
+    let (_, pixels) = image.layer_data.channel_data.pixels;
+    Ok(CpuTexture {
+        name: path.to_string_lossy().to_string(),
+        data: TextureData::RgbaF32(pixels),
+        width: size.width() as u32,
+        height: size.height() as u32,
+        ..Default::default()
+    })
+}
+
+fn exr_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::ExrDeserialization(error.to_string())
+}
@@ -0,0 +1,537 @@
+use crate::io::*;
+use crate::renderer::*;
+use serde_json::{json, Value};
+use std::path::Path;
+use three_d_asset::io::{RawAssets, Serialize};
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+impl GltfAsset {
+    ///
+    /// Creates an asset from the triangle meshes and materials in the given [CpuModel], for example procedurally generated geometry,
+    /// so it can be saved using [GltfAsset::serialize]. Each primitive in the model becomes a root node with its own mesh.
+    /// The transformation of each primitive must consist of a translation, rotation and (non-uniform) scale, ie. without shear.
+    /// Point clouds and animations are not included.
+    ///
+    pub fn from_cpu_model(cpu_model: &CpuModel) -> Self {
+        let mut meshes = Vec::new();
+        let mut nodes = Vec::new();
+        for primitive in cpu_model.geometries.iter() {
+            if let CpuGeometry::Triangles(geometry) = &primitive.geometry {
+                nodes.push(GltfNode {
+                    name: primitive.name.clone(),
+                    parent: None,
+                    children: Vec::new(),
+                    pose: JointPose::from_transformation(primitive.transformation),
+                    mesh: Some(meshes.len()),
+                    skin: None,
+                });
+                meshes.push(GltfMesh {
+                    name: primitive.name.clone(),
+                    primitives: vec![GltfPrimitive {
+                        geometry: geometry.clone(),
+                        material_index: primitive.material_index,
+                        joints: None,
+                        weights: None,
+                    }],
+                });
+            }
+        }
+        Self {
+            meshes,
+            materials: cpu_model.materials.clone(),
+            nodes,
+            skins: Vec::new(),
+            animations: Vec::new(),
+        }
+    }
+
+    ///
+    /// Serializes this asset into a binary glTF file (`.glb`) which can be saved using for example [three_d_asset::io::save].
+    /// All buffers and images are embedded in the file and the textures are encoded as PNG, so HDR textures are not supported.
+    ///
+    pub fn serialize(&self, path: impl AsRef<Path>) -> Result<RawAssets, RendererError> {
+        let mut raw_assets = RawAssets::new();
+        raw_assets.insert(path, self.to_glb()?);
+        Ok(raw_assets)
+    }
+
+    ///
+    /// Returns the content of a binary glTF file (`.glb`) containing this asset, see [GltfAsset::serialize].
+    ///
+    pub fn to_glb(&self) -> Result<Vec<u8>, RendererError> {
+        let mut writer = GlbWriter::default();
+
+        let mut textures = Vec::new();
+        let mut materials = Vec::new();
+        for material in self.materials.iter() {
+            let mut texture = |cpu_texture: &Option<CpuTexture>| {
+                cpu_texture
+                    .as_ref()
+                    .map(|cpu_texture| writer.add_texture(cpu_texture, &mut textures))
+                    .transpose()
+            };
+            let albedo: [f32; 4] = material.albedo.to_linear_srgb().into();
+            let emissive: [f32; 4] = material.emissive.to_linear_srgb().into();
+            let (metallic_roughness_texture, occlusion_texture) =
+                if material.occlusion_metallic_roughness_texture.is_some() {
+                    let index = texture(&material.occlusion_metallic_roughness_texture)?;
+                    (index, index)
+                } else {
+                    (
+                        texture(&material.metallic_roughness_texture)?,
+                        texture(&material.occlusion_texture)?,
+                    )
+                };
+            let mut pbr = json!({
+                "baseColorFactor": albedo,
+                "metallicFactor": material.metallic,
+                "roughnessFactor": material.roughness,
+            });
+            if let Some(index) = texture(&material.albedo_texture)? {
+                pbr["baseColorTexture"] = json!({ "index": index });
+            }
+            if let Some(index) = metallic_roughness_texture {
+                pbr["metallicRoughnessTexture"] = json!({ "index": index });
+            }
+            let mut value = json!({
+                "name": material.name,
+                "pbrMetallicRoughness": pbr,
+                "emissiveFactor": [emissive[0], emissive[1], emissive[2]],
+            });
+            if let Some(index) = texture(&material.normal_texture)? {
+                value["normalTexture"] = json!({ "index": index, "scale": material.normal_scale });
+            }
+            if let Some(index) = occlusion_texture {
+                value["occlusionTexture"] =
+                    json!({ "index": index, "strength": material.occlusion_strength });
+            }
+            if let Some(index) = texture(&material.emissive_texture)? {
+                value["emissiveTexture"] = json!({ "index": index });
+            }
+            if let Some(alpha_cutout) = material.alpha_cutout {
+                value["alphaMode"] = json!("MASK");
+                value["alphaCutoff"] = json!(alpha_cutout);
+            } else if material.albedo.a < 255 {
+                value["alphaMode"] = json!("BLEND");
+            }
+            materials.push(value);
+        }
+
+        let mut meshes = Vec::new();
+        for mesh in self.meshes.iter() {
+            let mut primitives = Vec::new();
+            for primitive in mesh.primitives.iter() {
+                primitives.push(writer.add_primitive(primitive)?);
+            }
+            meshes.push(json!({ "name": mesh.name, "primitives": primitives }));
+        }
+
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let rotation = node.pose.rotation;
+                let mut value = json!({
+                    "name": node.name,
+                    "translation": <[f32; 3]>::from(node.pose.translation),
+                    "rotation": [rotation.v.x, rotation.v.y, rotation.v.z, rotation.s],
+                    "scale": <[f32; 3]>::from(node.pose.scale),
+                });
+                if !node.children.is_empty() {
+                    value["children"] = json!(node.children);
+                }
+                if let Some(mesh) = node.mesh {
+                    value["mesh"] = json!(mesh);
+                }
+                if let Some(skin) = node.skin {
+                    value["skin"] = json!(skin);
+                }
+                value
+            })
+            .collect::<Vec<_>>();
+
+        let skins = self
+            .skins
+            .iter()
+            .map(|skin| {
+                let matrices = skin
+                    .inverse_bind_matrices
+                    .iter()
+                    .flat_map(|m| <[[f32; 4]; 4]>::from(*m).into_iter().flatten())
+                    .collect::<Vec<_>>();
+                let accessor = writer.add_accessor(
+                    &f32_bytes(&matrices),
+                    FLOAT,
+                    skin.inverse_bind_matrices.len(),
+                    "MAT4",
+                    None,
+                );
+                json!({
+                    "name": skin.name,
+                    "joints": skin.joints,
+                    "inverseBindMatrices": accessor,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let animations = self
+            .animations
+            .iter()
+            .map(|animation| writer.add_animation(animation))
+            .collect::<Vec<_>>();
+
+        let roots = (0..self.nodes.len())
+            .filter(|node| self.nodes[*node].parent.is_none())
+            .collect::<Vec<_>>();
+        let mut root = json!({
+            "asset": { "version": "2.0", "generator": "three-d" },
+            "scene": 0,
+            "scenes": [{ "nodes": roots }],
+            "nodes": nodes,
+        });
+        for (name, values) in [
+            ("meshes", meshes),
+            ("materials", materials),
+            ("skins", skins),
+            ("animations", animations),
+            ("textures", textures),
+            ("images", writer.images.clone()),
+            ("samplers", writer.samplers.clone()),
+            ("accessors", writer.accessors.clone()),
+            ("bufferViews", writer.buffer_views.clone()),
+        ] {
+            if !values.is_empty() {
+                root[name] = Value::Array(values);
+            }
+        }
+        if !writer.buffer.is_empty() {
+            root["buffers"] = json!([{ "byteLength": writer.buffer.len() }]);
+        }
+
+        let mut json = serde_json::to_vec(&root).map_err(gltf_error)?;
+        pad(&mut json, b' ');
+        let mut bin = writer.buffer;
+        pad(&mut bin, 0);
+        let length = 12 + 8 + json.len() + if bin.is_empty() { 0 } else { 8 + bin.len() };
+        let mut glb = Vec::with_capacity(length);
+        glb.extend(b"glTF");
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((length as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(b"JSON");
+        glb.extend(json);
+        if !bin.is_empty() {
+            glb.extend((bin.len() as u32).to_le_bytes());
+            glb.extend(b"BIN\0");
+            glb.extend(bin);
+        }
+        Ok(glb)
+    }
+}
+
+// Collects the binary data, buffer views, accessors, images and samplers of a GLB file.
+#[derive(Default)]
+struct GlbWriter {
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    images: Vec<Value>,
+    samplers: Vec<Value>,
+}
+
+impl GlbWriter {
+    fn add_buffer_view(&mut self, data: &[u8], target: Option<u32>) -> usize {
+        pad(&mut self.buffer, 0);
+        let mut buffer_view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": data.len(),
+        });
+        if let Some(target) = target {
+            buffer_view["target"] = json!(target);
+        }
+        self.buffer.extend_from_slice(data);
+        self.buffer_views.push(buffer_view);
+        self.buffer_views.len() - 1
+    }
+
+    fn add_accessor(
+        &mut self,
+        data: &[u8],
+        component_type: u32,
+        count: usize,
+        type_: &str,
+        target: Option<u32>,
+    ) -> usize {
+        let buffer_view = self.add_buffer_view(data, target);
+        self.accessors.push(json!({
+            "bufferView": buffer_view,
+            "componentType": component_type,
+            "count": count,
+            "type": type_,
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn add_primitive(&mut self, primitive: &GltfPrimitive) -> Result<Value, RendererError> {
+        let geometry = &primitive.geometry;
+        let positions = geometry.positions.to_f32();
+        let position_accessor = self.add_accessor(
+            &f32_bytes(
+                &positions
+                    .iter()
+                    .flat_map(|p| [p.x, p.y, p.z])
+                    .collect::<Vec<_>>(),
+            ),
+            FLOAT,
+            positions.len(),
+            "VEC3",
+            Some(ARRAY_BUFFER),
+        );
+        // The bounds of the positions are required by the specification
+        let mut aabb = AxisAlignedBoundingBox::new_with_positions(&positions);
+        if positions.is_empty() {
+            aabb = AxisAlignedBoundingBox::new_with_positions(&[vec3(0.0, 0.0, 0.0)]);
+        }
+        self.accessors[position_accessor]["min"] = json!(<[f32; 3]>::from(aabb.min()));
+        self.accessors[position_accessor]["max"] = json!(<[f32; 3]>::from(aabb.max()));
+        let mut attributes = json!({ "POSITION": position_accessor });
+
+        if let Some(normals) = &geometry.normals {
+            attributes["NORMAL"] = json!(self.add_accessor(
+                &f32_bytes(
+                    &normals
+                        .iter()
+                        .flat_map(|n| [n.x, n.y, n.z])
+                        .collect::<Vec<_>>()
+                ),
+                FLOAT,
+                normals.len(),
+                "VEC3",
+                Some(ARRAY_BUFFER),
+            ));
+        }
+        if let Some(tangents) = &geometry.tangents {
+            attributes["TANGENT"] = json!(self.add_accessor(
+                &f32_bytes(
+                    &tangents
+                        .iter()
+                        .flat_map(|t| [t.x, t.y, t.z, t.w])
+                        .collect::<Vec<_>>()
+                ),
+                FLOAT,
+                tangents.len(),
+                "VEC4",
+                Some(ARRAY_BUFFER),
+            ));
+        }
+        if let Some(uvs) = &geometry.uvs {
+            // glTF uv coordinates has the origin in the top left corner
+            attributes["TEXCOORD_0"] = json!(self.add_accessor(
+                &f32_bytes(
+                    &uvs.iter()
+                        .flat_map(|uv| [uv.x, 1.0 - uv.y])
+                        .collect::<Vec<_>>()
+                ),
+                FLOAT,
+                uvs.len(),
+                "VEC2",
+                Some(ARRAY_BUFFER),
+            ));
+        }
+        if let Some(colors) = &geometry.colors {
+            let accessor = self.add_accessor(
+                &colors
+                    .iter()
+                    .flat_map(|c| [c.r, c.g, c.b, c.a])
+                    .collect::<Vec<_>>(),
+                UNSIGNED_BYTE,
+                colors.len(),
+                "VEC4",
+                Some(ARRAY_BUFFER),
+            );
+            self.accessors[accessor]["normalized"] = json!(true);
+            attributes["COLOR_0"] = json!(accessor);
+        }
+        if let (Some(joints), Some(weights)) = (&primitive.joints, &primitive.weights) {
+            attributes["JOINTS_0"] = json!(self.add_accessor(
+                &joints
+                    .iter()
+                    .flatten()
+                    .flat_map(|j| j.to_le_bytes())
+                    .collect::<Vec<_>>(),
+                UNSIGNED_SHORT,
+                joints.len(),
+                "VEC4",
+                Some(ARRAY_BUFFER),
+            ));
+            attributes["WEIGHTS_0"] = json!(self.add_accessor(
+                &f32_bytes(&weights.iter().flatten().copied().collect::<Vec<_>>()),
+                FLOAT,
+                weights.len(),
+                "VEC4",
+                Some(ARRAY_BUFFER),
+            ));
+        }
+
+        let mut value = json!({ "attributes": attributes, "mode": 4 });
+        let indices = match &geometry.indices {
+            Indices::None => None,
+            Indices::U8(indices) => Some(indices.iter().map(|i| *i as u32).collect::<Vec<_>>()),
+            Indices::U16(indices) => Some(indices.iter().map(|i| *i as u32).collect::<Vec<_>>()),
+            Indices::U32(indices) => Some(indices.clone()),
+        };
+        if let Some(indices) = indices {
+            value["indices"] = json!(self.add_accessor(
+                &indices
+                    .iter()
+                    .flat_map(|i| i.to_le_bytes())
+                    .collect::<Vec<_>>(),
+                UNSIGNED_INT,
+                indices.len(),
+                "SCALAR",
+                Some(ELEMENT_ARRAY_BUFFER),
+            ));
+        }
+        if let Some(material_index) = primitive.material_index {
+            value["material"] = json!(material_index);
+        }
+        Ok(value)
+    }
+
+    // Adds the texture and its image and sampler and returns the index of the texture.
+    fn add_texture(
+        &mut self,
+        cpu_texture: &CpuTexture,
+        textures: &mut Vec<Value>,
+    ) -> Result<usize, RendererError> {
+        let path = "texture.png";
+        let png = cpu_texture
+            .serialize(path)
+            .and_then(|mut raw_assets| raw_assets.remove(path))
+            .map_err(gltf_error)?;
+        let buffer_view = self.add_buffer_view(&png, None);
+        self.images.push(json!({
+            "name": cpu_texture.name,
+            "bufferView": buffer_view,
+            "mimeType": "image/png",
+        }));
+        let filter = |interpolation: Interpolation| match interpolation {
+            Interpolation::Nearest => 9728,
+            _ => 9729,
+        };
+        let wrapping = |wrapping: Wrapping| match wrapping {
+            Wrapping::Repeat => 10497,
+            Wrapping::MirroredRepeat => 33648,
+            Wrapping::ClampToEdge => 33071,
+        };
+        self.samplers.push(json!({
+            "magFilter": filter(cpu_texture.mag_filter),
+            "minFilter": filter(cpu_texture.min_filter),
+            "wrapS": wrapping(cpu_texture.wrap_s),
+            "wrapT": wrapping(cpu_texture.wrap_t),
+        }));
+        textures.push(json!({
+            "source": self.images.len() - 1,
+            "sampler": self.samplers.len() - 1,
+        }));
+        Ok(textures.len() - 1)
+    }
+
+    fn add_animation(&mut self, animation: &AnimationClip) -> Value {
+        let mut samplers = Vec::new();
+        let mut channels = Vec::new();
+        for channel in animation.channels.iter() {
+            let input = self.add_accessor(
+                &f32_bytes(&channel.times),
+                FLOAT,
+                channel.times.len(),
+                "SCALAR",
+                None,
+            );
+            // The bounds of the keyframe times are required by the specification
+            let min = channel.times.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = channel
+                .times
+                .iter()
+                .copied()
+                .fold(f32::NEG_INFINITY, f32::max);
+            self.accessors[input]["min"] = json!([min]);
+            self.accessors[input]["max"] = json!([max]);
+            let interpolation = match channel.interpolation {
+                AnimationInterpolation::Step => "STEP",
+                AnimationInterpolation::Linear => "LINEAR",
+            };
+            let mut outputs = Vec::new();
+            if let Some(translations) = &channel.translations {
+                outputs.push((
+                    "translation",
+                    translations
+                        .iter()
+                        .flat_map(|t| [t.x, t.y, t.z])
+                        .collect::<Vec<_>>(),
+                    "VEC3",
+                ));
+            }
+            if let Some(rotations) = &channel.rotations {
+                outputs.push((
+                    "rotation",
+                    rotations
+                        .iter()
+                        .flat_map(|r| [r.v.x, r.v.y, r.v.z, r.s])
+                        .collect::<Vec<_>>(),
+                    "VEC4",
+                ));
+            }
+            if let Some(scales) = &channel.scales {
+                outputs.push((
+                    "scale",
+                    scales
+                        .iter()
+                        .flat_map(|s| [s.x, s.y, s.z])
+                        .collect::<Vec<_>>(),
+                    "VEC3",
+                ));
+            }
+            for (path, values, type_) in outputs {
+                let output =
+                    self.add_accessor(&f32_bytes(&values), FLOAT, channel.times.len(), type_, None);
+                samplers.push(json!({
+                    "input": input,
+                    "output": output,
+                    "interpolation": interpolation,
+                }));
+                channels.push(json!({
+                    "sampler": samplers.len() - 1,
+                    "target": { "node": channel.joint, "path": path },
+                }));
+            }
+        }
+        json!({
+            "name": animation.name,
+            "samplers": samplers,
+            "channels": channels,
+        })
+    }
+}
+
+fn gltf_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::GltfSerialization(error.to_string())
+}
+
+fn f32_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+// Pads the data to a multiple of four bytes, which is required for chunks and buffer views.
+fn pad(data: &mut Vec<u8>, value: u8) {
+    while data.len() % 4 != 0 {
+        data.push(value);
+    }
+}
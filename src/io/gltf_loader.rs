@@ -0,0 +1,598 @@
+use crate::renderer::*;
+use std::collections::HashMap;
+use std::path::Path;
+use three_d_asset::io::RawAssets;
+
+///
+/// A primitive of a [GltfMesh], ie. a triangle mesh with a single material.
+///
+#[derive(Clone, Debug)]
+pub struct GltfPrimitive {
+    /// The geometry of the primitive.
+    pub geometry: CpuMesh,
+    /// The index of the material in [GltfAsset::materials] or `None` if the default material should be used.
+    pub material_index: Option<usize>,
+    /// The indices of the (up to) four joints affecting each vertex, if the primitive is skinned.
+    /// The indices refer to [GltfSkin::joints] of the skin used together with this primitive.
+    pub joints: Option<Vec<[u16; 4]>>,
+    /// The weights of the four joints affecting each vertex, if the primitive is skinned.
+    pub weights: Option<Vec<[f32; 4]>>,
+}
+
+///
+/// A mesh in a [GltfAsset] consisting of one or more primitives.
+///
+#[derive(Clone, Debug)]
+pub struct GltfMesh {
+    /// The name of the mesh.
+    pub name: String,
+    /// The primitives of the mesh.
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+///
+/// A node in the node hierarchy of a [GltfAsset].
+///
+#[derive(Clone, Debug)]
+pub struct GltfNode {
+    /// The name of the node.
+    pub name: String,
+    /// The index of the parent node or `None` if this is a root node.
+    pub parent: Option<usize>,
+    /// The indices of the child nodes.
+    pub children: Vec<usize>,
+    /// The local transformation of the node relative to its parent.
+    pub pose: JointPose,
+    /// The index of the mesh in [GltfAsset::meshes] attached to this node.
+    pub mesh: Option<usize>,
+    /// The index of the skin in [GltfAsset::skins] used for deforming the mesh attached to this node.
+    pub skin: Option<usize>,
+}
+
+///
+/// A skin in a [GltfAsset], ie. the nodes used as joints when deforming a mesh and their inverse bind matrices.
+///
+#[derive(Clone, Debug)]
+pub struct GltfSkin {
+    /// The name of the skin.
+    pub name: String,
+    /// The index of the node used as each joint.
+    pub joints: Vec<usize>,
+    /// The inverse bind matrix of each joint.
+    pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+impl GltfSkin {
+    ///
+    /// Returns the [Skin] for deforming the given primitive with this skin or `None` if the primitive is not skinned
+    /// or refers to joints which are not part of this skin.
+    ///
+    pub fn skin(&self, primitive: &GltfPrimitive) -> Option<Skin> {
+        let joints = primitive.joints.as_ref()?;
+        let weights = primitive.weights.as_ref()?;
+        if joints.len() != weights.len()
+            || joints
+                .iter()
+                .flatten()
+                .any(|j| *j as usize >= self.inverse_bind_matrices.len())
+        {
+            return None;
+        }
+        Some(Skin::new(
+            joints.clone(),
+            weights.clone(),
+            self.inverse_bind_matrices.clone(),
+        ))
+    }
+
+    ///
+    /// Returns the transformation of each joint of this skin given the transformation of each node in the asset,
+    /// for example the result of [AnimationClip::joint_transformations] with the skeleton returned by [GltfAsset::skeleton].
+    /// The result can be given directly to [SkinnedMesh::set_joint_transformations], in which case the transformation of the [SkinnedMesh] should be the identity.
+    ///
+    pub fn joint_transformations(&self, node_transformations: &[Mat4]) -> Vec<Mat4> {
+        self.joints
+            .iter()
+            .map(|node| node_transformations[*node])
+            .collect()
+    }
+}
+
+///
+/// The content of a glTF 2.0 file (`.gltf` or `.glb`), ie. the meshes, PBR materials with textures, the node hierarchy, skins and animations.
+///
+/// Load the file and the buffers and images it references using for example [three_d_asset::io::load] or [three_d_asset::io::load_async]
+/// and then call [GltfAsset::deserialize]. Embedded buffers and images, including data URIs, are also supported.
+///
+/// The node hierarchy is represented as a [Skeleton] where each node is a joint (see [GltfAsset::skeleton]) and the animations are [AnimationClip]s
+/// animating the nodes of that skeleton, so the animated transformation of all nodes is found using [AnimationClip::joint_transformations].
+/// Cubic spline interpolated animations are sampled linearly between the keyframes and morph target animations are not supported.
+///
+#[derive(Clone, Debug, Default)]
+pub struct GltfAsset {
+    /// The meshes.
+    pub meshes: Vec<GltfMesh>,
+    /// The materials.
+    pub materials: Vec<CpuMaterial>,
+    /// The nodes.
+    pub nodes: Vec<GltfNode>,
+    /// The skins.
+    pub skins: Vec<GltfSkin>,
+    /// The animations, where each channel animates the node with the index [AnimationChannel::joint].
+    pub animations: Vec<AnimationClip>,
+}
+
+impl GltfAsset {
+    ///
+    /// Deserializes the glTF file at the given path from the given raw assets.
+    /// The external buffers and images referenced by the file are looked up relative to the path of the file and must also be in the raw assets.
+    ///
+    pub fn deserialize(
+        raw_assets: &mut RawAssets,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, RendererError> {
+        let path = path.as_ref();
+        let base_path = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        let gltf = gltf::Gltf::from_slice(raw_assets.get(path).map_err(gltf_error)?)
+            .map_err(gltf_error)?;
+
+        let mut buffers = Vec::new();
+        for buffer in gltf.buffers() {
+            let data = match buffer.source() {
+                gltf::buffer::Source::Bin => gltf
+                    .blob
+                    .clone()
+                    .ok_or_else(|| gltf_error("missing binary chunk"))?,
+                gltf::buffer::Source::Uri(uri) => load_uri(raw_assets, &base_path, uri)?.0,
+            };
+            if data.len() < buffer.length() {
+                return Err(gltf_error(format!(
+                    "buffer {} is too short, expected {} bytes but got {}",
+                    buffer.index(),
+                    buffer.length(),
+                    data.len()
+                )));
+            }
+            buffers.push(data);
+        }
+        let get_buffer = |buffer: gltf::Buffer| buffers.get(buffer.index()).map(|b| b.as_slice());
+
+        let mut textures = HashMap::new();
+        let mut materials = Vec::new();
+        for material in gltf.materials() {
+            materials.push(parse_material(
+                raw_assets,
+                &base_path,
+                &buffers,
+                &mut textures,
+                &material,
+            )?);
+        }
+
+        let mut meshes = Vec::new();
+        for mesh in gltf.meshes() {
+            let mut primitives = Vec::new();
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    continue;
+                }
+                let reader = primitive.reader(get_buffer);
+                let positions = reader
+                    .read_positions()
+                    .ok_or_else(|| gltf_error("missing vertex positions"))?
+                    .map(Vec3::from)
+                    .collect::<Vec<_>>();
+                let geometry = CpuMesh {
+                    indices: reader
+                        .read_indices()
+                        .map(|indices| Indices::U32(indices.into_u32().collect()))
+                        .unwrap_or(Indices::None),
+                    normals: reader
+                        .read_normals()
+                        .map(|normals| normals.map(Vec3::from).collect()),
+                    tangents: reader
+                        .read_tangents()
+                        .map(|tangents| tangents.map(Vec4::from).collect()),
+                    // glTF uv coordinates has the origin in the top left corner
+                    uvs: reader
+                        .read_tex_coords(0)
+                        .map(|uvs| uvs.into_f32().map(|uv| vec2(uv[0], 1.0 - uv[1])).collect()),
+                    colors: reader.read_colors(0).map(|colors| {
+                        colors
+                            .into_rgba_u8()
+                            .map(|c| Srgba::new(c[0], c[1], c[2], c[3]))
+                            .collect()
+                    }),
+                    positions: Positions::F32(positions),
+                };
+                primitives.push(GltfPrimitive {
+                    geometry,
+                    material_index: primitive.material().index(),
+                    joints: reader
+                        .read_joints(0)
+                        .map(|joints| joints.into_u16().collect()),
+                    weights: reader
+                        .read_weights(0)
+                        .map(|weights| weights.into_f32().collect()),
+                });
+            }
+            meshes.push(GltfMesh {
+                name: mesh.name().unwrap_or_default().to_string(),
+                primitives,
+            });
+        }
+
+        let mut nodes = Vec::new();
+        for node in gltf.nodes() {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            nodes.push(GltfNode {
+                name: node.name().unwrap_or_default().to_string(),
+                parent: None,
+                children: node.children().map(|child| child.index()).collect(),
+                pose: JointPose {
+                    translation: translation.into(),
+                    rotation: Quat::new(rotation[3], rotation[0], rotation[1], rotation[2]),
+                    scale: scale.into(),
+                },
+                mesh: node.mesh().map(|mesh| mesh.index()),
+                skin: node.skin().map(|skin| skin.index()),
+            });
+        }
+        for node in 0..nodes.len() {
+            for child in nodes[node].children.clone() {
+                if nodes[child].parent.is_some() {
+                    return Err(gltf_error(format!(
+                        "node {} has more than one parent",
+                        child
+                    )));
+                }
+                nodes[child].parent = Some(node);
+            }
+        }
+        for node in 0..nodes.len() {
+            let mut current = node;
+            for _ in 0..nodes.len() {
+                match nodes[current].parent {
+                    Some(parent) => current = parent,
+                    None => break,
+                }
+            }
+            if nodes[current].parent.is_some() {
+                return Err(gltf_error("the node hierarchy contains a cycle"));
+            }
+        }
+
+        let mut skins = Vec::new();
+        for skin in gltf.skins() {
+            let joints = skin.joints().map(|joint| joint.index()).collect::<Vec<_>>();
+            let inverse_bind_matrices = skin
+                .reader(get_buffer)
+                .read_inverse_bind_matrices()
+                .map(|matrices| matrices.map(Mat4::from).collect())
+                .unwrap_or_else(|| vec![Mat4::identity(); joints.len()]);
+            if inverse_bind_matrices.len() < joints.len() {
+                return Err(gltf_error(format!(
+                    "skin {} has {} inverse bind matrices but {} joints",
+                    skin.index(),
+                    inverse_bind_matrices.len(),
+                    joints.len()
+                )));
+            }
+            skins.push(GltfSkin {
+                name: skin.name().unwrap_or_default().to_string(),
+                joints,
+                inverse_bind_matrices,
+            });
+        }
+
+        let mut animations = Vec::new();
+        for animation in gltf.animations() {
+            let mut channels = Vec::new();
+            for channel in animation.channels() {
+                let reader = channel.reader(get_buffer);
+                let Some(times) = reader.read_inputs() else {
+                    continue;
+                };
+                let times = times.collect::<Vec<_>>();
+                let interpolation = channel.sampler().interpolation();
+                let mut animation_channel = AnimationChannel {
+                    joint: channel.target().node().index(),
+                    interpolation: if interpolation == gltf::animation::Interpolation::Step {
+                        AnimationInterpolation::Step
+                    } else {
+                        AnimationInterpolation::Linear
+                    },
+                    ..Default::default()
+                };
+                match reader.read_outputs() {
+                    Some(gltf::animation::util::ReadOutputs::Translations(values)) => {
+                        animation_channel.translations =
+                            Some(keyframe_values(interpolation, values.map(Vec3::from)));
+                    }
+                    Some(gltf::animation::util::ReadOutputs::Rotations(values)) => {
+                        animation_channel.rotations = Some(keyframe_values(
+                            interpolation,
+                            values.into_f32().map(|r| Quat::new(r[3], r[0], r[1], r[2])),
+                        ));
+                    }
+                    Some(gltf::animation::util::ReadOutputs::Scales(values)) => {
+                        animation_channel.scales =
+                            Some(keyframe_values(interpolation, values.map(Vec3::from)));
+                    }
+                    _ => continue,
+                }
+                let value_count = animation_channel
+                    .translations
+                    .as_ref()
+                    .map(|v| v.len())
+                    .or(animation_channel.rotations.as_ref().map(|v| v.len()))
+                    .or(animation_channel.scales.as_ref().map(|v| v.len()))
+                    .unwrap_or(0);
+                if value_count != times.len() {
+                    return Err(gltf_error(format!(
+                        "the number of keyframe values ({}) must match the number of keyframe times ({})",
+                        value_count,
+                        times.len()
+                    )));
+                }
+                animation_channel.times = times;
+                channels.push(animation_channel);
+            }
+            animations.push(AnimationClip {
+                name: animation.name().unwrap_or_default().to_string(),
+                channels,
+            });
+        }
+
+        Ok(Self {
+            meshes,
+            materials,
+            nodes,
+            skins,
+            animations,
+        })
+    }
+
+    ///
+    /// Returns the node hierarchy as a [Skeleton] where the joint with index `i` is the node with index `i` and the rest pose is the pose of the nodes in the file.
+    ///
+    pub fn skeleton(&self) -> Skeleton {
+        Skeleton::new(
+            self.nodes.iter().map(|node| node.parent).collect(),
+            self.nodes.iter().map(|node| node.pose).collect(),
+        )
+    }
+
+    ///
+    /// Returns the transformation of each node in the rest pose, ie. the local transformation of the node multiplied by the transformations of all its ancestors.
+    ///
+    pub fn node_transformations(&self) -> Vec<Mat4> {
+        let skeleton = self.skeleton();
+        skeleton.joint_transformations(skeleton.rest_pose())
+    }
+
+    ///
+    /// Returns the meshes attached to the nodes in the rest pose as a [CpuModel] which can be used to construct a [Model].
+    /// Skinned meshes are in the bind pose and the animations are not included, use [SkinnedMesh] and [GltfAsset::animations] instead.
+    ///
+    pub fn cpu_model(&self) -> CpuModel {
+        let node_transformations = self.node_transformations();
+        let mut geometries = Vec::new();
+        for (node, transformation) in self.nodes.iter().zip(node_transformations) {
+            let Some(mesh) = node.mesh.and_then(|mesh| self.meshes.get(mesh)) else {
+                continue;
+            };
+            for primitive in mesh.primitives.iter() {
+                geometries.push(three_d_asset::Primitive {
+                    name: if node.name.is_empty() {
+                        mesh.name.clone()
+                    } else {
+                        node.name.clone()
+                    },
+                    transformation,
+                    animations: Vec::new(),
+                    geometry: CpuGeometry::Triangles(primitive.geometry.clone()),
+                    material_index: primitive.material_index,
+                });
+            }
+        }
+        CpuModel {
+            name: String::new(),
+            geometries,
+            materials: self.materials.clone(),
+        }
+    }
+}
+
+fn gltf_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::GltfDeserialization(error.to_string())
+}
+
+// Returns the content of the given uri, which is either a data uri or a path relative to the glTF file, and the mime type if it is known.
+fn load_uri(
+    raw_assets: &RawAssets,
+    base_path: &Path,
+    uri: &str,
+) -> Result<(Vec<u8>, Option<String>), RendererError> {
+    if uri.starts_with("data:") {
+        let data_url =
+            data_url::DataUrl::process(uri).map_err(|e| gltf_error(format!("{:?}", e)))?;
+        let mime_type = format!(
+            "{}/{}",
+            data_url.mime_type().type_,
+            data_url.mime_type().subtype
+        );
+        let (data, _) = data_url
+            .decode_to_vec()
+            .map_err(|e| gltf_error(format!("{:?}", e)))?;
+        Ok((data, Some(mime_type)))
+    } else {
+        Ok((
+            raw_assets
+                .get(base_path.join(uri))
+                .map_err(gltf_error)?
+                .to_vec(),
+            None,
+        ))
+    }
+}
+
+fn parse_material(
+    raw_assets: &mut RawAssets,
+    base_path: &Path,
+    buffers: &[Vec<u8>],
+    textures: &mut HashMap<usize, CpuTexture>,
+    material: &gltf::Material,
+) -> Result<CpuMaterial, RendererError> {
+    let mut texture = |texture: Option<gltf::Texture>| {
+        texture
+            .map(|texture| parse_texture(raw_assets, base_path, buffers, textures, &texture))
+            .transpose()
+    };
+    let pbr = material.pbr_metallic_roughness();
+    let emissive = material.emissive_factor();
+    Ok(CpuMaterial {
+        name: material.name().unwrap_or_default().to_string(),
+        albedo: srgba_from_linear(pbr.base_color_factor()),
+        albedo_texture: texture(pbr.base_color_texture().map(|info| info.texture()))?,
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        metallic_roughness_texture: texture(
+            pbr.metallic_roughness_texture().map(|info| info.texture()),
+        )?,
+        occlusion_strength: material
+            .occlusion_texture()
+            .map(|info| info.strength())
+            .unwrap_or(1.0),
+        occlusion_texture: texture(material.occlusion_texture().map(|info| info.texture()))?,
+        normal_scale: material
+            .normal_texture()
+            .map(|info| info.scale())
+            .unwrap_or(1.0),
+        normal_texture: texture(material.normal_texture().map(|info| info.texture()))?,
+        emissive: srgba_from_linear([emissive[0], emissive[1], emissive[2], 1.0]),
+        emissive_texture: texture(material.emissive_texture().map(|info| info.texture()))?,
+        alpha_cutout: if material.alpha_mode() == gltf::material::AlphaMode::Mask {
+            Some(material.alpha_cutoff().unwrap_or(0.5))
+        } else {
+            None
+        },
+        ..Default::default()
+    })
+}
+
+// Decodes the image of the texture, once for each image, and applies the sampler of the texture.
+fn parse_texture(
+    raw_assets: &mut RawAssets,
+    base_path: &Path,
+    buffers: &[Vec<u8>],
+    textures: &mut HashMap<usize, CpuTexture>,
+    texture: &gltf::Texture,
+) -> Result<CpuTexture, RendererError> {
+    let image = texture.source();
+    let mut cpu_texture = if let Some(cpu_texture) = textures.get(&image.index()) {
+        cpu_texture.clone()
+    } else {
+        let (data, mime_type) = match image.source() {
+            gltf::image::Source::View { view, mime_type } => {
+                let buffer = &buffers[view.buffer().index()];
+                let start = view.offset();
+                let end = start + view.length();
+                (
+                    buffer
+                        .get(start..end)
+                        .ok_or_else(|| gltf_error("image buffer view out of range"))?
+                        .to_vec(),
+                    Some(mime_type.to_string()),
+                )
+            }
+            gltf::image::Source::Uri { uri, mime_type } => {
+                let (data, data_mime_type) = load_uri(raw_assets, base_path, uri)?;
+                (
+                    data,
+                    mime_type
+                        .map(|m| m.to_string())
+                        .or(data_mime_type)
+                        .or_else(|| {
+                            Path::new(uri)
+                                .extension()
+                                .map(|e| format!("image/{}", e.to_string_lossy()))
+                        }),
+                )
+            }
+        };
+        let extension = match mime_type.as_deref() {
+            Some("image/png") => "png",
+            Some("image/jpeg") | Some("image/jpg") => "jpg",
+            _ => {
+                return Err(gltf_error(format!(
+                    "unsupported image format {:?}",
+                    mime_type
+                )))
+            }
+        };
+        let image_path = base_path.join(format!("gltf_image_{}.{}", image.index(), extension));
+        raw_assets.insert(&image_path, data);
+        let mut cpu_texture: CpuTexture =
+            raw_assets.deserialize(&image_path).map_err(gltf_error)?;
+        cpu_texture.name = image.name().unwrap_or_default().to_string();
+        textures.insert(image.index(), cpu_texture.clone());
+        cpu_texture
+    };
+    let sampler = texture.sampler();
+    if let Some(mag_filter) = sampler.mag_filter() {
+        cpu_texture.mag_filter = match mag_filter {
+            gltf::texture::MagFilter::Nearest => Interpolation::Nearest,
+            gltf::texture::MagFilter::Linear => Interpolation::Linear,
+        };
+    }
+    if let Some(min_filter) = sampler.min_filter() {
+        cpu_texture.min_filter = match min_filter {
+            gltf::texture::MinFilter::Nearest
+            | gltf::texture::MinFilter::NearestMipmapNearest
+            | gltf::texture::MinFilter::NearestMipmapLinear => Interpolation::Nearest,
+            _ => Interpolation::Linear,
+        };
+    }
+    cpu_texture.wrap_s = wrapping(sampler.wrap_s());
+    cpu_texture.wrap_t = wrapping(sampler.wrap_t());
+    Ok(cpu_texture)
+}
+
+fn wrapping(mode: gltf::texture::WrappingMode) -> Wrapping {
+    match mode {
+        gltf::texture::WrappingMode::ClampToEdge => Wrapping::ClampToEdge,
+        gltf::texture::WrappingMode::MirroredRepeat => Wrapping::MirroredRepeat,
+        gltf::texture::WrappingMode::Repeat => Wrapping::Repeat,
+    }
+}
+
+// Cubic spline keyframes are stored as in-tangent, value and out-tangent, where only the value is used.
+fn keyframe_values<T>(
+    interpolation: gltf::animation::Interpolation,
+    values: impl Iterator<Item = T>,
+) -> Vec<T> {
+    if interpolation == gltf::animation::Interpolation::CubicSpline {
+        values.skip(1).step_by(3).collect()
+    } else {
+        values.collect()
+    }
+}
+
+fn srgba_from_linear(color: [f32; 4]) -> Srgba {
+    let to_srgb = |c: f32| {
+        let c = c.clamp(0.0, 1.0);
+        let c = if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        (c * 255.0).round() as u8
+    };
+    Srgba::new(
+        to_srgb(color[0]),
+        to_srgb(color[1]),
+        to_srgb(color[2]),
+        (color[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+    )
+}
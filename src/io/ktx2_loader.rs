@@ -0,0 +1,386 @@
+use crate::renderer::*;
+use std::path::Path;
+use three_d_asset::io::RawAssets;
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+
+///
+/// Deserializes the KTX2 texture (`.ktx2`) at the given path from the given raw assets and uploads it to a [Texture2D], including the mip levels stored in the file.
+/// This is the texture format used by the `KHR_texture_basisu` glTF extension.
+///
+/// - Textures in a block compressed format (BC1-BC7, ETC2, EAC and ASTC 4x4) are uploaded as they are, if the format is supported by the context, see [Context::supports_compressed_format].
+/// - Basis Universal textures in the UASTC format are transcoded to the best compressed format supported by the context, which is ASTC, BC7, ETC2 or BC1/BC3 in that order,
+///   or decompressed to RGBA if no compressed format is supported, in which case the mip maps are generated instead.
+/// - Textures in an uncompressed 8 bit, half float or float format are uploaded as a [CpuTexture] and the mip maps are generated.
+///
+/// Zstandard supercompression is supported, while ZLIB supercompression is not. Only 2D textures are supported, not 3D textures, texture arrays or cube maps.
+/// The returned [Texture2DRef] has a uv transformation which makes the texture appear with the same orientation as a texture loaded from a PNG or JPEG image,
+/// since the compressed data cannot be flipped when uploading it.
+///
+/// **Limitations:**
+/// - Basis Universal textures in the ETC1S format (BasisLZ supercompression), which is the default of most encoders, are not supported and return an error.
+///   Encode the textures with UASTC instead, for example using `toktx --encode uastc`.
+/// - Basis Universal textures cannot be transcoded on web, since the transcoder is written in C++, so loading a UASTC texture returns an error on web.
+///   On web, only KTX2 textures in a block compressed or uncompressed format can be loaded, which means that `KHR_texture_basisu` glTF textures are not supported on web.
+///   Provide a fallback texture in another format or transcode the textures to a block compressed format supported by the target devices before deployment.
+///
+pub fn deserialize_ktx2(
+    context: &Context,
+    raw_assets: &RawAssets,
+    path: impl AsRef<Path>,
+) -> Result<Texture2DRef, RendererError> {
+    let path = path.as_ref();
+    let bytes = raw_assets.get(path).map_err(ktx2_error)?;
+    if !bytes.starts_with(&IDENTIFIER) {
+        return Err(ktx2_error(
+            "the file does not start with the KTX2 identifier",
+        ));
+    }
+    let vk_format = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 20)?;
+    let height = read_u32(bytes, 24)?;
+    let depth = read_u32(bytes, 28)?;
+    let layer_count = read_u32(bytes, 32)?;
+    let face_count = read_u32(bytes, 36)?;
+    let level_count = read_u32(bytes, 40)?.max(1);
+    let supercompression_scheme = read_u32(bytes, 44)?;
+    let dfd_offset = read_u32(bytes, 48)? as usize;
+    let kvd_offset = read_u32(bytes, 56)? as usize;
+    let kvd_length = read_u32(bytes, 60)? as usize;
+    if width == 0 || height == 0 {
+        return Err(ktx2_error("the texture has zero size"));
+    }
+    if depth > 1 || layer_count > 1 || face_count != 1 {
+        return Err(ktx2_error(
+            "only 2D textures are supported, not 3D textures, texture arrays or cube maps",
+        ));
+    }
+
+    let mut levels = Vec::new();
+    for level in 0..level_count as usize {
+        let offset = read_u64(bytes, 80 + 24 * level)? as usize;
+        let length = read_u64(bytes, 88 + 24 * level)? as usize;
+        let data = bytes
+            .get(offset..offset + length)
+            .ok_or_else(|| ktx2_error(format!("mip level {} is outside the file", level)))?;
+        levels.push(match supercompression_scheme {
+            0 => data.to_vec(),
+            1 => {
+                return Err(ktx2_error(
+                    "Basis Universal textures in the ETC1S format (BasisLZ supercompression) are not supported, use UASTC instead",
+                ))
+            }
+            2 => {
+                use std::io::Read;
+                let mut cursor = std::io::Cursor::new(data);
+                let mut decoder = ruzstd::StreamingDecoder::new(&mut cursor).map_err(ktx2_error)?;
+                let mut decompressed = Vec::new();
+                decoder
+                    .read_to_end(&mut decompressed)
+                    .map_err(ktx2_error)?;
+                decompressed
+            }
+            scheme => {
+                return Err(ktx2_error(format!(
+                    "unsupported supercompression scheme {}",
+                    scheme
+                )))
+            }
+        });
+    }
+
+    // The rows are stored from top to bottom unless the orientation specifies otherwise
+    let y_up = key_value(bytes, kvd_offset, kvd_length, "KTXorientation")
+        .map_or(false, |orientation| orientation.get(1) == Some(&b'u'));
+    let name = path.to_string_lossy().to_string();
+
+    let (texture, is_flipped) = if vk_format == 0 {
+        // The color model and the channels of the first sample in the data format descriptor
+        let color_model = *bytes
+            .get(dfd_offset + 12)
+            .ok_or_else(|| ktx2_error("missing data format descriptor"))?;
+        if color_model != 166 {
+            return Err(ktx2_error(format!(
+                "unsupported color model {} of a texture without a format, only UASTC is supported",
+                color_model
+            )));
+        }
+        let channels = bytes.get(dfd_offset + 31).map_or(0, |c| c & 0x0F);
+        let has_alpha = channels == 3 || channels == 5;
+        transcode_uastc(context, name, width, height, &levels, has_alpha)?
+    } else if let Some(format) = compressed_format(vk_format) {
+        if !context.supports_compressed_format(format) {
+            return Err(ktx2_error(format!(
+                "the compressed format {:?} is not supported by the graphics backend",
+                format
+            )));
+        }
+        for (level, data) in levels.iter().enumerate() {
+            let expected_bytes =
+                format.byte_size((width >> level).max(1), (height >> level).max(1));
+            if data.len() != expected_bytes {
+                return Err(ktx2_error(format!(
+                    "invalid size of mip level {} (expected {} bytes but got {} bytes)",
+                    level,
+                    expected_bytes,
+                    data.len()
+                )));
+            }
+        }
+        let cpu_texture = compressed_texture(name, format, width, height, levels);
+        (Texture2D::new_compressed(context, &cpu_texture), false)
+    } else {
+        let data = uncompressed_data(vk_format, &levels[0])
+            .ok_or_else(|| ktx2_error(format!("unsupported format (VkFormat {})", vk_format)))?;
+        let cpu_texture = CpuTexture {
+            name,
+            data,
+            width,
+            height,
+            ..Default::default()
+        };
+        let pixel_count = width as usize * height as usize;
+        let texel_count = match &cpu_texture.data {
+            TextureData::RU8(data) => data.len(),
+            TextureData::RgU8(data) => data.len(),
+            TextureData::RgbU8(data) => data.len(),
+            TextureData::RgbaU8(data) => data.len(),
+            TextureData::RF16(data) => data.len(),
+            TextureData::RgF16(data) => data.len(),
+            TextureData::RgbF16(data) => data.len(),
+            TextureData::RgbaF16(data) => data.len(),
+            TextureData::RF32(data) => data.len(),
+            TextureData::RgF32(data) => data.len(),
+            TextureData::RgbF32(data) => data.len(),
+            TextureData::RgbaF32(data) => data.len(),
+        };
+        if texel_count != pixel_count {
+            return Err(ktx2_error(format!(
+                "invalid size of the largest mip level (expected {} pixels but got {} pixels)",
+                pixel_count, texel_count
+            )));
+        }
+        (Texture2D::new(context, &cpu_texture), true)
+    };
+
+    let mut texture = Texture2DRef::from_texture(texture);
+    // A texture constructed from a CpuTexture is flipped, so the first row is at the top, while compressed data is uploaded as it is
+    if is_flipped == y_up {
+        texture.transformation = Mat3::from_cols(
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, -1.0, 0.0),
+            vec3(0.0, 1.0, 1.0),
+        );
+    }
+    Ok(texture)
+}
+
+fn ktx2_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::Ktx2Deserialization(error.to_string())
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, RendererError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| ktx2_error("unexpected end of file"))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, RendererError> {
+    bytes
+        .get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| ktx2_error("unexpected end of file"))
+}
+
+// Returns the value of the given key in the key/value data, where each entry is padded to a multiple of four bytes.
+fn key_value<'a>(bytes: &'a [u8], offset: usize, length: usize, key: &str) -> Option<&'a [u8]> {
+    let data = bytes.get(offset..offset + length)?;
+    let mut i = 0;
+    while i + 4 <= data.len() {
+        let entry_length = u32::from_le_bytes(data[i..i + 4].try_into().unwrap()) as usize;
+        let entry = data.get(i + 4..i + 4 + entry_length)?;
+        let separator = entry.iter().position(|b| *b == 0)?;
+        if &entry[..separator] == key.as_bytes() {
+            return Some(&entry[separator + 1..]);
+        }
+        i += 4 + (entry_length + 3) / 4 * 4;
+    }
+    None
+}
+
+// Returns the compressed format of the given VkFormat, where the sRGB formats are mapped to the corresponding linear format since the materials decode sRGB colors in the shader.
+fn compressed_format(vk_format: u32) -> Option<CompressedFormat> {
+    Some(match vk_format {
+        131 | 132 => CompressedFormat::Bc1Rgb,
+        133 | 134 => CompressedFormat::Bc1Rgba,
+        135 | 136 => CompressedFormat::Bc2Rgba,
+        137 | 138 => CompressedFormat::Bc3Rgba,
+        139 => CompressedFormat::Bc4R,
+        141 => CompressedFormat::Bc5Rg,
+        143 => CompressedFormat::Bc6hRgbUfloat,
+        145 | 146 => CompressedFormat::Bc7Rgba,
+        147 | 148 => CompressedFormat::Etc2Rgb,
+        151 | 152 => CompressedFormat::Etc2Rgba,
+        153 => CompressedFormat::EacR11,
+        155 => CompressedFormat::EacRg11,
+        157 | 158 => CompressedFormat::Astc4x4Rgba,
+        _ => return None,
+    })
+}
+
+// Returns the pixels of the given uncompressed VkFormat.
+fn uncompressed_data(vk_format: u32, data: &[u8]) -> Option<TextureData> {
+    let halfs = || {
+        data.chunks_exact(2)
+            .map(|c| f16::from_bits(u16::from_le_bytes([c[0], c[1]])))
+            .collect::<Vec<_>>()
+    };
+    let floats = || {
+        data.chunks_exact(4)
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+            .collect::<Vec<_>>()
+    };
+    Some(match vk_format {
+        9 => TextureData::RU8(data.to_vec()),
+        16 => TextureData::RgU8(group(data.to_vec())),
+        23 | 29 => TextureData::RgbU8(group(data.to_vec())),
+        37 | 43 => TextureData::RgbaU8(group(data.to_vec())),
+        76 => TextureData::RF16(halfs()),
+        83 => TextureData::RgF16(group(halfs())),
+        90 => TextureData::RgbF16(group(halfs())),
+        97 => TextureData::RgbaF16(group(halfs())),
+        100 => TextureData::RF32(floats()),
+        103 => TextureData::RgF32(group(floats())),
+        106 => TextureData::RgbF32(group(floats())),
+        109 => TextureData::RgbaF32(group(floats())),
+        _ => return None,
+    })
+}
+
+fn group<T: Copy, const N: usize>(values: Vec<T>) -> Vec<[T; N]> {
+    values
+        .chunks_exact(N)
+        .map(|c| c.try_into().unwrap())
+        .collect()
+}
+
+fn compressed_texture(
+    name: String,
+    format: CompressedFormat,
+    width: u32,
+    height: u32,
+    mip_levels: Vec<Vec<u8>>,
+) -> CpuCompressedTexture {
+    CpuCompressedTexture {
+        name,
+        format,
+        width,
+        height,
+        mip_map_filter: (mip_levels.len() > 1).then_some(Interpolation::Linear),
+        mip_levels,
+        min_filter: Interpolation::Linear,
+        mag_filter: Interpolation::Linear,
+        wrap_s: Wrapping::Repeat,
+        wrap_t: Wrapping::Repeat,
+    }
+}
+
+// Transcodes the UASTC mip levels to the best compressed format supported by the context and returns the texture and whether or not it was flipped when uploaded.
+#[cfg(not(target_arch = "wasm32"))]
+fn transcode_uastc(
+    context: &Context,
+    name: String,
+    width: u32,
+    height: u32,
+    levels: &[Vec<u8>],
+    has_alpha: bool,
+) -> Result<(Texture2D, bool), RendererError> {
+    use basis_universal::{
+        DecodeFlags, LowLevelUastcTranscoder, SliceParametersUastc, TranscoderBlockFormat,
+    };
+    basis_universal::transcoder_init();
+    let transcoder = LowLevelUastcTranscoder::new();
+    let transcode = |level: usize, block_format: TranscoderBlockFormat| {
+        let level_width = (width >> level).max(1);
+        let level_height = (height >> level).max(1);
+        transcoder
+            .transcode_slice(
+                &levels[level],
+                SliceParametersUastc {
+                    num_blocks_x: (level_width + 3) / 4,
+                    num_blocks_y: (level_height + 3) / 4,
+                    has_alpha,
+                    original_width: level_width,
+                    original_height: level_height,
+                },
+                DecodeFlags::HIGH_QUALITY,
+                block_format,
+            )
+            .map_err(|e| {
+                ktx2_error(format!(
+                    "failed transcoding mip level {} from UASTC to {:?}: {:?}",
+                    level, block_format, e
+                ))
+            })
+    };
+
+    let candidates = if has_alpha {
+        [
+            (
+                CompressedFormat::Astc4x4Rgba,
+                TranscoderBlockFormat::ASTC_4x4,
+            ),
+            (CompressedFormat::Bc7Rgba, TranscoderBlockFormat::BC7),
+            (CompressedFormat::Etc2Rgba, TranscoderBlockFormat::ETC2_RGBA),
+            (CompressedFormat::Bc3Rgba, TranscoderBlockFormat::BC3),
+        ]
+    } else {
+        [
+            (
+                CompressedFormat::Astc4x4Rgba,
+                TranscoderBlockFormat::ASTC_4x4,
+            ),
+            (CompressedFormat::Bc7Rgba, TranscoderBlockFormat::BC7),
+            (CompressedFormat::Etc2Rgb, TranscoderBlockFormat::ETC1),
+            (CompressedFormat::Bc1Rgb, TranscoderBlockFormat::BC1),
+        ]
+    };
+    if let Some((format, block_format)) = candidates
+        .into_iter()
+        .find(|(format, _)| context.supports_compressed_format(*format))
+    {
+        let mip_levels = (0..levels.len())
+            .map(|level| transcode(level, block_format))
+            .collect::<Result<Vec<_>, _>>()?;
+        let cpu_texture = compressed_texture(name, format, width, height, mip_levels);
+        Ok((Texture2D::new_compressed(context, &cpu_texture), false))
+    } else {
+        // Decompress the largest mip level, the mip maps are generated from it
+        let data = transcode(0, TranscoderBlockFormat::RGBA32)?;
+        let cpu_texture = CpuTexture {
+            name,
+            data: TextureData::RgbaU8(group(data)),
+            width,
+            height,
+            ..Default::default()
+        };
+        Ok((Texture2D::new(context, &cpu_texture), true))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn transcode_uastc(
+    _context: &Context,
+    _name: String,
+    _width: u32,
+    _height: u32,
+    _levels: &[Vec<u8>],
+    _has_alpha: bool,
+) -> Result<(Texture2D, bool), RendererError> {
+    Err(ktx2_error(
+        "transcoding of Basis Universal textures is not supported on web, use a KTX2 texture in a block compressed format instead",
+    ))
+}
@@ -0,0 +1,406 @@
+use crate::renderer::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use three_d_asset::io::RawAssets;
+
+///
+/// Deserializes the OBJ file (`.obj`) at the given path from the given raw assets into a [CpuModel].
+///
+/// Each object (`o`) and group (`g`) in the file becomes a separate primitive and, if the material is switched (`usemtl`) inside an object or group,
+/// the faces with each material become separate primitives as well. Negative (relative) vertex indices are supported and polygons are triangulated.
+/// The materials are read from the material libraries (`mtllib`) and the textures referenced by the materials (`map_Kd`, `map_Ke`, `map_Bump`, `bump` and `norm`)
+/// are looked up relative to the material library. Missing material libraries and textures are ignored,
+/// so use [obj_dependencies] to get the paths that should be loaded or use [load_obj_async] to load the file and all its dependencies.
+///
+pub fn deserialize_obj(
+    raw_assets: &mut RawAssets,
+    path: impl AsRef<Path>,
+) -> Result<CpuModel, RendererError> {
+    let path = path.as_ref();
+    let obj = parse_obj(raw_assets, path)?;
+
+    let mut materials = Vec::new();
+    let mut textures: HashMap<PathBuf, CpuTexture> = HashMap::new();
+    for library in obj.material_libraries.iter() {
+        let Ok(bytes) = raw_assets.get(library) else {
+            continue;
+        };
+        let base_path = library
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default();
+        for mtl in parse_mtl(&String::from_utf8_lossy(bytes), &base_path) {
+            materials.push(mtl);
+        }
+    }
+    let materials = materials
+        .into_iter()
+        .map(|mtl| {
+            let mut texture = |path: &Option<PathBuf>| {
+                let path = path.as_ref()?;
+                if let Some(cpu_texture) = textures.get(path) {
+                    return Some(cpu_texture.clone());
+                }
+                let cpu_texture: CpuTexture = raw_assets.deserialize(path).ok()?;
+                textures.insert(path.clone(), cpu_texture.clone());
+                Some(cpu_texture)
+            };
+            CpuMaterial {
+                albedo_texture: texture(&mtl.albedo_texture),
+                normal_texture: texture(&mtl.normal_texture),
+                emissive_texture: texture(&mtl.emissive_texture),
+                ..mtl.material
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut geometries = Vec::new();
+    for mesh in obj.meshes {
+        if mesh.indices.is_empty() {
+            continue;
+        }
+        let material_index = mesh
+            .material
+            .as_ref()
+            .and_then(|name| materials.iter().position(|m| &m.name == name));
+        let has_normals = mesh.normals.iter().all(|n| n.is_some());
+        let has_uvs = mesh.uvs.iter().any(|uv| uv.is_some());
+        let has_colors = mesh.colors.iter().any(|c| c.is_some());
+        let mut geometry = CpuMesh {
+            positions: Positions::F32(mesh.positions),
+            indices: Indices::U32(mesh.indices),
+            normals: has_normals.then(|| mesh.normals.into_iter().flatten().collect()),
+            uvs: has_uvs.then(|| {
+                mesh.uvs
+                    .into_iter()
+                    .map(|uv| uv.unwrap_or(vec2(0.0, 0.0)))
+                    .collect()
+            }),
+            colors: has_colors.then(|| {
+                mesh.colors
+                    .into_iter()
+                    .map(|c| c.unwrap_or(Srgba::WHITE))
+                    .collect()
+            }),
+            ..Default::default()
+        };
+        if !has_normals {
+            geometry.compute_normals();
+        }
+        geometries.push(three_d_asset::Primitive {
+            name: mesh.name,
+            transformation: Mat4::identity(),
+            animations: Vec::new(),
+            geometry: CpuGeometry::Triangles(geometry),
+            material_index,
+        });
+    }
+    Ok(CpuModel {
+        name: path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        geometries,
+        materials,
+    })
+}
+
+///
+/// Returns the paths of the files referenced by the OBJ file at the given path, which must be in the given raw assets, ie. the material libraries
+/// and, for the material libraries that are also in the raw assets, the textures. Call this again after loading the material libraries to get the paths of the textures.
+///
+pub fn obj_dependencies(
+    raw_assets: &RawAssets,
+    path: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>, RendererError> {
+    let obj = parse_obj(raw_assets, path.as_ref())?;
+    let mut dependencies = Vec::new();
+    for library in obj.material_libraries {
+        if let Ok(bytes) = raw_assets.get(&library) {
+            let base_path = library
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default();
+            for mtl in parse_mtl(&String::from_utf8_lossy(bytes), &base_path) {
+                dependencies.extend(
+                    [mtl.albedo_texture, mtl.normal_texture, mtl.emissive_texture]
+                        .into_iter()
+                        .flatten(),
+                );
+            }
+        }
+        dependencies.push(library);
+    }
+    dependencies.sort();
+    dependencies.dedup();
+    Ok(dependencies)
+}
+
+///
+/// Loads the OBJ file at the given path or url together with its material libraries and textures using [three_d_asset::io::load_async]
+/// and deserializes it, see [deserialize_obj]. The dependencies are loaded one at a time and the ones that fail to load are skipped,
+/// like in [deserialize_obj], so only failing to load the OBJ file itself is an error.
+///
+pub async fn load_obj_async(path: impl AsRef<Path>) -> Result<CpuModel, RendererError> {
+    let path = path.as_ref();
+    let mut raw_assets = three_d_asset::io::load_async(&[path])
+        .await
+        .map_err(obj_error)?;
+    let mut failed = Vec::new();
+    // The textures are only known when the material libraries are loaded
+    for _ in 0..2 {
+        for dependency in obj_dependencies(&raw_assets, path)? {
+            if raw_assets.get(&dependency).is_ok() || failed.contains(&dependency) {
+                continue;
+            }
+            let loaded = three_d_asset::io::load_async(&[&dependency])
+                .await
+                .and_then(|mut loaded| loaded.remove(&dependency));
+            match loaded {
+                Ok(bytes) => {
+                    raw_assets.insert(dependency, bytes);
+                }
+                Err(_) => failed.push(dependency),
+            }
+        }
+    }
+    deserialize_obj(&mut raw_assets, path)
+}
+
+fn obj_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::ObjDeserialization(error.to_string())
+}
+
+// The content of an OBJ file where the faces are split into meshes with one group and material each.
+struct Obj {
+    material_libraries: Vec<PathBuf>,
+    meshes: Vec<ObjMesh>,
+}
+
+#[derive(Default)]
+struct ObjMesh {
+    name: String,
+    material: Option<String>,
+    positions: Vec<Vec3>,
+    normals: Vec<Option<Vec3>>,
+    uvs: Vec<Option<Vec2>>,
+    colors: Vec<Option<Srgba>>,
+    indices: Vec<u32>,
+    // The index of each vertex given by the position, uv and normal index in the file
+    vertices: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+}
+
+fn parse_obj(raw_assets: &RawAssets, path: &Path) -> Result<Obj, RendererError> {
+    let source = String::from_utf8_lossy(raw_assets.get(path).map_err(obj_error)?).to_string();
+    let base_path = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut colors: Vec<Option<Srgba>> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<Vec2> = Vec::new();
+    let mut material_libraries = Vec::new();
+    let mut meshes = vec![ObjMesh::default()];
+    let mut current = 0;
+    let mut group = String::new();
+    let mut material = None;
+
+    for (line_number, line) in source.lines().enumerate() {
+        let error = |message: &str| obj_error(format!("{} on line {}", message, line_number + 1));
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let values = tokens.clone().collect::<Vec<_>>();
+        let floats = || {
+            values
+                .iter()
+                .map(|v| v.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| error("invalid number"))
+        };
+        match keyword {
+            "v" => {
+                let v = floats()?;
+                if v.len() < 3 {
+                    return Err(error("a vertex must have at least three coordinates"));
+                }
+                positions.push(vec3(v[0], v[1], v[2]));
+                // Vertex colors are given after the position as an extension to the format
+                colors.push((v.len() >= 6).then(|| {
+                    let c = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+                    Srgba::new_opaque(c(v[3]), c(v[4]), c(v[5]))
+                }));
+            }
+            "vn" => {
+                let v = floats()?;
+                if v.len() < 3 {
+                    return Err(error("a normal must have three coordinates"));
+                }
+                normals.push(vec3(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let v = floats()?;
+                if v.is_empty() {
+                    return Err(error(
+                        "a texture coordinate must have at least one coordinate",
+                    ));
+                }
+                uvs.push(vec2(v[0], v.get(1).copied().unwrap_or(0.0)));
+            }
+            "f" => {
+                let mut face = Vec::new();
+                for value in values.iter() {
+                    let mut indices = value.split('/');
+                    let mut index = |count: usize| -> Result<Option<usize>, RendererError> {
+                        match indices.next() {
+                            None | Some("") => Ok(None),
+                            Some(index) => {
+                                let index = index
+                                    .parse::<i64>()
+                                    .map_err(|_| error("invalid vertex index"))?;
+                                // Negative indices are relative to the end of the list of vertices read so far
+                                let resolved = if index > 0 {
+                                    index - 1
+                                } else {
+                                    count as i64 + index
+                                };
+                                if index == 0 || resolved < 0 || resolved >= count as i64 {
+                                    return Err(error("vertex index out of range"));
+                                }
+                                Ok(Some(resolved as usize))
+                            }
+                        }
+                    };
+                    let position =
+                        index(positions.len())?.ok_or_else(|| error("missing vertex index"))?;
+                    let uv = index(uvs.len())?;
+                    let normal = index(normals.len())?;
+                    let mesh = &mut meshes[current];
+                    let vertex =
+                        *mesh
+                            .vertices
+                            .entry((position, uv, normal))
+                            .or_insert_with(|| {
+                                mesh.positions.push(positions[position]);
+                                mesh.uvs.push(uv.map(|i| uvs[i]));
+                                mesh.normals.push(normal.map(|i| normals[i]));
+                                mesh.colors.push(colors[position]);
+                                mesh.positions.len() as u32 - 1
+                            });
+                    face.push(vertex);
+                }
+                if face.len() < 3 {
+                    return Err(error("a face must have at least three vertices"));
+                }
+                for i in 1..face.len() - 1 {
+                    meshes[current]
+                        .indices
+                        .extend([face[0], face[i], face[i + 1]]);
+                }
+            }
+            "o" | "g" | "usemtl" => {
+                if keyword == "usemtl" {
+                    material = (!values.is_empty()).then(|| values.join(" "));
+                } else {
+                    group = values.join(" ");
+                }
+                current = meshes
+                    .iter()
+                    .position(|m| m.name == group && m.material == material)
+                    .unwrap_or_else(|| {
+                        meshes.push(ObjMesh {
+                            name: group.clone(),
+                            material: material.clone(),
+                            ..Default::default()
+                        });
+                        meshes.len() - 1
+                    });
+            }
+            "mtllib" => {
+                material_libraries.extend(values.iter().map(|v| base_path.join(v)));
+            }
+            _ => {}
+        }
+    }
+    Ok(Obj {
+        material_libraries,
+        meshes,
+    })
+}
+
+// A material in a material library with the paths to its textures.
+struct Mtl {
+    material: CpuMaterial,
+    albedo_texture: Option<PathBuf>,
+    normal_texture: Option<PathBuf>,
+    emissive_texture: Option<PathBuf>,
+}
+
+fn parse_mtl(source: &str, base_path: &Path) -> Vec<Mtl> {
+    let mut materials: Vec<Mtl> = Vec::new();
+    for line in source.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let values = tokens.collect::<Vec<_>>();
+        if keyword == "newmtl" {
+            materials.push(Mtl {
+                material: CpuMaterial {
+                    name: values.join(" "),
+                    ..Default::default()
+                },
+                albedo_texture: None,
+                normal_texture: None,
+                emissive_texture: None,
+            });
+            continue;
+        }
+        let Some(mtl) = materials.last_mut() else {
+            continue;
+        };
+        let floats = values
+            .iter()
+            .filter_map(|v| v.parse::<f32>().ok())
+            .collect::<Vec<_>>();
+        let color = |alpha: u8| {
+            let c = |i: usize| {
+                (floats.get(i).copied().unwrap_or(0.0).clamp(0.0, 1.0) * 255.0).round() as u8
+            };
+            Srgba::new(c(0), c(1), c(2), alpha)
+        };
+        // The texture file is the last value, the others are options
+        let texture = || values.last().map(|file| base_path.join(file));
+        let material = &mut mtl.material;
+        match keyword {
+            "Kd" if !floats.is_empty() => material.albedo = color(material.albedo.a),
+            "Ke" if !floats.is_empty() => material.emissive = color(255),
+            "d" if !floats.is_empty() => {
+                material.albedo.a = (floats[0].clamp(0.0, 1.0) * 255.0).round() as u8
+            }
+            "Tr" if !floats.is_empty() => {
+                material.albedo.a = ((1.0 - floats[0].clamp(0.0, 1.0)) * 255.0).round() as u8
+            }
+            // Convert the specular exponent of the Phong model to roughness
+            "Ns" if !floats.is_empty() => {
+                material.roughness = (2.0 / (floats[0].max(0.0) + 2.0)).sqrt()
+            }
+            "Pr" if !floats.is_empty() => material.roughness = floats[0],
+            "Pm" if !floats.is_empty() => material.metallic = floats[0],
+            "map_Kd" => mtl.albedo_texture = texture(),
+            "map_Ke" => mtl.emissive_texture = texture(),
+            "map_Bump" | "map_bump" | "bump" | "norm" => {
+                mtl.normal_texture = texture();
+                if let Some(i) = values.iter().position(|v| *v == "-bm") {
+                    if let Some(scale) = values.get(i + 1).and_then(|v| v.parse().ok()) {
+                        material.normal_scale = scale;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    materials
+}
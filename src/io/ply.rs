@@ -0,0 +1,402 @@
+use crate::renderer::*;
+use std::path::Path;
+use three_d_asset::io::RawAssets;
+
+///
+/// Deserializes the PLY file (`.ply`) at the given path from the given raw assets, where ASCII, binary little endian and binary big endian PLY files are supported.
+///
+/// The vertex positions (`x`, `y`, `z`), normals (`nx`, `ny`, `nz`), colors (`red`, `green`, `blue`, `alpha`) and uv coordinates (`u`, `v`, `s`, `t`, `texture_u` or `texture_v`)
+/// are read from the `vertex` element and the polygons in the `face` element are triangulated. The normals are computed if they are missing in the file.
+/// Other elements and properties are ignored.
+///
+pub fn deserialize_ply(
+    raw_assets: &RawAssets,
+    path: impl AsRef<Path>,
+) -> Result<CpuMesh, RendererError> {
+    let bytes = raw_assets.get(path).map_err(ply_error)?;
+    let (format, elements, body) = parse_header(bytes)?;
+    let mut reader = PlyReader {
+        format,
+        data: &bytes[body..],
+        position: 0,
+        tokens: std::str::from_utf8(&bytes[body..])
+            .unwrap_or("")
+            .split_ascii_whitespace(),
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+    for element in elements.iter() {
+        for _ in 0..element.count {
+            let mut position = vec3(0.0, 0.0, 0.0);
+            let mut normal = vec3(0.0, 0.0, 0.0);
+            let mut color = Srgba::WHITE;
+            let mut uv = vec2(0.0, 0.0);
+            for property in element.properties.iter() {
+                match property {
+                    PlyProperty::Scalar { name, data_type } => {
+                        let value = reader.read(*data_type)?;
+                        if element.name != "vertex" {
+                            continue;
+                        }
+                        // Colors are either stored as bytes or as floats in the range [0, 1]
+                        let color_value = || match data_type {
+                            PlyType::F32 | PlyType::F64 => (value * 255.0).clamp(0.0, 255.0) as u8,
+                            _ => value as u8,
+                        };
+                        match name.as_str() {
+                            "x" => position.x = value as f32,
+                            "y" => position.y = value as f32,
+                            "z" => position.z = value as f32,
+                            "nx" => normal.x = value as f32,
+                            "ny" => normal.y = value as f32,
+                            "nz" => normal.z = value as f32,
+                            "red" | "r" | "diffuse_red" => color.r = color_value(),
+                            "green" | "g" | "diffuse_green" => color.g = color_value(),
+                            "blue" | "b" | "diffuse_blue" => color.b = color_value(),
+                            "alpha" | "a" => color.a = color_value(),
+                            "u" | "s" | "texture_u" | "texture_s" => uv.x = value as f32,
+                            "v" | "t" | "texture_v" | "texture_t" => uv.y = value as f32,
+                            _ => {}
+                        }
+                    }
+                    PlyProperty::List {
+                        name,
+                        count_type,
+                        data_type,
+                    } => {
+                        let count = reader.read(*count_type)? as usize;
+                        let mut polygon = Vec::with_capacity(count);
+                        for _ in 0..count {
+                            polygon.push(reader.read(*data_type)? as u32);
+                        }
+                        if element.name == "face"
+                            && (name == "vertex_indices" || name == "vertex_index")
+                        {
+                            for i in 2..polygon.len() {
+                                indices.extend([polygon[0], polygon[i - 1], polygon[i]]);
+                            }
+                        }
+                    }
+                }
+            }
+            if element.name == "vertex" {
+                positions.push(position);
+                normals.push(normal);
+                colors.push(color);
+                uvs.push(uv);
+            }
+        }
+    }
+
+    if indices.is_empty() {
+        return Err(ply_error("the file does not contain any faces"));
+    }
+    if let Some(index) = indices.iter().find(|i| **i as usize >= positions.len()) {
+        return Err(ply_error(format!(
+            "the vertex index {} is out of range",
+            index
+        )));
+    }
+    let has_property = |names: &[&str]| {
+        elements
+            .iter()
+            .filter(|e| e.name == "vertex")
+            .flat_map(|e| e.properties.iter())
+            .any(
+                |p| matches!(p, PlyProperty::Scalar { name, .. } if names.contains(&name.as_str())),
+            )
+    };
+    let mut cpu_mesh = CpuMesh {
+        positions: Positions::F32(positions),
+        indices: Indices::U32(indices),
+        normals: has_property(&["nx", "ny", "nz"]).then_some(normals),
+        colors: has_property(&[
+            "red",
+            "r",
+            "diffuse_red",
+            "green",
+            "g",
+            "diffuse_green",
+            "blue",
+            "b",
+            "diffuse_blue",
+        ])
+        .then_some(colors),
+        uvs: has_property(&[
+            "u",
+            "s",
+            "texture_u",
+            "texture_s",
+            "v",
+            "t",
+            "texture_v",
+            "texture_t",
+        ])
+        .then_some(uvs),
+        ..Default::default()
+    };
+    if cpu_mesh.normals.is_none() {
+        cpu_mesh.compute_normals();
+    }
+    Ok(cpu_mesh)
+}
+
+///
+/// Serializes the given mesh into a PLY file (`.ply`) with the given encoding, which can be saved using for example [three_d_asset::io::save].
+/// The positions, normals, colors and uv coordinates of the mesh are saved as vertex properties and the triangles as faces.
+///
+pub fn serialize_ply(
+    cpu_mesh: &CpuMesh,
+    path: impl AsRef<Path>,
+    encoding: MeshEncoding,
+) -> RawAssets {
+    let positions = cpu_mesh.positions.to_f32();
+    let indices = match &cpu_mesh.indices {
+        Indices::None => (0..positions.len() as u32).collect::<Vec<_>>(),
+        Indices::U8(indices) => indices.iter().map(|i| *i as u32).collect(),
+        Indices::U16(indices) => indices.iter().map(|i| *i as u32).collect(),
+        Indices::U32(indices) => indices.clone(),
+    };
+    let face_count = indices.len() / 3;
+
+    let mut header = format!(
+        "ply\nformat {} 1.0\ncomment Created by three-d\nelement vertex {}\nproperty float x\nproperty float y\nproperty float z\n",
+        match encoding {
+            MeshEncoding::Ascii => "ascii",
+            MeshEncoding::Binary => "binary_little_endian",
+        },
+        positions.len()
+    );
+    if cpu_mesh.normals.is_some() {
+        header.push_str("property float nx\nproperty float ny\nproperty float nz\n");
+    }
+    if cpu_mesh.colors.is_some() {
+        header.push_str(
+            "property uchar red\nproperty uchar green\nproperty uchar blue\nproperty uchar alpha\n",
+        );
+    }
+    if cpu_mesh.uvs.is_some() {
+        header.push_str("property float s\nproperty float t\n");
+    }
+    header.push_str(&format!(
+        "element face {}\nproperty list uchar uint vertex_indices\nend_header\n",
+        face_count
+    ));
+
+    let mut bytes = header.into_bytes();
+    for i in 0..positions.len() {
+        let mut floats = vec![positions[i].x, positions[i].y, positions[i].z];
+        if let Some(normals) = &cpu_mesh.normals {
+            floats.extend([normals[i].x, normals[i].y, normals[i].z]);
+        }
+        let color = cpu_mesh.colors.as_ref().map(|colors| colors[i]);
+        let uv = cpu_mesh.uvs.as_ref().map(|uvs| uvs[i]);
+        match encoding {
+            MeshEncoding::Ascii => {
+                let mut line = floats
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if let Some(c) = color {
+                    line.push_str(&format!(" {} {} {} {}", c.r, c.g, c.b, c.a));
+                }
+                if let Some(uv) = uv {
+                    line.push_str(&format!(" {} {}", uv.x, uv.y));
+                }
+                line.push('\n');
+                bytes.extend(line.into_bytes());
+            }
+            MeshEncoding::Binary => {
+                bytes.extend(floats.iter().flat_map(|f| f.to_le_bytes()));
+                if let Some(c) = color {
+                    bytes.extend([c.r, c.g, c.b, c.a]);
+                }
+                if let Some(uv) = uv {
+                    bytes.extend([uv.x, uv.y].iter().flat_map(|f| f.to_le_bytes()));
+                }
+            }
+        }
+    }
+    for triangle in indices.chunks_exact(3) {
+        match encoding {
+            MeshEncoding::Ascii => bytes.extend(
+                format!("3 {} {} {}\n", triangle[0], triangle[1], triangle[2]).into_bytes(),
+            ),
+            MeshEncoding::Binary => {
+                bytes.push(3);
+                bytes.extend(triangle.iter().flat_map(|i| i.to_le_bytes()));
+            }
+        }
+    }
+
+    let mut raw_assets = RawAssets::new();
+    raw_assets.insert(path, bytes);
+    raw_assets
+}
+
+fn ply_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::PlyDeserialization(error.to_string())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PlyType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> Result<Self, RendererError> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return Err(ply_error(format!("unknown property type '{}'", name))),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+enum PlyProperty {
+    Scalar {
+        name: String,
+        data_type: PlyType,
+    },
+    List {
+        name: String,
+        count_type: PlyType,
+        data_type: PlyType,
+    },
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+// Returns the format, the elements and the byte offset of the body.
+fn parse_header(bytes: &[u8]) -> Result<(PlyFormat, Vec<PlyElement>, usize), RendererError> {
+    if !bytes.starts_with(b"ply") {
+        return Err(ply_error("the file does not start with 'ply'"));
+    }
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    let mut offset = 0;
+    loop {
+        let end = bytes[offset..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map(|i| offset + i)
+            .ok_or_else(|| ply_error("the header is missing 'end_header'"))?;
+        let line = String::from_utf8_lossy(&bytes[offset..end]);
+        offset = end + 1;
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let invalid = || ply_error(format!("invalid header line '{}'", line.trim()));
+        match tokens.as_slice() {
+            ["format", f, ..] => {
+                format = Some(match *f {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(ply_error(format!("unknown format '{}'", f))),
+                })
+            }
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| invalid())?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, data_type, name] => elements
+                .last_mut()
+                .ok_or_else(invalid)?
+                .properties
+                .push(PlyProperty::List {
+                    name: name.to_string(),
+                    count_type: PlyType::parse(count_type)?,
+                    data_type: PlyType::parse(data_type)?,
+                }),
+            ["property", data_type, name] => elements
+                .last_mut()
+                .ok_or_else(invalid)?
+                .properties
+                .push(PlyProperty::Scalar {
+                    name: name.to_string(),
+                    data_type: PlyType::parse(data_type)?,
+                }),
+            ["end_header"] => break,
+            _ => {}
+        }
+    }
+    let format = format.ok_or_else(|| ply_error("the header is missing the format"))?;
+    Ok((format, elements, offset))
+}
+
+struct PlyReader<'a> {
+    format: PlyFormat,
+    data: &'a [u8],
+    position: usize,
+    tokens: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl PlyReader<'_> {
+    fn read(&mut self, data_type: PlyType) -> Result<f64, RendererError> {
+        if self.format == PlyFormat::Ascii {
+            return self
+                .tokens
+                .next()
+                .and_then(|t| t.parse::<f64>().ok())
+                .ok_or_else(|| ply_error("unexpected end of data"));
+        }
+        let size = data_type.size();
+        let mut bytes = self
+            .data
+            .get(self.position..self.position + size)
+            .ok_or_else(|| ply_error("unexpected end of data"))?
+            .to_vec();
+        self.position += size;
+        if self.format == PlyFormat::BinaryBigEndian {
+            bytes.reverse();
+        }
+        Ok(match data_type {
+            PlyType::I8 => bytes[0] as i8 as f64,
+            PlyType::U8 => bytes[0] as f64,
+            PlyType::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            PlyType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            PlyType::I32 => i32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            PlyType::U32 => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            PlyType::F32 => f32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            PlyType::F64 => f64::from_le_bytes(bytes[..8].try_into().unwrap()),
+        })
+    }
+}
@@ -0,0 +1,403 @@
+use crate::renderer::*;
+use std::path::Path;
+use three_d_asset::io::RawAssets;
+
+///
+/// Deserializes the point cloud file at the given path from the given raw assets, where the format is given by the file extension:
+/// - LAS (`.las`): The point data record formats 0 to 10 are supported, including RGB colors. Compressed LAZ files are not supported.
+/// - PCD (`.pcd`): ASCII, binary and binary compressed data with the `x`, `y` and `z` fields and optionally a packed `rgb` or `rgba` field.
+/// - XYZ (`.xyz`, `.txt` or `.pts`): One point per line given by whitespace or comma separated coordinates, optionally followed by a color
+///   given either as integers in the range `[0, 255]` or as floats in the range `[0, 1]`.
+///
+/// The positions are given in double precision, since LAS files often contain geographic coordinates, see [Points] for how to render the point cloud.
+///
+pub fn deserialize_point_cloud(
+    raw_assets: &RawAssets,
+    path: impl AsRef<Path>,
+) -> Result<PointCloud, RendererError> {
+    let path = path.as_ref();
+    let bytes = raw_assets.get(path).map_err(point_cloud_error)?;
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let (positions, colors) = match extension.as_str() {
+        "las" => parse_las(bytes)?,
+        "pcd" => parse_pcd(bytes)?,
+        "xyz" | "txt" | "pts" => parse_xyz(bytes),
+        "laz" => return Err(point_cloud_error("compressed LAZ files are not supported")),
+        _ => {
+            return Err(point_cloud_error(format!(
+                "unknown file extension '{}'",
+                extension
+            )))
+        }
+    };
+    if positions.is_empty() {
+        return Err(point_cloud_error("the file does not contain any points"));
+    }
+    Ok(PointCloud {
+        positions: Positions::F64(positions),
+        colors,
+        ..Default::default()
+    })
+}
+
+fn point_cloud_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::PointCloudDeserialization(error.to_string())
+}
+
+type PointData = (Vec<Vector3<f64>>, Option<Vec<Srgba>>);
+
+fn parse_las(bytes: &[u8]) -> Result<PointData, RendererError> {
+    if bytes.len() < 227 || !bytes.starts_with(b"LASF") {
+        return Err(point_cloud_error("invalid LAS header"));
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+    let f64_at = |i: usize| f64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+
+    let header_size = u16_at(94) as usize;
+    let offset = u32_at(96) as usize;
+    if bytes[104] & 0b1100_0000 != 0 {
+        return Err(point_cloud_error("compressed LAZ data is not supported"));
+    }
+    let format = bytes[104] & 0b0011_1111;
+    let record_length = u16_at(105) as usize;
+    let mut count = u32_at(107) as usize;
+    if count == 0 && header_size >= 255 && bytes.len() >= 255 {
+        // LAS 1.4 stores the number of points as a 64 bit integer
+        count = usize::try_from(u64::from_le_bytes(bytes[247..255].try_into().unwrap()))
+            .map_err(|_| point_cloud_error("the number of points is too large"))?;
+    }
+    let scale = vec3(f64_at(131), f64_at(139), f64_at(147));
+    let translation = vec3(f64_at(155), f64_at(163), f64_at(171));
+    let color_offset = match format {
+        2 => Some(20),
+        3 | 5 => Some(28),
+        7 | 8 | 10 => Some(30),
+        0 | 1 | 4 | 6 | 9 => None,
+        _ => {
+            return Err(point_cloud_error(format!(
+                "unsupported point data record format {}",
+                format
+            )))
+        }
+    };
+    if record_length < 12 || color_offset.is_some_and(|c| record_length < c + 6) {
+        return Err(point_cloud_error("invalid point data record length"));
+    }
+    let end = count
+        .checked_mul(record_length)
+        .and_then(|length| length.checked_add(offset))
+        .ok_or_else(|| point_cloud_error("the number of points is too large"))?;
+    if bytes.len() < end {
+        return Err(point_cloud_error("unexpected end of point data"));
+    }
+
+    let mut positions = Vec::with_capacity(count);
+    let mut colors = color_offset.map(|_| Vec::with_capacity(count));
+    let mut max_color = 0;
+    for record in bytes[offset..end].chunks_exact(record_length) {
+        let i32_at = |i: usize| i32::from_le_bytes(record[i..i + 4].try_into().unwrap()) as f64;
+        positions.push(vec3(
+            i32_at(0) * scale.x + translation.x,
+            i32_at(4) * scale.y + translation.y,
+            i32_at(8) * scale.z + translation.z,
+        ));
+        if let (Some(c), Some(colors)) = (color_offset, colors.as_mut()) {
+            let channel = |i: usize| u16::from_le_bytes([record[c + i], record[c + i + 1]]);
+            let color = [channel(0), channel(2), channel(4)];
+            max_color = max_color.max(color[0].max(color[1]).max(color[2]));
+            colors.push(color);
+        }
+    }
+    // The colors should be 16 bit, but some files store 8 bit colors
+    let shift = if max_color > 255 { 8 } else { 0 };
+    let colors = colors.map(|colors| {
+        colors
+            .into_iter()
+            .map(|c| {
+                Srgba::new_opaque(
+                    (c[0] >> shift) as u8,
+                    (c[1] >> shift) as u8,
+                    (c[2] >> shift) as u8,
+                )
+            })
+            .collect()
+    });
+    Ok((positions, colors))
+}
+
+fn parse_pcd(bytes: &[u8]) -> Result<PointData, RendererError> {
+    let mut fields = Vec::new();
+    let mut sizes = Vec::new();
+    let mut types = Vec::new();
+    let mut counts = Vec::new();
+    let mut points = None;
+    let mut width_height = (0, 1);
+    let mut offset = 0;
+    let data = loop {
+        let end = bytes[offset..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map(|i| offset + i)
+            .ok_or_else(|| point_cloud_error("the header is missing 'DATA'"))?;
+        let line = String::from_utf8_lossy(&bytes[offset..end]).to_string();
+        offset = end + 1;
+        let mut tokens = line.split_whitespace();
+        let values = || line.split_whitespace().skip(1);
+        let numbers = || {
+            values()
+                .map(|v| v.parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| point_cloud_error(format!("invalid header line '{}'", line.trim())))
+        };
+        match tokens.next() {
+            Some("FIELDS") => fields = values().map(|v| v.to_string()).collect(),
+            Some("SIZE") => sizes = numbers()?,
+            Some("TYPE") => types = values().map(|v| v.to_string()).collect(),
+            Some("COUNT") => counts = numbers()?,
+            Some("WIDTH") => width_height.0 = numbers()?.first().copied().unwrap_or(0),
+            Some("HEIGHT") => width_height.1 = numbers()?.first().copied().unwrap_or(1),
+            Some("POINTS") => points = numbers()?.first().copied(),
+            Some("DATA") => break tokens.next().unwrap_or("").to_string(),
+            _ => {}
+        }
+    };
+    let count = points.unwrap_or(width_height.0 * width_height.1);
+    if counts.is_empty() {
+        counts = vec![1; fields.len()];
+    }
+    if sizes.len() != fields.len() || types.len() != fields.len() || counts.len() != fields.len() {
+        return Err(point_cloud_error(
+            "the number of fields, sizes, types and counts must match",
+        ));
+    }
+    let field_index = |name: &str| fields.iter().position(|f| f == name);
+    let (Some(x), Some(y), Some(z)) = (field_index("x"), field_index("y"), field_index("z")) else {
+        return Err(point_cloud_error(
+            "the fields must contain 'x', 'y' and 'z'",
+        ));
+    };
+    let color_field = field_index("rgb").or_else(|| field_index("rgba"));
+    for field in 0..fields.len() {
+        if !matches!(
+            (types[field].as_str(), sizes[field]),
+            ("F", 4 | 8) | ("U" | "I", 1 | 2 | 4)
+        ) {
+            return Err(point_cloud_error(format!(
+                "unsupported type '{}' with size {} of the '{}' field",
+                types[field], sizes[field], fields[field]
+            )));
+        }
+        if counts[field] == 0 {
+            return Err(point_cloud_error(format!(
+                "the '{}' field must have a count of at least one",
+                fields[field]
+            )));
+        }
+    }
+    if color_field.is_some_and(|field| sizes[field] != 4) {
+        return Err(point_cloud_error(
+            "the color field must have a size of 4 bytes",
+        ));
+    }
+    // The size of each point in bytes, which is not zero since each field has a size and a count of at least one
+    let stride = (0..fields.len())
+        .map(|f| sizes[f] * counts[f])
+        .sum::<usize>();
+    let data_length = || {
+        count
+            .checked_mul(stride)
+            .ok_or_else(|| point_cloud_error("the number of points is too large"))
+    };
+
+    // The value of the first element of each field of each point
+    let mut values: Vec<Vec<f64>> = vec![Vec::new(); fields.len()];
+    let mut packed_colors: Vec<u32> = Vec::new();
+    let reserve = |values: &mut Vec<Vec<f64>>, packed_colors: &mut Vec<u32>| {
+        values.iter_mut().for_each(|v| v.reserve(count));
+        if color_field.is_some() {
+            packed_colors.reserve(count);
+        }
+    };
+    let read = |bytes: &[u8], field: usize| -> f64 {
+        match (types[field].as_str(), sizes[field]) {
+            ("F", 4) => f32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ("F", 8) => f64::from_le_bytes(bytes[..8].try_into().unwrap()),
+            ("U", 1) => bytes[0] as f64,
+            ("U", 2) => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            ("U", 4) => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ("I", 1) => bytes[0] as i8 as f64,
+            ("I", 2) => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            ("I", 4) => i32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            _ => unreachable!(),
+        }
+    };
+    let packed = |bytes: &[u8]| u32::from_le_bytes(bytes[..4].try_into().unwrap());
+    match data.as_str() {
+        "ascii" => {
+            let source = String::from_utf8_lossy(&bytes[offset..]);
+            for line in source.lines().filter(|l| !l.trim().is_empty()).take(count) {
+                let tokens = line.split_whitespace().collect::<Vec<_>>();
+                let mut token = 0;
+                for field in 0..fields.len() {
+                    let value = tokens.get(token).copied().unwrap_or("0");
+                    if Some(field) == color_field {
+                        // The color is packed into the bits of a float or an unsigned integer
+                        packed_colors.push(match types[field].as_str() {
+                            "F" => value.parse::<f32>().unwrap_or(0.0).to_bits(),
+                            _ => value.parse::<u32>().unwrap_or(0),
+                        });
+                    }
+                    values[field].push(value.parse::<f64>().unwrap_or(0.0));
+                    token += counts[field];
+                }
+            }
+        }
+        "binary" => {
+            let length = data_length()?;
+            let point_data = bytes
+                .get(offset..)
+                .and_then(|data| data.get(..length))
+                .ok_or_else(|| point_cloud_error("unexpected end of point data"))?;
+            reserve(&mut values, &mut packed_colors);
+            for point in point_data.chunks_exact(stride) {
+                let mut start = 0;
+                for field in 0..fields.len() {
+                    if Some(field) == color_field {
+                        packed_colors.push(packed(&point[start..]));
+                    }
+                    values[field].push(read(&point[start..], field));
+                    start += sizes[field] * counts[field];
+                }
+            }
+        }
+        "binary_compressed" => {
+            let header = bytes
+                .get(offset..offset + 8)
+                .ok_or_else(|| point_cloud_error("unexpected end of point data"))?;
+            let compressed_size = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+            let size = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+            let compressed = bytes
+                .get(offset + 8..)
+                .and_then(|data| data.get(..compressed_size))
+                .ok_or_else(|| point_cloud_error("unexpected end of point data"))?;
+            let decompressed = lzf_decompress(compressed, size)?;
+            if decompressed.len() < data_length()? {
+                return Err(point_cloud_error("unexpected end of point data"));
+            }
+            reserve(&mut values, &mut packed_colors);
+            // The compressed data is stored field by field instead of point by point
+            let mut start = 0;
+            for field in 0..fields.len() {
+                let stride = sizes[field] * counts[field];
+                let end = start + count * stride;
+                for value in decompressed[start..end].chunks_exact(stride) {
+                    if Some(field) == color_field {
+                        packed_colors.push(packed(value));
+                    }
+                    values[field].push(read(value, field));
+                }
+                start = end;
+            }
+        }
+        _ => return Err(point_cloud_error(format!("unknown data type '{}'", data))),
+    }
+
+    let positions = (0..values[x].len())
+        .map(|i| vec3(values[x][i], values[y][i], values[z][i]))
+        .collect::<Vec<_>>();
+    let colors = color_field.map(|_| {
+        packed_colors
+            .iter()
+            .map(|c| Srgba::new_opaque((c >> 16) as u8, (c >> 8) as u8, *c as u8))
+            .collect()
+    });
+    Ok((positions, colors))
+}
+
+// Decompresses data compressed with the LZF algorithm, which is used by binary compressed PCD files.
+fn lzf_decompress(input: &[u8], size: usize) -> Result<Vec<u8>, RendererError> {
+    let error = || point_cloud_error("invalid compressed point data");
+    // The size is read from the file, so it is only used to bound the output instead of reserving memory up front
+    let mut output = Vec::new();
+    let mut i = 0;
+    while i < input.len() {
+        if output.len() > size {
+            return Err(error());
+        }
+        let control = input[i] as usize;
+        i += 1;
+        if control < 32 {
+            // A literal run
+            let literal = input.get(i..i + control + 1).ok_or_else(error)?;
+            output.extend_from_slice(literal);
+            i += control + 1;
+        } else {
+            // A back reference
+            let mut length = control >> 5;
+            if length == 7 {
+                length += *input.get(i).ok_or_else(error)? as usize;
+                i += 1;
+            }
+            let distance = ((control & 0x1f) << 8) + *input.get(i).ok_or_else(error)? as usize + 1;
+            i += 1;
+            let start = output.len().checked_sub(distance).ok_or_else(error)?;
+            for j in 0..length + 2 {
+                output.push(output[start + j]);
+            }
+        }
+    }
+    if output.len() != size {
+        return Err(error());
+    }
+    Ok(output)
+}
+
+fn parse_xyz(bytes: &[u8]) -> PointData {
+    let source = String::from_utf8_lossy(bytes);
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    for line in source.lines() {
+        let line = line.trim();
+        if line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        let tokens = line
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>();
+        let Ok(values) = tokens
+            .iter()
+            .map(|t| t.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            continue;
+        };
+        if values.len() < 3 {
+            continue;
+        }
+        positions.push(vec3(values[0], values[1], values[2]));
+        // The color is the last three values, since some files also contain an intensity
+        if values.len() >= 6 {
+            let color = &values[values.len() - 3..];
+            let is_float = tokens[tokens.len() - 3..].iter().any(|t| t.contains('.'));
+            let channel = |v: f64| {
+                if is_float {
+                    (v * 255.0).round().clamp(0.0, 255.0) as u8
+                } else {
+                    v.clamp(0.0, 255.0) as u8
+                }
+            };
+            colors.push(Srgba::new_opaque(
+                channel(color[0]),
+                channel(color[1]),
+                channel(color[2]),
+            ));
+        }
+    }
+    let colors = (!colors.is_empty() && colors.len() == positions.len()).then_some(colors);
+    (positions, colors)
+}
@@ -0,0 +1,21 @@
+use crate::renderer::*;
+use std::path::Path;
+use three_d_asset::io::Serialize;
+
+///
+/// Reads the colors of the given render target, for example the [RenderTarget::screen] render target, and saves them as a PNG image at the given path, which must have the `.png` extension.
+/// The color target must contain unsigned byte data, which is the case for the screen and for textures created with `u8` as the base type.
+/// Use [RenderTarget::read_to_cpu_texture] to get the colors without saving them, for example to compare them to a reference image in a test.
+///
+/// **Note:** This stalls the CPU until the GPU has finished rendering, so it should only be called when a screenshot is requested, for example when a button is pressed.
+///
+pub fn save_screenshot(target: &RenderTarget, path: impl AsRef<Path>) -> Result<(), RendererError> {
+    let path = path.as_ref();
+    let cpu_texture = target.read_to_cpu_texture();
+    let raw_assets = cpu_texture.serialize(path).map_err(screenshot_error)?;
+    three_d_asset::io::save(&raw_assets).map_err(screenshot_error)
+}
+
+fn screenshot_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::ScreenshotSaving(error.to_string())
+}
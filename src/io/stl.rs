@@ -0,0 +1,178 @@
+use crate::renderer::*;
+use std::path::Path;
+use three_d_asset::io::RawAssets;
+
+///
+/// The encoding of a mesh file which can be stored both as text and binary data, for example STL and PLY files.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MeshEncoding {
+    /// Human readable text.
+    Ascii,
+    /// Compact binary data in little endian byte order.
+    #[default]
+    Binary,
+}
+
+///
+/// Deserializes the STL file (`.stl`) at the given path from the given raw assets, where both ASCII and binary STL files are supported.
+/// The triangles do not share vertices, so each vertex has the normal of its triangle, which is computed if it is missing in the file.
+///
+pub fn deserialize_stl(
+    raw_assets: &RawAssets,
+    path: impl AsRef<Path>,
+) -> Result<CpuMesh, RendererError> {
+    let bytes = raw_assets.get(path).map_err(stl_error)?;
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut add_triangle = |normal: Vec3, vertices: [Vec3; 3]| {
+        // Use the normal given by the vertices if the normal is missing
+        let normal = if normal.magnitude2() > 0.0 {
+            normal.normalize()
+        } else {
+            let n = (vertices[1] - vertices[0]).cross(vertices[2] - vertices[0]);
+            if n.magnitude2() > 0.0 {
+                n.normalize()
+            } else {
+                n
+            }
+        };
+        positions.extend(vertices);
+        normals.extend([normal; 3]);
+    };
+
+    let binary_size = bytes
+        .get(80..84)
+        .map(|count| 84 + 50 * u32::from_le_bytes(count.try_into().unwrap()) as usize);
+    if binary_size == Some(bytes.len()) || !bytes.starts_with(b"solid") {
+        if binary_size.is_none() {
+            return Err(stl_error("the file is too short"));
+        }
+        let count = (bytes.len() - 84) / 50;
+        for triangle in bytes[84..84 + count * 50].chunks_exact(50) {
+            let v = |i: usize| {
+                let f = |j: usize| {
+                    f32::from_le_bytes(
+                        triangle[4 * (3 * i + j)..4 * (3 * i + j) + 4]
+                            .try_into()
+                            .unwrap(),
+                    )
+                };
+                vec3(f(0), f(1), f(2))
+            };
+            add_triangle(v(0), [v(1), v(2), v(3)]);
+        }
+    } else {
+        let source = String::from_utf8_lossy(bytes);
+        let mut normal = vec3(0.0, 0.0, 0.0);
+        let mut vertices = Vec::new();
+        for line in source.lines() {
+            let mut tokens = line.split_whitespace();
+            let keyword = tokens.next();
+            let values = tokens
+                .filter_map(|t| t.parse::<f32>().ok())
+                .collect::<Vec<_>>();
+            let vector = || {
+                (values.len() == 3)
+                    .then(|| vec3(values[0], values[1], values[2]))
+                    .ok_or_else(|| stl_error(format!("invalid line '{}'", line.trim())))
+            };
+            match keyword {
+                Some("facet") => {
+                    normal = vector()?;
+                    vertices.clear();
+                }
+                Some("vertex") => vertices.push(vector()?),
+                Some("endfacet") => {
+                    if vertices.len() != 3 {
+                        return Err(stl_error("a facet must have three vertices"));
+                    }
+                    add_triangle(normal, [vertices[0], vertices[1], vertices[2]]);
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(CpuMesh {
+        positions: Positions::F32(positions),
+        normals: Some(normals),
+        ..Default::default()
+    })
+}
+
+///
+/// Serializes the triangles of the given mesh into an STL file (`.stl`) with the given encoding, which can be saved using for example [three_d_asset::io::save].
+/// The normal of each triangle is computed from the vertices, since STL files do not support vertex normals, and the colors and uv coordinates are not saved.
+///
+pub fn serialize_stl(
+    cpu_mesh: &CpuMesh,
+    path: impl AsRef<Path>,
+    encoding: MeshEncoding,
+) -> RawAssets {
+    let triangles = triangles(cpu_mesh);
+    let normal = |t: &[Vec3; 3]| {
+        let n = (t[1] - t[0]).cross(t[2] - t[0]);
+        if n.magnitude2() > 0.0 {
+            n.normalize()
+        } else {
+            n
+        }
+    };
+    let bytes = match encoding {
+        MeshEncoding::Ascii => {
+            let mut source = String::from("solid mesh\n");
+            for t in triangles.iter() {
+                let n = normal(t);
+                source.push_str(&format!(
+                    "facet normal {} {} {}\n outer loop\n",
+                    n.x, n.y, n.z
+                ));
+                for v in t {
+                    source.push_str(&format!("  vertex {} {} {}\n", v.x, v.y, v.z));
+                }
+                source.push_str(" endloop\nendfacet\n");
+            }
+            source.push_str("endsolid mesh\n");
+            source.into_bytes()
+        }
+        MeshEncoding::Binary => {
+            let mut bytes = vec![0u8; 80];
+            bytes.extend((triangles.len() as u32).to_le_bytes());
+            for t in triangles.iter() {
+                for v in std::iter::once(normal(t)).chain(t.iter().copied()) {
+                    bytes.extend([v.x, v.y, v.z].iter().flat_map(|f| f.to_le_bytes()));
+                }
+                bytes.extend(0u16.to_le_bytes());
+            }
+            bytes
+        }
+    };
+    let mut raw_assets = RawAssets::new();
+    raw_assets.insert(path, bytes);
+    raw_assets
+}
+
+fn stl_error(error: impl std::fmt::Display) -> RendererError {
+    RendererError::StlDeserialization(error.to_string())
+}
+
+// Returns the positions of the vertices of each triangle.
+fn triangles(cpu_mesh: &CpuMesh) -> Vec<[Vec3; 3]> {
+    let positions = cpu_mesh.positions.to_f32();
+    let indices = match &cpu_mesh.indices {
+        Indices::None => (0..positions.len() as u32).collect::<Vec<_>>(),
+        Indices::U8(indices) => indices.iter().map(|i| *i as u32).collect(),
+        Indices::U16(indices) => indices.iter().map(|i| *i as u32).collect(),
+        Indices::U32(indices) => indices.clone(),
+    };
+    indices
+        .chunks_exact(3)
+        .map(|t| {
+            [
+                positions[t[0] as usize],
+                positions[t[1] as usize],
+                positions[t[2] as usize],
+            ]
+        })
+        .collect()
+}
@@ -16,6 +16,8 @@ pub mod core;
 pub mod renderer;
 pub use renderer::*;
 
+pub mod io;
+
 pub mod window;
 #[allow(unused_imports)]
 pub use window::*;
@@ -29,11 +29,73 @@ pub enum RendererError {
     InvalidBufferLength(String, usize, usize),
     #[error("the material {0} is required by the geometry {1} but could not be found")]
     MissingMaterial(String, String),
+    #[error("failed serializing or deserializing scene file: {0}")]
+    SceneSerialization(String),
+    #[error("failed reading or writing scene file: {0}")]
+    SceneFileIo(#[source] std::io::Error),
+    #[error("scene file version {0} is not supported, the newest supported version is {1}")]
+    UnsupportedSceneFileVersion(u32, u32),
+    #[error("failed deserializing glTF file: {0}")]
+    GltfDeserialization(String),
+    #[error("failed serializing glTF file: {0}")]
+    GltfSerialization(String),
+    #[error("failed deserializing OBJ file: {0}")]
+    ObjDeserialization(String),
+    #[error("failed deserializing STL file: {0}")]
+    StlDeserialization(String),
+    #[error("failed deserializing PLY file: {0}")]
+    PlyDeserialization(String),
+    #[error("failed deserializing point cloud file: {0}")]
+    PointCloudDeserialization(String),
+    #[error("failed deserializing EXR file: {0}")]
+    ExrDeserialization(String),
+    #[error("failed deserializing KTX2 file: {0}")]
+    Ktx2Deserialization(String),
+    #[error("failed deserializing DDS file: {0}")]
+    DdsDeserialization(String),
+    #[error("failed saving screenshot: {0}")]
+    ScreenshotSaving(String),
 }
 
 mod camera;
 pub use camera::*;
 
+mod scene_file;
+pub use scene_file::*;
+
+mod resource_pool;
+pub use resource_pool::*;
+
+mod tracked_scene;
+pub use tracked_scene::*;
+
+mod scene_graph;
+pub use scene_graph::*;
+
+mod render_queue;
+pub use render_queue::*;
+
+mod render_pipeline;
+pub use render_pipeline::*;
+
+mod update_scheduler;
+pub use update_scheduler::*;
+
+mod collider;
+pub use collider::*;
+
+mod gpu_picker;
+pub use gpu_picker::*;
+
+mod render_outputs;
+pub use render_outputs::*;
+
+mod portal;
+pub use portal::*;
+
+mod shader_defines;
+pub use shader_defines::*;
+
 pub mod material;
 pub use material::*;
 
@@ -72,6 +134,7 @@ macro_rules! impl_render_target_extensions_body {
         /// Render the objects using the given camera and lights into the part of this render target defined by the scissor box.
         /// Use an empty array for the `lights` argument, if the objects does not require lights to be rendered.
         /// Also, objects outside the camera frustum are not rendered and the objects are rendered in the order given by [cmp_render_order].
+        /// Objects with a [MaterialType::OrderIndependentTransparent] material are rendered after all other objects and composited on top using weighted blended order-independent transparency.
         ///
         pub fn render_partially(
             &self,
@@ -80,10 +143,15 @@ macro_rules! impl_render_target_extensions_body {
             objects: impl IntoIterator<Item = impl Object>,
             lights: &[&dyn Light],
         ) -> &Self {
-            let (mut deferred_objects, mut forward_objects): (Vec<_>, Vec<_>) = objects
+            let frustum = camera.frustum();
+            let (mut deferred_objects, forward_objects): (Vec<_>, Vec<_>) = objects
                 .into_iter()
-                .filter(|o| camera.in_frustum(&o.aabb()))
+                .filter(|o| frustum.intersects_aabb(&o.aabb()))
                 .partition(|o| o.material_type() == MaterialType::Deferred);
+            let (order_independent_objects, mut forward_objects): (Vec<_>, Vec<_>) =
+                forward_objects
+                    .into_iter()
+                    .partition(|o| o.material_type() == MaterialType::OrderIndependentTransparent);
 
             // Deferred
             if deferred_objects.len() > 0 {
@@ -118,7 +186,7 @@ macro_rules! impl_render_target_extensions_body {
                 )
                 .clear(ClearState::default())
                 .write::<RendererError>(|| {
-                    for object in deferred_objects {
+                    for object in deferred_objects.iter() {
                         object.render(&geometry_pass_camera, lights);
                     }
                     Ok(())
@@ -126,28 +194,156 @@ macro_rules! impl_render_target_extensions_body {
                 .unwrap();
 
                 // Lighting pass
-                self.apply_screen_effect_partially(
-                    scissor_box,
-                    &lighting_pass::LightingPassEffect {},
-                    camera,
-                    lights,
-                    Some(ColorTexture::Array {
-                        texture: &geometry_pass_texture,
-                        layers: &gbuffer_layers,
-                    }),
-                    Some(DepthTexture::Single(&geometry_pass_depth_texture)),
-                );
+                let geometry_pass_color = ColorTexture::Array {
+                    texture: &geometry_pass_texture,
+                    layers: &gbuffer_layers,
+                };
+                let geometry_pass_depth = DepthTexture::Single(&geometry_pass_depth_texture);
+                let (volume_lights, screen_lights): (Vec<&dyn Light>, Vec<&dyn Light>) =
+                    lights.iter().copied().partition(|l| l.volume().is_some());
+                if volume_lights.is_empty() {
+                    self.apply_screen_effect_partially(
+                        scissor_box,
+                        &lighting_pass::LightingPassEffect {},
+                        camera,
+                        lights,
+                        Some(geometry_pass_color),
+                        Some(geometry_pass_depth),
+                    );
+                } else {
+                    // The lights without a volume are applied to the entire screen and the lights with a volume only to the pixels covered by the volume.
+                    // The light is accumulated in a floating point texture before applying the tone and color mapping.
+                    let mut accumulation_camera = geometry_pass_camera.clone();
+                    accumulation_camera.disable_tone_and_color_mapping();
+                    let mut light_texture = Texture2D::new_empty::<[f16; 4]>(
+                        &self.context,
+                        viewport.width,
+                        viewport.height,
+                        Interpolation::Nearest,
+                        Interpolation::Nearest,
+                        None,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    );
+                    light_texture
+                        .as_color_target(None)
+                        .clear(ClearState::color(0.0, 0.0, 0.0, 0.0))
+                        .write::<RendererError>(|| {
+                            apply_screen_effect(
+                                &self.context,
+                                lighting_pass::LightingPassEffect {},
+                                &accumulation_camera,
+                                &screen_lights,
+                                Some(geometry_pass_color),
+                                Some(geometry_pass_depth),
+                            );
+                            lighting_pass::render_light_volumes(
+                                &self.context,
+                                &accumulation_camera,
+                                &volume_lights,
+                                geometry_pass_color,
+                                geometry_pass_depth,
+                            );
+                            Ok(())
+                        })
+                        .unwrap();
+                    self.apply_screen_effect_partially(
+                        scissor_box,
+                        &lighting_pass::LightAccumulationEffect {},
+                        camera,
+                        lights,
+                        Some(ColorTexture::Single(&light_texture)),
+                        Some(geometry_pass_depth),
+                    );
+                }
             }
 
             // Forward
             forward_objects.sort_by(|a, b| cmp_render_order(camera, a, b));
             self.write_partially::<RendererError>(scissor_box, || {
-                for object in forward_objects {
+                for object in forward_objects.iter() {
                     object.render(camera, lights);
                 }
                 Ok(())
             })
             .unwrap();
+
+            // Order-independent transparency
+            if order_independent_objects.len() > 0 {
+                let mut accumulation_camera = camera.clone();
+                let viewport =
+                    Viewport::new_at_origo(camera.viewport().width, camera.viewport().height);
+                accumulation_camera.set_viewport(viewport);
+                let mut accumulation_texture = Texture2DArray::new_empty::<[f16; 4]>(
+                    &self.context,
+                    viewport.width,
+                    viewport.height,
+                    2,
+                    Interpolation::Nearest,
+                    Interpolation::Nearest,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                );
+                let mut depth_texture = DepthTexture2D::new::<f32>(
+                    &self.context,
+                    viewport.width,
+                    viewport.height,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                );
+
+                // Depth of the opaque objects which occlude the transparent objects
+                let depth_material = DepthMaterial {
+                    render_states: RenderStates {
+                        write_mask: WriteMask::DEPTH,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                depth_texture
+                    .as_depth_target()
+                    .clear(ClearState::depth(1.0))
+                    .write::<RendererError>(|| {
+                        for object in deferred_objects.iter().chain(
+                            forward_objects
+                                .iter()
+                                .filter(|o| o.material_type() == MaterialType::Opaque),
+                        ) {
+                            object.render_with_material(&depth_material, &accumulation_camera, &[]);
+                        }
+                        Ok(())
+                    })
+                    .unwrap();
+
+                // Accumulation pass
+                let accumulation_layers = [0, 1];
+                RenderTarget::new(
+                    accumulation_texture.as_color_target(&accumulation_layers, None),
+                    depth_texture.as_depth_target(),
+                )
+                .clear(ClearState::color(0.0, 0.0, 0.0, 1.0))
+                .write::<RendererError>(|| {
+                    for object in order_independent_objects {
+                        object.render(&accumulation_camera, lights);
+                    }
+                    Ok(())
+                })
+                .unwrap();
+
+                // Composite pass
+                self.apply_screen_effect_partially(
+                    scissor_box,
+                    &weighted_blended_composite::WeightedBlendedCompositeEffect {},
+                    camera,
+                    lights,
+                    Some(ColorTexture::Array {
+                        texture: &accumulation_texture,
+                        layers: &accumulation_layers,
+                    }),
+                    None,
+                );
+            }
             self
         }
 
@@ -183,10 +379,11 @@ macro_rules! impl_render_target_extensions_body {
             geometries: impl IntoIterator<Item = impl Geometry>,
             lights: &[&dyn Light],
         ) -> &Self {
+            let frustum = camera.frustum();
             self.write_partially::<RendererError>(scissor_box, || {
                 for geometry in geometries
                     .into_iter()
-                    .filter(|o| camera.in_frustum(&o.aabb()))
+                    .filter(|o| frustum.intersects_aabb(&o.aabb()))
                 {
                     render_with_material(&self.context, camera, geometry, material, lights);
                 }
@@ -234,10 +431,11 @@ macro_rules! impl_render_target_extensions_body {
             color_texture: Option<ColorTexture>,
             depth_texture: Option<DepthTexture>,
         ) -> &Self {
+            let frustum = camera.frustum();
             self.write_partially::<RendererError>(scissor_box, || {
                 for geometry in geometries
                     .into_iter()
-                    .filter(|o| camera.in_frustum(&o.aabb()))
+                    .filter(|o| frustum.intersects_aabb(&o.aabb()))
                 {
                     render_with_effect(
                         &self.context,
@@ -388,16 +586,23 @@ pub fn render_with_material(
     lights: &[&dyn Light],
 ) {
     let fragment_attributes = material.fragment_attributes();
+    let mut defines = geometry.shader_defines();
+    defines.extend(&material.shader_defines());
+    if !material.render_states().clip_planes.is_empty() {
+        defines.insert(ClipPlanes::SHADER_DEFINE);
+    }
+    let defines = defines.source();
     let mut id = geometry.id(fragment_attributes).to_le_bytes().to_vec();
     id.extend(material.id().to_le_bytes());
     id.extend(lights.iter().map(|l| l.id()));
+    id.extend(defines.bytes());
 
     let mut programs = context.programs.write().unwrap();
     let program = programs.entry(id).or_insert_with(|| {
         Program::from_source(
             context,
-            &geometry.vertex_shader_source(fragment_attributes),
-            &material.fragment_shader_source(lights),
+            &(defines.clone() + &geometry.vertex_shader_source(fragment_attributes)),
+            &(defines.clone() + &material.fragment_shader_source(lights)),
         )
         .expect("Failed compiling shader")
     });
@@ -405,7 +610,7 @@ pub fn render_with_material(
     geometry.draw(
         camera,
         program,
-        material.render_states(),
+        clip_space_render_states(material.render_states(), camera),
         fragment_attributes,
     );
 }
@@ -425,21 +630,42 @@ pub fn render_with_effect(
     depth_texture: Option<DepthTexture>,
 ) {
     let fragment_attributes = effect.fragment_attributes();
+    let mut defines = geometry.shader_defines();
+    defines.extend(&effect.shader_defines());
+    if !effect.render_states().clip_planes.is_empty() {
+        defines.insert(ClipPlanes::SHADER_DEFINE);
+    }
+    let defines = defines.source();
     let mut id = geometry.id(fragment_attributes).to_le_bytes().to_vec();
     id.extend(effect.id(color_texture, depth_texture).to_le_bytes());
     id.extend(lights.iter().map(|l| l.id()));
+    id.extend(defines.bytes());
 
     let mut programs = context.programs.write().unwrap();
     let program = programs.entry(id).or_insert_with(|| {
         Program::from_source(
             context,
-            &geometry.vertex_shader_source(fragment_attributes),
-            &effect.fragment_shader_source(lights, color_texture, depth_texture),
+            &(defines.clone() + &geometry.vertex_shader_source(fragment_attributes)),
+            &(defines.clone()
+                + &effect.fragment_shader_source(lights, color_texture, depth_texture)),
         )
         .expect("Failed compiling shader")
     });
     effect.use_uniforms(program, camera, lights, color_texture, depth_texture);
-    geometry.draw(camera, program, effect.render_states(), fragment_attributes);
+    geometry.draw(
+        camera,
+        program,
+        clip_space_render_states(effect.render_states(), camera),
+        fragment_attributes,
+    );
+}
+
+// The clip planes of materials and effects are given in world space, but are evaluated in clip space, see [ClipPlanes].
+fn clip_space_render_states(mut render_states: RenderStates, camera: &Camera) -> RenderStates {
+    render_states.clip_planes = render_states
+        .clip_planes
+        .transformed(camera.projection() * camera.view());
+    render_states
 }
 
 ///
@@ -457,16 +683,18 @@ pub fn apply_screen_material(
     if fragment_attributes.normal || fragment_attributes.position || fragment_attributes.tangents {
         panic!("Not possible to use the given material to render full screen, the full screen geometry only provides uv coordinates and color");
     }
+    let defines = material.shader_defines().source();
     let mut id = (0b1u16 << 15).to_le_bytes().to_vec();
     id.extend(material.id().to_le_bytes());
     id.extend(lights.iter().map(|l| l.id()));
+    id.extend(defines.bytes());
 
     let mut programs = context.programs.write().unwrap();
     let program = programs.entry(id).or_insert_with(|| {
         Program::from_source(
             context,
             full_screen_vertex_shader_source(),
-            &material.fragment_shader_source(lights),
+            &(defines.clone() + &material.fragment_shader_source(lights)),
         )
         .expect("Failed compiling shader")
     });
@@ -496,16 +724,19 @@ pub fn apply_screen_effect(
     if fragment_attributes.normal || fragment_attributes.position || fragment_attributes.tangents {
         panic!("Not possible to use the given effect to render full screen, the full screen geometry only provides uv coordinates and color");
     }
+    let defines = effect.shader_defines().source();
     let mut id = (0b1u16 << 15).to_le_bytes().to_vec();
     id.extend(effect.id(color_texture, depth_texture).to_le_bytes());
     id.extend(lights.iter().map(|l| l.id()));
+    id.extend(defines.bytes());
 
     let mut programs = context.programs.write().unwrap();
     let program = programs.entry(id).or_insert_with(|| {
         Program::from_source(
             context,
             full_screen_vertex_shader_source(),
-            &effect.fragment_shader_source(lights, color_texture, depth_texture),
+            &(defines.clone()
+                + &effect.fragment_shader_source(lights, color_texture, depth_texture)),
         )
         .expect("Failed compiling shader")
     });
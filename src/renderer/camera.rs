@@ -4,6 +4,24 @@ pub use tone_mapping::*;
 mod color_space;
 pub use color_space::*;
 
+mod auto_exposure;
+pub use auto_exposure::*;
+
+mod camera_uniform_buffer;
+pub use camera_uniform_buffer::*;
+
+mod planar_reflection;
+pub use planar_reflection::*;
+
+mod camera_tween;
+pub use camera_tween::*;
+
+mod frustum;
+pub use frustum::*;
+
+mod anchor;
+pub use anchor::*;
+
 use crate::core::*;
 
 ///
@@ -16,6 +34,10 @@ pub struct Camera {
     pub tone_mapping: ToneMapping,
     /// This color mapping is applied to the final color of renders using this camera.
     pub color_mapping: ColorMapping,
+    /// The exposure in stops (EV) applied to the color before the tone mapping, ie. the color is scaled by `2^exposure`.
+    /// Has no effect if the tone mapping is [ToneMapping::None]. Use [AutoExposure] to adapt this value to the brightness of the scene.
+    pub exposure: f32,
+    oblique_near_plane: Option<Vec4>,
 }
 
 impl Camera {
@@ -37,6 +59,8 @@ impl Camera {
             ),
             tone_mapping: ToneMapping::default(),
             color_mapping: ColorMapping::default(),
+            exposure: 0.0,
+            oblique_near_plane: None,
         }
     }
 
@@ -64,6 +88,8 @@ impl Camera {
             ),
             tone_mapping: ToneMapping::default(),
             color_mapping: ColorMapping::default(),
+            exposure: 0.0,
+            oblique_near_plane: None,
         }
     }
 
@@ -93,6 +119,80 @@ impl Camera {
         )
     }
 
+    ///
+    /// Sets the [Camera::exposure] from an exposure value at ISO 100 (EV100), as used in photography,
+    /// for example 15 for a sunny day, 12 for an overcast day, 7 for a bright indoor scene and 2 for a night scene.
+    /// Use this when the lights are specified in photometric units, see for example [PointLight::set_luminous_power].
+    ///
+    pub fn set_exposure_value(&mut self, ev100: f32) {
+        // The maximum luminance that does not saturate the sensor is 1.2 * 2^EV100
+        self.exposure = -ev100 - 1.2f32.log2();
+    }
+
+    ///
+    /// Sets the [Camera::exposure] from the settings of a physical camera, ie. the aperture (f-number), the shutter time in seconds and the sensitivity (ISO),
+    /// for example an aperture of 16, a shutter time of 1/100 seconds and a sensitivity of 100 for a sunny day.
+    /// See also [Camera::set_exposure_value].
+    ///
+    pub fn set_physical_exposure(&mut self, aperture: f32, shutter_time: f32, sensitivity: f32) {
+        self.set_exposure_value((aperture * aperture / shutter_time * 100.0 / sensitivity).log2());
+    }
+
+    ///
+    /// Replaces the near plane of the camera frustum with the given plane, given in world space as `(normal, distance)`,
+    /// so that only the points `p` where `normal.dot(p) + distance >= 0` are rendered.
+    /// This is used to clip away everything behind a mirror or below a water surface without any cost in the shaders, see [PlanarReflection].
+    /// The plane is ignored if the camera is on the positive side of the plane. Use `None` to restore the original near plane.
+    ///
+    /// **Note:** The far plane is also tilted, so the depth precision is reduced.
+    ///
+    pub fn set_oblique_near_plane(&mut self, plane: Option<Vec4>) {
+        self.oblique_near_plane = plane;
+    }
+
+    ///
+    /// Returns the plane set using [Camera::set_oblique_near_plane].
+    ///
+    pub fn oblique_near_plane(&self) -> Option<Vec4> {
+        self.oblique_near_plane
+    }
+
+    ///
+    /// Returns the view frustum of this camera, including the oblique near plane if set, which is used for skipping the objects outside the view.
+    /// All render calls, for example [RenderTarget::render], already skip the objects that are outside the frustum,
+    /// but the frustum can be used to skip the work of for example updating or animating objects that are not visible.
+    ///
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_view_projection(self.projection() * self.view())
+    }
+
+    ///
+    /// Returns the projection matrix of this camera, including the oblique near plane if set, see [Camera::set_oblique_near_plane].
+    ///
+    pub fn projection(&self) -> Mat4 {
+        let projection = self.camera.projection();
+        let Some(plane) = self.oblique_near_plane else {
+            return projection;
+        };
+        // The plane in view space, the camera must be on the negative side of the plane
+        let plane = self.camera.view().invert().unwrap().transpose() * plane;
+        if plane.w >= 0.0 {
+            return projection;
+        }
+        // Replace the third row of the projection matrix (Lengyel, "Oblique View Frustum Depth Projection and Clipping")
+        let Some(inverse) = projection.invert() else {
+            return projection;
+        };
+        let corner = inverse * vec4(plane.x.signum(), plane.y.signum(), 1.0, 1.0);
+        let row = plane * (2.0 / plane.dot(corner));
+        let mut projection = projection;
+        projection.x.z = row.x - projection.x.w;
+        projection.y.z = row.y - projection.y.w;
+        projection.z.z = row.z - projection.z.w;
+        projection.w.z = row.w - projection.w.w;
+        projection
+    }
+
     ///
     /// Disables the tone and color mapping so as to be ready for rendering into an intermediate render target with this camera.
     ///
@@ -108,6 +208,185 @@ impl Camera {
         self.tone_mapping = ToneMapping::default();
         self.color_mapping = ColorMapping::default();
     }
+
+    ///
+    /// Moves the camera along its view direction, and changes the target to the center of the given bounding box,
+    /// so that the bounding box fills the view, for example to focus on a selected object.
+    /// The margin is the fraction of the view that is left empty around the bounding box, where 0 means no margin.
+    /// For an orthographic camera, the camera is moved to look at the center and the height of the view is changed.
+    /// The view direction, up direction and field of view are not changed. Does nothing if the bounding box is empty or infinite.
+    ///
+    /// Use [CameraTween] to smoothly move the camera to the new view instead of jumping there.
+    ///
+    pub fn look_at_bounding_box(&mut self, aabb: &AxisAlignedBoundingBox, margin: f32) {
+        if aabb.is_empty() || !aabb.size().magnitude().is_finite() {
+            return;
+        }
+        let center = aabb.center();
+        let radius = (0.5 * aabb.size().magnitude()).max(0.0001) / (1.0 - margin.clamp(0.0, 0.99));
+        let viewport = self.viewport();
+        let aspect = viewport.width.max(1) as f32 / viewport.height.max(1) as f32;
+        let view_direction = self.view_direction();
+        let up = *self.up();
+        match *self.projection_type() {
+            three_d_asset::ProjectionType::Perspective { field_of_view_y } => {
+                let half_y = 0.5 * field_of_view_y.0;
+                let half_x = (half_y.tan() * aspect).atan();
+                let distance = radius / half_y.min(half_x).sin();
+                self.set_view(center - view_direction * distance, center, up);
+            }
+            three_d_asset::ProjectionType::Orthographic { .. } => {
+                let distance = self.target().distance(*self.position());
+                let (z_near, z_far) = (self.z_near(), self.z_far());
+                self.set_view(center - view_direction * distance, center, up);
+                self.set_orthographic_projection(
+                    2.0 * radius * aspect.recip().max(1.0),
+                    z_near,
+                    z_far,
+                );
+            }
+        }
+    }
+
+    ///
+    /// Returns the height in world units of the view at the given depth, ie. distance along the view direction from the camera.
+    /// For an orthographic camera, this is the height of the view at all depths.
+    ///
+    pub fn view_height_at(&self, depth: f32) -> f32 {
+        match *self.projection_type() {
+            three_d_asset::ProjectionType::Orthographic { height } => height,
+            three_d_asset::ProjectionType::Perspective { field_of_view_y } => {
+                2.0 * depth.max(0.0) * (0.5 * field_of_view_y.0).tan()
+            }
+        }
+    }
+
+    ///
+    /// Returns the size in world units of a physical pixel at the given depth, ie. distance along the view direction from the camera.
+    /// This is for example useful for drawing lines or gizmos with a constant size on the screen.
+    ///
+    pub fn pixel_size_at(&self, depth: f32) -> f32 {
+        self.view_height_at(depth) / self.viewport().height.max(1) as f32
+    }
+
+    ///
+    /// Returns a [Ray](crate::renderer::Ray) from the near plane of the camera through the given pixel, for example the position of the mouse, used for picking on the CPU.
+    /// The pixel coordinate must be in physical pixels, where (viewport.x, viewport.y) indicate the bottom left corner of the viewport
+    /// and (viewport.x + viewport.width, viewport.y + viewport.height) indicate the top right corner.
+    ///
+    pub fn ray_from_screen(&self, pixel: impl Into<PhysicalPoint> + Copy) -> crate::renderer::Ray {
+        let direction = self.view_direction_at_pixel(pixel);
+        crate::renderer::Ray::new(
+            self.position_at_pixel(pixel) + direction * self.z_near(),
+            direction,
+        )
+    }
+
+    ///
+    /// Returns the fraction of the viewport covered by the projection of the given bounding box, between 0 (not visible) and 1 (covers the entire viewport).
+    /// The estimate is conservative, ie. the projected bounding box is used, and a bounding box intersecting the near plane is considered to cover the entire viewport.
+    /// This is for example useful for prioritizing the updates of shadow maps and reflections, see [UpdateScheduler](crate::renderer::UpdateScheduler).
+    ///
+    pub fn screen_coverage(&self, aabb: &AxisAlignedBoundingBox) -> f32 {
+        if !self.frustum().intersects_aabb(aabb) {
+            return 0.0;
+        }
+        let (min, max) = (aabb.min(), aabb.max());
+        if !(min.magnitude2().is_finite() && max.magnitude2().is_finite()) {
+            return 1.0;
+        }
+        let view_projection = self.projection() * self.view();
+        let mut ndc_min = vec2(1.0f32, 1.0);
+        let mut ndc_max = vec2(-1.0f32, -1.0);
+        for i in 0..8 {
+            let corner = vec3(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            );
+            let p = view_projection * corner.extend(1.0);
+            if p.w <= 0.0 {
+                return 1.0;
+            }
+            ndc_min = vec2(ndc_min.x.min(p.x / p.w), ndc_min.y.min(p.y / p.w));
+            ndc_max = vec2(ndc_max.x.max(p.x / p.w), ndc_max.y.max(p.y / p.w));
+        }
+        let size = vec2(
+            ndc_max.x.min(1.0) - ndc_min.x.max(-1.0),
+            ndc_max.y.min(1.0) - ndc_min.y.max(-1.0),
+        );
+        (size.x.max(0.0) * size.y.max(0.0) / 4.0).min(1.0)
+    }
+
+    ///
+    /// Switches to an orthographic projection where the height of the view is the height of the current view at the target,
+    /// so objects at the target keep their size on the screen. The near and far planes are not changed.
+    ///
+    pub fn set_orthographic_from_view_height(&mut self) {
+        let height = self.view_height_at(self.target_depth());
+        let (z_near, z_far) = (self.z_near(), self.z_far());
+        self.set_orthographic_projection(height, z_near, z_far);
+    }
+
+    ///
+    /// Switches to a perspective projection with the given field of view and moves the camera along the view direction,
+    /// so that the height of the view at the target is the same as before and objects at the target keep their size on the screen.
+    /// The target and the near and far planes are not changed.
+    ///
+    pub fn set_perspective_from_view_height(&mut self, field_of_view_y: impl Into<Radians>) {
+        let field_of_view_y = field_of_view_y.into();
+        let height = self.view_height_at(self.target_depth());
+        let depth = 0.5 * height / (0.5 * field_of_view_y.0).tan().max(0.0001);
+        let (target, up) = (*self.target(), *self.up());
+        let position = target - self.view_direction() * depth;
+        let (z_near, z_far) = (self.z_near(), self.z_far());
+        self.set_view(position, target, up);
+        self.set_perspective_projection(field_of_view_y, z_near, z_far);
+    }
+
+    ///
+    /// Switches between a perspective and an orthographic projection, keeping the size of the objects at the target on the screen,
+    /// see [Camera::set_orthographic_from_view_height] and [Camera::set_perspective_from_view_height].
+    /// The field of view is used when switching to a perspective projection.
+    ///
+    pub fn toggle_projection(&mut self, field_of_view_y: impl Into<Radians>) {
+        match *self.projection_type() {
+            three_d_asset::ProjectionType::Orthographic { .. } => {
+                self.set_perspective_from_view_height(field_of_view_y)
+            }
+            three_d_asset::ProjectionType::Perspective { .. } => {
+                self.set_orthographic_from_view_height()
+            }
+        }
+    }
+
+    ///
+    /// Zooms by scaling the view around the given point with the given factor, where a factor less than one zooms in, so the point stays at the same place on the screen.
+    /// For a perspective camera, the distance from the camera to the point is scaled and for an orthographic camera, the height of the view is scaled.
+    /// The view direction is not changed and the target is moved along with the camera.
+    /// Use [three_d_asset::Camera::zoom_towards] to instead move the camera a given distance towards a point within a minimum and maximum distance.
+    ///
+    pub fn zoom_towards(&mut self, point: Vec3, factor: f32) {
+        let factor = factor.max(0.0001);
+        let (position, target, up) = (*self.position(), *self.target(), *self.up());
+        let view_direction = self.view_direction();
+        let offset = position - point;
+        let new_position = match *self.projection_type() {
+            three_d_asset::ProjectionType::Perspective { .. } => point + offset * factor,
+            three_d_asset::ProjectionType::Orthographic { height } => {
+                let (z_near, z_far) = (self.z_near(), self.z_far());
+                self.set_orthographic_projection(height * factor, z_near, z_far);
+                let along = view_direction * offset.dot(view_direction);
+                point + along + (offset - along) * factor
+            }
+        };
+        self.set_view(new_position, target + new_position - position, up);
+    }
+
+    // The distance from the camera to the target along the view direction.
+    fn target_depth(&self) -> f32 {
+        (*self.target() - *self.position()).dot(self.view_direction())
+    }
 }
 
 use std::ops::Deref;
@@ -0,0 +1,127 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A point in 3D which is tracked on the screen, for example to place a label, an HTML element or an egui window next to an object.
+/// Call [Anchor::place] each frame, or whenever the camera or the anchor has moved, to get the current [AnchorPlacement].
+///
+/// When the anchor is outside the viewport or behind the camera, it is clamped to the edge of the viewport if [Anchor::clamp_to_edge] is enabled,
+/// and [AnchorPlacement::arrow] gives the direction towards the anchor so an arrow can be drawn at the edge.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Anchor {
+    /// The position in world space.
+    pub position: Vec3,
+    /// An offset in physical pixels added to the projected position, for example to place a label above the anchored point.
+    pub pixel_offset: Vec2,
+    /// Whether or not the anchor is clamped to the edge of the viewport when it is outside the viewport or behind the camera.
+    /// If disabled, [Anchor::place] returns `None` in these cases.
+    pub clamp_to_edge: bool,
+    /// The distance in physical pixels from the edge of the viewport to the position of a clamped anchor.
+    pub edge_margin: f32,
+}
+
+impl Anchor {
+    ///
+    /// Creates a new anchor at the given position in world space, which is clamped to the edge of the viewport with a margin of 16 pixels when it is not visible.
+    ///
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            pixel_offset: vec2(0.0, 0.0),
+            clamp_to_edge: true,
+            edge_margin: 16.0,
+        }
+    }
+
+    ///
+    /// Projects the anchor onto the viewport of the given camera.
+    /// Returns `None` if the anchor is not visible and [Anchor::clamp_to_edge] is disabled.
+    ///
+    pub fn place(&self, camera: &Camera) -> Option<AnchorPlacement> {
+        let viewport = camera.viewport();
+        let half_size = vec2(viewport.width as f32, viewport.height as f32) * 0.5;
+        let clip = camera.projection() * camera.view() * self.position.extend(1.0);
+        let behind = clip.w <= f32::EPSILON || clip.z < -clip.w;
+        let depth = (self.position - *camera.position()).dot(camera.view_direction());
+
+        if !behind {
+            let ndc = vec2(clip.x / clip.w, clip.y / clip.w);
+            if ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 {
+                let p = vec2(half_size.x * (1.0 + ndc.x), half_size.y * (1.0 + ndc.y))
+                    + self.pixel_offset;
+                return Some(AnchorPlacement {
+                    position: PhysicalPoint { x: p.x, y: p.y },
+                    depth,
+                    visible: true,
+                    behind,
+                    arrow: None,
+                    viewport,
+                });
+            }
+        }
+        if !self.clamp_to_edge {
+            return None;
+        }
+
+        // The direction from the center of the viewport towards the anchor, where an anchor behind the camera
+        // is projected without the perspective division, since the division by a negative w mirrors the position
+        let direction = if behind {
+            vec2(clip.x, clip.y)
+        } else {
+            vec2(clip.x / clip.w, clip.y / clip.w)
+        };
+        let mut direction = vec2(half_size.x * direction.x, half_size.y * direction.y);
+        if direction.magnitude2() < f32::EPSILON {
+            // Directly behind the camera
+            direction = vec2(0.0, -1.0);
+        }
+        let direction = direction.normalize();
+        let extent = vec2(
+            (half_size.x - self.edge_margin).max(0.0),
+            (half_size.y - self.edge_margin).max(0.0),
+        );
+        let scale = (extent.x / direction.x.abs()).min(extent.y / direction.y.abs());
+        let p = half_size + direction * scale;
+        Some(AnchorPlacement {
+            position: PhysicalPoint { x: p.x, y: p.y },
+            depth,
+            visible: false,
+            behind,
+            arrow: Some(direction),
+            viewport,
+        })
+    }
+}
+
+///
+/// The placement of an [Anchor] on the screen, see [Anchor::place].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnchorPlacement {
+    /// The position in physical pixels relative to the viewport, where (0, 0) is the bottom left corner and (viewport.width, viewport.height) is the top right corner.
+    /// This can be used directly with a camera created by [Camera::new_2d], for example to place a [Text] or a [Rectangle].
+    pub position: PhysicalPoint,
+    /// The distance from the camera to the anchor along the view direction, which can be used to sort or scale labels.
+    pub depth: f32,
+    /// Whether or not the anchor is inside the viewport and in front of the camera, otherwise the position is clamped to the edge of the viewport.
+    pub visible: bool,
+    /// Whether or not the anchor is behind the camera.
+    pub behind: bool,
+    /// The direction on the screen from the center of the viewport towards the anchor if it is clamped to the edge, where the y-axis points up.
+    pub arrow: Option<Vec2>,
+    viewport: Viewport,
+}
+
+impl AnchorPlacement {
+    ///
+    /// Returns the position in logical pixels relative to the top left corner of the window, which is the coordinate system used by HTML and egui overlays.
+    /// The window height is in logical pixels, see for example `FrameInput::window_height` and `FrameInput::device_pixel_ratio`.
+    ///
+    pub fn overlay_position(&self, window_height: u32, device_pixel_ratio: f32) -> Vec2 {
+        vec2(
+            (self.viewport.x as f32 + self.position.x) / device_pixel_ratio,
+            window_height as f32 - (self.viewport.y as f32 + self.position.y) / device_pixel_ratio,
+        )
+    }
+}
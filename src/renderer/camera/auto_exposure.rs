@@ -0,0 +1,107 @@
+use crate::renderer::*;
+
+///
+/// Automatic exposure, also known as eye adaptation, which adapts the [Camera::exposure] to the brightness of the rendered scene.
+///
+/// Each update builds a histogram of the logarithmic luminance of a HDR color texture on the GPU using [ImageStatistics], reads it back
+/// and computes the exposure needed to map the average luminance to middle grey.
+/// The exposure is then adapted smoothly towards that target with the given speed.
+///
+/// Render the scene into an intermediate HDR texture with the tone mapping disabled (see [Camera::disable_tone_and_color_mapping]),
+/// call [AutoExposure::update] with that texture and then apply the tone mapping with the adapted exposure when copying the texture to the screen,
+/// for example using [ScreenEffect](crate::renderer::ScreenEffect) with a camera where [AutoExposure::apply] has been called.
+///
+pub struct AutoExposure {
+    statistics: ImageStatistics,
+    exposure: f32,
+    /// The speed with which the exposure adapts to the target exposure. A higher value means faster adaptation.
+    pub speed: f32,
+    /// The minimum exposure in stops (EV).
+    pub min_exposure: f32,
+    /// The maximum exposure in stops (EV).
+    pub max_exposure: f32,
+    /// The luminance that the average luminance of the scene is mapped to. The default is middle grey (0.18).
+    pub key_value: f32,
+    /// The minimum logarithmic (base 2) luminance represented in the histogram. Darker pixels are ignored.
+    pub min_log_luminance: f32,
+    /// The maximum logarithmic (base 2) luminance represented in the histogram. Brighter pixels are ignored.
+    pub max_log_luminance: f32,
+    /// The number of samples in each direction used to build the histogram.
+    /// If blending into 32 bit floating point textures is not supported, see [Capabilities::float_blend],
+    /// the number of samples is limited to 45 in each direction, see [ImageStatistics::sampled_histogram].
+    pub sample_count: u32,
+}
+
+impl AutoExposure {
+    const BIN_COUNT: u32 = 64;
+
+    ///
+    /// Creates a new automatic exposure with the exposure initialized to 0 EV.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            statistics: ImageStatistics::new(context),
+            exposure: 0.0,
+            speed: 1.5,
+            min_exposure: -8.0,
+            max_exposure: 8.0,
+            key_value: 0.18,
+            min_log_luminance: -10.0,
+            max_log_luminance: 10.0,
+            sample_count: 64,
+        }
+    }
+
+    ///
+    /// Builds the luminance histogram of the given HDR color texture and adapts the exposure towards the exposure that maps
+    /// the average luminance to the key value.
+    /// The elapsed time is the time in milliseconds since the last update, for example the elapsed time given by the frame input of the window.
+    ///
+    /// **Note:** The histogram is read back from the GPU, which requires a synchronization between the CPU and GPU.
+    ///
+    pub fn update(&mut self, texture: &Texture2D, elapsed_time: f64) {
+        let histogram = self.statistics.sampled_histogram(
+            texture,
+            StatisticsChannel::LogLuminance,
+            Self::BIN_COUNT,
+            self.min_log_luminance,
+            self.max_log_luminance,
+            self.sample_count,
+        );
+        let mut weight = 0.0;
+        let mut log_luminance = 0.0;
+        let bin_size = (self.max_log_luminance - self.min_log_luminance) / Self::BIN_COUNT as f32;
+        for (i, count) in histogram.iter().enumerate() {
+            let value = *count as f32;
+            log_luminance += value * (self.min_log_luminance + (i as f32 + 0.5) * bin_size);
+            weight += value;
+        }
+        if weight > 0.0 {
+            let target = (self.key_value.log2() - log_luminance / weight)
+                .clamp(self.min_exposure, self.max_exposure);
+            let blend = 1.0 - (-self.speed * elapsed_time as f32 * 0.001).exp();
+            self.exposure += (target - self.exposure) * blend.clamp(0.0, 1.0);
+        }
+    }
+
+    ///
+    /// Returns the current adapted exposure in stops (EV).
+    ///
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    ///
+    /// Sets the current exposure in stops (EV) without any adaptation, for example when the camera is moved to a new location.
+    ///
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure.clamp(self.min_exposure, self.max_exposure);
+    }
+
+    ///
+    /// Applies the current adapted exposure to the given camera which is then used in the tone mapping.
+    ///
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.exposure = self.exposure;
+    }
+}
@@ -0,0 +1,171 @@
+use crate::renderer::*;
+
+///
+/// An easing curve which maps the linear progress of an animation in the range `[0..1]` to the progress of the animated value, see [CameraTween].
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Starts slowly and accelerates.
+    EaseIn,
+    /// Starts fast and decelerates.
+    EaseOut,
+    /// Starts slowly, accelerates and decelerates at the end.
+    #[default]
+    EaseInOut,
+    /// A custom easing curve, which should map 0 to 0 and 1 to 1.
+    Custom(fn(f32) -> f32),
+}
+
+impl Easing {
+    ///
+    /// Returns the eased progress given the linear progress in the range `[0..1]`.
+    ///
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t) * (1.0 - t),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Self::Custom(f) => f(t),
+        }
+    }
+}
+
+///
+/// Smoothly moves a [Camera] from one view to another over time, for example to focus on a selected object (see [Camera::look_at_bounding_box]).
+///
+/// The target and the distance from the target to the camera are interpolated linearly and the direction from the target to the camera and the up direction
+/// are interpolated along the shortest arc, so the camera swings around the target instead of cutting through it.
+/// The field of view of a perspective camera, the height of an orthographic camera and the near and far planes are also interpolated.
+/// If the projection type of the start and end view are different, the projection is changed at the end of the animation.
+///
+#[derive(Clone, Debug)]
+pub struct CameraTween {
+    from: Camera,
+    to: Camera,
+    duration: f64,
+    elapsed: f64,
+    finished: bool,
+    /// The easing curve applied to the progress of the animation.
+    pub easing: Easing,
+}
+
+impl CameraTween {
+    ///
+    /// Creates a new animation from the view of the `from` camera to the view of the `to` camera with the given duration in milliseconds.
+    /// Only the view and projection of the cameras are used, the viewport, tone mapping etc. of the animated camera are not changed.
+    ///
+    pub fn new(from: &Camera, to: &Camera, duration: f64, easing: Easing) -> Self {
+        Self {
+            from: from.clone(),
+            to: to.clone(),
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            finished: false,
+            easing,
+        }
+    }
+
+    ///
+    /// Returns the linear progress of the animation in the range `[0..1]`.
+    ///
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0) as f32
+        } else {
+            1.0
+        }
+    }
+
+    ///
+    /// Returns whether or not the animation has reached the end.
+    ///
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    ///
+    /// Advances the animation by the given elapsed time in milliseconds, for example the elapsed time given by the frame input of the window,
+    /// and sets the view and projection of the given camera accordingly.
+    /// Returns whether or not the camera has changed, ie. false when the animation has already finished.
+    ///
+    pub fn update(&mut self, camera: &mut Camera, elapsed_time: f64) -> bool {
+        if self.finished {
+            return false;
+        }
+        self.elapsed = (self.elapsed + elapsed_time.max(0.0)).min(self.duration);
+        self.finished = self.elapsed >= self.duration;
+        self.apply(camera, self.easing.apply(self.progress()));
+        true
+    }
+
+    ///
+    /// Sets the view and projection of the given camera to the interpolation between the start and end view at the given eased progress in the range `[0..1]`,
+    /// without changing the progress of this animation.
+    ///
+    pub fn apply(&self, camera: &mut Camera, t: f32) {
+        let (from, to) = (&self.from, &self.to);
+        let target = *from.target() + (*to.target() - *from.target()) * t;
+        let from_offset = *from.position() - *from.target();
+        let to_offset = *to.position() - *to.target();
+        let distance =
+            from_offset.magnitude() + (to_offset.magnitude() - from_offset.magnitude()) * t;
+        let direction = slerp(from_offset, to_offset, t);
+        let up = slerp(*from.up(), *to.up(), t);
+        camera.set_view(target + direction * distance, target, up);
+
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let (z_near, z_far) = (
+            lerp(from.z_near(), to.z_near()),
+            lerp(from.z_far(), to.z_far()),
+        );
+        match (*from.projection_type(), *to.projection_type()) {
+            (
+                three_d_asset::ProjectionType::Perspective { field_of_view_y: a },
+                three_d_asset::ProjectionType::Perspective { field_of_view_y: b },
+            ) => camera.set_perspective_projection(radians(lerp(a.0, b.0)), z_near, z_far),
+            (
+                three_d_asset::ProjectionType::Orthographic { height: a },
+                three_d_asset::ProjectionType::Orthographic { height: b },
+            ) => camera.set_orthographic_projection(lerp(a, b), z_near, z_far),
+            (from_projection, to_projection) => {
+                match if t < 1.0 {
+                    from_projection
+                } else {
+                    to_projection
+                } {
+                    three_d_asset::ProjectionType::Perspective { field_of_view_y } => {
+                        camera.set_perspective_projection(field_of_view_y, z_near, z_far)
+                    }
+                    three_d_asset::ProjectionType::Orthographic { height } => {
+                        camera.set_orthographic_projection(height, z_near, z_far)
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Interpolates the direction of the two vectors along the shortest arc and returns a unit vector.
+fn slerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    let (a, b) = (a.normalize(), b.normalize());
+    let angle = a.dot(b).clamp(-1.0, 1.0).acos();
+    if angle < 0.0001 {
+        return (a + (b - a) * t).normalize();
+    }
+    let axis = if angle > std::f32::consts::PI - 0.0001 {
+        // Opposite directions, rotate around any perpendicular axis
+        let other = if a.x.abs() < 0.9 {
+            vec3(1.0, 0.0, 0.0)
+        } else {
+            vec3(0.0, 1.0, 0.0)
+        };
+        a.cross(other).normalize()
+    } else {
+        a.cross(b).normalize()
+    };
+    Mat3::from_axis_angle(axis, radians(angle * t)) * a
+}
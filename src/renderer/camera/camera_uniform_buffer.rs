@@ -0,0 +1,68 @@
+use crate::renderer::*;
+
+///
+/// A [UniformBuffer] containing the camera data which can be shared between all programs instead of sending the camera uniforms to each program separately.
+/// Update it once per frame using [CameraUniformBuffer::update] and use it in a program using [CameraUniformBuffer::use_uniform_block].
+/// The uniform block must be declared in the shader as given by [CameraUniformBuffer::fragment_shader_source].
+///
+pub struct CameraUniformBuffer {
+    buffer: UniformBuffer,
+}
+
+impl CameraUniformBuffer {
+    /// The name of the uniform block in the shader.
+    pub const BLOCK_NAME: &'static str = "Camera";
+
+    ///
+    /// Creates a new camera uniform buffer with all values initialized to zero.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            buffer: UniformBuffer::new_with_layout(
+                context,
+                UniformBlockLayout::new()
+                    .add("viewProjection", UniformBlockType::Mat4)
+                    .add("view", UniformBlockType::Mat4)
+                    .add("projection", UniformBlockType::Mat4)
+                    .add("cameraPosition", UniformBlockType::Vec3),
+            ),
+        }
+    }
+
+    ///
+    /// Updates the buffer with the data from the given camera.
+    ///
+    pub fn update(&mut self, camera: &Camera) {
+        let view_projection = camera.projection() * camera.view();
+        let view_projection: &[f32; 16] = view_projection.as_ref();
+        let view: &[f32; 16] = camera.view().as_ref();
+        let projection: &[f32; 16] = camera.projection().as_ref();
+        let position: &[f32; 3] = camera.position().as_ref();
+        self.buffer.update(0, view_projection);
+        self.buffer.update(1, view);
+        self.buffer.update(2, projection);
+        self.buffer.update(3, position);
+    }
+
+    ///
+    /// Uses this buffer as the camera uniform block in the given program.
+    ///
+    pub fn use_uniform_block(&self, program: &Program) {
+        program.use_uniform_block(Self::BLOCK_NAME, &self.buffer);
+    }
+
+    ///
+    /// Returns the declaration of the camera uniform block which can be included in both the vertex and fragment shader.
+    ///
+    pub fn fragment_shader_source() -> &'static str {
+        "
+        layout (std140) uniform Camera
+        {
+            mat4 viewProjection;
+            mat4 view;
+            mat4 projection;
+            vec3 cameraPosition;
+        };
+        "
+    }
+}
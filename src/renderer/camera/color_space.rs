@@ -42,5 +42,6 @@ impl ColorMapping {
     ///
     pub fn use_uniforms(&self, program: &Program) {
         program.use_uniform("ColorMappingType", *self as u32);
+        program.set_srgb_encoded(*self == Self::ComputeToSrgb);
     }
 }
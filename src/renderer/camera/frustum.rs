@@ -0,0 +1,80 @@
+use crate::core::*;
+
+///
+/// The view frustum of a camera given by six planes, used for frustum culling, ie. skipping the objects that are outside the view.
+/// Extract it from a camera using [Camera::frustum](crate::renderer::Camera::frustum) once per frame and reuse it for testing all objects.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    ///
+    /// Extracts the frustum from the given view projection matrix, ie. `projection * view`.
+    ///
+    pub fn from_view_projection(view_projection: Mat4) -> Self {
+        let m = view_projection;
+        let row = |i: usize| vec4(m.x[i], m.y[i], m.z[i], m.w[i]);
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2].map(|plane| {
+            let length = plane.truncate().magnitude();
+            if length > 0.0 {
+                plane / length
+            } else {
+                plane
+            }
+        });
+        Self { planes }
+    }
+
+    ///
+    /// Returns the left, right, bottom, top, near and far planes given as `(normal, distance)`,
+    /// where the normal points into the frustum, ie. a point `p` is inside a plane if `normal.dot(p) + distance >= 0`.
+    ///
+    pub fn planes(&self) -> &[Vec4; 6] {
+        &self.planes
+    }
+
+    ///
+    /// Returns whether or not the given point is inside the frustum.
+    ///
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(point) + plane.w >= 0.0)
+    }
+
+    ///
+    /// Returns whether or not the sphere with the given center and radius intersects the frustum.
+    ///
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+    }
+
+    ///
+    /// Returns whether or not the given bounding box intersects the frustum.
+    /// The test is conservative, ie. a box close to a corner of the frustum may be reported as intersecting although it is outside.
+    /// An empty bounding box never intersects and an infinite bounding box always intersects.
+    ///
+    pub fn intersects_aabb(&self, aabb: &AxisAlignedBoundingBox) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+        let (min, max) = (aabb.min(), aabb.max());
+        if !(min.magnitude2().is_finite() && max.magnitude2().is_finite()) {
+            return true;
+        }
+        self.planes.iter().all(|plane| {
+            // The corner furthest in the direction of the normal
+            let corner = vec3(
+                if plane.x >= 0.0 { max.x } else { min.x },
+                if plane.y >= 0.0 { max.y } else { min.y },
+                if plane.z >= 0.0 { max.z } else { min.z },
+            );
+            plane.truncate().dot(corner) + plane.w >= 0.0
+        })
+    }
+}
@@ -0,0 +1,166 @@
+use crate::renderer::*;
+
+///
+/// Renders the reflection of a scene in a plane, for example a mirror or a calm water surface, into a texture.
+///
+/// The scene is rendered using a camera which is the given camera mirrored about the plane, where the near plane is replaced by the reflection plane
+/// (see [Camera::set_oblique_near_plane]) so everything behind the mirror or below the water surface is clipped away.
+/// A material rendering the mirror or water surface can then look up the reflection by projecting the world position of each fragment
+/// with [PlanarReflection::texture_transformation], ie. `vec4 p = textureTransformation * vec4(position, 1.0); texture(reflectionTexture, p.xy / p.w)`,
+/// optionally offsetting the texture coordinates by the normal of the surface to simulate ripples.
+///
+/// The reflection is rendered with the tone and color mapping disabled, so it should be tone and color mapped by the material sampling it.
+///
+pub struct PlanarReflection {
+    context: Context,
+    color_texture: Texture2D,
+    depth_texture: DepthTexture2D,
+    camera: Option<Camera>,
+    /// A point in the reflection plane.
+    pub point: Vec3,
+    /// The normal of the reflection plane. Only what is on the same side of the plane as the camera is reflected.
+    pub normal: Vec3,
+    /// The resolution of the reflection texture relative to the viewport of the camera. A lower value is faster but gives a blurrier reflection.
+    /// The default is 1.
+    pub resolution_scale: f32,
+    /// The distance the clip plane is moved behind the reflection plane, which can be used to avoid gaps where objects intersect a rippled water surface.
+    /// The default is 0.
+    pub clip_plane_offset: f32,
+    /// The color the reflection texture is cleared to before rendering the scene. The default is transparent black.
+    pub clear_color: Srgba,
+}
+
+impl PlanarReflection {
+    ///
+    /// Creates a new planar reflection in the plane through the given point with the given normal.
+    /// The texture is resized to match the viewport of the camera the first time [PlanarReflection::render] is called.
+    ///
+    pub fn new(context: &Context, point: Vec3, normal: Vec3) -> Self {
+        Self {
+            context: context.clone(),
+            color_texture: new_color_texture(context, 1, 1),
+            depth_texture: new_depth_texture(context, 1, 1),
+            camera: None,
+            point,
+            normal: normal.normalize(),
+            resolution_scale: 1.0,
+            clip_plane_offset: 0.0,
+            clear_color: Srgba::new(0, 0, 0, 0),
+        }
+    }
+
+    ///
+    /// Returns the given point mirrored about the reflection plane.
+    ///
+    pub fn mirror_point(&self, point: Vec3) -> Vec3 {
+        point - 2.0 * self.normal.dot(point - self.point) * self.normal
+    }
+
+    ///
+    /// Returns the given direction mirrored about the reflection plane.
+    ///
+    pub fn mirror_direction(&self, direction: Vec3) -> Vec3 {
+        direction - 2.0 * self.normal.dot(direction) * self.normal
+    }
+
+    ///
+    /// Returns the camera mirrored about the reflection plane used in the last call to [PlanarReflection::render].
+    ///
+    pub fn camera(&self) -> Option<&Camera> {
+        self.camera.as_ref()
+    }
+
+    ///
+    /// Returns the texture containing the reflection rendered in the last call to [PlanarReflection::render].
+    ///
+    pub fn texture(&self) -> &Texture2D {
+        &self.color_texture
+    }
+
+    ///
+    /// Returns the matrix which projects a position in world space into the homogeneous texture coordinates of the reflection texture,
+    /// ie. the texture coordinates are `p.xy / p.w` where `p = texture_transformation * position.extend(1.0)`.
+    ///
+    pub fn texture_transformation(&self) -> Mat4 {
+        let bias_matrix = Mat4::new(
+            0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.5, 0.5, 0.5, 1.0,
+        );
+        self.camera
+            .as_ref()
+            .map(|camera| bias_matrix * camera.projection() * camera.view())
+            .unwrap_or(bias_matrix)
+    }
+
+    ///
+    /// Renders the reflection of the given objects, as seen from the given camera, into the reflection texture.
+    /// The objects should not include the mirror or water surface itself.
+    /// Must not be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method, since it renders into its own render target.
+    ///
+    pub fn render(
+        &mut self,
+        camera: &Camera,
+        objects: impl IntoIterator<Item = impl Object>,
+        lights: &[&dyn Light],
+    ) -> &Texture2D {
+        let viewport = camera.viewport();
+        let scale = self.resolution_scale.max(0.0);
+        let width = ((viewport.width as f32 * scale).round() as u32).max(1);
+        let height = ((viewport.height as f32 * scale).round() as u32).max(1);
+        if self.color_texture.width() != width || self.color_texture.height() != height {
+            self.color_texture = new_color_texture(&self.context, width, height);
+            self.depth_texture = new_depth_texture(&self.context, width, height);
+        }
+
+        // Keep what is on the same side of the plane as the camera
+        let normal = if self.normal.dot(*camera.position() - self.point) >= 0.0 {
+            self.normal
+        } else {
+            -self.normal
+        };
+        let mut mirrored_camera = camera.clone();
+        mirrored_camera.set_viewport(Viewport::new_at_origo(width, height));
+        mirrored_camera.set_view(
+            self.mirror_point(*camera.position()),
+            self.mirror_point(*camera.target()),
+            self.mirror_direction(*camera.up()),
+        );
+        mirrored_camera.set_oblique_near_plane(Some(
+            normal.extend(-normal.dot(self.point) + self.clip_plane_offset),
+        ));
+        mirrored_camera.disable_tone_and_color_mapping();
+
+        let [r, g, b, a]: [f32; 4] = self.clear_color.to_linear_srgb().into();
+        RenderTarget::new(
+            self.color_texture.as_color_target(None),
+            self.depth_texture.as_depth_target(),
+        )
+        .clear(ClearState::color_and_depth(r, g, b, a, 1.0))
+        .render(&mirrored_camera, objects, lights);
+        self.camera = Some(mirrored_camera);
+        &self.color_texture
+    }
+}
+
+fn new_color_texture(context: &Context, width: u32, height: u32) -> Texture2D {
+    Texture2D::new_empty_with_precision(
+        context,
+        context.color_precision(),
+        width,
+        height,
+        Interpolation::Linear,
+        Interpolation::Linear,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    )
+}
+
+fn new_depth_texture(context: &Context, width: u32, height: u32) -> DepthTexture2D {
+    DepthTexture2D::new::<f32>(
+        context,
+        width,
+        height,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    )
+}
@@ -24,8 +24,12 @@ impl ToneMapping {
     pub fn fragment_shader_source() -> &'static str {
         "
         uniform uint toneMappingType;
+        uniform float toneMappingExposure;
 
         vec3 tone_mapping(vec3 color) {
+            if (toneMappingType != 0u) {
+                color *= exp2(toneMappingExposure);
+            }
             if (toneMappingType == 1u) {
                 color = color / (color + vec3(1.0));
                 color = clamp(color, 0.0, 1.0);
@@ -55,6 +59,16 @@ impl ToneMapping {
     /// Sends the uniform data needed to apply this tone mapping to the fragment shader.
     ///
     pub fn use_uniforms(&self, program: &Program) {
+        self.use_uniforms_with_exposure(program, 0.0);
+    }
+
+    ///
+    /// Sends the uniform data needed to apply this tone mapping to the fragment shader.
+    /// The color is scaled by `2^exposure` before the tone mapping is applied, ie. the exposure is given in stops (EV).
+    ///
+    pub fn use_uniforms_with_exposure(&self, program: &Program, exposure: f32) {
         program.use_uniform("toneMappingType", *self as u32);
+        program.set_tone_mapped(*self != Self::None);
+        program.use_uniform_if_required("toneMappingExposure", exposure);
     }
 }
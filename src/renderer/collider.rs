@@ -0,0 +1,423 @@
+use crate::renderer::*;
+
+///
+/// The shape of a [Collider] defined in the local space of the collider.
+/// All shapes are convex, which makes it possible to use the same overlap and ray cast queries for all combinations of shapes.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColliderShape {
+    /// A box defined by its minimum and maximum corner.
+    Aabb {
+        /// The minimum corner of the box.
+        min: Vec3,
+        /// The maximum corner of the box.
+        max: Vec3,
+    },
+    /// A sphere.
+    Sphere {
+        /// The center of the sphere.
+        center: Vec3,
+        /// The radius of the sphere.
+        radius: f32,
+    },
+    /// A capsule, ie. all points within the given radius from the line segment between the two end points.
+    Capsule {
+        /// The first end point of the line segment.
+        start: Vec3,
+        /// The second end point of the line segment.
+        end: Vec3,
+        /// The radius of the capsule.
+        radius: f32,
+    },
+    /// The convex hull of a set of points.
+    ConvexHull {
+        /// The points spanning the convex hull.
+        points: Vec<Vec3>,
+    },
+}
+
+impl ColliderShape {
+    ///
+    /// Returns the convex hull of the vertex positions of the given mesh.
+    ///
+    pub fn convex_hull(cpu_mesh: &CpuMesh) -> Self {
+        Self::ConvexHull {
+            points: cpu_mesh.positions.to_f32(),
+        }
+    }
+
+    ///
+    /// Returns the box enclosing the given bounding box.
+    ///
+    pub fn aabb(aabb: AxisAlignedBoundingBox) -> Self {
+        Self::Aabb {
+            min: aabb.min(),
+            max: aabb.max(),
+        }
+    }
+
+    ///
+    /// Returns the point of the shape which is furthest in the given direction.
+    ///
+    pub fn support(&self, direction: Vec3) -> Vec3 {
+        let unit = |d: Vec3| {
+            if d.magnitude2() > 0.0 {
+                d.normalize()
+            } else {
+                vec3(0.0, 0.0, 0.0)
+            }
+        };
+        match self {
+            Self::Aabb { min, max } => vec3(
+                if direction.x >= 0.0 { max.x } else { min.x },
+                if direction.y >= 0.0 { max.y } else { min.y },
+                if direction.z >= 0.0 { max.z } else { min.z },
+            ),
+            Self::Sphere { center, radius } => center + unit(direction) * *radius,
+            Self::Capsule { start, end, radius } => {
+                let end_point = if direction.dot(end - start) >= 0.0 {
+                    end
+                } else {
+                    start
+                };
+                end_point + unit(direction) * *radius
+            }
+            Self::ConvexHull { points } => points
+                .iter()
+                .copied()
+                .max_by(|a, b| direction.dot(*a).total_cmp(&direction.dot(*b)))
+                .unwrap_or(vec3(0.0, 0.0, 0.0)),
+        }
+    }
+}
+
+///
+/// A simple convex collider used for overlap and ray cast queries, for example for placement, snapping and trigger volumes.
+/// This is not a physics engine, the colliders do not move or respond to collisions.
+///
+/// The shape is defined in local space and transformed to world space by the [Collider::transformation],
+/// which is usually the same transformation as the one applied to the object the collider is attached to.
+/// See [ColliderSet] for attaching colliders to objects and querying all of them at once.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Collider {
+    /// The shape of the collider in local space.
+    pub shape: ColliderShape,
+    /// The local to world transformation applied to the shape.
+    pub transformation: Mat4,
+}
+
+impl Collider {
+    ///
+    /// Creates a new collider with the given shape and an identity transformation.
+    ///
+    pub fn new(shape: ColliderShape) -> Self {
+        Self {
+            shape,
+            transformation: Mat4::identity(),
+        }
+    }
+
+    ///
+    /// Returns the point of the collider in world space which is furthest in the given world space direction.
+    ///
+    pub fn support(&self, direction: Vec3) -> Vec3 {
+        let m = self.transformation;
+        let linear = Mat3::from_cols(m.x.truncate(), m.y.truncate(), m.z.truncate());
+        linear * self.shape.support(linear.transpose() * direction) + m.w.truncate()
+    }
+
+    ///
+    /// Returns the axis aligned bounding box of the collider in world space.
+    ///
+    pub fn aabb(&self) -> AxisAlignedBoundingBox {
+        let min = vec3(
+            self.support(vec3(-1.0, 0.0, 0.0)).x,
+            self.support(vec3(0.0, -1.0, 0.0)).y,
+            self.support(vec3(0.0, 0.0, -1.0)).z,
+        );
+        let max = vec3(
+            self.support(vec3(1.0, 0.0, 0.0)).x,
+            self.support(vec3(0.0, 1.0, 0.0)).y,
+            self.support(vec3(0.0, 0.0, 1.0)).z,
+        );
+        AxisAlignedBoundingBox::new_with_positions(&[min, max])
+    }
+
+    ///
+    /// Returns the point on or inside the collider which is closest to the given world space point.
+    /// If the point is inside the collider, the point itself is returned.
+    ///
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        point + closest_to_origin(|d| self.support(d) - point)
+    }
+
+    ///
+    /// Returns the distance from the given world space point to the collider, which is zero if the point is inside the collider.
+    ///
+    pub fn distance(&self, point: Vec3) -> f32 {
+        self.closest_point(point).distance(point)
+    }
+
+    ///
+    /// Returns whether or not the given world space point is inside the collider.
+    ///
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.distance(point) <= EPSILON
+    }
+
+    ///
+    /// Returns whether or not this collider overlaps the other collider.
+    ///
+    pub fn overlaps(&self, other: &Collider) -> bool {
+        let (a, b) = (self.aabb(), other.aabb());
+        if a.min().x > b.max().x
+            || a.min().y > b.max().y
+            || a.min().z > b.max().z
+            || b.min().x > a.max().x
+            || b.min().y > a.max().y
+            || b.min().z > a.max().z
+        {
+            return false;
+        }
+        closest_to_origin(|d| self.support(d) - other.support(-d)).magnitude() <= EPSILON
+    }
+
+    ///
+    /// Finds the closest intersection between the collider and the ray starting at the given world space position in the given direction.
+    /// Returns the distance from the start of the ray to the intersection or `None` if the ray does not hit the collider within the given maximum distance.
+    /// If the start of the ray is inside the collider, the distance is zero.
+    ///
+    pub fn ray_intersect(&self, position: Vec3, direction: Vec3, max_distance: f32) -> Option<f32> {
+        let direction = direction.normalize();
+        let mut distance = 0.0;
+        // Conservative advancement: the plane through the closest point, orthogonal to the direction towards the ray, separates the ray from the collider
+        for _ in 0..MAX_ITERATIONS {
+            let point = position + direction * distance;
+            let offset = point - self.closest_point(point);
+            let gap = offset.magnitude();
+            if gap <= EPSILON {
+                return Some(distance);
+            }
+            let cos_angle = -direction.dot(offset) / gap;
+            if cos_angle <= 0.0 {
+                return None;
+            }
+            distance += gap / cos_angle;
+            if distance > max_distance {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+///
+/// A set of [Collider]s, each attached to an object identified by a key, for example a [Handle] to an object in a [TrackedScene] or a [ResourcePool].
+///
+pub struct ColliderSet<K: Copy + PartialEq> {
+    colliders: Vec<(K, Collider)>,
+}
+
+impl<K: Copy + PartialEq> ColliderSet<K> {
+    ///
+    /// Creates a new empty collider set.
+    ///
+    pub fn new() -> Self {
+        Self {
+            colliders: Vec::new(),
+        }
+    }
+
+    ///
+    /// Attaches the collider to the object with the given key, replacing any collider already attached to that object.
+    ///
+    pub fn insert(&mut self, key: K, collider: Collider) {
+        match self.get_mut(key) {
+            Some(c) => *c = collider,
+            None => self.colliders.push((key, collider)),
+        }
+    }
+
+    ///
+    /// Removes and returns the collider attached to the object with the given key.
+    ///
+    pub fn remove(&mut self, key: K) -> Option<Collider> {
+        let index = self.colliders.iter().position(|(k, _)| *k == key)?;
+        Some(self.colliders.swap_remove(index).1)
+    }
+
+    ///
+    /// Returns the collider attached to the object with the given key.
+    ///
+    pub fn get(&self, key: K) -> Option<&Collider> {
+        self.colliders
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, c)| c)
+    }
+
+    ///
+    /// Returns the collider attached to the object with the given key, for example to update the transformation when the object moves.
+    ///
+    pub fn get_mut(&mut self, key: K) -> Option<&mut Collider> {
+        self.colliders
+            .iter_mut()
+            .find(|(k, _)| *k == key)
+            .map(|(_, c)| c)
+    }
+
+    ///
+    /// Returns an iterator over the keys and colliders in the set.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = &(K, Collider)> {
+        self.colliders.iter()
+    }
+
+    ///
+    /// Returns the keys of the objects with a collider that overlaps the given collider, for example a trigger volume.
+    ///
+    pub fn overlapping(&self, collider: &Collider) -> Vec<K> {
+        self.colliders
+            .iter()
+            .filter(|(_, c)| c.overlaps(collider))
+            .map(|(k, _)| *k)
+            .collect()
+    }
+
+    ///
+    /// Returns the keys of the objects with a collider that contains the given world space point.
+    ///
+    pub fn containing(&self, point: Vec3) -> Vec<K> {
+        self.colliders
+            .iter()
+            .filter(|(_, c)| c.contains(point))
+            .map(|(k, _)| *k)
+            .collect()
+    }
+
+    ///
+    /// Finds the closest intersection between the colliders and the ray starting at the given world space position in the given direction.
+    /// Returns the key of the hit object and the distance from the start of the ray to the intersection,
+    /// or `None` if the ray does not hit any collider within the given maximum distance.
+    ///
+    pub fn ray_intersect(
+        &self,
+        position: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<(K, f32)> {
+        let mut closest: Option<(K, f32)> = None;
+        for (key, collider) in self.colliders.iter() {
+            let max = closest.map(|(_, d)| d).unwrap_or(max_distance);
+            if let Some(distance) = collider.ray_intersect(position, direction, max) {
+                closest = Some((*key, distance));
+            }
+        }
+        closest
+    }
+}
+
+impl<K: Copy + PartialEq> Default for ColliderSet<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const EPSILON: f32 = 0.0001;
+const MAX_ITERATIONS: usize = 64;
+
+///
+/// Returns the point of the convex set, given by the support function, which is closest to the origin, using the GJK distance algorithm.
+///
+fn closest_to_origin(support: impl Fn(Vec3) -> Vec3) -> Vec3 {
+    let mut simplex = vec![support(vec3(1.0, 0.0, 0.0))];
+    let mut closest = simplex[0];
+    for _ in 0..MAX_ITERATIONS {
+        let distance2 = closest.magnitude2();
+        if distance2 <= EPSILON * EPSILON {
+            return closest;
+        }
+        let w = support(-closest);
+        if distance2 - closest.dot(w) <= EPSILON * distance2.sqrt() || simplex.contains(&w) {
+            return closest;
+        }
+        simplex.push(w);
+        let (point, support_set) = closest_on_simplex(&simplex);
+        closest = point;
+        simplex = support_set;
+    }
+    closest
+}
+
+///
+/// Returns the point of the simplex, with up to four vertices, which is closest to the origin together with the vertices of the smallest sub-simplex containing that point.
+///
+fn closest_on_simplex(simplex: &[Vec3]) -> (Vec3, Vec<Vec3>) {
+    let mut best: Option<(Vec3, Vec<Vec3>)> = None;
+    for subset in 1..(1usize << simplex.len()) {
+        let points = simplex
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| subset & (1 << i) != 0)
+            .map(|(_, p)| *p)
+            .collect::<Vec<_>>();
+        if let Some(point) = project_origin(&points) {
+            if best
+                .as_ref()
+                .map(|(b, _)| point.magnitude2() < b.magnitude2())
+                .unwrap_or(true)
+            {
+                best = Some((point, points));
+            }
+        }
+    }
+    best.unwrap_or_else(|| (simplex[0], vec![simplex[0]]))
+}
+
+///
+/// Projects the origin onto the affine hull of the points and returns the projection if it lies strictly inside the convex hull of the points.
+///
+fn project_origin(points: &[Vec3]) -> Option<Vec3> {
+    let p0 = points[0];
+    let edges = points[1..].iter().map(|p| p - p0).collect::<Vec<_>>();
+    let weights = match edges.len() {
+        0 => vec![],
+        1 => {
+            let length2 = edges[0].magnitude2();
+            if length2 <= f32::EPSILON {
+                return None;
+            }
+            vec![-edges[0].dot(p0) / length2]
+        }
+        2 => {
+            let (a, b) = (edges[0], edges[1]);
+            let (aa, ab, bb) = (a.dot(a), a.dot(b), b.dot(b));
+            let determinant = aa * bb - ab * ab;
+            if determinant.abs() <= f32::EPSILON {
+                return None;
+            }
+            let (ra, rb) = (-a.dot(p0), -b.dot(p0));
+            vec![
+                (ra * bb - rb * ab) / determinant,
+                (rb * aa - ra * ab) / determinant,
+            ]
+        }
+        _ => {
+            let matrix = Mat3::from_cols(edges[0], edges[1], edges[2]);
+            let inverse = matrix.invert()?;
+            let w = inverse * -p0;
+            vec![w.x, w.y, w.z]
+        }
+    };
+    let sum = weights.iter().sum::<f32>();
+    if weights.iter().any(|w| *w <= 0.0) || sum >= 1.0 {
+        return None;
+    }
+    Some(
+        edges
+            .iter()
+            .zip(weights.iter())
+            .fold(p0, |acc, (e, w)| acc + e * *w),
+    )
+}
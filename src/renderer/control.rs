@@ -18,6 +18,14 @@ mod fly_control;
 #[doc(inline)]
 pub use fly_control::*;
 
+mod orientation_gizmo;
+#[doc(inline)]
+pub use orientation_gizmo::*;
+
+mod scale_bar;
+#[doc(inline)]
+pub use scale_bar::*;
+
 pub use three_d_asset::PixelPoint as PhysicalPoint;
 
 /// Type of mouse button.
@@ -80,6 +88,19 @@ pub enum Event {
         /// Whether or not this event already have been handled.
         handled: bool,
     },
+    /// Fired continuously when two fingers on the screen are moved.
+    Pinch {
+        /// The change in distance between the two fingers since the last [Event::Pinch] event in logical pixels, positive when the fingers move apart.
+        delta: f32,
+        /// The relative movement of the point between the two fingers since the last [Event::Pinch] event in logical pixels.
+        translation: (f32, f32),
+        /// The screen position of the point between the two fingers in physical pixels.
+        position: PhysicalPoint,
+        /// The state of modifiers.
+        modifiers: Modifiers,
+        /// Whether or not this event already have been handled.
+        handled: bool,
+    },
     /// Fired when the mouse enters the window.
     MouseEnter,
     /// Fired when the mouse leaves the window.
@@ -137,6 +137,12 @@ impl CameraControl {
                         change |= *handled;
                     }
                 }
+                Event::Pinch { delta, handled, .. } => {
+                    if !*handled {
+                        *handled = self.handle_action(camera, self.scroll_vertical, *delta);
+                        change |= *handled;
+                    }
+                }
                 _ => {}
             }
         }
@@ -180,10 +186,37 @@ impl CameraControl {
                 min,
                 max,
             } => {
-                camera.zoom_towards(&target, speed * x, min, max);
+                three_d_asset::Camera::zoom_towards(camera, &target, speed * x, min, max);
             }
             CameraAction::None => {}
         }
         control_type != CameraAction::None
     }
 }
+
+// The fraction of a damped movement that remains after the given elapsed time in milliseconds,
+// where the damping is the fraction that remains after one frame at 60 frames per second.
+pub(super) fn remaining_fraction(damping: f32, elapsed_time: f64) -> f32 {
+    if damping <= 0.0 {
+        0.0
+    } else {
+        damping
+            .min(0.99)
+            .powf((elapsed_time.max(0.0) * 60.0 / 1000.0) as f32)
+    }
+}
+
+// Rotates the direction around the up direction by the yaw angle and then towards the up direction by the pitch angle,
+// without passing the up direction or the opposite direction.
+pub(super) fn rotate_with_fixed_up(direction: Vec3, up: Vec3, yaw: f32, pitch: f32) -> Vec3 {
+    let up = up.normalize();
+    let direction = Mat3::from_axis_angle(up, radians(yaw)) * direction;
+    let polar = direction.normalize().dot(up).clamp(-1.0, 1.0).acos();
+    let new_polar = (polar - pitch).clamp(0.01, std::f32::consts::PI - 0.01);
+    // Rotating around this axis by a positive angle increases the angle to the up direction
+    let axis = up.cross(direction);
+    if axis.magnitude2() < 0.000001 {
+        return direction;
+    }
+    Mat3::from_axis_angle(axis.normalize(), radians(new_polar - polar)) * direction
+}
@@ -1,31 +1,124 @@
+use super::camera_control::{remaining_fraction, rotate_with_fixed_up};
+use super::fly_control::MovementKeys;
 use crate::renderer::*;
 
 ///
 /// A control that makes the camera move like it is a person on the ground.
 ///
+/// - Dragging with the left mouse button or one finger turns the camera around the up direction and looks up and down, without tilting the horizon.
+/// - Scrolling or pinching moves the camera forward and backward in the horizontal plane.
+/// - The W, A, S and D or arrow keys move the camera forward, left, backward and right in the horizontal plane.
+///
+/// The up direction is the up direction of the camera.
+/// If [FirstPersonControl::damping] is larger than zero, the camera keeps moving and gradually slows down after the input stops,
+/// so [FirstPersonControl::update] must be called each frame, also when there are no events.
+///
 pub struct FirstPersonControl {
-    control: CameraControl,
+    /// The distance the camera moves per scroll unit.
+    pub speed: f32,
+    /// The distance the camera moves per second while a movement key is pressed.
+    pub keyboard_speed: f32,
+    /// The rotation in radians per logical pixel when dragging.
+    pub rotation_speed: f32,
+    /// The fraction of the movement that is kept to the next frame, at 60 frames per second, after the input stops, in the range `[0..1)`.
+    /// Zero means no damping, ie. the camera stops immediately, and a value close to one means that the camera slows down slowly. The default is 0.
+    pub damping: f32,
+    keys: MovementKeys,
+    rotation_velocity: Vec2,
+    move_velocity: f32,
 }
 
 impl FirstPersonControl {
     /// Creates a new first person control with the given speed of movements.
     pub fn new(speed: f32) -> Self {
         Self {
-            control: CameraControl {
-                left_drag_horizontal: CameraAction::Yaw {
-                    speed: std::f32::consts::PI / 1800.0,
-                },
-                left_drag_vertical: CameraAction::Pitch {
-                    speed: std::f32::consts::PI / 1800.0,
-                },
-                scroll_vertical: CameraAction::Forward { speed },
-                ..Default::default()
-            },
+            speed,
+            keyboard_speed: 50.0 * speed,
+            rotation_speed: std::f32::consts::PI / 1800.0,
+            damping: 0.0,
+            keys: MovementKeys::default(),
+            rotation_velocity: vec2(0.0, 0.0),
+            move_velocity: 0.0,
         }
     }
 
-    /// Handles the events. Must be called each frame.
+    /// Handles the events, assuming 60 frames per second, see [FirstPersonControl::update]. Must be called each frame.
     pub fn handle_events(&mut self, camera: &mut Camera, events: &mut [Event]) -> bool {
-        self.control.handle_events(camera, events)
+        self.update(camera, events, 1000.0 / 60.0)
+    }
+
+    ///
+    /// Handles the events and moves the camera, including the damped movement from previous frames and the movement from the pressed keys.
+    /// The elapsed time is the time in milliseconds since the last update, for example the elapsed time given by the frame input of the window.
+    /// Must be called each frame. Returns whether or not the camera has changed.
+    ///
+    pub fn update(&mut self, camera: &mut Camera, events: &mut [Event], elapsed_time: f64) -> bool {
+        for event in events.iter_mut() {
+            match event {
+                Event::MouseMotion {
+                    delta,
+                    button: Some(MouseButton::Left),
+                    handled,
+                    ..
+                } if !*handled => {
+                    self.rotation_velocity += vec2(delta.0, delta.1);
+                    *handled = true;
+                }
+                Event::MouseWheel { delta, handled, .. } if !*handled => {
+                    self.move_velocity += delta.1 * self.speed;
+                    *handled = true;
+                }
+                Event::Pinch { delta, handled, .. } if !*handled => {
+                    self.move_velocity += *delta * self.speed;
+                    *handled = true;
+                }
+                _ => {}
+            }
+        }
+        self.keys.handle_events(events);
+
+        let remaining = remaining_fraction(self.damping, elapsed_time);
+        let rotation = self.rotation_velocity * (1.0 - remaining);
+        let keys = self.keys.direction();
+        let movement = vec2(0.0, self.move_velocity * (1.0 - remaining))
+            + vec2(keys.x, keys.z) * self.keyboard_speed * (elapsed_time / 1000.0) as f32;
+        self.rotation_velocity = if remaining * self.rotation_velocity.magnitude() > 0.01 {
+            self.rotation_velocity * remaining
+        } else {
+            vec2(0.0, 0.0)
+        };
+        self.move_velocity = if remaining * self.move_velocity.abs() > 0.0001 * self.speed {
+            self.move_velocity * remaining
+        } else {
+            0.0
+        };
+
+        let up = camera.up().normalize();
+        let position = *camera.position();
+        let mut change = false;
+        if rotation.magnitude2() > 0.0 {
+            let distance = camera.target().distance(position).max(0.000001);
+            let direction = rotate_with_fixed_up(
+                camera.view_direction(),
+                up,
+                -rotation.x * self.rotation_speed,
+                -rotation.y * self.rotation_speed,
+            );
+            camera.set_view(position, position + direction * distance, up);
+            change = true;
+        }
+        if movement.magnitude2() > 0.0 {
+            // Move in the horizontal plane
+            let forward = camera.view_direction() - up * camera.view_direction().dot(up);
+            let forward = if forward.magnitude2() > 0.000001 {
+                forward.normalize()
+            } else {
+                up.cross(camera.right_direction()).normalize()
+            };
+            let right = forward.cross(up);
+            camera.translate(&(right * movement.x + forward * movement.y));
+            change = true;
+        }
+        change
     }
 }
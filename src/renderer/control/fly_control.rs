@@ -1,33 +1,175 @@
+use super::camera_control::remaining_fraction;
 use crate::renderer::*;
 
 ///
 /// A control that makes the camera fly through the 3D scene.
 ///
+/// - Dragging with the left mouse button or one finger rotates the camera around its right and up direction.
+/// - Dragging with the right or middle mouse button or two fingers moves the camera sideways and up and down.
+/// - Scrolling or pinching moves the camera forward and backward.
+/// - The W, A, S and D or arrow keys move the camera forward, left, backward and right and the Q and E keys move the camera down and up.
+///
+/// If [FlyControl::damping] is larger than zero, the camera keeps moving and gradually slows down after the input stops,
+/// so [FlyControl::update] must be called each frame, also when there are no events.
+///
 pub struct FlyControl {
-    control: CameraControl,
+    /// The distance the camera moves per scroll unit and per logical pixel when dragging.
+    pub speed: f32,
+    /// The distance the camera moves per second while a movement key is pressed.
+    pub keyboard_speed: f32,
+    /// The rotation in radians per logical pixel when dragging.
+    pub rotation_speed: f32,
+    /// The fraction of the movement that is kept to the next frame, at 60 frames per second, after the input stops, in the range `[0..1)`.
+    /// Zero means no damping, ie. the camera stops immediately, and a value close to one means that the camera slows down slowly. The default is 0.
+    pub damping: f32,
+    keys: MovementKeys,
+    rotation_velocity: Vec2,
+    move_velocity: Vec3,
 }
 
 impl FlyControl {
     /// Creates a new fly control with the given speed of movements.
     pub fn new(speed: f32) -> Self {
         Self {
-            control: CameraControl {
-                left_drag_horizontal: CameraAction::Yaw {
-                    speed: std::f32::consts::PI / 1800.0,
-                },
-                left_drag_vertical: CameraAction::Pitch {
-                    speed: std::f32::consts::PI / 1800.0,
-                },
-                scroll_vertical: CameraAction::Forward { speed },
-                right_drag_horizontal: CameraAction::Left { speed },
-                right_drag_vertical: CameraAction::Up { speed },
-                ..Default::default()
-            },
+            speed,
+            keyboard_speed: 50.0 * speed,
+            rotation_speed: std::f32::consts::PI / 1800.0,
+            damping: 0.0,
+            keys: MovementKeys::default(),
+            rotation_velocity: vec2(0.0, 0.0),
+            move_velocity: vec3(0.0, 0.0, 0.0),
         }
     }
 
-    /// Handles the events. Must be called each frame.
+    /// Handles the events, assuming 60 frames per second, see [FlyControl::update]. Must be called each frame.
     pub fn handle_events(&mut self, camera: &mut Camera, events: &mut [Event]) -> bool {
-        self.control.handle_events(camera, events)
+        self.update(camera, events, 1000.0 / 60.0)
+    }
+
+    ///
+    /// Handles the events and moves the camera, including the damped movement from previous frames and the movement from the pressed keys.
+    /// The elapsed time is the time in milliseconds since the last update, for example the elapsed time given by the frame input of the window.
+    /// Must be called each frame. Returns whether or not the camera has changed.
+    ///
+    pub fn update(&mut self, camera: &mut Camera, events: &mut [Event], elapsed_time: f64) -> bool {
+        for event in events.iter_mut() {
+            match event {
+                Event::MouseMotion {
+                    delta,
+                    button: Some(button),
+                    handled,
+                    ..
+                } if !*handled => {
+                    match button {
+                        MouseButton::Left => {
+                            self.rotation_velocity += vec2(delta.0, delta.1);
+                        }
+                        MouseButton::Right | MouseButton::Middle => {
+                            self.move_velocity += vec3(-delta.0, delta.1, 0.0) * self.speed;
+                        }
+                    }
+                    *handled = true;
+                }
+                Event::MouseWheel { delta, handled, .. } if !*handled => {
+                    self.move_velocity.z += delta.1 * self.speed;
+                    *handled = true;
+                }
+                Event::Pinch {
+                    delta,
+                    translation,
+                    handled,
+                    ..
+                } if !*handled => {
+                    self.move_velocity += vec3(-translation.0, translation.1, *delta) * self.speed;
+                    *handled = true;
+                }
+                _ => {}
+            }
+        }
+        self.keys.handle_events(events);
+
+        let remaining = remaining_fraction(self.damping, elapsed_time);
+        let rotation = self.rotation_velocity * (1.0 - remaining);
+        let movement = self.move_velocity * (1.0 - remaining)
+            + self.keys.direction() * self.keyboard_speed * (elapsed_time / 1000.0) as f32;
+        self.rotation_velocity = if remaining * self.rotation_velocity.magnitude() > 0.01 {
+            self.rotation_velocity * remaining
+        } else {
+            vec2(0.0, 0.0)
+        };
+        self.move_velocity = if remaining * self.move_velocity.magnitude() > 0.0001 * self.speed {
+            self.move_velocity * remaining
+        } else {
+            vec3(0.0, 0.0, 0.0)
+        };
+
+        let mut change = false;
+        if rotation.magnitude2() > 0.0 {
+            camera.yaw(radians(rotation.x * self.rotation_speed));
+            camera.pitch(radians(rotation.y * self.rotation_speed));
+            change = true;
+        }
+        if movement.magnitude2() > 0.0 {
+            let right = camera.right_direction();
+            let up = right.cross(camera.view_direction()).normalize();
+            let change_in_position =
+                right * movement.x + up * movement.y + camera.view_direction() * movement.z;
+            camera.translate(&change_in_position);
+            change = true;
+        }
+        change
+    }
+}
+
+///
+/// Keeps track of the keys used for moving the camera in [FlyControl] and [FirstPersonControl].
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct MovementKeys {
+    forward: bool,
+    backward: bool,
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+}
+
+impl MovementKeys {
+    pub(super) fn handle_events(&mut self, events: &mut [Event]) {
+        for event in events.iter_mut() {
+            let (kind, pressed, handled) = match event {
+                Event::KeyPress { kind, handled, .. } => (*kind, true, handled),
+                Event::KeyRelease { kind, handled, .. } => (*kind, false, handled),
+                Event::MouseLeave => {
+                    *self = Self::default();
+                    continue;
+                }
+                _ => continue,
+            };
+            if *handled && pressed {
+                continue;
+            }
+            let key = match kind {
+                Key::W | Key::ArrowUp => &mut self.forward,
+                Key::S | Key::ArrowDown => &mut self.backward,
+                Key::A | Key::ArrowLeft => &mut self.left,
+                Key::D | Key::ArrowRight => &mut self.right,
+                Key::E => &mut self.up,
+                Key::Q => &mut self.down,
+                _ => continue,
+            };
+            *key = pressed;
+            *handled = true;
+        }
+    }
+
+    // The direction given by the pressed keys as (right, up, forward).
+    pub(super) fn direction(&self) -> Vec3 {
+        let axis = |positive: bool, negative: bool| positive as i32 as f32 - negative as i32 as f32;
+        vec3(
+            axis(self.right, self.left),
+            axis(self.up, self.down),
+            axis(self.forward, self.backward),
+        )
     }
 }
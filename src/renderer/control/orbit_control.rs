@@ -1,44 +1,214 @@
+use super::camera_control::{remaining_fraction, rotate_with_fixed_up};
 use crate::renderer::*;
 
 ///
 /// A control that makes the camera orbit around a target.
 ///
+/// - Dragging with the left mouse button or one finger orbits the camera around the target while keeping the up direction of the camera fixed.
+/// - If [OrbitControl::enable_pan] is enabled, dragging with the right or middle mouse button or two fingers pans the camera and the target in the view plane.
+/// - Scrolling or pinching zooms towards the target or, if [OrbitControl::zoom_to_cursor] is enabled, towards the point under the cursor.
+///
+/// If [OrbitControl::damping] is larger than zero, the camera keeps moving and gradually slows down after the input stops,
+/// so [OrbitControl::update] must be called each frame, also when there are no events.
+///
 pub struct OrbitControl {
-    control: CameraControl,
+    /// The point the camera orbits around. The target is moved when panning and when zooming towards the cursor.
+    pub target: Vec3,
+    /// The minimum distance from the camera to the target. For an orthographic camera, this is the minimum height of the view.
+    pub min_distance: f32,
+    /// The maximum distance from the camera to the target. For an orthographic camera, this is the maximum height of the view.
+    pub max_distance: f32,
+    /// The rotation in radians per logical pixel when orbiting.
+    pub rotation_speed: f32,
+    /// The relative change in the distance to the target per scroll unit when zooming.
+    pub zoom_speed: f32,
+    /// Whether or not panning is enabled. The default is false.
+    pub enable_pan: bool,
+    /// Whether to zoom towards the point under the cursor instead of towards the target.
+    pub zoom_to_cursor: bool,
+    /// The fraction of the movement that is kept to the next frame, at 60 frames per second, after the input stops, in the range `[0..1)`.
+    /// Zero means no damping, ie. the camera stops immediately, and a value close to one means that the camera slows down slowly. The default is 0.
+    pub damping: f32,
+    orbit_velocity: Vec2,
+    pan_velocity: Vec2,
+    zoom_velocity: f32,
+    zoom_pixel: Option<PhysicalPoint>,
 }
 
 impl OrbitControl {
     /// Creates a new orbit control with the given target and minimum and maximum distance to the target.
     pub fn new(target: Vec3, min_distance: f32, max_distance: f32) -> Self {
         Self {
-            control: CameraControl {
-                left_drag_horizontal: CameraAction::OrbitLeft { target, speed: 0.1 },
-                left_drag_vertical: CameraAction::OrbitUp { target, speed: 0.1 },
-                scroll_vertical: CameraAction::Zoom {
-                    min: min_distance,
-                    max: max_distance,
-                    speed: 0.1,
-                    target,
-                },
-                ..Default::default()
-            },
+            target,
+            min_distance,
+            max_distance,
+            rotation_speed: 0.01,
+            zoom_speed: 0.01,
+            enable_pan: false,
+            zoom_to_cursor: false,
+            damping: 0.0,
+            orbit_velocity: vec2(0.0, 0.0),
+            pan_velocity: vec2(0.0, 0.0),
+            zoom_velocity: 0.0,
+            zoom_pixel: None,
         }
     }
 
-    /// Handles the events. Must be called each frame.
+    /// Handles the events, assuming 60 frames per second, see [OrbitControl::update]. Must be called each frame.
     pub fn handle_events(&mut self, camera: &mut Camera, events: &mut [Event]) -> bool {
-        if let CameraAction::Zoom { speed, target, .. } = &mut self.control.scroll_vertical {
-            let x = target.distance(*camera.position());
-            *speed = 0.01 * x + 0.001;
+        self.update(camera, events, 1000.0 / 60.0)
+    }
+
+    ///
+    /// Handles the events and moves the camera, including the damped movement from previous frames.
+    /// The elapsed time is the time in milliseconds since the last update, for example the elapsed time given by the frame input of the window.
+    /// Must be called each frame. Returns whether or not the camera has changed.
+    ///
+    pub fn update(&mut self, camera: &mut Camera, events: &mut [Event], elapsed_time: f64) -> bool {
+        for event in events.iter_mut() {
+            match event {
+                Event::MouseMotion {
+                    delta,
+                    button: Some(button),
+                    handled,
+                    ..
+                } if !*handled => match button {
+                    MouseButton::Left => {
+                        self.orbit_velocity += vec2(delta.0, delta.1);
+                        *handled = true;
+                    }
+                    MouseButton::Right | MouseButton::Middle if self.enable_pan => {
+                        self.pan_velocity += vec2(delta.0, delta.1);
+                        *handled = true;
+                    }
+                    _ => {}
+                },
+                Event::MouseWheel {
+                    delta,
+                    position,
+                    handled,
+                    ..
+                } if !*handled => {
+                    self.zoom_velocity += delta.1;
+                    self.zoom_pixel = Some(*position);
+                    *handled = true;
+                }
+                Event::Pinch {
+                    delta,
+                    translation,
+                    position,
+                    handled,
+                    ..
+                } if !*handled => {
+                    self.zoom_velocity += *delta;
+                    self.zoom_pixel = Some(*position);
+                    if self.enable_pan {
+                        self.pan_velocity += vec2(translation.0, translation.1);
+                    }
+                    *handled = true;
+                }
+                _ => {}
+            }
+        }
+
+        let remaining = remaining_fraction(self.damping, elapsed_time);
+        let orbit = self.orbit_velocity * (1.0 - remaining);
+        let pan = self.pan_velocity * (1.0 - remaining);
+        let zoom = self.zoom_velocity * (1.0 - remaining);
+        self.orbit_velocity = if remaining * self.orbit_velocity.magnitude() > 0.01 {
+            self.orbit_velocity * remaining
+        } else {
+            vec2(0.0, 0.0)
+        };
+        self.pan_velocity = if remaining * self.pan_velocity.magnitude() > 0.01 {
+            self.pan_velocity * remaining
+        } else {
+            vec2(0.0, 0.0)
+        };
+        self.zoom_velocity = if remaining * self.zoom_velocity.abs() > 0.001 {
+            self.zoom_velocity * remaining
+        } else {
+            0.0
+        };
+
+        let mut change = false;
+        if orbit.magnitude2() > 0.0 {
+            self.orbit(camera, orbit);
+            change = true;
         }
-        if let CameraAction::OrbitLeft { speed, target } = &mut self.control.left_drag_horizontal {
-            let x = target.distance(*camera.position());
-            *speed = 0.01 * x + 0.001;
+        if pan.magnitude2() > 0.0 {
+            self.pan(camera, pan);
+            change = true;
         }
-        if let CameraAction::OrbitUp { speed, target } = &mut self.control.left_drag_vertical {
-            let x = target.distance(*camera.position());
-            *speed = 0.01 * x + 0.001;
+        if zoom != 0.0 {
+            self.zoom(camera, zoom);
+            change = true;
         }
-        self.control.handle_events(camera, events)
+        change
+    }
+
+    fn orbit(&self, camera: &mut Camera, delta: Vec2) {
+        let up = camera.up().normalize();
+        let offset = rotate_with_fixed_up(
+            *camera.position() - self.target,
+            up,
+            -delta.x * self.rotation_speed,
+            delta.y * self.rotation_speed,
+        );
+        camera.set_view(self.target + offset, self.target, up);
+    }
+
+    fn pan(&mut self, camera: &mut Camera, delta: Vec2) {
+        let right = camera.right_direction();
+        let up = right.cross(camera.view_direction()).normalize();
+        let change = (up * delta.y - right * delta.x) * self.units_per_pixel(camera);
+        camera.translate(&change);
+        self.target += change;
+    }
+
+    fn zoom(&mut self, camera: &mut Camera, delta: f32) {
+        let position = *camera.position();
+        let pixel = self.zoom_pixel.filter(|_| self.zoom_to_cursor);
+        let scale = (-self.zoom_speed * delta).exp();
+        let (z_near, z_far) = (camera.z_near(), camera.z_far());
+        match *camera.projection_type() {
+            three_d_asset::ProjectionType::Perspective { .. } => {
+                let distance = self.target.distance(position).max(0.000001);
+                let scale = scale.clamp(
+                    self.min_distance / distance,
+                    self.max_distance.max(self.min_distance) / distance,
+                );
+                // The point that stays fixed on the screen while zooming
+                let center = if let Some(pixel) = pixel {
+                    let direction = camera.view_direction_at_pixel(pixel);
+                    let view_direction = camera.view_direction();
+                    position
+                        + direction * (self.target - position).dot(view_direction)
+                            / direction.dot(view_direction).max(0.000001)
+                } else {
+                    self.target
+                };
+                self.target = center + (self.target - center) * scale;
+                let up = camera.up().normalize();
+                camera.set_view(center + (position - center) * scale, self.target, up);
+            }
+            three_d_asset::ProjectionType::Orthographic { height } => {
+                let scale = scale.clamp(
+                    self.min_distance / height.max(0.000001),
+                    self.max_distance.max(self.min_distance) / height.max(0.000001),
+                );
+                if let Some(pixel) = pixel {
+                    let change = (camera.position_at_pixel(pixel) - position) * (1.0 - scale);
+                    camera.translate(&change);
+                    self.target += change;
+                }
+                camera.set_orthographic_projection(height * scale, z_near, z_far);
+            }
+        }
+    }
+
+    // The length in world space of a pixel at the distance of the target.
+    fn units_per_pixel(&self, camera: &Camera) -> f32 {
+        camera.pixel_size_at((self.target - *camera.position()).dot(camera.view_direction()))
     }
 }
@@ -0,0 +1,208 @@
+use crate::renderer::*;
+
+///
+/// The corner of the screen in which a widget, for example the [OrientationGizmo] or the [ScaleBar], is placed.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GizmoCorner {
+    /// The top left corner.
+    TopLeft,
+    /// The top right corner.
+    TopRight,
+    /// The bottom left corner.
+    BottomLeft,
+    /// The bottom right corner.
+    BottomRight,
+}
+
+///
+/// A small widget rendered in a corner of the screen which shows the orientation of the camera using three axes (the x-axis is red, the y-axis is green and the z-axis is blue).
+/// Clicking one of the six handles at the end of the axes aligns the camera view with that axis, keeping the distance to the camera target.
+///
+pub struct OrientationGizmo {
+    axes: Axes,
+    handles: Gm<InstancedMesh, ColorMaterial>,
+    /// The size of the widget in physical pixels.
+    pub size: u32,
+    /// The distance in physical pixels from the edges of the screen to the widget.
+    pub margin: u32,
+    /// The corner of the screen in which the widget is placed.
+    pub corner: GizmoCorner,
+}
+
+const HANDLE_RADIUS: f32 = 0.15;
+
+impl OrientationGizmo {
+    ///
+    /// Creates a new orientation gizmo with the given size in physical pixels placed in the given corner of the screen.
+    ///
+    pub fn new(context: &Context, size: u32, corner: GizmoCorner) -> Self {
+        let mut sphere = CpuMesh::sphere(16);
+        sphere.transform(&Mat4::from_scale(HANDLE_RADIUS)).unwrap();
+        let handles = Gm::new(
+            InstancedMesh::new(
+                context,
+                &Instances {
+                    transformations: Self::directions()
+                        .iter()
+                        .map(|d| Mat4::from_translation(*d))
+                        .collect(),
+                    texture_transformations: None,
+                    colors: Some(vec![
+                        Srgba::RED,
+                        Srgba::new_opaque(128, 40, 40),
+                        Srgba::GREEN,
+                        Srgba::new_opaque(40, 128, 40),
+                        Srgba::BLUE,
+                        Srgba::new_opaque(40, 40, 128),
+                    ]),
+                    texture_layers: None,
+                },
+                &sphere,
+            ),
+            ColorMaterial::default(),
+        );
+        Self {
+            axes: Axes::new(context, 0.04, 1.0),
+            handles,
+            size,
+            margin: 10,
+            corner,
+        }
+    }
+
+    ///
+    /// Returns the viewport of the widget given the viewport of the screen.
+    ///
+    pub fn viewport(&self, screen_viewport: Viewport) -> Viewport {
+        let left = matches!(self.corner, GizmoCorner::TopLeft | GizmoCorner::BottomLeft);
+        let bottom = matches!(
+            self.corner,
+            GizmoCorner::BottomLeft | GizmoCorner::BottomRight
+        );
+        let x = if left {
+            screen_viewport.x + self.margin as i32
+        } else {
+            screen_viewport.x + screen_viewport.width as i32 - (self.size + self.margin) as i32
+        };
+        let y = if bottom {
+            screen_viewport.y + self.margin as i32
+        } else {
+            screen_viewport.y + screen_viewport.height as i32 - (self.size + self.margin) as i32
+        };
+        Viewport {
+            x,
+            y,
+            width: self.size,
+            height: self.size,
+        }
+    }
+
+    ///
+    /// Renders the widget on top of the content of the given render target, showing the orientation of the given camera.
+    ///
+    pub fn render(&self, target: &RenderTarget, camera: &Camera) {
+        let gizmo_camera = self.gizmo_camera(camera);
+        let scissor_box = ScissorBox::from(gizmo_camera.viewport());
+        target
+            .clear_partially(scissor_box, ClearState::depth(1.0))
+            .render_partially(
+                scissor_box,
+                &gizmo_camera,
+                self.axes.into_iter().chain(&self.handles),
+                &[],
+            );
+    }
+
+    ///
+    /// Handles the events. If one of the handles is clicked, the camera view is aligned with the corresponding axis.
+    /// Returns whether or not the camera has changed.
+    ///
+    pub fn handle_events(&mut self, camera: &mut Camera, events: &mut [Event]) -> bool {
+        let mut change = false;
+        for event in events.iter_mut() {
+            if let Event::MousePress {
+                button: MouseButton::Left,
+                position,
+                handled,
+                ..
+            } = event
+            {
+                if *handled {
+                    continue;
+                }
+                let gizmo_camera = self.gizmo_camera(camera);
+                let viewport = gizmo_camera.viewport();
+                if position.x < viewport.x as f32
+                    || position.x > (viewport.x + viewport.width as i32) as f32
+                    || position.y < viewport.y as f32
+                    || position.y > (viewport.y + viewport.height as i32) as f32
+                {
+                    continue;
+                }
+                *handled = true;
+                if let Some(direction) = self.pick_handle(&gizmo_camera, *position) {
+                    let target = *camera.target();
+                    let distance = target.distance(*camera.position());
+                    let up = if direction.y.abs() > 0.99 {
+                        vec3(0.0, 0.0, -direction.y)
+                    } else {
+                        vec3(0.0, 1.0, 0.0)
+                    };
+                    camera.set_view(target + direction * distance, target, up);
+                    change = true;
+                }
+            }
+        }
+        change
+    }
+
+    fn directions() -> [Vec3; 6] {
+        [
+            vec3(1.0, 0.0, 0.0),
+            vec3(-1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(0.0, -1.0, 0.0),
+            vec3(0.0, 0.0, 1.0),
+            vec3(0.0, 0.0, -1.0),
+        ]
+    }
+
+    fn gizmo_camera(&self, camera: &Camera) -> Camera {
+        let direction = camera.view_direction();
+        let mut gizmo_camera = Camera::new_orthographic(
+            self.viewport(camera.viewport()),
+            -3.0 * direction,
+            vec3(0.0, 0.0, 0.0),
+            *camera.up(),
+            2.0 + 2.0 * HANDLE_RADIUS,
+            0.1,
+            10.0,
+        );
+        gizmo_camera.disable_tone_and_color_mapping();
+        gizmo_camera.color_mapping = camera.color_mapping;
+        gizmo_camera
+    }
+
+    fn pick_handle(&self, gizmo_camera: &Camera, position: PhysicalPoint) -> Option<Vec3> {
+        let viewport = gizmo_camera.viewport();
+        let view_projection = gizmo_camera.projection() * gizmo_camera.view();
+        let radius = HANDLE_RADIUS / (2.0 + 2.0 * HANDLE_RADIUS) * viewport.height as f32;
+        Self::directions()
+            .into_iter()
+            .filter_map(|direction| {
+                let clip = view_projection * direction.extend(1.0);
+                let pixel = vec2(
+                    viewport.x as f32 + (0.5 * clip.x / clip.w + 0.5) * viewport.width as f32,
+                    viewport.y as f32 + (0.5 * clip.y / clip.w + 0.5) * viewport.height as f32,
+                );
+                if pixel.distance(vec2(position.x, position.y)) <= radius {
+                    Some((direction, clip.z / clip.w))
+                } else {
+                    None
+                }
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(direction, _)| direction)
+    }
+}
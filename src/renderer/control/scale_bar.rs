@@ -0,0 +1,273 @@
+use crate::renderer::*;
+
+///
+/// A scale bar rendered in a corner of the screen which shows the length of a bar in scene units, for example "50 mm", and optionally rulers along the bottom and left edges of the screen.
+/// The length is calibrated from the given camera each time [ScaleBar::update] is called and is always rounded to a value that is easy to read,
+/// ie. one, two or five times a power of ten, which is shown with at most [ScaleBar::max_length] pixels.
+///
+/// For an orthographic camera, the scale is the same everywhere on the screen. For a perspective camera, the scale depends on the distance to the camera,
+/// so the scale is calibrated at the distance from the camera to the camera target.
+///
+pub struct ScaleBar {
+    context: Context,
+    ticks: Gm<InstancedMesh, ColorMaterial>,
+    labels: Vec<Text>,
+    label_count: usize,
+    /// The name of the scene unit, for example "m", which is shown after the lengths.
+    pub unit: String,
+    /// Whether the unit is a metric base unit, in which case the lengths are shown with a metric prefix, for example "5 um" and "20 km" instead of "0.000005 m" and "20000 m".
+    /// Only the prefixes nano (n), micro (u), milli (m) and kilo (k) are used.
+    pub metric_prefixes: bool,
+    /// The maximum length of the scale bar in physical pixels.
+    pub max_length: u32,
+    /// The distance in physical pixels from the edges of the screen to the scale bar.
+    pub margin: u32,
+    /// The corner of the screen in which the scale bar is placed.
+    pub corner: GizmoCorner,
+    /// Whether to also show rulers along the bottom and left edges of the screen,
+    /// showing the coordinates in scene units along the right and up direction of the camera.
+    pub rulers: bool,
+    /// The color of the scale bar, rulers and labels.
+    pub color: Srgba,
+    /// The thickness of the lines in physical pixels.
+    pub thickness: f32,
+    /// The height of the text in physical pixels.
+    pub text_size: f32,
+}
+
+// The length in pixels of the major ticks, the minor ticks are half as long.
+const TICK_LENGTH: f32 = 8.0;
+// The approximate distance in pixels between the major ticks of the rulers.
+const RULER_SPACING: f32 = 100.0;
+
+impl ScaleBar {
+    ///
+    /// Creates a new scale bar placed in the given corner of the screen showing lengths in the given unit with metric prefixes.
+    /// Call [ScaleBar::update] before rendering.
+    ///
+    pub fn new(context: &Context, unit: &str, corner: GizmoCorner) -> Self {
+        Self {
+            context: context.clone(),
+            ticks: Gm::new(
+                InstancedMesh::new(context, &Instances::default(), &CpuMesh::square()),
+                ColorMaterial::default(),
+            ),
+            labels: Vec::new(),
+            label_count: 0,
+            unit: unit.to_string(),
+            metric_prefixes: true,
+            max_length: 150,
+            margin: 10,
+            corner,
+            rulers: false,
+            color: Srgba::WHITE,
+            thickness: 2.0,
+            text_size: 16.0,
+        }
+    }
+
+    ///
+    /// Returns the length in scene units covered by one physical pixel on the screen at the distance from the camera to the camera target.
+    ///
+    pub fn units_per_pixel(camera: &Camera) -> f32 {
+        let height = match camera.projection_type() {
+            three_d_asset::ProjectionType::Orthographic { height } => *height,
+            three_d_asset::ProjectionType::Perspective { field_of_view_y } => {
+                let distance = (*camera.target() - *camera.position()).dot(camera.view_direction());
+                2.0 * distance * (0.5 * field_of_view_y.0).tan()
+            }
+        };
+        height / camera.viewport().height.max(1) as f32
+    }
+
+    ///
+    /// Returns the length of the scale bar in scene units and in physical pixels for the given camera.
+    ///
+    pub fn length(&self, camera: &Camera) -> (f32, f32) {
+        let units_per_pixel = Self::units_per_pixel(camera);
+        let length = nice_length(units_per_pixel * self.max_length as f32);
+        (length, length / units_per_pixel)
+    }
+
+    ///
+    /// Updates the length of the scale bar and the rulers to match the given camera. Call this each frame before rendering or at least each time the camera has changed.
+    ///
+    pub fn update(&mut self, camera: &Camera) {
+        let viewport = camera.viewport();
+        let (width, height) = (viewport.width as f32, viewport.height as f32);
+        let units_per_pixel = Self::units_per_pixel(camera);
+        let mut quads = Vec::new();
+        let mut labels = Vec::new();
+
+        // The scale bar
+        let (length, pixels) = self.length(camera);
+        let label = self.format(length, length);
+        let text_width = self.text_width(&label);
+        let box_width = pixels.max(text_width);
+        let box_height = self.text_size + 4.0 + TICK_LENGTH;
+        let margin = self.margin as f32;
+        let x = if matches!(self.corner, GizmoCorner::TopLeft | GizmoCorner::BottomLeft) {
+            margin
+        } else {
+            width - margin - box_width
+        };
+        let y = if matches!(
+            self.corner,
+            GizmoCorner::BottomLeft | GizmoCorner::BottomRight
+        ) {
+            margin
+        } else {
+            height - margin - box_height
+        };
+        let x = x + 0.5 * (box_width - pixels);
+        quads.push(quad(vec2(x, y), vec2(pixels, self.thickness)));
+        let subdivisions = subdivisions(length);
+        for i in 0..=subdivisions {
+            let tick = if i == 0 || i == subdivisions {
+                TICK_LENGTH
+            } else {
+                0.5 * TICK_LENGTH
+            };
+            let tick_x = x + pixels * i as f32 / subdivisions as f32 - 0.5 * self.thickness;
+            quads.push(quad(vec2(tick_x, y), vec2(self.thickness, tick)));
+        }
+        labels.push((label, vec2(x + 0.5 * (pixels - text_width), y + box_height)));
+
+        // The rulers
+        if self.rulers {
+            let step = nice_length(units_per_pixel * RULER_SPACING);
+            let subdivisions = subdivisions(step);
+            let minor_step = step / subdivisions as f32;
+            let right = camera.right_direction();
+            let up = right.cross(camera.view_direction()).normalize();
+            let target = *camera.target();
+            for (axis, size) in [(0, width), (1, height)] {
+                // The coordinate at the center of the screen
+                let center = if axis == 0 {
+                    target.dot(right)
+                } else {
+                    target.dot(up)
+                };
+                let first = ((center - 0.5 * size * units_per_pixel) / minor_step).ceil() as i64;
+                let last = ((center + 0.5 * size * units_per_pixel) / minor_step).floor() as i64;
+                for i in first..=last {
+                    let value = i as f32 * minor_step;
+                    let pixel = 0.5 * size + (value - center) / units_per_pixel;
+                    let major = i.rem_euclid(subdivisions as i64) == 0;
+                    let tick = if major {
+                        TICK_LENGTH
+                    } else {
+                        0.5 * TICK_LENGTH
+                    };
+                    if axis == 0 {
+                        quads.push(quad(
+                            vec2(pixel - 0.5 * self.thickness, 0.0),
+                            vec2(self.thickness, tick),
+                        ));
+                    } else {
+                        quads.push(quad(
+                            vec2(0.0, pixel - 0.5 * self.thickness),
+                            vec2(tick, self.thickness),
+                        ));
+                    }
+                    if major {
+                        let label = self.format(value, step);
+                        let position = if axis == 0 {
+                            vec2(
+                                pixel - 0.5 * self.text_width(&label),
+                                TICK_LENGTH + 2.0 + self.text_size,
+                            )
+                        } else {
+                            vec2(TICK_LENGTH + 2.0, pixel + 0.5 * self.text_size)
+                        };
+                        labels.push((label, position));
+                    }
+                }
+            }
+        }
+
+        self.ticks.set_instances(&Instances {
+            transformations: quads,
+            ..Default::default()
+        });
+        self.ticks.material.color = self.color;
+        while self.labels.len() < labels.len() {
+            self.labels.push(Text::new(&self.context, ""));
+        }
+        for (text, (label, position)) in self.labels.iter_mut().zip(labels.iter()) {
+            text.set_text(label);
+            text.set_transformation(Mat4::from_translation(position.extend(0.0)));
+            text.size = self.text_size;
+            text.color = self.color;
+        }
+        self.label_count = labels.len();
+    }
+
+    ///
+    /// Renders the scale bar and rulers on top of the content of the given render target, using the viewport of the given camera.
+    /// This clears the depth buffer within the viewport, so render this after the rest of the scene.
+    ///
+    pub fn render(&self, target: &RenderTarget, camera: &Camera) {
+        let mut overlay_camera = Camera::new_2d(camera.viewport());
+        overlay_camera.disable_tone_and_color_mapping();
+        overlay_camera.color_mapping = camera.color_mapping;
+        let scissor_box = ScissorBox::from(camera.viewport());
+        target
+            .clear_partially(scissor_box, ClearState::depth(1.0))
+            .render_partially(
+                scissor_box,
+                &overlay_camera,
+                std::iter::once(&self.ticks as &dyn Object).chain(
+                    self.labels[..self.label_count]
+                        .iter()
+                        .map(|l| l as &dyn Object),
+                ),
+                &[],
+            );
+    }
+
+    // Formats the given value, which is a multiple of the given step, using the metric prefix of the step if enabled.
+    fn format(&self, value: f32, step: f32) -> String {
+        let (scale, prefix) = if self.metric_prefixes {
+            [(1e3, "k"), (1.0, ""), (1e-3, "m"), (1e-6, "u"), (1e-9, "n")]
+                .into_iter()
+                .find(|(scale, _)| step >= 0.999 * scale)
+                .unwrap_or((1.0, ""))
+        } else {
+            (1.0, "")
+        };
+        // Show enough decimals to distinguish the multiples of the step
+        let decimals = (-(step / scale).log10().floor()).max(0.0) as usize;
+        format!("{:.*} {}{}", decimals, value / scale, prefix, self.unit)
+    }
+
+    fn text_width(&self, text: &str) -> f32 {
+        text.chars().count() as f32 * self.text_size * 0.75
+    }
+}
+
+// Returns the largest value, which is one, two or five times a power of ten, that is smaller than or equal to the given value.
+fn nice_length(max: f32) -> f32 {
+    let power = 10f32.powf(max.max(f32::MIN_POSITIVE).log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|m| m * power)
+        .find(|l| *l <= max * 1.0001)
+        .unwrap_or(power)
+}
+
+// The number of minor divisions of a length given by [nice_length].
+fn subdivisions(length: f32) -> u32 {
+    let power = 10f32.powf(length.log10().floor());
+    if (length / power).round() as u32 == 2 {
+        4
+    } else {
+        5
+    }
+}
+
+// The transformation of the square from -1 to 1 into a rectangle with the given bottom left corner and size.
+fn quad(corner: Vec2, size: Vec2) -> Mat4 {
+    Mat4::from_translation((corner + 0.5 * size).extend(0.0))
+        * Mat4::from_nonuniform_scale(0.5 * size.x, 0.5 * size.y, 1.0)
+}
@@ -41,6 +41,10 @@ macro_rules! impl_effect_body {
         fn render_states(&self) -> RenderStates {
             self.$inner().render_states()
         }
+
+        fn shader_defines(&self) -> ShaderDefines {
+            self.$inner().shader_defines()
+        }
     };
 }
 
@@ -64,7 +68,28 @@ mod water;
 #[doc(inline)]
 pub use water::*;
 
+mod overlay;
+#[doc(inline)]
+pub use overlay::*;
+
+mod custom;
+#[doc(inline)]
+pub use custom::*;
+
+mod image_processor;
+#[doc(inline)]
+pub use image_processor::*;
+
+mod image_statistics;
+#[doc(inline)]
+pub use image_statistics::*;
+
+mod temporal_upsampler;
+#[doc(inline)]
+pub use temporal_upsampler::*;
+
 pub(crate) mod lighting_pass;
+pub(crate) mod weighted_blended_composite;
 
 use crate::renderer::*;
 use std::ops::Deref;
@@ -114,6 +139,15 @@ pub trait Effect {
     /// Returns the render states needed to render with this effect.
     ///
     fn render_states(&self) -> RenderStates;
+
+    ///
+    /// Returns the [ShaderDefines] which are prepended to the fragment shader source when applying this effect.
+    /// Each combination of defines results in a separately cached program, so the defines can differ between effects
+    /// without changing [Effect::id].
+    ///
+    fn shader_defines(&self) -> ShaderDefines {
+        ShaderDefines::default()
+    }
 }
 
 impl<T: Effect + ?Sized> Effect for &T {
@@ -176,4 +210,8 @@ impl<T: Effect> Effect for std::sync::RwLock<T> {
     fn render_states(&self) -> RenderStates {
         self.read().unwrap().render_states()
     }
+
+    fn shader_defines(&self) -> ShaderDefines {
+        self.read().unwrap().shader_defines()
+    }
 }
@@ -85,6 +85,7 @@ impl Effect for CopyEffect {
             cull: Cull::Back,
             write_mask: self.write_mask,
             blend: self.blend,
+            ..Default::default()
         }
     }
 }
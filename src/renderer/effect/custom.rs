@@ -0,0 +1,246 @@
+use crate::renderer::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+///
+/// The value of a user defined uniform in a [CustomEffect], see [CustomEffect::set_uniform].
+///
+#[derive(Clone)]
+pub enum EffectUniform {
+    /// A `float` uniform.
+    Float(f32),
+    /// A `vec2` uniform.
+    Vec2(Vec2),
+    /// A `vec3` uniform.
+    Vec3(Vec3),
+    /// A `vec4` uniform.
+    Vec4(Vec4),
+    /// An `int` uniform.
+    Int(i32),
+    /// A `mat3` uniform.
+    Mat3(Mat3),
+    /// A `mat4` uniform.
+    Mat4(Mat4),
+    /// A `sampler2D` uniform.
+    Texture(Texture2DRef),
+}
+
+impl From<f32> for EffectUniform {
+    fn from(value: f32) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<Vec2> for EffectUniform {
+    fn from(value: Vec2) -> Self {
+        Self::Vec2(value)
+    }
+}
+
+impl From<Vec3> for EffectUniform {
+    fn from(value: Vec3) -> Self {
+        Self::Vec3(value)
+    }
+}
+
+impl From<Vec4> for EffectUniform {
+    fn from(value: Vec4) -> Self {
+        Self::Vec4(value)
+    }
+}
+
+impl From<i32> for EffectUniform {
+    fn from(value: i32) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<Mat3> for EffectUniform {
+    fn from(value: Mat3) -> Self {
+        Self::Mat3(value)
+    }
+}
+
+impl From<Mat4> for EffectUniform {
+    fn from(value: Mat4) -> Self {
+        Self::Mat4(value)
+    }
+}
+
+impl From<Texture2DRef> for EffectUniform {
+    fn from(value: Texture2DRef) -> Self {
+        Self::Texture(value)
+    }
+}
+
+///
+/// A post effect defined by a fragment shader written by the user, for example a night vision or thermal view effect.
+/// Apply it in the same way as the built-in effects, for example using [RenderTarget::apply_screen_effect].
+///
+/// The fragment shader source is the body of a GLSL 3.30 fragment shader where the following is available:
+/// - `in vec2 uvs;` The uv coordinates of the fragment on the screen.
+/// - `layout (location = 0) out vec4 outColor;` The output color.
+/// - `vec4 sample_color(vec2 uv)` Samples the color texture given to the effect, if any.
+/// - `float sample_depth(vec2 uv)` Samples the depth texture given to the effect, if any.
+/// - `uniform vec2 resolution;` The size of the viewport in pixels.
+/// - `uniform float zNear;` and `uniform float zFar;` The near and far plane of the camera.
+/// - `vec3 tone_mapping(vec3 color)` and `vec3 color_mapping(vec3 color)` Applies the tone mapping and color mapping defined in the [Camera].
+/// - The uniforms specified with [CustomEffect::set_uniform].
+///
+/// ```
+/// # use three_d::*;
+/// let mut effect = CustomEffect::new("
+///     uniform float strength;
+///     void main() {
+///         vec3 color = sample_color(uvs).rgb;
+///         float intensity = dot(color, vec3(0.299, 0.587, 0.114));
+///         outColor = vec4(mix(color, vec3(0.0, intensity, 0.0), strength), 1.0);
+///     }
+/// ");
+/// effect.set_uniform("strength", 0.8);
+/// ```
+///
+#[derive(Clone)]
+pub struct CustomEffect {
+    source: String,
+    source_hash: u64,
+    uniforms: HashMap<String, EffectUniform>,
+    /// The render states used when applying the effect.
+    pub render_states: RenderStates,
+}
+
+impl CustomEffect {
+    ///
+    /// Creates a new custom effect with the given fragment shader source, see [CustomEffect] for a description of what is available in the shader.
+    ///
+    pub fn new(fragment_shader_source: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        fragment_shader_source.hash(&mut hasher);
+        Self {
+            source: fragment_shader_source.to_string(),
+            source_hash: hasher.finish(),
+            uniforms: HashMap::new(),
+            render_states: RenderStates {
+                write_mask: WriteMask::COLOR,
+                depth_test: DepthTest::Always,
+                cull: Cull::Back,
+                ..Default::default()
+            },
+        }
+    }
+
+    ///
+    /// Sets the value of the uniform with the given name, which is sent to the shader each time the effect is applied.
+    ///
+    pub fn set_uniform(&mut self, name: &str, value: impl Into<EffectUniform>) {
+        self.uniforms.insert(name.to_string(), value.into());
+    }
+
+    ///
+    /// Removes the uniform with the given name.
+    ///
+    pub fn remove_uniform(&mut self, name: &str) {
+        self.uniforms.remove(name);
+    }
+}
+
+impl Effect for CustomEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}{}{}{}
+            uniform vec2 resolution;
+            uniform float zNear;
+            uniform float zFar;
+
+            in vec2 uvs;
+            layout (location = 0) out vec4 outColor;
+
+            {}",
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            color_texture
+                .map(|t| t.fragment_shader_source())
+                .unwrap_or("".to_string()),
+            depth_texture
+                .map(|t| t.fragment_shader_source())
+                .unwrap_or("".to_string()),
+            self.source
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        // Bit 14 is not set, which separates custom effects from the built-in effects,
+        // while the different sources are separated by the define returned from shader_defines
+        0b1u16 << 13
+            | color_texture.map(|t| t.id()).unwrap_or(0u16)
+            | depth_texture.map(|t| t.id()).unwrap_or(0u16)
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        if program.requires_uniform("toneMappingType") {
+            camera
+                .tone_mapping
+                .use_uniforms_with_exposure(program, camera.exposure);
+        }
+        if program.requires_uniform("ColorMappingType") {
+            camera.color_mapping.use_uniforms(program);
+        }
+        if let Some(color_texture) = color_texture {
+            color_texture.use_uniforms(program);
+        }
+        if let Some(depth_texture) = depth_texture {
+            depth_texture.use_uniforms(program);
+        }
+        let viewport = camera.viewport();
+        program.use_uniform_if_required(
+            "resolution",
+            vec2(viewport.width as f32, viewport.height as f32),
+        );
+        program.use_uniform_if_required("zNear", camera.z_near());
+        program.use_uniform_if_required("zFar", camera.z_far());
+        for (name, value) in self.uniforms.iter() {
+            if !program.requires_uniform(name) {
+                continue;
+            }
+            match value {
+                EffectUniform::Float(v) => program.use_uniform(name, v),
+                EffectUniform::Vec2(v) => program.use_uniform(name, v),
+                EffectUniform::Vec3(v) => program.use_uniform(name, v),
+                EffectUniform::Vec4(v) => program.use_uniform(name, v),
+                EffectUniform::Int(v) => program.use_uniform(name, v),
+                EffectUniform::Mat3(v) => program.use_uniform(name, v),
+                EffectUniform::Mat4(v) => program.use_uniform(name, v),
+                EffectUniform::Texture(texture) => program.use_texture(name, texture),
+            }
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn shader_defines(&self) -> ShaderDefines {
+        // The hash of the source is part of the program key, so each source is compiled into a separate program
+        ShaderDefines::new([format!("CUSTOM_EFFECT_{:016x}", self.source_hash)])
+    }
+}
@@ -75,7 +75,9 @@ impl Effect for FogEffect {
         color_texture: Option<ColorTexture>,
         depth_texture: Option<DepthTexture>,
     ) {
-        camera.tone_mapping.use_uniforms(program);
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
         camera.color_mapping.use_uniforms(program);
         color_texture
             .expect("Must supply a color texture to apply a fog effect")
@@ -77,7 +77,9 @@ impl Effect for ScreenEffect {
         depth_texture: Option<DepthTexture>,
     ) {
         if let Some(color_texture) = color_texture {
-            camera.tone_mapping.use_uniforms(program);
+            camera
+                .tone_mapping
+                .use_uniforms_with_exposure(program, camera.exposure);
             camera.color_mapping.use_uniforms(program);
             color_texture.use_uniforms(program);
         }
@@ -92,6 +94,7 @@ impl Effect for ScreenEffect {
             cull: Cull::Back,
             write_mask: self.write_mask,
             blend: self.blend,
+            ..Default::default()
         }
     }
 }
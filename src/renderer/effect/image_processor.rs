@@ -0,0 +1,199 @@
+use crate::renderer::*;
+
+struct ImagePass {
+    effect: Box<dyn Effect>,
+    size: Option<(u32, u32)>,
+}
+
+///
+/// Runs a chain of [Effect]s over an input texture and returns the result in a new texture, for example to blur, resize or color grade an image on the GPU
+/// without setting up a scene. Each pass renders a full screen quad with the output of the previous pass as the color texture, see [ColorTexture],
+/// so any effect, including a [CustomEffect], can be used as a pass.
+///
+/// The intermediate results are stored in RGBA 16 bit floating point textures, or 32 bit if [ImageProcessor::set_high_precision] is enabled,
+/// and the effects are applied without tone mapping and color mapping.
+///
+/// ```no_run
+/// # use three_d::*;
+/// # let context: Context = unimplemented!();
+/// # let image: Texture2D = unimplemented!();
+/// let mut processor = ImageProcessor::new(&context);
+/// processor.add_blur(4.0).add_resize(128, 128);
+/// let thumbnail = processor.process::<[u8; 4]>(&image);
+/// ```
+///
+pub struct ImageProcessor {
+    context: Context,
+    passes: Vec<ImagePass>,
+    high_precision: bool,
+}
+
+impl ImageProcessor {
+    ///
+    /// Creates a new image processor without any passes.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            passes: Vec::new(),
+            high_precision: false,
+        }
+    }
+
+    ///
+    /// Enables or disables storing the intermediate results in 32 bit floating point textures instead of textures with the [Context::color_precision],
+    /// which is needed when processing data that is not colors, for example depth values.
+    ///
+    pub fn set_high_precision(&mut self, high_precision: bool) {
+        self.high_precision = high_precision;
+    }
+
+    ///
+    /// Adds a pass which applies the given effect. The size of the output of the pass is the same as the size of the input.
+    ///
+    pub fn add_effect(&mut self, effect: impl Effect + 'static) -> &mut Self {
+        self.passes.push(ImagePass {
+            effect: Box::new(effect),
+            size: None,
+        });
+        self
+    }
+
+    ///
+    /// Adds a pass which applies the given effect and outputs an image with the given size.
+    ///
+    pub fn add_effect_with_size(
+        &mut self,
+        effect: impl Effect + 'static,
+        width: u32,
+        height: u32,
+    ) -> &mut Self {
+        self.passes.push(ImagePass {
+            effect: Box::new(effect),
+            size: Some((width, height)),
+        });
+        self
+    }
+
+    ///
+    /// Adds a pass which resizes the image to the given size using linear interpolation.
+    ///
+    pub fn add_resize(&mut self, width: u32, height: u32) -> &mut Self {
+        self.add_effect_with_size(CopyEffect::default(), width, height)
+    }
+
+    ///
+    /// Adds two passes which blur the image using a separable gaussian blur with the given radius in pixels.
+    ///
+    pub fn add_blur(&mut self, radius: f32) -> &mut Self {
+        for direction in [vec2(1.0, 0.0), vec2(0.0, 1.0)] {
+            let mut effect = CustomEffect::new(include_str!("shaders/gaussian_blur.frag"));
+            effect.set_uniform("direction", direction);
+            effect.set_uniform("radius", radius);
+            self.add_effect(effect);
+        }
+        self
+    }
+
+    ///
+    /// Adds a pass which multiplies the RGBA color of each pixel with the given matrix, for example to adjust brightness, saturation or swap channels.
+    ///
+    pub fn add_color_matrix(&mut self, matrix: Mat4) -> &mut Self {
+        let mut effect = CustomEffect::new(
+            "
+            uniform mat4 colorMatrix;
+            void main() {
+                outColor = colorMatrix * sample_color(uvs);
+            }
+            ",
+        );
+        effect.set_uniform("colorMatrix", matrix);
+        self.add_effect(effect)
+    }
+
+    ///
+    /// Returns the number of passes.
+    ///
+    pub fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    ///
+    /// Returns whether or not there are no passes.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    ///
+    /// Removes all passes.
+    ///
+    pub fn clear(&mut self) {
+        self.passes.clear();
+    }
+
+    ///
+    /// Runs all passes over the input texture and returns the result in a new texture with the given data type.
+    /// If there are no passes, the input is copied.
+    ///
+    pub fn process<T: TextureDataType>(&self, input: &Texture2D) -> Texture2D {
+        let copy = [ImagePass {
+            effect: Box::new(CopyEffect::default()),
+            size: None,
+        }];
+        let passes = if self.passes.is_empty() {
+            &copy[..]
+        } else {
+            &self.passes[..]
+        };
+        let (mut width, mut height) = (input.width(), input.height());
+        let mut current: Option<Texture2D> = None;
+        for (i, pass) in passes.iter().enumerate() {
+            (width, height) = pass.size.unwrap_or((width, height));
+            let mut output = if i == passes.len() - 1 {
+                self.new_texture::<T>(width, height)
+            } else if self.high_precision {
+                self.new_texture::<[f32; 4]>(width, height)
+            } else {
+                Texture2D::new_empty_with_precision(
+                    &self.context,
+                    self.context.color_precision(),
+                    width,
+                    height,
+                    Interpolation::Linear,
+                    Interpolation::Linear,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                )
+            };
+            let mut camera = Camera::new_2d(Viewport::new_at_origo(width, height));
+            camera.disable_tone_and_color_mapping();
+            output
+                .as_color_target(None)
+                .clear(ClearState::color(0.0, 0.0, 0.0, 0.0))
+                .apply_screen_effect(
+                    pass.effect.as_ref(),
+                    &camera,
+                    &[],
+                    Some(ColorTexture::Single(current.as_ref().unwrap_or(input))),
+                    None,
+                );
+            current = Some(output);
+        }
+        current.unwrap()
+    }
+
+    fn new_texture<T: TextureDataType>(&self, width: u32, height: u32) -> Texture2D {
+        Texture2D::new_empty::<T>(
+            &self.context,
+            width,
+            height,
+            Interpolation::Linear,
+            Interpolation::Linear,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        )
+    }
+}
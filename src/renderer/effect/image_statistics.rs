@@ -0,0 +1,379 @@
+use crate::renderer::*;
+
+// Returns the value of the given channel of a pixel, where the channel is the index of a [StatisticsChannel].
+const CHANNEL_VALUE_SOURCE: &str = "
+float channel_value(vec4 c, int channel)
+{
+    if (channel < 4) {
+        return c[channel];
+    }
+    float luminance = dot(c.rgb, vec3(0.2126, 0.7152, 0.0722));
+    return channel == 4 ? luminance : log2(luminance);
+}
+";
+
+///
+/// The value of each pixel that is used when computing [ImageStatistics].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatisticsChannel {
+    /// The red channel.
+    Red,
+    /// The green channel.
+    Green,
+    /// The blue channel.
+    Blue,
+    /// The alpha channel.
+    Alpha,
+    /// The relative luminance of the red, green and blue channels, assuming the texture contains linear colors.
+    Luminance,
+    /// The base 2 logarithm of the relative luminance, which is negative infinity for black pixels.
+    LogLuminance,
+}
+
+impl StatisticsChannel {
+    fn index(&self) -> i32 {
+        match self {
+            Self::Red => 0,
+            Self::Green => 1,
+            Self::Blue => 2,
+            Self::Alpha => 3,
+            Self::Luminance => 4,
+            Self::LogLuminance => 5,
+        }
+    }
+}
+
+///
+/// The minimum, maximum and mean of a channel of a texture, see [ImageStatistics::compute].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelStatistics {
+    /// The minimum value.
+    pub min: f32,
+    /// The maximum value.
+    pub max: f32,
+    /// The mean value.
+    pub mean: f32,
+    /// The number of pixels.
+    pub pixel_count: u32,
+}
+
+///
+/// Computes statistics, ie. the minimum, maximum, mean and histogram, of a channel of a texture on the GPU and reads back only the result,
+/// which is a few bytes for the minimum, maximum and mean and one value per bin for the histogram.
+/// This is useful for auto exposure, for choosing the range of a color map in scientific visualization and for asserting properties of a rendered image in tests.
+///
+/// The values are computed in 32 bit floating point render targets, which requires that the context supports rendering to floating point textures,
+/// see [Capabilities::float_render_targets].
+/// The histogram is counted by blending into a render target. If blending into 32 bit floating point textures is not supported, see [Capabilities::float_blend],
+/// it is counted in 16 bit floating point instead, which is only exact up to 2048, so the histogram is computed from an evenly spaced subset of the pixels.
+///
+/// **Note:** The result is read back from the GPU, which requires a synchronization between the CPU and GPU.
+///
+pub struct ImageStatistics {
+    context: Context,
+    float_histogram: bool,
+    reduction_program: Program,
+    histogram_program: Program,
+    corners: VertexBuffer,
+    bin_corners: VertexBuffer,
+}
+
+impl ImageStatistics {
+    // The largest number of samples which can be counted exactly in a 16 bit float bin.
+    const MAX_HALF_FLOAT_COUNT: u32 = 2048;
+
+    ///
+    /// Creates a new image statistics calculator.
+    ///
+    pub fn new(context: &Context) -> Self {
+        let reduction_program = Program::from_source(
+            context,
+            "
+            in vec2 corner;
+
+            void main()
+            {
+                gl_Position = vec4(corner, 0.0, 1.0);
+            }
+            ",
+            &format!(
+                "{}{}",
+                CHANNEL_VALUE_SOURCE,
+                "
+            uniform sampler2D inputTexture;
+            uniform ivec2 inputSize;
+            uniform int channel;
+
+            layout (location = 0) out vec4 outColor;
+
+            void main()
+            {
+                ivec2 base = ivec2(gl_FragCoord.xy) * 4;
+                vec4 result = vec4(1e38, -1e38, 0.0, 0.0);
+                for (int y = 0; y < 4; y++) {
+                    for (int x = 0; x < 4; x++) {
+                        ivec2 p = base + ivec2(x, y);
+                        if (p.x < inputSize.x && p.y < inputSize.y) {
+                            vec4 c = texelFetch(inputTexture, p, 0);
+                            if (channel < 0) {
+                                // The input is the minimum, maximum, sum and count from the previous pass
+                                result = vec4(min(result.x, c.x), max(result.y, c.y), result.zw + c.zw);
+                            } else {
+                                float v = channel_value(c, channel);
+                                result = vec4(min(result.x, v), max(result.y, v), result.z + v, result.w + 1.0);
+                            }
+                        }
+                    }
+                }
+                outColor = result;
+            }
+            "
+            ),
+        )
+        .expect("Failed compiling shader");
+        let histogram_program = Program::from_source(
+            context,
+            &format!(
+                "{}{}",
+                CHANNEL_VALUE_SOURCE,
+                "
+            uniform sampler2D inputTexture;
+            uniform ivec2 grid;
+            uniform int channel;
+            uniform vec2 range;
+            uniform float binCount;
+
+            in vec2 corner;
+
+            void main()
+            {
+                vec2 sample_position = vec2(gl_InstanceID % grid.x, gl_InstanceID / grid.x) + 0.5;
+                ivec2 p = ivec2(sample_position * vec2(textureSize(inputTexture, 0)) / vec2(grid));
+                float v = channel_value(texelFetch(inputTexture, p, 0), channel);
+                float t = (v - range.x) / (range.y - range.x);
+                // Values outside the half-open range are placed outside the viewport, and thereby ignored
+                float bin = t >= 0.0 && t < 1.0 ? min(floor(t * binCount), binCount - 1.0) : -10.0 * binCount;
+                float x = 2.0 * (bin + 0.5) / binCount - 1.0;
+                gl_Position = vec4(x + corner.x / binCount, corner.y, 0.0, 1.0);
+            }
+            "
+            ),
+            "
+            layout (location = 0) out vec4 outColor;
+
+            void main()
+            {
+                outColor = vec4(1.0, 0.0, 0.0, 0.0);
+            }
+            ",
+        )
+        .expect("Failed compiling shader");
+        Self {
+            context: context.clone(),
+            float_histogram: context.capabilities().float_render_targets
+                && context.capabilities().float_blend,
+            reduction_program,
+            histogram_program,
+            corners: VertexBuffer::new_with_data(
+                context,
+                &[vec2(-1.0, -1.0), vec2(3.0, -1.0), vec2(-1.0, 3.0)],
+            ),
+            // A triangle which only covers the center of the pixel of a bin
+            bin_corners: VertexBuffer::new_with_data(
+                context,
+                &[vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(0.0, 3.0)],
+            ),
+        }
+    }
+
+    ///
+    /// Computes the minimum, maximum and mean of the given channel of the texture.
+    ///
+    pub fn compute(&self, texture: &Texture2D, channel: StatisticsChannel) -> ChannelStatistics {
+        let (mut width, mut height) = (texture.width(), texture.height());
+        let mut current: Option<Texture2D> = None;
+        while current.is_none() || width > 1 || height > 1 {
+            let (input_width, input_height) = (width, height);
+            width = width.div_ceil(4);
+            height = height.div_ceil(4);
+            let mut output = self.new_texture(width, height);
+            output
+                .as_color_target(None)
+                .write::<CoreError>(|| {
+                    self.reduction_program
+                        .use_texture("inputTexture", current.as_ref().unwrap_or(texture));
+                    self.reduction_program
+                        .use_uniform("inputSize", vec2(input_width as i32, input_height as i32));
+                    self.reduction_program.use_uniform(
+                        "channel",
+                        if current.is_some() {
+                            -1
+                        } else {
+                            channel.index()
+                        },
+                    );
+                    self.draw_full_screen(Viewport::new_at_origo(width, height));
+                    Ok(())
+                })
+                .unwrap();
+            current = Some(output);
+        }
+        let result = current.unwrap().as_color_target(None).read::<[f32; 4]>()[0];
+        ChannelStatistics {
+            min: result[0],
+            max: result[1],
+            mean: if result[3] > 0.0 {
+                result[2] / result[3]
+            } else {
+                0.0
+            },
+            pixel_count: result[3] as u32,
+        }
+    }
+
+    ///
+    /// Computes the histogram of the given channel of the texture, ie. the number of pixels with a value in each of the bins
+    /// which evenly divide the half-open range from `min` to `max`. The pixels with a value outside the range, including the value `max`, are not counted.
+    /// Use [ImageStatistics::compute] to find the range of the values in the texture.
+    ///
+    /// If blending into 32 bit floating point textures is not supported, see [Capabilities::float_blend],
+    /// the counts are estimated from an evenly spaced subset of at most 2048 pixels.
+    ///
+    pub fn histogram(
+        &self,
+        texture: &Texture2D,
+        channel: StatisticsChannel,
+        bin_count: u32,
+        min: f32,
+        max: f32,
+    ) -> Vec<u32> {
+        let (width, height) = (texture.width(), texture.height());
+        let grid = self.sample_grid(width, height);
+        let scale = (width * height) as f32 / (grid.0 * grid.1) as f32;
+        self.count(texture, channel, bin_count, min, max, grid)
+            .into_iter()
+            .map(|v| (v * scale).round() as u32)
+            .collect()
+    }
+
+    ///
+    /// Computes the histogram of the given channel of the texture like [ImageStatistics::histogram],
+    /// but only from a grid of the given number of evenly spaced samples in each direction, which is cheaper for large textures.
+    /// Returns the number of samples in each bin.
+    ///
+    pub fn sampled_histogram(
+        &self,
+        texture: &Texture2D,
+        channel: StatisticsChannel,
+        bin_count: u32,
+        min: f32,
+        max: f32,
+        sample_count: u32,
+    ) -> Vec<u32> {
+        let grid = self.sample_grid(sample_count, sample_count);
+        self.count(texture, channel, bin_count, min, max, grid)
+            .into_iter()
+            .map(|v| v.round() as u32)
+            .collect()
+    }
+
+    // Limits the grid of samples such that the count of a bin is exact when it is counted in 16 bit float.
+    fn sample_grid(&self, columns: u32, rows: u32) -> (u32, u32) {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        if self.float_histogram || columns * rows <= Self::MAX_HALF_FLOAT_COUNT {
+            (columns, rows)
+        } else {
+            let scale = (Self::MAX_HALF_FLOAT_COUNT as f32 / (columns * rows) as f32).sqrt();
+            (
+                ((columns as f32 * scale) as u32).max(1),
+                ((rows as f32 * scale) as u32).max(1),
+            )
+        }
+    }
+
+    fn count(
+        &self,
+        texture: &Texture2D,
+        channel: StatisticsChannel,
+        bin_count: u32,
+        min: f32,
+        max: f32,
+        grid: (u32, u32),
+    ) -> Vec<f32> {
+        let bin_count = bin_count.max(1);
+        let mut histogram = if self.float_histogram {
+            self.new_texture(bin_count, 1)
+        } else {
+            Texture2D::new_empty::<[f16; 4]>(
+                &self.context,
+                bin_count,
+                1,
+                Interpolation::Nearest,
+                Interpolation::Nearest,
+                None,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            )
+        };
+        histogram
+            .as_color_target(None)
+            .clear(ClearState::color(0.0, 0.0, 0.0, 0.0))
+            .write::<CoreError>(|| {
+                let program = &self.histogram_program;
+                program.use_texture("inputTexture", texture);
+                program.use_uniform("grid", vec2(grid.0 as i32, grid.1 as i32));
+                program.use_uniform("channel", channel.index());
+                program.use_uniform("range", vec2(min, max));
+                program.use_uniform("binCount", bin_count as f32);
+                program.use_vertex_attribute("corner", &self.bin_corners);
+                program.draw_arrays_instanced(
+                    RenderStates {
+                        write_mask: WriteMask::COLOR,
+                        depth_test: DepthTest::Always,
+                        blend: Blend::ADD,
+                        ..Default::default()
+                    },
+                    Viewport::new_at_origo(bin_count, 1),
+                    3,
+                    grid.0 * grid.1,
+                );
+                Ok(())
+            })
+            .unwrap();
+        histogram
+            .as_color_target(None)
+            .read::<[f32; 4]>()
+            .into_iter()
+            .map(|v| v[0])
+            .collect()
+    }
+
+    fn draw_full_screen(&self, viewport: Viewport) {
+        self.reduction_program
+            .use_vertex_attribute("corner", &self.corners);
+        self.reduction_program.draw_arrays(
+            RenderStates {
+                write_mask: WriteMask::COLOR,
+                depth_test: DepthTest::Always,
+                ..Default::default()
+            },
+            viewport,
+            3,
+        );
+    }
+
+    fn new_texture(&self, width: u32, height: u32) -> Texture2D {
+        Texture2D::new_empty::<[f32; 4]>(
+            &self.context,
+            width,
+            height,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        )
+    }
+}
@@ -20,6 +20,7 @@ impl Effect for LightingPassEffect {
         fragment_shader.push_str(&depth_texture.unwrap().fragment_shader_source());
         fragment_shader.push_str(ToneMapping::fragment_shader_source());
         fragment_shader.push_str(ColorMapping::fragment_shader_source());
+        fragment_shader.push_str(include_str!("shaders/deferred_shared.frag"));
         fragment_shader.push_str(include_str!("shaders/deferred_lighting.frag"));
         fragment_shader
     }
@@ -43,7 +44,9 @@ impl Effect for LightingPassEffect {
         color_texture: Option<ColorTexture>,
         depth_texture: Option<DepthTexture>,
     ) {
-        camera.tone_mapping.use_uniforms(program);
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
         camera.color_mapping.use_uniforms(program);
         color_texture.unwrap().use_uniforms(program);
         depth_texture.unwrap().use_uniforms(program);
@@ -82,3 +85,232 @@ enum DebugType {
     Uv,
     None,
 }
+
+///
+/// Composites the lighting accumulated in a floating point texture by the lighting pass and [render_light_volumes] onto the render target,
+/// applying the tone and color mapping of the camera.
+///
+pub struct LightAccumulationEffect {}
+
+impl Effect for LightAccumulationEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}{}{}{}{}",
+            color_texture.unwrap().fragment_shader_source(),
+            depth_texture.unwrap().fragment_shader_source(),
+            ToneMapping::fragment_shader_source(),
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/deferred_light_accumulation.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 13
+            | 0b1u16 << 10
+            | color_texture.unwrap().id()
+            | depth_texture.unwrap().id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
+        camera.color_mapping.use_uniforms(program);
+        color_texture.unwrap().use_uniforms(program);
+        depth_texture.unwrap().use_uniforms(program);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// Renders the lighting contribution of the given lights, which must all have a [Light::volume], by rendering the back faces of a proxy geometry
+/// enclosing the volume of each light with additive blending, so that only the pixels covered by the volume are shaded.
+/// The color texture must be the geometry buffer produced by the [DeferredPhysicalMaterial] and the depth texture the corresponding depth.
+///
+pub fn render_light_volumes(
+    context: &Context,
+    camera: &Camera,
+    lights: &[&dyn Light],
+    color_texture: ColorTexture,
+    depth_texture: DepthTexture,
+) {
+    let sphere = LightVolumeMesh::sphere(context);
+    let cone = LightVolumeMesh::cone(context);
+    let viewport = camera.viewport();
+    for light in lights {
+        let (mesh, shape, model_matrix) = match light.volume() {
+            Some(LightVolume::Sphere { center, radius }) => (
+                &sphere,
+                0,
+                Mat4::from_translation(center) * Mat4::from_scale(radius * sphere.scale),
+            ),
+            Some(LightVolume::Cone {
+                apex,
+                direction,
+                angle,
+                length,
+            }) => {
+                let radius = length * angle.0.tan() * cone.scale;
+                let axis = direction.normalize();
+                let up = if axis.x.abs() < 0.9 {
+                    vec3(1.0, 0.0, 0.0)
+                } else {
+                    vec3(0.0, 1.0, 0.0)
+                };
+                let side = axis.cross(up).normalize();
+                (
+                    &cone,
+                    1,
+                    Mat4::from_cols(
+                        (length * axis).extend(0.0),
+                        (radius * side).extend(0.0),
+                        (radius * axis.cross(side)).extend(0.0),
+                        apex.extend(1.0),
+                    ),
+                )
+            }
+            None => continue,
+        };
+
+        let mut id = (0b1u16 << 15).to_le_bytes().to_vec();
+        id.extend(
+            (0b1u16 << 14
+                | 0b1u16 << 13
+                | 0b1u16 << 12
+                | 0b1u16 << 10
+                | color_texture.id()
+                | depth_texture.id())
+            .to_le_bytes(),
+        );
+        id.push(light.id());
+
+        let mut programs = context.programs.write().unwrap();
+        let program = programs.entry(id).or_insert_with(|| {
+            let mut fragment_shader = lights_shader_source(
+                &[*light],
+                LightingModel::Cook(
+                    NormalDistributionFunction::TrowbridgeReitzGGX,
+                    GeometryFunction::SmithSchlickGGX,
+                ),
+            );
+            fragment_shader.push_str(&color_texture.fragment_shader_source());
+            fragment_shader.push_str(&depth_texture.fragment_shader_source());
+            fragment_shader.push_str(include_str!("shaders/deferred_shared.frag"));
+            fragment_shader.push_str(include_str!("shaders/deferred_light_volume.frag"));
+            Program::from_source(
+                context,
+                include_str!("shaders/deferred_light_volume.vert"),
+                &fragment_shader,
+            )
+            .expect("Failed compiling shader")
+        });
+        color_texture.use_uniforms(program);
+        depth_texture.use_uniforms(program);
+        light.use_uniforms(program, 0);
+        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform("modelMatrix", model_matrix);
+        program.use_uniform(
+            "viewProjectionInverse",
+            (camera.projection() * camera.view()).invert().unwrap(),
+        );
+        program.use_uniform("volumeInverse", model_matrix.invert().unwrap());
+        program.use_uniform("volumeShape", shape);
+        program.use_uniform(
+            "viewport",
+            vec4(
+                viewport.x as f32,
+                viewport.y as f32,
+                viewport.width as f32,
+                viewport.height as f32,
+            ),
+        );
+        program.use_uniform_if_required("cameraPosition", camera.position());
+        program.use_vertex_attribute("position", &mesh.positions);
+        program.draw_elements(
+            RenderStates {
+                write_mask: WriteMask::COLOR,
+                depth_test: DepthTest::Always,
+                blend: Blend::ADD,
+                // The back faces are visible also when the camera is inside the volume
+                cull: Cull::Front,
+                ..Default::default()
+            },
+            viewport,
+            &mesh.indices,
+        );
+    }
+}
+
+// A closed mesh enclosing the unit sphere or the unit cone, which is scaled to enclose the volume of a light.
+struct LightVolumeMesh {
+    positions: VertexBuffer,
+    indices: ElementBuffer,
+    // The factor the radius must be scaled with, such that the flat faces enclose the curved surface
+    scale: f32,
+}
+
+impl LightVolumeMesh {
+    const SUBDIVISIONS: u32 = 16;
+
+    fn sphere(context: &Context) -> Self {
+        let cpu_mesh = CpuMesh::sphere(Self::SUBDIVISIONS);
+        let indices = match &cpu_mesh.indices {
+            Indices::U8(ind) => ind.iter().map(|i| *i as u32).collect(),
+            Indices::U16(ind) => ind.iter().map(|i| *i as u32).collect(),
+            Indices::U32(ind) => ind.clone(),
+            Indices::None => (0..cpu_mesh.vertex_count() as u32).collect::<Vec<_>>(),
+        };
+        let scale = 1.0 / (std::f32::consts::PI / Self::SUBDIVISIONS as f32).cos();
+        Self {
+            positions: VertexBuffer::new_with_data(context, &cpu_mesh.positions.to_f32()),
+            indices: ElementBuffer::new_with_data(context, &indices),
+            scale: scale * scale,
+        }
+    }
+
+    // A cone with the apex in origo and a base with radius one at x = 1.
+    fn cone(context: &Context) -> Self {
+        let n = Self::SUBDIVISIONS;
+        let mut positions = vec![vec3(0.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)];
+        let mut indices = Vec::new();
+        for i in 0..n {
+            let angle = 2.0 * std::f32::consts::PI * i as f32 / n as f32;
+            positions.push(vec3(1.0, angle.cos(), angle.sin()));
+            let (current, next) = (2 + i, 2 + (i + 1) % n);
+            indices.extend([0, next, current]);
+            indices.extend([1, current, next]);
+        }
+        Self {
+            positions: VertexBuffer::new_with_data(context, &positions),
+            indices: ElementBuffer::new_with_data(context, &indices),
+            scale: 1.0 / (std::f32::consts::PI / n as f32).cos(),
+        }
+    }
+}
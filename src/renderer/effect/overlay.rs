@@ -0,0 +1,99 @@
+use crate::renderer::*;
+
+///
+/// Composites a 2D overlay, for example a user interface or text, on top of the final image.
+/// The overlay color texture is expected to contain colors in sRGB space, ie. the colors are written to the render target as they are,
+/// without applying the tone and color mapping set in the [Camera].
+/// This means that the overlay is not affected by the HDR pipeline, so the colors match the colors specified when rendering the overlay.
+///
+/// Render the overlay into a separate texture, then render the scene to the screen and finally apply this effect with the overlay texture
+/// using for example [RenderTarget::apply_screen_effect].
+///
+#[derive(Clone, Debug)]
+pub struct OverlayEffect {
+    /// Whether or not the colors in the overlay texture has premultiplied alpha, ie. the color channels have already been multiplied with the alpha channel.
+    pub premultiplied_alpha: bool,
+}
+
+impl Default for OverlayEffect {
+    fn default() -> Self {
+        Self {
+            premultiplied_alpha: true,
+        }
+    }
+}
+
+impl Effect for OverlayEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn crate::Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        let color_texture =
+            color_texture.expect("Must supply a color texture to apply an overlay effect");
+        format!(
+            "{}
+
+            in vec2 uvs;
+            layout (location = 0) out vec4 outColor;
+
+            void main()
+            {{
+                outColor = sample_color(uvs);
+                {}
+            }}
+
+        ",
+            color_texture.fragment_shader_source(),
+            if self.premultiplied_alpha {
+                ""
+            } else {
+                "outColor.rgb *= outColor.a;"
+            }
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 11
+            | if self.premultiplied_alpha {
+                0b1u16 << 10
+            } else {
+                0
+            }
+            | color_texture
+                .expect("Must supply a color texture to apply an overlay effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn crate::Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        color_texture
+            .expect("Must supply a color texture to apply an overlay effect")
+            .use_uniforms(program);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            write_mask: WriteMask::COLOR,
+            blend: Blend::PREMULTIPLIED_ALPHA,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,19 @@
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    float depth = sample_depth(uvs);
+    if(depth > 0.99999)
+    {
+        discard;
+    }
+    gl_FragDepth = depth;
+
+    vec4 color = sample_color(uvs);
+    outColor.rgb = tone_mapping(color.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    outColor.a = color.a;
+}
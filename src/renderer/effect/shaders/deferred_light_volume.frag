@@ -0,0 +1,41 @@
+
+uniform mat4 viewProjectionInverse;
+uniform mat4 volumeInverse;
+uniform int volumeShape;
+uniform vec4 viewport;
+uniform vec3 cameraPosition;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec2 uvs = (gl_FragCoord.xy - viewport.xy) / viewport.zw;
+    float depth = sample_depth(uvs);
+    if(depth > 0.99999)
+    {
+        discard;
+    }
+
+    // Skip the surfaces in front of or behind the volume
+    vec3 position = world_pos_from_depth(viewProjectionInverse, depth, uvs);
+    vec3 p = (volumeInverse * vec4(position, 1.0)).xyz;
+    if(volumeShape == 0 ? dot(p, p) > 1.0 : p.x < 0.0 || p.x > 1.0 || length(p.yz) > p.x)
+    {
+        discard;
+    }
+
+    vec3 surface_color;
+    float metallic_factor;
+    vec3 normal;
+    float roughness_factor;
+    float occlusion;
+    vec3 emissive;
+    bool unlit;
+    sample_geometry_buffer(uvs, surface_color, metallic_factor, normal, roughness_factor, occlusion, emissive, unlit);
+    if(unlit)
+    {
+        discard;
+    }
+
+    outColor = vec4(calculate_lighting(cameraPosition, surface_color, position, normal, metallic_factor, roughness_factor, occlusion), 0.0);
+}
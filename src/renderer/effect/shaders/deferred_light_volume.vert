@@ -0,0 +1,11 @@
+uniform mat4 viewProjection;
+uniform mat4 modelMatrix;
+
+in vec3 position;
+
+void main()
+{
+    gl_Position = viewProjection * modelMatrix * vec4(position, 1.0);
+    // Keep the back faces behind the far plane, since the surfaces in front of the far plane might still be inside the volume
+    gl_Position.z = min(gl_Position.z, gl_Position.w);
+}
@@ -20,20 +20,15 @@ void main()
 
     vec3 position = world_pos_from_depth(viewProjectionInverse, depth, uvs);
    	
-    vec4 c = sample_layer(uvs, 0);
-    vec4 surface_color = vec4(c.rgb, 1.0);
-    float metallic_factor = c.w;
-
-    vec4 n = sample_layer(uvs, 1);
-    vec2 n2 = n.xy*2.0 - 1.0;
-    float z = 1.0 - n2.x * n2.x - n2.y * n2.y;
-    if (z > 0.0001) {
-        z = sqrt(z);
-    }
-    vec3 normal = normalize(vec3(n2.x, n2.y, (int(floor(n.z * 255.0)) & 128) == 128 ? z: -z));
-    float roughness_factor = n.w;
-    float occlusion = float(int(floor(n.z * 255.0)) & 127) / 127.0;
-    vec3 total_emissive = sample_layer(uvs, 2).rgb;
+    vec3 color;
+    float metallic_factor;
+    vec3 normal;
+    float roughness_factor;
+    float occlusion;
+    vec3 total_emissive;
+    bool unlit;
+    sample_geometry_buffer(uvs, color, metallic_factor, normal, roughness_factor, occlusion, total_emissive, unlit);
+    vec4 surface_color = vec4(color, 1.0);
 
     if(debug_type == 0) // Position
     {
@@ -61,7 +56,7 @@ void main()
         outColor = vec4(uvs, 0., 1.);
     }
     else { // None
-        outColor.rgb = total_emissive + calculate_lighting(cameraPosition, surface_color.rgb, position, normal, metallic_factor, roughness_factor, occlusion);
+        outColor.rgb = total_emissive + (unlit ? surface_color.rgb : calculate_lighting(cameraPosition, surface_color.rgb, position, normal, metallic_factor, roughness_factor, occlusion));
         outColor.rgb = tone_mapping(outColor.rgb);
         outColor.rgb = color_mapping(outColor.rgb);
         outColor.a = surface_color.a;
@@ -0,0 +1,21 @@
+
+// Reads the surface parameters written to the geometry buffer by the DeferredPhysicalMaterial
+void sample_geometry_buffer(vec2 uvs, out vec3 surface_color, out float metallic_factor, out vec3 normal, out float roughness_factor, out float occlusion, out vec3 emissive, out bool unlit)
+{
+    vec4 c = sample_layer(uvs, 0);
+    surface_color = c.rgb;
+    metallic_factor = c.w;
+
+    vec4 n = sample_layer(uvs, 1);
+    vec2 n2 = n.xy*2.0 - 1.0;
+    float z = 1.0 - n2.x * n2.x - n2.y * n2.y;
+    if (z > 0.0001) {
+        z = sqrt(z);
+    }
+    normal = normalize(vec3(n2.x, n2.y, (int(floor(n.z * 255.0)) & 128) == 128 ? z: -z));
+    roughness_factor = n.w;
+    occlusion = float(int(floor(n.z * 255.0)) & 127) / 127.0;
+    vec4 e = sample_layer(uvs, 2);
+    emissive = e.rgb;
+    unlit = e.a > 0.5;
+}
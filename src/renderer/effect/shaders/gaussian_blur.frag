@@ -0,0 +1,17 @@
+uniform vec2 direction;
+uniform float radius;
+
+void main()
+{
+    vec2 texel = direction / vec2(textureSize(colorMap, 0));
+    float sigma = max(radius / 3.0, 0.0001);
+    int samples = int(ceil(radius));
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+    for (int i = -samples; i <= samples; i++) {
+        float weight = exp(-0.5 * float(i * i) / (sigma * sigma));
+        sum += weight * sample_color(uvs + float(i) * texel);
+        total += weight;
+    }
+    outColor = sum / total;
+}
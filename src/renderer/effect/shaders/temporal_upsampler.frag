@@ -0,0 +1,68 @@
+
+uniform sampler2D lowResolutionTexture;
+uniform sampler2D historyTexture;
+#ifdef USE_MOTION_TEXTURE
+uniform sampler2D motionTexture;
+#endif
+uniform vec2 jitter;
+uniform vec2 size;
+uniform mat4 viewProjectionInverse;
+uniform mat4 previousViewProjection;
+uniform vec3 cameraPosition;
+uniform float historyWeight;
+uniform float depthSensitivity;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+float view_distance(vec2 uv)
+{
+    return distance(world_pos_from_depth(viewProjectionInverse, sample_depth(uv), uv), cameraPosition);
+}
+
+void main()
+{
+    ivec2 lowResolutionSize = textureSize(lowResolutionTexture, 0);
+    float depth = sample_depth(uvs);
+    float dist = distance(world_pos_from_depth(viewProjectionInverse, depth, uvs), cameraPosition);
+
+    // The low resolution sample in the bottom left of this pixel and the bilinear weights of the four surrounding samples
+    vec2 pixel = uvs * size;
+    vec2 base = floor((pixel - 0.5 - jitter) * 0.5);
+    vec2 f = clamp((pixel - 0.5 - jitter - 2.0 * base) * 0.5, 0.0, 1.0);
+
+    // Bilateral upsampling, where the samples at a different depth than this pixel get a lower weight
+    vec4 sum = vec4(0.0);
+    float weight_sum = 0.0;
+    vec4 min_color = vec4(1e38);
+    vec4 max_color = vec4(-1e38);
+    for (int y = 0; y < 2; y++) {
+        for (int x = 0; x < 2; x++) {
+            ivec2 texel = clamp(ivec2(base) + ivec2(x, y), ivec2(0), lowResolutionSize - 1);
+            vec2 sample_uv = (2.0 * vec2(texel) + jitter + 0.5) / size;
+            vec4 c = texelFetch(lowResolutionTexture, texel, 0);
+            float bilinear = (x == 0 ? 1.0 - f.x : f.x) * (y == 0 ? 1.0 - f.y : f.y);
+            float difference = abs(view_distance(sample_uv) - dist) / max(dist, 0.0001);
+            float weight = max(bilinear * exp(-depthSensitivity * difference), 0.00001);
+            sum += weight * c;
+            weight_sum += weight;
+            min_color = min(min_color, c);
+            max_color = max(max_color, c);
+        }
+    }
+    vec4 color = sum / weight_sum;
+
+    // Blend with the result of the previous frames, clamped to the range of the nearby samples to reduce ghosting
+#ifdef USE_MOTION_TEXTURE
+    vec2 previous_uv = uvs - texture(motionTexture, uvs).xy;
+#else
+    vec4 previous = previousViewProjection * vec4(world_pos_from_depth(viewProjectionInverse, depth, uvs), 1.0);
+    vec2 previous_uv = 0.5 * previous.xy / previous.w + 0.5;
+#endif
+    if (historyWeight > 0.0 && all(greaterThanEqual(previous_uv, vec2(0.0))) && all(lessThanEqual(previous_uv, vec2(1.0)))) {
+        vec4 history = clamp(texture(historyTexture, previous_uv), min_color, max_color);
+        color = mix(color, history, historyWeight);
+    }
+    outColor = color;
+}
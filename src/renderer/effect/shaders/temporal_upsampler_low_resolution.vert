@@ -0,0 +1,15 @@
+uniform vec2 uvScale;
+uniform vec2 uvOffset;
+
+in vec3 position;
+
+out vec2 uvs;
+out vec4 col;
+
+void main()
+{
+    // Map the center of each low resolution pixel to the center of one of the full resolution pixels it covers
+    uvs = (0.5 * position.xy + 0.5) * uvScale + uvOffset;
+    col = vec4(1.0);
+    gl_Position = vec4(position, 1.0);
+}
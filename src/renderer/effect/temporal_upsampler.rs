@@ -0,0 +1,259 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Renders an expensive screen space [Effect], for example ambient occlusion, volumetric lighting or screen space reflections, at half resolution
+/// and upsamples the result to full resolution, which reduces the cost of the effect to about a quarter and is a major performance win on integrated GPUs.
+///
+/// Each frame, the effect is evaluated at one of the four pixels in each two by two block of full resolution pixels, cycling through the four pixels over four frames.
+/// The result is upsampled using a bilateral filter, which uses the depth to avoid blurring the effect across edges,
+/// and blended with the result of the previous frames, which is reprojected using the depth and the previous camera or the given motion vectors.
+/// When the camera and scene is still, the result converges to the full resolution result after four frames.
+///
+/// The effect is rendered with the tone and color mapping disabled, so the result is in the same color space as the input and should be composited onto the scene afterwards,
+/// for example using [CopyEffect] or a custom effect reading [TemporalUpsampler::texture].
+///
+/// **Note:** The result is computed using 16 bit floating point render targets, which requires that the context supports rendering to half float textures.
+///
+pub struct TemporalUpsampler {
+    context: Context,
+    low_resolution_texture: Texture2D,
+    history: [Texture2D; 2],
+    current: usize,
+    frame: u32,
+    previous_view_projection: Option<Mat4>,
+    upsample_program: Option<(bool, Program)>,
+    /// The weight of the reprojected result of the previous frames in the range `[0..1]`. Higher values give a more stable and detailed result but more ghosting when the scene moves.
+    /// The default is 0.9.
+    pub history_weight: f32,
+    /// How quickly the weight of a low resolution sample falls off with the relative difference in depth to the upsampled pixel.
+    /// Higher values preserve edges better but can give aliased edges. The default is 50.
+    pub depth_sensitivity: f32,
+}
+
+impl TemporalUpsampler {
+    // The pixel in each two by two block of full resolution pixels that is sampled in each frame.
+    const JITTER: [(i32, i32); 4] = [(0, 0), (1, 1), (1, 0), (0, 1)];
+
+    ///
+    /// Creates a new temporal upsampler. The textures are resized to match the viewport of the camera the first time [TemporalUpsampler::apply] is called.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            low_resolution_texture: new_texture(context, 1, 1, Interpolation::Nearest),
+            history: [
+                new_texture(context, 1, 1, Interpolation::Linear),
+                new_texture(context, 1, 1, Interpolation::Linear),
+            ],
+            current: 0,
+            frame: 0,
+            previous_view_projection: None,
+            upsample_program: None,
+            history_weight: 0.9,
+            depth_sensitivity: 50.0,
+        }
+    }
+
+    ///
+    /// Discards the result of the previous frames, which should be done when the view changes abruptly, for example when switching between cameras.
+    ///
+    pub fn reset_history(&mut self) {
+        self.previous_view_projection = None;
+    }
+
+    ///
+    /// Returns the upsampled result of the last call to [TemporalUpsampler::apply].
+    ///
+    pub fn texture(&self) -> &Texture2D {
+        &self.history[self.current]
+    }
+
+    ///
+    /// Renders the given effect at half the resolution of the viewport of the camera and upsamples the result to full resolution, see [TemporalUpsampler].
+    /// The depth texture must contain the depth of the scene at full resolution and the optional motion texture the movement, in uv coordinates, of each pixel since the previous frame
+    /// in the red and green channels, ie. the uv coordinates in the current frame minus the uv coordinates in the previous frame.
+    /// Without a motion texture, the movement is computed from the change of the camera, which is only correct for static scenes.
+    ///
+    /// Returns the upsampled result, see [TemporalUpsampler::texture].
+    /// Must not be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method, since it renders into its own render targets.
+    ///
+    pub fn apply(
+        &mut self,
+        effect: &dyn Effect,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: &DepthTexture2D,
+        motion_texture: Option<&Texture2D>,
+    ) -> &Texture2D {
+        let viewport = camera.viewport();
+        let (width, height) = (viewport.width.max(1), viewport.height.max(1));
+        let (low_width, low_height) = (width.div_ceil(2), height.div_ceil(2));
+        if self.history[0].width() != width || self.history[0].height() != height {
+            self.low_resolution_texture =
+                new_texture(&self.context, low_width, low_height, Interpolation::Nearest);
+            self.history = [
+                new_texture(&self.context, width, height, Interpolation::Linear),
+                new_texture(&self.context, width, height, Interpolation::Linear),
+            ];
+            self.previous_view_projection = None;
+        }
+        let (jitter_x, jitter_y) = Self::JITTER[self.frame as usize % Self::JITTER.len()];
+        self.frame = self.frame.wrapping_add(1);
+        let depth = DepthTexture::Single(depth_texture);
+
+        // Evaluate the effect at one of the full resolution pixels in each two by two block
+        let mut low_resolution_camera = camera.clone();
+        low_resolution_camera.set_viewport(Viewport::new_at_origo(low_width, low_height));
+        low_resolution_camera.disable_tone_and_color_mapping();
+        let context = self.context.clone();
+        self.low_resolution_texture
+            .as_color_target(None)
+            .clear(ClearState::color(0.0, 0.0, 0.0, 0.0))
+            .write::<RendererError>(|| {
+                let defines = effect.shader_defines().source();
+                let mut id = (0b1u16 << 15 | 0b1u16 << 14).to_le_bytes().to_vec();
+                id.extend(effect.id(color_texture, Some(depth)).to_le_bytes());
+                id.extend(lights.iter().map(|l| l.id()));
+                id.extend(defines.bytes());
+                let mut programs = context.programs.write().unwrap();
+                let program = programs.entry(id).or_insert_with(|| {
+                    Program::from_source(
+                        &context,
+                        include_str!("shaders/temporal_upsampler_low_resolution.vert"),
+                        &(defines.clone()
+                            + &effect.fragment_shader_source(lights, color_texture, Some(depth))),
+                    )
+                    .expect("Failed compiling shader")
+                });
+                program.use_uniform(
+                    "uvScale",
+                    vec2(
+                        2.0 * low_width as f32 / width as f32,
+                        2.0 * low_height as f32 / height as f32,
+                    ),
+                );
+                program.use_uniform(
+                    "uvOffset",
+                    vec2(
+                        (jitter_x as f32 - 0.5) / width as f32,
+                        (jitter_y as f32 - 0.5) / height as f32,
+                    ),
+                );
+                effect.use_uniforms(
+                    program,
+                    &low_resolution_camera,
+                    lights,
+                    color_texture,
+                    Some(depth),
+                );
+                full_screen_draw(
+                    &context,
+                    program,
+                    effect.render_states(),
+                    low_resolution_camera.viewport(),
+                );
+                Ok(())
+            })
+            .unwrap();
+
+        // Upsample and blend with the reprojected result of the previous frames
+        let use_motion = motion_texture.is_some();
+        if self
+            .upsample_program
+            .as_ref()
+            .map(|(motion, _)| *motion != use_motion)
+            .unwrap_or(true)
+        {
+            let fragment_shader = format!(
+                "{}{}{}{}",
+                if use_motion {
+                    "#define USE_MOTION_TEXTURE\n"
+                } else {
+                    ""
+                },
+                include_str!("../../core/shared.frag"),
+                depth.fragment_shader_source(),
+                include_str!("shaders/temporal_upsampler.frag")
+            );
+            self.upsample_program = Some((
+                use_motion,
+                Program::from_source(
+                    &self.context,
+                    full_screen_vertex_shader_source(),
+                    &fragment_shader,
+                )
+                .expect("Failed compiling shader"),
+            ));
+        }
+        let program = &self.upsample_program.as_ref().unwrap().1;
+        let view_projection = camera.projection() * camera.view();
+        let history_weight = if self.previous_view_projection.is_some() {
+            self.history_weight.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let previous = self.current;
+        self.current = 1 - self.current;
+        let (first, second) = self.history.split_at_mut(1);
+        let (history, target) = if previous == 0 {
+            (&first[0], &mut second[0])
+        } else {
+            (&second[0], &mut first[0])
+        };
+        target
+            .as_color_target(None)
+            .write::<RendererError>(|| {
+                depth.use_uniforms(program);
+                program.use_texture("lowResolutionTexture", &self.low_resolution_texture);
+                program.use_texture("historyTexture", history);
+                if let Some(motion_texture) = motion_texture {
+                    program.use_texture("motionTexture", motion_texture);
+                }
+                program.use_uniform("jitter", vec2(jitter_x as f32, jitter_y as f32));
+                program.use_uniform("size", vec2(width as f32, height as f32));
+                program.use_uniform("viewProjectionInverse", view_projection.invert().unwrap());
+                program.use_uniform_if_required(
+                    "previousViewProjection",
+                    self.previous_view_projection.unwrap_or(view_projection),
+                );
+                program.use_uniform("cameraPosition", camera.position());
+                program.use_uniform("historyWeight", history_weight);
+                program.use_uniform("depthSensitivity", self.depth_sensitivity);
+                full_screen_draw(
+                    &self.context,
+                    program,
+                    RenderStates {
+                        write_mask: WriteMask::COLOR,
+                        depth_test: DepthTest::Always,
+                        ..Default::default()
+                    },
+                    Viewport::new_at_origo(width, height),
+                );
+                Ok(())
+            })
+            .unwrap();
+        self.previous_view_projection = Some(view_projection);
+        &self.history[self.current]
+    }
+}
+
+fn new_texture(
+    context: &Context,
+    width: u32,
+    height: u32,
+    interpolation: Interpolation,
+) -> Texture2D {
+    Texture2D::new_empty_with_precision(
+        context,
+        context.color_precision(),
+        width,
+        height,
+        interpolation,
+        interpolation,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    )
+}
@@ -95,7 +95,9 @@ impl Effect for WaterEffect {
         color_texture: Option<ColorTexture>,
         depth_texture: Option<DepthTexture>,
     ) {
-        camera.tone_mapping.use_uniforms(program);
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
         camera.color_mapping.use_uniforms(program);
         color_texture
             .expect("Must supply a color texture to apply a water effect")
@@ -26,6 +26,10 @@ macro_rules! impl_geometry_body {
             self.$inner().id(required_attributes)
         }
 
+        fn shader_defines(&self) -> ShaderDefines {
+            self.$inner().shader_defines()
+        }
+
         fn render_with_material(
             &self,
             material: &dyn Material,
@@ -50,6 +54,10 @@ macro_rules! impl_geometry_body {
         fn aabb(&self) -> AxisAlignedBoundingBox {
             self.$inner().aabb()
         }
+
+        fn is_animated(&self) -> bool {
+            self.$inner().is_animated()
+        }
     };
 }
 
@@ -65,6 +73,10 @@ mod sprites;
 #[doc(inline)]
 pub use sprites::*;
 
+mod points;
+#[doc(inline)]
+pub use points::*;
+
 mod particles;
 #[doc(inline)]
 pub use particles::*;
@@ -85,6 +97,46 @@ mod circle;
 #[doc(inline)]
 pub use circle::*;
 
+mod batched_mesh;
+#[doc(inline)]
+pub use batched_mesh::*;
+
+mod mesh_analysis;
+#[doc(inline)]
+pub use mesh_analysis::*;
+
+mod ray;
+#[doc(inline)]
+pub use ray::*;
+
+mod bvh;
+#[doc(inline)]
+pub use bvh::*;
+
+mod vertex_paint;
+#[doc(inline)]
+pub use vertex_paint::*;
+
+mod mesh_builder;
+#[doc(inline)]
+pub use mesh_builder::*;
+
+mod skin;
+#[doc(inline)]
+pub use skin::*;
+
+mod skeleton;
+#[doc(inline)]
+pub use skeleton::*;
+
+mod skinned_mesh;
+#[doc(inline)]
+pub use skinned_mesh::*;
+
+mod wind;
+#[doc(inline)]
+pub use wind::*;
+
 use crate::core::*;
 use crate::renderer::*;
 
@@ -131,6 +183,15 @@ pub trait Geometry {
     ///
     fn id(&self, required_attributes: FragmentAttributes) -> u16;
 
+    ///
+    /// Returns the [ShaderDefines] which are prepended to the shader source when rendering this geometry, for example `SKINNED`.
+    /// Each combination of defines results in a separately cached program, so the defines can differ between geometries
+    /// without changing [Geometry::id].
+    ///
+    fn shader_defines(&self) -> ShaderDefines {
+        ShaderDefines::default()
+    }
+
     ///
     /// Render the geometry with the given [Material].
     /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
@@ -162,6 +223,15 @@ pub trait Geometry {
     /// The time parameter should be some continious time, for example the time since start.
     ///
     fn animate(&mut self, _time: f32) {}
+
+    ///
+    /// Returns whether or not the shape or placement of this geometry changes each frame, for example because it is skinned or has an animation which is updated by [Geometry::animate].
+    /// Anything rendered from an animated geometry, for example a shadow map, must be regenerated each frame to follow the animation,
+    /// see [RenderPipeline::update_shadow_maps](crate::renderer::RenderPipeline::update_shadow_maps).
+    ///
+    fn is_animated(&self) -> bool {
+        false
+    }
 }
 
 use std::ops::Deref;
@@ -220,6 +290,10 @@ impl<T: Geometry> Geometry for std::sync::RwLock<T> {
         self.read().unwrap().id(required_attributes)
     }
 
+    fn shader_defines(&self) -> ShaderDefines {
+        self.read().unwrap().shader_defines()
+    }
+
     fn render_with_material(
         &self,
         material: &dyn Material,
@@ -252,6 +326,10 @@ impl<T: Geometry> Geometry for std::sync::RwLock<T> {
         self.read().unwrap().aabb()
     }
 
+    fn is_animated(&self) -> bool {
+        self.read().unwrap().is_animated()
+    }
+
     fn animate(&mut self, time: f32) {
         self.write().unwrap().animate(time)
     }
@@ -264,6 +342,7 @@ struct BaseMesh {
     tangents: Option<VertexBuffer>,
     uvs: Option<VertexBuffer>,
     colors: Option<VertexBuffer>,
+    attributes: VertexAttributes,
 }
 
 impl BaseMesh {
@@ -286,22 +365,32 @@ impl BaseMesh {
             tangents: cpu_mesh
                 .tangents
                 .as_ref()
-                .map(|data| VertexBuffer::new_with_data(context, data)),
-            uvs: cpu_mesh.uvs.as_ref().map(|data| {
-                VertexBuffer::new_with_data(
-                    context,
-                    &data
-                        .iter()
-                        .map(|uv| vec2(uv.x, 1.0 - uv.y))
-                        .collect::<Vec<_>>(),
-                )
-            }),
+                .map(|data| VertexBuffer::new_with_data(context, data))
+                .or_else(|| {
+                    // Generate the tangents needed for normal mapping if the mesh contains normals and uv coordinates
+                    let normals = cpu_mesh.normals.as_ref()?;
+                    let uvs = cpu_mesh.uvs.as_ref()?;
+                    Some(VertexBuffer::new_with_data(
+                        context,
+                        &mesh_analysis::tangents(
+                            &cpu_mesh.positions.to_f32(),
+                            normals,
+                            uvs,
+                            &mesh_analysis::triangles(cpu_mesh),
+                        ),
+                    ))
+                }),
+            uvs: cpu_mesh
+                .uvs
+                .as_ref()
+                .map(|data| VertexBuffer::new_with_data(context, &flip_uvs(data))),
             colors: cpu_mesh.colors.as_ref().map(|data| {
                 VertexBuffer::new_with_data(
                     context,
                     &data.iter().map(|c| c.to_linear_srgb()).collect::<Vec<_>>(),
                 )
             }),
+            attributes: VertexAttributes::new(),
         }
     }
 
@@ -324,6 +413,30 @@ impl BaseMesh {
         }
     }
 
+    pub fn draw_subsets(
+        &self,
+        program: &Program,
+        render_states: RenderStates,
+        camera: &Camera,
+        attributes: FragmentAttributes,
+        subsets: &[(u32, u32)],
+    ) {
+        let index_buffer = self
+            .indices
+            .as_ref()
+            .expect("drawing a subset requires an index buffer");
+        self.use_attributes(program, attributes);
+        for (first, count) in subsets {
+            program.draw_subset_of_elements(
+                render_states,
+                camera.viewport(),
+                index_buffer,
+                *first,
+                *count,
+            );
+        }
+    }
+
     pub fn draw_instanced(
         &self,
         program: &Program,
@@ -352,11 +465,11 @@ impl BaseMesh {
     }
 
     fn use_attributes(&self, program: &Program, attributes: FragmentAttributes) {
-        program.use_vertex_attribute("position", &self.positions);
+        program.use_vertex_attribute(VertexAttribute::Position.name(), &self.positions);
 
         if attributes.normal {
             program.use_vertex_attribute(
-                "normal",
+                VertexAttribute::Normal.name(),
                 self.normals.as_ref().unwrap_or_else(|| {
                     panic!(
                         "the material requires normal attributes but the geometry did not provide it"
@@ -367,10 +480,10 @@ impl BaseMesh {
 
         if attributes.tangents {
             program.use_vertex_attribute(
-                "tangent",
+                VertexAttribute::Tangent.name(),
                 self.tangents.as_ref().unwrap_or_else(|| {
                     panic!(
-                        "the material requires tangent attributes but the geometry did not provide it"
+                        "the material requires tangent attributes but the geometry did not provide it, tangents are generated automatically if the geometry contains normals and uv coordinates"
                     )
                 }),
             );
@@ -378,7 +491,7 @@ impl BaseMesh {
 
         if attributes.uv {
             program.use_vertex_attribute(
-                "uv_coordinates",
+                VertexAttribute::Uv0.name(),
                 self.uvs.as_ref().unwrap_or_else(|| {
                     panic!(
                         "the material requires uv coordinate attributes but the geometry did not provide it"
@@ -389,8 +502,71 @@ impl BaseMesh {
 
         if attributes.color {
             if let Some(colors) = &self.colors {
-                program.use_vertex_attribute("color", colors);
+                program.use_vertex_attribute(VertexAttribute::Color.name(), colors);
             }
         }
+
+        self.attributes.use_attributes(program);
+    }
+
+    // Sets a vertex attribute given by the user of a mesh, the positions and uv coordinates must be given as data instead
+    // since the bounding box is computed from the positions and the uv coordinates are flipped like in [BaseMesh::new]
+    pub fn set_user_vertex_attribute(&mut self, attribute: VertexAttribute, buffer: VertexBuffer) {
+        match attribute {
+            VertexAttribute::Position => panic!("Failed setting vertex attribute {}: The positions must be updated using the update_positions method so that the bounding box is updated.", attribute),
+            VertexAttribute::Uv0 | VertexAttribute::Uv1 => panic!("Failed setting vertex attribute {}: The uv coordinates must be set using the set_uvs method so that they are flipped like the uv coordinates in a CpuMesh.", attribute),
+            _ => {}
+        }
+        let vertex_count = self.positions.vertex_count();
+        if buffer.vertex_count() != vertex_count {
+            panic!("Failed setting vertex attribute {}: The number of values {} does not match the number of vertices {} in the mesh.", attribute, buffer.vertex_count(), vertex_count)
+        }
+        self.set_vertex_attribute(attribute, buffer);
+    }
+
+    pub fn set_uvs(&mut self, context: &Context, attribute: VertexAttribute, uvs: &[Vec2]) {
+        if !matches!(attribute, VertexAttribute::Uv0 | VertexAttribute::Uv1) {
+            panic!(
+                "Failed setting uv coordinates: {} is not a set of uv coordinates.",
+                attribute
+            )
+        }
+        let vertex_count = self.positions.vertex_count();
+        if uvs.len() as u32 != vertex_count {
+            panic!("Failed setting vertex attribute {}: The number of uv coordinates {} does not match the number of vertices {} in the mesh.", attribute, uvs.len(), vertex_count)
+        }
+        self.set_vertex_attribute(
+            attribute,
+            VertexBuffer::new_with_data(context, &flip_uvs(uvs)),
+        );
     }
+
+    pub fn set_vertex_attribute(&mut self, attribute: VertexAttribute, buffer: VertexBuffer) {
+        match attribute {
+            VertexAttribute::Position => self.positions = buffer,
+            VertexAttribute::Normal => self.normals = Some(buffer),
+            VertexAttribute::Tangent => self.tangents = Some(buffer),
+            VertexAttribute::Uv0 => self.uvs = Some(buffer),
+            VertexAttribute::Color => self.colors = Some(buffer),
+            _ => {
+                self.attributes.insert(attribute, buffer);
+            }
+        }
+    }
+
+    pub fn remove_vertex_attribute(&mut self, attribute: &VertexAttribute) -> Option<VertexBuffer> {
+        match attribute {
+            VertexAttribute::Position => None,
+            VertexAttribute::Normal => self.normals.take(),
+            VertexAttribute::Tangent => self.tangents.take(),
+            VertexAttribute::Uv0 => self.uvs.take(),
+            VertexAttribute::Color => self.colors.take(),
+            _ => self.attributes.remove(attribute),
+        }
+    }
+}
+
+// Flips the v coordinate which is the convention used for all uv coordinates sent to the GPU
+fn flip_uvs(uvs: &[Vec2]) -> Vec<Vec2> {
+    uvs.iter().map(|uv| vec2(uv.x, 1.0 - uv.y)).collect()
 }
@@ -0,0 +1,312 @@
+use crate::core::*;
+use crate::renderer::*;
+
+use super::BaseMesh;
+
+///
+/// The range of indices in a [BatchedMesh] that belongs to one of the meshes that was merged into the batch.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatchRange {
+    /// The index of the first index in the index buffer.
+    pub first: u32,
+    /// The number of indices.
+    pub count: u32,
+    /// The bounding box of the mesh in world space.
+    pub aabb: AxisAlignedBoundingBox,
+}
+
+///
+/// A triangle mesh [Geometry] which is constructed by merging many static meshes, each with its own transformation, into one vertex and index buffer.
+/// This means that all of the meshes are rendered in one draw call instead of one draw call per mesh, which is much faster when rendering many small meshes.
+/// The meshes are baked into world space, so they cannot be moved after the batch is constructed.
+///
+/// The index range of each mesh is stored, see [BatchedMesh::ranges], and the meshes outside the view frustum are skipped when drawing.
+/// The visible meshes that are next to each other in the index buffer are drawn in one draw call,
+/// so order the meshes such that meshes close to each other in space are also close to each other in the list of meshes.
+///
+pub struct BatchedMesh {
+    context: Context,
+    base_mesh: BaseMesh,
+    ranges: Vec<BatchRange>,
+    aabb: AxisAlignedBoundingBox,
+}
+
+impl BatchedMesh {
+    ///
+    /// Creates a new batched mesh by merging the given [CpuMesh]es after applying the given transformation to each of them.
+    /// If some of the meshes have normals, uv coordinates or colors and others do not,
+    /// the normals are computed and the uv coordinates and colors are set to zero and white respectively for the meshes that are missing them.
+    ///
+    pub fn new(context: &Context, parts: &[(&CpuMesh, Mat4)]) -> Self {
+        let has_normals = parts.iter().any(|(m, _)| m.normals.is_some());
+        let has_tangents = parts.iter().all(|(m, _)| m.tangents.is_some());
+        let has_uvs = parts.iter().any(|(m, _)| m.uvs.is_some());
+        let has_colors = parts.iter().any(|(m, _)| m.colors.is_some());
+
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut tangents = Vec::new();
+        let mut uvs = Vec::new();
+        let mut colors = Vec::new();
+        let mut indices = Vec::new();
+        let mut ranges = Vec::new();
+        let mut aabb = AxisAlignedBoundingBox::EMPTY;
+        for &(cpu_mesh, transformation) in parts {
+            let vertex_offset = positions.len() as u32;
+            let first = indices.len() as u32;
+            let part_positions = cpu_mesh
+                .positions
+                .to_f32()
+                .into_iter()
+                .map(|p| (transformation * p.extend(1.0)).truncate())
+                .collect::<Vec<_>>();
+            let part_aabb = AxisAlignedBoundingBox::new_with_positions(&part_positions);
+            aabb.expand_with_aabb(&part_aabb);
+            positions.extend(part_positions);
+
+            if has_normals {
+                let normal_matrix = transformation
+                    .invert()
+                    .unwrap_or(Mat4::identity())
+                    .transpose();
+                let part_normals = cpu_mesh.normals.clone().unwrap_or_else(|| {
+                    let mut cpu_mesh = cpu_mesh.clone();
+                    cpu_mesh.compute_normals();
+                    cpu_mesh.normals.unwrap()
+                });
+                normals.extend(
+                    part_normals
+                        .into_iter()
+                        .map(|n| (normal_matrix * n.extend(0.0)).truncate().normalize()),
+                );
+            }
+            if has_tangents {
+                tangents.extend(cpu_mesh.tangents.as_ref().unwrap().iter().map(|t| {
+                    (transformation * t.truncate().extend(0.0))
+                        .truncate()
+                        .normalize()
+                        .extend(t.w)
+                }));
+            }
+            if has_uvs {
+                match &cpu_mesh.uvs {
+                    Some(part_uvs) => uvs.extend_from_slice(part_uvs),
+                    None => {
+                        uvs.extend(std::iter::repeat(vec2(0.0, 0.0)).take(cpu_mesh.vertex_count()))
+                    }
+                }
+            }
+            if has_colors {
+                match &cpu_mesh.colors {
+                    Some(part_colors) => colors.extend_from_slice(part_colors),
+                    None => {
+                        colors.extend(std::iter::repeat(Srgba::WHITE).take(cpu_mesh.vertex_count()))
+                    }
+                }
+            }
+
+            match &cpu_mesh.indices {
+                Indices::U8(ind) => indices.extend(ind.iter().map(|i| *i as u32 + vertex_offset)),
+                Indices::U16(ind) => indices.extend(ind.iter().map(|i| *i as u32 + vertex_offset)),
+                Indices::U32(ind) => indices.extend(ind.iter().map(|i| *i + vertex_offset)),
+                Indices::None => {
+                    indices.extend(vertex_offset..vertex_offset + cpu_mesh.vertex_count() as u32)
+                }
+            }
+            ranges.push(BatchRange {
+                first,
+                count: indices.len() as u32 - first,
+                aabb: part_aabb,
+            });
+        }
+
+        let cpu_mesh = CpuMesh {
+            positions: Positions::F32(positions),
+            indices: Indices::U32(indices),
+            normals: has_normals.then_some(normals),
+            tangents: (has_tangents && !parts.is_empty()).then_some(tangents),
+            uvs: has_uvs.then_some(uvs),
+            colors: has_colors.then_some(colors),
+        };
+        Self {
+            context: context.clone(),
+            base_mesh: BaseMesh::new(context, &cpu_mesh),
+            ranges,
+            aabb,
+        }
+    }
+
+    ///
+    /// Creates a new batched mesh like [BatchedMesh::new], where each mesh uses the layer with the given index when rendered with a [TextureArrayMaterial].
+    /// This makes it possible to merge meshes which only differ by texture into one batch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of texture layers does not match the number of meshes.
+    pub fn new_with_texture_layers(
+        context: &Context,
+        parts: &[(&CpuMesh, Mat4)],
+        texture_layers: &[u32],
+    ) -> Self {
+        if parts.len() != texture_layers.len() {
+            panic!(
+                "the number of texture layers ({}) must match the number of meshes ({})",
+                texture_layers.len(),
+                parts.len()
+            );
+        }
+        let mut batched_mesh = Self::new(context, parts);
+        let layers = parts
+            .iter()
+            .zip(texture_layers.iter())
+            .flat_map(|((cpu_mesh, _), layer)| {
+                std::iter::repeat(*layer as f32).take(cpu_mesh.vertex_count())
+            })
+            .collect::<Vec<_>>();
+        batched_mesh.base_mesh.set_vertex_attribute(
+            VertexAttribute::Custom("texture_layer".to_string()),
+            VertexBuffer::new_with_data(context, &layers),
+        );
+        batched_mesh
+    }
+
+    ///
+    /// Returns the index ranges of the meshes that was merged into this batch, in the same order as the meshes given to [BatchedMesh::new].
+    ///
+    pub fn ranges(&self) -> &[BatchRange] {
+        &self.ranges
+    }
+
+    ///
+    /// Returns the number of vertices in this mesh.
+    ///
+    pub fn vertex_count(&self) -> u32 {
+        self.base_mesh.positions.vertex_count()
+    }
+
+    // Returns the first index and number of indices of each continuous range of meshes that are inside the view frustum of the camera.
+    fn visible_subsets(&self, camera: &Camera) -> Vec<(u32, u32)> {
+        let mut subsets: Vec<(u32, u32)> = Vec::new();
+        let frustum = camera.frustum();
+        for range in self
+            .ranges
+            .iter()
+            .filter(|r| r.count > 0 && frustum.intersects_aabb(&r.aabb))
+        {
+            match subsets.last_mut() {
+                Some((first, count)) if *first + *count == range.first => *count += range.count,
+                _ => subsets.push((range.first, range.count)),
+            }
+        }
+        subsets
+    }
+}
+
+impl<'a> IntoIterator for &'a BatchedMesh {
+    type Item = &'a dyn Geometry;
+    type IntoIter = std::iter::Once<&'a dyn Geometry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for BatchedMesh {
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        self.aabb
+    }
+
+    fn draw(
+        &self,
+        camera: &Camera,
+        program: &Program,
+        render_states: RenderStates,
+        attributes: FragmentAttributes,
+    ) {
+        let subsets = self.visible_subsets(camera);
+        if subsets.is_empty() {
+            return;
+        }
+        program.use_uniform_if_required("normalMatrix", Mat4::identity());
+        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform("modelMatrix", Mat4::identity());
+
+        self.base_mesh
+            .draw_subsets(program, render_states, camera, attributes, &subsets);
+    }
+
+    fn vertex_shader_source(&self, required_attributes: FragmentAttributes) -> String {
+        format!(
+            "{}{}{}{}{}{}",
+            if required_attributes.normal {
+                "#define USE_NORMALS\n"
+            } else {
+                ""
+            },
+            if required_attributes.tangents {
+                "#define USE_TANGENTS\n"
+            } else {
+                ""
+            },
+            if required_attributes.uv {
+                "#define USE_UVS\n"
+            } else {
+                ""
+            },
+            if required_attributes.color && self.base_mesh.colors.is_some() {
+                "#define USE_VERTEX_COLORS\n"
+            } else {
+                ""
+            },
+            include_str!("../../core/shared.frag"),
+            include_str!("shaders/mesh.vert"),
+        )
+    }
+
+    fn id(&self, required_attributes: FragmentAttributes) -> u16 {
+        // Same shader as the Mesh
+        let mut id = 0b1u16 << 15 | 0b1u16 << 4;
+        if required_attributes.normal {
+            id |= 0b1u16;
+        }
+        if required_attributes.tangents {
+            id |= 0b1u16 << 1;
+        }
+        if required_attributes.uv {
+            id |= 0b1u16 << 2;
+        }
+        if required_attributes.color && self.base_mesh.colors.is_some() {
+            id |= 0b1u16 << 3;
+        }
+        id
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        render_with_material(&self.context, camera, &self, material, lights);
+    }
+
+    fn render_with_effect(
+        &self,
+        material: &dyn Effect,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        render_with_effect(
+            &self.context,
+            camera,
+            self,
+            material,
+            lights,
+            color_texture,
+            depth_texture,
+        )
+    }
+}
@@ -0,0 +1,408 @@
+use super::mesh_analysis::triangles;
+use super::ray::ray_triangle_intersection;
+use crate::renderer::*;
+
+///
+/// A bounding volume hierarchy over the triangles of a [CpuMesh], used to speed up ray intersection and closest point queries on large meshes,
+/// for example for picking (see [Ray::intersect_bvh]), snapping and simple collision queries.
+///
+/// The hierarchy stores a copy of the positions and normals of the mesh and all queries are in the local space of the mesh.
+/// If the mesh is deformed, for example by a [Skin], use [MeshBvh::refit] to update the bounding volumes without changing the hierarchy,
+/// which is fast but makes the queries slower if the triangles have moved far. Use [MeshBvh::rebuild] when the topology has changed.
+///
+pub struct MeshBvh {
+    positions: Vec<Vec3>,
+    normals: Option<Vec<Vec3>>,
+    triangles: Vec<[u32; 3]>,
+    nodes: Vec<BvhNode>,
+    order: Vec<usize>,
+}
+
+struct BvhNode {
+    min: Vec3,
+    max: Vec3,
+    // Either the index of the first child (the second child is at `first + 1`) or the start of the triangles in the leaf
+    first: usize,
+    count: usize,
+}
+
+///
+/// The closest point on the surface of a mesh to a query point, see [MeshBvh::closest_point].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClosestPoint {
+    /// The closest point on the surface of the mesh.
+    pub position: Vec3,
+    /// The index of the triangle containing the closest point, see [RayHit::triangle_index].
+    pub triangle_index: usize,
+    /// The distance from the query point to the closest point.
+    pub distance: f32,
+}
+
+impl MeshBvh {
+    const LEAF_SIZE: usize = 4;
+
+    ///
+    /// Builds a bounding volume hierarchy over the triangles of the given mesh.
+    ///
+    pub fn new(cpu_mesh: &CpuMesh) -> Self {
+        let positions = cpu_mesh.positions.to_f32();
+        let triangles = triangles(cpu_mesh);
+        let centers = triangles
+            .iter()
+            .map(|t| {
+                (positions[t[0] as usize] + positions[t[1] as usize] + positions[t[2] as usize])
+                    / 3.0
+            })
+            .collect::<Vec<_>>();
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            order: (0..triangles.len()).collect(),
+            positions,
+            normals: cpu_mesh.normals.clone(),
+            triangles,
+        };
+        if !bvh.triangles.is_empty() {
+            bvh.nodes.push(BvhNode {
+                min: vec3(0.0, 0.0, 0.0),
+                max: vec3(0.0, 0.0, 0.0),
+                first: 0,
+                count: bvh.triangles.len(),
+            });
+            bvh.build(0, &centers);
+        }
+        bvh
+    }
+
+    ///
+    /// Rebuilds the hierarchy from the given mesh, which can have a different number of vertices and triangles than the mesh used to build this hierarchy.
+    ///
+    pub fn rebuild(&mut self, cpu_mesh: &CpuMesh) {
+        *self = Self::new(cpu_mesh);
+    }
+
+    ///
+    /// Updates the positions and normals and the bounding volumes to the given deformed mesh, keeping the hierarchy.
+    ///
+    /// # Panic
+    /// Will panic if the number of vertices or triangles of the mesh is different from the mesh used to build this hierarchy.
+    ///
+    pub fn refit(&mut self, cpu_mesh: &CpuMesh) {
+        let positions = cpu_mesh.positions.to_f32();
+        if positions.len() != self.positions.len()
+            || triangles(cpu_mesh).len() != self.triangles.len()
+        {
+            panic!(
+                "the number of vertices ({}) and triangles ({}) must match the mesh used to build the bounding volume hierarchy ({} and {})",
+                positions.len(),
+                triangles(cpu_mesh).len(),
+                self.positions.len(),
+                self.triangles.len()
+            );
+        }
+        self.positions = positions;
+        self.normals = cpu_mesh.normals.clone();
+        // The children are always stored after their parent, so the nodes are refitted bottom-up by iterating backwards
+        for i in (0..self.nodes.len()).rev() {
+            let (first, count) = (self.nodes[i].first, self.nodes[i].count);
+            let (min, max) = if count == 0 {
+                let (a, b) = (&self.nodes[first], &self.nodes[first + 1]);
+                (
+                    vec3(
+                        a.min.x.min(b.min.x),
+                        a.min.y.min(b.min.y),
+                        a.min.z.min(b.min.z),
+                    ),
+                    vec3(
+                        a.max.x.max(b.max.x),
+                        a.max.y.max(b.max.y),
+                        a.max.z.max(b.max.z),
+                    ),
+                )
+            } else {
+                self.bounds(first, count)
+            };
+            self.nodes[i].min = min;
+            self.nodes[i].max = max;
+        }
+    }
+
+    ///
+    /// Returns the number of triangles in the hierarchy.
+    ///
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    ///
+    /// Returns the bounding box of all triangles in the hierarchy.
+    ///
+    pub fn aabb(&self) -> AxisAlignedBoundingBox {
+        self.nodes
+            .first()
+            .map(|root| AxisAlignedBoundingBox::new_with_positions(&[root.min, root.max]))
+            .unwrap_or(AxisAlignedBoundingBox::EMPTY)
+    }
+
+    ///
+    /// Returns whether or not the given ray, in the local space of the mesh, hits any triangle before the given maximum distance.
+    /// This is faster than finding the closest intersection using [Ray::intersect_bvh].
+    ///
+    pub fn intersects(&self, ray: &Ray, max_distance: f32) -> bool {
+        let inv_direction = inverse_direction(ray.direction);
+        let mut stack = vec![0; self.nodes.len().min(1)];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if ray_box_distance(ray.origin, inv_direction, node.min, node.max, max_distance)
+                .is_none()
+            {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+            } else if self.order[node.first..node.first + node.count]
+                .iter()
+                .any(|i| {
+                    self.intersect_triangle(*i, ray.origin, ray.direction)
+                        .is_some_and(|(distance, _, _)| distance < max_distance)
+                })
+            {
+                return true;
+            }
+        }
+        false
+    }
+
+    ///
+    /// Returns the closest point on the surface of the mesh to the given point, both in the local space of the mesh, or `None` if the mesh has no triangles.
+    ///
+    pub fn closest_point(&self, point: Vec3) -> Option<ClosestPoint> {
+        let mut closest: Option<(usize, Vec3, f32)> = None;
+        let mut stack = vec![0; self.nodes.len().min(1)];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if closest.is_some_and(|c| box_distance2(point, node.min, node.max) >= c.2) {
+                continue;
+            }
+            if node.count == 0 {
+                // Visit the closest child first to find a good candidate early
+                let (a, b) = (&self.nodes[node.first], &self.nodes[node.first + 1]);
+                if box_distance2(point, a.min, a.max) < box_distance2(point, b.min, b.max) {
+                    stack.push(node.first + 1);
+                    stack.push(node.first);
+                } else {
+                    stack.push(node.first);
+                    stack.push(node.first + 1);
+                }
+            } else {
+                for i in &self.order[node.first..node.first + node.count] {
+                    let t = self.triangles[*i];
+                    let position = closest_point_on_triangle(
+                        point,
+                        self.positions[t[0] as usize],
+                        self.positions[t[1] as usize],
+                        self.positions[t[2] as usize],
+                    );
+                    let distance2 = position.distance2(point);
+                    if closest.map_or(true, |c| distance2 < c.2) {
+                        closest = Some((*i, position, distance2));
+                    }
+                }
+            }
+        }
+        closest.map(|(triangle_index, position, distance2)| ClosestPoint {
+            position,
+            triangle_index,
+            distance: distance2.sqrt(),
+        })
+    }
+
+    ///
+    /// Returns the index of the closest hit triangle, the distance measured in multiples of the length of the direction
+    /// and the barycentric coordinates of the intersection point, or `None` if no triangle is hit.
+    ///
+    pub(super) fn closest_hit(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+    ) -> Option<(usize, f32, f32, f32)> {
+        let inv_direction = inverse_direction(direction);
+        let mut closest: Option<(usize, f32, f32, f32)> = None;
+        let mut stack = vec![0; self.nodes.len().min(1)];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            let max_distance = closest.map_or(f32::INFINITY, |c| c.1);
+            if ray_box_distance(origin, inv_direction, node.min, node.max, max_distance).is_none() {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+            } else {
+                for i in &self.order[node.first..node.first + node.count] {
+                    if let Some((distance, u, v)) = self.intersect_triangle(*i, origin, direction) {
+                        if closest.map_or(true, |c| distance < c.1) {
+                            closest = Some((*i, distance, u, v));
+                        }
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    pub(super) fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub(super) fn normals(&self) -> Option<&[Vec3]> {
+        self.normals.as_deref()
+    }
+
+    pub(super) fn triangle(&self, index: usize) -> [u32; 3] {
+        self.triangles[index]
+    }
+
+    fn intersect_triangle(
+        &self,
+        index: usize,
+        origin: Vec3,
+        direction: Vec3,
+    ) -> Option<(f32, f32, f32)> {
+        let t = self.triangles[index];
+        ray_triangle_intersection(
+            origin,
+            direction,
+            self.positions[t[0] as usize],
+            self.positions[t[1] as usize],
+            self.positions[t[2] as usize],
+        )
+    }
+
+    fn build(&mut self, node: usize, centers: &[Vec3]) {
+        let (first, count) = (self.nodes[node].first, self.nodes[node].count);
+        let (min, max) = self.bounds(first, count);
+        self.nodes[node].min = min;
+        self.nodes[node].max = max;
+        if count <= Self::LEAF_SIZE {
+            return;
+        }
+        let size = max - min;
+        let axis = if size.x > size.y && size.x > size.z {
+            0
+        } else if size.y > size.z {
+            1
+        } else {
+            2
+        };
+        self.order[first..first + count]
+            .sort_by(|a, b| centers[*a][axis].total_cmp(&centers[*b][axis]));
+        let half = count / 2;
+        let child = self.nodes.len();
+        self.nodes.push(BvhNode {
+            min,
+            max,
+            first,
+            count: half,
+        });
+        self.nodes.push(BvhNode {
+            min,
+            max,
+            first: first + half,
+            count: count - half,
+        });
+        self.nodes[node].first = child;
+        self.nodes[node].count = 0;
+        self.build(child, centers);
+        self.build(child + 1, centers);
+    }
+
+    // The bounds of the given range of triangles in the order.
+    fn bounds(&self, first: usize, count: usize) -> (Vec3, Vec3) {
+        let mut min = vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = vec3(f32::MIN, f32::MIN, f32::MIN);
+        for i in &self.order[first..first + count] {
+            for v in self.triangles[*i] {
+                let p = self.positions[v as usize];
+                min = vec3(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+                max = vec3(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+            }
+        }
+        (min, max)
+    }
+}
+
+fn inverse_direction(direction: Vec3) -> Vec3 {
+    vec3(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z)
+}
+
+// Returns the distance along the ray to the entry point of the box, or `None` if the box is not hit before the maximum distance.
+fn ray_box_distance(
+    origin: Vec3,
+    inv_direction: Vec3,
+    min: Vec3,
+    max: Vec3,
+    max_distance: f32,
+) -> Option<f32> {
+    let mut t_min = 0.0f32;
+    let mut t_max = max_distance;
+    for axis in 0..3 {
+        let t0 = (min[axis] - origin[axis]) * inv_direction[axis];
+        let t1 = (max[axis] - origin[axis]) * inv_direction[axis];
+        t_min = t_min.max(t0.min(t1));
+        t_max = t_max.min(t0.max(t1));
+    }
+    (t_min <= t_max).then_some(t_min)
+}
+
+fn box_distance2(point: Vec3, min: Vec3, max: Vec3) -> f32 {
+    let d = vec3(
+        (min.x - point.x).max(point.x - max.x).max(0.0),
+        (min.y - point.y).max(point.y - max.y).max(0.0),
+        (min.z - point.z).max(point.z - max.z).max(0.0),
+    );
+    d.magnitude2()
+}
+
+// The closest point on the triangle to the given point, see Real-Time Collision Detection by Christer Ericson.
+fn closest_point_on_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let sum = va + vb + vc;
+    if sum <= 0.0 {
+        // Degenerate triangle
+        return a;
+    }
+    a + ab * (vb / sum) + ac * (vc / sum)
+}
@@ -5,19 +5,30 @@ use std::sync::RwLock;
 
 use super::BaseMesh;
 
+struct InstanceBufferState {
+    buffers: HashMap<String, InstanceBuffer>,
+    // The number of instances in the buffers, which is less than the total number of instances if some are culled.
+    count: u32,
+    // The indices of the instances in the order they are stored in the buffers.
+    indices: Vec<usize>,
+    // The view projection matrix, the model matrix and whether the instances were sorted and culled when the buffers were last created.
+    key: Option<(Mat4, Mat4, bool, bool)>,
+}
+
 ///
 /// Similar to [Mesh], except it is possible to render many instances of the same mesh efficiently.
 ///
 pub struct InstancedMesh {
     context: Context,
     base_mesh: BaseMesh,
-    instance_buffers: RwLock<(HashMap<String, InstanceBuffer>, Vec3)>,
+    instance_buffers: RwLock<InstanceBufferState>,
     aabb: AxisAlignedBoundingBox,
     aabb_local: AxisAlignedBoundingBox,
     transformation: Mat4,
     current_transformation: Mat4,
     animation: Option<Box<dyn Fn(f32) -> Mat4 + Send + Sync>>,
     instances: Instances,
+    instance_culling: bool,
 }
 
 impl InstancedMesh {
@@ -31,18 +42,36 @@ impl InstancedMesh {
         let mut instanced_mesh = Self {
             context: context.clone(),
             base_mesh: BaseMesh::new(context, cpu_mesh),
-            instance_buffers: RwLock::new((Default::default(), vec3(0.0, 0.0, 0.0))),
+            instance_buffers: RwLock::new(InstanceBufferState {
+                buffers: HashMap::new(),
+                count: 0,
+                indices: Vec::new(),
+                key: None,
+            }),
             aabb,
             aabb_local: aabb,
             transformation: Mat4::identity(),
             current_transformation: Mat4::identity(),
             animation: None,
             instances: instances.clone(),
+            instance_culling: false,
         };
         instanced_mesh.set_instances(instances);
         instanced_mesh
     }
 
+    ///
+    /// Creates a new instanced 3D mesh from the given [CpuMesh] which is rendered once for each of the given transformations in a single draw call.
+    /// See [InstancedMesh::new] for setting other per-instance attributes than the transformation.
+    ///
+    pub fn new_with_transformations(
+        context: &Context,
+        transformations: &[Mat4],
+        cpu_mesh: &CpuMesh,
+    ) -> Self {
+        Self::new(context, &Instances::from(transformations), cpu_mesh)
+    }
+
     ///
     /// Returns the local to world transformation applied to all instances.
     ///
@@ -73,6 +102,26 @@ impl InstancedMesh {
         self.instances.count()
     }
 
+    ///
+    /// Enables or disables frustum culling of the individual instances. When enabled, the instances outside the view frustum of the camera are removed from the instance buffers before rendering,
+    /// which saves vertex processing when only a small part of many instances are visible. The buffers are updated each time the camera or transformation changes,
+    /// so this is mostly useful for instances that are spread out, for example vegetation, and not for many instances that are visible most of the time.
+    /// Culling is disabled by default.
+    ///
+    /// Independent of this setting, instances rendered with blending enabled, ie. with a transparent material, are sorted back to front each time the camera or transformation changes.
+    ///
+    pub fn set_instance_culling(&mut self, instance_culling: bool) {
+        self.instance_culling = instance_culling;
+        self.update_instance_buffers(None, false, false);
+    }
+
+    ///
+    /// Returns whether or not frustum culling of the individual instances is enabled, see [InstancedMesh::set_instance_culling].
+    ///
+    pub fn instance_culling(&self) -> bool {
+        self.instance_culling
+    }
+
     ///
     /// Update the instances.
     ///
@@ -82,7 +131,71 @@ impl InstancedMesh {
         self.instances = instances.clone();
         self.update_aabb();
 
-        self.update_instance_buffers(None);
+        self.update_instance_buffers(None, false, false);
+    }
+
+    ///
+    /// Returns the attributes of the instances.
+    ///
+    pub fn instances(&self) -> &Instances {
+        &self.instances
+    }
+
+    ///
+    /// Update the colors multiplied onto the base color of each instance, see [Instances::colors].
+    /// This is cheaper than [InstancedMesh::set_instances] since only the color buffer is updated.
+    ///
+    pub fn set_instance_colors(&mut self, colors: Option<Vec<Srgba>>) {
+        self.instances.colors = colors;
+        #[cfg(debug_assertions)]
+        self.instances.validate().expect("invalid instances");
+        self.update_color_buffer(&mut self.instance_buffers.write().unwrap());
+    }
+
+    ///
+    /// Update the texture transform applied to the uv coordinates of each instance, see [Instances::texture_transformations].
+    /// This is cheaper than [InstancedMesh::set_instances] since only the texture transformation buffers are updated.
+    ///
+    pub fn set_instance_texture_transformations(
+        &mut self,
+        texture_transformations: Option<Vec<Mat3>>,
+    ) {
+        self.instances.texture_transformations = texture_transformations;
+        #[cfg(debug_assertions)]
+        self.instances.validate().expect("invalid instances");
+        self.update_texture_transformation_buffers(&mut self.instance_buffers.write().unwrap());
+    }
+
+    ///
+    /// Sets the vertex buffer with the given semantic, for example normals or a custom attribute.
+    /// The built-in attributes (normal, tangent and color) are used by the built-in shaders
+    /// while any other attribute is sent to the shader if the shader contains an attribute with the name given by [VertexAttribute::name].
+    /// The positions cannot be changed.
+    /// Use [InstancedMesh::set_uvs] to set uv coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vertex buffer does not contain one value per vertex in the mesh or if the attribute is the position or a set of uv coordinates.
+    ///
+    pub fn set_vertex_attribute(&mut self, attribute: VertexAttribute, buffer: VertexBuffer) {
+        self.base_mesh.set_user_vertex_attribute(attribute, buffer);
+    }
+
+    ///
+    /// Sets the given set of uv coordinates, ie. [VertexAttribute::Uv0] or [VertexAttribute::Uv1], using the same convention as the uv coordinates in a [CpuMesh].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of uv coordinates does not match the number of vertices in the mesh or if the attribute is not a set of uv coordinates.
+    ///
+    pub fn set_uvs(&mut self, attribute: VertexAttribute, uvs: &[Vec2]) {
+        self.base_mesh.set_uvs(&self.context, attribute, uvs);
+    }
+    ///
+    /// Removes and returns the vertex buffer with the given semantic. The position attribute cannot be removed.
+    ///
+    pub fn remove_vertex_attribute(&mut self, attribute: &VertexAttribute) -> Option<VertexBuffer> {
+        self.base_mesh.remove_vertex_attribute(attribute)
     }
 
     fn update_aabb(&mut self) {
@@ -95,80 +208,120 @@ impl InstancedMesh {
         self.aabb = aabb;
     }
 
+    // The buffers only need to be sorted again when the camera moves, but culled again when the camera moves or rotates.
+    fn instance_buffer_key(
+        &self,
+        camera: &Camera,
+        sort: bool,
+        cull: bool,
+    ) -> (Mat4, Mat4, bool, bool) {
+        (
+            if cull {
+                camera.projection() * camera.view()
+            } else {
+                Mat4::from_translation(*camera.position())
+            },
+            self.current_transformation,
+            sort,
+            cull,
+        )
+    }
+
     ///
-    /// This function creates the instance buffers, ordering them by distance to the camera
+    /// This function creates the instance buffers, optionally removing the instances outside the view frustum and ordering them back to front as seen from the camera.
     ///
-    fn update_instance_buffers(&self, camera: Option<&Camera>) {
+    fn update_instance_buffers(&self, camera: Option<&Camera>, sort: bool, cull: bool) {
         let mut s = self.instance_buffers.write().unwrap();
-        let indices = if let Some(position) = camera.map(|c| *c.position()) {
-            s.1 = position;
-            // Need to order by using the position.
-            let distances = self
+        // Only translations are needed if none of the instances are rotated or scaled.
+        let translations_only =
+            self.instances.transformations.iter().all(|t| {
+                Mat3::from_cols(t.x.truncate(), t.y.truncate(), t.z.truncate()).is_identity()
+            });
+        let mut indices = (0..self.instance_count() as usize).collect::<Vec<usize>>();
+        s.key = None;
+        if let Some(camera) = camera.filter(|_| sort || cull) {
+            s.key = Some(self.instance_buffer_key(camera, sort, cull));
+            let aabbs = self
                 .instances
                 .transformations
                 .iter()
-                .map(|m| (self.transformation * m).w.truncate().distance2(position))
+                .map(|m| {
+                    let mut aabb = self.aabb_local;
+                    aabb.transform(&if translations_only {
+                        Mat4::from_translation(m.w.truncate()) * self.current_transformation
+                    } else {
+                        self.current_transformation * m
+                    });
+                    aabb
+                })
                 .collect::<Vec<_>>();
-            let mut indices = (0..self.instance_count() as usize).collect::<Vec<usize>>();
-            indices.sort_by(|a, b| {
-                distances[*b]
-                    .partial_cmp(&distances[*a])
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
-            indices
-        } else {
-            // No need to order, just return the indices as is.
-            (0..self.instances.transformations.len()).collect::<Vec<usize>>()
-        };
+            if cull {
+                let frustum = camera.frustum();
+                indices.retain(|i| frustum.intersects_aabb(&aabbs[*i]));
+            }
+            if sort {
+                let position = *camera.position();
+                let distances = aabbs
+                    .iter()
+                    .map(|aabb| aabb.center().distance2(position))
+                    .collect::<Vec<_>>();
+                indices.sort_by(|a, b| {
+                    distances[*b]
+                        .partial_cmp(&distances[*a])
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            }
+        }
+        s.count = indices.len() as u32;
+        s.indices = indices;
 
         // Next, we can compute the instance buffers with that ordering.
-        let instance_buffers = &mut s.0;
-        instance_buffers.clear();
-
-        if indices
-            .iter()
-            .map(|i| self.instances.transformations[*i])
-            .all(|t| Mat3::from_cols(t.x.truncate(), t.y.truncate(), t.z.truncate()).is_identity())
-        {
-            instance_buffers.insert(
+        s.buffers.clear();
+        if translations_only {
+            let translations = s
+                .indices
+                .iter()
+                .map(|i| self.instances.transformations[*i].w.truncate())
+                .collect::<Vec<_>>();
+            s.buffers.insert(
                 "instance_translation".to_string(),
-                InstanceBuffer::new_with_data(
-                    &self.context,
-                    &indices
-                        .iter()
-                        .map(|i| self.instances.transformations[*i])
-                        .map(|t| t.w.truncate())
-                        .collect::<Vec<_>>(),
-                ),
+                InstanceBuffer::new_with_data(&self.context, &translations),
             );
         } else {
             let mut row1 = Vec::new();
             let mut row2 = Vec::new();
             let mut row3 = Vec::new();
-            for transformation in indices.iter().map(|i| self.instances.transformations[*i]) {
+            for transformation in s.indices.iter().map(|i| self.instances.transformations[*i]) {
                 row1.push(transformation.row(0));
                 row2.push(transformation.row(1));
                 row3.push(transformation.row(2));
             }
 
-            instance_buffers.insert(
+            s.buffers.insert(
                 "row1".to_string(),
                 InstanceBuffer::new_with_data(&self.context, &row1),
             );
-            instance_buffers.insert(
+            s.buffers.insert(
                 "row2".to_string(),
                 InstanceBuffer::new_with_data(&self.context, &row2),
             );
-            instance_buffers.insert(
+            s.buffers.insert(
                 "row3".to_string(),
                 InstanceBuffer::new_with_data(&self.context, &row3),
             );
         }
+        self.update_texture_transformation_buffers(&mut s);
+        self.update_color_buffer(&mut s);
+        self.update_texture_layer_buffer(&mut s);
+    }
 
+    fn update_texture_transformation_buffers(&self, s: &mut InstanceBufferState) {
+        s.buffers.remove("tex_transform_row1");
+        s.buffers.remove("tex_transform_row2");
         if let Some(texture_transforms) = &self.instances.texture_transformations {
             let mut instance_tex_transform1 = Vec::new();
             let mut instance_tex_transform2 = Vec::new();
-            for texture_transform in indices.iter().map(|i| texture_transforms[*i]) {
+            for texture_transform in s.indices.iter().map(|i| texture_transforms[*i]) {
                 instance_tex_transform1.push(vec3(
                     texture_transform.x.x,
                     texture_transform.y.x,
@@ -180,27 +333,48 @@ impl InstancedMesh {
                     texture_transform.z.y,
                 ));
             }
-            instance_buffers.insert(
+            s.buffers.insert(
                 "tex_transform_row1".to_string(),
                 InstanceBuffer::new_with_data(&self.context, &instance_tex_transform1),
             );
-            instance_buffers.insert(
+            s.buffers.insert(
                 "tex_transform_row2".to_string(),
                 InstanceBuffer::new_with_data(&self.context, &instance_tex_transform2),
             );
         }
+    }
+
+    fn update_color_buffer(&self, s: &mut InstanceBufferState) {
+        s.buffers.remove("instance_color");
         if let Some(instance_colors) = &self.instances.colors {
             // Create the re-ordered color buffer by depth.
-            let ordered_instance_colors = indices
+            let ordered_instance_colors = s
+                .indices
                 .iter()
                 .map(|i| instance_colors[*i].to_linear_srgb())
                 .collect::<Vec<_>>();
-            instance_buffers.insert(
+            s.buffers.insert(
                 "instance_color".to_string(),
                 InstanceBuffer::new_with_data(&self.context, &ordered_instance_colors),
             );
         }
     }
+
+    fn update_texture_layer_buffer(&self, s: &mut InstanceBufferState) {
+        s.buffers.remove("instance_texture_layer");
+        if let Some(texture_layers) = &self.instances.texture_layers {
+            s.buffers.insert(
+                "instance_texture_layer".to_string(),
+                InstanceBuffer::new_with_data(
+                    &self.context,
+                    &s.indices
+                        .iter()
+                        .map(|i| texture_layers[*i] as f32)
+                        .collect::<Vec<_>>(),
+                ),
+            );
+        }
+    }
 }
 
 impl<'a> IntoIterator for &'a InstancedMesh {
@@ -220,14 +394,23 @@ impl Geometry for InstancedMesh {
         render_states: RenderStates,
         attributes: FragmentAttributes,
     ) {
-        // Check if we need a reorder, this only applies to transparent materials.
-        if render_states.blend != Blend::Disabled
-            && *camera.position() != self.instance_buffers.read().unwrap().1
-        {
-            self.update_instance_buffers(Some(camera));
+        // Check if we need to cull or reorder, reordering only applies to transparent materials.
+        let sort = render_states.blend != Blend::Disabled;
+        let cull = self.instance_culling;
+        let key = self.instance_buffers.read().unwrap().key;
+        if sort || cull {
+            if key != Some(self.instance_buffer_key(camera, sort, cull)) {
+                self.update_instance_buffers(Some(camera), sort, cull);
+            }
+        } else if key.map(|(_, _, _, culled)| culled).unwrap_or(false) {
+            self.update_instance_buffers(None, false, false);
         }
 
-        let instance_buffers = &self.instance_buffers.read().unwrap().0;
+        let state = self.instance_buffers.read().unwrap();
+        if state.count == 0 {
+            return;
+        }
+        let instance_buffers = &state.buffers;
         if attributes.normal && instance_buffers.contains_key("instance_translation") {
             if let Some(inverse) = self.current_transformation.invert() {
                 program.use_uniform_if_required("normalMatrix", inverse.transpose());
@@ -247,6 +430,7 @@ impl Geometry for InstancedMesh {
             "tex_transform_row1",
             "tex_transform_row2",
             "instance_color",
+            "instance_texture_layer",
         ] {
             if program.requires_attribute(attribute_name) {
                 program.use_instance_attribute(
@@ -256,19 +440,14 @@ impl Geometry for InstancedMesh {
                 );
             }
         }
-        self.base_mesh.draw_instanced(
-            program,
-            render_states,
-            camera,
-            attributes,
-            self.instance_count(),
-        );
+        self.base_mesh
+            .draw_instanced(program, render_states, camera, attributes, state.count);
     }
 
     fn vertex_shader_source(&self, required_attributes: FragmentAttributes) -> String {
-        let instance_buffers = &self.instance_buffers.read().unwrap().0;
+        let instance_buffers = &self.instance_buffers.read().unwrap().buffers;
         format!(
-            "{}{}{}{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}",
             if required_attributes.normal {
                 "#define USE_NORMALS\n"
             } else {
@@ -304,6 +483,11 @@ impl Geometry for InstancedMesh {
             } else {
                 ""
             },
+            if instance_buffers.contains_key("instance_texture_layer") {
+                "#define USE_INSTANCE_TEXTURE_LAYERS\n"
+            } else {
+                ""
+            },
             include_str!("../../core/shared.frag"),
             include_str!("shaders/mesh.vert"),
         )
@@ -314,7 +498,7 @@ impl Geometry for InstancedMesh {
             .instance_buffers
             .read()
             .expect("failed to acquire read access")
-            .0;
+            .buffers;
         let mut id = 0b1u16 << 15 | 0b1u16 << 7;
         if required_attributes.normal {
             id |= 0b1u16;
@@ -337,6 +521,9 @@ impl Geometry for InstancedMesh {
         if required_attributes.uv && instance_buffers.contains_key("tex_transform_row1") {
             id |= 0b1u16 << 6;
         }
+        if instance_buffers.contains_key("instance_texture_layer") {
+            id |= 0b1u16 << 8;
+        }
         id
     }
 
@@ -352,6 +539,10 @@ impl Geometry for InstancedMesh {
         }
     }
 
+    fn is_animated(&self) -> bool {
+        self.animation.is_some()
+    }
+
     fn render_with_material(
         &self,
         material: &dyn Material,
@@ -396,6 +587,8 @@ pub struct Instances {
     pub texture_transformations: Option<Vec<Mat3>>,
     /// Colors multiplied onto the base color of each instance.
     pub colors: Option<Vec<Srgba>>,
+    /// The layer of the texture array used by each instance when rendered with a [TextureArrayMaterial].
+    pub texture_layers: Option<Vec<u32>>,
 }
 
 impl Instances {
@@ -423,6 +616,10 @@ impl Instances {
         )?;
         buffer_check(Some(self.transformations.len()), "transformations")?;
         buffer_check(self.colors.as_ref().map(|b| b.len()), "colors")?;
+        buffer_check(
+            self.texture_layers.as_ref().map(|b| b.len()),
+            "texture layers",
+        )?;
 
         Ok(())
     }
@@ -431,6 +628,21 @@ impl Instances {
     pub fn count(&self) -> u32 {
         self.transformations.len() as u32
     }
+
+    ///
+    /// Sets the texture transformations so the uv coordinates of each instance are scaled by the given scale and then offset by the given offset,
+    /// for example to select a different tile of a texture atlas for each instance.
+    ///
+    pub fn set_uv_offsets(&mut self, offsets: &[Vec2], scale: Vec2) {
+        self.texture_transformations = Some(
+            offsets
+                .iter()
+                .map(|offset| {
+                    Mat3::from_translation(*offset) * Mat3::from_nonuniform_scale(scale.x, scale.y)
+                })
+                .collect(),
+        );
+    }
 }
 
 impl From<PointCloud> for Instances {
@@ -447,3 +659,18 @@ impl From<PointCloud> for Instances {
         }
     }
 }
+
+impl From<Vec<Mat4>> for Instances {
+    fn from(transformations: Vec<Mat4>) -> Self {
+        Self {
+            transformations,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&[Mat4]> for Instances {
+    fn from(transformations: &[Mat4]) -> Self {
+        Self::from(transformations.to_vec())
+    }
+}
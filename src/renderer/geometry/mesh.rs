@@ -19,6 +19,7 @@ impl Mesh {
     ///
     /// Creates a new triangle mesh from the given [CpuMesh].
     /// All data in the [CpuMesh] is transfered to the GPU, so make sure to remove all unnecessary data from the [CpuMesh] before calling this method.
+    /// If the [CpuMesh] contains normals and uv coordinates but no tangents, the tangents are computed so that the mesh can be rendered with a normal map.
     ///
     pub fn new(context: &Context, cpu_mesh: &CpuMesh) -> Self {
         let aabb = cpu_mesh.compute_aabb();
@@ -85,7 +86,7 @@ impl Mesh {
         self.base_mesh.positions.vertex_count()
     }
 
-    /// Updates the vertex positions of the mesh.
+    /// Updates the vertex positions of the mesh and the bounding box.
     ///
     /// # Panics
     ///
@@ -95,6 +96,7 @@ impl Mesh {
             panic!("Failed updating positions: The number of positions {} does not match the number of vertices {} in the mesh.", positions.len(), self.vertex_count())
         }
         self.base_mesh.positions.fill(positions);
+        self.aabb = AxisAlignedBoundingBox::new_with_positions(positions);
     }
 
     ///
@@ -114,6 +116,149 @@ impl Mesh {
             self.base_mesh.normals = Some(VertexBuffer::new_with_data(&self.context, normals));
         }
     }
+
+    ///
+    /// Updates the mesh after a deformation, for example from skinning, morphing or displacement, where the given [CpuMesh] contains the deformed positions.
+    /// The normals, and the tangents if the mesh contains uv coordinates, are recalculated from the deformed positions and stored in the given [CpuMesh]
+    /// so that lighting and normal mapping remain correct on the deformed mesh. Finally the positions, normals and tangents are updated on the GPU
+    /// and the bounding box is recomputed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of vertices in the [CpuMesh] does not match the number of vertices in the mesh.
+    pub fn update_deformation(&mut self, cpu_mesh: &mut CpuMesh) {
+        if cpu_mesh.vertex_count() as u32 != self.vertex_count() {
+            panic!("Failed updating deformation: The number of vertices {} does not match the number of vertices {} in the mesh.", cpu_mesh.vertex_count(), self.vertex_count())
+        }
+        cpu_mesh.compute_normals();
+        self.update_positions(&cpu_mesh.positions.to_f32());
+        self.update_normals(cpu_mesh.normals.as_ref().unwrap());
+        if cpu_mesh.uvs.is_some() {
+            cpu_mesh.compute_tangents();
+            if let Some(tangents) = cpu_mesh.tangents.as_ref() {
+                if let Some(tangent_buffer) = &mut self.base_mesh.tangents {
+                    tangent_buffer.fill(tangents);
+                } else {
+                    self.base_mesh.tangents =
+                        Some(VertexBuffer::new_with_data(&self.context, tangents));
+                }
+            }
+        }
+        self.aabb = cpu_mesh.compute_aabb();
+    }
+
+    ///
+    /// Updates the positions, and the normals and tangents if the given [CpuMesh] contains them, of the mesh as well as the bounding box.
+    /// In contrast to [Mesh::update_deformation], the normals and tangents are not recalculated,
+    /// which is useful when the deformation already transforms them, for example the result of [Skin::apply].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of vertices in the [CpuMesh] does not match the number of vertices in the mesh.
+    pub fn update_vertices(&mut self, cpu_mesh: &CpuMesh) {
+        if cpu_mesh.vertex_count() as u32 != self.vertex_count() {
+            panic!("Failed updating vertices: The number of vertices {} does not match the number of vertices {} in the mesh.", cpu_mesh.vertex_count(), self.vertex_count())
+        }
+        self.update_positions(&cpu_mesh.positions.to_f32());
+        if let Some(normals) = cpu_mesh.normals.as_ref() {
+            self.update_normals(normals);
+        }
+        if let Some(tangents) = cpu_mesh.tangents.as_ref() {
+            if let Some(tangent_buffer) = &mut self.base_mesh.tangents {
+                tangent_buffer.fill(tangents);
+            } else {
+                self.base_mesh.tangents =
+                    Some(VertexBuffer::new_with_data(&self.context, tangents));
+            }
+        }
+        self.aabb = cpu_mesh.compute_aabb();
+    }
+
+    ///
+    /// Updates the vertex colors of the mesh.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of colors does not match the number of vertices in the mesh.
+    pub fn update_colors(&mut self, colors: &[Srgba]) {
+        if colors.len() as u32 != self.vertex_count() {
+            panic!("Failed updating colors: The number of colors {} does not match the number of vertices {} in the mesh.", colors.len(), self.vertex_count())
+        }
+        let colors = colors
+            .iter()
+            .map(|c| c.to_linear_srgb())
+            .collect::<Vec<_>>();
+        if let Some(color_buffer) = &mut self.base_mesh.colors {
+            color_buffer.fill(&colors);
+        } else {
+            self.base_mesh.colors = Some(VertexBuffer::new_with_data(&self.context, &colors));
+        }
+    }
+
+    ///
+    /// Updates the vertex colors of the vertices starting at `first_vertex` without uploading the colors of the rest of the vertices.
+    /// If the mesh does not have vertex colors, the colors of the rest of the vertices are set to white.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the colors are outside the range of vertices in the mesh.
+    pub fn update_colors_partially(&mut self, first_vertex: u32, colors: &[Srgba]) {
+        if first_vertex as usize + colors.len() > self.vertex_count() as usize {
+            panic!("Failed updating colors: The colors of the vertices {}..{} are outside the {} vertices in the mesh.", first_vertex, first_vertex as usize + colors.len(), self.vertex_count())
+        }
+        let context = &self.context;
+        let vertex_count = self.vertex_count() as usize;
+        self.base_mesh
+            .colors
+            .get_or_insert_with(|| {
+                VertexBuffer::new_with_data(
+                    context,
+                    &vec![Srgba::WHITE.to_linear_srgb(); vertex_count],
+                )
+            })
+            .fill_subset(
+                first_vertex,
+                &colors
+                    .iter()
+                    .map(|c| c.to_linear_srgb())
+                    .collect::<Vec<_>>(),
+            );
+    }
+}
+
+impl Mesh {
+    ///
+    /// Sets the vertex buffer with the given semantic, for example normals or a custom attribute.
+    /// The built-in attributes (normal, tangent and color) are used by the built-in shaders
+    /// while any other attribute is sent to the shader if the shader contains an attribute with the name given by [VertexAttribute::name].
+    /// The positions cannot be set this way, use [Mesh::update_positions] instead so that the bounding box is updated.
+    /// Use [Mesh::set_uvs] to set uv coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vertex buffer does not contain one value per vertex in the mesh or if the attribute is the position or a set of uv coordinates.
+    ///
+    pub fn set_vertex_attribute(&mut self, attribute: VertexAttribute, buffer: VertexBuffer) {
+        self.base_mesh.set_user_vertex_attribute(attribute, buffer);
+    }
+
+    ///
+    /// Sets the given set of uv coordinates, ie. [VertexAttribute::Uv0] or [VertexAttribute::Uv1], using the same convention as the uv coordinates in a [CpuMesh].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of uv coordinates does not match the number of vertices in the mesh or if the attribute is not a set of uv coordinates.
+    ///
+    pub fn set_uvs(&mut self, attribute: VertexAttribute, uvs: &[Vec2]) {
+        self.base_mesh.set_uvs(&self.context, attribute, uvs);
+    }
+
+    ///
+    /// Removes and returns the vertex buffer with the given semantic. The position attribute cannot be removed.
+    ///
+    pub fn remove_vertex_attribute(&mut self, attribute: &VertexAttribute) -> Option<VertexBuffer> {
+        self.base_mesh.remove_vertex_attribute(attribute)
+    }
 }
 
 impl<'a> IntoIterator for &'a Mesh {
@@ -138,6 +283,10 @@ impl Geometry for Mesh {
         }
     }
 
+    fn is_animated(&self) -> bool {
+        self.animation.is_some()
+    }
+
     fn draw(
         &self,
         camera: &Camera,
@@ -0,0 +1,186 @@
+use crate::renderer::*;
+
+///
+/// Computes the ambient occlusion at each vertex of the given mesh by casting `sample_count` rays from each vertex into the hemisphere around the vertex normal
+/// and counting how many of them hit the mesh within the given maximum distance.
+/// Returns a value per vertex between 0 (fully occluded) and 1 (not occluded).
+///
+/// The result can be stored in the vertex colors using [apply_vertex_values_to_colors] or used directly as a vertex attribute.
+/// If the mesh does not contain normals, they are computed before computing the ambient occlusion.
+///
+pub fn compute_vertex_ambient_occlusion(
+    cpu_mesh: &CpuMesh,
+    sample_count: u32,
+    max_distance: f32,
+) -> Vec<f32> {
+    let positions = cpu_mesh.positions.to_f32();
+    let normals = cpu_mesh.normals.clone().unwrap_or_else(|| {
+        let mut mesh = cpu_mesh.clone();
+        mesh.compute_normals();
+        mesh.normals.unwrap()
+    });
+    let bvh = MeshBvh::new(cpu_mesh);
+    let directions = hemisphere_directions(sample_count.max(1));
+    let bias = 1.0e-4 * cpu_mesh.compute_aabb().size().magnitude().max(1.0e-4);
+
+    positions
+        .iter()
+        .zip(normals.iter())
+        .map(|(position, normal)| {
+            let normal = normal.normalize();
+            let tangent = if normal.x.abs() > 0.9 {
+                normal.cross(vec3(0.0, 1.0, 0.0)).normalize()
+            } else {
+                normal.cross(vec3(1.0, 0.0, 0.0)).normalize()
+            };
+            let bitangent = normal.cross(tangent);
+            let origin = position + normal * bias;
+            let hits = directions
+                .iter()
+                .filter(|d| {
+                    let direction = tangent * d.x + bitangent * d.y + normal * d.z;
+                    bvh.intersects(&Ray::new(origin, direction), max_distance)
+                })
+                .count();
+            1.0 - hits as f32 / directions.len() as f32
+        })
+        .collect()
+}
+
+///
+/// Computes an estimate of the mean curvature at each vertex of the given mesh based on the angle between the vertex normal and the edges to the neighbouring vertices.
+/// The curvature is positive for concave areas (for example creases) and negative for convex areas (for example edges),
+/// and the magnitude is the inverse of the radius of curvature.
+///
+/// The result can for example be used to generate a wear mask, see [apply_vertex_values_to_colors].
+/// If the mesh does not contain normals, they are computed before computing the curvature.
+///
+pub fn compute_vertex_curvature(cpu_mesh: &CpuMesh) -> Vec<f32> {
+    let positions = cpu_mesh.positions.to_f32();
+    let normals = cpu_mesh.normals.clone().unwrap_or_else(|| {
+        let mut mesh = cpu_mesh.clone();
+        mesh.compute_normals();
+        mesh.normals.unwrap()
+    });
+    let mut curvature = vec![0.0f32; positions.len()];
+    let mut count = vec![0u32; positions.len()];
+    for triangle in triangles(cpu_mesh) {
+        for i in 0..3 {
+            let a = triangle[i] as usize;
+            let b = triangle[(i + 1) % 3] as usize;
+            for (from, to) in [(a, b), (b, a)] {
+                let edge = positions[to] - positions[from];
+                let length2 = edge.magnitude2();
+                if length2 > 0.0 {
+                    curvature[from] += 2.0 * normals[from].normalize().dot(edge) / length2;
+                    count[from] += 1;
+                }
+            }
+        }
+    }
+    curvature
+        .into_iter()
+        .zip(count)
+        .map(|(c, n)| if n > 0 { c / n as f32 } else { 0.0 })
+        .collect()
+}
+
+///
+/// Multiplies the vertex colors of the given mesh with the given per-vertex values, for example the result of [compute_vertex_ambient_occlusion].
+/// The values are clamped to the range `[0, 1]` and if the mesh does not have vertex colors, they are initialized to white.
+///
+pub fn apply_vertex_values_to_colors(cpu_mesh: &mut CpuMesh, values: &[f32]) {
+    let vertex_count = cpu_mesh.vertex_count();
+    if values.len() != vertex_count {
+        panic!(
+            "the number of values ({}) must match the number of vertices ({})",
+            values.len(),
+            vertex_count
+        );
+    }
+    let colors = cpu_mesh
+        .colors
+        .get_or_insert_with(|| vec![Srgba::WHITE; vertex_count]);
+    for (color, value) in colors.iter_mut().zip(values) {
+        // Multiply in linear space and convert back to sRGB
+        let factor = value.clamp(0.0, 1.0).powf(1.0 / 2.2);
+        color.r = (color.r as f32 * factor).round() as u8;
+        color.g = (color.g as f32 * factor).round() as u8;
+        color.b = (color.b as f32 * factor).round() as u8;
+    }
+}
+
+// Computes the per vertex tangents from the given triangles without requiring a mutable or cloned CpuMesh,
+// the fourth component is the handedness of the tangent space
+pub(super) fn tangents(
+    positions: &[Vec3],
+    normals: &[Vec3],
+    uvs: &[Vec2],
+    triangles: &[[u32; 3]],
+) -> Vec<Vec4> {
+    let mut tangents = vec![vec3(0.0, 0.0, 0.0); positions.len()];
+    let mut bitangents = vec![vec3(0.0, 0.0, 0.0); positions.len()];
+    for [i0, i1, i2] in triangles.iter().map(|t| t.map(|i| i as usize)) {
+        let e1 = positions[i1] - positions[i0];
+        let e2 = positions[i2] - positions[i0];
+        let d1 = uvs[i1] - uvs[i0];
+        let d2 = uvs[i2] - uvs[i0];
+        let determinant = d1.x * d2.y - d2.x * d1.y;
+        if determinant.abs() < f32::EPSILON {
+            continue;
+        }
+        let r = 1.0 / determinant;
+        let tangent = (e1 * d2.y - e2 * d1.y) * r;
+        let bitangent = (e2 * d1.x - e1 * d2.x) * r;
+        for i in [i0, i1, i2] {
+            tangents[i] += tangent;
+            bitangents[i] += bitangent;
+        }
+    }
+    normals
+        .iter()
+        .zip(tangents)
+        .zip(bitangents)
+        .map(|((&n, t), b)| {
+            // Gram-Schmidt orthogonalize and fall back to any vector orthogonal to the normal if the uv coordinates are degenerate
+            let mut t = t - n * n.dot(t);
+            if t.magnitude2() < f32::EPSILON {
+                t = n.cross(if n.x.abs() < 0.9 {
+                    vec3(1.0, 0.0, 0.0)
+                } else {
+                    vec3(0.0, 1.0, 0.0)
+                });
+            }
+            let t = t.normalize();
+            let handedness = if n.cross(t).dot(b) < 0.0 { -1.0 } else { 1.0 };
+            t.extend(handedness)
+        })
+        .collect()
+}
+
+pub(super) fn triangles(cpu_mesh: &CpuMesh) -> Vec<[u32; 3]> {
+    let indices: Vec<u32> = match &cpu_mesh.indices {
+        Indices::U8(ind) => ind.iter().map(|i| *i as u32).collect(),
+        Indices::U16(ind) => ind.iter().map(|i| *i as u32).collect(),
+        Indices::U32(ind) => ind.clone(),
+        Indices::None => (0..cpu_mesh.vertex_count() as u32).collect(),
+    };
+    indices
+        .chunks_exact(3)
+        .map(|t| [t[0], t[1], t[2]])
+        .collect()
+}
+
+///
+/// Deterministic cosine weighted directions on the hemisphere around the z-axis.
+///
+fn hemisphere_directions(count: u32) -> Vec<Vec3> {
+    let golden_angle = std::f32::consts::PI * (3.0 - 5.0f32.sqrt());
+    (0..count)
+        .map(|i| {
+            let r = ((i as f32 + 0.5) / count as f32).sqrt();
+            let phi = i as f32 * golden_angle;
+            vec3(r * phi.cos(), r * phi.sin(), (1.0 - r * r).max(0.0).sqrt())
+        })
+        .collect()
+}
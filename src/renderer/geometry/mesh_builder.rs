@@ -0,0 +1,310 @@
+use crate::renderer::*;
+
+///
+/// Creates a [CpuMesh] by sweeping a 2D profile along a 3D path, for example to generate pipes, rails and other profiles.
+///
+/// The profile is defined in the plane orthogonal to the path, where the x-axis is the normal and the y-axis the binormal of the path.
+/// The frame is transported along the path to avoid twisting, starting from the normal closest to [Extrusion::up].
+/// A counterclockwise profile gives outward facing triangles.
+///
+/// The u coordinate of the uv coordinates follows the profile and the v coordinate follows the path.
+///
+#[derive(Clone, Debug)]
+pub struct Extrusion {
+    /// The 2D profile which is extruded.
+    pub profile: Vec<Vec2>,
+    /// Whether or not the last point of the profile is connected to the first point.
+    pub closed_profile: bool,
+    /// The path along which the profile is extruded.
+    pub path: Vec<Vec3>,
+    /// Whether or not to close the ends of the extrusion. Only used for closed profiles and requires the profile to be star-shaped around its centroid, for example convex.
+    pub caps: bool,
+    /// The direction used to orient the profile at the start of the path.
+    pub up: Vec3,
+}
+
+impl Extrusion {
+    ///
+    /// Creates a new extrusion of the given closed profile along the given path with caps at both ends.
+    ///
+    pub fn new(profile: Vec<Vec2>, path: Vec<Vec3>) -> Self {
+        Self {
+            profile,
+            closed_profile: true,
+            path,
+            caps: true,
+            up: vec3(0.0, 1.0, 0.0),
+        }
+    }
+
+    ///
+    /// Builds the mesh. The normals are computed from the triangles, so hard edges in the profile are smoothed, and are averaged across the uv seam.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the profile has less than two points or the path has less than two points.
+    ///
+    pub fn to_cpu_mesh(&self) -> CpuMesh {
+        if self.profile.len() < 2 || self.path.len() < 2 {
+            panic!("an extrusion requires at least two profile points and two path points");
+        }
+        let mut profile = self.profile.clone();
+        if self.closed_profile {
+            // Duplicate the first point to get a seam in the uv coordinates
+            profile.push(profile[0]);
+        }
+        let profile_lengths = cumulative_lengths(&profile);
+        let path_lengths = cumulative_lengths(&self.path);
+        let frames = path_frames(&self.path, self.up);
+
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut indices = Vec::new();
+        for (k, (point, (_, normal, binormal))) in self.path.iter().zip(frames.iter()).enumerate() {
+            for (i, p) in profile.iter().enumerate() {
+                positions.push(point + normal * p.x + binormal * p.y);
+                uvs.push(vec2(
+                    profile_lengths[i] / profile_lengths.last().unwrap().max(f32::EPSILON),
+                    path_lengths[k] / path_lengths.last().unwrap().max(f32::EPSILON),
+                ));
+            }
+        }
+        let ring = profile.len() as u32;
+        for k in 0..self.path.len() as u32 - 1 {
+            for i in 0..ring - 1 {
+                let a = k * ring + i;
+                let b = a + 1;
+                let c = b + ring;
+                let d = a + ring;
+                indices.extend([a, b, c, a, c, d]);
+            }
+        }
+
+        if self.caps && self.closed_profile {
+            let centroid = self.profile.iter().fold(vec2(0.0, 0.0), |acc, p| acc + p)
+                / self.profile.len() as f32;
+            let (min, max) = self.profile.iter().fold(
+                (vec2(f32::MAX, f32::MAX), vec2(f32::MIN, f32::MIN)),
+                |(min, max), p| {
+                    (
+                        vec2(min.x.min(p.x), min.y.min(p.y)),
+                        vec2(max.x.max(p.x), max.y.max(p.y)),
+                    )
+                },
+            );
+            let size = (max - min).map(|v| v.max(f32::EPSILON));
+            for (k, flip) in [(0, true), (self.path.len() - 1, false)] {
+                let (_, normal, binormal) = frames[k];
+                let to_position = |p: Vec2| self.path[k] + normal * p.x + binormal * p.y;
+                let to_uv = |p: Vec2| vec2((p.x - min.x) / size.x, (p.y - min.y) / size.y);
+                let center = positions.len() as u32;
+                positions.push(to_position(centroid));
+                uvs.push(to_uv(centroid));
+                for p in self.profile.iter() {
+                    positions.push(to_position(*p));
+                    uvs.push(to_uv(*p));
+                }
+                let n = self.profile.len() as u32;
+                for i in 0..n {
+                    let a = center + 1 + i;
+                    let b = center + 1 + (i + 1) % n;
+                    if flip {
+                        indices.extend([center, b, a]);
+                    } else {
+                        indices.extend([center, a, b]);
+                    }
+                }
+            }
+        }
+
+        let mut cpu_mesh = CpuMesh {
+            positions: Positions::F32(positions),
+            indices: Indices::U32(indices),
+            uvs: Some(uvs),
+            ..Default::default()
+        };
+        cpu_mesh.compute_normals();
+        if self.closed_profile {
+            // The first and last point of each ring are at the same position
+            weld_seam_normals(
+                &mut cpu_mesh,
+                (0..self.path.len()).map(|k| (k * ring as usize, (k + 1) * ring as usize - 1)),
+            );
+        }
+        cpu_mesh
+    }
+}
+
+///
+/// Creates a [CpuMesh] by revolving a 2D profile around the y-axis, for example to generate turned parts like vases, bottles and table legs.
+///
+/// The x coordinate of each profile point is the distance to the axis and the y coordinate is the height.
+/// A profile ordered from the bottom to the top gives outward facing triangles.
+///
+/// The u coordinate of the uv coordinates follows the rotation and the v coordinate follows the profile.
+///
+#[derive(Clone, Debug)]
+pub struct Lathe {
+    /// The 2D profile which is revolved.
+    pub profile: Vec<Vec2>,
+    /// The number of segments around the axis.
+    pub segments: u32,
+    /// The angle of the revolution, where a full revolution gives a closed surface.
+    pub angle: Radians,
+    /// Whether or not to close the bottom and the top of the surface, if the first or last profile point is not on the axis.
+    pub caps: bool,
+}
+
+impl Lathe {
+    ///
+    /// Creates a new full revolution of the given profile with the given number of segments and caps at the bottom and top.
+    ///
+    pub fn new(profile: Vec<Vec2>, segments: u32) -> Self {
+        Self {
+            profile,
+            segments,
+            angle: radians(std::f32::consts::TAU),
+            caps: true,
+        }
+    }
+
+    ///
+    /// Builds the mesh. The normals are computed from the triangles, so hard edges in the profile are smoothed, and are averaged across the uv seam.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the profile has less than two points or the number of segments is less than three.
+    ///
+    pub fn to_cpu_mesh(&self) -> CpuMesh {
+        if self.profile.len() < 2 || self.segments < 3 {
+            panic!("a lathe requires at least two profile points and three segments");
+        }
+        let profile_lengths = cumulative_lengths(&self.profile);
+        let position = |p: Vec2, j: u32| {
+            let angle = self.angle.0 * j as f32 / self.segments as f32;
+            vec3(p.x * angle.sin(), p.y, p.x * angle.cos())
+        };
+
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut indices = Vec::new();
+        for j in 0..=self.segments {
+            for (i, p) in self.profile.iter().enumerate() {
+                positions.push(position(*p, j));
+                uvs.push(vec2(
+                    j as f32 / self.segments as f32,
+                    profile_lengths[i] / profile_lengths.last().unwrap().max(f32::EPSILON),
+                ));
+            }
+        }
+        let ring = self.profile.len() as u32;
+        for j in 0..self.segments {
+            for i in 0..ring - 1 {
+                let a = j * ring + i;
+                let b = a + ring;
+                let c = b + 1;
+                let d = a + 1;
+                indices.extend([a, b, c, a, c, d]);
+            }
+        }
+
+        if self.caps {
+            let first = self.profile[0];
+            let last = *self.profile.last().unwrap();
+            for (p, is_top) in [(first, false), (last, true)] {
+                if p.x <= f32::EPSILON {
+                    continue;
+                }
+                let center = positions.len() as u32;
+                positions.push(vec3(0.0, p.y, 0.0));
+                uvs.push(vec2(0.5, 0.5));
+                for j in 0..=self.segments {
+                    let angle = self.angle.0 * j as f32 / self.segments as f32;
+                    positions.push(position(p, j));
+                    uvs.push(vec2(0.5 + 0.5 * angle.sin(), 0.5 + 0.5 * angle.cos()));
+                }
+                for j in 0..self.segments {
+                    let a = center + 1 + j;
+                    let b = a + 1;
+                    if is_top {
+                        indices.extend([center, a, b]);
+                    } else {
+                        indices.extend([center, b, a]);
+                    }
+                }
+            }
+        }
+
+        let mut cpu_mesh = CpuMesh {
+            positions: Positions::F32(positions),
+            indices: Indices::U32(indices),
+            uvs: Some(uvs),
+            ..Default::default()
+        };
+        cpu_mesh.compute_normals();
+        if (self.angle.0.abs() - std::f32::consts::TAU).abs() < 1e-4 {
+            // The first and last ring are at the same position for a full revolution
+            let last_ring = (self.segments * ring) as usize;
+            weld_seam_normals(
+                &mut cpu_mesh,
+                (0..ring as usize).map(|i| (i, last_ring + i)),
+            );
+        }
+        cpu_mesh
+    }
+}
+
+// Averages the normals of each pair of vertices which are at the same position on each side of a uv seam, so the seam is not visible in the shading.
+fn weld_seam_normals(cpu_mesh: &mut CpuMesh, pairs: impl Iterator<Item = (usize, usize)>) {
+    if let Some(normals) = cpu_mesh.normals.as_mut() {
+        for (a, b) in pairs {
+            let sum = normals[a] + normals[b];
+            if sum.magnitude2() > f32::EPSILON {
+                normals[a] = sum.normalize();
+                normals[b] = normals[a];
+            }
+        }
+    }
+}
+
+fn cumulative_lengths<V: InnerSpace<Scalar = f32> + Copy>(points: &[V]) -> Vec<f32> {
+    let mut lengths = vec![0.0];
+    for w in points.windows(2) {
+        lengths.push(lengths.last().unwrap() + (w[1] - w[0]).magnitude());
+    }
+    lengths
+}
+
+// Returns the tangent, normal and binormal at each point of the path, where the normal is transported along the path to avoid twisting.
+fn path_frames(path: &[Vec3], up: Vec3) -> Vec<(Vec3, Vec3, Vec3)> {
+    let tangents = (0..path.len())
+        .map(|k| {
+            let previous = path[k.saturating_sub(1)];
+            let next = path[(k + 1).min(path.len() - 1)];
+            let t = next - previous;
+            if t.magnitude2() > 0.0 {
+                t.normalize()
+            } else {
+                vec3(0.0, 0.0, 1.0)
+            }
+        })
+        .collect::<Vec<_>>();
+    let orthogonal = |v: Vec3, t: Vec3| {
+        let n = v - t * v.dot(t);
+        if n.magnitude2() > 0.000001 {
+            Some(n.normalize())
+        } else {
+            None
+        }
+    };
+    let mut normal = orthogonal(up, tangents[0])
+        .or_else(|| orthogonal(vec3(1.0, 0.0, 0.0), tangents[0]))
+        .unwrap_or_else(|| orthogonal(vec3(0.0, 0.0, 1.0), tangents[0]).unwrap());
+    tangents
+        .iter()
+        .map(|t| {
+            normal = orthogonal(normal, *t).unwrap_or(normal);
+            (*t, normal, t.cross(normal))
+        })
+        .collect()
+}
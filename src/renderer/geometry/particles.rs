@@ -329,4 +329,8 @@ impl Geometry for ParticleSystem {
     fn animate(&mut self, time: f32) {
         self.time = time;
     }
+
+    fn is_animated(&self) -> bool {
+        true
+    }
 }
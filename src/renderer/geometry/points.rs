@@ -0,0 +1,322 @@
+use crate::core::*;
+use crate::renderer::*;
+
+// The maximum number of points in each of the buffers the points are uploaded to.
+const POINTS_PER_CHUNK: usize = 1 << 20;
+
+///
+/// A [Geometry] which renders a large [PointCloud], for example from a laser scan, as a set of quads facing the camera with an optional color per point.
+///
+/// The points are spatially sorted and uploaded in chunks of about a million points, where each chunk is only drawn when it is inside the view frustum,
+/// so clouds with tens of millions of points can be rendered and uploaded progressively using [Points::append].
+/// The positions are stored relative to the center of the first point cloud, so the precision of clouds with large (geographic) coordinates is kept.
+///
+pub struct Points {
+    context: Context,
+    corner_buffer: VertexBuffer,
+    chunks: Vec<PointChunk>,
+    origin: Vector3<f64>,
+    has_colors: bool,
+    aabb_local: AxisAlignedBoundingBox,
+    transformation: Mat4,
+    /// The diameter of each point in world units, which means that the points get smaller on the screen when they are further away from a perspective camera.
+    pub point_size: f32,
+    /// The minimum and maximum diameter of each point in pixels which limits the attenuation given by [Points::point_size].
+    pub pixel_size_range: (f32, f32),
+}
+
+struct PointChunk {
+    positions: InstanceBuffer,
+    colors: Option<InstanceBuffer>,
+    aabb: AxisAlignedBoundingBox,
+}
+
+impl Points {
+    ///
+    /// Creates new [Points] from the given point cloud with the given point size in world units.
+    /// If the point cloud has colors, the points are rendered with these colors when the material uses vertex colors, for example [ColorMaterial].
+    ///
+    pub fn new(context: &Context, point_cloud: &PointCloud, point_size: f32) -> Self {
+        let positions = positions_f64(&point_cloud.positions);
+        let mut aabb = (
+            vec3(f64::MAX, f64::MAX, f64::MAX),
+            vec3(f64::MIN, f64::MIN, f64::MIN),
+        );
+        for p in positions.iter() {
+            aabb.0 = vec3(aabb.0.x.min(p.x), aabb.0.y.min(p.y), aabb.0.z.min(p.z));
+            aabb.1 = vec3(aabb.1.x.max(p.x), aabb.1.y.max(p.y), aabb.1.z.max(p.z));
+        }
+        let origin = if positions.is_empty() {
+            vec3(0.0, 0.0, 0.0)
+        } else {
+            0.5 * (aabb.0 + aabb.1)
+        };
+        let mut points = Self {
+            context: context.clone(),
+            corner_buffer: VertexBuffer::new_with_data(
+                context,
+                &[
+                    vec3(-1.0, -1.0, 0.0),
+                    vec3(1.0, -1.0, 0.0),
+                    vec3(1.0, 1.0, 0.0),
+                    vec3(1.0, 1.0, 0.0),
+                    vec3(-1.0, 1.0, 0.0),
+                    vec3(-1.0, -1.0, 0.0),
+                ],
+            ),
+            chunks: Vec::new(),
+            origin,
+            has_colors: point_cloud.colors.is_some(),
+            aabb_local: AxisAlignedBoundingBox::EMPTY,
+            transformation: Mat4::identity(),
+            point_size,
+            pixel_size_range: (1.0, 64.0),
+        };
+        points.append(point_cloud);
+        points
+    }
+
+    ///
+    /// Uploads the points in the given point cloud in addition to the existing points, which makes it possible to upload a very large point cloud in parts over several frames.
+    /// The colors are ignored if the point cloud given to [Points::new] did not have colors and are white if missing in this point cloud.
+    ///
+    pub fn append(&mut self, point_cloud: &PointCloud) {
+        let positions = positions_f64(&point_cloud.positions)
+            .into_iter()
+            .map(|p| (p - self.origin).cast::<f32>().unwrap())
+            .collect::<Vec<_>>();
+        if positions.is_empty() {
+            return;
+        }
+        let aabb = AxisAlignedBoundingBox::new_with_positions(&positions);
+        self.aabb_local.expand_with_aabb(&aabb);
+
+        // Sort the points along a Morton curve, so the points in each chunk are close together which makes the frustum culling of chunks efficient
+        let size = aabb.size();
+        let cell = |v: f32, min: f32, size: f32| {
+            if size > 0.0 {
+                (((v - min) / size) * 1023.0) as u32
+            } else {
+                0
+            }
+        };
+        let spread = |mut x: u32| {
+            x = (x | (x << 16)) & 0x030000ff;
+            x = (x | (x << 8)) & 0x0300f00f;
+            x = (x | (x << 4)) & 0x030c30c3;
+            (x | (x << 2)) & 0x09249249
+        };
+        let mut order = positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let min = aabb.min();
+                let code = spread(cell(p.x, min.x, size.x))
+                    | spread(cell(p.y, min.y, size.y)) << 1
+                    | spread(cell(p.z, min.z, size.z)) << 2;
+                (code, i)
+            })
+            .collect::<Vec<_>>();
+        order.sort_unstable_by_key(|(code, _)| *code);
+
+        for indices in order.chunks(POINTS_PER_CHUNK) {
+            let chunk_positions = indices
+                .iter()
+                .map(|(_, i)| positions[*i])
+                .collect::<Vec<_>>();
+            let colors = self.has_colors.then(|| {
+                let colors = indices
+                    .iter()
+                    .map(|(_, i)| {
+                        point_cloud
+                            .colors
+                            .as_ref()
+                            .map_or(Srgba::WHITE, |c| c[*i])
+                            .to_linear_srgb()
+                    })
+                    .collect::<Vec<_>>();
+                InstanceBuffer::new_with_data(&self.context, &colors)
+            });
+            self.chunks.push(PointChunk {
+                aabb: AxisAlignedBoundingBox::new_with_positions(&chunk_positions),
+                positions: InstanceBuffer::new_with_data(&self.context, &chunk_positions),
+                colors,
+            });
+        }
+    }
+
+    ///
+    /// Returns the number of points.
+    ///
+    pub fn point_count(&self) -> u32 {
+        self.chunks
+            .iter()
+            .map(|c| c.positions.instance_count())
+            .sum()
+    }
+
+    ///
+    /// Returns the position which the points are stored relative to, ie. the position in the point cloud which is placed at the origin before the transformation is applied.
+    ///
+    pub fn origin(&self) -> Vector3<f64> {
+        self.origin
+    }
+
+    ///
+    /// Returns the local to world transformation applied to the points after they are moved relative to the [Points::origin].
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.transformation
+    }
+
+    ///
+    /// Set the local to world transformation applied to the points after they are moved relative to the [Points::origin].
+    ///
+    pub fn set_transformation(&mut self, transformation: Mat4) {
+        self.transformation = transformation;
+    }
+
+    // Returns the given bounding box in local coordinates expanded by the point size and transformed to world coordinates.
+    fn world_aabb(&self, aabb: &AxisAlignedBoundingBox) -> AxisAlignedBoundingBox {
+        if aabb.is_empty() {
+            return *aabb;
+        }
+        let r = 0.5 * self.point_size;
+        let mut aabb = AxisAlignedBoundingBox::new_with_positions(&[
+            aabb.min() - vec3(r, r, r),
+            aabb.max() + vec3(r, r, r),
+        ]);
+        aabb.transform(&self.transformation);
+        aabb
+    }
+}
+
+// Returns the positions in double precision.
+fn positions_f64(positions: &Positions) -> Vec<Vector3<f64>> {
+    match positions {
+        Positions::F32(positions) => positions.iter().map(|p| p.cast().unwrap()).collect(),
+        Positions::F64(positions) => positions.clone(),
+    }
+}
+
+impl<'a> IntoIterator for &'a Points {
+    type Item = &'a dyn Geometry;
+    type IntoIter = std::iter::Once<&'a dyn Geometry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for Points {
+    fn draw(
+        &self,
+        camera: &Camera,
+        program: &Program,
+        render_states: RenderStates,
+        attributes: FragmentAttributes,
+    ) {
+        let right = camera.right_direction().normalize();
+        let up = right.cross(camera.view_direction()).normalize();
+        let projection = camera.projection();
+        program.use_uniform("viewProjection", projection * camera.view());
+        program.use_uniform("modelMatrix", self.transformation);
+        program.use_uniform("cameraRight", right);
+        program.use_uniform("cameraUp", up);
+        program.use_uniform("pointSize", self.point_size);
+        program.use_uniform(
+            "pixelSizeRange",
+            vec2(self.pixel_size_range.0, self.pixel_size_range.1),
+        );
+        program.use_uniform(
+            "pixelScale",
+            2.0 / (camera.viewport().height as f32 * projection[1][1]),
+        );
+        program.use_vertex_attribute("position", &self.corner_buffer);
+        for chunk in self.chunks.iter() {
+            if !camera.in_frustum(&self.world_aabb(&chunk.aabb)) {
+                continue;
+            }
+            program.use_instance_attribute("point_position", &chunk.positions);
+            if attributes.color {
+                if let Some(colors) = &chunk.colors {
+                    program.use_instance_attribute("point_color", colors);
+                }
+            }
+            program.draw_arrays_instanced(
+                render_states,
+                camera.viewport(),
+                6,
+                chunk.positions.instance_count(),
+            );
+        }
+    }
+
+    fn vertex_shader_source(&self, required_attributes: FragmentAttributes) -> String {
+        format!(
+            "{}{}{}{}",
+            if required_attributes.normal {
+                "#define USE_NORMALS\n"
+            } else {
+                ""
+            },
+            if required_attributes.uv {
+                "#define USE_UVS\n"
+            } else {
+                ""
+            },
+            if required_attributes.color && self.has_colors {
+                "#define USE_VERTEX_COLORS\n"
+            } else {
+                ""
+            },
+            include_str!("shaders/points.vert"),
+        )
+    }
+
+    fn id(&self, required_attributes: FragmentAttributes) -> u16 {
+        let mut id = 0b1u16 << 15 | 0b1u16 << 9;
+        if required_attributes.normal {
+            id |= 0b1u16;
+        }
+        if required_attributes.uv {
+            id |= 0b1u16 << 2;
+        }
+        if required_attributes.color && self.has_colors {
+            id |= 0b1u16 << 3;
+        }
+        id
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        render_with_material(&self.context, camera, self, material, lights);
+    }
+
+    fn render_with_effect(
+        &self,
+        material: &dyn Effect,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        render_with_effect(
+            &self.context,
+            camera,
+            self,
+            material,
+            lights,
+            color_texture,
+            depth_texture,
+        )
+    }
+
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        self.world_aabb(&self.aabb_local)
+    }
+}
@@ -0,0 +1,201 @@
+use super::mesh_analysis::triangles;
+use crate::renderer::*;
+
+///
+/// A ray given by an origin and a direction, used for picking objects and triangles on the CPU.
+/// See [Camera::ray_from_screen] for constructing a ray through a pixel on the screen.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    /// The start point of the ray.
+    pub origin: Vec3,
+    /// The direction of the ray with unit length.
+    pub direction: Vec3,
+}
+
+///
+/// The closest intersection between a [Ray] and a mesh, see [Ray::intersect].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// The intersection point in world space.
+    pub position: Vec3,
+    /// The normal at the intersection point in world space with unit length.
+    /// The normal is interpolated from the vertex normals if the mesh has normals, otherwise it is the normal of the hit triangle given by the counter clockwise winding order.
+    pub normal: Vec3,
+    /// The index of the hit triangle, ie. the triangle defined by the indices `3 * triangle_index` to `3 * triangle_index + 2`
+    /// or by the positions with these indices if the mesh is not indexed.
+    pub triangle_index: usize,
+    /// The distance from the origin of the ray to the intersection point.
+    pub distance: f32,
+}
+
+impl Ray {
+    ///
+    /// Constructs a new ray with the given origin and direction. The direction is normalized.
+    ///
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    ///
+    /// Returns the point at the given distance along the ray.
+    ///
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    ///
+    /// Returns the distance along the ray to the entry point of the given bounding box, zero if the origin is inside the box,
+    /// or `None` if the ray does not hit the box.
+    ///
+    pub fn intersect_aabb(&self, aabb: &AxisAlignedBoundingBox) -> Option<f32> {
+        if aabb.is_empty() {
+            return None;
+        }
+        let (min, max) = (aabb.min(), aabb.max());
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+        for axis in 0..3 {
+            let inv_direction = 1.0 / self.direction[axis];
+            let t0 = (min[axis] - self.origin[axis]) * inv_direction;
+            let t1 = (max[axis] - self.origin[axis]) * inv_direction;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+        (t_min <= t_max).then_some(t_min)
+    }
+
+    ///
+    /// Finds the closest intersection between this ray and the triangles of the given mesh transformed by the given transformation,
+    /// for example the transformation of the [Mesh] constructed from the CPU mesh.
+    /// Returns `None` if no triangle is hit or if the transformation is not invertible.
+    /// Both sides of the triangles are hit.
+    ///
+    pub fn intersect(&self, cpu_mesh: &CpuMesh, transformation: &Mat4) -> Option<RayHit> {
+        // Transform the ray into the space of the mesh, keeping the direction unnormalized so the distances are the same in both spaces
+        let inverse = transformation.invert()?;
+        let origin = (inverse * self.origin.extend(1.0)).truncate();
+        let direction = (inverse * self.direction.extend(0.0)).truncate();
+
+        let positions = cpu_mesh.positions.to_f32();
+        let mut closest: Option<(usize, [u32; 3], f32, f32, f32)> = None;
+        for (i, t) in triangles(cpu_mesh).into_iter().enumerate() {
+            if let Some((distance, u, v)) = ray_triangle_intersection(
+                origin,
+                direction,
+                positions[t[0] as usize],
+                positions[t[1] as usize],
+                positions[t[2] as usize],
+            ) {
+                if closest.map_or(true, |c| distance < c.2) {
+                    closest = Some((i, t, distance, u, v));
+                }
+            }
+        }
+        let (triangle_index, t, distance, u, v) = closest?;
+        Some(self.hit(
+            &inverse,
+            &positions,
+            cpu_mesh.normals.as_deref(),
+            t,
+            triangle_index,
+            distance,
+            u,
+            v,
+        ))
+    }
+
+    ///
+    /// Finds the closest intersection between this ray and the triangles in the given bounding volume hierarchy transformed by the given transformation,
+    /// which is much faster than [Ray::intersect] for large meshes.
+    /// Returns `None` if no triangle is hit or if the transformation is not invertible.
+    /// Both sides of the triangles are hit.
+    ///
+    pub fn intersect_bvh(&self, bvh: &MeshBvh, transformation: &Mat4) -> Option<RayHit> {
+        let inverse = transformation.invert()?;
+        let origin = (inverse * self.origin.extend(1.0)).truncate();
+        let direction = (inverse * self.direction.extend(0.0)).truncate();
+        let (triangle_index, distance, u, v) = bvh.closest_hit(origin, direction)?;
+        Some(self.hit(
+            &inverse,
+            bvh.positions(),
+            bvh.normals(),
+            bvh.triangle(triangle_index),
+            triangle_index,
+            distance,
+            u,
+            v,
+        ))
+    }
+
+    fn hit(
+        &self,
+        inverse: &Mat4,
+        positions: &[Vec3],
+        normals: Option<&[Vec3]>,
+        t: [u32; 3],
+        triangle_index: usize,
+        distance: f32,
+        u: f32,
+        v: f32,
+    ) -> RayHit {
+        let normal = match normals {
+            Some(normals) => {
+                normals[t[0] as usize] * (1.0 - u - v)
+                    + normals[t[1] as usize] * u
+                    + normals[t[2] as usize] * v
+            }
+            None => (positions[t[1] as usize] - positions[t[0] as usize])
+                .cross(positions[t[2] as usize] - positions[t[0] as usize]),
+        };
+        let normal = (inverse.transpose() * normal.extend(0.0)).truncate();
+        RayHit {
+            position: self.at(distance),
+            normal: if normal.magnitude2() > 0.0 {
+                normal.normalize()
+            } else {
+                -self.direction
+            },
+            triangle_index,
+            distance,
+        }
+    }
+}
+
+///
+/// Returns the distance along the ray and the barycentric coordinates `(u, v)` of the intersection point with the triangle,
+/// where the distance is measured in multiples of the length of the direction, or `None` if the ray does not hit the triangle.
+///
+pub(super) fn ray_triangle_intersection(
+    origin: Vec3,
+    direction: Vec3,
+    p0: Vec3,
+    p1: Vec3,
+    p2: Vec3,
+) -> Option<(f32, f32, f32)> {
+    // Möller–Trumbore intersection
+    let edge1 = p1 - p0;
+    let edge2 = p2 - p0;
+    let h = direction.cross(edge2);
+    let a = edge1.dot(h);
+    if a.abs() < 1.0e-8 {
+        return None;
+    }
+    let f = 1.0 / a;
+    let s = origin - p0;
+    let u = f * s.dot(h);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(edge1);
+    let v = f * direction.dot(q);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = f * edge2.dot(q);
+    (t > 0.0).then_some((t, u, v))
+}
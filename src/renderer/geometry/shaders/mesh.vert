@@ -20,6 +20,21 @@ in vec4 row2;
 in vec4 row3;
 #endif
 
+#ifdef SKINNED
+in vec4 joints;
+in vec4 weights;
+uniform sampler2D jointMatrices;
+
+mat4 joint_matrix(float joint)
+{
+    int j = int(joint);
+    return mat4(texelFetch(jointMatrices, ivec2(0, j), 0),
+        texelFetch(jointMatrices, ivec2(1, j), 0),
+        texelFetch(jointMatrices, ivec2(2, j), 0),
+        texelFetch(jointMatrices, ivec2(3, j), 0));
+}
+#endif
+
 out vec3 pos;
 
 #ifdef USE_NORMALS 
@@ -54,6 +69,56 @@ in vec4 instance_color;
 
 out vec4 col;
 
+#ifdef USE_WIND
+uniform vec3 windDirection;
+uniform float windGustSize;
+uniform float windPhase;
+uniform vec3 windTravel;
+
+// The hash repeats every 256 lattice cells, so the travel of the gusts can be wrapped without a visible jump
+float wind_hash(vec3 p)
+{
+    p = mod(p, 256.0);
+    p = fract(p * 0.3183099 + 0.1);
+    p *= 17.0;
+    return fract(p.x * p.y * p.z * (p.x + p.y + p.z));
+}
+
+// Smooth value noise in the range [0, 1]
+float wind_noise(vec3 p)
+{
+    vec3 i = floor(p);
+    vec3 f = fract(p);
+    f = f * f * (3.0 - 2.0 * f);
+    return mix(mix(mix(wind_hash(i), wind_hash(i + vec3(1.0, 0.0, 0.0)), f.x),
+                   mix(wind_hash(i + vec3(0.0, 1.0, 0.0)), wind_hash(i + vec3(1.0, 1.0, 0.0)), f.x), f.y),
+               mix(mix(wind_hash(i + vec3(0.0, 0.0, 1.0)), wind_hash(i + vec3(1.0, 0.0, 1.0)), f.x),
+                   mix(wind_hash(i + vec3(0.0, 1.0, 1.0)), wind_hash(i + vec3(1.0, 1.0, 1.0)), f.x), f.y), f.z);
+}
+
+// The displacement of a vertex at the given world position with full weight, where gusts travel along the wind direction and the vertices sway back and forth
+vec3 wind_offset(vec3 p)
+{
+    float strength = length(windDirection);
+    if (strength <= 0.0) {
+        return vec3(0.0);
+    }
+    vec3 q = p / max(windGustSize, 0.0001);
+    float gust = wind_noise(q - windTravel);
+    float sway = sin(6.2831853 * (windPhase + wind_noise(0.5 * q)));
+    return windDirection * gust * (0.7 + 0.3 * sway);
+}
+#endif
+
+#ifdef USE_TEXTURE_LAYERS
+#ifdef USE_INSTANCE_TEXTURE_LAYERS
+in float instance_texture_layer;
+#else
+in float texture_layer;
+#endif
+flat out float textureLayer;
+#endif
+
 void main()
 {
     // *** POSITION ***
@@ -68,6 +133,15 @@ void main()
     local2World *= transform;
 #endif
 
+#ifdef SKINNED
+    float total_weight = weights.x + weights.y + weights.z + weights.w;
+    if (total_weight > 0.0) {
+        mat4 skin = (weights.x * joint_matrix(joints.x) + weights.y * joint_matrix(joints.y)
+            + weights.z * joint_matrix(joints.z) + weights.w * joint_matrix(joints.w)) / total_weight;
+        local2World *= skin;
+    }
+#endif
+
     vec4 worldPosition = local2World * vec4(position, 1.);
     worldPosition /= worldPosition.w;
 #ifdef PARTICLES
@@ -75,6 +149,14 @@ void main()
 #endif
 #ifdef USE_INSTANCE_TRANSLATIONS 
     worldPosition.xyz += instance_translation;
+#endif
+#ifdef USE_WIND
+    // The alpha channel of the vertex color is the weight of the wind displacement
+    float windWeight = 1.0;
+#ifdef USE_VERTEX_COLORS
+    windWeight = color.a;
+#endif
+    worldPosition.xyz += windWeight * wind_offset(worldPosition.xyz);
 #endif
     gl_Position = viewProjection * worldPosition;
 
@@ -82,7 +164,7 @@ void main()
 
     // *** NORMAL ***
 #ifdef USE_NORMALS 
-#ifdef USE_INSTANCE_TRANSFORMS
+#if defined(USE_INSTANCE_TRANSFORMS) || defined(SKINNED)
     mat3 normalMat = mat3(transpose(inverse(local2World)));
 #else
     mat3 normalMat = mat3(normalMatrix);
@@ -112,9 +194,22 @@ void main()
     // *** COLOR ***
     col = vec4(1.0);
 #ifdef USE_VERTEX_COLORS 
+#ifdef USE_WIND
+    col *= vec4(color.rgb, 1.0);
+#else
     col *= color;
 #endif
+#endif
 #ifdef USE_INSTANCE_COLORS
     col *= instance_color;
+#endif
+
+    // *** TEXTURE LAYER ***
+#ifdef USE_TEXTURE_LAYERS
+#ifdef USE_INSTANCE_TEXTURE_LAYERS
+    textureLayer = instance_texture_layer;
+#else
+    textureLayer = texture_layer;
+#endif
 #endif
 }
\ No newline at end of file
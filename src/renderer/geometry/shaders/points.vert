@@ -0,0 +1,52 @@
+uniform mat4 viewProjection;
+uniform mat4 modelMatrix;
+uniform vec3 cameraRight;
+uniform vec3 cameraUp;
+uniform float pointSize;
+uniform vec2 pixelSizeRange;
+uniform float pixelScale;
+
+in vec3 position;
+in vec3 point_position;
+
+#ifdef USE_VERTEX_COLORS
+in vec4 point_color;
+#endif
+
+out vec3 pos;
+out vec4 col;
+
+#ifdef USE_NORMALS
+out vec3 nor;
+#endif
+
+#ifdef USE_UVS
+out vec2 uvs;
+#endif
+
+void main()
+{
+    vec4 center = modelMatrix * vec4(point_position, 1.0);
+    center /= center.w;
+
+    // The size of a pixel in world units at the distance of the point
+    float pixelSize = pixelScale * (viewProjection * center).w;
+    float size = clamp(pointSize, pixelSizeRange.x * pixelSize, pixelSizeRange.y * pixelSize);
+
+    vec3 worldPosition = center.xyz + 0.5 * size * (position.x * cameraRight + position.y * cameraUp);
+    gl_Position = viewProjection * vec4(worldPosition, 1.0);
+    pos = worldPosition;
+
+#ifdef USE_NORMALS
+    nor = normalize(cross(cameraRight, cameraUp));
+#endif
+
+#ifdef USE_UVS
+    uvs = 0.5 * position.xy + 0.5;
+#endif
+
+    col = vec4(1.0);
+#ifdef USE_VERTEX_COLORS
+    col *= point_color;
+#endif
+}
@@ -0,0 +1,303 @@
+use crate::renderer::*;
+
+///
+/// The local transformation of a joint relative to its parent, given as a translation, rotation and scale which are applied in reverse order.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointPose {
+    /// The translation of the joint.
+    pub translation: Vec3,
+    /// The rotation of the joint.
+    pub rotation: Quat,
+    /// The non-uniform scale of the joint.
+    pub scale: Vec3,
+}
+
+impl JointPose {
+    ///
+    /// Decomposes the given transformation into a pose. The transformation must consist of a translation, rotation and (non-uniform) scale, ie. without shear.
+    ///
+    pub fn from_transformation(transformation: Mat4) -> Self {
+        let x = transformation.x.truncate();
+        let y = transformation.y.truncate();
+        let z = transformation.z.truncate();
+        let mut scale = vec3(x.magnitude(), y.magnitude(), z.magnitude());
+        if Mat3::from_cols(x, y, z).determinant() < 0.0 {
+            scale.x = -scale.x;
+        }
+        let divide = |v: Vec3, s: f32| if s != 0.0 { v / s } else { v };
+        Self {
+            translation: transformation.w.truncate(),
+            rotation: Quat::from(Mat3::from_cols(
+                divide(x, scale.x),
+                divide(y, scale.y),
+                divide(z, scale.z),
+            )),
+            scale,
+        }
+    }
+
+    ///
+    /// Returns the transformation matrix of this pose.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        Mat4::from_translation(self.translation)
+            * Mat4::from(self.rotation)
+            * Mat4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    ///
+    /// Returns the pose between this pose and the other pose given by the factor in the range `[0, 1]`,
+    /// using linear interpolation for the translation and scale and spherical linear interpolation for the rotation.
+    ///
+    pub fn interpolate(&self, other: &Self, factor: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, factor),
+            rotation: self.rotation.slerp(other.rotation, factor),
+            scale: self.scale.lerp(other.scale, factor),
+        }
+    }
+}
+
+impl Default for JointPose {
+    fn default() -> Self {
+        Self {
+            translation: vec3(0.0, 0.0, 0.0),
+            rotation: Quat::new(1.0, 0.0, 0.0, 0.0),
+            scale: vec3(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+///
+/// A hierarchy of joints used for deforming a mesh, see [Skin] and [SkinnedMesh].
+/// The joints are identified by their index, which must correspond to the joint indices used in the [Skin].
+///
+#[derive(Clone, Debug, Default)]
+pub struct Skeleton {
+    parents: Vec<Option<usize>>,
+    rest_pose: Vec<JointPose>,
+}
+
+impl Skeleton {
+    ///
+    /// Creates a new skeleton from the parent of each joint, `None` for the root joints, and the local pose of each joint in the rest pose.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parents and poses are not the same, if a parent index is out of range or if the hierarchy contains a cycle.
+    pub fn new(parents: Vec<Option<usize>>, rest_pose: Vec<JointPose>) -> Self {
+        if parents.len() != rest_pose.len() {
+            panic!(
+                "the number of parents ({}) must match the number of joint poses ({})",
+                parents.len(),
+                rest_pose.len()
+            );
+        }
+        for joint in 0..parents.len() {
+            let mut current = joint;
+            for _ in 0..parents.len() {
+                match parents[current] {
+                    Some(parent) if parent >= parents.len() => panic!(
+                        "the parent index {} is out of range, there are {} joints",
+                        parent,
+                        parents.len()
+                    ),
+                    Some(parent) => current = parent,
+                    None => break,
+                }
+            }
+            if parents[current].is_some() {
+                panic!("the joint hierarchy must not contain cycles");
+            }
+        }
+        Self { parents, rest_pose }
+    }
+
+    ///
+    /// Returns the number of joints.
+    ///
+    pub fn joint_count(&self) -> usize {
+        self.parents.len()
+    }
+
+    ///
+    /// Returns the parent of the given joint or `None` if the joint is a root joint.
+    ///
+    pub fn parent(&self, joint: usize) -> Option<usize> {
+        self.parents[joint]
+    }
+
+    ///
+    /// Returns the local pose of each joint in the rest pose.
+    ///
+    pub fn rest_pose(&self) -> &[JointPose] {
+        &self.rest_pose
+    }
+
+    ///
+    /// Returns the transformation of each joint in the coordinate system of the skeleton given the local pose of each joint,
+    /// ie. the local transformation of the joint multiplied by the transformations of all its ancestors.
+    /// The result can be given directly to [SkinnedMesh::set_joint_transformations] or [Skin::apply].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of poses does not match the number of joints.
+    pub fn joint_transformations(&self, pose: &[JointPose]) -> Vec<Mat4> {
+        if pose.len() != self.joint_count() {
+            panic!(
+                "the number of joint poses ({}) must match the number of joints ({})",
+                pose.len(),
+                self.joint_count()
+            );
+        }
+        let mut transformations: Vec<Option<Mat4>> = vec![None; self.joint_count()];
+        for joint in 0..self.joint_count() {
+            self.joint_transformation(joint, pose, &mut transformations);
+        }
+        transformations.into_iter().map(|t| t.unwrap()).collect()
+    }
+
+    // Computes the transformation of the joint and its ancestors, if they are not already computed.
+    fn joint_transformation(
+        &self,
+        joint: usize,
+        pose: &[JointPose],
+        transformations: &mut [Option<Mat4>],
+    ) -> Mat4 {
+        if let Some(transformation) = transformations[joint] {
+            return transformation;
+        }
+        let local = pose[joint].transformation();
+        let transformation = match self.parents[joint] {
+            Some(parent) => self.joint_transformation(parent, pose, transformations) * local,
+            None => local,
+        };
+        transformations[joint] = Some(transformation);
+        transformation
+    }
+}
+
+///
+/// How the values between two keyframes of an [AnimationChannel] are computed.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AnimationInterpolation {
+    /// The value of the previous keyframe is used until the next keyframe.
+    Step,
+    /// The values are interpolated linearly, using spherical linear interpolation for rotations.
+    #[default]
+    Linear,
+}
+
+///
+/// The keyframes of the local pose of a single joint in an [AnimationClip].
+/// Each of the translations, rotations and scales must either be `None`, in which case the value from the rest pose is used, or contain one value per keyframe time.
+///
+#[derive(Clone, Debug, Default)]
+pub struct AnimationChannel {
+    /// The index of the animated joint.
+    pub joint: usize,
+    /// The time of each keyframe in seconds in increasing order.
+    pub times: Vec<f32>,
+    /// The translation of the joint at each keyframe.
+    pub translations: Option<Vec<Vec3>>,
+    /// The rotation of the joint at each keyframe.
+    pub rotations: Option<Vec<Quat>>,
+    /// The scale of the joint at each keyframe.
+    pub scales: Option<Vec<Vec3>>,
+    /// How the values between the keyframes are computed.
+    pub interpolation: AnimationInterpolation,
+}
+
+impl AnimationChannel {
+    // Returns the index of the keyframe before the given time and the interpolation factor towards the next keyframe.
+    fn keyframe(&self, time: f32) -> (usize, usize, f32) {
+        let last = self.times.len().saturating_sub(1);
+        let next = self.times.partition_point(|t| *t <= time);
+        if next == 0 {
+            (0, 0, 0.0)
+        } else if next > last {
+            (last, last, 0.0)
+        } else {
+            let previous = next - 1;
+            let factor = match self.interpolation {
+                AnimationInterpolation::Step => 0.0,
+                AnimationInterpolation::Linear => {
+                    let duration = self.times[next] - self.times[previous];
+                    if duration > 0.0 {
+                        (time - self.times[previous]) / duration
+                    } else {
+                        0.0
+                    }
+                }
+            };
+            (previous, next, factor)
+        }
+    }
+
+    fn apply(&self, time: f32, pose: &mut JointPose) {
+        if self.times.is_empty() {
+            return;
+        }
+        let (previous, next, factor) = self.keyframe(time);
+        if let Some(translations) = &self.translations {
+            pose.translation = translations[previous].lerp(translations[next], factor);
+        }
+        if let Some(rotations) = &self.rotations {
+            pose.rotation = if factor > 0.0 {
+                rotations[previous].slerp(rotations[next], factor)
+            } else {
+                rotations[previous]
+            };
+        }
+        if let Some(scales) = &self.scales {
+            pose.scale = scales[previous].lerp(scales[next], factor);
+        }
+    }
+}
+
+///
+/// An animation of the joints of a [Skeleton], for example a walk cycle, which can be sampled at any time to get the pose of the skeleton.
+///
+#[derive(Clone, Debug, Default)]
+pub struct AnimationClip {
+    /// The name of the animation.
+    pub name: String,
+    /// The keyframes of each animated joint.
+    pub channels: Vec<AnimationChannel>,
+}
+
+impl AnimationClip {
+    ///
+    /// Returns the duration of the animation in seconds, ie. the time of the last keyframe.
+    ///
+    pub fn duration(&self) -> f32 {
+        self.channels
+            .iter()
+            .filter_map(|channel| channel.times.last())
+            .fold(0.0, |a, b| a.max(*b))
+    }
+
+    ///
+    /// Returns the local pose of each joint of the given skeleton at the given time in seconds.
+    /// The joints which are not animated by this clip are in the rest pose and the time is clamped to the duration of the animation,
+    /// so use `time % clip.duration()` to loop the animation.
+    ///
+    pub fn sample(&self, skeleton: &Skeleton, time: f32) -> Vec<JointPose> {
+        let mut pose = skeleton.rest_pose().to_vec();
+        for channel in self.channels.iter() {
+            if let Some(joint_pose) = pose.get_mut(channel.joint) {
+                channel.apply(time, joint_pose);
+            }
+        }
+        pose
+    }
+
+    ///
+    /// Returns the transformation of each joint of the given skeleton at the given time in seconds, see [AnimationClip::sample] and [Skeleton::joint_transformations].
+    ///
+    pub fn joint_transformations(&self, skeleton: &Skeleton, time: f32) -> Vec<Mat4> {
+        skeleton.joint_transformations(&self.sample(skeleton, time))
+    }
+}
@@ -0,0 +1,176 @@
+use crate::renderer::*;
+
+///
+/// The weighted joint influences of the vertices of a [CpuMesh], used for deforming the mesh according to a skeleton on the CPU.
+///
+/// Skinning on the CPU is slower than on the GPU but works on all backends, also those without enough vertex uniforms or textures
+/// to hold the joint matrices, and the deformed geometry is available on the CPU, for example for export, collision detection or picking.
+/// The result of [Skin::apply] can be uploaded to a [Mesh] using [Mesh::update_vertices].
+///
+#[derive(Clone, Debug, Default)]
+pub struct Skin {
+    /// The indices of the (up to) four joints affecting each vertex.
+    pub joints: Vec<[u16; 4]>,
+    /// The weights of the four joints affecting each vertex. The weights of a vertex are normalized when skinning, so they don't need to sum to one.
+    /// A vertex where all weights are zero is not deformed.
+    pub weights: Vec<[f32; 4]>,
+    /// The inverse bind matrix of each joint, ie. the transformation from the coordinate system of the mesh to the coordinate system of the joint in the bind pose.
+    pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+impl Skin {
+    ///
+    /// Creates a new skin from the joint indices and weights of each vertex and the inverse bind matrix of each joint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of joint indices and weights are not the same or if a joint index refers to a joint without an inverse bind matrix.
+    pub fn new(
+        joints: Vec<[u16; 4]>,
+        weights: Vec<[f32; 4]>,
+        inverse_bind_matrices: Vec<Mat4>,
+    ) -> Self {
+        if joints.len() != weights.len() {
+            panic!(
+                "the number of joint indices ({}) must match the number of weights ({})",
+                joints.len(),
+                weights.len()
+            );
+        }
+        if let Some(joint) = joints
+            .iter()
+            .flatten()
+            .find(|j| **j as usize >= inverse_bind_matrices.len())
+        {
+            panic!(
+                "the joint index {} is out of range, there are {} joints",
+                joint,
+                inverse_bind_matrices.len()
+            );
+        }
+        Self {
+            joints,
+            weights,
+            inverse_bind_matrices,
+        }
+    }
+
+    ///
+    /// Returns the number of vertices affected by this skin.
+    ///
+    pub fn vertex_count(&self) -> usize {
+        self.joints.len()
+    }
+
+    ///
+    /// Returns the number of joints.
+    ///
+    pub fn joint_count(&self) -> usize {
+        self.inverse_bind_matrices.len()
+    }
+
+    ///
+    /// Returns the skinning matrix of each joint given the current transformation of each joint,
+    /// ie. the joint transformation multiplied by the inverse bind matrix.
+    /// These are the matrices to send to a shader when skinning on the GPU.
+    ///
+    /// The joint transformations must be given in the coordinate system of the mesh, so if the joints are part of a scene hierarchy,
+    /// multiply the global transformation of each joint by the inverse of the global transformation of the mesh.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of joint transformations does not match the number of joints.
+    pub fn skinning_matrices(&self, joint_transformations: &[Mat4]) -> Vec<Mat4> {
+        if joint_transformations.len() != self.joint_count() {
+            panic!(
+                "the number of joint transformations ({}) must match the number of joints ({})",
+                joint_transformations.len(),
+                self.joint_count()
+            );
+        }
+        joint_transformations
+            .iter()
+            .zip(self.inverse_bind_matrices.iter())
+            .map(|(transformation, inverse_bind_matrix)| transformation * inverse_bind_matrix)
+            .collect()
+    }
+
+    ///
+    /// Returns a copy of the given [CpuMesh] deformed by the joints with the given transformations (see [Skin::skinning_matrices]).
+    /// The positions are blended using linear blend skinning and the normals and tangents, if present, are transformed accordingly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of vertices in the [CpuMesh] does not match the number of vertices in this skin
+    /// or if the number of joint transformations does not match the number of joints.
+    pub fn apply(&self, cpu_mesh: &CpuMesh, joint_transformations: &[Mat4]) -> CpuMesh {
+        if cpu_mesh.vertex_count() != self.vertex_count() {
+            panic!(
+                "the number of vertices in the mesh ({}) must match the number of vertices in the skin ({})",
+                cpu_mesh.vertex_count(),
+                self.vertex_count()
+            );
+        }
+        let skinning_matrices = self.skinning_matrices(joint_transformations);
+        let vertex_matrices = (0..self.vertex_count())
+            .map(|i| self.vertex_matrix(&skinning_matrices, i))
+            .collect::<Vec<_>>();
+
+        let mut deformed = cpu_mesh.clone();
+        deformed.positions = Positions::F32(
+            cpu_mesh
+                .positions
+                .to_f32()
+                .into_iter()
+                .zip(vertex_matrices.iter())
+                .map(|(p, m)| m.map_or(p, |m| (m * p.extend(1.0)).truncate()))
+                .collect(),
+        );
+        if let Some(normals) = cpu_mesh.normals.as_ref() {
+            deformed.normals = Some(
+                normals
+                    .iter()
+                    .zip(vertex_matrices.iter())
+                    .map(|(n, m)| match m {
+                        Some(m) => {
+                            let normal_matrix = m.invert().unwrap_or(*m).transpose();
+                            (normal_matrix * n.extend(0.0)).truncate().normalize()
+                        }
+                        None => *n,
+                    })
+                    .collect(),
+            );
+        }
+        if let Some(tangents) = cpu_mesh.tangents.as_ref() {
+            deformed.tangents = Some(
+                tangents
+                    .iter()
+                    .zip(vertex_matrices.iter())
+                    .map(|(t, m)| match m {
+                        Some(m) => (m * t.truncate().extend(0.0))
+                            .truncate()
+                            .normalize()
+                            .extend(t.w),
+                        None => *t,
+                    })
+                    .collect(),
+            );
+        }
+        deformed
+    }
+
+    // The weighted sum of the skinning matrices affecting the vertex or None if all weights are zero.
+    fn vertex_matrix(&self, skinning_matrices: &[Mat4], vertex: usize) -> Option<Mat4> {
+        let weights = self.weights[vertex];
+        let total = weights.iter().sum::<f32>();
+        if total <= 0.0 {
+            return None;
+        }
+        self.joints[vertex]
+            .iter()
+            .zip(weights.iter())
+            .filter(|(_, weight)| **weight > 0.0)
+            .map(|(joint, weight)| skinning_matrices[*joint as usize] * (*weight / total))
+            .reduce(|a, b| a + b)
+    }
+}
@@ -0,0 +1,313 @@
+use crate::core::*;
+use crate::renderer::*;
+
+use super::BaseMesh;
+
+///
+/// A triangle mesh [Geometry] which is deformed by a skeleton on the GPU, for example an animated character.
+///
+/// The joint indices and weights of each vertex are given by a [Skin] and the skinning matrices are uploaded to a texture
+/// which is read in the vertex shader, so the number of joints is not limited by the number of vertex uniforms.
+/// Update the pose each frame using [SkinnedMesh::set_joint_transformations], for example with the result of [AnimationClip::joint_transformations].
+/// Use [Skin::apply] instead when the deformed geometry is needed on the CPU.
+///
+pub struct SkinnedMesh {
+    base_mesh: BaseMesh,
+    context: Context,
+    skin: Skin,
+    joint_texture: Texture2D,
+    aabb_local: AxisAlignedBoundingBox,
+    aabb: AxisAlignedBoundingBox,
+    transformation: Mat4,
+}
+
+impl SkinnedMesh {
+    ///
+    /// Creates a new skinned mesh from the given [CpuMesh] and [Skin]. The mesh is initially in the bind pose.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of vertices in the [CpuMesh] does not match the number of vertices in the skin.
+    pub fn new(context: &Context, cpu_mesh: &CpuMesh, skin: &Skin) -> Self {
+        if cpu_mesh.vertex_count() != skin.vertex_count() {
+            panic!(
+                "the number of vertices in the mesh ({}) must match the number of vertices in the skin ({})",
+                cpu_mesh.vertex_count(),
+                skin.vertex_count()
+            );
+        }
+        let mut base_mesh = BaseMesh::new(context, cpu_mesh);
+        base_mesh.set_vertex_attribute(
+            VertexAttribute::Joints,
+            VertexBuffer::new_with_data(
+                context,
+                &skin
+                    .joints
+                    .iter()
+                    .map(|j| vec4(j[0] as f32, j[1] as f32, j[2] as f32, j[3] as f32))
+                    .collect::<Vec<_>>(),
+            ),
+        );
+        base_mesh.set_vertex_attribute(
+            VertexAttribute::Weights,
+            VertexBuffer::new_with_data(
+                context,
+                &skin
+                    .weights
+                    .iter()
+                    .map(|w| vec4(w[0], w[1], w[2], w[3]))
+                    .collect::<Vec<_>>(),
+            ),
+        );
+        let joint_count = skin.joint_count().max(1) as u32;
+        let aabb = cpu_mesh.compute_aabb();
+        let mut skinned_mesh = Self {
+            base_mesh,
+            context: context.clone(),
+            skin: skin.clone(),
+            joint_texture: Texture2D::new_empty::<[f32; 4]>(
+                context,
+                4,
+                joint_count,
+                Interpolation::Nearest,
+                Interpolation::Nearest,
+                None,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            ),
+            aabb_local: aabb,
+            aabb,
+            transformation: Mat4::identity(),
+        };
+        let bind_pose = skin
+            .inverse_bind_matrices
+            .iter()
+            .map(|m| m.invert().unwrap_or(Mat4::identity()))
+            .collect::<Vec<_>>();
+        skinned_mesh.set_joint_transformations(&bind_pose);
+        skinned_mesh
+    }
+
+    ///
+    /// Returns the local to world transformation applied to this mesh.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.transformation
+    }
+
+    ///
+    /// Set the local to world transformation applied to this mesh after skinning.
+    ///
+    pub fn set_transformation(&mut self, transformation: Mat4) {
+        self.transformation = transformation;
+    }
+
+    ///
+    /// Returns the skin of this mesh.
+    ///
+    pub fn skin(&self) -> &Skin {
+        &self.skin
+    }
+
+    ///
+    /// Sets the current transformation of each joint in the coordinate system of the mesh, see [Skin::skinning_matrices].
+    /// The skinning matrices are uploaded to the GPU and the bounding box is updated to conservatively contain the deformed mesh.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of joint transformations does not match the number of joints in the skin.
+    pub fn set_joint_transformations(&mut self, joint_transformations: &[Mat4]) {
+        let skinning_matrices = self.skin.skinning_matrices(joint_transformations);
+        let mut data = Vec::with_capacity(4 * skinning_matrices.len().max(1));
+        // The rows are given in reverse order since they are flipped when filling the texture, so row j contains the matrix of joint j
+        for m in skinning_matrices.iter().rev() {
+            data.extend([m.x, m.y, m.z, m.w].map(|c| [c.x, c.y, c.z, c.w]));
+        }
+        if skinning_matrices.is_empty() {
+            data.extend([[0.0; 4]; 4]);
+        }
+        self.joint_texture.fill(&data);
+
+        let mut aabb = AxisAlignedBoundingBox::EMPTY;
+        for m in skinning_matrices.iter() {
+            let mut joint_aabb = self.aabb_local;
+            joint_aabb.transform(m);
+            aabb.expand_with_aabb(&joint_aabb);
+        }
+        if skinning_matrices.is_empty()
+            || self
+                .skin
+                .weights
+                .iter()
+                .any(|w| w.iter().sum::<f32>() <= 0.0)
+        {
+            // Vertices without weights are not deformed
+            aabb.expand_with_aabb(&self.aabb_local);
+        }
+        self.aabb = aabb;
+    }
+
+    ///
+    /// Sets the pose of the given skeleton given by the animation clip at the given time, see [AnimationClip::joint_transformations].
+    ///
+    pub fn set_animation_pose(&mut self, skeleton: &Skeleton, clip: &AnimationClip, time: f32) {
+        self.set_joint_transformations(&clip.joint_transformations(skeleton, time));
+    }
+
+    ///
+    /// Sets the vertex buffer with the given semantic, for example normals or a custom attribute.
+    /// The built-in attributes (normal, tangent and color) are used by the built-in shaders
+    /// while any other attribute is sent to the shader if the shader contains an attribute with the name given by [VertexAttribute::name].
+    /// The positions cannot be changed and the joints and weights are given by the [Skin].
+    /// Use [SkinnedMesh::set_uvs] to set uv coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vertex buffer does not contain one value per vertex in the mesh or if the attribute is the position, the joints, the weights or a set of uv coordinates.
+    ///
+    pub fn set_vertex_attribute(&mut self, attribute: VertexAttribute, buffer: VertexBuffer) {
+        if matches!(
+            attribute,
+            VertexAttribute::Joints | VertexAttribute::Weights
+        ) {
+            panic!(
+                "Failed setting vertex attribute {}: The joints and weights are given by the skin.",
+                attribute
+            )
+        }
+        self.base_mesh.set_user_vertex_attribute(attribute, buffer);
+    }
+
+    ///
+    /// Sets the given set of uv coordinates, ie. [VertexAttribute::Uv0] or [VertexAttribute::Uv1], using the same convention as the uv coordinates in a [CpuMesh].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of uv coordinates does not match the number of vertices in the mesh or if the attribute is not a set of uv coordinates.
+    ///
+    pub fn set_uvs(&mut self, attribute: VertexAttribute, uvs: &[Vec2]) {
+        self.base_mesh.set_uvs(&self.context, attribute, uvs);
+    }
+
+    ///
+    /// Removes and returns the vertex buffer with the given semantic. The position, joints and weights attributes cannot be removed.
+    ///
+    pub fn remove_vertex_attribute(&mut self, attribute: &VertexAttribute) -> Option<VertexBuffer> {
+        if matches!(
+            attribute,
+            VertexAttribute::Joints | VertexAttribute::Weights
+        ) {
+            return None;
+        }
+        self.base_mesh.remove_vertex_attribute(attribute)
+    }
+}
+
+impl<'a> IntoIterator for &'a SkinnedMesh {
+    type Item = &'a dyn Geometry;
+    type IntoIter = std::iter::Once<&'a dyn Geometry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for SkinnedMesh {
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        let mut aabb = self.aabb;
+        aabb.transform(&self.transformation);
+        aabb
+    }
+
+    fn is_animated(&self) -> bool {
+        true
+    }
+
+    fn draw(
+        &self,
+        camera: &Camera,
+        program: &Program,
+        render_states: RenderStates,
+        attributes: FragmentAttributes,
+    ) {
+        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform("modelMatrix", self.transformation);
+        program.use_texture("jointMatrices", &self.joint_texture);
+
+        self.base_mesh
+            .draw(program, render_states, camera, attributes);
+    }
+
+    fn vertex_shader_source(&self, required_attributes: FragmentAttributes) -> String {
+        format!(
+            "#define SKINNED\n{}{}{}{}{}{}",
+            if required_attributes.normal {
+                "#define USE_NORMALS\n"
+            } else {
+                ""
+            },
+            if required_attributes.tangents {
+                "#define USE_TANGENTS\n"
+            } else {
+                ""
+            },
+            if required_attributes.uv {
+                "#define USE_UVS\n"
+            } else {
+                ""
+            },
+            if required_attributes.color && self.base_mesh.colors.is_some() {
+                "#define USE_VERTEX_COLORS\n"
+            } else {
+                ""
+            },
+            include_str!("../../core/shared.frag"),
+            include_str!("shaders/mesh.vert"),
+        )
+    }
+
+    fn id(&self, required_attributes: FragmentAttributes) -> u16 {
+        let mut id = 0b1u16 << 15 | 0b1u16 << 6;
+        if required_attributes.normal {
+            id |= 0b1u16;
+        }
+        if required_attributes.tangents {
+            id |= 0b1u16 << 1;
+        }
+        if required_attributes.uv {
+            id |= 0b1u16 << 2;
+        }
+        if required_attributes.color && self.base_mesh.colors.is_some() {
+            id |= 0b1u16 << 3;
+        }
+        id
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        render_with_material(&self.context, camera, self, material, lights);
+    }
+
+    fn render_with_effect(
+        &self,
+        material: &dyn Effect,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        render_with_effect(
+            &self.context,
+            camera,
+            self,
+            material,
+            lights,
+            color_texture,
+            depth_texture,
+        )
+    }
+}
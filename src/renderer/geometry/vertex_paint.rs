@@ -0,0 +1,239 @@
+use crate::renderer::*;
+
+///
+/// Defines how the influence of a [Brush] decreases from the center to the edge of the brush.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BrushFalloff {
+    /// The same influence everywhere inside the brush.
+    Constant,
+    /// The influence decreases linearly to zero at the edge of the brush.
+    Linear,
+    /// The influence decreases smoothly to zero at the edge of the brush.
+    #[default]
+    Smooth,
+}
+
+///
+/// A brush used to paint vertex attributes with a [VertexPainter].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Brush {
+    /// The radius of the brush in world space.
+    pub radius: f32,
+    /// The strength of the brush between 0 and 1, where 1 means that the vertices in the center of the brush are set to the painted value.
+    pub strength: f32,
+    /// How the influence decreases from the center to the edge of the brush.
+    pub falloff: BrushFalloff,
+}
+
+impl Brush {
+    ///
+    /// Returns the influence of the brush, between 0 and 1, at the given distance from the center of the brush.
+    ///
+    pub fn influence(&self, distance: f32) -> f32 {
+        if distance > self.radius || self.radius <= 0.0 {
+            return 0.0;
+        }
+        let t = distance / self.radius;
+        let falloff = match self.falloff {
+            BrushFalloff::Constant => 1.0,
+            BrushFalloff::Linear => 1.0 - t,
+            BrushFalloff::Smooth => {
+                let s = 1.0 - t;
+                s * s * (3.0 - 2.0 * s)
+            }
+        };
+        self.strength.clamp(0.0, 1.0) * falloff
+    }
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            radius: 1.0,
+            strength: 1.0,
+            falloff: BrushFalloff::default(),
+        }
+    }
+}
+
+///
+/// Edits the per-vertex colors and weights of a [Mesh] inside the radius of a [Brush], for example to implement vertex painting or weight painting in an application.
+/// The vertex positions and the painted values are kept on the CPU and only the range of vertices that changed is uploaded to the GPU after each brush stroke,
+/// see [Mesh::update_colors_partially].
+///
+/// The weights are not used when rendering the mesh, but can be inspected by visualizing them as vertex colors using [VertexPainter::set_weight_visualization].
+///
+pub struct VertexPainter {
+    positions: Vec<Vec3>,
+    colors: Vec<Srgba>,
+    weights: Vec<f32>,
+    weight_visualization: Option<(Srgba, Srgba)>,
+}
+
+impl VertexPainter {
+    ///
+    /// Creates a new vertex painter for a [Mesh] created from the given [CpuMesh].
+    /// The colors are initialized to the colors of the cpu mesh, or white if it has no colors, and the weights are initialized to zero.
+    ///
+    pub fn new(cpu_mesh: &CpuMesh) -> Self {
+        let vertex_count = cpu_mesh.vertex_count();
+        Self {
+            positions: cpu_mesh.positions.to_f32(),
+            colors: cpu_mesh
+                .colors
+                .clone()
+                .unwrap_or_else(|| vec![Srgba::WHITE; vertex_count]),
+            weights: vec![0.0; vertex_count],
+            weight_visualization: None,
+        }
+    }
+
+    ///
+    /// Returns the number of vertices.
+    ///
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    ///
+    /// Returns the painted vertex colors, for example to store them in the [CpuMesh].
+    ///
+    pub fn colors(&self) -> &[Srgba] {
+        &self.colors
+    }
+
+    ///
+    /// Returns the painted vertex weights.
+    ///
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    ///
+    /// Sets the weights of all vertices and updates the colors of the mesh if the weights are visualized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of weights does not match the number of vertices.
+    pub fn set_weights(&mut self, mesh: &mut Mesh, weights: Vec<f32>) {
+        if weights.len() != self.vertex_count() {
+            panic!(
+                "the number of weights ({}) must match the number of vertices ({})",
+                weights.len(),
+                self.vertex_count()
+            );
+        }
+        self.weights = weights;
+        self.upload(mesh, 0, self.vertex_count());
+    }
+
+    ///
+    /// Visualizes the weights as vertex colors on the mesh by interpolating between the `low` color at weight 0 and the `high` color at weight 1,
+    /// or shows the painted colors again if `None` is specified.
+    ///
+    pub fn set_weight_visualization(&mut self, mesh: &mut Mesh, colors: Option<(Srgba, Srgba)>) {
+        self.weight_visualization = colors;
+        self.upload(mesh, 0, self.vertex_count());
+    }
+
+    ///
+    /// Returns the indices of the vertices inside the brush centered at the given world space position together with the influence of the brush on each vertex.
+    ///
+    pub fn vertices_in_brush(
+        &self,
+        mesh: &Mesh,
+        position: Vec3,
+        brush: &Brush,
+    ) -> Vec<(usize, f32)> {
+        let transformation = mesh.transformation();
+        self.positions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| {
+                let world_position = (transformation * p.extend(1.0)).truncate();
+                let influence = brush.influence(world_position.distance(position));
+                if influence > 0.0 {
+                    Some((i, influence))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    ///
+    /// Paints the given color onto the vertices inside the brush centered at the given world space position
+    /// and uploads the changed colors to the mesh. Returns the number of affected vertices.
+    ///
+    pub fn paint_color(
+        &mut self,
+        mesh: &mut Mesh,
+        position: Vec3,
+        brush: &Brush,
+        color: Srgba,
+    ) -> usize {
+        let vertices = self.vertices_in_brush(mesh, position, brush);
+        for (i, influence) in vertices.iter() {
+            let current = self.colors[*i];
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * influence).round() as u8;
+            self.colors[*i] = Srgba::new(
+                mix(current.r, color.r),
+                mix(current.g, color.g),
+                mix(current.b, color.b),
+                mix(current.a, color.a),
+            );
+        }
+        self.upload_affected(mesh, &vertices);
+        vertices.len()
+    }
+
+    ///
+    /// Paints the given weight onto the vertices inside the brush centered at the given world space position
+    /// and uploads the changed colors to the mesh if the weights are visualized. Returns the number of affected vertices.
+    ///
+    pub fn paint_weight(
+        &mut self,
+        mesh: &mut Mesh,
+        position: Vec3,
+        brush: &Brush,
+        weight: f32,
+    ) -> usize {
+        let vertices = self.vertices_in_brush(mesh, position, brush);
+        for (i, influence) in vertices.iter() {
+            self.weights[*i] += (weight - self.weights[*i]) * influence;
+        }
+        self.upload_affected(mesh, &vertices);
+        vertices.len()
+    }
+
+    fn upload_affected(&self, mesh: &mut Mesh, vertices: &[(usize, f32)]) {
+        let first = vertices.iter().map(|(i, _)| *i).min();
+        let last = vertices.iter().map(|(i, _)| *i).max();
+        if let (Some(first), Some(last)) = (first, last) {
+            self.upload(mesh, first, last + 1);
+        }
+    }
+
+    fn upload(&self, mesh: &mut Mesh, start: usize, end: usize) {
+        if let Some((low, high)) = self.weight_visualization {
+            let mix = |a: u8, b: u8, t: f32| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+            let colors = self.weights[start..end]
+                .iter()
+                .map(|w| {
+                    let t = w.clamp(0.0, 1.0);
+                    Srgba::new(
+                        mix(low.r, high.r, t),
+                        mix(low.g, high.g, t),
+                        mix(low.b, high.b, t),
+                        mix(low.a, high.a, t),
+                    )
+                })
+                .collect::<Vec<_>>();
+            mesh.update_colors_partially(start as u32, &colors);
+        } else {
+            mesh.update_colors_partially(start as u32, &self.colors[start..end]);
+        }
+    }
+}
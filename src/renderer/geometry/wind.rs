@@ -0,0 +1,159 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Wraps a [Geometry] and makes the vertices sway in the wind, for example the leaves of trees, grass or flags,
+/// without writing a custom shader. The displacement is given by noise which travels along the wind direction,
+/// so nearby vertices move together in gusts, and is animated using the time of the [Context] (see [Context::set_time]),
+/// so all objects in the wind are driven by the same clock.
+///
+/// The weight of the displacement of each vertex is given by the alpha channel of the vertex colors, for example zero at the root of a plant and one at the tips,
+/// which means that the alpha channel of the vertex colors is not used as transparency. All vertices have full weight if the geometry does not have vertex colors.
+/// The wind is applied by the geometries that use the standard mesh vertex shader, ie. [Mesh], [InstancedMesh], [BatchedMesh] and [SkinnedMesh].
+///
+/// Since the displacement is part of the geometry, it is applied with any material, including the materials used when rendering shadow maps,
+/// and the geometry is [animated](Geometry::is_animated), so the shadow maps of a [RenderPipeline] follow the sway.
+///
+pub struct Wind<G: Geometry> {
+    context: Context,
+    /// The wrapped geometry.
+    pub geometry: G,
+    /// The direction of the wind in world space, where the length is the maximum displacement in world units of a vertex with full weight.
+    pub direction: Vec3,
+    /// How many times per second the vertices sway back and forth.
+    pub frequency: f32,
+    /// The size in world units of the gusts, ie. how far apart vertices must be before they move independently of each other.
+    pub gust_size: f32,
+}
+
+impl<G: Geometry> Wind<G> {
+    ///
+    /// Wraps the given geometry and makes the vertices sway along the given direction, see [Wind::direction].
+    ///
+    pub fn new(context: &Context, geometry: G, direction: Vec3) -> Self {
+        Self {
+            context: context.clone(),
+            geometry,
+            direction,
+            frequency: 0.5,
+            gust_size: 5.0,
+        }
+    }
+
+    fn use_wind_uniforms(&self, program: &Program) {
+        // The time is wrapped in double precision before it is sent to the shader, since a single precision time loses precision during long sessions.
+        // The sway repeats every period and the noise which gives the gusts repeats every 256 units, so the wrapping does not result in a jump.
+        let t = self.context.time() * self.frequency as f64;
+        let strength = self.direction.magnitude();
+        let travel = if strength > 0.0 {
+            let d = self.direction / strength;
+            vec3(
+                (d.x as f64 * t).rem_euclid(256.0) as f32,
+                (d.y as f64 * t).rem_euclid(256.0) as f32,
+                (d.z as f64 * t).rem_euclid(256.0) as f32,
+            )
+        } else {
+            vec3(0.0, 0.0, 0.0)
+        };
+        program.use_uniform_if_required("windDirection", self.direction);
+        program.use_uniform_if_required("windGustSize", self.gust_size);
+        program.use_uniform_if_required("windPhase", t.rem_euclid(1.0) as f32);
+        program.use_uniform_if_required("windTravel", travel);
+    }
+}
+
+impl<G: Geometry> std::ops::Deref for Wind<G> {
+    type Target = G;
+    fn deref(&self) -> &Self::Target {
+        &self.geometry
+    }
+}
+
+impl<G: Geometry> std::ops::DerefMut for Wind<G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.geometry
+    }
+}
+
+// The vertex colors are always needed for the weights
+fn wind_attributes(attributes: FragmentAttributes) -> FragmentAttributes {
+    FragmentAttributes {
+        color: true,
+        ..attributes
+    }
+}
+
+impl<G: Geometry> Geometry for Wind<G> {
+    fn draw(
+        &self,
+        camera: &Camera,
+        program: &Program,
+        render_states: RenderStates,
+        attributes: FragmentAttributes,
+    ) {
+        self.use_wind_uniforms(program);
+        self.geometry
+            .draw(camera, program, render_states, wind_attributes(attributes))
+    }
+
+    fn vertex_shader_source(&self, required_attributes: FragmentAttributes) -> String {
+        self.geometry
+            .vertex_shader_source(wind_attributes(required_attributes))
+    }
+
+    fn id(&self, required_attributes: FragmentAttributes) -> u16 {
+        self.geometry.id(wind_attributes(required_attributes))
+    }
+
+    fn shader_defines(&self) -> ShaderDefines {
+        let mut defines = self.geometry.shader_defines();
+        defines.insert("USE_WIND");
+        defines
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        render_with_material(&self.context, camera, self, material, lights);
+    }
+
+    fn render_with_effect(
+        &self,
+        material: &dyn Effect,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        render_with_effect(
+            &self.context,
+            camera,
+            self,
+            material,
+            lights,
+            color_texture,
+            depth_texture,
+        )
+    }
+
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        // The vertices are displaced at most the length of the wind direction
+        let aabb = self.geometry.aabb();
+        if aabb.is_empty() {
+            return aabb;
+        }
+        let extent = vec3(1.0, 1.0, 1.0) * self.direction.magnitude();
+        AxisAlignedBoundingBox::new_with_positions(&[aabb.min() - extent, aabb.max() + extent])
+    }
+
+    fn animate(&mut self, time: f32) {
+        self.geometry.animate(time)
+    }
+
+    fn is_animated(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,135 @@
+use crate::renderer::*;
+
+///
+/// Picks objects on the GPU by rendering the index of each object with an [IdMaterial] into an offscreen render target
+/// and reading back the pixel under the cursor asynchronously.
+/// In contrast to picking on the CPU using a [Ray], the cost does not depend on the number of triangles, so this scales to very large scenes.
+///
+/// Start a pick with [GpuPicker::pick], for example when the mouse is clicked, and poll the result with [GpuPicker::try_result] in the following frames.
+/// Only the pixel under the cursor is rasterized and the render target is reused as long as the size of the viewport does not change.
+///
+pub struct GpuPicker {
+    context: Context,
+    targets: Option<(Texture2D, DepthTexture2D)>,
+    readback: Option<PixelReadback<[u8; 4]>>,
+}
+
+impl GpuPicker {
+    ///
+    /// Creates a new GPU picker.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            targets: None,
+            readback: None,
+        }
+    }
+
+    ///
+    /// Starts picking the geometries with the given camera at the given pixel, for example the position of the mouse, and discards any pending result.
+    /// The pixel coordinate must be in physical pixels, where (viewport.x, viewport.y) indicate the bottom left corner of the viewport
+    /// and (viewport.x + viewport.width, viewport.y + viewport.height) indicate the top right corner.
+    /// The result is the index of the closest geometry in the iteration order, see [GpuPicker::try_result].
+    ///
+    pub fn pick(
+        &mut self,
+        camera: &Camera,
+        pixel: impl Into<PhysicalPoint>,
+        geometries: impl IntoIterator<Item = impl Geometry>,
+    ) {
+        let viewport = camera.viewport();
+        let (width, height) = (viewport.width.max(1), viewport.height.max(1));
+        if !matches!(&self.targets, Some((color, _)) if color.width() == width && color.height() == height)
+        {
+            self.targets = Some((
+                Texture2D::new_empty::<[u8; 4]>(
+                    &self.context,
+                    width,
+                    height,
+                    Interpolation::Nearest,
+                    Interpolation::Nearest,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+                DepthTexture2D::new::<f32>(
+                    &self.context,
+                    width,
+                    height,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+            ));
+        }
+        let (color, depth) = self.targets.as_mut().unwrap();
+
+        let pixel = pixel.into();
+        let scissor_box = ScissorBox {
+            x: (pixel.x as i32 - viewport.x).clamp(0, width as i32 - 1),
+            y: (pixel.y as i32 - viewport.y).clamp(0, height as i32 - 1),
+            width: 1,
+            height: 1,
+        };
+        let mut camera = camera.clone();
+        camera.set_viewport(Viewport::new_at_origo(width, height));
+        let frustum = camera.frustum();
+
+        let target = RenderTarget::new(color.as_color_target(None), depth.as_depth_target());
+        target
+            .clear_partially(
+                scissor_box,
+                ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0),
+            )
+            .write_partially::<RendererError>(scissor_box, || {
+                for (index, geometry) in geometries.into_iter().enumerate() {
+                    if frustum.intersects_aabb(&geometry.aabb()) {
+                        // Zero is reserved for the background
+                        let material = IdMaterial {
+                            id: index as u32 + 1,
+                            ..Default::default()
+                        };
+                        render_with_material(&self.context, &camera, &geometry, &material, &[]);
+                    }
+                }
+                Ok(())
+            })
+            .unwrap();
+        self.readback = Some(target.read_color_partially_async(scissor_box));
+    }
+
+    ///
+    /// Returns whether or not a pick has been started and the result has not yet been returned by [GpuPicker::try_result].
+    ///
+    pub fn is_pending(&self) -> bool {
+        self.readback.is_some()
+    }
+
+    ///
+    /// Returns the result of the last pick if the GPU has finished, otherwise `None` is returned without waiting for the GPU.
+    /// The result is the index of the picked geometry in the geometries given to [GpuPicker::pick] or `None` if no geometry was hit.
+    /// The result is only returned once.
+    ///
+    pub fn try_result(&mut self) -> Option<Option<usize>> {
+        let data = self.readback.as_ref()?.try_read()?;
+        self.readback = None;
+        Some(decode(&data))
+    }
+
+    ///
+    /// Waits for the GPU to finish the last pick and returns the result as in [GpuPicker::try_result], or `None` if no pick is pending.
+    ///
+    /// **Note:** This stalls the CPU until the GPU has finished, so prefer [GpuPicker::try_result] if possible.
+    ///
+    pub fn wait_result(&mut self) -> Option<Option<usize>> {
+        let data = self.readback.take()?.wait();
+        Some(decode(&data))
+    }
+}
+
+fn decode(data: &[[u8; 4]]) -> Option<usize> {
+    data.first()
+        .map(|bytes| u32::from_le_bytes(*bytes))
+        .filter(|id| *id > 0)
+        .map(|id| id as usize - 1)
+}
@@ -15,6 +15,9 @@ macro_rules! impl_light_body {
         fn id(&self) -> u8 {
             self.$inner().id()
         }
+        fn volume(&self) -> Option<LightVolume> {
+            self.$inner().volume()
+        }
     };
 }
 
@@ -32,6 +35,14 @@ mod point_light;
 #[doc(inline)]
 pub use point_light::*;
 
+mod area_light;
+#[doc(inline)]
+pub use area_light::*;
+
+mod clustered_lights;
+#[doc(inline)]
+pub use clustered_lights::*;
+
 mod ambient_light;
 #[doc(inline)]
 pub use ambient_light::*;
@@ -40,12 +51,25 @@ mod environment;
 #[doc(inline)]
 pub use environment::*;
 
+mod light_probe_grid;
+#[doc(inline)]
+pub use light_probe_grid::*;
+
+mod lights_uniform_buffer;
+#[doc(inline)]
+pub use lights_uniform_buffer::*;
+
 use crate::core::*;
 use crate::renderer::camera::*;
+use crate::renderer::Geometry;
 
 ///
 /// Specifies how the intensity of a light fades over distance.
-/// The light intensity is scaled by ``` 1 / max(1, constant + distance * linear + distance * distance * quadratic) ```.
+/// The light intensity is scaled by ``` 1 / max(1, constant + distance * linear + distance * distance * quadratic) ```
+/// and, if a range is specified, smoothly faded to zero at the range.
+///
+/// For physically based falloff, use [Attenuation::inverse_square], where the intensity of the light is the luminous intensity in candela
+/// if the distances are in meters, see for example [PointLight::set_luminous_power].
 ///
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
 pub struct Attenuation {
@@ -55,6 +79,46 @@ pub struct Attenuation {
     pub linear: f32,
     /// Quadratic attenuation factor.
     pub quadratic: f32,
+    /// The distance at which the light has faded to zero, or `None` if the light never fades completely.
+    /// The light is multiplied by the window function ``` (1 - (distance / range)^4)^2 ``` which only affects the light close to the range,
+    /// so that objects outside the range are not lit by the light.
+    pub range: Option<f32>,
+}
+
+impl Attenuation {
+    ///
+    /// Constructs a new attenuation with the given constant, linear and quadratic factors and no range, ie. the light never fades completely.
+    ///
+    pub fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+        Self {
+            constant,
+            linear,
+            quadratic,
+            range: None,
+        }
+    }
+
+    ///
+    /// Physically based attenuation, where the intensity falls off with the inverse square of the distance,
+    /// ie. ``` 1 / max(1, distance * distance) ```, and is faded to zero at the given range, if specified.
+    ///
+    pub fn inverse_square(range: Option<f32>) -> Self {
+        Self {
+            constant: 0.0,
+            linear: 0.0,
+            quadratic: 1.0,
+            range,
+        }
+    }
+
+    pub(crate) fn uniform(&self) -> Vec4 {
+        vec4(
+            self.constant,
+            self.linear,
+            self.quadratic,
+            self.range.unwrap_or(0.0).max(0.0),
+        )
+    }
 }
 
 impl Default for Attenuation {
@@ -63,6 +127,168 @@ impl Default for Attenuation {
             constant: 1.0,
             linear: 0.0,
             quadratic: 0.0,
+            range: None,
+        }
+    }
+}
+
+///
+/// The technique used for computing the shadows of a [DirectionalLight] or [SpotLight], see [ShadowSettings::technique].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShadowTechnique {
+    /// The depth seen from the light is stored in the shadow map and compared to the depth of each surface,
+    /// filtered with a kernel of the size given by [ShadowSettings::pcf_kernel_size].
+    PercentageCloserFiltering,
+    /// The depth and squared depth seen from the light are stored in a floating point texture, which is blurred with the given radius in texels,
+    /// and the visibility is estimated using Chebyshev's inequality.
+    /// This gives soft shadows at the cost of one texture lookup, but may cause light bleeding where several occluders overlap,
+    /// which is reduced by increasing the light bleeding reduction between 0 and 1.
+    Variance {
+        /// The radius of the blur in texels.
+        blur_radius: f32,
+        /// The amount of light bleeding reduction in the range `[0, 1]`. Higher values remove more light bleeding but makes the shadows harder.
+        light_bleeding_reduction: f32,
+    },
+    /// The exponential of the depth seen from the light is stored in a floating point texture, which is blurred with the given radius in texels,
+    /// and the visibility is estimated from the exponential of the difference between the depths.
+    /// This gives soft shadows at the cost of one texture lookup. A higher exponent gives harder shadows with less light leaking close to the occluders,
+    /// but too high values overflow the floating point precision, 80 is about the maximum.
+    Exponential {
+        /// The radius of the blur in texels.
+        blur_radius: f32,
+        /// The exponent, which controls the sharpness of the shadows.
+        exponent: f32,
+    },
+}
+
+///
+/// Controls how the shadow map of a [DirectionalLight], [SpotLight] or [PointLight] is generated and sampled.
+/// The filtering softens the edges of the shadows and the biases remove shadow acne, ie. self-shadowing artifacts on lit surfaces,
+/// but a too high bias detaches the shadows from the objects casting them (peter panning).
+///
+/// Changing the settings takes effect the next time the light is rendered,
+/// except for [ShadowSettings::technique] which takes effect the next time the shadow map is generated.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowSettings {
+    /// The technique used for computing the shadows. The default is [ShadowTechnique::PercentageCloserFiltering].
+    /// [PointLight]s only support [ShadowTechnique::PercentageCloserFiltering] and ignore this setting.
+    pub technique: ShadowTechnique,
+    /// The width and height, in texels, of the percentage-closer filtering kernel. A value of 1 gives hard shadows and higher values give softer shadow edges at the cost of more texture lookups.
+    /// Even numbers are rounded up to the nearest odd number.
+    pub pcf_kernel_size: u32,
+    /// A constant bias added to the depth of the surface before comparing it with the depth in the shadow map.
+    pub depth_bias: f32,
+    /// A bias which is scaled by the slope of the surface relative to the light direction, which removes shadow acne on surfaces at grazing angles.
+    pub slope_scaled_bias: f32,
+    /// The distance, in world units, the surface position is offset along the surface normal before looking up in the shadow map.
+    /// The offset is scaled by the angle between the normal and the light direction.
+    pub normal_offset: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            technique: ShadowTechnique::PercentageCloserFiltering,
+            pcf_kernel_size: 3,
+            depth_bias: 0.005,
+            slope_scaled_bias: 0.01,
+            normal_offset: 0.0,
+        }
+    }
+}
+
+impl ShadowSettings {
+    // The parameters sent to the shader, where the first parameter depends on the technique used when generating the shadow map.
+    pub(crate) fn parameters(&self, technique: ShadowTechnique) -> Vec4 {
+        vec4(
+            match technique {
+                ShadowTechnique::PercentageCloserFiltering => {
+                    (self.pcf_kernel_size.max(1) / 2) as f32
+                }
+                ShadowTechnique::Variance {
+                    light_bleeding_reduction,
+                    ..
+                } => light_bleeding_reduction.clamp(0.0, 0.99),
+                ShadowTechnique::Exponential { exponent, .. } => exponent,
+            },
+            self.depth_bias,
+            self.slope_scaled_bias,
+            self.normal_offset,
+        )
+    }
+}
+
+///
+/// A light which can cast shadows, ie. a [DirectionalLight], [SpotLight] or [PointLight].
+/// Makes it possible to update the shadow maps of different types of lights together, for example when the shadow quality is changed in a [RenderPipeline](crate::renderer::RenderPipeline).
+///
+pub trait ShadowCaster: Light {
+    ///
+    /// Generates the shadow map with the given texture size using the given geometries as shadow casters,
+    /// or clears the shadow map if the texture size is `None`.
+    ///
+    fn update_shadow_map(&mut self, texture_size: Option<u32>, geometries: &[&dyn Geometry]);
+
+    ///
+    /// Returns the settings used when generating and sampling the shadow map.
+    ///
+    fn shadow_settings_mut(&mut self) -> &mut ShadowSettings;
+}
+
+///
+/// The volume outside of which a light does not affect any surfaces, see [Light::volume].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightVolume {
+    /// A sphere, for example the volume lit by a [PointLight] with a limited range.
+    Sphere {
+        /// The center of the sphere.
+        center: Vec3,
+        /// The radius of the sphere.
+        radius: f32,
+    },
+    /// A cone, for example the volume lit by a [SpotLight] with a limited range.
+    Cone {
+        /// The position of the apex of the cone.
+        apex: Vec3,
+        /// The direction from the apex along the axis of the cone.
+        direction: Vec3,
+        /// The angle between the axis and the side of the cone.
+        angle: Radians,
+        /// The distance from the apex to the base of the cone.
+        length: f32,
+    },
+}
+
+impl LightVolume {
+    ///
+    /// Returns a bounding box containing the volume.
+    ///
+    pub fn aabb(&self) -> AxisAlignedBoundingBox {
+        match *self {
+            Self::Sphere { center, radius } => {
+                let r = vec3(radius, radius, radius);
+                AxisAlignedBoundingBox::new_with_positions(&[center - r, center + r])
+            }
+            Self::Cone {
+                apex,
+                direction,
+                angle,
+                length,
+            } => {
+                // The box around the apex and the circle at the base of the cone
+                let direction = direction.normalize();
+                let base = apex + direction * length;
+                let radius = length * angle.0.tan().abs();
+                let extent = vec3(
+                    (1.0 - direction.x * direction.x).max(0.0).sqrt(),
+                    (1.0 - direction.y * direction.y).max(0.0).sqrt(),
+                    (1.0 - direction.z * direction.z).max(0.0).sqrt(),
+                ) * radius;
+                AxisAlignedBoundingBox::new_with_positions(&[apex, base - extent, base + extent])
+            }
         }
     }
 }
@@ -86,6 +312,15 @@ pub trait Light {
     /// outside of this crate, always return an id that is smaller than `0b1u8 << 7`.
     ///
     fn id(&self) -> u8;
+
+    ///
+    /// Returns the volume outside of which this light does not affect any surfaces, or `None` if the light can affect all surfaces.
+    /// The deferred lighting pass renders the lights with a volume by only shading the pixels covered by the volume, see [DeferredPhysicalMaterial](crate::renderer::DeferredPhysicalMaterial),
+    /// which is much faster than shading the entire screen when there are many lights which each only affect a small part of the scene.
+    ///
+    fn volume(&self) -> Option<LightVolume> {
+        None
+    }
 }
 
 impl<T: Light + ?Sized> Light for &T {
@@ -122,6 +357,9 @@ impl<T: Light> Light for std::sync::Arc<std::sync::RwLock<T>> {
     fn id(&self) -> u8 {
         self.read().unwrap().id()
     }
+    fn volume(&self) -> Option<LightVolume> {
+        self.read().unwrap().volume()
+    }
 }
 
 ///
@@ -164,6 +402,151 @@ fn shadow_matrix(camera: &Camera) -> Mat4 {
     bias_matrix * camera.projection() * camera.view()
 }
 
+///
+/// Renders the depth of the geometries seen from the shadow camera into a depth texture and,
+/// if the technique is not percentage-closer filtering, the moments of the depth into a blurred floating point texture.
+/// Each geometry is rendered using [Geometry::render_with_material](crate::renderer::Geometry::render_with_material), so the shadows follow the vertex deformation of the geometry, for example skinning, instancing or wind.
+///
+fn generate_shadow_textures(
+    context: &Context,
+    texture_size: u32,
+    shadow_camera: &Camera,
+    geometries: impl IntoIterator<Item = impl crate::renderer::Geometry>,
+    technique: ShadowTechnique,
+) -> (DepthTexture2D, Option<Texture2D>) {
+    use crate::renderer::*;
+    let mut depth_texture = DepthTexture2D::new::<f32>(
+        context,
+        texture_size,
+        texture_size,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    );
+    let geometries = geometries
+        .into_iter()
+        .filter(|g| shadow_camera.in_frustum(&g.aabb()));
+    let (moments_material, blur_radius, clear_state) = match technique {
+        ShadowTechnique::PercentageCloserFiltering => {
+            let depth_material = DepthMaterial {
+                render_states: RenderStates {
+                    write_mask: WriteMask::DEPTH,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            depth_texture
+                .as_depth_target()
+                .clear(ClearState::default())
+                .write::<RendererError>(|| {
+                    for geometry in geometries {
+                        geometry.render_with_material(&depth_material, shadow_camera, &[]);
+                    }
+                    Ok(())
+                })
+                .unwrap();
+            return (depth_texture, None);
+        }
+        ShadowTechnique::Variance { blur_radius, .. } => (
+            ShadowMomentsMaterial { exponent: None },
+            blur_radius,
+            ClearState::color_and_depth(1.0, 1.0, 0.0, 0.0, 1.0),
+        ),
+        ShadowTechnique::Exponential {
+            blur_radius,
+            exponent,
+        } => (
+            ShadowMomentsMaterial {
+                exponent: Some(exponent),
+            },
+            blur_radius,
+            ClearState::color_and_depth(exponent.exp(), 0.0, 0.0, 0.0, 1.0),
+        ),
+    };
+    let mut moments_texture = Texture2D::new_empty::<[f32; 2]>(
+        context,
+        texture_size,
+        texture_size,
+        Interpolation::Linear,
+        Interpolation::Linear,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    );
+    RenderTarget::new(
+        moments_texture.as_color_target(None),
+        depth_texture.as_depth_target(),
+    )
+    .clear(clear_state)
+    .write::<RendererError>(|| {
+        for geometry in geometries {
+            geometry.render_with_material(&moments_material, shadow_camera, &[]);
+        }
+        Ok(())
+    })
+    .unwrap();
+    if blur_radius > 0.0 {
+        let mut processor = ImageProcessor::new(context);
+        processor.set_high_precision(true);
+        processor.add_blur(blur_radius);
+        moments_texture = processor.process::<[f32; 2]>(&moments_texture);
+    }
+    (depth_texture, Some(moments_texture))
+}
+
+// Returns the name of the shader function in light_shared.frag that computes the visibility using the given technique.
+fn shadow_function(technique: ShadowTechnique) -> &'static str {
+    match technique {
+        ShadowTechnique::PercentageCloserFiltering => "calculate_shadow",
+        ShadowTechnique::Variance { .. } => "calculate_variance_shadow",
+        ShadowTechnique::Exponential { .. } => "calculate_exponential_shadow",
+    }
+}
+
+// Writes the depth and squared depth for variance shadow maps or the exponential of the depth for exponential shadow maps.
+struct ShadowMomentsMaterial {
+    exponent: Option<f32>,
+}
+
+impl crate::renderer::Material for ShadowMomentsMaterial {
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        format!(
+            "{}{}",
+            if self.exponent.is_some() {
+                "#define EXPONENTIAL\n"
+            } else {
+                ""
+            },
+            include_str!("light/shaders/shadow_moments.frag")
+        )
+    }
+
+    fn id(&self) -> u16 {
+        if self.exponent.is_some() {
+            0b1u16 << 15 | 0b1110u16
+        } else {
+            0b1u16 << 15 | 0b1111u16
+        }
+    }
+
+    fn fragment_attributes(&self) -> crate::renderer::FragmentAttributes {
+        crate::renderer::FragmentAttributes::NONE
+    }
+
+    fn use_uniforms(&self, program: &Program, _camera: &Camera, _lights: &[&dyn Light]) {
+        if let Some(exponent) = self.exponent {
+            program.use_uniform("exponent", exponent);
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates::default()
+    }
+
+    fn material_type(&self) -> crate::renderer::MaterialType {
+        crate::renderer::MaterialType::Opaque
+    }
+}
+
 fn compute_up_direction(direction: Vec3) -> Vec3 {
     if vec3(1.0, 0.0, 0.0).dot(direction).abs() > 0.9 {
         (vec3(0.0, 1.0, 0.0).cross(direction)).normalize()
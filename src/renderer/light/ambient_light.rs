@@ -37,6 +37,29 @@ impl AmbientLight {
             environment: Some(Environment::new(context, environment_map)),
         }
     }
+
+    /// Constructs an ambient light that shines based on the given environment map, where the precalculated maps are computed with the given options.
+    pub fn new_with_environment_and_options(
+        context: &Context,
+        intensity: f32,
+        color: Srgba,
+        environment_map: &TextureCubeMap,
+        options: EnvironmentOptions,
+    ) -> Self {
+        Self {
+            intensity,
+            color,
+            environment: Some(Environment::new_with_options(
+                context,
+                environment_map,
+                LightingModel::Cook(
+                    NormalDistributionFunction::TrowbridgeReitzGGX,
+                    GeometryFunction::SmithSchlickGGX,
+                ),
+                options,
+            )),
+        }
+    }
 }
 
 impl Light for AmbientLight {
@@ -47,6 +70,7 @@ impl Light for AmbientLight {
                 uniform samplerCube irradianceMap;
                 uniform samplerCube prefilterMap;
                 uniform sampler2D brdfLUT;
+                uniform float maxReflectionLod;
                 uniform vec3 ambientColor;
     
                 vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
@@ -67,8 +91,7 @@ impl Light for AmbientLight {
                     vec3 diffuse = diffuse_fresnel * mix(surface_color, vec3(0.0), metallic) * irradiance;
                     
                     // sample both the pre-filter map and the BRDF lut and combine them together as per the Split-Sum approximation to get the IBL specular part.
-                    const float MAX_REFLECTION_LOD = 4.0;
-                    vec3 prefilteredColor = textureLod(prefilterMap, R,  roughness * MAX_REFLECTION_LOD).rgb;    
+                    vec3 prefilteredColor = textureLod(prefilterMap, R,  roughness * maxReflectionLod).rgb;    
                     vec2 brdf  = texture(brdfLUT, vec2(NdV, roughness)).rg;
                     vec3 specular = prefilteredColor * (specular_fresnel * brdf.x + brdf.y);
     
@@ -93,6 +116,10 @@ impl Light for AmbientLight {
             program.use_texture_cube("irradianceMap", &environment.irradiance_map);
             program.use_texture_cube("prefilterMap", &environment.prefilter_map);
             program.use_texture("brdfLUT", &environment.brdf_map);
+            program.use_uniform(
+                "maxReflectionLod",
+                environment.prefilter_mip_levels.max(1) as f32 - 1.0,
+            );
         }
         program.use_uniform(
             "ambientColor",
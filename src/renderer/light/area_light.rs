@@ -0,0 +1,105 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A light which shines from one side of a rectangle, for example a window, a ceiling panel or a softbox.
+/// The light is evaluated by integrating cosine lobes over the rectangle, which gives soft highlights and the characteristic shape of the reflection of the rectangle on glossy surfaces.
+/// The diffuse reflection is exact, except that the part of the rectangle below the horizon of a surface is not clipped,
+/// while the specular reflection is an approximation which uses a cosine lobe around the reflection direction, narrowed by the roughness,
+/// instead of the GGX distribution fitted by linearly transformed cosines (LTC), so the highlights are not stretched at grazing angles as they physically would be.
+/// The light does not cast shadows and the intensity falls off with the distance as given by the solid angle of the rectangle, so there is no [Attenuation].
+///
+pub struct AreaLight {
+    /// The intensity of the light, ie. the radiance emitted from each point of the rectangle.
+    pub intensity: f32,
+    /// The base color of the light.
+    pub color: Srgba,
+    /// The position of the center of the rectangle.
+    pub position: Vec3,
+    /// The direction the light shines, ie. the normal of the front side of the rectangle.
+    pub direction: Vec3,
+    /// The up direction of the rectangle, which together with the direction defines the orientation of the rectangle. This does not need to be orthogonal to the direction.
+    pub up: Vec3,
+    /// The width of the rectangle.
+    pub width: f32,
+    /// The height of the rectangle, ie. the size in the up direction.
+    pub height: f32,
+}
+
+impl AreaLight {
+    /// Constructs a new area light.
+    pub fn new(
+        _context: &Context,
+        intensity: f32,
+        color: Srgba,
+        position: &Vec3,
+        direction: &Vec3,
+        up: &Vec3,
+        width: f32,
+        height: f32,
+    ) -> AreaLight {
+        AreaLight {
+            intensity,
+            color,
+            position: *position,
+            direction: *direction,
+            up: *up,
+            width,
+            height,
+        }
+    }
+
+    ///
+    /// Returns the four corners of the rectangle in counterclockwise order as seen from the front side.
+    ///
+    pub fn corners(&self) -> [Vec3; 4] {
+        let (half_width, half_height) = self.half_extents();
+        [
+            self.position - half_width - half_height,
+            self.position - half_width + half_height,
+            self.position + half_width + half_height,
+            self.position + half_width - half_height,
+        ]
+    }
+
+    // The vectors from the center of the rectangle to the right and top edges.
+    fn half_extents(&self) -> (Vec3, Vec3) {
+        let direction = self.direction.normalize();
+        let right = direction.cross(self.up).normalize();
+        let up = right.cross(direction);
+        (0.5 * self.width * right, 0.5 * self.height * up)
+    }
+}
+
+impl Light for AreaLight {
+    fn shader_source(&self, i: u32) -> String {
+        format!(
+            "
+                uniform vec3 color{i};
+                uniform vec3 position{i};
+                uniform vec3 halfWidth{i};
+                uniform vec3 halfHeight{i};
+                vec3 calculate_lighting{i}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    return calculate_area_light_approximation(color{i}, position{i}, halfWidth{i}, halfHeight{i}, surface_color, position, normal, view_direction, metallic, roughness);
+                }}
+
+            "
+        )
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        let (half_width, half_height) = self.half_extents();
+        program.use_uniform(
+            &format!("color{}", i),
+            self.color.to_linear_srgb().truncate() * self.intensity,
+        );
+        program.use_uniform(&format!("position{}", i), self.position);
+        program.use_uniform(&format!("halfWidth{}", i), half_width);
+        program.use_uniform(&format!("halfHeight{}", i), half_height);
+    }
+
+    fn id(&self) -> u8 {
+        0b1u8 << 7 | 0b1100u8
+    }
+}
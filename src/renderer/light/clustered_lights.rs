@@ -0,0 +1,246 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A point light with a limited range which is part of [ClusteredLights].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClusteredPointLight {
+    /// The intensity of the light.
+    pub intensity: f32,
+    /// The base color of the light.
+    pub color: Srgba,
+    /// The position of the light.
+    pub position: Vec3,
+    /// The constant, linear and quadratic attenuation factors of the light, see [Attenuation]. The range of the attenuation is ignored, see [ClusteredPointLight::range].
+    pub attenuation: Attenuation,
+    /// The distance at which the light has faded to zero. The light does not affect surfaces outside this range,
+    /// which is what makes it possible to skip the light when shading surfaces that are far away from it.
+    pub range: f32,
+}
+
+///
+/// A large number of point lights, for example hundreds of lamps in a city at night, which are rendered efficiently using clustered forward shading.
+///
+/// The view frustum of the camera is divided into a grid of clusters, evenly in screen space and logarithmically in depth,
+/// and each frame [ClusteredLights::update] finds the lights that affect each cluster on the CPU and uploads the result to data textures.
+/// When shading a surface, only the lights in the cluster containing the surface are evaluated instead of all of the lights.
+/// Since each light has a limited [range](ClusteredPointLight::range), most clusters only contain a few lights.
+///
+/// Use this as any other [Light], for example by passing it to a render call together with an [AmbientLight].
+/// The lights do not cast shadows.
+///
+pub struct ClusteredLights {
+    context: Context,
+    lights: Vec<ClusteredPointLight>,
+    light_texture: Texture2D,
+    grid_texture: Texture2D,
+    index_texture: Texture2D,
+    view: Mat4,
+    projection: Mat4,
+    depth_range: Vec2,
+}
+
+impl ClusteredLights {
+    const GRID_SIZE: (u32, u32, u32) = (16, 9, 24);
+    const DATA_TEXTURE_WIDTH: u32 = 1024;
+
+    ///
+    /// Creates a new set of clustered lights without any lights.
+    ///
+    pub fn new(context: &Context) -> Self {
+        let (x, y, z) = Self::GRID_SIZE;
+        Self {
+            context: context.clone(),
+            lights: Vec::new(),
+            light_texture: new_data_texture::<[f32; 4]>(context, &[[0.0; 4]]),
+            grid_texture: new_data_texture::<[f32; 2]>(
+                context,
+                &vec![[0.0; 2]; (x * y * z) as usize],
+            ),
+            index_texture: new_data_texture::<f32>(context, &[0.0]),
+            view: Mat4::identity(),
+            projection: Mat4::identity(),
+            depth_range: vec2(0.1, 100.0),
+        }
+    }
+
+    ///
+    /// Returns the lights.
+    ///
+    pub fn lights(&self) -> &[ClusteredPointLight] {
+        &self.lights
+    }
+
+    ///
+    /// Sets the lights and uploads them to the GPU. Call [ClusteredLights::update] afterwards to assign the new lights to the clusters.
+    ///
+    pub fn set_lights(&mut self, lights: &[ClusteredPointLight]) {
+        self.lights = lights.to_vec();
+        let data = self
+            .lights
+            .iter()
+            .flat_map(|light| {
+                let color = light.color.to_linear_srgb().truncate() * light.intensity;
+                [
+                    light.position.extend(light.range.max(0.0)).into(),
+                    color.extend(0.0).into(),
+                    [
+                        light.attenuation.constant,
+                        light.attenuation.linear,
+                        light.attenuation.quadratic,
+                        0.0,
+                    ],
+                ]
+            })
+            .collect::<Vec<[f32; 4]>>();
+        self.light_texture = new_data_texture(&self.context, &data);
+    }
+
+    ///
+    /// Assigns the lights to the clusters of the view frustum of the given camera. Call this each frame before rendering with the camera,
+    /// or at least each time the camera or the lights has changed.
+    ///
+    pub fn update(&mut self, camera: &Camera) {
+        let (grid_x, grid_y, grid_z) = Self::GRID_SIZE;
+        self.view = camera.view();
+        self.projection = camera.projection();
+        let far = camera.z_far().max(0.002);
+        let near = camera.z_near().max(0.001 * far);
+        self.depth_range = vec2(near, far);
+        let slice = |depth: f32| {
+            ((depth.max(near) / near).ln() / (far / near).ln() * grid_z as f32)
+                .clamp(0.0, grid_z as f32 - 1.0) as u32
+        };
+        let tile = |ndc: f32, size: u32| {
+            ((0.5 * ndc + 0.5) * size as f32).clamp(0.0, size as f32 - 1.0) as u32
+        };
+
+        let mut clusters = vec![Vec::new(); (grid_x * grid_y * grid_z) as usize];
+        for (index, light) in self.lights.iter().enumerate() {
+            let center = (self.view * light.position.extend(1.0)).truncate();
+            let range = light.range.max(0.0);
+            let (min_depth, max_depth) = (-center.z - range, -center.z + range);
+            if max_depth < near || min_depth > far {
+                continue;
+            }
+
+            // Find the screen space bounds by projecting the corners of the bounding box of the light,
+            // unless a corner is in front of the near plane in which case the light might cover the entire screen.
+            let mut min_ndc = vec2(-1.0f32, -1.0);
+            let mut max_ndc = vec2(1.0f32, 1.0);
+            if min_depth > near {
+                min_ndc = vec2(f32::MAX, f32::MAX);
+                max_ndc = vec2(f32::MIN, f32::MIN);
+                for i in 0..8 {
+                    let corner = center
+                        + vec3(
+                            if i & 1 == 0 { -range } else { range },
+                            if i & 2 == 0 { -range } else { range },
+                            if i & 4 == 0 { -range } else { range },
+                        );
+                    let clip = self.projection * corner.extend(1.0);
+                    let ndc = clip.truncate().truncate() / clip.w;
+                    min_ndc = vec2(min_ndc.x.min(ndc.x), min_ndc.y.min(ndc.y));
+                    max_ndc = vec2(max_ndc.x.max(ndc.x), max_ndc.y.max(ndc.y));
+                }
+                if max_ndc.x < -1.0 || min_ndc.x > 1.0 || max_ndc.y < -1.0 || min_ndc.y > 1.0 {
+                    continue;
+                }
+            }
+
+            for z in slice(min_depth)..=slice(max_depth) {
+                for y in tile(min_ndc.y, grid_y)..=tile(max_ndc.y, grid_y) {
+                    for x in tile(min_ndc.x, grid_x)..=tile(max_ndc.x, grid_x) {
+                        clusters[(x + grid_x * (y + grid_y * z)) as usize].push(index as f32);
+                    }
+                }
+            }
+        }
+
+        let mut grid = Vec::with_capacity(clusters.len());
+        let mut indices = Vec::new();
+        for cluster in clusters {
+            grid.push([indices.len() as f32, cluster.len() as f32]);
+            indices.extend(cluster);
+        }
+        self.grid_texture.fill(&pad_data(
+            &grid,
+            self.grid_texture.width() * self.grid_texture.height(),
+        ));
+        if indices.len() as u32 > self.index_texture.width() * self.index_texture.height() {
+            // Grow to the next power of two to avoid reallocating each frame
+            let capacity = (indices.len() as u32).next_power_of_two();
+            self.index_texture = new_data_texture(&self.context, &vec![0.0f32; capacity as usize]);
+        }
+        self.index_texture.fill(&pad_data(
+            &indices,
+            self.index_texture.width() * self.index_texture.height(),
+        ));
+    }
+}
+
+impl Light for ClusteredLights {
+    fn shader_source(&self, i: u32) -> String {
+        format!(
+            "
+                uniform sampler2D clusterLights{i};
+                uniform sampler2D clusterGrid{i};
+                uniform sampler2D clusterIndices{i};
+                uniform mat4 clusterView{i};
+                uniform mat4 clusterProjection{i};
+                uniform vec3 clusterGridSize{i};
+                uniform vec2 clusterDepthRange{i};
+                vec3 calculate_lighting{i}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    return calculate_clustered_lighting(clusterLights{i}, clusterGrid{i}, clusterIndices{i}, clusterView{i}, clusterProjection{i},
+                        clusterGridSize{i}, clusterDepthRange{i}, surface_color, position, normal, view_direction, metallic, roughness);
+                }}
+
+            "
+        )
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        let (grid_x, grid_y, grid_z) = Self::GRID_SIZE;
+        program.use_texture(&format!("clusterLights{}", i), &self.light_texture);
+        program.use_texture(&format!("clusterGrid{}", i), &self.grid_texture);
+        program.use_texture(&format!("clusterIndices{}", i), &self.index_texture);
+        program.use_uniform(&format!("clusterView{}", i), self.view);
+        program.use_uniform(&format!("clusterProjection{}", i), self.projection);
+        program.use_uniform(
+            &format!("clusterGridSize{}", i),
+            vec3(grid_x as f32, grid_y as f32, grid_z as f32),
+        );
+        program.use_uniform(&format!("clusterDepthRange{}", i), self.depth_range);
+    }
+
+    fn id(&self) -> u8 {
+        0b1u8 << 7 | 0b1101u8
+    }
+}
+
+// Creates a texture containing the given values row by row with at most DATA_TEXTURE_WIDTH values in each row.
+fn new_data_texture<T: TextureDataType + Default>(context: &Context, data: &[T]) -> Texture2D {
+    let count = (data.len() as u32).max(1);
+    let width = count.min(ClusteredLights::DATA_TEXTURE_WIDTH);
+    let height = count.div_ceil(width);
+    let mut texture = Texture2D::new_empty::<T>(
+        context,
+        width,
+        height,
+        Interpolation::Nearest,
+        Interpolation::Nearest,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    );
+    texture.fill(&pad_data(data, width * height));
+    texture
+}
+
+fn pad_data<T: Clone + Default>(data: &[T], count: u32) -> Vec<T> {
+    let mut data = data.to_vec();
+    data.resize(count as usize, T::default());
+    data
+}
@@ -9,7 +9,11 @@ use crate::renderer::*;
 pub struct DirectionalLight {
     context: Context,
     shadow_texture: Option<DepthTexture2D>,
+    shadow_moments: Option<Texture2D>,
+    shadow_technique: ShadowTechnique,
     shadow_matrix: Mat4,
+    /// Controls the filtering and biasing when sampling the shadow map, see [ShadowSettings].
+    pub shadow_settings: ShadowSettings,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
     pub intensity: f32,
     /// The base color of the light.
@@ -29,7 +33,10 @@ impl DirectionalLight {
         DirectionalLight {
             context: context.clone(),
             shadow_matrix: Mat4::identity(),
+            shadow_settings: ShadowSettings::default(),
             shadow_texture: None,
+            shadow_moments: None,
+            shadow_technique: ShadowTechnique::PercentageCloserFiltering,
             intensity,
             color,
             direction: *direction,
@@ -42,6 +49,7 @@ impl DirectionalLight {
     ///
     pub fn clear_shadow_map(&mut self) {
         self.shadow_texture = None;
+        self.shadow_moments = None;
         self.shadow_matrix = Mat4::identity();
     }
 
@@ -80,39 +88,15 @@ impl DirectionalLight {
             z_near,
             z_far,
         );
-        let mut shadow_texture = DepthTexture2D::new::<f32>(
+        let (shadow_texture, shadow_moments) = generate_shadow_textures(
             &self.context,
             texture_size,
-            texture_size,
-            Wrapping::ClampToEdge,
-            Wrapping::ClampToEdge,
+            &shadow_camera,
+            geometries,
+            self.shadow_settings.technique,
         );
-        let depth_material = DepthMaterial {
-            render_states: RenderStates {
-                write_mask: WriteMask::DEPTH,
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-        shadow_texture
-            .as_depth_target()
-            .clear(ClearState::default())
-            .write::<RendererError>(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
-                {
-                    render_with_material(
-                        &self.context,
-                        &shadow_camera,
-                        &geometry,
-                        &depth_material,
-                        &[],
-                    );
-                }
-                Ok(())
-            })
-            .unwrap();
+        self.shadow_moments = shadow_moments;
+        self.shadow_technique = self.shadow_settings.technique;
         self.shadow_texture = Some(shadow_texture);
         self.shadow_matrix = shadow_matrix(&shadow_camera);
     }
@@ -123,6 +107,13 @@ impl DirectionalLight {
     pub fn shadow_map(&self) -> Option<&DepthTexture2D> {
         self.shadow_texture.as_ref()
     }
+
+    ///
+    /// Returns a reference to the blurred moments texture if the shadow map has been generated using [ShadowTechnique::Variance] or [ShadowTechnique::Exponential].
+    ///
+    pub fn shadow_moments_map(&self) -> Option<&Texture2D> {
+        self.shadow_moments.as_ref()
+    }
 }
 
 impl Light for DirectionalLight {
@@ -132,6 +123,7 @@ impl Light for DirectionalLight {
                 "
                     uniform sampler2D shadowMap{};
                     uniform mat4 shadowMVP{};
+                    uniform vec4 shadowParameters{};
 
                     uniform vec3 color{};
                     uniform vec3 direction{};
@@ -139,10 +131,10 @@ impl Light for DirectionalLight {
                     vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
                     {{
                         return calculate_light(color{}, -direction{}, surface_color, view_direction, normal, metallic, roughness)
-                            * calculate_shadow(-direction{}, normal, shadowMap{}, shadowMVP{}, position);
+                            * {shadow}(-direction{}, normal, shadowMap{}, shadowMVP{}, position, shadowParameters{});
                     }}
 
-                ", i, i, i, i, i, i, i, i, i, i)
+                ", i, i, i, i, i, i, i, i, i, i, i, i, shadow = shadow_function(self.shadow_technique))
         } else {
             format!(
                 "
@@ -159,8 +151,16 @@ impl Light for DirectionalLight {
     }
     fn use_uniforms(&self, program: &Program, i: u32) {
         if let Some(ref tex) = self.shadow_texture {
-            program.use_depth_texture(&format!("shadowMap{}", i), tex);
+            if let Some(ref moments) = self.shadow_moments {
+                program.use_texture(&format!("shadowMap{}", i), moments);
+            } else {
+                program.use_depth_texture(&format!("shadowMap{}", i), tex);
+            }
             program.use_uniform(&format!("shadowMVP{}", i), self.shadow_matrix);
+            program.use_uniform(
+                &format!("shadowParameters{}", i),
+                self.shadow_settings.parameters(self.shadow_technique),
+            );
         }
         program.use_uniform(
             &format!("color{}", i),
@@ -171,9 +171,27 @@ impl Light for DirectionalLight {
 
     fn id(&self) -> u8 {
         if self.shadow_texture.is_some() {
-            0b1u8 << 7 | 0b10u8
+            match self.shadow_technique {
+                ShadowTechnique::PercentageCloserFiltering => 0b1u8 << 7 | 0b10u8,
+                ShadowTechnique::Variance { .. } => 0b1u8 << 7 | 0b1000u8,
+                ShadowTechnique::Exponential { .. } => 0b1u8 << 7 | 0b1001u8,
+            }
         } else {
             0b1u8 << 7 | 0b11u8
         }
     }
 }
+
+impl ShadowCaster for DirectionalLight {
+    fn update_shadow_map(&mut self, texture_size: Option<u32>, geometries: &[&dyn Geometry]) {
+        if let Some(texture_size) = texture_size {
+            self.generate_shadow_map(texture_size, geometries.iter().copied());
+        } else {
+            self.clear_shadow_map();
+        }
+    }
+
+    fn shadow_settings_mut(&mut self) -> &mut ShadowSettings {
+        &mut self.shadow_settings
+    }
+}
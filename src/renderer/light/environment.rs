@@ -13,6 +13,40 @@ pub struct Environment {
     pub prefilter_map: TextureCubeMap,
     /// A 2D texture that contain the BRDF lookup tables (LUT).
     pub brdf_map: Texture2D,
+    /// The number of mip levels in the [Environment::prefilter_map] that contain prefiltered colors, where the last level corresponds to a roughness of 1.
+    pub prefilter_mip_levels: u32,
+}
+
+///
+/// Options for computing an [Environment], which makes it possible to trade quality for computation time and memory.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnvironmentOptions {
+    /// The width and height of each side of the irradiance cube map.
+    pub irradiance_size: u32,
+    /// The width and height of each side of the largest mip level of the prefiltered cube map.
+    pub prefilter_size: u32,
+    /// The number of mip levels in the prefiltered cube map, each of which corresponds to a surface roughness between 0 and 1.
+    /// This is clamped to the number of mip levels available for the given prefilter size.
+    pub prefilter_mip_levels: u32,
+    /// The width and height of the BRDF lookup texture.
+    pub brdf_size: u32,
+    /// Whether or not to stretch the directions of the texels in the irradiance and prefiltered cube maps, so the texels on the edge of two sides contain the same color.
+    /// This hides the seams between the sides when the cube maps are sampled without seamless filtering, at the cost of slightly distorting the content near the edges.
+    /// If `None`, the edge fixup is applied if the context does not support seamless cube map filtering, see [Capabilities::seamless_cube_maps].
+    pub edge_fixup: Option<bool>,
+}
+
+impl Default for EnvironmentOptions {
+    fn default() -> Self {
+        Self {
+            irradiance_size: 32,
+            prefilter_size: 128,
+            prefilter_mip_levels: 5,
+            brdf_size: 512,
+            edge_fixup: None,
+        }
+    }
 }
 
 impl Environment {
@@ -39,8 +73,30 @@ impl Environment {
         environment_map: &TextureCubeMap,
         lighting_model: LightingModel,
     ) -> Self {
+        Self::new_with_options(
+            context,
+            environment_map,
+            lighting_model,
+            EnvironmentOptions::default(),
+        )
+    }
+
+    ///
+    /// Computes the maps needed for physically based rendering with lighting from an environment from the given environment map,
+    /// with the specified lighting model and with the resolutions given in the options.
+    ///
+    pub fn new_with_options(
+        context: &Context,
+        environment_map: &TextureCubeMap,
+        lighting_model: LightingModel,
+        options: EnvironmentOptions,
+    ) -> Self {
+        let edge_fixup = options
+            .edge_fixup
+            .unwrap_or(!context.capabilities().seamless_cube_maps);
+
         // Diffuse
-        let irradiance_size = 32;
+        let irradiance_size = options.irradiance_size.max(1);
         let mut irradiance_map = TextureCubeMap::new_empty::<[f16; 4]>(
             context,
             irradiance_size,
@@ -62,6 +118,7 @@ impl Environment {
                         &IrradianceMaterial {
                             environment_map,
                             side,
+                            edge_fixup_scale: edge_fixup_scale(edge_fixup, irradiance_size),
                         },
                         &Camera::new_2d(viewport),
                         &[],
@@ -70,7 +127,10 @@ impl Environment {
         }
 
         // Prefilter
-        let prefilter_size = 128;
+        let prefilter_size = options.prefilter_size.max(1);
+        let max_mip_levels = options
+            .prefilter_mip_levels
+            .clamp(1, prefilter_size.ilog2() + 1);
         let mut prefilter_map = TextureCubeMap::new_empty::<[f16; 4]>(
             context,
             prefilter_size,
@@ -83,7 +143,6 @@ impl Environment {
             Wrapping::ClampToEdge,
         );
         {
-            let max_mip_levels = 5;
             for mip in 0..max_mip_levels {
                 for side in CubeMapSide::iter() {
                     let sides = [side];
@@ -99,6 +158,10 @@ impl Environment {
                                 side,
                                 mip,
                                 max_mip_levels,
+                                edge_fixup_scale: edge_fixup_scale(
+                                    edge_fixup,
+                                    color_target.width(),
+                                ),
                             },
                             &Camera::new_2d(viewport),
                             &[],
@@ -110,8 +173,8 @@ impl Environment {
         // BRDF
         let mut brdf_map = Texture2D::new_empty::<[f32; 2]>(
             context,
-            512,
-            512,
+            options.brdf_size.max(1),
+            options.brdf_size.max(1),
             Interpolation::Linear,
             Interpolation::Linear,
             None,
@@ -132,16 +195,27 @@ impl Environment {
             irradiance_map,
             prefilter_map,
             brdf_map,
+            prefilter_mip_levels: max_mip_levels,
         }
     }
 }
 
+// The scale applied to the texel directions of a cube map side with the given size, so the centers of the edge texels point exactly at the edge.
+fn edge_fixup_scale(edge_fixup: bool, size: u32) -> f32 {
+    if edge_fixup && size > 1 {
+        size as f32 / (size as f32 - 1.0)
+    } else {
+        1.0
+    }
+}
+
 struct PrefilterMaterial<'a> {
     lighting_model: LightingModel,
     environment_map: &'a TextureCubeMap,
     side: CubeMapSide,
     mip: u32,
     max_mip_levels: u32,
+    edge_fixup_scale: f32,
 }
 
 impl Material for PrefilterMaterial<'_> {
@@ -170,11 +244,12 @@ impl Material for PrefilterMaterial<'_> {
         program.use_texture_cube("environmentMap", self.environment_map);
         program.use_uniform(
             "roughness",
-            self.mip as f32 / (self.max_mip_levels as f32 - 1.0),
+            self.mip as f32 / (self.max_mip_levels as f32 - 1.0).max(1.0),
         );
         program.use_uniform("resolution", self.environment_map.width() as f32);
         program.use_uniform("direction", self.side.direction());
         program.use_uniform("up", self.side.up());
+        program.use_uniform("edgeFixupScale", self.edge_fixup_scale);
     }
 
     fn render_states(&self) -> RenderStates {
@@ -226,6 +301,7 @@ impl Material for BrdfMaterial {
 struct IrradianceMaterial<'a> {
     environment_map: &'a TextureCubeMap,
     side: CubeMapSide,
+    edge_fixup_scale: f32,
 }
 
 impl Material for IrradianceMaterial<'_> {
@@ -252,6 +328,7 @@ impl Material for IrradianceMaterial<'_> {
         program.use_texture_cube("environmentMap", self.environment_map);
         program.use_uniform("direction", self.side.direction());
         program.use_uniform("up", self.side.up());
+        program.use_uniform("edgeFixupScale", self.edge_fixup_scale);
     }
 
     fn render_states(&self) -> RenderStates {
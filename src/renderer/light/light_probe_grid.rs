@@ -0,0 +1,383 @@
+use crate::renderer::*;
+
+// The convolution of each band with the clamped cosine lobe divided by pi, see "An Efficient Representation for Irradiance Environment Maps" by Ramamoorthi and Hanrahan
+const BAND_FACTORS: [f32; 9] = [
+    1.0,
+    2.0 / 3.0,
+    2.0 / 3.0,
+    2.0 / 3.0,
+    0.25,
+    0.25,
+    0.25,
+    0.25,
+    0.25,
+];
+
+///
+/// The incoming light in all directions at a point, represented by the first nine spherical harmonics coefficients (up to and including band 2).
+/// This is a compact approximation of the low frequency part of the light, which is sufficient for diffuse lighting.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphericalHarmonics {
+    /// The coefficients of the radiance in linear color space.
+    pub coefficients: [Vec3; 9],
+}
+
+impl SphericalHarmonics {
+    ///
+    /// Bakes the incoming light at the given position by rendering the given objects with the given lights into the six sides of a cube
+    /// with the given texture size and projecting the result onto the spherical harmonics basis.
+    ///
+    pub fn bake(
+        context: &Context,
+        position: Vec3,
+        texture_size: u32,
+        z_near: f32,
+        z_far: f32,
+        objects: &[&dyn Object],
+        lights: &[&dyn Light],
+    ) -> Self {
+        let texture_size = texture_size.max(1);
+        let mut color = Texture2D::new_empty::<[f16; 4]>(
+            context,
+            texture_size,
+            texture_size,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let mut depth = DepthTexture2D::new::<f32>(
+            context,
+            texture_size,
+            texture_size,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+
+        let mut spherical_harmonics = Self::default();
+        let mut total_weight = 0.0;
+        for (direction, up) in [
+            (Vec3::unit_x(), -Vec3::unit_y()),
+            (-Vec3::unit_x(), -Vec3::unit_y()),
+            (Vec3::unit_y(), Vec3::unit_z()),
+            (-Vec3::unit_y(), -Vec3::unit_z()),
+            (Vec3::unit_z(), -Vec3::unit_y()),
+            (-Vec3::unit_z(), -Vec3::unit_y()),
+        ] {
+            let mut camera = Camera::new_perspective(
+                Viewport::new_at_origo(texture_size, texture_size),
+                position,
+                position + direction,
+                up,
+                degrees(90.0),
+                z_near,
+                z_far,
+            );
+            // The radiance is baked in linear space, the tone and color mapping is applied when the probes are used for rendering
+            camera.disable_tone_and_color_mapping();
+            let data = RenderTarget::new(color.as_color_target(None), depth.as_depth_target())
+                .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 1.0, 1.0))
+                .render(&camera, objects, lights)
+                .read_color::<[f32; 4]>();
+
+            let right = direction.cross(up);
+            for (i, rgba) in data.iter().enumerate() {
+                let x = 2.0 * ((i as u32 % texture_size) as f32 + 0.5) / texture_size as f32 - 1.0;
+                let y = 2.0 * ((i as u32 / texture_size) as f32 + 0.5) / texture_size as f32 - 1.0;
+                // The solid angle of a pixel on a cube side is proportional to this weight
+                let weight = (1.0 + x * x + y * y).powf(-1.5);
+                let sample_direction = (direction + right * x + up * y).normalize();
+                let radiance = vec3(rgba[0], rgba[1], rgba[2]);
+                for (coefficient, basis) in spherical_harmonics
+                    .coefficients
+                    .iter_mut()
+                    .zip(basis(sample_direction))
+                {
+                    *coefficient += radiance * (basis * weight);
+                }
+                total_weight += weight;
+            }
+        }
+        let scale = 4.0 * std::f32::consts::PI / total_weight;
+        for coefficient in spherical_harmonics.coefficients.iter_mut() {
+            *coefficient *= scale;
+        }
+        spherical_harmonics
+    }
+
+    ///
+    /// Returns the irradiance divided by pi for a surface with the given normal, ie. the light reflected by a white diffuse surface.
+    ///
+    pub fn irradiance(&self, normal: Vec3) -> Vec3 {
+        let mut irradiance = vec3(0.0, 0.0, 0.0);
+        for ((coefficient, basis), factor) in self
+            .coefficients
+            .iter()
+            .zip(basis(normal.normalize()))
+            .zip(BAND_FACTORS)
+        {
+            irradiance += coefficient * (basis * factor);
+        }
+        vec3(
+            irradiance.x.max(0.0),
+            irradiance.y.max(0.0),
+            irradiance.z.max(0.0),
+        )
+    }
+
+    ///
+    /// Returns the linear interpolation between this and the other spherical harmonics, where a factor of zero returns this and a factor of one returns the other.
+    ///
+    pub fn lerp(&self, other: &Self, factor: f32) -> Self {
+        let mut coefficients = self.coefficients;
+        for (coefficient, other) in coefficients.iter_mut().zip(other.coefficients) {
+            *coefficient = *coefficient * (1.0 - factor) + other * factor;
+        }
+        Self { coefficients }
+    }
+}
+
+impl Default for SphericalHarmonics {
+    fn default() -> Self {
+        Self {
+            coefficients: [vec3(0.0, 0.0, 0.0); 9],
+        }
+    }
+}
+
+fn basis(direction: Vec3) -> [f32; 9] {
+    let (x, y, z) = (direction.x, direction.y, direction.z);
+    [
+        0.282095,
+        0.488603 * y,
+        0.488603 * z,
+        0.488603 * x,
+        1.092548 * x * y,
+        1.092548 * y * z,
+        0.315392 * (3.0 * z * z - 1.0),
+        1.092548 * x * z,
+        0.546274 * (x * x - y * y),
+    ]
+}
+
+///
+/// A regular grid of light probes inside a bounding box, which each stores the incoming light at the position of the probe as [SphericalHarmonics].
+/// When baked from a static scene, the light bouncing off the surfaces in the scene is captured, so the probes provide a cheap approximation
+/// of diffuse global illumination which varies throughout the scene, in contrast to a single global [AmbientLight].
+///
+/// The light at a position is found by trilinear interpolation between the eight closest probes, see [LightProbeGrid::light],
+/// and is typically evaluated once per object at the center of the bounding box of the object.
+///
+#[derive(Clone, Debug)]
+pub struct LightProbeGrid {
+    aabb: AxisAlignedBoundingBox,
+    resolution: [u32; 3],
+    probes: Vec<SphericalHarmonics>,
+}
+
+impl LightProbeGrid {
+    ///
+    /// Creates a new grid with the given number of probes along each axis placed uniformly inside the given bounding box,
+    /// where the probes on the boundary are placed on the sides of the box. The probes are initially black, call [LightProbeGrid::bake] to bake the probes.
+    ///
+    pub fn new(aabb: AxisAlignedBoundingBox, resolution: [u32; 3]) -> Self {
+        let resolution = resolution.map(|r| r.max(1));
+        Self {
+            aabb,
+            resolution,
+            probes: vec![
+                SphericalHarmonics::default();
+                (resolution[0] * resolution[1] * resolution[2]) as usize
+            ],
+        }
+    }
+
+    ///
+    /// Returns the bounding box of the grid.
+    ///
+    pub fn aabb(&self) -> AxisAlignedBoundingBox {
+        self.aabb
+    }
+
+    ///
+    /// Returns the number of probes along each axis.
+    ///
+    pub fn resolution(&self) -> [u32; 3] {
+        self.resolution
+    }
+
+    ///
+    /// Returns the position of the probe with the given index along each axis.
+    ///
+    pub fn probe_position(&self, index: [u32; 3]) -> Vec3 {
+        let mut position = self.aabb.min();
+        let size = self.aabb.size();
+        for axis in 0..3 {
+            if self.resolution[axis] > 1 {
+                position[axis] +=
+                    size[axis] * index[axis] as f32 / (self.resolution[axis] - 1) as f32;
+            } else {
+                position[axis] += 0.5 * size[axis];
+            }
+        }
+        position
+    }
+
+    ///
+    /// Returns the probe with the given index along each axis.
+    ///
+    pub fn probe(&self, index: [u32; 3]) -> &SphericalHarmonics {
+        &self.probes[self.probe_index(index)]
+    }
+
+    ///
+    /// Returns a mutable reference to the probe with the given index along each axis.
+    ///
+    pub fn probe_mut(&mut self, index: [u32; 3]) -> &mut SphericalHarmonics {
+        let i = self.probe_index(index);
+        &mut self.probes[i]
+    }
+
+    ///
+    /// Bakes all probes by rendering the given objects with the given lights from the position of each probe, see [SphericalHarmonics::bake].
+    /// This is expensive, so it should be done once for a static scene and not every frame.
+    /// The lights should not include a light created from this grid, unless the intention is to add an additional bounce of light.
+    ///
+    pub fn bake(
+        &mut self,
+        context: &Context,
+        texture_size: u32,
+        z_near: f32,
+        z_far: f32,
+        objects: &[&dyn Object],
+        lights: &[&dyn Light],
+    ) {
+        for z in 0..self.resolution[2] {
+            for y in 0..self.resolution[1] {
+                for x in 0..self.resolution[0] {
+                    let position = self.probe_position([x, y, z]);
+                    *self.probe_mut([x, y, z]) = SphericalHarmonics::bake(
+                        context,
+                        position,
+                        texture_size,
+                        z_near,
+                        z_far,
+                        objects,
+                        lights,
+                    );
+                }
+            }
+        }
+    }
+
+    ///
+    /// Returns the incoming light at the given position, interpolated trilinearly between the eight closest probes.
+    /// Positions outside the grid use the closest probes on the boundary of the grid.
+    ///
+    pub fn sample(&self, position: Vec3) -> SphericalHarmonics {
+        let size = self.aabb.size();
+        let mut index = [0u32; 3];
+        let mut factor = [0.0f32; 3];
+        for axis in 0..3 {
+            let max_index = self.resolution[axis] - 1;
+            let coordinate = if max_index > 0 && size[axis] > 0.0 {
+                ((position[axis] - self.aabb.min()[axis]) / size[axis] * max_index as f32)
+                    .clamp(0.0, max_index as f32)
+            } else {
+                0.0
+            };
+            index[axis] = (coordinate.floor() as u32).min(max_index.saturating_sub(1));
+            factor[axis] = coordinate - index[axis] as f32;
+        }
+        let corner = |dx: u32, dy: u32, dz: u32| {
+            let i = [
+                (index[0] + dx).min(self.resolution[0] - 1),
+                (index[1] + dy).min(self.resolution[1] - 1),
+                (index[2] + dz).min(self.resolution[2] - 1),
+            ];
+            *self.probe(i)
+        };
+        let lerp_x = |dy, dz| corner(0, dy, dz).lerp(&corner(1, dy, dz), factor[0]);
+        let lerp_y = |dz| lerp_x(0, dz).lerp(&lerp_x(1, dz), factor[1]);
+        lerp_y(0).lerp(&lerp_y(1), factor[2])
+    }
+
+    ///
+    /// Returns a light which applies the incoming light at the given position with the given intensity,
+    /// for example the center of the bounding box of the object that is rendered with the light.
+    ///
+    pub fn light(&self, position: Vec3, intensity: f32) -> SphericalHarmonicsLight {
+        SphericalHarmonicsLight {
+            intensity,
+            spherical_harmonics: self.sample(position),
+        }
+    }
+
+    fn probe_index(&self, index: [u32; 3]) -> usize {
+        assert!(
+            index[0] < self.resolution[0]
+                && index[1] < self.resolution[1]
+                && index[2] < self.resolution[2],
+            "the probe index {:?} must be smaller than the resolution {:?}",
+            index,
+            self.resolution
+        );
+        (index[0] + self.resolution[0] * (index[1] + self.resolution[1] * index[2])) as usize
+    }
+}
+
+///
+/// A light which shines diffuse light onto the surfaces from all directions as given by [SphericalHarmonics], for example sampled from a [LightProbeGrid].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphericalHarmonicsLight {
+    /// The intensity of the light.
+    pub intensity: f32,
+    /// The incoming light.
+    pub spherical_harmonics: SphericalHarmonics,
+}
+
+impl Light for SphericalHarmonicsLight {
+    fn shader_source(&self, i: u32) -> String {
+        format!(
+            "
+                uniform vec3 shCoefficients{}[9];
+
+                vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    vec3 n = normal;
+                    vec3 irradiance = shCoefficients{}[0] * 0.282095
+                        + shCoefficients{}[1] * 0.488603 * n.y
+                        + shCoefficients{}[2] * 0.488603 * n.z
+                        + shCoefficients{}[3] * 0.488603 * n.x
+                        + shCoefficients{}[4] * 1.092548 * n.x * n.y
+                        + shCoefficients{}[5] * 1.092548 * n.y * n.z
+                        + shCoefficients{}[6] * 0.315392 * (3.0 * n.z * n.z - 1.0)
+                        + shCoefficients{}[7] * 1.092548 * n.x * n.z
+                        + shCoefficients{}[8] * 0.546274 * (n.x * n.x - n.y * n.y);
+
+                    vec3 F0 = mix(vec3(0.04), surface_color, metallic);
+                    vec3 diffuse_fresnel = 1.0 - fresnel_schlick_roughness(F0, max(0.001, dot(normal, view_direction)), roughness);
+                    return diffuse_fresnel * mix(surface_color, vec3(0.0), metallic) * max(irradiance, vec3(0.0)) * occlusion;
+                }}
+
+            ", i, i, i, i, i, i, i, i, i, i, i)
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        let coefficients: Vec<Vec3> = self
+            .spherical_harmonics
+            .coefficients
+            .iter()
+            .zip(BAND_FACTORS)
+            .map(|(coefficient, factor)| coefficient * (factor * self.intensity))
+            .collect();
+        program.use_uniform_array(&format!("shCoefficients{}", i), &coefficients);
+    }
+
+    fn id(&self) -> u8 {
+        0b1u8 << 7 | 0b1110u8
+    }
+}
@@ -0,0 +1,174 @@
+use crate::renderer::*;
+
+///
+/// A [UniformBuffer] containing the data of up to [LightsUniformBuffer::MAX_LIGHT_COUNT] directional, point and spot lights and the color of an ambient light,
+/// which can be shared between all programs instead of sending the light uniforms to each program separately.
+/// Update it when the lights change using [LightsUniformBuffer::update] and use it in a program using [LightsUniformBuffer::use_uniform_block].
+/// The uniform block must be declared in the shader as given by [LightsUniformBuffer::fragment_shader_source].
+///
+/// For each light `i` less than `lightCount`, the block contains
+/// - `lightColors[i]`: The linear color multiplied by the intensity in the first three components and the type of light in the last, 0 for directional, 1 for point and 2 for spot lights.
+/// - `lightPositions[i]`: The position in the first three components and the cosine of the [inner cutoff](SpotLight::inner_cutoff) angle of a spot light in the last.
+/// - `lightDirections[i]`: The normalized direction in the first three components and the cosine of the [cutoff](SpotLight::cutoff) angle of a spot light in the last.
+/// - `lightAttenuations[i]`: The constant, linear and quadratic [Attenuation] factors and the range, where 0 means that the light never fades completely.
+///
+/// Shadows, gobos and the environment of the ambient light are not part of the block.
+///
+pub struct LightsUniformBuffer {
+    buffer: UniformBuffer,
+}
+
+impl LightsUniformBuffer {
+    /// The name of the uniform block in the shader.
+    pub const BLOCK_NAME: &'static str = "Lights";
+
+    /// The maximum number of directional, point and spot lights in the block.
+    pub const MAX_LIGHT_COUNT: usize = 16;
+
+    ///
+    /// Creates a new lights uniform buffer without any lights.
+    ///
+    pub fn new(context: &Context) -> Self {
+        let count = Self::MAX_LIGHT_COUNT as u32;
+        Self {
+            buffer: UniformBuffer::new_with_layout(
+                context,
+                UniformBlockLayout::new()
+                    .add("ambientColor", UniformBlockType::Vec3)
+                    .add("lightCount", UniformBlockType::Float)
+                    .add_array("lightColors", UniformBlockType::Vec4, count)
+                    .add_array("lightPositions", UniformBlockType::Vec4, count)
+                    .add_array("lightDirections", UniformBlockType::Vec4, count)
+                    .add_array("lightAttenuations", UniformBlockType::Vec4, count),
+            ),
+        }
+    }
+
+    ///
+    /// Updates the buffer with the given lights.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total number of directional, point and spot lights is larger than [LightsUniformBuffer::MAX_LIGHT_COUNT].
+    ///
+    pub fn update(
+        &mut self,
+        ambient: Option<&AmbientLight>,
+        directional: &[&DirectionalLight],
+        point: &[&PointLight],
+        spot: &[&SpotLight],
+    ) {
+        let count = directional.len() + point.len() + spot.len();
+        if count > Self::MAX_LIGHT_COUNT {
+            panic!(
+                "Failed updating lights: The number of lights {} is larger than the maximum number of lights {}",
+                count,
+                Self::MAX_LIGHT_COUNT
+            );
+        }
+        let mut colors = vec![0.0; 4 * Self::MAX_LIGHT_COUNT];
+        let mut positions = vec![0.0; 4 * Self::MAX_LIGHT_COUNT];
+        let mut directions = vec![0.0; 4 * Self::MAX_LIGHT_COUNT];
+        let mut attenuations = vec![0.0; 4 * Self::MAX_LIGHT_COUNT];
+        let no_attenuation = Attenuation {
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 0.0,
+            range: None,
+        };
+        let lights = directional
+            .iter()
+            .map(|l| {
+                (
+                    0.0,
+                    l.color,
+                    l.intensity,
+                    vec3(0.0, 0.0, 0.0),
+                    l.direction,
+                    (1.0, 1.0),
+                    no_attenuation,
+                )
+            })
+            .chain(point.iter().map(|l| {
+                (
+                    1.0,
+                    l.color,
+                    l.intensity,
+                    l.position,
+                    vec3(0.0, 0.0, 0.0),
+                    (1.0, 1.0),
+                    l.attenuation,
+                )
+            }))
+            .chain(spot.iter().map(|l| {
+                (
+                    2.0,
+                    l.color,
+                    l.intensity,
+                    l.position,
+                    l.direction,
+                    (l.clamped_inner_cutoff().0.cos(), l.cutoff.0.cos()),
+                    l.attenuation,
+                )
+            }));
+        for (i, (light_type, color, intensity, position, direction, cutoffs, attenuation)) in
+            lights.enumerate()
+        {
+            let color = color.to_linear_srgb().truncate() * intensity;
+            let direction = if direction.magnitude2() > 0.0 {
+                direction.normalize()
+            } else {
+                direction
+            };
+            colors[4 * i..4 * i + 4].copy_from_slice(&[color.x, color.y, color.z, light_type]);
+            positions[4 * i..4 * i + 4]
+                .copy_from_slice(&[position.x, position.y, position.z, cutoffs.0]);
+            directions[4 * i..4 * i + 4].copy_from_slice(&[
+                direction.x,
+                direction.y,
+                direction.z,
+                cutoffs.1,
+            ]);
+            attenuations[4 * i..4 * i + 4].copy_from_slice(&[
+                attenuation.constant,
+                attenuation.linear,
+                attenuation.quadratic,
+                attenuation.range.unwrap_or(0.0),
+            ]);
+        }
+        let ambient_color = ambient.map_or(vec3(0.0, 0.0, 0.0), |l| {
+            l.color.to_linear_srgb().truncate() * l.intensity
+        });
+        let ambient_color: &[f32; 3] = ambient_color.as_ref();
+        self.buffer.update(0, ambient_color);
+        self.buffer.update(1, &[count as f32]);
+        self.buffer.update(2, &colors);
+        self.buffer.update(3, &positions);
+        self.buffer.update(4, &directions);
+        self.buffer.update(5, &attenuations);
+    }
+
+    ///
+    /// Uses this buffer as the lights uniform block in the given program.
+    ///
+    pub fn use_uniform_block(&self, program: &Program) {
+        program.use_uniform_block(Self::BLOCK_NAME, &self.buffer);
+    }
+
+    ///
+    /// Returns the declaration of the lights uniform block which can be included in both the vertex and fragment shader.
+    ///
+    pub fn fragment_shader_source() -> &'static str {
+        "
+        layout (std140) uniform Lights
+        {
+            vec3 ambientColor;
+            float lightCount;
+            vec4 lightColors[16];
+            vec4 lightPositions[16];
+            vec4 lightDirections[16];
+            vec4 lightAttenuations[16];
+        };
+        "
+    }
+}
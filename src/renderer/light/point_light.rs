@@ -3,9 +3,17 @@ use crate::renderer::*;
 
 ///
 /// A light which shines from the given position in all directions.
+/// The light will cast shadows if you [generate a shadow map](PointLight::generate_shadow_map).
 ///
 pub struct PointLight {
+    context: Context,
+    shadow_texture: Option<DepthTextureCubeMap>,
+    shadow_clip_planes: Vec2,
+    /// Controls the filtering and biasing when sampling the shadow map, see [ShadowSettings].
+    /// The depth bias and slope scaled bias are relative to the distance from the light.
+    pub shadow_settings: ShadowSettings,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
+    /// When using physically based [Attenuation::inverse_square] attenuation, this is the luminous intensity in candela, see also [PointLight::set_luminous_power].
     pub intensity: f32,
     /// The base color of the light.
     pub color: Srgba,
@@ -18,58 +26,219 @@ pub struct PointLight {
 impl PointLight {
     /// Constructs a new point light.
     pub fn new(
-        _context: &Context,
+        context: &Context,
         intensity: f32,
         color: Srgba,
         position: &Vec3,
         attenuation: Attenuation,
     ) -> PointLight {
         PointLight {
+            context: context.clone(),
+            shadow_texture: None,
+            shadow_clip_planes: vec2(0.0, 0.0),
+            shadow_settings: ShadowSettings::default(),
             intensity,
             color,
             position: *position,
             attenuation,
         }
     }
+
+    ///
+    /// Clear the shadow map, effectively disable the shadow.
+    /// Only necessary if you want to disable the shadow, if you want to update the shadow, just use [PointLight::generate_shadow_map].
+    ///
+    pub fn clear_shadow_map(&mut self) {
+        self.shadow_texture = None;
+        self.shadow_clip_planes = vec2(0.0, 0.0);
+    }
+
+    ///
+    /// Generate a shadow map which is used to simulate shadows from the point light onto the geometries given as input.
+    /// The depth seen from the light is rendered into each of the six sides of a cube map, so this is six times as expensive as generating the shadow map of a [SpotLight].
+    /// It is recomended that the texture size is power of 2.
+    /// If the shadows are too low resolution (the edges between shadow and non-shadow are pixelated) try to increase the texture size.
+    ///
+    pub fn generate_shadow_map(
+        &mut self,
+        texture_size: u32,
+        geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+    ) {
+        let viewport = Viewport::new_at_origo(texture_size, texture_size);
+
+        let mut z_far = 0.0f32;
+        let mut z_near = f32::MAX;
+        for geometry in geometries.clone() {
+            let aabb = geometry.aabb();
+            if !aabb.is_empty() {
+                z_far = z_far.max(aabb.distance_max(&self.position));
+                z_near = z_near.min(aabb.distance(&self.position));
+            }
+        }
+        if z_far <= 0.0 {
+            return;
+        }
+        let z_near = z_near.max(0.01).min(z_far * 0.5);
+
+        let mut shadow_texture = DepthTextureCubeMap::new::<f32>(
+            &self.context,
+            texture_size,
+            texture_size,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let depth_material = DepthMaterial {
+            render_states: RenderStates {
+                write_mask: WriteMask::DEPTH,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for side in CubeMapSide::iter() {
+            let shadow_camera = Camera::new_perspective(
+                viewport,
+                self.position,
+                self.position + side.direction(),
+                side.up(),
+                degrees(90.0),
+                z_near,
+                z_far,
+            );
+            shadow_texture
+                .as_depth_target(side)
+                .clear(ClearState::default())
+                .write::<RendererError>(|| {
+                    for geometry in geometries
+                        .clone()
+                        .into_iter()
+                        .filter(|g| shadow_camera.in_frustum(&g.aabb()))
+                    {
+                        geometry.render_with_material(&depth_material, &shadow_camera, &[]);
+                    }
+                    Ok(())
+                })
+                .unwrap();
+        }
+        self.shadow_texture = Some(shadow_texture);
+        self.shadow_clip_planes = vec2(z_near, z_far);
+    }
+
+    ///
+    /// Returns a reference to the shadow map if it has been generated.
+    ///
+    pub fn shadow_map(&self) -> Option<&DepthTextureCubeMap> {
+        self.shadow_texture.as_ref()
+    }
+}
+
+impl PointLight {
+    ///
+    /// Sets the intensity from the luminous power in lumens, for example as given on the packaging of a light bulb,
+    /// which is emitted equally in all directions. Use this together with physically based [Attenuation::inverse_square] attenuation,
+    /// distances in meters and a physical camera exposure, see [Camera::set_exposure_value].
+    ///
+    pub fn set_luminous_power(&mut self, lumens: f32) {
+        self.intensity = lumens / (4.0 * std::f32::consts::PI);
+    }
+
+    ///
+    /// Returns the luminous power in lumens, see [PointLight::set_luminous_power].
+    ///
+    pub fn luminous_power(&self) -> f32 {
+        self.intensity * 4.0 * std::f32::consts::PI
+    }
 }
 
 impl Light for PointLight {
     fn shader_source(&self, i: u32) -> String {
-        format!(
-        "
-            uniform vec3 color{};
-            uniform vec3 attenuation{};
-            uniform vec3 position{};
-
-            vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
-            {{
-                vec3 light_direction = position{} - position;
-                float distance = length(light_direction);
-                light_direction = light_direction / distance;
-
-                vec3 light_color = attenuate(color{}, attenuation{}, distance);
-                return calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness);
-            }}
-        
-        ", i, i, i, i, i, i, i)
+        if self.shadow_texture.is_some() {
+            format!(
+            "
+                uniform samplerCube shadowMap{};
+                uniform vec2 shadowClipPlanes{};
+                uniform vec4 shadowParameters{};
+
+                uniform vec3 color{};
+                uniform vec4 attenuation{};
+                uniform vec3 position{};
+
+                vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    vec3 light_direction = position{} - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+
+                    vec3 light_color = attenuate(color{}, attenuation{}, distance);
+                    return calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness)
+                        * calculate_point_shadow(normal, shadowMap{}, position{}, shadowClipPlanes{}, position, shadowParameters{});
+                }}
+
+            ", i, i, i, i, i, i, i, i, i, i, i, i, i, i)
+        } else {
+            format!(
+            "
+                uniform vec3 color{};
+                uniform vec4 attenuation{};
+                uniform vec3 position{};
+
+                vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    vec3 light_direction = position{} - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+
+                    vec3 light_color = attenuate(color{}, attenuation{}, distance);
+                    return calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness);
+                }}
+
+            ", i, i, i, i, i, i, i)
+        }
     }
     fn use_uniforms(&self, program: &Program, i: u32) {
+        if let Some(ref tex) = self.shadow_texture {
+            program.use_depth_texture_cube(&format!("shadowMap{}", i), tex);
+            program.use_uniform(&format!("shadowClipPlanes{}", i), self.shadow_clip_planes);
+            program.use_uniform(
+                &format!("shadowParameters{}", i),
+                self.shadow_settings
+                    .parameters(ShadowTechnique::PercentageCloserFiltering),
+            );
+        }
         program.use_uniform(
             &format!("color{}", i),
             self.color.to_linear_srgb().truncate() * self.intensity,
         );
-        program.use_uniform(
-            &format!("attenuation{}", i),
-            vec3(
-                self.attenuation.constant,
-                self.attenuation.linear,
-                self.attenuation.quadratic,
-            ),
-        );
+        program.use_uniform(&format!("attenuation{}", i), self.attenuation.uniform());
         program.use_uniform(&format!("position{}", i), self.position);
     }
 
     fn id(&self) -> u8 {
-        0b1u8 << 7 | 0b100u8
+        if self.shadow_texture.is_some() {
+            0b1u8 << 7 | 0b111u8
+        } else {
+            0b1u8 << 7 | 0b100u8
+        }
+    }
+
+    fn volume(&self) -> Option<LightVolume> {
+        self.attenuation.range.map(|radius| LightVolume::Sphere {
+            center: self.position,
+            radius,
+        })
+    }
+}
+
+impl ShadowCaster for PointLight {
+    fn update_shadow_map(&mut self, texture_size: Option<u32>, geometries: &[&dyn Geometry]) {
+        if let Some(texture_size) = texture_size {
+            self.generate_shadow_map(texture_size, geometries.iter().copied());
+        } else {
+            self.clear_shadow_map();
+        }
+    }
+
+    fn shadow_settings_mut(&mut self) -> &mut ShadowSettings {
+        &mut self.shadow_settings
     }
 }
@@ -14,8 +14,8 @@ vec2 IntegrateBRDF(float NdotV, float roughness)
 
     vec3 N = vec3(0.0, 0.0, 1.0);
     
-    const uint SAMPLE_COUNT = 1024u;
-    for(uint i = 0u; i < SAMPLE_COUNT; ++i)
+    const int SAMPLE_COUNT = 1024;
+    for(int i = 0; i < SAMPLE_COUNT; ++i)
     {
         // generates a sample vector that's biased towards the
         // preferred alignment direction (importance sampling).
@@ -3,6 +3,7 @@
 uniform samplerCube environmentMap;
 uniform vec3 direction;
 uniform vec3 up;
+uniform float edgeFixupScale;
 
 in vec2 uvs;
 
@@ -16,7 +17,8 @@ void main()
     // is the radiance of light coming from -Normal direction, which is what
     // we use in the PBR shader to sample irradiance.
     vec3 right = cross(direction, up);
-    vec3 N = normalize(up * (uvs.y - 0.5) * 2.0 + right * (uvs.x - 0.5) * 2.0 + direction);
+    vec2 uv = (uvs - 0.5) * 2.0 * edgeFixupScale;
+    vec3 N = normalize(up * uv.y + right * uv.x + direction);
 
     vec3 irradiance = vec3(0.0);   
     
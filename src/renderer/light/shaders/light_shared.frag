@@ -137,46 +137,255 @@ vec3 calculate_light(vec3 light_color, vec3 L, vec3 surface_color, vec3 V, vec3
     return (diffuse + specular) * light_color * NdL;
 }
 
-vec3 attenuate(vec3 light_color, vec3 attenuation, float distance)
+vec3 attenuate(vec3 light_color, vec4 attenuation, float distance)
 {
     float att =  attenuation.x +
         attenuation.y * distance +
         attenuation.z * distance * distance;
 
-    return light_color / max(1.0, att);
+    // The range is stored in the w component, where zero means no range
+    float window = 1.0;
+    if (attenuation.w > 0.0) {
+        float r = distance / attenuation.w;
+        window = saturate(1.0 - r * r * r * r);
+        window *= window;
+    }
+    return window * light_color / max(1.0, att);
+}
+
+// Returns the integral of the clamped cosine over the spherical polygon edge from v1 to v2, multiplied by the direction of the edge plane.
+// The directions are normalized and the fitted approximation of theta / sin(theta) is from Heitz et al. 2016.
+vec3 polygon_edge_integral(vec3 v1, vec3 v2)
+{
+    float x = dot(v1, v2);
+    float y = abs(x);
+    float a = 0.8543985 + (0.4965155 + 0.0145206 * y) * y;
+    float b = 3.4175940 + (4.1616724 + y) * y;
+    float v = a / b;
+    float theta_sintheta = x > 0.0 ? v : 0.5 * inversesqrt(max(1.0 - x * x, 1e-7)) - v;
+    return cross(v1, v2) * theta_sintheta;
+}
+
+// Returns the integral of the clamped cosine distribution around the z-axis over the rectangle with the given corners after transforming them by the linear transformation.
+// The corners are relative to the shaded position and the result is zero if the front side of the rectangle is not facing the shaded position.
+float integrate_cosine_lobe(mat3 transformation, vec3 p0, vec3 p1, vec3 p2, vec3 p3)
+{
+    vec3 l0 = normalize(transformation * p0);
+    vec3 l1 = normalize(transformation * p1);
+    vec3 l2 = normalize(transformation * p2);
+    vec3 l3 = normalize(transformation * p3);
+    vec3 form_factor = polygon_edge_integral(l0, l1) + polygon_edge_integral(l1, l2) + polygon_edge_integral(l2, l3) + polygon_edge_integral(l3, l0);
+    return max(form_factor.z, 0.0) / (2.0 * PI);
 }
 
-float is_visible(vec3 lightDirection, vec3 normal, sampler2D shadowMap, vec4 shadow_coord, vec2 offset)
+// Returns an approximation of the light reflected towards the viewer from a rectangular area light with the given center and half extents.
+// The diffuse reflection is the integral of the clamped cosine around the normal, which is exact except that the part of the rectangle below the horizon is not clipped.
+// The specular reflection is not the GGX lobe fitted by linearly transformed cosines (Heitz et al. 2016), which requires lookup tables of the fitted transformations,
+// but a clamped cosine around the reflection direction which is narrowed by the roughness.
+vec3 calculate_area_light_approximation(vec3 light_color, vec3 light_position, vec3 half_width, vec3 half_height, vec3 surface_color, vec3 position, vec3 N, vec3 V, float metallic, float roughness)
+{
+    vec3 p0 = light_position - half_width - half_height - position;
+    vec3 p1 = light_position + half_width - half_height - position;
+    vec3 p2 = light_position + half_width + half_height - position;
+    vec3 p3 = light_position - half_width + half_height - position;
+
+    vec3 tangent = normalize(cross(N, abs(N.y) < 0.99 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0)));
+    mat3 diffuse_transformation = transpose(mat3(tangent, cross(N, tangent), N));
+    float diffuse_integral = integrate_cosine_lobe(diffuse_transformation, p0, p1, p2, p3);
+
+    vec3 R = reflect(-V, N);
+    vec3 reflection_tangent = normalize(cross(R, abs(R.y) < 0.99 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0)));
+    float alpha = max(roughness * roughness, 0.01);
+    mat3 specular_transformation = mat3(1.0 / alpha, 0.0, 0.0, 0.0, 1.0 / alpha, 0.0, 0.0, 0.0, 1.0)
+        * transpose(mat3(reflection_tangent, cross(R, reflection_tangent), R));
+    float specular_integral = dot(N, R) > 0.0 ? integrate_cosine_lobe(specular_transformation, p0, p1, p2, p3) : 0.0;
+
+    float NdV = max(0.001, dot(N, V));
+    vec3 F0 = mix(vec3(0.04), surface_color, metallic);
+    vec3 specular_fresnel = fresnel_schlick_roughness(F0, NdV, roughness);
+    vec3 diffuse = (1.0 - specular_fresnel) * mix(surface_color, vec3(0.0), metallic) * diffuse_integral;
+    vec3 specular = specular_fresnel * specular_integral;
+    return (diffuse + specular) * light_color;
+}
+
+#if __VERSION__ > 100
+// Returns the texel coordinates of the i'th value in a data texture, which is stored row by row from the top.
+ivec2 data_texel(sampler2D data, int i)
+{
+    ivec2 size = textureSize(data, 0);
+    return ivec2(i % size.x, size.y - 1 - i / size.x);
+}
+
+// Returns the light from the point lights in the cluster containing the given position.
+// The lights are stored as three texels per light (position and range, color and attenuation), the grid contains the offset into the list of light indices
+// and the number of lights for each cluster, and the clusters are evenly divided in screen space and logarithmically divided in depth between the near and far planes.
+vec3 calculate_clustered_lighting(sampler2D lights, sampler2D grid, sampler2D indices, mat4 view, mat4 projection, vec3 grid_size, vec2 depth_range,
+    vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness)
+{
+    vec4 view_position = view * vec4(position, 1.0);
+    vec4 clip_position = projection * view_position;
+    vec2 ndc = clip_position.xy / clip_position.w;
+    float depth = max(-view_position.z, depth_range.x);
+    ivec3 cluster = ivec3(clamp(vec3(
+        (0.5 * ndc + 0.5) * grid_size.xy,
+        log(depth / depth_range.x) / log(depth_range.y / depth_range.x) * grid_size.z
+    ), vec3(0.0), grid_size - 1.0));
+    int cluster_index = cluster.x + int(grid_size.x) * (cluster.y + int(grid_size.y) * cluster.z);
+    vec2 offset_and_count = texelFetch(grid, data_texel(grid, cluster_index), 0).xy;
+    int offset = int(offset_and_count.x);
+    int count = int(offset_and_count.y);
+
+    vec3 result = vec3(0.0);
+    for (int k = 0; k < count; k++) {
+        int light_index = int(texelFetch(indices, data_texel(indices, offset + k), 0).x);
+        vec4 position_and_range = texelFetch(lights, data_texel(lights, 3 * light_index), 0);
+        vec3 color = texelFetch(lights, data_texel(lights, 3 * light_index + 1), 0).rgb;
+        vec3 attenuation = texelFetch(lights, data_texel(lights, 3 * light_index + 2), 0).xyz;
+
+        vec3 light_direction = position_and_range.xyz - position;
+        float distance = length(light_direction);
+        if (distance < position_and_range.w) {
+            light_direction = light_direction / distance;
+            vec3 light_color = attenuate(color, vec4(attenuation, position_and_range.w), distance);
+            result += calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness);
+        }
+    }
+    return result;
+}
+#else
+// GLSL ES 1.00 cannot fetch texels, so clustered lighting is not available
+vec3 calculate_clustered_lighting(sampler2D lights, sampler2D grid, sampler2D indices, mat4 view, mat4 projection, vec3 grid_size, vec2 depth_range,
+    vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness)
+{
+    return vec3(0.0);
+}
+#endif
+
+// Returns the visibility of the surface from the light using percentage-closer filtering.
+// The shadow parameters are the radius of the filter kernel in texels, the constant depth bias, the slope scaled depth bias and the normal offset.
+float calculate_shadow(vec3 lightDirection, vec3 normal, sampler2D shadowMap, mat4 shadowMVP, vec3 position, vec4 shadowParameters)
 {
-    vec2 uv = (shadow_coord.xy + offset)/shadow_coord.w;
+    float cos_angle = clamp(dot(normal, lightDirection), 0.0, 1.0);
+    float sin_angle = sqrt(1.0 - cos_angle * cos_angle);
+    vec4 shadow_coord = shadowMVP * vec4(position + normal * shadowParameters.w * sin_angle, 1.);
+    vec2 uv = shadow_coord.xy / shadow_coord.w;
     if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
         return 1.0;
     }
+    float slope = min(sin_angle / max(cos_angle, 0.001), 5.0);
+    float bias = shadowParameters.y + shadowParameters.z * slope;
+    float true_distance = (shadow_coord.z - bias) / shadow_coord.w;
+
+#if __VERSION__ > 100
+    int radius = int(shadowParameters.x);
+    vec2 texel_size = 1.0 / vec2(textureSize(shadowMap, 0));
+    float visibility = 0.0;
+    for (int x = -radius; x <= radius; x++)
+    {
+        for (int y = -radius; y <= radius; y++)
+        {
+            float shadow_cast_distance = texture(shadowMap, uv + vec2(x, y) * texel_size).x;
+            visibility += shadow_cast_distance > 0.999 || shadow_cast_distance > true_distance ? 1.0 : 0.0;
+        }
+    }
+    float size = float(2 * radius + 1);
+    return visibility / (size * size);
+#else
+    // GLSL ES 1.00 cannot query the size of the shadow map, so the shadow map is sampled once without filtering
     float shadow_cast_distance = texture(shadowMap, uv).x;
-    if(shadow_cast_distance > 0.999) {
+    return shadow_cast_distance > 0.999 || shadow_cast_distance > true_distance ? 1.0 : 0.0;
+#endif
+}
+
+// Computes the shadow map coordinates and the biased depth of the surface seen from the light, returns false if the surface is outside the shadow map.
+bool shadow_coordinates(vec3 lightDirection, vec3 normal, mat4 shadowMVP, vec3 position, vec4 shadowParameters, out vec2 uv, out float depth)
+{
+    float cos_angle = clamp(dot(normal, lightDirection), 0.0, 1.0);
+    float sin_angle = sqrt(1.0 - cos_angle * cos_angle);
+    vec4 shadow_coord = shadowMVP * vec4(position + normal * shadowParameters.w * sin_angle, 1.);
+    uv = shadow_coord.xy / shadow_coord.w;
+    if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        return false;
+    }
+    float slope = min(sin_angle / max(cos_angle, 0.001), 5.0);
+    depth = (shadow_coord.z - shadowParameters.y - shadowParameters.z * slope) / shadow_coord.w;
+    return true;
+}
+
+// Returns the visibility of the surface using a shadow map with the depth and squared depth, where the first shadow parameter is the light bleeding reduction.
+float calculate_variance_shadow(vec3 lightDirection, vec3 normal, sampler2D shadowMap, mat4 shadowMVP, vec3 position, vec4 shadowParameters)
+{
+    vec2 uv;
+    float depth;
+    if(!shadow_coordinates(lightDirection, normal, shadowMVP, position, shadowParameters, uv, depth)) {
+        return 1.0;
+    }
+    vec2 moments = texture(shadowMap, uv).xy;
+    if(depth <= moments.x) {
+        return 1.0;
+    }
+    float variance = max(moments.y - moments.x * moments.x, 0.00002);
+    float difference = depth - moments.x;
+    float p_max = variance / (variance + difference * difference);
+    return clamp((p_max - shadowParameters.x) / (1.0 - shadowParameters.x), 0.0, 1.0);
+}
+
+// Returns the visibility of the surface using a shadow map with the exponential of the depth, where the first shadow parameter is the exponent.
+float calculate_exponential_shadow(vec3 lightDirection, vec3 normal, sampler2D shadowMap, mat4 shadowMVP, vec3 position, vec4 shadowParameters)
+{
+    vec2 uv;
+    float depth;
+    if(!shadow_coordinates(lightDirection, normal, shadowMVP, position, shadowParameters, uv, depth)) {
         return 1.0;
     }
-    // Adjust shadow bias based on surface normal and light direction
-    float bias = max(0.05 * (1.0 - dot(normal, lightDirection)), 0.005);
-    float true_distance = (shadow_coord.z - bias)/shadow_coord.w;
-    return shadow_cast_distance > true_distance ? 1.0 : 0.0;
+    float occluder = texture(shadowMap, uv).x;
+    return clamp(occluder * exp(-shadowParameters.x * depth), 0.0, 1.0);
 }
 
-float calculate_shadow(vec3 lightDirection, vec3 normal, sampler2D shadowMap, mat4 shadowMVP, vec3 position)
+// Returns the visibility of the surface from a point light using a cube map with the depth seen from the light in each direction.
+// The clip planes are the near and far planes used when rendering the cube map and the shadow parameters are the same as for calculate_shadow,
+// except that the biases are relative to the distance from the light.
+float calculate_point_shadow(vec3 normal, samplerCube shadowMap, vec3 lightPosition, vec2 clipPlanes, vec3 position, vec4 shadowParameters)
 {
-    vec4 shadow_coord = shadowMVP * vec4(position, 1.);
+    vec3 light_direction = normalize(lightPosition - position);
+    float cos_angle = clamp(dot(normal, light_direction), 0.0, 1.0);
+    float sin_angle = sqrt(1.0 - cos_angle * cos_angle);
+    vec3 direction = position + normal * shadowParameters.w * sin_angle - lightPosition;
+    vec3 abs_direction = abs(direction);
+    // The distance along the axis of the cube map side is the depth seen from the light
+    float depth = max(abs_direction.x, max(abs_direction.y, abs_direction.z));
+    if(depth > clipPlanes.y) {
+        return 1.0;
+    }
+    float slope = min(sin_angle / max(cos_angle, 0.001), 5.0);
+    float true_distance = depth * (1.0 - shadowParameters.y - shadowParameters.z * slope);
+
+    float near = clipPlanes.x;
+    float far = clipPlanes.y;
+#if __VERSION__ > 100
+    vec3 tangent = normalize(cross(direction, abs_direction.y < 0.99 * depth ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0)));
+    vec3 bitangent = normalize(cross(direction, tangent));
+    float texel_size = 2.0 * depth / float(textureSize(shadowMap, 0).x);
+    int radius = int(shadowParameters.x);
     float visibility = 0.0;
-    vec2 poissonDisk[4] = vec2[](
-                                 vec2( -0.94201624, -0.39906216 ),
-                                 vec2( 0.94558609, -0.76890725 ),
-                                 vec2( -0.094184101, -0.92938870 ),
-                                 vec2( 0.34495938, 0.29387760 )
-                                 );
-    for (int i=0;i<4;i++)
+    for (int x = -radius; x <= radius; x++)
     {
-        visibility += is_visible(lightDirection, normal, shadowMap, shadow_coord, poissonDisk[i] * 0.001f);
+        for (int y = -radius; y <= radius; y++)
+        {
+            vec3 sample_direction = direction + (float(x) * tangent + float(y) * bitangent) * texel_size;
+            float shadow_cast_depth = texture(shadowMap, sample_direction).x;
+            float shadow_cast_distance = 2.0 * near * far / (far + near - (2.0 * shadow_cast_depth - 1.0) * (far - near));
+            visibility += shadow_cast_depth > 0.999 || shadow_cast_distance > true_distance ? 1.0 : 0.0;
+        }
     }
-    return visibility * 0.25;
+    float size = float(2 * radius + 1);
+    return visibility / (size * size);
+#else
+    // GLSL ES 1.00 cannot query the size of the shadow map, so the shadow map is sampled once without filtering
+    float shadow_cast_depth = texture(shadowMap, direction).x;
+    float shadow_cast_distance = 2.0 * near * far / (far + near - (2.0 * shadow_cast_depth - 1.0) * (far - near));
+    return shadow_cast_depth > 0.999 || shadow_cast_distance > true_distance ? 1.0 : 0.0;
+#endif
 }
 
 vec3 ImportanceSampleGGX(vec2 Xi, vec3 N, float roughness)
@@ -6,21 +6,23 @@ uniform float roughness;
 uniform float resolution; // resolution of source cubemap (per face)
 uniform vec3 direction;
 uniform vec3 up;
+uniform float edgeFixupScale;
 
 void main()
 {
     vec3 right = cross(direction, up);
-    vec3 N = normalize(up * (uvs.y - 0.5) * 2.0 + right * (uvs.x - 0.5) * 2.0 + direction);
+    vec2 uv = (uvs - 0.5) * 2.0 * edgeFixupScale;
+    vec3 N = normalize(up * uv.y + right * uv.x + direction);
     
     // make the simplyfying assumption that V equals R equals the normal 
     vec3 R = N;
     vec3 V = R;
 
-    const uint SAMPLE_COUNT = 1024u;
+    const int SAMPLE_COUNT = 1024;
     vec3 prefilteredColor = vec3(0.0);
     float totalWeight = 0.0;
     
-    for(uint i = 0u; i < SAMPLE_COUNT; ++i)
+    for(int i = 0; i < SAMPLE_COUNT; ++i)
     {
         // generates a sample vector that's biased towards the preferred alignment direction (importance sampling).
         vec2 Xi = Hammersley(i, SAMPLE_COUNT);
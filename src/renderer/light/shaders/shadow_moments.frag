@@ -0,0 +1,17 @@
+#ifdef EXPONENTIAL
+uniform float exponent;
+#endif
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    float depth = gl_FragCoord.z;
+#ifdef EXPONENTIAL
+    outColor = vec4(exp(exponent * depth), 0.0, 0.0, 1.0);
+#else
+    float dx = dFdx(depth);
+    float dy = dFdy(depth);
+    outColor = vec4(depth, depth * depth + 0.25 * (dx * dx + dy * dy), 0.0, 1.0);
+#endif
+}
@@ -3,14 +3,20 @@ use crate::renderer::light::*;
 use crate::renderer::*;
 
 ///
-/// A light which shines from the given position and in the given direction.
-/// The light will cast shadows if you [generate a shadow map](SpotLight::generate_shadow_map).
+/// A light which shines from the given position and in the given direction within a cone, for example a flashlight or a stage light.
+/// The intensity is constant inside the [inner cutoff angle](SpotLight::inner_cutoff) and falls off smoothly to zero at the [cutoff angle](SpotLight::cutoff).
+/// The light will cast shadows if you [generate a shadow map](SpotLight::generate_shadow_map) and the color can be modulated by a projected texture, see [SpotLight::gobo].
 ///
 pub struct SpotLight {
     context: Context,
     shadow_texture: Option<DepthTexture2D>,
+    shadow_moments: Option<Texture2D>,
+    shadow_technique: ShadowTechnique,
     shadow_matrix: Mat4,
+    /// Controls the filtering and biasing when sampling the shadow map, see [ShadowSettings].
+    pub shadow_settings: ShadowSettings,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
+    /// When using physically based [Attenuation::inverse_square] attenuation, this is the luminous intensity in candela, see also [SpotLight::set_luminous_power].
     pub intensity: f32,
     /// The base color of the light.
     pub color: Srgba,
@@ -18,8 +24,14 @@ pub struct SpotLight {
     pub position: Vec3,
     /// The direction the light shines.
     pub direction: Vec3,
-    /// The cutoff angle for the light.
+    /// The cutoff angle for the light, ie. the angle between the direction and the edge of the cone of light.
     pub cutoff: Radians,
+    /// The angle between the direction and the edge of the inner cone where the light has full intensity. Between this angle and the [cutoff](SpotLight::cutoff) angle, the intensity falls off smoothly.
+    /// The default is three quarters of the cutoff angle and the angle is clamped to be smaller than the cutoff angle when rendering.
+    pub inner_cutoff: Radians,
+    /// A texture, often called a gobo or cookie, which is projected from the light and multiplied onto the color of the light, for example to simulate a window frame or the pattern of a stage light.
+    /// The texture covers the cone of light defined by the [cutoff](SpotLight::cutoff) angle and is oriented with the up direction used for the shadow map.
+    pub gobo: Option<Texture2DRef>,
     /// The [Attenuation] of the light.
     pub attenuation: Attenuation,
 }
@@ -35,16 +47,22 @@ impl SpotLight {
         cutoff: impl Into<Radians>,
         attenuation: Attenuation,
     ) -> SpotLight {
+        let cutoff = cutoff.into();
         SpotLight {
             context: context.clone(),
             shadow_texture: None,
+            shadow_moments: None,
+            shadow_technique: ShadowTechnique::PercentageCloserFiltering,
             intensity,
             color,
             position: *position,
             direction: *direction,
-            cutoff: cutoff.into(),
+            cutoff,
+            inner_cutoff: cutoff * 0.75,
+            gobo: None,
             attenuation,
             shadow_matrix: Mat4::identity(),
+            shadow_settings: ShadowSettings::default(),
         }
     }
 
@@ -54,6 +72,7 @@ impl SpotLight {
     ///
     pub fn clear_shadow_map(&mut self) {
         self.shadow_texture = None;
+        self.shadow_moments = None;
         self.shadow_matrix = Mat4::identity();
     }
 
@@ -94,39 +113,15 @@ impl SpotLight {
         );
         self.shadow_matrix = shadow_matrix(&shadow_camera);
 
-        let mut shadow_texture = DepthTexture2D::new::<f32>(
+        let (shadow_texture, shadow_moments) = generate_shadow_textures(
             &self.context,
             texture_size,
-            texture_size,
-            Wrapping::ClampToEdge,
-            Wrapping::ClampToEdge,
+            &shadow_camera,
+            geometries,
+            self.shadow_settings.technique,
         );
-        let depth_material = DepthMaterial {
-            render_states: RenderStates {
-                write_mask: WriteMask::DEPTH,
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-        shadow_texture
-            .as_depth_target()
-            .clear(ClearState::default())
-            .write::<RendererError>(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
-                {
-                    render_with_material(
-                        &self.context,
-                        &shadow_camera,
-                        &geometry,
-                        &depth_material,
-                        &[],
-                    );
-                }
-                Ok(())
-            })
-            .unwrap();
+        self.shadow_moments = shadow_moments;
+        self.shadow_technique = self.shadow_settings.technique;
         self.shadow_texture = Some(shadow_texture);
     }
 
@@ -136,97 +131,198 @@ impl SpotLight {
     pub fn shadow_map(&self) -> Option<&DepthTexture2D> {
         self.shadow_texture.as_ref()
     }
-}
 
-impl Light for SpotLight {
-    fn shader_source(&self, i: u32) -> String {
-        if self.shadow_texture.is_some() {
-            format!(
-                "
-                    uniform sampler2D shadowMap{};
-                    uniform mat4 shadowMVP{};
-
-                    uniform vec3 color{};
-                    uniform vec3 attenuation{};
-                    uniform vec3 position{};
-                    uniform float cutoff{};
-                    uniform vec3 direction{};
-                    vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
-                    {{
-                        vec3 light_direction = position{} - position;
-                        float distance = length(light_direction);
-                        light_direction = light_direction / distance;
+    ///
+    /// Returns a reference to the blurred moments texture if the shadow map has been generated using [ShadowTechnique::Variance] or [ShadowTechnique::Exponential].
+    ///
+    pub fn shadow_moments_map(&self) -> Option<&Texture2D> {
+        self.shadow_moments.as_ref()
+    }
+
+    // The matrix which projects a position onto the uv coordinates of the gobo texture, such that the texture covers the cone of light.
+    fn gobo_matrix(&self) -> Mat4 {
+        let camera = Camera::new_perspective(
+            Viewport::new_at_origo(1, 1),
+            self.position,
+            self.position + self.direction,
+            compute_up_direction(self.direction),
+            radians((2.0 * self.cutoff.0).min(0.99 * std::f32::consts::PI)),
+            0.01,
+            1.0,
+        );
+        shadow_matrix(&camera)
+    }
 
-                        float angle = acos(dot(-light_direction, normalize(direction{})));
-                        float cutoff = cutoff{};
+    // The inner cutoff angle clamped to be smaller than the cutoff angle, since the fall off is undefined otherwise.
+    pub(super) fn clamped_inner_cutoff(&self) -> Radians {
+        radians(self.inner_cutoff.0.min(0.99 * self.cutoff.0).max(0.0))
+    }
+}
 
-                        vec3 result = vec3(0.0);
-                        if (angle < cutoff) {{
-                            vec3 light_color = attenuate(color{}, attenuation{}, distance);
-                            result = calculate_light(light_color, light_direction, surface_color, view_direction, normal,
-                                metallic, roughness) * (1.0 - smoothstep(0.75 * cutoff, cutoff, angle));
-                            result *= calculate_shadow(light_direction, normal, shadowMap{}, shadowMVP{}, position);
-                        }}
-                        return result;
-                    }}
+impl SpotLight {
+    ///
+    /// Sets the intensity from the luminous power in lumens as if the light was emitted in all directions, like a point light with a reflector,
+    /// so that the brightness inside the cone does not change when the cutoff angle is changed.
+    /// Use this together with physically based [Attenuation::inverse_square] attenuation,
+    /// distances in meters and a physical camera exposure, see [Camera::set_exposure_value].
+    ///
+    pub fn set_luminous_power(&mut self, lumens: f32) {
+        self.intensity = lumens / (4.0 * std::f32::consts::PI);
+    }
 
-                ", i, i, i, i, i, i, i, i, i, i, i, i, i, i, i)
+    ///
+    /// Returns the luminous power in lumens, see [SpotLight::set_luminous_power].
+    ///
+    pub fn luminous_power(&self) -> f32 {
+        self.intensity * 4.0 * std::f32::consts::PI
+    }
+}
+
+impl Light for SpotLight {
+    fn shader_source(&self, i: u32) -> String {
+        let (shadow_uniforms, shadow) = if self.shadow_texture.is_some() {
+            (
+                format!(
+                    "
+                    uniform sampler2D shadowMap{i};
+                    uniform mat4 shadowMVP{i};
+                    uniform vec4 shadowParameters{i};
+                    "
+                ),
+                format!(
+                    "result *= {}(light_direction, normal, shadowMap{i}, shadowMVP{i}, position, shadowParameters{i});",
+                    shadow_function(self.shadow_technique)
+                ),
+            )
         } else {
-            format!(
-                "
-                    uniform vec3 color{};
-                    uniform vec3 attenuation{};
-                    uniform vec3 position{};
-                    uniform float cutoff{};
-                    uniform vec3 direction{};
-                    vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+            (String::new(), String::new())
+        };
+        let (gobo_uniforms, gobo) = if self.gobo.is_some() {
+            (
+                format!(
+                    "
+                    uniform sampler2D goboTexture{i};
+                    uniform mat3 goboTransformation{i};
+                    uniform mat4 goboMVP{i};
+                    "
+                ),
+                format!(
+                    "
+                            vec4 gobo_coord = goboMVP{i} * vec4(position, 1.0);
+                            vec2 gobo_uv = (goboTransformation{i} * vec3(gobo_coord.xy / gobo_coord.w, 1.0)).xy;
+                            light_color *= texture(goboTexture{i}, gobo_uv).rgb;
+                    "
+                ),
+            )
+        } else {
+            (String::new(), String::new())
+        };
+        format!(
+            "
+                    {shadow_uniforms}
+                    {gobo_uniforms}
+                    uniform vec3 color{i};
+                    uniform vec4 attenuation{i};
+                    uniform vec3 position{i};
+                    uniform float cutoff{i};
+                    uniform float innerCutoff{i};
+                    uniform vec3 direction{i};
+                    vec3 calculate_lighting{i}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
                     {{
-                        vec3 light_direction = position{} - position;
+                        vec3 light_direction = position{i} - position;
                         float distance = length(light_direction);
                         light_direction = light_direction / distance;
 
-                        float angle = acos(dot(-light_direction, normalize(direction{})));
-                        float cutoff = cutoff{};
+                        float angle = acos(dot(-light_direction, normalize(direction{i})));
+                        float cutoff = cutoff{i};
 
                         vec3 result = vec3(0.0);
                         if (angle < cutoff) {{
-                            vec3 light_color = attenuate(color{}, attenuation{}, distance);
+                            vec3 light_color = attenuate(color{i}, attenuation{i}, distance);
+                            {gobo}
                             result = calculate_light(light_color, light_direction, surface_color, view_direction, normal,
-                                metallic, roughness) * (1.0 - smoothstep(0.75 * cutoff, cutoff, angle));
+                                metallic, roughness) * (1.0 - smoothstep(innerCutoff{i}, cutoff, angle));
+                            {shadow}
                         }}
                         return result;
                     }}
 
-                ", i, i, i, i, i, i, i, i, i, i, i)
-        }
+                "
+        )
     }
+
     fn use_uniforms(&self, program: &Program, i: u32) {
         if let Some(ref tex) = self.shadow_texture {
-            program.use_depth_texture(&format!("shadowMap{}", i), tex);
+            if let Some(ref moments) = self.shadow_moments {
+                program.use_texture(&format!("shadowMap{}", i), moments);
+            } else {
+                program.use_depth_texture(&format!("shadowMap{}", i), tex);
+            }
             program.use_uniform(&format!("shadowMVP{}", i), self.shadow_matrix);
+            program.use_uniform(
+                &format!("shadowParameters{}", i),
+                self.shadow_settings.parameters(self.shadow_technique),
+            );
         }
         program.use_uniform(
             &format!("color{}", i),
             self.color.to_linear_srgb().truncate() * self.intensity,
         );
-        program.use_uniform(
-            &format!("attenuation{}", i),
-            vec3(
-                self.attenuation.constant,
-                self.attenuation.linear,
-                self.attenuation.quadratic,
-            ),
-        );
+        program.use_uniform(&format!("attenuation{}", i), self.attenuation.uniform());
         program.use_uniform(&format!("position{}", i), self.position);
         program.use_uniform(&format!("direction{}", i), self.direction.normalize());
         program.use_uniform(&format!("cutoff{}", i), self.cutoff.0);
+        program.use_uniform(&format!("innerCutoff{}", i), self.clamped_inner_cutoff().0);
+        if let Some(ref gobo) = self.gobo {
+            program.use_texture(&format!("goboTexture{}", i), &gobo.texture);
+            program.use_uniform(&format!("goboTransformation{}", i), gobo.transformation);
+            program.use_uniform(&format!("goboMVP{}", i), self.gobo_matrix());
+        }
     }
 
     fn id(&self) -> u8 {
-        if self.shadow_texture.is_some() {
-            0b1u8 << 7 | 0b101u8
+        let gobo = if self.gobo.is_some() { 0b10000u8 } else { 0u8 };
+        gobo | if self.shadow_texture.is_some() {
+            match self.shadow_technique {
+                ShadowTechnique::PercentageCloserFiltering => 0b1u8 << 7 | 0b101u8,
+                ShadowTechnique::Variance { .. } => 0b1u8 << 7 | 0b1010u8,
+                ShadowTechnique::Exponential { .. } => 0b1u8 << 7 | 0b1011u8,
+            }
         } else {
             0b1u8 << 7 | 0b110u8
         }
     }
+
+    fn volume(&self) -> Option<LightVolume> {
+        self.attenuation.range.map(|range| {
+            // A wide cone is larger than the sphere around the light
+            if self.cutoff < Radians::from(degrees(60.0)) {
+                LightVolume::Cone {
+                    apex: self.position,
+                    direction: self.direction,
+                    angle: self.cutoff,
+                    length: range,
+                }
+            } else {
+                LightVolume::Sphere {
+                    center: self.position,
+                    radius: range,
+                }
+            }
+        })
+    }
+}
+
+impl ShadowCaster for SpotLight {
+    fn update_shadow_map(&mut self, texture_size: Option<u32>, geometries: &[&dyn Geometry]) {
+        if let Some(texture_size) = texture_size {
+            self.generate_shadow_map(texture_size, geometries.iter().copied());
+        } else {
+            self.clear_shadow_map();
+        }
+    }
+
+    fn shadow_settings_mut(&mut self) -> &mut ShadowSettings {
+        &mut self.shadow_settings
+    }
 }
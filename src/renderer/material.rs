@@ -25,6 +25,9 @@ macro_rules! impl_material_body {
         fn id(&self) -> u16 {
             self.$inner().id()
         }
+        fn shader_defines(&self) -> ShaderDefines {
+            self.$inner().shader_defines()
+        }
     };
 }
 
@@ -54,6 +57,18 @@ mod position_material;
 #[doc(inline)]
 pub use position_material::*;
 
+mod id_material;
+#[doc(inline)]
+pub use id_material::*;
+
+mod texture_array_material;
+#[doc(inline)]
+pub use texture_array_material::*;
+
+mod physical_texture_array_material;
+#[doc(inline)]
+pub use physical_texture_array_material::*;
+
 mod uv_material;
 #[doc(inline)]
 pub use uv_material::*;
@@ -74,6 +89,12 @@ mod isosurface_material;
 #[doc(inline)]
 pub use isosurface_material::*;
 
+mod color_map_luts;
+
+mod color_map_material;
+#[doc(inline)]
+pub use color_map_material::*;
+
 use std::{ops::Deref, sync::Arc};
 
 ///
@@ -128,6 +149,75 @@ impl std::convert::From<Arc<Texture2D>> for Texture2DRef {
     }
 }
 
+///
+/// A 2D texture which is streamed to the GPU over several frames using an [UploadQueue], so that uploading a large texture does not freeze the application.
+/// Until the upload is complete, a placeholder texture is used instead.
+///
+/// Call [UploadQueue::update] each frame and use [StreamingTexture::texture] to get the texture to render with,
+/// for example when assigning the albedo texture of a material.
+///
+pub struct StreamingTexture {
+    upload: Upload<Texture2D>,
+    placeholder: Texture2DRef,
+}
+
+impl StreamingTexture {
+    ///
+    /// Queues the upload of the given [CpuTexture] on the given upload queue.
+    /// If no placeholder is given, a white texture of size 1x1 is used until the upload is complete.
+    ///
+    pub fn new(
+        context: &Context,
+        upload_queue: &mut UploadQueue,
+        cpu_texture: &CpuTexture,
+        placeholder: Option<Texture2DRef>,
+    ) -> Self {
+        let placeholder = placeholder.unwrap_or_else(|| {
+            let mut texture = Texture2D::new_empty::<[u8; 4]>(
+                context,
+                1,
+                1,
+                Interpolation::Nearest,
+                Interpolation::Nearest,
+                None,
+                Wrapping::Repeat,
+                Wrapping::Repeat,
+            );
+            texture.fill(&[[255u8, 255, 255, 255]]);
+            Texture2DRef::from_texture(texture)
+        });
+        Self {
+            upload: upload_queue.upload_texture(cpu_texture),
+            placeholder,
+        }
+    }
+
+    ///
+    /// Returns whether or not the upload is complete, in which case [StreamingTexture::texture] returns the uploaded texture.
+    ///
+    pub fn is_complete(&self) -> bool {
+        self.upload.is_complete()
+    }
+
+    ///
+    /// Returns the fraction of the texture data that has been uploaded, a value between 0 and 1.
+    ///
+    pub fn progress(&self) -> f32 {
+        self.upload.progress()
+    }
+
+    ///
+    /// Returns the uploaded texture if the upload is complete and otherwise the placeholder texture.
+    ///
+    pub fn texture(&self) -> Texture2DRef {
+        if self.upload.is_complete() {
+            self.upload.resource().clone().into()
+        } else {
+            self.placeholder.clone()
+        }
+    }
+}
+
 ///
 /// Defines the material type which is needed to render the objects in the correct order.
 /// For example, transparent objects need to be rendered back to front, whereas opaque objects need to be rendered front to back.
@@ -138,6 +228,10 @@ pub enum MaterialType {
     Opaque,
     /// Forward transparent
     Transparent,
+    /// Forward transparent rendered using weighted blended order-independent transparency, which does not require the objects to be sorted and handles intersecting geometry.
+    /// The material must write the output of weighted blended order-independent transparency and use [Blend::WEIGHTED_BLENDED_ACCUMULATION],
+    /// see for example [PhysicalMaterial::order_independent_transparency].
+    OrderIndependentTransparent,
     /// Deferred opaque
     Deferred,
 }
@@ -184,6 +278,9 @@ impl FragmentAttributes {
 /// Alternatively, a geometry and a material can be combined in a [Gm],
 /// thereby creating an [Object] which can be used in a render call, for example [RenderTarget::render].
 ///
+/// A material only provides the fragment shader, so any deformation of the vertices must be implemented by the geometry, for example [Wind],
+/// otherwise the deformation is not applied when the geometry is rendered with another material, for example into a shadow map.
+///
 pub trait Material {
     ///
     /// Returns the fragment shader source for this material.
@@ -218,6 +315,15 @@ pub trait Material {
     /// Returns the type of material.
     ///
     fn material_type(&self) -> MaterialType;
+
+    ///
+    /// Returns the [ShaderDefines] which are prepended to the shader source when rendering with this material.
+    /// Each combination of defines results in a separately cached program, so the defines can differ between objects
+    /// without changing [Material::id]. See [WithDefines] for adding defines to an existing material.
+    ///
+    fn shader_defines(&self) -> ShaderDefines {
+        ShaderDefines::default()
+    }
 }
 
 ///
@@ -283,6 +389,9 @@ impl<T: Material> Material for std::sync::RwLock<T> {
     fn id(&self) -> u16 {
         self.read().unwrap().id()
     }
+    fn shader_defines(&self) -> ShaderDefines {
+        self.read().unwrap().shader_defines()
+    }
 }
 
 fn is_transparent(cpu_material: &CpuMaterial) -> bool {
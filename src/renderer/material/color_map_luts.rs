@@ -0,0 +1,1039 @@
+//
+// The 256 colors of each of the built-in color maps in sRGB, evenly spaced from the value 0 to the value 1.
+//
+
+pub(super) const VIRIDIS: [[u8; 3]; 256] = [
+    [68, 1, 84],
+    [68, 2, 85],
+    [68, 4, 87],
+    [68, 5, 88],
+    [68, 7, 90],
+    [69, 8, 91],
+    [69, 9, 92],
+    [69, 11, 94],
+    [69, 12, 95],
+    [69, 14, 96],
+    [69, 15, 98],
+    [69, 16, 99],
+    [69, 18, 100],
+    [69, 19, 102],
+    [69, 21, 103],
+    [69, 22, 104],
+    [69, 23, 105],
+    [69, 25, 107],
+    [69, 26, 108],
+    [70, 28, 109],
+    [70, 29, 110],
+    [70, 30, 111],
+    [69, 32, 112],
+    [69, 33, 114],
+    [69, 34, 115],
+    [69, 36, 116],
+    [69, 37, 117],
+    [69, 38, 118],
+    [69, 40, 119],
+    [69, 41, 120],
+    [69, 42, 121],
+    [69, 43, 121],
+    [69, 45, 122],
+    [69, 46, 123],
+    [69, 47, 124],
+    [68, 49, 125],
+    [68, 50, 126],
+    [68, 51, 126],
+    [68, 52, 127],
+    [68, 54, 128],
+    [67, 55, 129],
+    [67, 56, 129],
+    [67, 57, 130],
+    [67, 58, 131],
+    [67, 60, 131],
+    [66, 61, 132],
+    [66, 62, 132],
+    [66, 63, 133],
+    [65, 64, 133],
+    [65, 66, 134],
+    [65, 67, 134],
+    [64, 68, 135],
+    [64, 69, 135],
+    [64, 70, 136],
+    [63, 71, 136],
+    [63, 72, 137],
+    [63, 74, 137],
+    [62, 75, 137],
+    [62, 76, 138],
+    [61, 77, 138],
+    [61, 78, 138],
+    [61, 79, 139],
+    [60, 80, 139],
+    [60, 81, 139],
+    [59, 82, 139],
+    [59, 83, 140],
+    [58, 84, 140],
+    [58, 85, 140],
+    [57, 87, 140],
+    [57, 88, 140],
+    [56, 89, 141],
+    [56, 90, 141],
+    [55, 91, 141],
+    [55, 92, 141],
+    [54, 93, 141],
+    [54, 94, 141],
+    [53, 95, 141],
+    [53, 96, 141],
+    [52, 97, 142],
+    [52, 98, 142],
+    [51, 99, 142],
+    [50, 100, 142],
+    [50, 101, 142],
+    [49, 102, 142],
+    [49, 103, 142],
+    [48, 104, 142],
+    [48, 105, 142],
+    [47, 106, 142],
+    [47, 107, 142],
+    [46, 108, 142],
+    [45, 109, 142],
+    [45, 110, 142],
+    [44, 111, 142],
+    [44, 112, 142],
+    [43, 112, 142],
+    [43, 113, 142],
+    [42, 114, 142],
+    [42, 115, 142],
+    [41, 116, 142],
+    [41, 117, 142],
+    [40, 118, 142],
+    [40, 119, 142],
+    [39, 120, 142],
+    [39, 121, 142],
+    [38, 122, 142],
+    [38, 123, 142],
+    [37, 124, 142],
+    [37, 125, 142],
+    [36, 126, 142],
+    [36, 127, 141],
+    [35, 128, 141],
+    [35, 129, 141],
+    [35, 130, 141],
+    [34, 131, 141],
+    [34, 131, 141],
+    [34, 132, 141],
+    [33, 133, 141],
+    [33, 134, 141],
+    [33, 135, 141],
+    [32, 136, 141],
+    [32, 137, 141],
+    [32, 138, 141],
+    [32, 139, 140],
+    [31, 140, 140],
+    [31, 141, 140],
+    [31, 142, 140],
+    [31, 143, 140],
+    [31, 144, 140],
+    [31, 145, 140],
+    [31, 146, 139],
+    [31, 147, 139],
+    [31, 147, 139],
+    [31, 148, 139],
+    [31, 149, 139],
+    [31, 150, 139],
+    [31, 151, 138],
+    [31, 152, 138],
+    [31, 153, 138],
+    [31, 154, 138],
+    [31, 155, 137],
+    [31, 156, 137],
+    [32, 157, 137],
+    [32, 158, 137],
+    [32, 159, 136],
+    [33, 160, 136],
+    [33, 160, 136],
+    [33, 161, 135],
+    [34, 162, 135],
+    [34, 163, 135],
+    [35, 164, 134],
+    [35, 165, 134],
+    [36, 166, 134],
+    [37, 167, 133],
+    [37, 168, 133],
+    [38, 169, 132],
+    [39, 170, 132],
+    [39, 171, 131],
+    [40, 171, 131],
+    [41, 172, 130],
+    [42, 173, 130],
+    [43, 174, 129],
+    [44, 175, 128],
+    [45, 176, 128],
+    [46, 177, 127],
+    [47, 178, 127],
+    [48, 179, 126],
+    [49, 179, 125],
+    [50, 180, 124],
+    [51, 181, 124],
+    [52, 182, 123],
+    [54, 183, 122],
+    [55, 184, 121],
+    [56, 185, 120],
+    [58, 186, 120],
+    [59, 186, 119],
+    [61, 187, 118],
+    [62, 188, 117],
+    [64, 189, 116],
+    [65, 190, 115],
+    [67, 191, 114],
+    [69, 191, 113],
+    [71, 192, 112],
+    [72, 193, 111],
+    [74, 194, 109],
+    [76, 195, 108],
+    [78, 195, 107],
+    [80, 196, 106],
+    [82, 197, 105],
+    [84, 198, 103],
+    [86, 198, 102],
+    [88, 199, 101],
+    [90, 200, 100],
+    [92, 201, 98],
+    [94, 201, 97],
+    [96, 202, 96],
+    [99, 203, 94],
+    [101, 203, 93],
+    [103, 204, 91],
+    [106, 205, 90],
+    [108, 206, 88],
+    [110, 206, 87],
+    [113, 207, 85],
+    [115, 208, 84],
+    [118, 208, 82],
+    [120, 209, 81],
+    [123, 209, 79],
+    [125, 210, 77],
+    [128, 211, 76],
+    [131, 211, 74],
+    [133, 212, 73],
+    [136, 213, 71],
+    [139, 213, 69],
+    [141, 214, 68],
+    [144, 214, 66],
+    [147, 215, 65],
+    [150, 215, 63],
+    [152, 216, 61],
+    [155, 216, 60],
+    [158, 217, 58],
+    [161, 217, 57],
+    [164, 218, 55],
+    [167, 218, 54],
+    [169, 219, 52],
+    [172, 219, 51],
+    [175, 220, 49],
+    [178, 220, 48],
+    [181, 221, 46],
+    [184, 221, 45],
+    [187, 222, 43],
+    [189, 222, 42],
+    [192, 222, 41],
+    [195, 223, 40],
+    [198, 223, 39],
+    [201, 224, 37],
+    [203, 224, 36],
+    [206, 224, 35],
+    [209, 225, 35],
+    [212, 225, 34],
+    [214, 226, 33],
+    [217, 226, 32],
+    [220, 226, 32],
+    [222, 227, 31],
+    [225, 227, 31],
+    [227, 227, 31],
+    [230, 228, 30],
+    [232, 228, 30],
+    [234, 228, 30],
+    [237, 229, 30],
+    [239, 229, 31],
+    [241, 229, 31],
+    [243, 229, 32],
+    [245, 230, 32],
+    [247, 230, 33],
+    [249, 230, 34],
+    [251, 231, 36],
+    [253, 231, 37],
+];
+
+pub(super) const PLASMA: [[u8; 3]; 256] = [
+    [13, 8, 135],
+    [15, 8, 136],
+    [17, 8, 137],
+    [19, 8, 137],
+    [22, 8, 138],
+    [24, 8, 139],
+    [26, 8, 140],
+    [28, 8, 141],
+    [30, 8, 142],
+    [32, 8, 143],
+    [34, 8, 144],
+    [36, 8, 145],
+    [38, 7, 145],
+    [40, 7, 146],
+    [42, 7, 147],
+    [44, 7, 148],
+    [46, 6, 149],
+    [48, 6, 150],
+    [50, 6, 151],
+    [51, 6, 152],
+    [53, 5, 152],
+    [55, 5, 153],
+    [57, 5, 154],
+    [59, 4, 155],
+    [61, 4, 156],
+    [62, 4, 157],
+    [64, 3, 157],
+    [66, 3, 158],
+    [68, 3, 159],
+    [70, 3, 159],
+    [71, 2, 160],
+    [73, 2, 161],
+    [75, 2, 161],
+    [77, 2, 162],
+    [78, 2, 163],
+    [80, 1, 163],
+    [82, 1, 164],
+    [83, 1, 164],
+    [85, 1, 165],
+    [87, 1, 165],
+    [88, 1, 166],
+    [90, 1, 166],
+    [92, 1, 166],
+    [93, 1, 167],
+    [95, 1, 167],
+    [97, 1, 167],
+    [98, 1, 168],
+    [100, 1, 168],
+    [101, 1, 168],
+    [103, 1, 168],
+    [105, 1, 168],
+    [106, 2, 169],
+    [108, 2, 169],
+    [109, 2, 169],
+    [111, 2, 169],
+    [112, 3, 169],
+    [114, 3, 169],
+    [115, 3, 169],
+    [117, 4, 169],
+    [118, 4, 169],
+    [120, 5, 168],
+    [121, 5, 168],
+    [123, 5, 168],
+    [124, 6, 168],
+    [126, 6, 168],
+    [127, 7, 168],
+    [129, 8, 167],
+    [130, 8, 167],
+    [132, 9, 167],
+    [133, 9, 166],
+    [135, 10, 166],
+    [136, 11, 166],
+    [137, 12, 165],
+    [139, 12, 165],
+    [140, 13, 164],
+    [142, 14, 164],
+    [143, 15, 163],
+    [144, 15, 163],
+    [146, 16, 162],
+    [147, 17, 162],
+    [149, 18, 161],
+    [150, 19, 161],
+    [151, 20, 160],
+    [153, 21, 160],
+    [154, 22, 159],
+    [155, 23, 158],
+    [157, 24, 158],
+    [158, 25, 157],
+    [159, 26, 156],
+    [161, 27, 156],
+    [162, 28, 155],
+    [163, 29, 154],
+    [164, 30, 153],
+    [166, 31, 153],
+    [167, 32, 152],
+    [168, 33, 151],
+    [169, 34, 150],
+    [171, 35, 149],
+    [172, 37, 149],
+    [173, 38, 148],
+    [174, 39, 147],
+    [176, 40, 146],
+    [177, 41, 145],
+    [178, 42, 144],
+    [179, 43, 144],
+    [180, 45, 143],
+    [181, 46, 142],
+    [183, 47, 141],
+    [184, 48, 140],
+    [185, 49, 139],
+    [186, 51, 138],
+    [187, 52, 137],
+    [188, 53, 136],
+    [189, 54, 135],
+    [191, 56, 135],
+    [192, 57, 134],
+    [193, 58, 133],
+    [194, 59, 132],
+    [195, 60, 131],
+    [196, 62, 130],
+    [197, 63, 129],
+    [198, 64, 128],
+    [199, 65, 127],
+    [200, 67, 126],
+    [201, 68, 125],
+    [202, 69, 124],
+    [203, 70, 123],
+    [204, 71, 122],
+    [205, 73, 121],
+    [206, 74, 120],
+    [207, 75, 120],
+    [208, 76, 119],
+    [209, 78, 118],
+    [210, 79, 117],
+    [211, 80, 116],
+    [212, 81, 115],
+    [213, 82, 114],
+    [214, 84, 113],
+    [215, 85, 112],
+    [216, 86, 111],
+    [216, 87, 110],
+    [217, 89, 109],
+    [218, 90, 108],
+    [219, 91, 107],
+    [220, 92, 106],
+    [221, 93, 105],
+    [222, 94, 105],
+    [223, 96, 104],
+    [223, 97, 103],
+    [224, 98, 102],
+    [225, 99, 101],
+    [226, 100, 100],
+    [227, 102, 99],
+    [227, 103, 98],
+    [228, 104, 97],
+    [229, 105, 96],
+    [230, 106, 95],
+    [230, 108, 94],
+    [231, 109, 94],
+    [232, 110, 93],
+    [233, 111, 92],
+    [233, 112, 91],
+    [234, 113, 90],
+    [235, 115, 89],
+    [235, 116, 88],
+    [236, 117, 87],
+    [237, 118, 86],
+    [237, 119, 85],
+    [238, 121, 84],
+    [239, 122, 84],
+    [239, 123, 83],
+    [240, 124, 82],
+    [240, 125, 81],
+    [241, 127, 80],
+    [242, 128, 79],
+    [242, 129, 78],
+    [243, 130, 77],
+    [243, 131, 76],
+    [244, 133, 76],
+    [244, 134, 75],
+    [245, 135, 74],
+    [246, 136, 73],
+    [246, 138, 72],
+    [247, 139, 71],
+    [247, 140, 70],
+    [248, 142, 69],
+    [248, 143, 68],
+    [248, 144, 68],
+    [249, 146, 67],
+    [249, 147, 66],
+    [250, 148, 65],
+    [250, 150, 64],
+    [251, 151, 63],
+    [251, 152, 62],
+    [251, 154, 61],
+    [252, 155, 61],
+    [252, 156, 60],
+    [252, 158, 59],
+    [253, 159, 58],
+    [253, 161, 57],
+    [253, 162, 56],
+    [254, 164, 55],
+    [254, 165, 54],
+    [254, 166, 54],
+    [254, 168, 53],
+    [255, 169, 52],
+    [255, 171, 51],
+    [255, 173, 50],
+    [255, 174, 49],
+    [255, 176, 48],
+    [255, 177, 48],
+    [255, 179, 47],
+    [255, 180, 46],
+    [255, 182, 45],
+    [255, 183, 44],
+    [255, 185, 44],
+    [255, 187, 43],
+    [255, 188, 42],
+    [255, 190, 41],
+    [255, 192, 41],
+    [255, 193, 40],
+    [255, 195, 39],
+    [255, 197, 38],
+    [255, 198, 38],
+    [255, 200, 37],
+    [255, 202, 36],
+    [255, 203, 36],
+    [255, 205, 35],
+    [255, 207, 35],
+    [255, 209, 34],
+    [255, 210, 33],
+    [255, 212, 33],
+    [255, 214, 32],
+    [254, 215, 32],
+    [254, 217, 32],
+    [254, 219, 31],
+    [253, 220, 31],
+    [253, 222, 30],
+    [252, 224, 30],
+    [252, 225, 30],
+    [252, 227, 30],
+    [251, 229, 30],
+    [250, 230, 29],
+    [250, 232, 29],
+    [249, 233, 29],
+    [249, 235, 29],
+    [248, 237, 29],
+    [247, 238, 30],
+    [246, 240, 30],
+    [246, 241, 30],
+    [245, 242, 30],
+    [244, 244, 31],
+    [243, 245, 31],
+    [242, 247, 32],
+    [241, 248, 32],
+    [240, 249, 33],
+];
+
+pub(super) const TURBO: [[u8; 3]; 256] = [
+    [48, 18, 59],
+    [54, 21, 67],
+    [60, 24, 74],
+    [65, 27, 81],
+    [70, 30, 89],
+    [75, 33, 96],
+    [79, 36, 103],
+    [82, 39, 110],
+    [86, 42, 116],
+    [89, 45, 123],
+    [91, 48, 129],
+    [93, 51, 136],
+    [95, 54, 142],
+    [97, 57, 148],
+    [98, 59, 154],
+    [99, 62, 160],
+    [100, 65, 165],
+    [100, 68, 170],
+    [101, 71, 176],
+    [101, 73, 181],
+    [101, 76, 186],
+    [101, 79, 190],
+    [100, 82, 195],
+    [99, 84, 199],
+    [99, 87, 203],
+    [98, 90, 207],
+    [97, 92, 211],
+    [96, 95, 215],
+    [94, 98, 218],
+    [93, 100, 221],
+    [92, 103, 224],
+    [90, 106, 227],
+    [89, 108, 230],
+    [87, 111, 233],
+    [85, 114, 235],
+    [84, 116, 237],
+    [82, 119, 239],
+    [80, 121, 241],
+    [78, 124, 242],
+    [76, 127, 244],
+    [75, 129, 245],
+    [73, 132, 246],
+    [71, 134, 247],
+    [69, 137, 248],
+    [67, 140, 249],
+    [66, 142, 249],
+    [64, 145, 250],
+    [62, 147, 250],
+    [61, 150, 250],
+    [59, 152, 250],
+    [57, 155, 249],
+    [56, 157, 249],
+    [55, 160, 248],
+    [53, 162, 248],
+    [52, 165, 247],
+    [51, 167, 246],
+    [49, 169, 245],
+    [48, 172, 244],
+    [47, 174, 242],
+    [46, 177, 241],
+    [45, 179, 240],
+    [44, 181, 238],
+    [44, 184, 236],
+    [43, 186, 234],
+    [43, 188, 232],
+    [42, 190, 230],
+    [42, 193, 228],
+    [41, 195, 226],
+    [41, 197, 224],
+    [41, 199, 221],
+    [41, 201, 219],
+    [41, 203, 217],
+    [41, 205, 214],
+    [41, 207, 211],
+    [42, 209, 209],
+    [42, 211, 206],
+    [43, 213, 203],
+    [43, 215, 200],
+    [44, 217, 198],
+    [45, 219, 195],
+    [46, 221, 192],
+    [47, 222, 189],
+    [48, 224, 186],
+    [49, 226, 183],
+    [50, 228, 180],
+    [51, 229, 177],
+    [53, 231, 174],
+    [54, 232, 170],
+    [56, 234, 167],
+    [58, 235, 164],
+    [59, 237, 161],
+    [61, 238, 158],
+    [63, 239, 155],
+    [65, 241, 152],
+    [67, 242, 149],
+    [69, 243, 146],
+    [71, 244, 143],
+    [73, 245, 140],
+    [76, 246, 137],
+    [78, 247, 134],
+    [80, 248, 131],
+    [83, 249, 128],
+    [85, 250, 125],
+    [88, 251, 122],
+    [91, 252, 119],
+    [93, 252, 116],
+    [96, 253, 114],
+    [99, 253, 111],
+    [102, 254, 108],
+    [104, 254, 106],
+    [107, 255, 103],
+    [110, 255, 100],
+    [113, 255, 98],
+    [116, 255, 95],
+    [119, 255, 93],
+    [122, 255, 91],
+    [125, 255, 88],
+    [128, 255, 86],
+    [131, 255, 84],
+    [134, 255, 82],
+    [137, 255, 80],
+    [140, 255, 78],
+    [144, 255, 76],
+    [147, 255, 74],
+    [150, 255, 72],
+    [153, 255, 70],
+    [156, 254, 69],
+    [159, 254, 67],
+    [162, 253, 65],
+    [165, 252, 64],
+    [168, 252, 62],
+    [171, 251, 61],
+    [174, 250, 60],
+    [177, 249, 58],
+    [180, 248, 57],
+    [183, 248, 56],
+    [186, 247, 55],
+    [189, 245, 54],
+    [192, 244, 53],
+    [195, 243, 52],
+    [198, 242, 51],
+    [200, 241, 50],
+    [203, 239, 49],
+    [206, 238, 48],
+    [208, 236, 48],
+    [211, 235, 47],
+    [214, 233, 46],
+    [216, 232, 46],
+    [219, 230, 45],
+    [221, 229, 45],
+    [223, 227, 44],
+    [225, 225, 44],
+    [228, 223, 43],
+    [230, 221, 43],
+    [232, 219, 43],
+    [234, 218, 42],
+    [236, 216, 42],
+    [238, 214, 42],
+    [240, 211, 42],
+    [241, 209, 42],
+    [243, 207, 41],
+    [245, 205, 41],
+    [246, 203, 41],
+    [248, 201, 41],
+    [249, 198, 41],
+    [250, 196, 41],
+    [252, 194, 41],
+    [253, 191, 41],
+    [254, 189, 40],
+    [255, 186, 40],
+    [255, 184, 40],
+    [255, 181, 40],
+    [255, 179, 40],
+    [255, 176, 40],
+    [255, 174, 40],
+    [255, 171, 40],
+    [255, 168, 40],
+    [255, 166, 39],
+    [255, 163, 39],
+    [255, 160, 39],
+    [255, 158, 39],
+    [255, 155, 38],
+    [255, 152, 38],
+    [255, 149, 38],
+    [255, 147, 38],
+    [255, 144, 37],
+    [255, 141, 37],
+    [255, 138, 36],
+    [255, 136, 36],
+    [255, 133, 35],
+    [255, 130, 35],
+    [255, 127, 34],
+    [255, 124, 34],
+    [255, 122, 33],
+    [255, 119, 32],
+    [254, 116, 31],
+    [253, 113, 31],
+    [252, 111, 30],
+    [251, 108, 29],
+    [249, 105, 28],
+    [248, 102, 27],
+    [247, 100, 26],
+    [245, 97, 25],
+    [244, 94, 24],
+    [242, 92, 23],
+    [240, 89, 22],
+    [239, 86, 20],
+    [237, 84, 19],
+    [235, 81, 18],
+    [233, 79, 16],
+    [231, 76, 15],
+    [229, 73, 14],
+    [227, 71, 12],
+    [225, 69, 11],
+    [223, 66, 9],
+    [221, 64, 8],
+    [219, 61, 6],
+    [216, 59, 5],
+    [214, 57, 3],
+    [212, 54, 2],
+    [209, 52, 0],
+    [207, 50, 0],
+    [204, 48, 0],
+    [202, 46, 0],
+    [199, 44, 0],
+    [197, 42, 0],
+    [194, 40, 0],
+    [192, 38, 0],
+    [189, 36, 0],
+    [187, 34, 0],
+    [184, 32, 0],
+    [182, 31, 0],
+    [179, 29, 0],
+    [176, 27, 0],
+    [174, 26, 0],
+    [171, 24, 0],
+    [169, 23, 0],
+    [166, 21, 0],
+    [163, 20, 0],
+    [161, 18, 0],
+    [158, 17, 0],
+    [156, 16, 0],
+    [153, 15, 0],
+    [150, 13, 0],
+    [148, 12, 0],
+    [145, 11, 0],
+    [143, 10, 0],
+    [140, 9, 0],
+    [138, 9, 0],
+    [136, 8, 0],
+    [133, 7, 0],
+    [131, 6, 0],
+    [129, 6, 0],
+    [126, 5, 0],
+    [124, 5, 0],
+    [122, 4, 3],
+];
+
+pub(super) const COOL_WARM: [[u8; 3]; 256] = [
+    [59, 76, 192],
+    [60, 78, 194],
+    [61, 80, 195],
+    [62, 81, 197],
+    [64, 83, 198],
+    [65, 85, 200],
+    [66, 86, 201],
+    [67, 88, 203],
+    [68, 90, 204],
+    [69, 92, 206],
+    [71, 93, 207],
+    [72, 95, 209],
+    [73, 97, 210],
+    [74, 99, 211],
+    [75, 100, 213],
+    [77, 102, 214],
+    [78, 104, 215],
+    [79, 105, 217],
+    [80, 107, 218],
+    [82, 109, 219],
+    [83, 110, 221],
+    [84, 112, 222],
+    [85, 114, 223],
+    [87, 115, 224],
+    [88, 117, 225],
+    [89, 119, 226],
+    [90, 120, 228],
+    [92, 122, 229],
+    [93, 124, 230],
+    [94, 125, 231],
+    [95, 127, 232],
+    [97, 128, 233],
+    [98, 130, 234],
+    [99, 132, 235],
+    [101, 133, 236],
+    [102, 135, 237],
+    [103, 136, 238],
+    [105, 138, 239],
+    [106, 139, 239],
+    [107, 141, 240],
+    [109, 142, 241],
+    [110, 144, 242],
+    [111, 145, 243],
+    [113, 147, 243],
+    [114, 148, 244],
+    [115, 150, 245],
+    [117, 151, 246],
+    [118, 153, 246],
+    [119, 154, 247],
+    [121, 156, 248],
+    [122, 157, 248],
+    [124, 159, 249],
+    [125, 160, 249],
+    [126, 161, 250],
+    [128, 163, 250],
+    [129, 164, 251],
+    [130, 166, 251],
+    [132, 167, 252],
+    [133, 168, 252],
+    [135, 170, 252],
+    [136, 171, 253],
+    [137, 172, 253],
+    [139, 173, 253],
+    [140, 175, 254],
+    [141, 176, 254],
+    [143, 177, 254],
+    [144, 178, 254],
+    [146, 180, 254],
+    [147, 181, 255],
+    [148, 182, 255],
+    [150, 183, 255],
+    [151, 184, 255],
+    [153, 185, 255],
+    [154, 187, 255],
+    [155, 188, 255],
+    [157, 189, 255],
+    [158, 190, 255],
+    [159, 191, 255],
+    [161, 192, 255],
+    [162, 193, 255],
+    [163, 194, 254],
+    [165, 195, 254],
+    [166, 196, 254],
+    [168, 197, 254],
+    [169, 198, 254],
+    [170, 199, 253],
+    [172, 200, 253],
+    [173, 201, 253],
+    [174, 201, 252],
+    [176, 202, 252],
+    [177, 203, 252],
+    [178, 204, 251],
+    [180, 205, 251],
+    [181, 206, 250],
+    [182, 206, 250],
+    [183, 207, 249],
+    [185, 208, 249],
+    [186, 209, 248],
+    [187, 209, 248],
+    [188, 210, 247],
+    [190, 211, 246],
+    [191, 211, 246],
+    [192, 212, 245],
+    [193, 212, 244],
+    [195, 213, 244],
+    [196, 214, 243],
+    [197, 214, 242],
+    [198, 215, 241],
+    [200, 215, 241],
+    [201, 216, 240],
+    [202, 216, 239],
+    [203, 216, 238],
+    [204, 217, 237],
+    [205, 217, 236],
+    [206, 218, 235],
+    [208, 218, 234],
+    [209, 218, 233],
+    [210, 219, 232],
+    [211, 219, 231],
+    [212, 219, 230],
+    [213, 219, 229],
+    [214, 220, 228],
+    [215, 220, 227],
+    [216, 220, 226],
+    [217, 220, 225],
+    [218, 220, 224],
+    [219, 221, 222],
+    [220, 221, 221],
+    [221, 220, 220],
+    [222, 220, 219],
+    [223, 220, 217],
+    [225, 219, 216],
+    [226, 218, 214],
+    [227, 218, 213],
+    [228, 217, 211],
+    [229, 217, 210],
+    [229, 216, 209],
+    [230, 216, 207],
+    [231, 215, 206],
+    [232, 214, 204],
+    [233, 214, 203],
+    [234, 213, 201],
+    [235, 212, 200],
+    [235, 211, 198],
+    [236, 211, 197],
+    [237, 210, 195],
+    [238, 209, 194],
+    [238, 208, 192],
+    [239, 207, 191],
+    [239, 206, 189],
+    [240, 206, 188],
+    [241, 205, 186],
+    [241, 204, 184],
+    [242, 203, 183],
+    [242, 202, 181],
+    [243, 201, 180],
+    [243, 200, 178],
+    [244, 199, 177],
+    [244, 198, 175],
+    [244, 197, 173],
+    [245, 196, 172],
+    [245, 195, 170],
+    [245, 193, 169],
+    [246, 192, 167],
+    [246, 191, 166],
+    [246, 190, 164],
+    [246, 189, 162],
+    [247, 188, 161],
+    [247, 186, 159],
+    [247, 185, 158],
+    [247, 184, 156],
+    [247, 183, 155],
+    [247, 181, 153],
+    [247, 180, 151],
+    [247, 179, 150],
+    [247, 178, 148],
+    [247, 176, 147],
+    [247, 175, 145],
+    [247, 173, 144],
+    [247, 172, 142],
+    [247, 171, 140],
+    [247, 169, 139],
+    [247, 168, 137],
+    [247, 166, 136],
+    [246, 165, 134],
+    [246, 163, 133],
+    [246, 162, 131],
+    [246, 160, 129],
+    [245, 159, 128],
+    [245, 157, 126],
+    [245, 156, 125],
+    [244, 154, 123],
+    [244, 153, 122],
+    [244, 151, 120],
+    [243, 149, 119],
+    [243, 148, 117],
+    [242, 146, 116],
+    [242, 144, 114],
+    [241, 143, 113],
+    [241, 141, 111],
+    [240, 139, 110],
+    [240, 138, 108],
+    [239, 136, 107],
+    [239, 134, 105],
+    [238, 133, 104],
+    [237, 131, 102],
+    [237, 129, 101],
+    [236, 127, 99],
+    [235, 125, 98],
+    [234, 124, 96],
+    [234, 122, 95],
+    [233, 120, 93],
+    [232, 118, 92],
+    [231, 116, 90],
+    [230, 114, 89],
+    [229, 112, 88],
+    [229, 111, 86],
+    [228, 109, 85],
+    [227, 107, 83],
+    [226, 105, 82],
+    [225, 103, 81],
+    [224, 101, 79],
+    [223, 99, 78],
+    [222, 97, 77],
+    [221, 95, 75],
+    [220, 93, 74],
+    [219, 91, 73],
+    [218, 89, 71],
+    [216, 86, 70],
+    [215, 84, 69],
+    [214, 82, 67],
+    [213, 80, 66],
+    [212, 78, 65],
+    [211, 76, 64],
+    [209, 73, 62],
+    [208, 71, 61],
+    [207, 69, 60],
+    [206, 67, 59],
+    [204, 64, 57],
+    [203, 62, 56],
+    [202, 59, 55],
+    [200, 57, 54],
+    [199, 54, 52],
+    [198, 52, 51],
+    [196, 49, 50],
+    [195, 46, 49],
+    [193, 43, 48],
+    [192, 40, 47],
+    [191, 37, 46],
+    [189, 34, 44],
+    [188, 30, 43],
+    [186, 26, 42],
+    [185, 22, 41],
+    [183, 17, 40],
+    [182, 11, 39],
+    [180, 4, 38],
+];
@@ -0,0 +1,207 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A built-in color map, also called a transfer function, which maps a scalar value in the range `[0..1]` to a color.
+/// Use it with a [ColorMapMaterial] to visualize scalar data, for example simulation results or sensor data.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMap {
+    /// The perceptually uniform viridis color map going from dark blue over green to yellow.
+    Viridis,
+    /// The perceptually uniform plasma color map going from dark blue over purple and orange to yellow.
+    Plasma,
+    /// The turbo color map, an improved rainbow color map going from dark blue over green and yellow to dark red.
+    Turbo,
+    /// The diverging cool to warm color map going from blue over light gray to red, which is useful for data with a meaningful center value.
+    CoolWarm,
+}
+
+impl ColorMap {
+    // The number of texels in the color map texture.
+    const RESOLUTION: u32 = 256;
+
+    ///
+    /// Returns the color at the given value in the range `[0..1]`. Values outside the range are clamped.
+    ///
+    pub fn color(&self, value: f32) -> Srgba {
+        let lut = self.lut();
+        let t = value.clamp(0.0, 1.0) * (lut.len() - 1) as f32;
+        let i = (t as usize).min(lut.len() - 2);
+        let f = t - i as f32;
+        let (a, b) = (lut[i], lut[i + 1]);
+        let mix = |a: u8, b: u8| (a as f32 + f * (b as f32 - a as f32)).round() as u8;
+        Srgba::new(mix(a[0], b[0]), mix(a[1], b[1]), mix(a[2], b[2]), 255)
+    }
+
+    ///
+    /// Returns the given number of colors evenly spaced along the color map, including the first and last color.
+    ///
+    pub fn colors(&self, count: u32) -> Vec<Srgba> {
+        (0..count)
+            .map(|i| self.color(i as f32 / (count.max(2) - 1) as f32))
+            .collect()
+    }
+
+    ///
+    /// Returns the color map as a one pixel high [CpuTexture] where the first pixel is the color at the value 0 and the last pixel is the color at the value 1.
+    ///
+    pub fn cpu_texture(&self) -> CpuTexture {
+        CpuTexture {
+            name: format!("{:?}", self),
+            data: TextureData::RgbaU8(
+                self.colors(Self::RESOLUTION)
+                    .into_iter()
+                    .map(|c| [c.r, c.g, c.b, c.a])
+                    .collect(),
+            ),
+            width: Self::RESOLUTION,
+            height: 1,
+            min_filter: Interpolation::Linear,
+            mag_filter: Interpolation::Linear,
+            mipmap: None,
+            wrap_s: Wrapping::ClampToEdge,
+            wrap_t: Wrapping::ClampToEdge,
+        }
+    }
+
+    ///
+    /// Creates a texture containing the color map in linear sRGB, see [ColorMap::cpu_texture].
+    ///
+    pub fn texture(&self, context: &Context) -> Texture2DRef {
+        let mut cpu_texture = self.cpu_texture();
+        cpu_texture.data.to_linear_srgb();
+        Texture2DRef::from_cpu_texture(context, &cpu_texture)
+    }
+
+    // The colors evenly spaced along the color map which are linearly interpolated in between.
+    fn lut(&self) -> &'static [[u8; 3]; 256] {
+        match self {
+            Self::Viridis => &super::color_map_luts::VIRIDIS,
+            Self::Plasma => &super::color_map_luts::PLASMA,
+            Self::Turbo => &super::color_map_luts::TURBO,
+            Self::CoolWarm => &super::color_map_luts::COOL_WARM,
+        }
+    }
+}
+
+///
+/// The source of the scalar values that are mapped to colors by a [ColorMapMaterial].
+///
+#[derive(Clone)]
+pub enum ScalarSource {
+    /// The scalar values are given per vertex in the u coordinate of the uv coordinates of the [Geometry], see [ColorMapMaterial::vertex_scalars],
+    /// and are interpolated across each triangle before being mapped to a color.
+    Vertex,
+    /// The scalar values are given by the red channel of a texture which is sampled using the uv coordinates of the [Geometry].
+    Texture(Texture2DRef),
+}
+
+///
+/// A material that renders a [Geometry] by mapping a scalar value through a color map, which is useful for visualizing simulation results or sensor data.
+/// The scalar value is given either per vertex or by a texture, see [ScalarSource], and the values from [ColorMapMaterial::min] to [ColorMapMaterial::max] are mapped to the entire color map.
+/// This material is not affected by lights.
+///
+#[derive(Clone)]
+pub struct ColorMapMaterial {
+    /// The color map texture where the first texel in the first row is the color at the minimum value and the last texel is the color at the maximum value.
+    /// Use [ColorMap::texture] to create one of the built-in color maps, or create a custom one in the same way.
+    pub color_map: Texture2DRef,
+    /// The source of the scalar values.
+    pub source: ScalarSource,
+    /// The scalar value that is mapped to the first color in the color map. Smaller values are clamped to this value.
+    pub min: f32,
+    /// The scalar value that is mapped to the last color in the color map. Larger values are clamped to this value.
+    pub max: f32,
+    /// If specified, the color map is divided into this number of discrete colors instead of being continuous, which makes it easier to read off values, like contour bands on a map.
+    pub steps: Option<u32>,
+    /// Render states.
+    pub render_states: RenderStates,
+}
+
+impl ColorMapMaterial {
+    ///
+    /// Constructs a new color map material which maps the scalar values given per vertex from the range `[min..max]` through the given color map.
+    /// Use [ColorMapMaterial::vertex_scalars] to assign the scalar values to the mesh.
+    ///
+    pub fn new(context: &Context, color_map: ColorMap, min: f32, max: f32) -> Self {
+        Self {
+            color_map: color_map.texture(context),
+            source: ScalarSource::Vertex,
+            min,
+            max,
+            steps: None,
+            render_states: RenderStates::default(),
+        }
+    }
+
+    ///
+    /// Constructs a new color map material which maps the scalar values in the red channel of the given texture from the range `[min..max]` through the given color map.
+    ///
+    pub fn new_with_texture(
+        context: &Context,
+        color_map: ColorMap,
+        scalars: Texture2DRef,
+        min: f32,
+        max: f32,
+    ) -> Self {
+        Self {
+            source: ScalarSource::Texture(scalars),
+            ..Self::new(context, color_map, min, max)
+        }
+    }
+
+    ///
+    /// Returns uv coordinates containing the given scalar values, one per vertex, which should be assigned to [CpuMesh::uvs] before constructing the mesh.
+    ///
+    pub fn vertex_scalars(scalars: &[f32]) -> Vec<Vec2> {
+        scalars.iter().map(|s| vec2(*s, 0.0)).collect()
+    }
+}
+
+impl Material for ColorMapMaterial {
+    fn id(&self) -> u16 {
+        match self.source {
+            ScalarSource::Vertex => 0b1u16 << 15 | 0b1000u16,
+            ScalarSource::Texture(_) => 0b1u16 << 15 | 0b1001u16,
+        }
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        let mut shader = String::new();
+        if let ScalarSource::Texture(_) = self.source {
+            shader.push_str("#define USE_SCALAR_TEXTURE\n");
+        }
+        shader.push_str(include_str!("../../core/shared.frag"));
+        shader.push_str(ColorMapping::fragment_shader_source());
+        shader.push_str(include_str!("shaders/color_map_material.frag"));
+        shader
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
+        camera.color_mapping.use_uniforms(program);
+        program.use_texture("colorMap", &self.color_map);
+        program.use_uniform("colorMapSize", self.color_map.width() as f32);
+        program.use_uniform("range", vec2(self.min, self.max));
+        program.use_uniform("steps", self.steps.unwrap_or(0) as f32);
+        if let ScalarSource::Texture(ref tex) = self.source {
+            program.use_uniform("textureTransformation", tex.transformation);
+            program.use_texture("scalarTexture", tex);
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
@@ -11,6 +11,9 @@ use crate::renderer::*;
 /// Instead render the object into a [RenderTarget] consisting of a [Texture2DArray] with three RGBA u8 layers as color target and a [DepthTexture2D] as depth target.
 /// Then call the [DeferredPhysicalMaterial::lighting_pass] method with these textures to render to the screen.
 ///
+/// When using the render methods, the lights with a [Light::volume], for example [PointLight]s with a limited [range](Attenuation::range),
+/// are applied by rendering the volume of each light instead of the entire screen, which makes it possible to render many small lights efficiently.
+///
 #[derive(Clone)]
 pub struct DeferredPhysicalMaterial {
     /// Name.
@@ -36,6 +39,15 @@ pub struct DeferredPhysicalMaterial {
     pub normal_scale: f32,
     /// A tangent space normal map, also known as bump map.
     pub normal_texture: Option<Texture2DRef>,
+    /// A height map used for parallax occlusion mapping, which offsets the texture coordinates based on the view direction to give the surface depth without extra geometry.
+    /// The height values are sampled from the red channel where 1 is the original surface and 0 is [Self::height_scale] below the surface.
+    /// Requires the geometry to have uv coordinates and tangents.
+    pub height_texture: Option<Texture2DRef>,
+    /// The depth, in texture coordinates, of the lowest point in the [Self::height_texture].
+    pub height_scale: f32,
+    /// The maximum number of steps taken when searching the [Self::height_texture] for the intersection with the view ray.
+    /// More steps gives a more accurate result at the cost of performance.
+    pub parallax_steps: u32,
     /// Render states
     pub render_states: RenderStates,
     /// Color of light shining from an object.
@@ -43,6 +55,9 @@ pub struct DeferredPhysicalMaterial {
     /// Texture with color of light shining from an object.
     /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
     pub emissive_texture: Option<Texture2DRef>,
+    /// Whether or not this material is affected by lights.
+    /// Unlit surfaces are marked in the alpha channel of the emissive layer of the geometry buffer and are skipped by the lighting pass.
+    pub shading_mode: ShadingMode,
     /// A threshold on the alpha value of the color as a workaround for transparency.
     /// If the alpha value of a pixel touched by an object with this material is less than the threshold, then that object is not contributing to the color of that pixel.
     /// On the other hand, if the alpha value is more than the threshold, then it is contributing fully to that pixel and thereby blocks out everything behind.
@@ -109,6 +124,9 @@ impl DeferredPhysicalMaterial {
             roughness: cpu_material.roughness,
             metallic_roughness_texture,
             normal_texture,
+            height_texture: None,
+            height_scale: 0.05,
+            parallax_steps: 16,
             normal_scale: cpu_material.normal_scale,
             occlusion_texture,
             occlusion_strength: cpu_material.occlusion_strength,
@@ -116,11 +134,14 @@ impl DeferredPhysicalMaterial {
             alpha_cutout: cpu_material.alpha_cutout,
             emissive: cpu_material.emissive,
             emissive_texture,
+            shading_mode: ShadingMode::Lit,
         }
     }
 
     ///
     /// Constructs a deferred physical material from a physical material.
+    /// The [shading mode](PhysicalMaterial::shading_mode) is carried over while the [lighting model](PhysicalMaterial::lighting_model) is not,
+    /// since all lit surfaces are shaded with the same Cook-Torrance lighting model in the [lighting pass](DeferredPhysicalMaterial::lighting_pass).
     ///
     pub fn from_physical_material(physical_material: &PhysicalMaterial) -> Self {
         Self {
@@ -131,6 +152,9 @@ impl DeferredPhysicalMaterial {
             roughness: physical_material.roughness,
             metallic_roughness_texture: physical_material.metallic_roughness_texture.clone(),
             normal_texture: physical_material.normal_texture.clone(),
+            height_texture: physical_material.height_texture.clone(),
+            height_scale: physical_material.height_scale,
+            parallax_steps: physical_material.parallax_steps,
             normal_scale: physical_material.normal_scale,
             occlusion_texture: physical_material.occlusion_texture.clone(),
             occlusion_strength: physical_material.occlusion_strength,
@@ -141,11 +165,14 @@ impl DeferredPhysicalMaterial {
             },
             emissive: physical_material.emissive,
             emissive_texture: physical_material.emissive_texture.clone(),
-            alpha_cutout: if physical_material.is_transparent {
-                Some(0.5)
-            } else {
-                None
-            },
+            shading_mode: physical_material.shading_mode,
+            alpha_cutout: physical_material
+                .alpha_cutout
+                .or(if physical_material.is_transparent {
+                    Some(0.5)
+                } else {
+                    None
+                }),
         }
     }
     ///
@@ -195,22 +222,32 @@ impl Material for DeferredPhysicalMaterial {
         if self.emissive_texture.is_some() {
             id |= 0b1u16 << 4;
         }
+        if self.height_texture.is_some() {
+            id |= 0b1u16 << 7;
+        }
         if self.alpha_cutout.is_some() {
             id |= 0b1u16 << 5;
         }
+        if self.shading_mode == ShadingMode::Unlit {
+            id |= 0b1u16 << 8;
+        }
         id
     }
 
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
         let mut output = include_str!("../../core/shared.frag").to_string();
+        if self.shading_mode == ShadingMode::Unlit {
+            output.push_str("#define UNLIT;\n");
+        }
         if self.albedo_texture.is_some()
             || self.metallic_roughness_texture.is_some()
             || self.normal_texture.is_some()
             || self.occlusion_texture.is_some()
             || self.emissive_texture.is_some()
+            || self.height_texture.is_some()
             || self.alpha_cutout.is_some()
         {
-            output.push_str("in vec2 uvs;\n");
+            output.push_str("in vec2 uvs;\n#define USE_UVS;\n");
             if self.albedo_texture.is_some() {
                 output.push_str("#define USE_ALBEDO_TEXTURE;\n");
             }
@@ -220,8 +257,15 @@ impl Material for DeferredPhysicalMaterial {
             if self.occlusion_texture.is_some() {
                 output.push_str("#define USE_OCCLUSION_TEXTURE;\n");
             }
+            if self.normal_texture.is_some() || self.height_texture.is_some() {
+                output.push_str("in vec3 tang;\nin vec3 bitang;\n");
+            }
             if self.normal_texture.is_some() {
-                output.push_str("#define USE_NORMAL_TEXTURE;\nin vec3 tang;\nin vec3 bitang;\n");
+                output.push_str("#define USE_NORMAL_TEXTURE;\n");
+            }
+            if self.height_texture.is_some() {
+                output.push_str("#define USE_HEIGHT_TEXTURE;\n");
+                output.push_str(include_str!("shaders/parallax_occlusion_mapping.frag"));
             }
             if self.emissive_texture.is_some() {
                 output.push_str("#define USE_EMISSIVE_TEXTURE;\n");
@@ -250,12 +294,13 @@ impl Material for DeferredPhysicalMaterial {
                 || self.normal_texture.is_some()
                 || self.occlusion_texture.is_some()
                 || self.emissive_texture.is_some()
+                || self.height_texture.is_some()
                 || self.alpha_cutout.is_some(),
-            tangents: self.normal_texture.is_some(),
+            tangents: self.normal_texture.is_some() || self.height_texture.is_some(),
         }
     }
 
-    fn use_uniforms(&self, program: &Program, _camera: &Camera, _lights: &[&dyn Light]) {
+    fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
         program.use_uniform("metallic", self.metallic);
         program.use_uniform("roughness", self.roughness);
         program.use_uniform("albedo", self.albedo.to_linear_srgb());
@@ -278,6 +323,13 @@ impl Material for DeferredPhysicalMaterial {
             program.use_uniform("normalTexTransform", texture.transformation);
             program.use_texture("normalTexture", texture);
         }
+        if let Some(ref texture) = self.height_texture {
+            program.use_uniform("cameraPosition", camera.position());
+            program.use_uniform("heightTexTransform", texture.transformation);
+            program.use_uniform("heightScale", self.height_scale);
+            program.use_uniform("parallaxSteps", self.parallax_steps as i32);
+            program.use_texture("heightTexture", texture);
+        }
         if program.requires_uniform("emissiveTexture") {
             if let Some(ref texture) = self.emissive_texture {
                 program.use_uniform("emissiveTexTransform", texture.transformation);
@@ -305,6 +357,9 @@ impl Default for DeferredPhysicalMaterial {
             roughness: 1.0,
             metallic_roughness_texture: None,
             normal_texture: None,
+            height_texture: None,
+            height_scale: 0.05,
+            parallax_steps: 16,
             normal_scale: 1.0,
             occlusion_texture: None,
             occlusion_strength: 1.0,
@@ -312,6 +367,7 @@ impl Default for DeferredPhysicalMaterial {
             alpha_cutout: None,
             emissive: Srgba::BLACK,
             emissive_texture: None,
+            shading_mode: ShadingMode::Lit,
         }
     }
 }
@@ -0,0 +1,56 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Render the object with a color that encodes the given id, which is used for picking objects on the GPU, see [GpuPicker].
+/// The id is stored in the red, green, blue and alpha channels with the least significant byte in the red channel,
+/// so the id can be read back exactly from a render target with an unsigned byte RGBA format using [u32::from_le_bytes].
+///
+#[derive(Default, Clone)]
+pub struct IdMaterial {
+    /// The id written to the render target.
+    pub id: u32,
+    /// Render states.
+    pub render_states: RenderStates,
+}
+
+impl FromCpuMaterial for IdMaterial {
+    fn from_cpu_material(_context: &Context, _cpu_material: &CpuMaterial) -> Self {
+        Self::default()
+    }
+}
+
+impl Material for IdMaterial {
+    fn id(&self) -> u16 {
+        0b1u16 << 15 | 0b1u16 << 13
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        include_str!("shaders/id_material.frag").to_string()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes::NONE
+    }
+
+    fn use_uniforms(&self, program: &Program, _camera: &Camera, _lights: &[&dyn Light]) {
+        let bytes = self.id.to_le_bytes();
+        program.use_uniform(
+            "idColor",
+            vec4(
+                bytes[0] as f32,
+                bytes[1] as f32,
+                bytes[2] as f32,
+                bytes[3] as f32,
+            ) / 255.0,
+        );
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
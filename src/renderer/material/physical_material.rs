@@ -1,10 +1,27 @@
 use crate::core::*;
 use crate::renderer::*;
 
+///
+/// Defines whether or not a [PhysicalMaterial] is affected by lights.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShadingMode {
+    /// The material is shaded by the lights in the scene.
+    #[default]
+    Lit,
+    /// The material bypasses lighting entirely and the color is the albedo color plus the emissive color,
+    /// which is useful for example for HUD geometry and glowing signage rendered together with lit geometry.
+    Unlit,
+}
+
 ///
 /// A physically-based material that renders a [Geometry] in an approximate correct physical manner based on Physically Based Rendering (PBR).
-/// This material is affected by lights.
+/// This material is affected by lights unless the [shading mode](PhysicalMaterial::shading_mode) is [ShadingMode::Unlit].
+///
+/// The material follows the metallic-roughness model, with texture slots for albedo, metallic and roughness, occlusion, normals and emission,
+/// and can be rendered in a deferred pipeline using [DeferredPhysicalMaterial::from_physical_material].
 ///
+#[doc(alias = "PbrMaterial")]
 #[derive(Clone)]
 pub struct PhysicalMaterial {
     /// Name.
@@ -30,10 +47,22 @@ pub struct PhysicalMaterial {
     pub normal_scale: f32,
     /// A tangent space normal map, also known as bump map.
     pub normal_texture: Option<Texture2DRef>,
+    /// A height map used for parallax occlusion mapping, which offsets the texture coordinates based on the view direction to give the surface depth without extra geometry.
+    /// The height values are sampled from the red channel where 1 is the original surface and 0 is [Self::height_scale] below the surface.
+    /// Requires the geometry to have uv coordinates and tangents.
+    pub height_texture: Option<Texture2DRef>,
+    /// The depth, in texture coordinates, of the lowest point in the [Self::height_texture].
+    pub height_scale: f32,
+    /// The maximum number of steps taken when searching the [Self::height_texture] for the intersection with the view ray.
+    /// More steps gives a more accurate result at the cost of performance.
+    pub parallax_steps: u32,
     /// Render states.
     pub render_states: RenderStates,
     /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
     pub is_transparent: bool,
+    /// Whether a transparent material is rendered using weighted blended order-independent transparency instead of blending the objects sorted from back to front.
+    /// This handles intersecting transparent geometry at the cost of an approximate result and is only supported when rendering using for example [RenderTarget::render].
+    pub order_independent_transparency: bool,
     /// Color of light shining from an object.
     pub emissive: Srgba,
     /// Texture with color of light shining from an object.
@@ -41,6 +70,13 @@ pub struct PhysicalMaterial {
     pub emissive_texture: Option<Texture2DRef>,
     /// The lighting model used when rendering this material
     pub lighting_model: LightingModel,
+    /// Whether or not this material is affected by lights.
+    pub shading_mode: ShadingMode,
+    /// A threshold on the alpha value of the color, which makes it possible to render for example foliage as an opaque material.
+    /// If the alpha value of a pixel touched by an object with this material is less than the threshold, then that object is not contributing to the color of that pixel.
+    /// On the other hand, if the alpha value is more than the threshold, then it is contributing fully to that pixel and thereby blocks out everything behind.
+    /// If [RenderStates::alpha_to_coverage] is enabled, the edges are antialiased when rendering to a multisample render target instead of being cut sharply.
+    pub alpha_cutout: Option<f32>,
 }
 
 impl PhysicalMaterial {
@@ -123,6 +159,9 @@ impl PhysicalMaterial {
             roughness: cpu_material.roughness,
             metallic_roughness_texture,
             normal_texture,
+            height_texture: None,
+            height_scale: 0.05,
+            parallax_steps: 16,
             normal_scale: cpu_material.normal_scale,
             occlusion_texture,
             occlusion_strength: cpu_material.occlusion_strength,
@@ -136,9 +175,12 @@ impl PhysicalMaterial {
                 RenderStates::default()
             },
             is_transparent,
+            order_independent_transparency: false,
             emissive: cpu_material.emissive,
             emissive_texture,
             lighting_model: cpu_material.lighting_model,
+            shading_mode: ShadingMode::Lit,
+            alpha_cutout: cpu_material.alpha_cutout,
         }
     }
 }
@@ -167,18 +209,41 @@ impl Material for PhysicalMaterial {
         if self.emissive_texture.is_some() {
             id |= 0b1u16 << 4;
         }
+        if self.height_texture.is_some() {
+            id |= 0b1u16 << 9;
+        }
+        if self.alpha_cutout.is_some() {
+            id |= 0b1u16 << 8;
+        }
+        if self.shading_mode == ShadingMode::Unlit {
+            id |= 0b1u16 << 10;
+        }
+        if self.material_type() == MaterialType::OrderIndependentTransparent {
+            id |= 0b1u16 << 11;
+        }
+        if self.alpha_cutout.is_some() && self.render_states.alpha_to_coverage {
+            id |= 0b1u16 << 12;
+        }
         id
     }
 
     fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
-        let mut output = lights_shader_source(lights, self.lighting_model);
+        let mut output = match self.shading_mode {
+            ShadingMode::Lit => lights_shader_source(lights, self.lighting_model),
+            ShadingMode::Unlit => {
+                let mut output = lights_shader_source(&[], self.lighting_model);
+                output.push_str("#define UNLIT;\n");
+                output
+            }
+        };
         if self.albedo_texture.is_some()
             || self.metallic_roughness_texture.is_some()
             || self.normal_texture.is_some()
             || self.occlusion_texture.is_some()
             || self.emissive_texture.is_some()
+            || self.height_texture.is_some()
         {
-            output.push_str("in vec2 uvs;\n");
+            output.push_str("in vec2 uvs;\n#define USE_UVS;\n");
             if self.albedo_texture.is_some() {
                 output.push_str("#define USE_ALBEDO_TEXTURE;\n");
             }
@@ -188,13 +253,32 @@ impl Material for PhysicalMaterial {
             if self.occlusion_texture.is_some() {
                 output.push_str("#define USE_OCCLUSION_TEXTURE;\n");
             }
+            if self.normal_texture.is_some() || self.height_texture.is_some() {
+                output.push_str("in vec3 tang;\nin vec3 bitang;\n");
+            }
             if self.normal_texture.is_some() {
-                output.push_str("#define USE_NORMAL_TEXTURE;\nin vec3 tang;\nin vec3 bitang;\n");
+                output.push_str("#define USE_NORMAL_TEXTURE;\n");
+            }
+            if self.height_texture.is_some() {
+                output.push_str("#define USE_HEIGHT_TEXTURE;\n");
+                output.push_str(include_str!("shaders/parallax_occlusion_mapping.frag"));
             }
             if self.emissive_texture.is_some() {
                 output.push_str("#define USE_EMISSIVE_TEXTURE;\n");
             }
         }
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            output.push_str(
+                format!("#define ALPHACUT;\nfloat acut = {:?};\n", alpha_cutout).as_str(),
+            );
+            if self.render_states.alpha_to_coverage {
+                output.push_str("#define ALPHA_TO_COVERAGE;\n");
+            }
+        }
+        if self.material_type() == MaterialType::OrderIndependentTransparent {
+            output.push_str("#define WEIGHTED_BLENDED_OIT;\n");
+            output.push_str(include_str!("shaders/weighted_blended_oit.frag"));
+        }
         output.push_str(ToneMapping::fragment_shader_source());
         output.push_str(ColorMapping::fragment_shader_source());
         output.push_str(include_str!("shaders/physical_material.frag"));
@@ -210,20 +294,26 @@ impl Material for PhysicalMaterial {
                 || self.metallic_roughness_texture.is_some()
                 || self.normal_texture.is_some()
                 || self.occlusion_texture.is_some()
-                || self.emissive_texture.is_some(),
-            tangents: self.normal_texture.is_some(),
+                || self.emissive_texture.is_some()
+                || self.height_texture.is_some(),
+            tangents: self.normal_texture.is_some() || self.height_texture.is_some(),
         }
     }
 
     fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
-        camera.tone_mapping.use_uniforms(program);
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
         camera.color_mapping.use_uniforms(program);
-        if !lights.is_empty() {
-            program.use_uniform_if_required("cameraPosition", camera.position());
-            for (i, light) in lights.iter().enumerate() {
-                light.use_uniforms(program, i as u32);
+        let is_lit = self.shading_mode == ShadingMode::Lit;
+        if !lights.is_empty() || !is_lit {
+            if is_lit {
+                program.use_uniform_if_required("cameraPosition", camera.position());
+                for (i, light) in lights.iter().enumerate() {
+                    light.use_uniforms(program, i as u32);
+                }
             }
-            program.use_uniform("metallic", self.metallic);
+            program.use_uniform_if_required("metallic", self.metallic);
             program.use_uniform_if_required("roughness", self.roughness);
             if program.requires_uniform("albedoTexture") {
                 if let Some(ref texture) = self.albedo_texture {
@@ -260,13 +350,32 @@ impl Material for PhysicalMaterial {
                 program.use_texture("emissiveTexture", texture);
             }
         }
+        if program.requires_uniform("heightTexture") {
+            if let Some(ref texture) = self.height_texture {
+                program.use_uniform("cameraPosition", camera.position());
+                program.use_uniform("heightTexTransform", texture.transformation);
+                program.use_uniform("heightScale", self.height_scale);
+                program.use_uniform("parallaxSteps", self.parallax_steps as i32);
+                program.use_texture("heightTexture", texture);
+            }
+        }
     }
 
     fn render_states(&self) -> RenderStates {
-        self.render_states
+        if self.material_type() == MaterialType::OrderIndependentTransparent {
+            RenderStates {
+                write_mask: WriteMask::COLOR,
+                blend: Blend::WEIGHTED_BLENDED_ACCUMULATION,
+                ..self.render_states
+            }
+        } else {
+            self.render_states
+        }
     }
     fn material_type(&self) -> MaterialType {
-        if self.is_transparent {
+        if self.is_transparent && self.order_independent_transparency {
+            MaterialType::OrderIndependentTransparent
+        } else if self.is_transparent {
             MaterialType::Transparent
         } else {
             MaterialType::Opaque
@@ -284,14 +393,20 @@ impl Default for PhysicalMaterial {
             roughness: 1.0,
             metallic_roughness_texture: None,
             normal_texture: None,
+            height_texture: None,
+            height_scale: 0.05,
+            parallax_steps: 16,
             normal_scale: 1.0,
             occlusion_texture: None,
             occlusion_strength: 1.0,
             render_states: RenderStates::default(),
             is_transparent: false,
+            order_independent_transparency: false,
             emissive: Srgba::BLACK,
             emissive_texture: None,
             lighting_model: LightingModel::Blinn,
+            shading_mode: ShadingMode::Lit,
+            alpha_cutout: None,
         }
     }
 }
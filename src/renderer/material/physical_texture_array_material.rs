@@ -0,0 +1,279 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::sync::Arc;
+
+///
+/// A physically-based material similar to [PhysicalMaterial] where each texture is a [Texture2DArray] instead of a single texture,
+/// and the layer is given per vertex (see [BatchedMesh::new_with_texture_layers]) or per instance (see [Instances::texture_layers]).
+///
+/// This makes it possible to render objects with physical materials which only differ by texture in the same draw call,
+/// by packing the textures of the materials into texture arrays and merging the objects into a [BatchedMesh] or an [InstancedMesh].
+/// Geometries without texture layers use the first layer.
+///
+#[derive(Clone)]
+pub struct PhysicalTextureArrayMaterial {
+    /// Albedo base color, also called diffuse color.
+    pub albedo: Srgba,
+    /// Texture array with albedo base colors, also called diffuse color.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub albedo_texture: Option<Arc<Texture2DArray>>,
+    /// A value in the range `[0..1]` specifying how metallic the surface is.
+    pub metallic: f32,
+    /// A value in the range `[0..1]` specifying how rough the surface is.
+    pub roughness: f32,
+    /// Texture array containing the metallic and roughness parameters which are multiplied with the [Self::metallic] and [Self::roughness] values in the shader.
+    /// The metallic values are sampled from the blue channel and the roughness from the green channel.
+    pub metallic_roughness_texture: Option<Arc<Texture2DArray>>,
+    /// A scalar multiplier controlling the amount of occlusion applied from the [Self::occlusion_texture]. A value of 0.0 means no occlusion. A value of 1.0 means full occlusion.
+    pub occlusion_strength: f32,
+    /// Texture array with occlusion maps. Higher values indicate areas that should receive full indirect lighting and lower values indicate no indirect lighting.
+    /// The occlusion values are sampled from the red channel.
+    pub occlusion_texture: Option<Arc<Texture2DArray>>,
+    /// A scalar multiplier applied to each normal vector of the [Self::normal_texture].
+    pub normal_scale: f32,
+    /// Texture array with tangent space normal maps, also known as bump maps.
+    pub normal_texture: Option<Arc<Texture2DArray>>,
+    /// Color of light shining from an object.
+    pub emissive: Srgba,
+    /// Texture array with color of light shining from an object.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub emissive_texture: Option<Arc<Texture2DArray>>,
+    /// A transformation applied to the uv coordinates before reading a texel value at those uv coordinates.
+    pub texture_transformation: Mat3,
+    /// The lighting model used when rendering this material
+    pub lighting_model: LightingModel,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
+    pub is_transparent: bool,
+    /// A threshold on the alpha value of the color, see [PhysicalMaterial::alpha_cutout].
+    pub alpha_cutout: Option<f32>,
+}
+
+impl PhysicalTextureArrayMaterial {
+    ///
+    /// Constructs a new physical material by packing the textures of the given [CpuMaterial]s into texture arrays, where the index of each material is its layer.
+    /// Textures in sRGB (`RgbU8` and `RgbaU8`) are converted to linear sRGB.
+    /// The colors, factors, lighting model and alpha cutout are taken from the first material, since they are the same for all layers.
+    /// If the input contains an [CpuMaterial::occlusion_metallic_roughness_texture], this texture is used for both
+    /// [PhysicalTextureArrayMaterial::metallic_roughness_texture] and [PhysicalTextureArrayMaterial::occlusion_texture].
+    /// Tries to infer whether this material is transparent or opaque from the alpha value of the albedo colors and the alpha values in the albedo textures.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no materials are given, if a texture is only given for some of the materials
+    /// or if the textures in the same slot do not have the same size and format.
+    ///
+    pub fn new(context: &Context, cpu_materials: &[&CpuMaterial]) -> Result<Self, CoreError> {
+        let first = cpu_materials.first().ok_or_else(|| {
+            CoreError::TextureArrayCreation("at least one material is required".to_string())
+        })?;
+        let albedo_texture = texture_array(context, cpu_materials, "albedo", true, |m| {
+            m.albedo_texture.as_ref()
+        })?;
+        let metallic_roughness_texture =
+            texture_array(context, cpu_materials, "metallic roughness", false, |m| {
+                m.occlusion_metallic_roughness_texture
+                    .as_ref()
+                    .or(m.metallic_roughness_texture.as_ref())
+            })?;
+        let occlusion_texture = if cpu_materials
+            .iter()
+            .all(|m| m.occlusion_metallic_roughness_texture.is_some())
+        {
+            metallic_roughness_texture.clone()
+        } else {
+            texture_array(context, cpu_materials, "occlusion", false, |m| {
+                m.occlusion_texture.as_ref()
+            })?
+        };
+        let normal_texture = texture_array(context, cpu_materials, "normal", false, |m| {
+            m.normal_texture.as_ref()
+        })?;
+        let emissive_texture = texture_array(context, cpu_materials, "emissive", true, |m| {
+            m.emissive_texture.as_ref()
+        })?;
+        let is_transparent = cpu_materials.iter().any(|m| super::is_transparent(m));
+        Ok(Self {
+            albedo: first.albedo,
+            albedo_texture,
+            metallic: first.metallic,
+            roughness: first.roughness,
+            metallic_roughness_texture,
+            occlusion_strength: first.occlusion_strength,
+            occlusion_texture,
+            normal_scale: first.normal_scale,
+            normal_texture,
+            emissive: first.emissive,
+            emissive_texture,
+            texture_transformation: Mat3::identity(),
+            lighting_model: first.lighting_model,
+            render_states: if is_transparent {
+                RenderStates {
+                    write_mask: WriteMask::COLOR,
+                    blend: Blend::TRANSPARENCY,
+                    ..Default::default()
+                }
+            } else {
+                RenderStates::default()
+            },
+            is_transparent,
+            alpha_cutout: first.alpha_cutout,
+        })
+    }
+}
+
+// Packs the texture in the given slot of each of the materials into a texture array, if all of the materials have a texture in that slot.
+fn texture_array(
+    context: &Context,
+    cpu_materials: &[&CpuMaterial],
+    slot: &str,
+    is_color: bool,
+    texture: impl Fn(&CpuMaterial) -> Option<&CpuTexture>,
+) -> Result<Option<Arc<Texture2DArray>>, CoreError> {
+    let cpu_textures = cpu_materials
+        .iter()
+        .filter_map(|m| texture(m))
+        .map(|cpu_texture| {
+            let mut cpu_texture = cpu_texture.clone();
+            if is_color {
+                if let TextureData::RgbU8(_) | TextureData::RgbaU8(_) = cpu_texture.data {
+                    cpu_texture.data.to_linear_srgb();
+                }
+            }
+            cpu_texture
+        })
+        .collect::<Vec<_>>();
+    if cpu_textures.is_empty() {
+        Ok(None)
+    } else if cpu_textures.len() != cpu_materials.len() {
+        Err(CoreError::TextureArrayCreation(format!(
+            "the {} texture is only given for {} of {} materials",
+            slot,
+            cpu_textures.len(),
+            cpu_materials.len()
+        )))
+    } else {
+        Ok(Some(Arc::new(Texture2DArray::new(
+            context,
+            &cpu_textures.iter().collect::<Vec<_>>(),
+        )?)))
+    }
+}
+
+impl Material for PhysicalTextureArrayMaterial {
+    fn id(&self) -> u16 {
+        let mut id = 0b1u16 << 15 | 0b1u16 << 13 | 0b1u16 << 12 | 0b1u16 << 11;
+        if self.albedo_texture.is_some() {
+            id |= 0b1u16;
+        }
+        if self.metallic_roughness_texture.is_some() {
+            id |= 0b1u16 << 1;
+        }
+        if self.occlusion_texture.is_some() {
+            id |= 0b1u16 << 2;
+        }
+        if self.normal_texture.is_some() {
+            id |= 0b1u16 << 3;
+        }
+        if self.emissive_texture.is_some() {
+            id |= 0b1u16 << 4;
+        }
+        if self.alpha_cutout.is_some() {
+            id |= 0b1u16 << 5;
+        }
+        id
+    }
+
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        let mut output = lights_shader_source(lights, self.lighting_model);
+        if self.fragment_attributes().uv {
+            output.push_str("in vec2 uvs;\n#define USE_UVS;\n");
+            if self.albedo_texture.is_some() {
+                output.push_str("#define USE_ALBEDO_TEXTURE;\n");
+            }
+            if self.metallic_roughness_texture.is_some() {
+                output.push_str("#define USE_METALLIC_ROUGHNESS_TEXTURE;\n");
+            }
+            if self.occlusion_texture.is_some() {
+                output.push_str("#define USE_OCCLUSION_TEXTURE;\n");
+            }
+            if self.normal_texture.is_some() {
+                output.push_str("in vec3 tang;\nin vec3 bitang;\n#define USE_NORMAL_TEXTURE;\n");
+            }
+            if self.emissive_texture.is_some() {
+                output.push_str("#define USE_EMISSIVE_TEXTURE;\n");
+            }
+        }
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            output.push_str(
+                format!("#define ALPHACUT;\nfloat acut = {:?};\n", alpha_cutout).as_str(),
+            );
+        }
+        output.push_str(ToneMapping::fragment_shader_source());
+        output.push_str(ColorMapping::fragment_shader_source());
+        output.push_str(include_str!("shaders/physical_texture_array_material.frag"));
+        output
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            position: true,
+            normal: true,
+            color: true,
+            uv: self.albedo_texture.is_some()
+                || self.metallic_roughness_texture.is_some()
+                || self.normal_texture.is_some()
+                || self.occlusion_texture.is_some()
+                || self.emissive_texture.is_some(),
+            tangents: self.normal_texture.is_some(),
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
+        camera.color_mapping.use_uniforms(program);
+        if !lights.is_empty() {
+            program.use_uniform_if_required("cameraPosition", camera.position());
+            for (i, light) in lights.iter().enumerate() {
+                light.use_uniforms(program, i as u32);
+            }
+        }
+        program.use_uniform_if_required("metallic", self.metallic);
+        program.use_uniform_if_required("roughness", self.roughness);
+        program.use_uniform_if_required("occlusionStrength", self.occlusion_strength);
+        program.use_uniform_if_required("normalScale", self.normal_scale);
+        program.use_uniform_if_required("textureTransformation", self.texture_transformation);
+        program.use_uniform("albedo", self.albedo.to_linear_srgb());
+        program.use_uniform("emissive", self.emissive.to_linear_srgb());
+        for (name, texture) in [
+            ("albedoTexture", &self.albedo_texture),
+            ("metallicRoughnessTexture", &self.metallic_roughness_texture),
+            ("occlusionTexture", &self.occlusion_texture),
+            ("normalTexture", &self.normal_texture),
+            ("emissiveTexture", &self.emissive_texture),
+        ] {
+            if let Some(texture) = texture.as_ref().filter(|_| program.requires_uniform(name)) {
+                program.use_texture_array(name, texture);
+            }
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.is_transparent {
+            MaterialType::Transparent
+        } else {
+            MaterialType::Opaque
+        }
+    }
+
+    fn shader_defines(&self) -> ShaderDefines {
+        ShaderDefines::new(["USE_TEXTURE_LAYERS"])
+    }
+}
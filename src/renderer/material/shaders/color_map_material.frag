@@ -0,0 +1,34 @@
+
+uniform sampler2D colorMap;
+uniform float colorMapSize;
+uniform vec2 range;
+uniform float steps;
+
+#ifdef USE_SCALAR_TEXTURE
+uniform sampler2D scalarTexture;
+uniform mat3 textureTransformation;
+#endif
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+#ifdef USE_SCALAR_TEXTURE
+    float value = texture(scalarTexture, (textureTransformation * vec3(uvs, 1.0)).xy).r;
+#else
+    float value = uvs.x;
+#endif
+
+    float t = clamp((value - range.x) / (range.y - range.x), 0.0, 1.0);
+    if (steps > 0.5) {
+        // Snap to one of the evenly spaced colors, including the first and last color
+        t = min(floor(t * steps), steps - 1.0) / max(steps - 1.0, 1.0);
+    }
+
+    // Sample at the center of the texels, so the first and last texel are the colors at the minimum and maximum value
+    float u = (t * (colorMapSize - 1.0) + 0.5) / colorMapSize;
+    outColor = vec4(texture(colorMap, vec2(u, 0.5)).rgb, 1.0);
+    outColor.rgb = color_mapping(outColor.rgb);
+}
@@ -31,6 +31,10 @@ uniform mat3 normalTexTransform;
 uniform float normalScale;
 #endif
 
+#ifdef USE_HEIGHT_TEXTURE
+uniform vec3 cameraPosition;
+#endif
+
 in vec3 pos;
 in vec3 nor;
 in vec4 col;
@@ -41,9 +45,22 @@ layout (location = 2) out vec4 outEmissive;
 
 void main()
 {
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+#if defined(USE_NORMAL_TEXTURE) || defined(USE_HEIGHT_TEXTURE)
+    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
+    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+#endif
+
+#if defined(USE_HEIGHT_TEXTURE)
+    vec2 tex_coords = parallax_occlusion_mapping(uvs, normalize(transpose(tbn) * (cameraPosition - pos)));
+#elif defined(USE_UVS)
+    vec2 tex_coords = uvs;
+#endif
+
     vec4 surface_color = albedo * col;
 #ifdef USE_ALBEDO_TEXTURE
-    vec4 c = texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
+    vec4 c = texture(albedoTexture, (albedoTexTransform * vec3(tex_coords, 1.0)).xy);
     #ifdef ALPHACUT
         if (c.a < acut) discard;
     #endif
@@ -53,27 +70,23 @@ void main()
     float metallic_factor = metallic;
     float roughness_factor = roughness;
 #ifdef USE_METALLIC_ROUGHNESS_TEXTURE
-    vec2 t = texture(metallicRoughnessTexture, (metallicRoughnessTexTransform * vec3(uvs, 1.0)).xy).gb;
+    vec2 t = texture(metallicRoughnessTexture, (metallicRoughnessTexTransform * vec3(tex_coords, 1.0)).xy).gb;
     roughness_factor *= t.x;
     metallic_factor *= t.y;
 #endif
 
     float occlusion = 1.0;
 #ifdef USE_OCCLUSION_TEXTURE
-    occlusion = mix(1.0, texture(occlusionTexture, (occlusionTexTransform * vec3(uvs, 1.0)).xy).r, occlusionStrength);
+    occlusion = mix(1.0, texture(occlusionTexture, (occlusionTexTransform * vec3(tex_coords, 1.0)).xy).r, occlusionStrength);
 #endif
 
-    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
 #ifdef USE_NORMAL_TEXTURE
-    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
-    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
-    mat3 tbn = mat3(tangent, bitangent, normal);
-    normal = tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
+    normal = tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(tex_coords, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
 #endif
 
     vec3 total_emissive = emissive.rgb;
 #ifdef USE_EMISSIVE_TEXTURE
-    total_emissive *= texture(emissiveTexture, (emissiveTexTransform * vec3(uvs, 1.0)).xy).rgb;
+    total_emissive *= texture(emissiveTexture, (emissiveTexTransform * vec3(tex_coords, 1.0)).xy).rgb;
 #endif
 
     outColor = vec4(surface_color.rgb, metallic_factor);
@@ -83,5 +96,10 @@ void main()
         nz = 0;
     }
     outNormal = vec4(0.5 * normal.xy + 0.5, float(o | nz << 7)/255.0, roughness_factor);
+#ifdef UNLIT
+    // The alpha channel marks the surface as unlit for the lighting pass
+    outEmissive = vec4(total_emissive, 1.0);
+#else
     outEmissive = vec4(total_emissive, 0.0);
+#endif
 }
\ No newline at end of file
@@ -0,0 +1,38 @@
+
+uniform sampler2D heightTexture;
+uniform mat3 heightTexTransform;
+uniform float heightScale;
+uniform int parallaxSteps;
+
+// Returns the uv coordinates offset by parallax occlusion mapping, where view_direction is the direction from the surface towards the camera in tangent space.
+vec2 parallax_occlusion_mapping(vec2 uv, vec3 view_direction)
+{
+    int steps = max(parallaxSteps, 1);
+    float layer_depth = 1.0 / float(steps);
+    vec2 delta = heightScale * layer_depth * view_direction.xy / max(view_direction.z, 0.05);
+
+    // The gradients are computed outside the loop since derivatives are undefined in non-uniform control flow
+    vec2 height_uv = (heightTexTransform * vec3(uv, 1.0)).xy;
+    vec2 dx = dFdx(height_uv);
+    vec2 dy = dFdy(height_uv);
+
+    vec2 current_uv = uv;
+    float current_layer_depth = 0.0;
+    float current_depth = 1.0 - textureGrad(heightTexture, height_uv, dx, dy).r;
+    for (int i = 0; i < steps; i++) {
+        if (current_layer_depth >= current_depth) {
+            break;
+        }
+        current_uv -= delta;
+        current_depth = 1.0 - textureGrad(heightTexture, (heightTexTransform * vec3(current_uv, 1.0)).xy, dx, dy).r;
+        current_layer_depth += layer_depth;
+    }
+
+    // Interpolate between the last two samples to avoid visible layers
+    vec2 previous_uv = current_uv + delta;
+    float after = current_depth - current_layer_depth;
+    float before = 1.0 - textureGrad(heightTexture, (heightTexTransform * vec3(previous_uv, 1.0)).xy, dx, dy).r - current_layer_depth + layer_depth;
+    float denominator = after - before;
+    float weight = abs(denominator) > 0.00001 ? after / denominator : 0.0;
+    return mix(current_uv, previous_uv, clamp(weight, 0.0, 1.0));
+}
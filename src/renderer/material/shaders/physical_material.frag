@@ -40,43 +40,64 @@ layout (location = 0) out vec4 outColor;
 
 void main()
 {
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+#if defined(USE_NORMAL_TEXTURE) || defined(USE_HEIGHT_TEXTURE)
+    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
+    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+#endif
+
+#if defined(USE_HEIGHT_TEXTURE)
+    vec2 tex_coords = parallax_occlusion_mapping(uvs, normalize(transpose(tbn) * (cameraPosition - pos)));
+#elif defined(USE_UVS)
+    vec2 tex_coords = uvs;
+#endif
+
     vec4 surface_color = albedo * col;
 #ifdef USE_ALBEDO_TEXTURE
-    vec4 c = texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
-    #ifdef ALPHACUT
-        if (c.a < acut) discard;
-    #endif
-    surface_color *= c;
+    surface_color *= texture(albedoTexture, (albedoTexTransform * vec3(tex_coords, 1.0)).xy);
+#endif
+#ifdef ALPHACUT
+#ifdef ALPHA_TO_COVERAGE
+    // Sharpen the alpha around the threshold to a width of one pixel which is then turned into a coverage mask
+    surface_color.a = clamp((surface_color.a - acut) / max(fwidth(surface_color.a), 0.0001) + 0.5, 0.0, 1.0);
+    if (surface_color.a <= 0.0) discard;
+#else
+    if (surface_color.a < acut) discard;
+#endif
 #endif
 
     float metallic_factor = metallic;
     float roughness_factor = roughness;
 #ifdef USE_METALLIC_ROUGHNESS_TEXTURE
-    vec2 t = texture(metallicRoughnessTexture, (metallicRoughnessTexTransform * vec3(uvs, 1.0)).xy).gb;
+    vec2 t = texture(metallicRoughnessTexture, (metallicRoughnessTexTransform * vec3(tex_coords, 1.0)).xy).gb;
     roughness_factor *= t.x;
     metallic_factor *= t.y;
 #endif
 
     float occlusion = 1.0;
 #ifdef USE_OCCLUSION_TEXTURE
-    occlusion = mix(1.0, texture(occlusionTexture, (occlusionTexTransform * vec3(uvs, 1.0)).xy).r, occlusionStrength);
+    occlusion = mix(1.0, texture(occlusionTexture, (occlusionTexTransform * vec3(tex_coords, 1.0)).xy).r, occlusionStrength);
 #endif
 
-    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
 #ifdef USE_NORMAL_TEXTURE
-    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
-    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
-    mat3 tbn = mat3(tangent, bitangent, normal);
-    normal = tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
+    normal = tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(tex_coords, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
 #endif
 
     vec3 total_emissive = emissive.rgb;
 #ifdef USE_EMISSIVE_TEXTURE
-    total_emissive *= texture(emissiveTexture, (emissiveTexTransform * vec3(uvs, 1.0)).xy).rgb;
+    total_emissive *= texture(emissiveTexture, (emissiveTexTransform * vec3(tex_coords, 1.0)).xy).rgb;
 #endif
 
+#ifdef UNLIT
+    outColor.rgb = total_emissive + surface_color.rgb;
+#else
     outColor.rgb = total_emissive + calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, metallic_factor, roughness_factor, occlusion);
+#endif
     outColor.rgb = tone_mapping(outColor.rgb);
     outColor.rgb = color_mapping(outColor.rgb);
     outColor.a = surface_color.a;
+#ifdef WEIGHTED_BLENDED_OIT
+    outColor = weighted_blended_oit(outColor);
+#endif
 }
\ No newline at end of file
@@ -0,0 +1,81 @@
+uniform float metallic;
+uniform float roughness;
+uniform vec3 cameraPosition;
+uniform mat3 textureTransformation;
+
+uniform vec4 albedo;
+#ifdef USE_ALBEDO_TEXTURE
+uniform sampler2DArray albedoTexture;
+#endif
+
+uniform vec4 emissive;
+#ifdef USE_EMISSIVE_TEXTURE
+uniform sampler2DArray emissiveTexture;
+#endif
+
+#ifdef USE_METALLIC_ROUGHNESS_TEXTURE
+uniform sampler2DArray metallicRoughnessTexture;
+#endif
+
+#ifdef USE_OCCLUSION_TEXTURE
+uniform sampler2DArray occlusionTexture;
+uniform float occlusionStrength;
+#endif
+
+#ifdef USE_NORMAL_TEXTURE
+uniform sampler2DArray normalTexture;
+uniform float normalScale;
+#endif
+
+in vec3 pos;
+in vec3 nor;
+in vec4 col;
+flat in float textureLayer;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+#ifdef USE_UVS
+    vec3 tex_coords = vec3((textureTransformation * vec3(uvs, 1.0)).xy, textureLayer);
+#endif
+
+    vec4 surface_color = albedo * col;
+#ifdef USE_ALBEDO_TEXTURE
+    surface_color *= texture(albedoTexture, tex_coords);
+#endif
+#ifdef ALPHACUT
+    if (surface_color.a < acut) discard;
+#endif
+
+    float metallic_factor = metallic;
+    float roughness_factor = roughness;
+#ifdef USE_METALLIC_ROUGHNESS_TEXTURE
+    vec2 t = texture(metallicRoughnessTexture, tex_coords).gb;
+    roughness_factor *= t.x;
+    metallic_factor *= t.y;
+#endif
+
+    float occlusion = 1.0;
+#ifdef USE_OCCLUSION_TEXTURE
+    occlusion = mix(1.0, texture(occlusionTexture, tex_coords).r, occlusionStrength);
+#endif
+
+#ifdef USE_NORMAL_TEXTURE
+    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
+    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+    normal = tbn * ((2.0 * texture(normalTexture, tex_coords).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
+#endif
+
+    vec3 total_emissive = emissive.rgb;
+#ifdef USE_EMISSIVE_TEXTURE
+    total_emissive *= texture(emissiveTexture, tex_coords).rgb;
+#endif
+
+    outColor.rgb = total_emissive + calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, metallic_factor, roughness_factor, occlusion);
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    outColor.a = surface_color.a;
+}
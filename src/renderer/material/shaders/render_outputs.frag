@@ -0,0 +1,25 @@
+
+#ifdef OUTPUT_IDS
+layout (location = 0) out vec4 outId;
+uniform float outputId;
+#endif
+
+#ifdef OUTPUT_LINEAR_DEPTH
+#ifdef OUTPUT_IDS
+layout (location = 1) out vec4 outLinearDepth;
+#else
+layout (location = 0) out vec4 outLinearDepth;
+#endif
+uniform mat4 outputInverseProjection;
+#endif
+
+void main()
+{
+#ifdef OUTPUT_IDS
+    outId = vec4(outputId, 0.0, 0.0, 1.0);
+#endif
+#ifdef OUTPUT_LINEAR_DEPTH
+    vec4 p = outputInverseProjection * vec4(0.0, 0.0, 2.0 * gl_FragCoord.z - 1.0, 1.0);
+    outLinearDepth = vec4(-p.z / p.w, 0.0, 0.0, 1.0);
+#endif
+}
@@ -0,0 +1,16 @@
+uniform vec4 surfaceColor;
+uniform sampler2DArray tex;
+uniform mat3 textureTransformation;
+
+in vec2 uvs;
+in vec4 col;
+flat in float textureLayer;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    outColor = surfaceColor * col;
+    outColor *= texture(tex, vec3((textureTransformation * vec3(uvs, 1.0)).xy, textureLayer));
+    outColor.rgb = color_mapping(outColor.rgb);
+}
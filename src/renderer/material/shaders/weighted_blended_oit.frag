@@ -0,0 +1,12 @@
+
+layout (location = 1) out vec4 outWeight;
+
+// Returns the weighted premultiplied color accumulated in the first render target and writes the weighted alpha to the second render target,
+// using the depth based weight function from "Weighted Blended Order-Independent Transparency" by McGuire and Bavoil.
+vec4 weighted_blended_oit(vec4 color)
+{
+    float a = clamp(color.a, 0.0, 1.0);
+    float weight = clamp(pow(min(1.0, a * 10.0) + 0.01, 3.0) * 1e8 * pow(1.0 - gl_FragCoord.z * 0.9, 3.0), 1e-2, 3e3);
+    outWeight = vec4(a * weight, 0.0, 0.0, a);
+    return vec4(color.rgb * a * weight, a);
+}
@@ -0,0 +1,104 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::sync::Arc;
+
+///
+/// A material similar to [ColorMaterial] which samples a layer of a [Texture2DArray] instead of a single texture,
+/// where the layer is given per vertex (see [BatchedMesh::new_with_texture_layers]) or per instance (see [Instances::texture_layers]).
+///
+/// This makes it possible to render objects which only differ by texture in the same draw call,
+/// by packing the textures into one texture array and merging the objects into a [BatchedMesh] or an [InstancedMesh].
+/// Geometries without texture layers use the first layer. Use [PhysicalTextureArrayMaterial] for objects with physical materials.
+///
+#[derive(Clone)]
+pub struct TextureArrayMaterial {
+    /// Base surface color which is multiplied with the texture.
+    pub color: Srgba,
+    /// The texture array, where all textures must have the same size and format.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub texture: Arc<Texture2DArray>,
+    /// A transformation applied to the uv coordinates before reading a texel value at those uv coordinates.
+    pub texture_transformation: Mat3,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
+    pub is_transparent: bool,
+}
+
+impl TextureArrayMaterial {
+    ///
+    /// Constructs a new opaque material by packing the given textures into a texture array, where the index of each texture is its layer.
+    /// Textures in sRGB (`RgbU8` and `RgbaU8`) are converted to linear sRGB.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no textures are given or if the textures do not have the same size and format.
+    ///
+    pub fn new(context: &Context, cpu_textures: &[&CpuTexture]) -> Result<Self, CoreError> {
+        let cpu_textures = cpu_textures
+            .iter()
+            .map(|cpu_texture| {
+                let mut cpu_texture = (*cpu_texture).clone();
+                if let TextureData::RgbU8(_) | TextureData::RgbaU8(_) = cpu_texture.data {
+                    cpu_texture.data.to_linear_srgb();
+                }
+                cpu_texture
+            })
+            .collect::<Vec<_>>();
+        Ok(Self {
+            color: Srgba::WHITE,
+            texture: Arc::new(Texture2DArray::new(
+                context,
+                &cpu_textures.iter().collect::<Vec<_>>(),
+            )?),
+            texture_transformation: Mat3::identity(),
+            render_states: RenderStates::default(),
+            is_transparent: false,
+        })
+    }
+}
+
+impl Material for TextureArrayMaterial {
+    fn id(&self) -> u16 {
+        0b1u16 << 15 | 0b1u16 << 13 | 0b1u16 << 12
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        let mut shader = String::new();
+        shader.push_str(include_str!("../../core/shared.frag"));
+        shader.push_str(ColorMapping::fragment_shader_source());
+        shader.push_str(include_str!("shaders/texture_array_material.frag"));
+        shader
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            color: true,
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
+        camera.color_mapping.use_uniforms(program);
+        program.use_uniform("surfaceColor", self.color.to_linear_srgb());
+        program.use_uniform("textureTransformation", self.texture_transformation);
+        program.use_texture_array("tex", &self.texture);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.is_transparent {
+            MaterialType::Transparent
+        } else {
+            MaterialType::Opaque
+        }
+    }
+
+    fn shader_defines(&self) -> ShaderDefines {
+        ShaderDefines::new(["USE_TEXTURE_LAYERS"])
+    }
+}
@@ -26,6 +26,10 @@ mod model;
 #[doc(inline)]
 pub use model::*;
 
+mod static_scene_batch;
+#[doc(inline)]
+pub use static_scene_batch::*;
+
 mod instanced_model;
 #[doc(inline)]
 pub use instanced_model::*;
@@ -42,6 +46,22 @@ mod imposters;
 #[doc(inline)]
 pub use imposters::*;
 
+mod analytic_imposters;
+#[doc(inline)]
+pub use analytic_imposters::*;
+
+mod particle_system;
+#[doc(inline)]
+pub use particle_system::*;
+
+mod imposter_cloud;
+#[doc(inline)]
+pub use imposter_cloud::*;
+
+mod debug_draw;
+#[doc(inline)]
+pub use debug_draw::*;
+
 mod terrain;
 #[doc(inline)]
 pub use terrain::*;
@@ -54,6 +74,14 @@ mod axes;
 #[doc(inline)]
 pub use axes::*;
 
+mod text;
+#[doc(inline)]
+pub use text::*;
+
+mod trail;
+#[doc(inline)]
+pub use trail::*;
+
 use crate::core::*;
 use crate::renderer::*;
 
@@ -0,0 +1,421 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A set of spheres which are ray traced in the fragment shader on camera facing billboards,
+/// which gives perfect silhouettes and correct depth at the cost of only six vertices per sphere.
+/// Useful for rendering a large number of spheres, for example atoms in molecular visualizations or nodes in graphs.
+///
+/// When rendered as an [Object], the spheres are shaded using the [LightingModel] given by the `lighting_model` field.
+/// When rendered as a [Geometry] with another [Material], the billboards are rendered instead of the spheres.
+///
+pub struct SphereImposters {
+    context: Context,
+    imposters: AnalyticImposters,
+    /// The metallic value used when shading the spheres.
+    pub metallic: f32,
+    /// The roughness value used when shading the spheres.
+    pub roughness: f32,
+    /// The lighting model used when shading the spheres.
+    pub lighting_model: LightingModel,
+}
+
+impl SphereImposters {
+    ///
+    /// Creates a new set of spheres with the given centers, radii and colors.
+    /// If no colors are given, the spheres are white.
+    ///
+    /// # Panic
+    /// Will panic if the number of radii or colors does not match the number of centers.
+    ///
+    pub fn new(
+        context: &Context,
+        centers: &[Vec3],
+        radii: &[f32],
+        colors: Option<&[Srgba]>,
+    ) -> Self {
+        let mut imposters = AnalyticImposters::new(context, true);
+        imposters.set_spheres(centers, radii, colors);
+        Self {
+            context: context.clone(),
+            imposters,
+            metallic: 0.0,
+            roughness: 1.0,
+            lighting_model: LightingModel::Blinn,
+        }
+    }
+
+    ///
+    /// Updates the centers, radii and colors of the spheres. The number of centers also determines the number of spheres.
+    ///
+    /// # Panic
+    /// Will panic if the number of radii or colors does not match the number of centers.
+    ///
+    pub fn set_spheres(&mut self, centers: &[Vec3], radii: &[f32], colors: Option<&[Srgba]>) {
+        self.imposters.set_spheres(centers, radii, colors);
+    }
+}
+
+///
+/// A set of cylinders and cones (capped cones with different radii at each end) which are ray traced in the fragment shader
+/// on an oriented bounding box, which gives perfect silhouettes and correct depth at a low vertex cost.
+/// Useful for rendering a large number of cylinders, for example bonds in molecular visualizations or edges in graphs.
+///
+/// When rendered as an [Object], the cylinders are shaded using the [LightingModel] given by the `lighting_model` field.
+/// When rendered as a [Geometry] with another [Material], the bounding boxes are rendered instead of the cylinders.
+///
+pub struct CylinderImposters {
+    context: Context,
+    imposters: AnalyticImposters,
+    /// The metallic value used when shading the cylinders.
+    pub metallic: f32,
+    /// The roughness value used when shading the cylinders.
+    pub roughness: f32,
+    /// The lighting model used when shading the cylinders.
+    pub lighting_model: LightingModel,
+}
+
+impl CylinderImposters {
+    ///
+    /// Creates a new set of cylinders, each going from a start point with a start radius to an end point with an end radius.
+    /// If the radii are different, the shape is a cone and an end radius of zero gives a pointed cone.
+    /// If no colors are given, the cylinders are white.
+    ///
+    /// # Panic
+    /// Will panic if the number of end points, radii or colors does not match the number of start points.
+    ///
+    pub fn new(
+        context: &Context,
+        starts: &[Vec3],
+        ends: &[Vec3],
+        start_radii: &[f32],
+        end_radii: &[f32],
+        colors: Option<&[Srgba]>,
+    ) -> Self {
+        let mut imposters = AnalyticImposters::new(context, false);
+        imposters.set_cylinders(starts, ends, start_radii, end_radii, colors);
+        Self {
+            context: context.clone(),
+            imposters,
+            metallic: 0.0,
+            roughness: 1.0,
+            lighting_model: LightingModel::Blinn,
+        }
+    }
+
+    ///
+    /// Updates the end points, radii and colors of the cylinders. The number of start points also determines the number of cylinders.
+    ///
+    /// # Panic
+    /// Will panic if the number of end points, radii or colors does not match the number of start points.
+    ///
+    pub fn set_cylinders(
+        &mut self,
+        starts: &[Vec3],
+        ends: &[Vec3],
+        start_radii: &[f32],
+        end_radii: &[f32],
+        colors: Option<&[Srgba]>,
+    ) {
+        self.imposters
+            .set_cylinders(starts, ends, start_radii, end_radii, colors);
+    }
+}
+
+macro_rules! impl_analytic_imposters {
+    ($name:ident, $id:expr) => {
+        impl<'a> IntoIterator for &'a $name {
+            type Item = &'a dyn Object;
+            type IntoIter = std::iter::Once<&'a dyn Object>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                std::iter::once(self)
+            }
+        }
+
+        impl Geometry for $name {
+            fn draw(
+                &self,
+                camera: &Camera,
+                program: &Program,
+                render_states: RenderStates,
+                _attributes: FragmentAttributes,
+            ) {
+                self.imposters.draw(camera, program, render_states);
+            }
+
+            fn vertex_shader_source(&self, _required_attributes: FragmentAttributes) -> String {
+                self.imposters.vertex_shader_source()
+            }
+
+            fn id(&self, _required_attributes: FragmentAttributes) -> u16 {
+                0b1u16 << 15 | $id
+            }
+
+            fn render_with_material(
+                &self,
+                material: &dyn Material,
+                camera: &Camera,
+                lights: &[&dyn Light],
+            ) {
+                render_with_material(&self.context, camera, self, material, lights);
+            }
+
+            fn render_with_effect(
+                &self,
+                material: &dyn Effect,
+                camera: &Camera,
+                lights: &[&dyn Light],
+                color_texture: Option<ColorTexture>,
+                depth_texture: Option<DepthTexture>,
+            ) {
+                render_with_effect(
+                    &self.context,
+                    camera,
+                    self,
+                    material,
+                    lights,
+                    color_texture,
+                    depth_texture,
+                )
+            }
+
+            fn aabb(&self) -> AxisAlignedBoundingBox {
+                self.imposters.aabb
+            }
+        }
+
+        impl Object for $name {
+            fn render(&self, camera: &Camera, lights: &[&dyn Light]) {
+                self.imposters.render(
+                    camera,
+                    lights,
+                    self.metallic,
+                    self.roughness,
+                    self.lighting_model,
+                );
+            }
+
+            fn material_type(&self) -> MaterialType {
+                MaterialType::Opaque
+            }
+        }
+    };
+}
+
+impl_analytic_imposters!(SphereImposters, 0b110u16);
+impl_analytic_imposters!(CylinderImposters, 0b111u16);
+
+struct AnalyticImposters {
+    context: Context,
+    sphere: bool,
+    positions: VertexBuffer,
+    starts: InstanceBuffer,
+    ends: InstanceBuffer,
+    colors: InstanceBuffer,
+    aabb: AxisAlignedBoundingBox,
+}
+
+impl AnalyticImposters {
+    fn new(context: &Context, sphere: bool) -> Self {
+        let positions = if sphere {
+            vec![
+                vec3(-1.0, -1.0, 0.0),
+                vec3(1.0, -1.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(-1.0, 1.0, 0.0),
+                vec3(-1.0, -1.0, 0.0),
+            ]
+        } else {
+            CpuMesh::cube().positions.to_f32()
+        };
+        Self {
+            context: context.clone(),
+            sphere,
+            positions: VertexBuffer::new_with_data(context, &positions),
+            starts: InstanceBuffer::new(context),
+            ends: InstanceBuffer::new(context),
+            colors: InstanceBuffer::new(context),
+            aabb: AxisAlignedBoundingBox::EMPTY,
+        }
+    }
+
+    fn set_spheres(&mut self, centers: &[Vec3], radii: &[f32], colors: Option<&[Srgba]>) {
+        if radii.len() != centers.len() {
+            panic!(
+                "the number of radii ({}) must match the number of centers ({})",
+                radii.len(),
+                centers.len()
+            );
+        }
+        let spheres = centers
+            .iter()
+            .zip(radii)
+            .map(|(c, r)| c.extend(*r))
+            .collect::<Vec<_>>();
+        self.aabb = AxisAlignedBoundingBox::new_with_positions(
+            &spheres
+                .iter()
+                .flat_map(|s| {
+                    let r = vec3(s.w, s.w, s.w);
+                    [s.truncate() - r, s.truncate() + r]
+                })
+                .collect::<Vec<_>>(),
+        );
+        self.starts.fill(&spheres);
+        self.ends.fill(&spheres);
+        self.set_colors(centers.len(), colors);
+    }
+
+    fn set_cylinders(
+        &mut self,
+        starts: &[Vec3],
+        ends: &[Vec3],
+        start_radii: &[f32],
+        end_radii: &[f32],
+        colors: Option<&[Srgba]>,
+    ) {
+        let count = starts.len();
+        if ends.len() != count || start_radii.len() != count || end_radii.len() != count {
+            panic!(
+                "the number of end points ({}), start radii ({}) and end radii ({}) must match the number of start points ({})",
+                ends.len(),
+                start_radii.len(),
+                end_radii.len(),
+                count
+            );
+        }
+        let starts = starts
+            .iter()
+            .zip(start_radii)
+            .map(|(p, r)| p.extend(*r))
+            .collect::<Vec<_>>();
+        let ends = ends
+            .iter()
+            .zip(end_radii)
+            .map(|(p, r)| p.extend(*r))
+            .collect::<Vec<_>>();
+        self.aabb = AxisAlignedBoundingBox::new_with_positions(
+            &starts
+                .iter()
+                .chain(ends.iter())
+                .flat_map(|s| {
+                    let r = vec3(s.w, s.w, s.w);
+                    [s.truncate() - r, s.truncate() + r]
+                })
+                .collect::<Vec<_>>(),
+        );
+        self.starts.fill(&starts);
+        self.ends.fill(&ends);
+        self.set_colors(count, colors);
+    }
+
+    fn set_colors(&mut self, count: usize, colors: Option<&[Srgba]>) {
+        let colors = if let Some(colors) = colors {
+            if colors.len() != count {
+                panic!(
+                    "the number of colors ({}) must match the number of instances ({})",
+                    colors.len(),
+                    count
+                );
+            }
+            colors
+                .iter()
+                .map(|c| c.to_linear_srgb())
+                .collect::<Vec<_>>()
+        } else {
+            vec![vec4(1.0, 1.0, 1.0, 1.0); count]
+        };
+        self.colors.fill(&colors);
+    }
+
+    fn vertex_shader_source(&self) -> String {
+        format!(
+            "{}{}",
+            if self.sphere { "#define SPHERE\n" } else { "" },
+            include_str!("shaders/analytic_imposters.vert")
+        )
+    }
+
+    fn draw(&self, camera: &Camera, program: &Program, render_states: RenderStates) {
+        let is_orthographic = matches!(
+            camera.projection_type(),
+            three_d_asset::ProjectionType::Orthographic { .. }
+        );
+        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform_if_required("eye", camera.position());
+        program.use_uniform_if_required("viewDirection", camera.view_direction());
+        program.use_uniform_if_required("isOrthographic", is_orthographic as i32);
+        program.use_vertex_attribute("position", &self.positions);
+        program.use_instance_attribute("start", &self.starts);
+        if program.requires_attribute("end") {
+            program.use_instance_attribute("end", &self.ends);
+        }
+        if program.requires_attribute("instance_color") {
+            program.use_instance_attribute("instance_color", &self.colors);
+        }
+        program.draw_arrays_instanced(
+            render_states,
+            camera.viewport(),
+            self.positions.vertex_count(),
+            self.starts.instance_count(),
+        );
+    }
+
+    fn render(
+        &self,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        metallic: f32,
+        roughness: f32,
+        lighting_model: LightingModel,
+    ) {
+        let mut id = if self.sphere {
+            b"sphere_imposters".to_vec()
+        } else {
+            b"cylinder_imposters".to_vec()
+        };
+        id.extend(lights.iter().map(|l| l.id()));
+        let mut programs = self.context.programs.write().unwrap();
+        let program = programs.entry(id).or_insert_with(|| {
+            let mut fragment_shader_source = String::new();
+            if self.sphere {
+                fragment_shader_source.push_str("#define SPHERE\n");
+            }
+            if !lights.is_empty() {
+                fragment_shader_source.push_str("#define USE_LIGHTS\n");
+                fragment_shader_source.push_str(&lights_shader_source(lights, lighting_model));
+            }
+            fragment_shader_source.push_str(ToneMapping::fragment_shader_source());
+            fragment_shader_source.push_str(ColorMapping::fragment_shader_source());
+            fragment_shader_source.push_str(include_str!("shaders/analytic_imposters.frag"));
+            Program::from_source(
+                &self.context,
+                &self.vertex_shader_source(),
+                &fragment_shader_source,
+            )
+            .expect("Failed compiling shader")
+        });
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
+        camera.color_mapping.use_uniforms(program);
+        if !lights.is_empty() {
+            program.use_uniform_if_required("cameraPosition", camera.position());
+            for (i, light) in lights.iter().enumerate() {
+                light.use_uniforms(program, i as u32);
+            }
+            program.use_uniform_if_required("metallic", metallic);
+            program.use_uniform_if_required("roughness", roughness);
+        }
+        self.draw(
+            camera,
+            program,
+            RenderStates {
+                cull: if self.sphere { Cull::None } else { Cull::Front },
+                ..Default::default()
+            },
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use crate::renderer::*;
+use std::sync::{Arc, Mutex};
+
+///
+/// Immediate mode drawing of lines, bounding boxes, spheres, axes and text for debugging, for example physics, AI or culling built on top of this crate.
+///
+/// The draw calls, for example [DebugDrawHandle::line], can be issued from anywhere during the frame, also from other threads using a [DebugDraw::handle],
+/// and are batched and rendered together when [DebugDraw::flush] is called at the end of the frame, after which they are cleared.
+/// The lines are drawn with a constant width in pixels given by [DebugDraw::line_width] and the text is drawn facing the camera with a constant height in pixels.
+///
+pub struct DebugDraw {
+    context: Context,
+    handle: DebugDrawHandle,
+    lines: Option<Mesh>,
+    texts: Vec<Text>,
+    /// The width of the lines in physical pixels. The default is 2.
+    pub line_width: f32,
+    /// The height of the text in physical pixels. The default is 16.
+    pub text_size: f32,
+    /// Whether or not the lines are hidden behind the objects in the scene, which requires that the depth of the scene is in the render target.
+    /// The default is false, ie. the lines are always drawn on top. The text is always tested against the depth.
+    pub depth_test: bool,
+}
+
+impl DebugDraw {
+    ///
+    /// Creates a new debug draw with no draw calls.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            handle: DebugDrawHandle::default(),
+            lines: None,
+            texts: Vec::new(),
+            line_width: 2.0,
+            text_size: 16.0,
+            depth_test: false,
+        }
+    }
+
+    ///
+    /// Returns a handle which can be stored, for example in a physics or AI system, and used to issue draw calls from anywhere, also from other threads.
+    /// All handles add draw calls to this debug draw.
+    ///
+    pub fn handle(&self) -> DebugDrawHandle {
+        self.handle.clone()
+    }
+
+    ///
+    /// Renders all draw calls issued since the last flush into the given render target using the given camera, and then clears the draw calls.
+    ///
+    pub fn flush(&mut self, target: &RenderTarget, camera: &Camera) {
+        let commands = std::mem::take(&mut *self.handle.commands.lock().unwrap());
+
+        if !commands.lines.is_empty() {
+            let mut positions = Vec::with_capacity(commands.lines.len() * 6);
+            let mut colors = Vec::with_capacity(commands.lines.len() * 6);
+            for (p0, p1, color) in commands.lines.iter() {
+                let direction = p1 - p0;
+                let eye = match camera.projection_type() {
+                    three_d_asset::ProjectionType::Perspective { .. } => {
+                        0.5 * (p0 + p1) - *camera.position()
+                    }
+                    three_d_asset::ProjectionType::Orthographic { .. } => camera.view_direction(),
+                };
+                let side = direction.cross(eye);
+                let side = if side.magnitude2() > 0.0 {
+                    side.normalize()
+                } else {
+                    camera.right_direction()
+                };
+                let w0 = side * 0.5 * self.line_width * units_per_pixel(camera, *p0);
+                let w1 = side * 0.5 * self.line_width * units_per_pixel(camera, *p1);
+                positions.extend([p0 - w0, p1 - w1, p1 + w1, p1 + w1, p0 + w0, p0 - w0]);
+                colors.extend([*color; 6]);
+            }
+            // Reuse the line buffers from the previous frames and fill the unused vertices with degenerate triangles,
+            // so the buffers are only reallocated when the number of lines grows beyond or shrinks far below the capacity
+            let capacity = 6 * commands.lines.len().next_power_of_two();
+            if let Some(mesh) = self
+                .lines
+                .as_mut()
+                .filter(|mesh| (capacity..=4 * capacity).contains(&(mesh.vertex_count() as usize)))
+            {
+                let vertex_count = mesh.vertex_count() as usize;
+                positions.resize(vertex_count, vec3(0.0, 0.0, 0.0));
+                colors.resize(vertex_count, Srgba::new(0, 0, 0, 0));
+                mesh.update_positions(&positions);
+                mesh.update_colors(&colors);
+            } else {
+                positions.resize(capacity, vec3(0.0, 0.0, 0.0));
+                colors.resize(capacity, Srgba::new(0, 0, 0, 0));
+                self.lines = Some(Mesh::new(
+                    &self.context,
+                    &CpuMesh {
+                        positions: Positions::F32(positions),
+                        colors: Some(colors),
+                        ..Default::default()
+                    },
+                ));
+            }
+            let material = ColorMaterial {
+                color: Srgba::WHITE,
+                render_states: RenderStates {
+                    depth_test: if self.depth_test {
+                        DepthTest::LessOrEqual
+                    } else {
+                        DepthTest::Always
+                    },
+                    write_mask: WriteMask::COLOR,
+                    blend: Blend::TRANSPARENCY,
+                    ..Default::default()
+                },
+                is_transparent: true,
+                ..Default::default()
+            };
+            target.render_with_material(&material, camera, self.lines.as_ref(), &[]);
+        }
+
+        if !commands.texts.is_empty() {
+            // Reuse the text objects from the previous frames so only the changed glyphs are uploaded
+            for (i, (position, text, color)) in commands.texts.iter().enumerate() {
+                if i == self.texts.len() {
+                    self.texts.push(Text::new(&self.context, text));
+                }
+                let label = &mut self.texts[i];
+                label.set_text(text);
+                label.color = *color;
+                label.size = self.text_size * units_per_pixel(camera, *position);
+                let right = camera.right_direction().normalize();
+                let up = right.cross(camera.view_direction()).normalize();
+                let rotation = Mat4::from_cols(
+                    right.extend(0.0),
+                    up.extend(0.0),
+                    right.cross(up).extend(0.0),
+                    vec4(0.0, 0.0, 0.0, 1.0),
+                );
+                label.set_transformation(Mat4::from_translation(*position) * rotation);
+            }
+            target.render(camera, &self.texts[..commands.texts.len()], &[]);
+        }
+        self.texts
+            .truncate(commands.texts.len().max(self.texts.len() / 2));
+    }
+}
+
+impl std::ops::Deref for DebugDraw {
+    type Target = DebugDrawHandle;
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+#[derive(Default)]
+struct DebugCommands {
+    lines: Vec<(Vec3, Vec3, Srgba)>,
+    texts: Vec<(Vec3, String, Srgba)>,
+}
+
+///
+/// A handle used to issue draw calls to a [DebugDraw], see [DebugDraw::handle].
+/// The handle is cheap to clone and can be sent to other threads.
+///
+#[derive(Clone, Default)]
+pub struct DebugDrawHandle {
+    commands: Arc<Mutex<DebugCommands>>,
+}
+
+impl DebugDrawHandle {
+    ///
+    /// Draws a line between the two given points in world space.
+    ///
+    pub fn line(&self, p0: Vec3, p1: Vec3, color: Srgba) {
+        self.commands.lock().unwrap().lines.push((p0, p1, color));
+    }
+
+    ///
+    /// Draws the edges of the given axis aligned bounding box. Does nothing if the bounding box is empty or infinite.
+    ///
+    pub fn aabb(&self, aabb: &AxisAlignedBoundingBox, color: Srgba) {
+        if aabb.is_empty() || !aabb.size().magnitude().is_finite() {
+            return;
+        }
+        let (min, max) = (aabb.min(), aabb.max());
+        let corner = |i: usize| {
+            vec3(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+        let mut commands = self.commands.lock().unwrap();
+        for i in 0..8 {
+            for axis in [1, 2, 4] {
+                if i & axis == 0 {
+                    commands.lines.push((corner(i), corner(i | axis), color));
+                }
+            }
+        }
+    }
+
+    ///
+    /// Draws a sphere with the given center and radius as three circles, one around each axis.
+    ///
+    pub fn sphere(&self, center: Vec3, radius: f32, color: Srgba) {
+        const SEGMENTS: usize = 32;
+        let mut commands = self.commands.lock().unwrap();
+        for (u, v) in [
+            (Vec3::unit_x(), Vec3::unit_y()),
+            (Vec3::unit_y(), Vec3::unit_z()),
+            (Vec3::unit_z(), Vec3::unit_x()),
+        ] {
+            let point = |i: usize| {
+                let angle = i as f32 * 2.0 * std::f32::consts::PI / SEGMENTS as f32;
+                center + (u * angle.cos() + v * angle.sin()) * radius
+            };
+            for i in 0..SEGMENTS {
+                commands.lines.push((point(i), point(i + 1), color));
+            }
+        }
+    }
+
+    ///
+    /// Draws the x-axis (red), the y-axis (green) and the z-axis (blue) of the coordinate system given by the transformation, with the given length.
+    ///
+    pub fn axis(&self, transformation: Mat4, length: f32) {
+        let origin = (transformation * vec4(0.0, 0.0, 0.0, 1.0)).truncate();
+        let mut commands = self.commands.lock().unwrap();
+        for (axis, color) in [
+            (Vec3::unit_x(), Srgba::RED),
+            (Vec3::unit_y(), Srgba::GREEN),
+            (Vec3::unit_z(), Srgba::BLUE),
+        ] {
+            let direction = (transformation * axis.extend(0.0)).truncate();
+            if direction.magnitude2() > 0.0 {
+                commands
+                    .lines
+                    .push((origin, origin + direction.normalize() * length, color));
+            }
+        }
+    }
+
+    ///
+    /// Draws the given text facing the camera with the top left corner at the given position in world space.
+    ///
+    pub fn text3d(&self, position: Vec3, text: &str, color: Srgba) {
+        self.commands
+            .lock()
+            .unwrap()
+            .texts
+            .push((position, text.to_owned(), color));
+    }
+
+    ///
+    /// Removes all draw calls issued since the last flush.
+    ///
+    pub fn clear(&self) {
+        *self.commands.lock().unwrap() = DebugCommands::default();
+    }
+
+    ///
+    /// Returns whether or not there are any draw calls since the last flush.
+    ///
+    pub fn is_empty(&self) -> bool {
+        let commands = self.commands.lock().unwrap();
+        commands.lines.is_empty() && commands.texts.is_empty()
+    }
+}
+
+// The length in world space of a physical pixel at the given position.
+fn units_per_pixel(camera: &Camera, position: Vec3) -> f32 {
+    camera.pixel_size_at(
+        (position - *camera.position())
+            .dot(camera.view_direction())
+            .max(camera.z_near()),
+    )
+}
@@ -0,0 +1,395 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Defines how the billboards of an [ImposterCloud] are oriented towards the camera.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BillboardOrientation {
+    /// The billboards always face the camera, which is useful for round shapes like smoke and clouds.
+    Spherical,
+    /// The billboards rotate around the given axis trying to face the camera, which is useful for upright shapes like trees and people.
+    Cylindrical(Vec3),
+}
+
+///
+/// A single textured billboard in an [ImposterCloud].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CloudImposter {
+    /// The center of the billboard in world space.
+    pub position: Vec3,
+    /// The width and height of the billboard in world space.
+    pub size: Vec2,
+    /// The rotation of the billboard around its center in the billboard plane.
+    pub rotation: Radians,
+    /// The index of the frame in the texture atlas, counted row by row starting from the top left frame, see [ImposterCloud::frames].
+    pub frame: u32,
+    /// The color which is multiplied with the color from the texture atlas.
+    pub color: Srgba,
+}
+
+impl Default for CloudImposter {
+    fn default() -> Self {
+        Self {
+            position: vec3(0.0, 0.0, 0.0),
+            size: vec2(1.0, 1.0),
+            rotation: radians(0.0),
+            frame: 0,
+            color: Srgba::WHITE,
+        }
+    }
+}
+
+///
+/// A large number of camera facing textured billboards, for example puffs of smoke in a cloud or people in a distant crowd,
+/// rendered in a single draw call. Each billboard shows one frame of a texture atlas, which is a texture divided into a grid of equally sized frames,
+/// so different billboards can show different images, for example different people or the same person seen from different angles.
+///
+/// The billboards are stored on the GPU packed into two vectors per billboard (the position and frame, and the size and rotation) and a color,
+/// so updating the billboards only requires a single upload of these buffers, see [ImposterCloud::set_imposters].
+///
+/// When rendered as an [Object], the billboards are alpha blended by default, in which case [ImposterCloud::sort] should be called when the camera moves,
+/// or rendered as opaque with the transparent parts cut away, see [ImposterCloud::alpha_cutout].
+/// Use [ImposterCloud::render_soft] to fade out the billboards where they intersect the rest of the scene.
+/// When rendered as a [Geometry] with another [Material], the billboards are rendered with that material instead.
+///
+pub struct ImposterCloud {
+    context: Context,
+    imposters: Vec<CloudImposter>,
+    position_frames: InstanceBuffer,
+    shapes: InstanceBuffer,
+    colors: InstanceBuffer,
+    aabb: AxisAlignedBoundingBox,
+    /// The texture atlas containing the frames shown on the billboards.
+    pub texture: Texture2DRef,
+    /// The number of columns and rows of frames in the texture atlas.
+    pub frames: (u32, u32),
+    /// Defines how the billboards are oriented towards the camera.
+    pub orientation: BillboardOrientation,
+    /// Defines how the billboards are blended with the content of the render target. Not used if [ImposterCloud::alpha_cutout] is specified.
+    pub blend_mode: ParticleBlendMode,
+    /// If specified, the billboards are rendered as opaque, writing depth, and the parts where the alpha value is below this value are cut away,
+    /// which does not require the billboards to be sorted. This is useful for example for distant crowds and vegetation.
+    pub alpha_cutout: Option<f32>,
+    /// The distance in world space over which the billboards fade out in front of the scene when using [ImposterCloud::render_soft].
+    pub softness: f32,
+}
+
+impl ImposterCloud {
+    ///
+    /// Creates a new imposter cloud with the given billboards showing frames from the given texture atlas with the given number of columns and rows.
+    ///
+    pub fn new(
+        context: &Context,
+        imposters: &[CloudImposter],
+        texture: Texture2DRef,
+        frames: (u32, u32),
+        orientation: BillboardOrientation,
+    ) -> Self {
+        let mut cloud = Self {
+            context: context.clone(),
+            imposters: Vec::new(),
+            position_frames: InstanceBuffer::new(context),
+            shapes: InstanceBuffer::new(context),
+            colors: InstanceBuffer::new(context),
+            aabb: AxisAlignedBoundingBox::EMPTY,
+            texture,
+            frames,
+            orientation,
+            blend_mode: ParticleBlendMode::default(),
+            alpha_cutout: None,
+            softness: 0.5,
+        };
+        cloud.set_imposters(imposters);
+        cloud
+    }
+
+    ///
+    /// Returns the billboards.
+    ///
+    pub fn imposters(&self) -> &[CloudImposter] {
+        &self.imposters
+    }
+
+    ///
+    /// Updates the billboards. The number of billboards can be different from before.
+    ///
+    pub fn set_imposters(&mut self, imposters: &[CloudImposter]) {
+        self.imposters = imposters.to_vec();
+        self.aabb = AxisAlignedBoundingBox::new_with_positions(
+            &imposters
+                .iter()
+                .flat_map(|i| {
+                    // The billboard can rotate in any direction
+                    let r = 0.5 * i.size.magnitude();
+                    let r = vec3(r, r, r);
+                    [i.position - r, i.position + r]
+                })
+                .collect::<Vec<_>>(),
+        );
+        self.update_buffers();
+    }
+
+    ///
+    /// Sorts the billboards from back to front as seen from the given camera, which is needed for correct alpha blending.
+    /// Not needed if the [ImposterCloud::blend_mode] is [ParticleBlendMode::Additive] or [ImposterCloud::alpha_cutout] is specified.
+    ///
+    pub fn sort(&mut self, camera: &Camera) {
+        let position = *camera.position();
+        let view_direction = camera.view_direction();
+        let is_orthographic = matches!(
+            camera.projection_type(),
+            three_d_asset::ProjectionType::Orthographic { .. }
+        );
+        let distance = |p: Vec3| {
+            if is_orthographic {
+                (p - position).dot(view_direction)
+            } else {
+                (p - position).magnitude2()
+            }
+        };
+        self.imposters.sort_by(|a, b| {
+            distance(b.position)
+                .partial_cmp(&distance(a.position))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        self.update_buffers();
+    }
+
+    ///
+    /// Renders the billboards and fades them out where they are closer to the scene behind them than the [ImposterCloud::softness] distance,
+    /// which removes the hard edges where the billboards intersect the scene.
+    /// The depth texture must contain the depth of the scene rendered with the same camera, for example by rendering the opaque objects into a [DepthTexture2D] first.
+    /// The depth texture cannot be the depth target that is written to in the same render call.
+    ///
+    pub fn render_soft(&self, camera: &Camera, depth_texture: &DepthTexture2D) {
+        self.render_imposters(camera, Some(depth_texture));
+    }
+
+    fn update_buffers(&mut self) {
+        self.position_frames.fill(
+            &self
+                .imposters
+                .iter()
+                .map(|i| i.position.extend(i.frame as f32))
+                .collect::<Vec<_>>(),
+        );
+        self.shapes.fill(
+            &self
+                .imposters
+                .iter()
+                .map(|i| vec4(i.size.x, i.size.y, i.rotation.0, 0.0))
+                .collect::<Vec<_>>(),
+        );
+        self.colors.fill(
+            &self
+                .imposters
+                .iter()
+                .map(|i| i.color.to_linear_srgb())
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    fn vertex_shader_source(&self) -> String {
+        format!(
+            "{}{}",
+            if let BillboardOrientation::Cylindrical(_) = self.orientation {
+                "#define CYLINDRICAL\n"
+            } else {
+                ""
+            },
+            include_str!("shaders/imposter_cloud.vert")
+        )
+    }
+
+    fn draw(&self, camera: &Camera, program: &Program, render_states: RenderStates) {
+        let view_direction = camera.view_direction();
+        let right = view_direction.cross(*camera.up()).normalize();
+        let up = right.cross(view_direction);
+        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform("eye", camera.position());
+        program.use_uniform("viewDirection", view_direction);
+        program.use_uniform("cameraRight", right);
+        program.use_uniform("cameraUp", up);
+        program.use_uniform(
+            "isOrthographic",
+            matches!(
+                camera.projection_type(),
+                three_d_asset::ProjectionType::Orthographic { .. }
+            ) as i32,
+        );
+        if let BillboardOrientation::Cylindrical(axis) = self.orientation {
+            program.use_uniform("axis", axis.normalize());
+        }
+        program.use_uniform(
+            "frames",
+            vec2(self.frames.0.max(1) as f32, self.frames.1.max(1) as f32),
+        );
+        program.use_instance_attribute("positionFrame", &self.position_frames);
+        program.use_instance_attribute("shape", &self.shapes);
+        if program.requires_attribute("instanceColor") {
+            program.use_instance_attribute("instanceColor", &self.colors);
+        }
+        program.draw_arrays_instanced(
+            render_states,
+            camera.viewport(),
+            6,
+            self.position_frames.instance_count(),
+        );
+    }
+
+    fn render_imposters(&self, camera: &Camera, depth_texture: Option<&DepthTexture2D>) {
+        let additive = self.blend_mode == ParticleBlendMode::Additive;
+        let cutout = self.alpha_cutout.is_some();
+        let mut id = b"imposter_cloud".to_vec();
+        id.extend([
+            matches!(self.orientation, BillboardOrientation::Cylindrical(_)) as u8,
+            depth_texture.is_some() as u8,
+            additive as u8,
+            cutout as u8,
+        ]);
+        let mut programs = self.context.programs.write().unwrap();
+        let program = programs.entry(id).or_insert_with(|| {
+            let mut fragment_shader_source = String::new();
+            if depth_texture.is_some() {
+                fragment_shader_source.push_str("#define USE_SOFT_PARTICLES\n");
+            }
+            if cutout {
+                fragment_shader_source.push_str("#define ALPHA_CUTOUT\n");
+            } else if additive {
+                fragment_shader_source.push_str("#define ADDITIVE\n");
+            }
+            fragment_shader_source.push_str(ToneMapping::fragment_shader_source());
+            fragment_shader_source.push_str(ColorMapping::fragment_shader_source());
+            fragment_shader_source.push_str(include_str!("shaders/imposter_cloud.frag"));
+            Program::from_source(
+                &self.context,
+                &self.vertex_shader_source(),
+                &fragment_shader_source,
+            )
+            .expect("Failed compiling shader")
+        });
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
+        camera.color_mapping.use_uniforms(program);
+        program.use_uniform("textureTransformation", self.texture.transformation);
+        program.use_texture("tex", &self.texture);
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            program.use_uniform("alphaCutout", alpha_cutout);
+        }
+        if let Some(depth_texture) = depth_texture {
+            let viewport = camera.viewport();
+            program.use_depth_texture("depthMap", depth_texture);
+            program.use_uniform(
+                "viewport",
+                vec4(
+                    viewport.x as f32,
+                    viewport.y as f32,
+                    viewport.width as f32,
+                    viewport.height as f32,
+                ),
+            );
+            program.use_uniform("zNear", camera.z_near());
+            program.use_uniform("zFar", camera.z_far());
+            program.use_uniform("softness", self.softness.max(0.0001));
+        }
+        let render_states = if cutout {
+            RenderStates {
+                cull: Cull::None,
+                ..Default::default()
+            }
+        } else {
+            RenderStates {
+                write_mask: WriteMask::COLOR,
+                blend: if additive {
+                    Blend::ADD
+                } else {
+                    Blend::TRANSPARENCY
+                },
+                cull: Cull::None,
+                ..Default::default()
+            }
+        };
+        self.draw(camera, program, render_states);
+    }
+}
+
+impl<'a> IntoIterator for &'a ImposterCloud {
+    type Item = &'a dyn Object;
+    type IntoIter = std::iter::Once<&'a dyn Object>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for ImposterCloud {
+    fn draw(
+        &self,
+        camera: &Camera,
+        program: &Program,
+        render_states: RenderStates,
+        _attributes: FragmentAttributes,
+    ) {
+        self.draw(camera, program, render_states);
+    }
+
+    fn vertex_shader_source(&self, _required_attributes: FragmentAttributes) -> String {
+        self.vertex_shader_source()
+    }
+
+    fn id(&self, _required_attributes: FragmentAttributes) -> u16 {
+        match self.orientation {
+            BillboardOrientation::Spherical => 0b1u16 << 15 | 0b1010u16,
+            BillboardOrientation::Cylindrical(_) => 0b1u16 << 15 | 0b1011u16,
+        }
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        render_with_material(&self.context, camera, self, material, lights);
+    }
+
+    fn render_with_effect(
+        &self,
+        material: &dyn Effect,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        render_with_effect(
+            &self.context,
+            camera,
+            self,
+            material,
+            lights,
+            color_texture,
+            depth_texture,
+        )
+    }
+
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        self.aabb
+    }
+}
+
+impl Object for ImposterCloud {
+    fn render(&self, camera: &Camera, _lights: &[&dyn Light]) {
+        self.render_imposters(camera, None);
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.alpha_cutout.is_some() {
+            MaterialType::Opaque
+        } else {
+            MaterialType::Transparent
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Defines how and where new particles are emitted in a [GpuParticleSystem] and how they behave during their lifetime.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct ParticleEmitter {
+    /// The center of the emitter.
+    pub position: Vec3,
+    /// The half size of the box around the emitter position in which new particles are placed at random.
+    pub position_spread: Vec3,
+    /// The number of particles emitted per second.
+    pub rate: f32,
+    /// The time in seconds a particle is alive.
+    pub lifetime: f32,
+    /// The maximum random deviation in seconds from the lifetime.
+    pub lifetime_spread: f32,
+    /// The initial velocity of a particle.
+    pub velocity: Vec3,
+    /// The maximum length of the random vector added to the initial velocity of a particle.
+    pub velocity_spread: f32,
+    /// The acceleration applied to all particles, for example gravity.
+    pub acceleration: Vec3,
+    /// The size of the billboard of a particle when it is emitted.
+    pub start_size: f32,
+    /// The size of the billboard of a particle at the end of its lifetime.
+    pub end_size: f32,
+    /// The color of a particle when it is emitted.
+    pub start_color: Srgba,
+    /// The color of a particle at the end of its lifetime.
+    pub end_color: Srgba,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            position: vec3(0.0, 0.0, 0.0),
+            position_spread: vec3(0.0, 0.0, 0.0),
+            rate: 100.0,
+            lifetime: 2.0,
+            lifetime_spread: 0.0,
+            velocity: vec3(0.0, 1.0, 0.0),
+            velocity_spread: 0.5,
+            acceleration: vec3(0.0, 0.0, 0.0),
+            start_size: 0.1,
+            end_size: 0.1,
+            start_color: Srgba::WHITE,
+            end_color: Srgba::new(255, 255, 255, 0),
+        }
+    }
+}
+
+///
+/// Defines how the particles of a [GpuParticleSystem] are blended with the content of the render target.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ParticleBlendMode {
+    /// The particle colors are multiplied by their alpha value and added to the render target, which is useful for example for fire, sparks and magic effects.
+    /// The result does not depend on the order in which the particles are rendered.
+    Additive,
+    /// The particles are alpha blended with the render target, which is useful for example for smoke and dust.
+    #[default]
+    Alpha,
+}
+
+///
+/// A particle system where the particles are emitted, simulated and rendered on the GPU.
+/// The simulation is done using [Program::transform_feedback] on a [PingPongBuffer],
+/// so a large number of particles can be simulated without any data transfer between the CPU and GPU.
+///
+/// Call [GpuParticleSystem::update] each frame to emit new particles and move the existing ones.
+/// When rendered as an [Object], the particles are rendered as camera facing billboards, optionally with a texture, using the given [ParticleBlendMode].
+/// Use [GpuParticleSystem::render_soft] to fade out the particles where they intersect the rest of the scene (soft particles).
+/// When rendered as a [Geometry] with another [Material], the billboards are rendered with that material instead.
+///
+pub struct GpuParticleSystem {
+    context: Context,
+    position_ages: PingPongBuffer,
+    velocity_lifetimes: PingPongBuffer,
+    max_count: u32,
+    next_index: u32,
+    emission_remainder: f32,
+    burst_count: u32,
+    seed: i32,
+    /// The configuration of the emitter, which can be changed at any time and affects the particles emitted afterwards.
+    pub emitter: ParticleEmitter,
+    /// Defines how the particles are blended with the content of the render target.
+    pub blend_mode: ParticleBlendMode,
+    /// An optional texture applied to each particle billboard. If no texture is given, the particles are rendered as round dots with a soft edge.
+    pub texture: Option<Texture2DRef>,
+    /// The distance in world space over which the particles fade out in front of the scene when using [GpuParticleSystem::render_soft].
+    pub softness: f32,
+}
+
+impl GpuParticleSystem {
+    ///
+    /// Creates a new particle system with room for at most `max_count` particles alive at the same time and with the given emitter.
+    /// Initially, no particles are alive.
+    ///
+    pub fn new(context: &Context, max_count: u32, emitter: ParticleEmitter) -> Self {
+        let max_count = max_count.max(1);
+        let (position_ages, velocity_lifetimes) = Self::dead_particles(max_count);
+        Self {
+            context: context.clone(),
+            position_ages: PingPongBuffer::new_with_data(context, &position_ages),
+            velocity_lifetimes: PingPongBuffer::new_with_data(context, &velocity_lifetimes),
+            max_count,
+            next_index: 0,
+            emission_remainder: 0.0,
+            burst_count: 0,
+            seed: 0,
+            emitter,
+            blend_mode: ParticleBlendMode::default(),
+            texture: None,
+            softness: 0.5,
+        }
+    }
+
+    ///
+    /// Returns the maximum number of particles alive at the same time.
+    /// When more particles are emitted, the oldest particles are replaced.
+    ///
+    pub fn max_count(&self) -> u32 {
+        self.max_count
+    }
+
+    ///
+    /// Emits the given number of particles in addition to the continuous emission on the next call to [GpuParticleSystem::update], for example for an explosion.
+    ///
+    pub fn emit(&mut self, count: u32) {
+        self.burst_count = (self.burst_count + count).min(self.max_count);
+    }
+
+    ///
+    /// Removes all particles.
+    ///
+    pub fn reset(&mut self) {
+        let (position_ages, velocity_lifetimes) = Self::dead_particles(self.max_count);
+        self.position_ages.fill(&position_ages);
+        self.velocity_lifetimes.fill(&velocity_lifetimes);
+        self.next_index = 0;
+        self.emission_remainder = 0.0;
+        self.burst_count = 0;
+    }
+
+    ///
+    /// Emits new particles according to the emitter and moves the existing particles forward in time.
+    /// The elapsed time is the time in milliseconds since the last update, for example the elapsed time given by the frame input of the window.
+    ///
+    pub fn update(&mut self, elapsed_time: f64) {
+        let delta_time = (elapsed_time * 0.001) as f32;
+        let emission = self.emitter.rate.max(0.0) * delta_time + self.emission_remainder;
+        let emitted = emission.floor();
+        self.emission_remainder = emission - emitted;
+        let spawn_count = (emitted as u32 + self.burst_count).min(self.max_count);
+        self.burst_count = 0;
+        self.seed = self.seed.wrapping_add(1);
+
+        let mut programs = self.context.programs.write().unwrap();
+        let program = programs
+            .entry(b"particle_system_update".to_vec())
+            .or_insert_with(|| {
+                Program::from_vertex_source_with_transform_feedback(
+                    &self.context,
+                    include_str!("shaders/particle_system_update.vert"),
+                    &["outPositionAge", "outVelocityLifetime"],
+                )
+                .expect("Failed compiling shader")
+            });
+        program.use_uniform("deltaTime", delta_time);
+        program.use_uniform_if_required("seed", self.seed);
+        program.use_uniform("spawnStart", self.next_index as i32);
+        program.use_uniform("spawnCount", spawn_count as i32);
+        program.use_uniform("maxCount", self.max_count as i32);
+        program.use_uniform_if_required("emitterPosition", self.emitter.position);
+        program.use_uniform_if_required("positionSpread", self.emitter.position_spread);
+        program.use_uniform_if_required("velocity", self.emitter.velocity);
+        program.use_uniform_if_required("velocitySpread", self.emitter.velocity_spread);
+        program.use_uniform_if_required("acceleration", self.emitter.acceleration);
+        program.use_uniform_if_required("lifetime", self.emitter.lifetime);
+        program.use_uniform_if_required("lifetimeSpread", self.emitter.lifetime_spread);
+        program.use_vertex_attribute("positionAge", self.position_ages.source());
+        program.use_vertex_attribute("velocityLifetime", self.velocity_lifetimes.source());
+        program.transform_feedback(
+            self.max_count,
+            &[
+                self.position_ages.target(),
+                self.velocity_lifetimes.target(),
+            ],
+        );
+        self.position_ages.swap();
+        self.velocity_lifetimes.swap();
+        self.next_index = (self.next_index + spawn_count) % self.max_count;
+    }
+
+    ///
+    /// Renders the particles and fades them out where they are closer to the scene behind them than the [GpuParticleSystem::softness] distance,
+    /// which removes the hard edges where the particle billboards intersect the scene.
+    /// The depth texture must contain the depth of the scene rendered with the same camera, for example by rendering the opaque objects into a [DepthTexture2D] first.
+    /// The depth texture cannot be the depth target that is written to in the same render call.
+    ///
+    pub fn render_soft(&self, camera: &Camera, depth_texture: &DepthTexture2D) {
+        self.render_particles(camera, Some(depth_texture));
+    }
+
+    fn render_particles(&self, camera: &Camera, depth_texture: Option<&DepthTexture2D>) {
+        let additive = self.blend_mode == ParticleBlendMode::Additive;
+        let mut id = b"particle_system".to_vec();
+        id.extend([
+            self.texture.is_some() as u8,
+            depth_texture.is_some() as u8,
+            additive as u8,
+        ]);
+        let mut programs = self.context.programs.write().unwrap();
+        let program = programs.entry(id).or_insert_with(|| {
+            let mut fragment_shader_source = String::new();
+            if self.texture.is_some() {
+                fragment_shader_source.push_str("#define USE_TEXTURE\n");
+            }
+            if depth_texture.is_some() {
+                fragment_shader_source.push_str("#define USE_SOFT_PARTICLES\n");
+            }
+            if additive {
+                fragment_shader_source.push_str("#define ADDITIVE\n");
+            }
+            fragment_shader_source.push_str(ToneMapping::fragment_shader_source());
+            fragment_shader_source.push_str(ColorMapping::fragment_shader_source());
+            fragment_shader_source.push_str(include_str!("shaders/particle_system.frag"));
+            Program::from_source(
+                &self.context,
+                include_str!("shaders/particle_system.vert"),
+                &fragment_shader_source,
+            )
+            .expect("Failed compiling shader")
+        });
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
+        camera.color_mapping.use_uniforms(program);
+        if let Some(ref texture) = self.texture {
+            program.use_uniform("textureTransformation", texture.transformation);
+            program.use_texture("tex", texture);
+        }
+        if let Some(depth_texture) = depth_texture {
+            let viewport = camera.viewport();
+            program.use_depth_texture("depthMap", depth_texture);
+            program.use_uniform(
+                "viewport",
+                vec4(
+                    viewport.x as f32,
+                    viewport.y as f32,
+                    viewport.width as f32,
+                    viewport.height as f32,
+                ),
+            );
+            program.use_uniform("zNear", camera.z_near());
+            program.use_uniform("zFar", camera.z_far());
+            program.use_uniform(
+                "isOrthographic",
+                matches!(
+                    camera.projection_type(),
+                    three_d_asset::ProjectionType::Orthographic { .. }
+                ) as i32,
+            );
+            program.use_uniform("softness", self.softness.max(0.0001));
+        }
+        self.draw_particles(
+            camera,
+            program,
+            RenderStates {
+                write_mask: WriteMask::COLOR,
+                blend: if additive {
+                    Blend::ADD
+                } else {
+                    Blend::TRANSPARENCY
+                },
+                cull: Cull::None,
+                ..Default::default()
+            },
+        );
+    }
+
+    fn draw_particles(&self, camera: &Camera, program: &Program, render_states: RenderStates) {
+        let view_direction = camera.view_direction();
+        let right = view_direction.cross(*camera.up()).normalize();
+        let up = right.cross(view_direction);
+        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform("cameraRight", right);
+        program.use_uniform("cameraUp", up);
+        program.use_uniform("startSize", self.emitter.start_size);
+        program.use_uniform("endSize", self.emitter.end_size);
+        program.use_uniform_if_required("startColor", self.emitter.start_color.to_linear_srgb());
+        program.use_uniform_if_required("endColor", self.emitter.end_color.to_linear_srgb());
+        program.use_vertex_attribute_with_divisor("positionAge", self.position_ages.source(), 1);
+        program.use_vertex_attribute_with_divisor(
+            "velocityLifetime",
+            self.velocity_lifetimes.source(),
+            1,
+        );
+        program.draw_arrays_instanced(render_states, camera.viewport(), 6, self.max_count);
+    }
+
+    fn dead_particles(max_count: u32) -> (Vec<Vec4>, Vec<Vec4>) {
+        // A particle is dead when its age is larger than its lifetime
+        (
+            vec![vec4(0.0, 0.0, 0.0, 1.0); max_count as usize],
+            vec![vec4(0.0, 0.0, 0.0, 0.0); max_count as usize],
+        )
+    }
+}
+
+impl<'a> IntoIterator for &'a GpuParticleSystem {
+    type Item = &'a dyn Object;
+    type IntoIter = std::iter::Once<&'a dyn Object>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for GpuParticleSystem {
+    fn draw(
+        &self,
+        camera: &Camera,
+        program: &Program,
+        render_states: RenderStates,
+        _attributes: FragmentAttributes,
+    ) {
+        self.draw_particles(camera, program, render_states);
+    }
+
+    fn vertex_shader_source(&self, _required_attributes: FragmentAttributes) -> String {
+        include_str!("shaders/particle_system.vert").to_owned()
+    }
+
+    fn id(&self, _required_attributes: FragmentAttributes) -> u16 {
+        0b1u16 << 15 | 0b1000u16
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        render_with_material(&self.context, camera, self, material, lights);
+    }
+
+    fn render_with_effect(
+        &self,
+        material: &dyn Effect,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        render_with_effect(
+            &self.context,
+            camera,
+            self,
+            material,
+            lights,
+            color_texture,
+            depth_texture,
+        )
+    }
+
+    ///
+    /// Returns a conservative estimate of the bounding box of the particles, computed from the emitter configuration.
+    ///
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        let e = &self.emitter;
+        let lifetime = (e.lifetime + e.lifetime_spread.abs()).max(0.0);
+        let travel = lifetime * (e.velocity.magnitude() + e.velocity_spread.abs())
+            + 0.5 * e.acceleration.magnitude() * lifetime * lifetime
+            + 0.5 * e.start_size.abs().max(e.end_size.abs());
+        let extent = e.position_spread.map(|v| v.abs()) + vec3(travel, travel, travel);
+        AxisAlignedBoundingBox::new_with_positions(&[e.position - extent, e.position + extent])
+    }
+
+    fn is_animated(&self) -> bool {
+        true
+    }
+}
+
+impl Object for GpuParticleSystem {
+    fn render(&self, camera: &Camera, _lights: &[&dyn Light]) {
+        self.render_particles(camera, None);
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Transparent
+    }
+}
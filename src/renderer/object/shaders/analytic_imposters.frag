@@ -0,0 +1,101 @@
+uniform mat4 viewProjection;
+uniform vec3 eye;
+uniform vec3 viewDirection;
+uniform int isOrthographic;
+uniform vec3 cameraPosition;
+uniform float metallic;
+uniform float roughness;
+
+in vec3 pos;
+in vec4 col;
+flat in vec4 shapeStart;
+flat in vec4 shapeEnd;
+
+layout (location = 0) out vec4 outColor;
+
+float dot2(vec3 v) { return dot(v, v); }
+
+// Returns the distance along the ray and the normal at the intersection or a negative distance if there is no intersection.
+vec4 intersect_sphere(vec3 ro, vec3 rd, vec3 center, float radius)
+{
+    vec3 oc = ro - center;
+    float b = dot(oc, rd);
+    float c = dot(oc, oc) - radius * radius;
+    float h = b * b - c;
+    if (h < 0.0) return vec4(-1.0);
+    float t = -b - sqrt(h);
+    return vec4(t, normalize(oc + t * rd));
+}
+
+// Capped cone from pa with radius ra to pb with radius rb.
+vec4 intersect_capped_cone(vec3 ro, vec3 rd, vec3 pa, vec3 pb, float ra, float rb)
+{
+    vec3 ba = pb - pa;
+    vec3 oa = ro - pa;
+    vec3 ob = ro - pb;
+    float m0 = dot(ba, ba);
+    float m1 = dot(oa, ba);
+    float m2 = dot(rd, ba);
+    float m3 = dot(rd, oa);
+    float m5 = dot(oa, oa);
+    float m9 = dot(ob, ba);
+
+    // caps
+    if (m1 < 0.0) {
+        if (dot2(oa * m2 - rd * m1) < ra * ra * m2 * m2) {
+            return vec4(-m1 / m2, -ba * inversesqrt(m0));
+        }
+    } else if (m9 > 0.0) {
+        float t = -m9 / m2;
+        if (dot2(ob + rd * t) < rb * rb) {
+            return vec4(t, ba * inversesqrt(m0));
+        }
+    }
+
+    // body
+    float rr = ra - rb;
+    float hy = m0 + rr * rr;
+    float k2 = m0 * m0 - m2 * m2 * hy;
+    float k1 = m0 * m0 * m3 - m1 * m2 * hy + m0 * ra * (rr * m2);
+    float k0 = m0 * m0 * m5 - m1 * m1 * hy + m0 * ra * (rr * m1 * 2.0 - m0 * ra);
+    float h = k1 * k1 - k2 * k0;
+    if (h < 0.0) return vec4(-1.0);
+    float t = (-k1 - sqrt(h)) / k2;
+    float y = m1 + t * m2;
+    if (y < 0.0 || y > m0) return vec4(-1.0);
+    return vec4(t, normalize(m0 * (m0 * (oa + t * rd) + rr * ba * ra) - ba * hy * y));
+}
+
+void main()
+{
+    vec3 rd;
+    vec3 ro;
+    float extent = 2.0 * max(shapeStart.w, shapeEnd.w) + length(shapeEnd.xyz - shapeStart.xyz);
+    if (isOrthographic == 1) {
+        rd = normalize(viewDirection);
+        ro = pos - extent * rd;
+    } else {
+        rd = normalize(pos - eye);
+        ro = eye;
+    }
+
+#ifdef SPHERE
+    vec4 hit = intersect_sphere(ro, rd, shapeStart.xyz, shapeStart.w);
+#else
+    vec4 hit = intersect_capped_cone(ro, rd, shapeStart.xyz, shapeEnd.xyz, shapeStart.w, shapeEnd.w);
+#endif
+    if (hit.x < 0.0) discard;
+
+    vec3 position = ro + hit.x * rd;
+    vec3 normal = hit.yzw;
+    vec4 clip = viewProjection * vec4(position, 1.0);
+    float ndcDepth = clip.z / clip.w;
+    gl_FragDepth = 0.5 * (gl_DepthRange.diff * ndcDepth + gl_DepthRange.far + gl_DepthRange.near);
+
+    outColor = col;
+#ifdef USE_LIGHTS
+    outColor.rgb = calculate_lighting(cameraPosition, col.rgb, position, normal, metallic, roughness, 1.0);
+#endif
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+}
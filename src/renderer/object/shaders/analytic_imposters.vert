@@ -0,0 +1,53 @@
+uniform mat4 viewProjection;
+uniform vec3 eye;
+uniform vec3 viewDirection;
+uniform int isOrthographic;
+
+in vec3 position;
+in vec4 start;
+in vec4 end;
+in vec4 instance_color;
+
+out vec3 pos;
+out vec3 nor;
+out vec2 uvs;
+out vec4 col;
+flat out vec4 shapeStart;
+flat out vec4 shapeEnd;
+
+void main()
+{
+    shapeStart = start;
+    shapeEnd = end;
+    col = instance_color;
+    uvs = 0.5 * position.xy + 0.5;
+
+#ifdef SPHERE
+    // A billboard which covers the silhouette of the sphere
+    vec3 center = start.xyz;
+    float radius = start.w;
+    vec3 z = isOrthographic == 1 ? -viewDirection : normalize(eye - center);
+    vec3 t = abs(z.y) > 0.99 ? vec3(1.0, 0.0, 0.0) : vec3(0.0, 1.0, 0.0);
+    vec3 x = normalize(cross(t, z));
+    vec3 y = cross(z, x);
+    float scale = radius;
+    if (isOrthographic == 0) {
+        float d = length(eye - center);
+        scale = d > 1.01 * radius ? radius * d / sqrt(d * d - radius * radius) : 10.0 * radius;
+    }
+    pos = center + scale * (x * position.x + y * position.y);
+    nor = z;
+#else
+    // An oriented box which covers the cylinder or cone
+    vec3 axis = end.xyz - start.xyz;
+    float len = max(length(axis), 0.00001);
+    vec3 u = axis / len;
+    vec3 t = abs(u.y) > 0.99 ? vec3(1.0, 0.0, 0.0) : vec3(0.0, 1.0, 0.0);
+    vec3 v = normalize(cross(t, u));
+    vec3 w = cross(u, v);
+    float radius = max(start.w, end.w);
+    pos = start.xyz + u * ((0.5 * position.x + 0.5) * len) + radius * (v * position.y + w * position.z);
+    nor = normalize(v * position.y + w * position.z);
+#endif
+    gl_Position = viewProjection * vec4(pos, 1.0);
+}
@@ -0,0 +1,56 @@
+
+uniform sampler2D tex;
+uniform mat3 textureTransformation;
+
+#ifdef ALPHA_CUTOUT
+uniform float alphaCutout;
+#endif
+
+#ifdef USE_SOFT_PARTICLES
+uniform sampler2D depthMap;
+uniform vec4 viewport;
+uniform float zNear;
+uniform float zFar;
+uniform int isOrthographic;
+uniform float softness;
+
+float linear_depth(float depth)
+{
+    float z = 2.0 * depth - 1.0;
+    return isOrthographic == 1 ? 0.5 * (z + 1.0) * (zFar - zNear) + zNear : 2.0 * zNear * zFar / (zFar + zNear - z * (zFar - zNear));
+}
+#endif
+
+in vec3 pos;
+in vec2 uvs;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 color = col * texture(tex, (textureTransformation * vec3(uvs, 1.0)).xy);
+
+#ifdef ALPHA_CUTOUT
+    if (color.a < alphaCutout) discard;
+    color.a = 1.0;
+#endif
+
+#ifdef USE_SOFT_PARTICLES
+    // Fade out the billboard where it is close to the scene behind it
+    vec2 screenUv = (gl_FragCoord.xy - viewport.xy) / viewport.zw;
+    float sceneDepth = linear_depth(texture(depthMap, screenUv).x);
+    float fragmentDepth = linear_depth(gl_FragCoord.z);
+    color.a *= clamp((sceneDepth - fragmentDepth) / softness, 0.0, 1.0);
+#endif
+
+    if (color.a < 0.001) discard;
+
+    outColor.rgb = tone_mapping(color.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+#ifdef ADDITIVE
+    outColor = vec4(outColor.rgb * color.a, 0.0);
+#else
+    outColor.a = color.a;
+#endif
+}
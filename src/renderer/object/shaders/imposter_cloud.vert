@@ -0,0 +1,59 @@
+uniform mat4 viewProjection;
+uniform vec3 eye;
+uniform vec3 viewDirection;
+uniform vec3 cameraRight;
+uniform vec3 cameraUp;
+uniform int isOrthographic;
+uniform vec2 frames;
+#ifdef CYLINDRICAL
+uniform vec3 axis;
+#endif
+
+in vec4 positionFrame;
+in vec4 shape;
+in vec4 instanceColor;
+
+out vec3 pos;
+out vec3 nor;
+out vec2 uvs;
+out vec4 col;
+
+const vec2 corners[6] = vec2[6](
+    vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0),
+    vec2(1.0, 1.0), vec2(-1.0, 1.0), vec2(-1.0, -1.0)
+);
+
+void main()
+{
+    vec2 corner = corners[gl_VertexID];
+    vec3 center = positionFrame.xyz;
+    vec3 z = isOrthographic == 1 ? -viewDirection : normalize(eye - center);
+
+    // The billboard axes, falling back to the camera axes when the billboard is seen along the up direction
+#ifdef CYLINDRICAL
+    vec3 y = axis;
+    vec3 x = cross(y, z);
+    x = length(x) > 0.0001 ? normalize(x) : cameraRight;
+    z = cross(x, y);
+#else
+    vec3 x = cross(cameraUp, z);
+    x = length(x) > 0.0001 ? normalize(x) : cameraRight;
+    vec3 y = cross(z, x);
+#endif
+
+    float c = cos(shape.z);
+    float s = sin(shape.z);
+    vec2 p = 0.5 * shape.xy * corner;
+    p = vec2(c * p.x - s * p.y, s * p.x + c * p.y);
+    pos = center + x * p.x + y * p.y;
+    nor = z;
+    col = instanceColor;
+
+    // The frames are counted row by row from the top left corner of the atlas
+    float frame = positionFrame.w + 0.5;
+    float column = mod(floor(frame), frames.x);
+    float row = floor(frame / frames.x);
+    uvs = (vec2(column, frames.y - 1.0 - row) + 0.5 * corner + 0.5) / frames;
+
+    gl_Position = viewProjection * vec4(pos, 1.0);
+}
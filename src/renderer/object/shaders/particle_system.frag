@@ -0,0 +1,54 @@
+#ifdef USE_TEXTURE
+uniform sampler2D tex;
+uniform mat3 textureTransformation;
+#endif
+
+#ifdef USE_SOFT_PARTICLES
+uniform sampler2D depthMap;
+uniform vec4 viewport;
+uniform float zNear;
+uniform float zFar;
+uniform int isOrthographic;
+uniform float softness;
+
+float linear_depth(float depth)
+{
+    float z = 2.0 * depth - 1.0;
+    return isOrthographic == 1 ? 0.5 * (z + 1.0) * (zFar - zNear) + zNear : 2.0 * zNear * zFar / (zFar + zNear - z * (zFar - zNear));
+}
+#endif
+
+in vec3 pos;
+in vec2 uvs;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 color = col;
+#ifdef USE_TEXTURE
+    color *= texture(tex, (textureTransformation * vec3(uvs, 1.0)).xy);
+#else
+    // A round particle with a soft edge
+    color.a *= 1.0 - smoothstep(0.5, 1.0, length(2.0 * uvs - 1.0));
+#endif
+
+#ifdef USE_SOFT_PARTICLES
+    // Fade out the particle where it is close to the scene behind it
+    vec2 screenUv = (gl_FragCoord.xy - viewport.xy) / viewport.zw;
+    float sceneDepth = linear_depth(texture(depthMap, screenUv).x);
+    float fragmentDepth = linear_depth(gl_FragCoord.z);
+    color.a *= clamp((sceneDepth - fragmentDepth) / softness, 0.0, 1.0);
+#endif
+
+    if (color.a < 0.001) discard;
+
+    outColor.rgb = tone_mapping(color.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+#ifdef ADDITIVE
+    outColor = vec4(outColor.rgb * color.a, 0.0);
+#else
+    outColor.a = color.a;
+#endif
+}
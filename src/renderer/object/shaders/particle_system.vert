@@ -0,0 +1,33 @@
+uniform mat4 viewProjection;
+uniform vec3 cameraRight;
+uniform vec3 cameraUp;
+uniform float startSize;
+uniform float endSize;
+uniform vec4 startColor;
+uniform vec4 endColor;
+
+in vec4 positionAge;
+in vec4 velocityLifetime;
+
+out vec3 pos;
+out vec3 nor;
+out vec2 uvs;
+out vec4 col;
+
+const vec2 corners[6] = vec2[6](
+    vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0),
+    vec2(1.0, 1.0), vec2(-1.0, 1.0), vec2(-1.0, -1.0)
+);
+
+void main()
+{
+    vec2 corner = corners[gl_VertexID];
+    float t = positionAge.w / max(velocityLifetime.w, 0.00001);
+    // Dead particles collapse to a point and are therefore not rasterized
+    float size = t < 1.0 ? mix(startSize, endSize, t) : 0.0;
+    col = mix(startColor, endColor, clamp(t, 0.0, 1.0));
+    uvs = 0.5 * corner + 0.5;
+    nor = normalize(cross(cameraRight, cameraUp));
+    pos = positionAge.xyz + 0.5 * size * (cameraRight * corner.x + cameraUp * corner.y);
+    gl_Position = viewProjection * vec4(pos, 1.0);
+}
@@ -0,0 +1,53 @@
+uniform float deltaTime;
+uniform int seed;
+uniform int spawnStart;
+uniform int spawnCount;
+uniform int maxCount;
+uniform vec3 emitterPosition;
+uniform vec3 positionSpread;
+uniform vec3 velocity;
+uniform float velocitySpread;
+uniform vec3 acceleration;
+uniform float lifetime;
+uniform float lifetimeSpread;
+
+in vec4 positionAge;
+in vec4 velocityLifetime;
+
+out vec4 outPositionAge;
+out vec4 outVelocityLifetime;
+
+float random(uint index)
+{
+    uint h = uint(gl_VertexID) * 4u + index + uint(seed) * 1664525u;
+    h ^= h >> 16;
+    h *= 0x7feb352du;
+    h ^= h >> 15;
+    h *= 0x846ca68bu;
+    h ^= h >> 16;
+    return float(h) / 4294967295.0;
+}
+
+vec3 random_in_sphere()
+{
+    float z = 2.0 * random(0u) - 1.0;
+    float phi = 6.2831853 * random(1u);
+    float r = sqrt(max(1.0 - z * z, 0.0));
+    return vec3(r * cos(phi), r * sin(phi), z) * pow(random(2u), 1.0 / 3.0);
+}
+
+void main()
+{
+    int offset = (gl_VertexID - spawnStart + maxCount) % maxCount;
+    if (offset < spawnCount) {
+        vec3 p = emitterPosition + positionSpread * (2.0 * vec3(random(3u), random(4u), random(5u)) - 1.0);
+        vec3 v = velocity + velocitySpread * random_in_sphere();
+        float l = max(lifetime + lifetimeSpread * (2.0 * random(6u) - 1.0), 0.0);
+        outPositionAge = vec4(p, 0.0);
+        outVelocityLifetime = vec4(v, l);
+    } else {
+        vec3 v = velocityLifetime.xyz + acceleration * deltaTime;
+        outPositionAge = vec4(positionAge.xyz + v * deltaTime, positionAge.w + deltaTime);
+        outVelocityLifetime = vec4(v, velocityLifetime.w);
+    }
+}
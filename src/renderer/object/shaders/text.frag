@@ -0,0 +1,16 @@
+uniform sampler2D glyphAtlas;
+uniform vec4 textColor;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    float coverage = texture(glyphAtlas, uvs).r;
+    if (coverage < 0.5) {
+        discard;
+    }
+    outColor = textColor;
+    outColor.rgb = color_mapping(outColor.rgb);
+}
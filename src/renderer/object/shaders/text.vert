@@ -0,0 +1,29 @@
+uniform mat4 viewProjection;
+uniform mat4 modelMatrix;
+uniform mat3 normalMatrix;
+uniform vec2 glyphSize;
+uniform vec2 cellSize;
+
+in vec2 position;
+in vec4 glyph;
+
+out vec3 pos;
+out vec3 nor;
+out vec3 tang;
+out vec3 bitang;
+out vec2 uvs;
+out vec4 col;
+
+void main()
+{
+    // The glyph contains the offset of the glyph in the text followed by the column and row of the glyph in the atlas
+    vec4 world_pos = modelMatrix * vec4((glyph.xy + position) * glyphSize, 0.0, 1.0);
+    pos = world_pos.xyz / world_pos.w;
+    nor = normalize(normalMatrix * vec3(0.0, 0.0, 1.0));
+    // The u coordinate increases along the x-axis and the v coordinate along the y-axis of the glyph quads
+    tang = normalize(mat3(modelMatrix) * vec3(1.0, 0.0, 0.0));
+    bitang = normalize(cross(nor, tang));
+    uvs = vec2((glyph.z + position.x) * cellSize.x, 1.0 - (glyph.w + 1.0 - position.y) * cellSize.y);
+    col = vec4(1.0);
+    gl_Position = viewProjection * world_pos;
+}
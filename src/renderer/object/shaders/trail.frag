@@ -0,0 +1,17 @@
+in vec2 uvs;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 color = col;
+    // Soften the edges of the ribbon
+    color.a *= 1.0 - smoothstep(0.7, 1.0, abs(2.0 * uvs.y - 1.0));
+
+    if (color.a < 0.001) discard;
+
+    outColor.rgb = tone_mapping(color.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    outColor.a = color.a;
+}
@@ -0,0 +1,30 @@
+uniform mat4 viewProjection;
+uniform vec3 cameraPosition;
+uniform vec3 viewDirection;
+uniform int isOrthographic;
+uniform float startWidth;
+uniform float endWidth;
+uniform vec4 startColor;
+uniform vec4 endColor;
+
+in vec4 positionAge;
+in vec4 directionSide;
+
+out vec3 pos;
+out vec3 nor;
+out vec2 uvs;
+out vec4 col;
+
+void main()
+{
+    float t = clamp(positionAge.w, 0.0, 1.0);
+    vec3 eye = isOrthographic == 1 ? viewDirection : positionAge.xyz - cameraPosition;
+    // The ribbon is spanned by the direction of the trail and the direction orthogonal to both the trail and the view direction
+    vec3 side = cross(directionSide.xyz, eye);
+    side = dot(side, side) > 0.0 ? normalize(side) : vec3(0.0);
+    col = mix(startColor, endColor, t);
+    uvs = vec2(t, 0.5 * directionSide.w + 0.5);
+    nor = -normalize(eye);
+    pos = positionAge.xyz + 0.5 * mix(startWidth, endWidth, t) * directionSide.w * side;
+    gl_Position = viewProjection * vec4(pos, 1.0);
+}
@@ -9,6 +9,7 @@ pub struct Skybox {
     context: Context,
     vertex_buffer: VertexBuffer,
     material: SkyboxMaterial,
+    rotation: Mat3,
 }
 
 impl Skybox {
@@ -128,6 +129,7 @@ impl Skybox {
             context: context.clone(),
             vertex_buffer,
             material: SkyboxMaterial { texture },
+            rotation: Mat3::identity(),
         }
     }
 
@@ -137,6 +139,30 @@ impl Skybox {
     pub fn texture(&self) -> &Arc<TextureCubeMap> {
         &self.material.texture
     }
+
+    ///
+    /// Replaces the cube map texture, for example when switching between environments in a viewer.
+    /// The colors are assumed to be in the same color space as described in [Skybox::new_with_texture].
+    ///
+    pub fn set_texture(&mut self, texture: Arc<TextureCubeMap>) {
+        self.material.texture = texture;
+    }
+
+    ///
+    /// Returns the rotation applied to the skybox, see [Skybox::set_rotation].
+    ///
+    pub fn rotation(&self) -> Mat3 {
+        self.rotation
+    }
+
+    ///
+    /// Sets the rotation of the skybox, for example to align the horizon or the sun of a panorama with the scene.
+    /// The skybox is always centered at the camera and only the rotation of the camera affects the background,
+    /// so this is the only transformation that can be applied.
+    ///
+    pub fn set_rotation(&mut self, rotation: Mat3) {
+        self.rotation = rotation;
+    }
 }
 
 impl<'a> IntoIterator for &'a Skybox {
@@ -158,6 +184,7 @@ impl Geometry for Skybox {
     ) {
         program.use_uniform("view", camera.view());
         program.use_uniform("projection", camera.projection());
+        program.use_uniform("rotation", self.rotation);
         program.use_vertex_attribute("position", &self.vertex_buffer);
         program.draw_arrays(render_states, camera.viewport(), 36);
     }
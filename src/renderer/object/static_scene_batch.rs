@@ -0,0 +1,116 @@
+use crate::renderer::*;
+
+///
+/// A static scene where all meshes sharing a material are merged into one [BatchedMesh], ie. into one vertex and index buffer,
+/// which reduces the number of draw calls and buffer binds to one per material instead of one per mesh.
+/// This is useful for scenes with thousands of small static parts, for example an architectural model.
+/// The meshes cannot be moved after the batch is constructed, use a [Model] for the dynamic parts of the scene.
+///
+/// Each batch is a [Gm] combining a [BatchedMesh] with a material, which can be accessed through [Deref](std::ops::Deref),
+/// for example to change the material or to get the index range of each mesh using [BatchedMesh::ranges].
+///
+pub struct StaticSceneBatch<M: Material>(Vec<Gm<BatchedMesh, M>>);
+
+impl<M: Material + FromCpuMaterial + Clone + Default> StaticSceneBatch<M> {
+    ///
+    /// Constructs a [StaticSceneBatch] from a [CpuModel], where the [CpuMesh]es are transformed by their transformation in the [CpuModel]
+    /// and merged into one [BatchedMesh] per material. The materials are constructed from the [CpuMaterial]s in the [CpuModel],
+    /// and the meshes without a material are merged into one batch with the default material.
+    ///
+    pub fn new(context: &Context, cpu_model: &CpuModel) -> Result<Self, RendererError> {
+        let mut groups: Vec<(Option<usize>, Vec<(&CpuMesh, Mat4)>)> = Vec::new();
+        for primitive in cpu_model.geometries.iter() {
+            if let CpuGeometry::Triangles(geometry) = &primitive.geometry {
+                if let Some(material_index) = primitive.material_index {
+                    if material_index >= cpu_model.materials.len() {
+                        return Err(RendererError::MissingMaterial(
+                            material_index.to_string(),
+                            primitive.name.clone(),
+                        ));
+                    }
+                }
+                let part = (geometry, primitive.transformation);
+                match groups
+                    .iter_mut()
+                    .find(|(index, _)| *index == primitive.material_index)
+                {
+                    Some((_, parts)) => parts.push(part),
+                    None => groups.push((primitive.material_index, vec![part])),
+                }
+            }
+        }
+        Ok(Self(
+            groups
+                .into_iter()
+                .map(|(material_index, parts)| Gm {
+                    geometry: BatchedMesh::new(context, &parts),
+                    material: material_index
+                        .map(|i| M::from_cpu_material(context, &cpu_model.materials[i]))
+                        .unwrap_or_default(),
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl<M: Material> StaticSceneBatch<M> {
+    ///
+    /// Constructs a [StaticSceneBatch] from the given materials and parts, where each part consists of the index of the material in the list of materials,
+    /// a [CpuMesh] and the transformation applied to the mesh. The parts with the same material are merged into one [BatchedMesh]
+    /// and the materials that are not used by any part are ignored.
+    ///
+    pub fn from_parts(
+        context: &Context,
+        materials: Vec<M>,
+        parts: &[(usize, &CpuMesh, Mat4)],
+    ) -> Result<Self, RendererError> {
+        let mut groups: Vec<Vec<(&CpuMesh, Mat4)>> = materials.iter().map(|_| Vec::new()).collect();
+        for (i, (material_index, cpu_mesh, transformation)) in parts.iter().enumerate() {
+            groups
+                .get_mut(*material_index)
+                .ok_or_else(|| {
+                    RendererError::MissingMaterial(
+                        material_index.to_string(),
+                        format!("part {}", i),
+                    )
+                })?
+                .push((*cpu_mesh, *transformation));
+        }
+        Ok(Self(
+            materials
+                .into_iter()
+                .zip(groups)
+                .filter(|(_, parts)| !parts.is_empty())
+                .map(|(material, parts)| Gm {
+                    geometry: BatchedMesh::new(context, &parts),
+                    material,
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl<'a, M: Material> IntoIterator for &'a StaticSceneBatch<M> {
+    type Item = &'a dyn Object;
+    type IntoIter = std::vec::IntoIter<&'a dyn Object>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+            .map(|m| m as &dyn Object)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<M: Material> std::ops::Deref for StaticSceneBatch<M> {
+    type Target = Vec<Gm<BatchedMesh, M>>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<M: Material> std::ops::DerefMut for StaticSceneBatch<M> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
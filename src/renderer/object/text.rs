@@ -0,0 +1,444 @@
+use crate::core::*;
+use crate::renderer::*;
+
+// The printable ASCII characters from ' ' to '~' in a 5x7 pixel font.
+// Each glyph is given as five columns from left to right where the least significant bit is the top row.
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x5F, 0x00, 0x00],
+    [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7F, 0x14, 0x7F, 0x14],
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12],
+    [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50],
+    [0x00, 0x05, 0x03, 0x00, 0x00],
+    [0x00, 0x1C, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1C, 0x00],
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08],
+    [0x08, 0x08, 0x3E, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00],
+    [0x08, 0x08, 0x08, 0x08, 0x08],
+    [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02],
+    [0x3E, 0x51, 0x49, 0x45, 0x3E],
+    [0x00, 0x42, 0x7F, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46],
+    [0x21, 0x41, 0x45, 0x4B, 0x31],
+    [0x18, 0x14, 0x12, 0x7F, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39],
+    [0x3C, 0x4A, 0x49, 0x49, 0x30],
+    [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36],
+    [0x06, 0x49, 0x49, 0x29, 0x1E],
+    [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00],
+    [0x08, 0x14, 0x22, 0x41, 0x00],
+    [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08],
+    [0x02, 0x01, 0x51, 0x09, 0x06],
+    [0x32, 0x49, 0x79, 0x41, 0x3E],
+    [0x7E, 0x11, 0x11, 0x11, 0x7E],
+    [0x7F, 0x49, 0x49, 0x49, 0x36],
+    [0x3E, 0x41, 0x41, 0x41, 0x22],
+    [0x7F, 0x41, 0x41, 0x22, 0x1C],
+    [0x7F, 0x49, 0x49, 0x49, 0x41],
+    [0x7F, 0x09, 0x09, 0x09, 0x01],
+    [0x3E, 0x41, 0x49, 0x49, 0x7A],
+    [0x7F, 0x08, 0x08, 0x08, 0x7F],
+    [0x00, 0x41, 0x7F, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3F, 0x01],
+    [0x7F, 0x08, 0x14, 0x22, 0x41],
+    [0x7F, 0x40, 0x40, 0x40, 0x40],
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F],
+    [0x7F, 0x04, 0x08, 0x10, 0x7F],
+    [0x3E, 0x41, 0x41, 0x41, 0x3E],
+    [0x7F, 0x09, 0x09, 0x09, 0x06],
+    [0x3E, 0x41, 0x51, 0x21, 0x5E],
+    [0x7F, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31],
+    [0x01, 0x01, 0x7F, 0x01, 0x01],
+    [0x3F, 0x40, 0x40, 0x40, 0x3F],
+    [0x1F, 0x20, 0x40, 0x20, 0x1F],
+    [0x3F, 0x40, 0x38, 0x40, 0x3F],
+    [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x07, 0x08, 0x70, 0x08, 0x07],
+    [0x61, 0x51, 0x49, 0x45, 0x43],
+    [0x00, 0x7F, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20],
+    [0x00, 0x41, 0x41, 0x7F, 0x00],
+    [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40],
+    [0x00, 0x01, 0x02, 0x04, 0x00],
+    [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7F, 0x48, 0x44, 0x44, 0x38],
+    [0x38, 0x44, 0x44, 0x44, 0x20],
+    [0x38, 0x44, 0x44, 0x48, 0x7F],
+    [0x38, 0x54, 0x54, 0x54, 0x18],
+    [0x08, 0x7E, 0x09, 0x01, 0x02],
+    [0x0C, 0x52, 0x52, 0x52, 0x3E],
+    [0x7F, 0x08, 0x04, 0x04, 0x78],
+    [0x00, 0x44, 0x7D, 0x40, 0x00],
+    [0x20, 0x40, 0x44, 0x3D, 0x00],
+    [0x7F, 0x10, 0x28, 0x44, 0x00],
+    [0x00, 0x41, 0x7F, 0x40, 0x00],
+    [0x7C, 0x04, 0x18, 0x04, 0x78],
+    [0x7C, 0x08, 0x04, 0x04, 0x78],
+    [0x38, 0x44, 0x44, 0x44, 0x38],
+    [0x7C, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7C],
+    [0x7C, 0x08, 0x04, 0x04, 0x08],
+    [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3F, 0x44, 0x40, 0x20],
+    [0x3C, 0x40, 0x40, 0x20, 0x7C],
+    [0x1C, 0x20, 0x40, 0x20, 0x1C],
+    [0x3C, 0x40, 0x30, 0x40, 0x3C],
+    [0x44, 0x28, 0x10, 0x28, 0x44],
+    [0x0C, 0x50, 0x50, 0x50, 0x3C],
+    [0x44, 0x64, 0x54, 0x4C, 0x44],
+    [0x00, 0x08, 0x36, 0x41, 0x00],
+    [0x00, 0x00, 0x7F, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00],
+    [0x08, 0x04, 0x08, 0x10, 0x08],
+];
+
+// The size in pixels of a cell in the glyph atlas, which includes one pixel of spacing to the right of and below each glyph.
+const CELL_WIDTH: usize = 6;
+const CELL_HEIGHT: usize = 8;
+const ATLAS_COLUMNS: usize = 16;
+const ATLAS_ROWS: usize = 6;
+
+///
+/// A text label drawn with a built-in monospace bitmap font, which is designed for text that changes often,
+/// for example a frame rate counter, a live sensor readout or the value of a slider.
+///
+/// Each character is drawn as an instance of a single quad, so changing the text with [Text::set_text] only writes the changed glyphs into an existing GPU buffer.
+/// The buffer is only reallocated when the text becomes longer than any previous text, and then with room to spare, so the steady state is free of allocations.
+///
+/// The text is placed in the xy-plane of the local coordinate system with the top left corner of the first line at the origin, the lines going in the positive x direction
+/// and new lines, started by `'\n'`, placed below each other in the negative y direction.
+/// Use [Text::set_transformation] to place the text in the world, or render it with an orthographic camera, for example [Camera::new_2d], to place it on the screen.
+/// Characters outside the printable ASCII range are drawn as `'?'`.
+///
+pub struct Text {
+    context: Context,
+    position_buffer: VertexBuffer,
+    glyph_buffer: InstanceBuffer,
+    capacity: u32,
+    glyphs: Vec<Vec4>,
+    previous_glyphs: Vec<Vec4>,
+    text: String,
+    atlas: Texture2D,
+    transformation: Mat4,
+    /// The height of a line of text in the local coordinate system. If the text is rendered with [Camera::new_2d] and no transformation, this is the height in pixels.
+    /// The default is 16.
+    pub size: f32,
+    /// The color of the text.
+    pub color: Srgba,
+}
+
+impl Text {
+    ///
+    /// Creates a new text label showing the given text.
+    ///
+    pub fn new(context: &Context, text: &str) -> Self {
+        let mut atlas_data = vec![0u8; CELL_WIDTH * ATLAS_COLUMNS * CELL_HEIGHT * ATLAS_ROWS];
+        for (index, glyph) in GLYPHS.iter().enumerate() {
+            let (cell_x, cell_y) = (index % ATLAS_COLUMNS, index / ATLAS_COLUMNS);
+            for (column, bits) in glyph.iter().enumerate() {
+                for row in 0..7 {
+                    if bits & (1 << row) != 0 {
+                        let x = cell_x * CELL_WIDTH + column;
+                        let y = cell_y * CELL_HEIGHT + row;
+                        atlas_data[y * CELL_WIDTH * ATLAS_COLUMNS + x] = 255;
+                    }
+                }
+            }
+        }
+        let mut atlas = Texture2D::new_empty::<u8>(
+            context,
+            (CELL_WIDTH * ATLAS_COLUMNS) as u32,
+            (CELL_HEIGHT * ATLAS_ROWS) as u32,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        atlas.fill(&atlas_data);
+
+        let mut label = Self {
+            context: context.clone(),
+            position_buffer: VertexBuffer::new_with_data(
+                context,
+                &[
+                    vec2(0.0f32, 0.0),
+                    vec2(1.0, 0.0),
+                    vec2(1.0, 1.0),
+                    vec2(1.0, 1.0),
+                    vec2(0.0, 1.0),
+                    vec2(0.0, 0.0),
+                ],
+            ),
+            glyph_buffer: InstanceBuffer::new(context),
+            capacity: 0,
+            glyphs: Vec::new(),
+            previous_glyphs: Vec::new(),
+            text: String::new(),
+            atlas,
+            transformation: Mat4::identity(),
+            size: 16.0,
+            color: Srgba::WHITE,
+        };
+        label.set_text(text);
+        label
+    }
+
+    ///
+    /// Returns the text which is currently shown.
+    ///
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    ///
+    /// Changes the shown text. Does nothing if the text is unchanged and otherwise only uploads the glyphs that changed.
+    ///
+    pub fn set_text(&mut self, text: &str) {
+        if self.text == text {
+            return;
+        }
+        // Reuse the allocations of the previous glyphs
+        std::mem::swap(&mut self.glyphs, &mut self.previous_glyphs);
+        self.glyphs.clear();
+        let (mut column, mut line) = (0.0, 0.0);
+        for c in text.chars() {
+            if c == '\n' {
+                column = 0.0;
+                line += 1.0;
+                continue;
+            }
+            if c != ' ' {
+                let index = if (' '..='~').contains(&c) {
+                    c as usize - ' ' as usize
+                } else {
+                    '?' as usize - ' ' as usize
+                };
+                self.glyphs.push(vec4(
+                    column,
+                    -line - 1.0,
+                    (index % ATLAS_COLUMNS) as f32,
+                    (index / ATLAS_COLUMNS) as f32,
+                ));
+            }
+            column += 1.0;
+        }
+
+        let count = self.glyphs.len() as u32;
+        if count > self.capacity {
+            self.capacity = count.next_power_of_two().max(16);
+            self.glyph_buffer =
+                InstanceBuffer::new_with_capacity::<Vec4>(&self.context, self.capacity);
+            self.glyph_buffer.fill_subset(0, &self.glyphs);
+        } else if let Some(first) = self
+            .glyphs
+            .iter()
+            .zip(self.previous_glyphs.iter())
+            .position(|(a, b)| a != b)
+            .or_else(|| {
+                (self.previous_glyphs.len() < self.glyphs.len())
+                    .then_some(self.previous_glyphs.len())
+            })
+        {
+            self.glyph_buffer
+                .fill_subset(first as u32, &self.glyphs[first..]);
+        }
+        self.text.clear();
+        self.text.push_str(text);
+    }
+
+    ///
+    /// Returns the local to world transformation applied to the text.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.transformation
+    }
+
+    ///
+    /// Set the local to world transformation applied to the text.
+    ///
+    pub fn set_transformation(&mut self, transformation: Mat4) {
+        self.transformation = transformation;
+    }
+
+    fn glyph_size(&self) -> Vec2 {
+        vec2(
+            self.size * CELL_WIDTH as f32 / CELL_HEIGHT as f32,
+            self.size,
+        )
+    }
+}
+
+impl<'a> IntoIterator for &'a Text {
+    type Item = &'a dyn Object;
+    type IntoIter = std::iter::Once<&'a dyn Object>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for Text {
+    fn draw(
+        &self,
+        camera: &Camera,
+        program: &Program,
+        render_states: RenderStates,
+        _attributes: FragmentAttributes,
+    ) {
+        if self.glyphs.is_empty() {
+            return;
+        }
+        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform("modelMatrix", self.transformation);
+        program.use_uniform_if_required(
+            "normalMatrix",
+            self.transformation
+                .invert()
+                .unwrap_or(Mat4::identity())
+                .transpose(),
+        );
+        program.use_uniform("glyphSize", self.glyph_size());
+        program.use_uniform(
+            "cellSize",
+            vec2(1.0 / ATLAS_COLUMNS as f32, 1.0 / ATLAS_ROWS as f32),
+        );
+        program.use_vertex_attribute("position", &self.position_buffer);
+        program.use_instance_attribute("glyph", &self.glyph_buffer);
+        program.draw_arrays_instanced(
+            render_states,
+            camera.viewport(),
+            6,
+            self.glyphs.len() as u32,
+        )
+    }
+
+    fn vertex_shader_source(&self, _required_attributes: FragmentAttributes) -> String {
+        include_str!("shaders/text.vert").to_owned()
+    }
+
+    fn id(&self, _required_attributes: FragmentAttributes) -> u16 {
+        0b1u16 << 15 | 0b1001u16
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        render_with_material(&self.context, camera, &self, material, lights)
+    }
+
+    fn render_with_effect(
+        &self,
+        material: &dyn Effect,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        render_with_effect(
+            &self.context,
+            camera,
+            self,
+            material,
+            lights,
+            color_texture,
+            depth_texture,
+        )
+    }
+
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        if self.glyphs.is_empty() {
+            return AxisAlignedBoundingBox::EMPTY;
+        }
+        let size = self.glyph_size();
+        let (min, max) = self.glyphs.iter().fold(
+            (vec2(f32::MAX, f32::MAX), vec2(f32::MIN, f32::MIN)),
+            |(min, max), g| {
+                (
+                    vec2(min.x.min(g.x), min.y.min(g.y)),
+                    vec2(max.x.max(g.x + 1.0), max.y.max(g.y + 1.0)),
+                )
+            },
+        );
+        let mut aabb = AxisAlignedBoundingBox::new_with_positions(&[
+            vec3(min.x * size.x, min.y * size.y, 0.0),
+            vec3(max.x * size.x, max.y * size.y, 0.0),
+        ]);
+        aabb.transform(&self.transformation);
+        aabb
+    }
+}
+
+impl Object for Text {
+    fn render(&self, camera: &Camera, lights: &[&dyn Light]) {
+        render_with_material(
+            &self.context,
+            camera,
+            self,
+            &TextMaterial {
+                atlas: &self.atlas,
+                color: self.color,
+            },
+            lights,
+        )
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
+
+struct TextMaterial<'a> {
+    atlas: &'a Texture2D,
+    color: Srgba,
+}
+
+impl Material for TextMaterial<'_> {
+    fn id(&self) -> u16 {
+        0b1u16 << 15 | 0b1101u16
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        format!(
+            "{}{}",
+            ColorMapping::fragment_shader_source(),
+            include_str!("shaders/text.frag")
+        )
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
+        camera.color_mapping.use_uniforms(program);
+        program.use_texture("glyphAtlas", self.atlas);
+        program.use_uniform("textColor", self.color.to_linear_srgb());
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            cull: Cull::None,
+            ..Default::default()
+        }
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
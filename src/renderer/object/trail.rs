@@ -0,0 +1,285 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::collections::VecDeque;
+
+///
+/// A motion trail which records the recent positions of a moving object, for example a projectile or a tracked hand, or displays a path.
+/// The trail is rendered as a ribbon through the recorded positions which always faces the camera,
+/// and the width and color of the ribbon change from the newest position to the positions which are about to expire.
+///
+/// Call [Trail::push] with the current position of the object and then [Trail::update] each frame.
+/// When rendered as an [Object], the ribbon is alpha blended with the content of the render target.
+/// When rendered as a [Geometry] with another [Material], the ribbon is rendered with that material instead.
+///
+pub struct Trail {
+    context: Context,
+    points: VecDeque<(Vec3, f64)>,
+    time: f64,
+    max_count: u32,
+    position_ages: VertexBuffer,
+    direction_sides: VertexBuffer,
+    aabb: AxisAlignedBoundingBox,
+    /// The time in seconds a position is part of the trail after it is recorded.
+    pub lifetime: f32,
+    /// The width of the ribbon at the newest position.
+    pub start_width: f32,
+    /// The width of the ribbon at the positions which are about to expire.
+    pub end_width: f32,
+    /// The color of the ribbon at the newest position.
+    pub start_color: Srgba,
+    /// The color of the ribbon at the positions which are about to expire.
+    pub end_color: Srgba,
+    /// The minimum distance between two recorded positions. As long as the newest position is closer than this to the position before it, the newest position is moved instead of adding a new position.
+    pub min_distance: f32,
+}
+
+impl Trail {
+    ///
+    /// Creates a new empty trail which records at most `max_count` positions. When more positions are recorded, the oldest positions are removed.
+    ///
+    pub fn new(context: &Context, max_count: u32) -> Self {
+        Self {
+            context: context.clone(),
+            points: VecDeque::new(),
+            time: 0.0,
+            max_count: max_count.max(2),
+            position_ages: VertexBuffer::new(context),
+            direction_sides: VertexBuffer::new(context),
+            aabb: AxisAlignedBoundingBox::EMPTY,
+            lifetime: 1.0,
+            start_width: 0.1,
+            end_width: 0.0,
+            start_color: Srgba::WHITE,
+            end_color: Srgba::new(255, 255, 255, 0),
+            min_distance: 0.01,
+        }
+    }
+
+    ///
+    /// Returns the maximum number of recorded positions.
+    ///
+    pub fn max_count(&self) -> u32 {
+        self.max_count
+    }
+
+    ///
+    /// Returns the number of recorded positions which have not expired.
+    ///
+    pub fn count(&self) -> u32 {
+        self.points.len() as u32
+    }
+
+    ///
+    /// Records the given position as the newest position of the trail.
+    /// The trail is not changed on the screen before the next call to [Trail::update].
+    ///
+    pub fn push(&mut self, position: Vec3) {
+        let replace = self.points.len() > 1
+            && self
+                .points
+                .get(self.points.len() - 2)
+                .map(|(p, _)| p.distance2(position) < self.min_distance * self.min_distance)
+                .unwrap_or(false);
+        if replace {
+            *self.points.back_mut().unwrap() = (position, self.time);
+        } else {
+            self.points.push_back((position, self.time));
+        }
+        while self.points.len() > self.max_count as usize {
+            self.points.pop_front();
+        }
+    }
+
+    ///
+    /// Removes all recorded positions.
+    ///
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.update(0.0);
+    }
+
+    ///
+    /// Moves the trail forward in time, removes the positions which are older than the [Trail::lifetime] and updates the ribbon.
+    /// The elapsed time is the time in milliseconds since the last update, for example the elapsed time given by the frame input of the window.
+    ///
+    pub fn update(&mut self, elapsed_time: f64) {
+        self.time += elapsed_time * 0.001;
+        let lifetime = self.lifetime.max(0.0001) as f64;
+        while matches!(self.points.front(), Some((_, time)) if self.time - time > lifetime) {
+            self.points.pop_front();
+        }
+
+        // The ribbon is built from the newest to the oldest position, where each segment is two triangles
+        let points = self.points.iter().rev().collect::<Vec<_>>();
+        let segment_count = points.len().saturating_sub(1);
+        let mut position_ages = Vec::with_capacity(segment_count * 6);
+        let mut direction_sides = Vec::with_capacity(segment_count * 6);
+        let vertex = |i: usize| {
+            let (position, time) = points[i];
+            let direction = points[i.saturating_sub(1)].0 - points[(i + 1).min(points.len() - 1)].0;
+            let direction = if direction.magnitude2() > 0.0 {
+                direction.normalize()
+            } else {
+                direction
+            };
+            (
+                position.extend(((self.time - time) / lifetime) as f32),
+                direction,
+            )
+        };
+        for i in 0..segment_count {
+            let (p0, d0) = vertex(i);
+            let (p1, d1) = vertex(i + 1);
+            position_ages.extend([p0, p1, p1, p1, p0, p0]);
+            direction_sides.extend([
+                d0.extend(-1.0),
+                d1.extend(-1.0),
+                d1.extend(1.0),
+                d1.extend(1.0),
+                d0.extend(1.0),
+                d0.extend(-1.0),
+            ]);
+        }
+        self.position_ages.fill(&position_ages);
+        self.direction_sides.fill(&direction_sides);
+
+        let positions = self.points.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+        self.aabb = AxisAlignedBoundingBox::new_with_positions(&positions);
+        let half_width = 0.5 * self.start_width.abs().max(self.end_width.abs());
+        if !self.aabb.is_empty() {
+            let extent = vec3(half_width, half_width, half_width);
+            self.aabb = AxisAlignedBoundingBox::new_with_positions(&[
+                self.aabb.min() - extent,
+                self.aabb.max() + extent,
+            ]);
+        }
+    }
+
+    fn draw_trail(&self, camera: &Camera, program: &Program, render_states: RenderStates) {
+        if self.position_ages.vertex_count() == 0 {
+            return;
+        }
+        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform_if_required("cameraPosition", *camera.position());
+        program.use_uniform_if_required("viewDirection", camera.view_direction());
+        program.use_uniform_if_required(
+            "isOrthographic",
+            matches!(
+                camera.projection_type(),
+                three_d_asset::ProjectionType::Orthographic { .. }
+            ) as i32,
+        );
+        program.use_uniform_if_required("startWidth", self.start_width);
+        program.use_uniform_if_required("endWidth", self.end_width);
+        program.use_uniform_if_required("startColor", self.start_color.to_linear_srgb());
+        program.use_uniform_if_required("endColor", self.end_color.to_linear_srgb());
+        program.use_vertex_attribute("positionAge", &self.position_ages);
+        program.use_vertex_attribute("directionSide", &self.direction_sides);
+        program.draw_arrays(
+            render_states,
+            camera.viewport(),
+            self.position_ages.vertex_count(),
+        );
+    }
+}
+
+impl<'a> IntoIterator for &'a Trail {
+    type Item = &'a dyn Object;
+    type IntoIter = std::iter::Once<&'a dyn Object>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self)
+    }
+}
+
+impl Geometry for Trail {
+    fn draw(
+        &self,
+        camera: &Camera,
+        program: &Program,
+        render_states: RenderStates,
+        _attributes: FragmentAttributes,
+    ) {
+        self.draw_trail(camera, program, render_states);
+    }
+
+    fn vertex_shader_source(&self, _required_attributes: FragmentAttributes) -> String {
+        include_str!("shaders/trail.vert").to_owned()
+    }
+
+    fn id(&self, _required_attributes: FragmentAttributes) -> u16 {
+        0b1u16 << 15 | 0b1110u16
+    }
+
+    fn render_with_material(
+        &self,
+        material: &dyn Material,
+        camera: &Camera,
+        lights: &[&dyn Light],
+    ) {
+        render_with_material(&self.context, camera, self, material, lights);
+    }
+
+    fn render_with_effect(
+        &self,
+        material: &dyn Effect,
+        camera: &Camera,
+        lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        render_with_effect(
+            &self.context,
+            camera,
+            self,
+            material,
+            lights,
+            color_texture,
+            depth_texture,
+        )
+    }
+
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        self.aabb
+    }
+
+    fn is_animated(&self) -> bool {
+        true
+    }
+}
+
+impl Object for Trail {
+    fn render(&self, camera: &Camera, _lights: &[&dyn Light]) {
+        let mut programs = self.context.programs.write().unwrap();
+        let program = programs.entry(b"trail".to_vec()).or_insert_with(|| {
+            let mut fragment_shader_source = String::new();
+            fragment_shader_source.push_str(ToneMapping::fragment_shader_source());
+            fragment_shader_source.push_str(ColorMapping::fragment_shader_source());
+            fragment_shader_source.push_str(include_str!("shaders/trail.frag"));
+            Program::from_source(
+                &self.context,
+                include_str!("shaders/trail.vert"),
+                &fragment_shader_source,
+            )
+            .expect("Failed compiling shader")
+        });
+        camera
+            .tone_mapping
+            .use_uniforms_with_exposure(program, camera.exposure);
+        camera.color_mapping.use_uniforms(program);
+        self.draw_trail(
+            camera,
+            program,
+            RenderStates {
+                write_mask: WriteMask::COLOR,
+                blend: Blend::TRANSPARENCY,
+                cull: Cull::None,
+                ..Default::default()
+            },
+        );
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Transparent
+    }
+}
@@ -301,4 +301,8 @@ impl Geometry for WaterPatch {
     fn animate(&mut self, time: f32) {
         self.time = time;
     }
+
+    fn is_animated(&self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,202 @@
+use crate::renderer::*;
+
+///
+/// An opening through which another part of the scene is seen, see [PortalRenderer].
+///
+#[derive(Clone, Copy)]
+pub struct Portal<'a> {
+    /// The geometry of the opening in world space, for example a rectangle in a door frame or the glass of a window.
+    /// Only the front facing triangles of the opening are used, unless the opening is rendered with culling disabled.
+    pub opening: &'a dyn Geometry,
+    /// The rigid transformation (rotation and translation) from the opening to the place it leads to.
+    /// What is seen through the opening is what a camera transformed by this transformation sees,
+    /// so for a portal from a frame `A` to a frame `B` this is `B * A⁻¹` and for a window into the same space this is the identity.
+    pub transformation: Mat4,
+    /// An optional plane at the destination given in world space as `(normal, distance)`, where only the points `p` with `normal.dot(p) + distance >= 0` are seen through the opening.
+    /// This should be the plane of the exit of the portal facing away from the exit, so the objects between the transformed camera and the exit are not rendered, see [Camera::set_oblique_near_plane].
+    pub clip_plane: Option<Vec4>,
+}
+
+///
+/// Renders a scene where other parts of the scene are seen through portals, for example doors between rooms, magic windows in augmented reality
+/// or two portals facing each other. The openings of the portals are masked out in the stencil buffer and the part of the scene seen through each opening
+/// is rendered with a transformed camera only where the stencil buffer is set, recursively up to a maximum depth.
+///
+/// The render target must have a stencil buffer, for example a depth texture with a [DepthStencilFormat] or a screen with a stencil buffer,
+/// and the stencil buffer must be cleared to zero before rendering.
+///
+pub struct PortalRenderer {
+    context: Context,
+    /// The maximum number of portals seen through each other, where zero means that the portals are not rendered. The default is 2 and the maximum is 255.
+    pub max_depth: u8,
+}
+
+impl PortalRenderer {
+    ///
+    /// Creates a new portal renderer.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            max_depth: 2,
+        }
+    }
+
+    ///
+    /// Renders the given geometries with the given materials and lights as seen from the given camera into the given render target,
+    /// including what is seen through the given portals.
+    /// The geometries should not include the openings of the portals.
+    ///
+    pub fn render(
+        &self,
+        target: &RenderTarget,
+        camera: &Camera,
+        objects: &[(&dyn Geometry, &dyn Material)],
+        portals: &[Portal],
+        lights: &[&dyn Light],
+    ) {
+        target
+            .write::<RendererError>(|| {
+                self.render_level(camera, objects, portals, lights, 0);
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    fn render_level(
+        &self,
+        camera: &Camera,
+        objects: &[(&dyn Geometry, &dyn Material)],
+        portals: &[Portal],
+        lights: &[&dyn Light],
+        level: u8,
+    ) {
+        let frustum = camera.frustum();
+        for (geometry, material) in objects {
+            if frustum.intersects_aabb(&geometry.aabb()) {
+                render_with_material(
+                    &self.context,
+                    camera,
+                    geometry,
+                    StencilMaterial(*material, Stencil::equal(level)),
+                    lights,
+                );
+            }
+        }
+        if level >= self.max_depth || level == u8::MAX {
+            return;
+        }
+        for portal in portals {
+            if !frustum.intersects_aabb(&portal.opening.aabb()) {
+                continue;
+            }
+            // Mark the visible part of the opening
+            self.render_opening(
+                camera,
+                portal.opening,
+                WriteMask::NONE,
+                DepthTest::Less,
+                DepthRange::FULL,
+                stencil_update(level, StencilOperation::Increment),
+            );
+            // Clear the depth inside the opening
+            self.render_opening(
+                camera,
+                portal.opening,
+                WriteMask::DEPTH,
+                DepthTest::Always,
+                DepthRange::new(1.0, 1.0),
+                Stencil::equal(level + 1),
+            );
+
+            let mut portal_camera = camera.clone();
+            portal_camera.set_view(
+                (portal.transformation * camera.position().extend(1.0)).truncate(),
+                (portal.transformation * camera.target().extend(1.0)).truncate(),
+                (portal.transformation * camera.up().extend(0.0)).truncate(),
+            );
+            portal_camera.set_oblique_near_plane(portal.clip_plane);
+            self.render_level(&portal_camera, objects, portals, lights, level + 1);
+
+            // Restore the depth of the opening and the stencil so the opening is occluded correctly by the rest of this level
+            self.render_opening(
+                camera,
+                portal.opening,
+                WriteMask::DEPTH,
+                DepthTest::Always,
+                DepthRange::FULL,
+                stencil_update(level + 1, StencilOperation::Decrement),
+            );
+        }
+    }
+
+    fn render_opening(
+        &self,
+        camera: &Camera,
+        opening: &dyn Geometry,
+        write_mask: WriteMask,
+        depth_test: DepthTest,
+        depth_range: DepthRange,
+        stencil: Stencil,
+    ) {
+        let material = ColorMaterial {
+            render_states: RenderStates {
+                write_mask,
+                depth_test,
+                depth_range,
+                stencil,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        render_with_material(&self.context, camera, opening, &material, &[]);
+    }
+}
+
+// Only passes where the stencil buffer is equal to the reference value and then applies the given operation.
+fn stencil_update(reference: u8, pass: StencilOperation) -> Stencil {
+    Stencil::Enabled {
+        test: StencilTest::Equal,
+        reference,
+        read_mask: 0xFF,
+        write_mask: 0xFF,
+        stencil_fail: StencilOperation::Keep,
+        depth_fail: StencilOperation::Keep,
+        pass,
+    }
+}
+
+// Renders with the given material but only where the stencil test passes.
+struct StencilMaterial<'a>(&'a dyn Material, Stencil);
+
+impl Material for StencilMaterial<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        self.0.fragment_shader_source(lights)
+    }
+
+    fn id(&self) -> u16 {
+        self.0.id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.0.use_uniforms(program, camera, lights)
+    }
+
+    fn render_states(&self) -> RenderStates {
+        let mut render_states = self.0.render_states();
+        render_states.stencil = self.1;
+        render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        self.0.material_type()
+    }
+
+    fn shader_defines(&self) -> ShaderDefines {
+        self.0.shader_defines()
+    }
+}
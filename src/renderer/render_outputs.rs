@@ -0,0 +1,325 @@
+use crate::renderer::*;
+
+///
+/// Renders the beauty image together with an object id buffer and/or a linear depth buffer,
+/// so interactive applications which need picking and depth based effects get all of them from one call.
+///
+/// The beauty image is rendered as when rendering to a [RenderTarget], so all objects and materials are supported.
+/// Afterwards, the object ids and the linear depth are written in a single additional pass into separate 32 bit float textures using multiple render targets.
+/// The object id is the index of the object in the objects given to [RenderOutputs::render] plus one, where zero means that no object covers the pixel.
+/// The linear depth is the distance from the camera to the surface along the view direction, or [f32::MAX] where no object covers the pixel.
+/// Both are stored in the red channel, so the id is exact for scenes with up to 16 million objects, and both belong to the nearest surface, also if it is transparent.
+///
+pub struct RenderOutputs {
+    context: Context,
+    ids: bool,
+    linear_depth: bool,
+    viewport: Viewport,
+    targets: Option<Targets>,
+}
+
+struct Targets {
+    beauty: Texture2D,
+    outputs: Option<Texture2DArray>,
+    depth: DepthTexture2D,
+}
+
+impl RenderOutputs {
+    ///
+    /// Creates new render outputs which write the beauty image and, if enabled, the object ids and the linear depth.
+    ///
+    /// # Panic
+    /// Will panic if the object ids or the linear depth are enabled and the context does not support rendering to 32 bit float textures,
+    /// or if both are enabled and the context does not support multiple render targets, see [Capabilities].
+    ///
+    pub fn new(context: &Context, ids: bool, linear_depth: bool) -> Self {
+        assert!(
+            !(ids || linear_depth) || context.capabilities().float_render_targets,
+            "object ids and linear depth require support for float render targets"
+        );
+        assert!(
+            !(ids && linear_depth) || context.supports_multiple_render_targets(),
+            "object ids together with linear depth require support for multiple render targets"
+        );
+        Self {
+            context: context.clone(),
+            ids,
+            linear_depth,
+            viewport: Viewport::new_at_origo(1, 1),
+            targets: None,
+        }
+    }
+
+    ///
+    /// Renders the objects with the given camera and lights into all of the outputs after clearing the beauty image with the given clear state.
+    /// The outputs have the same size as the viewport of the camera and are reused as long as the size does not change.
+    /// The ids are given by the order of the objects in the iterator.
+    ///
+    pub fn render(
+        &mut self,
+        camera: &Camera,
+        objects: impl IntoIterator<Item = impl Object>,
+        lights: &[&dyn Light],
+        clear_state: ClearState,
+    ) {
+        self.viewport = camera.viewport();
+        let (width, height) = (self.viewport.width.max(1), self.viewport.height.max(1));
+        let layer_count = self.ids as u32 + self.linear_depth as u32;
+        if !matches!(&self.targets, Some(targets) if targets.depth.width() == width && targets.depth.height() == height)
+        {
+            self.targets = Some(Targets {
+                beauty: Texture2D::new_empty::<[f16; 4]>(
+                    &self.context,
+                    width,
+                    height,
+                    Interpolation::Nearest,
+                    Interpolation::Nearest,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+                outputs: (layer_count > 0).then(|| {
+                    Texture2DArray::new_empty::<f32>(
+                        &self.context,
+                        width,
+                        height,
+                        layer_count,
+                        Interpolation::Nearest,
+                        Interpolation::Nearest,
+                        None,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    )
+                }),
+                depth: DepthTexture2D::new::<f32>(
+                    &self.context,
+                    width,
+                    height,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+            });
+        }
+        let mut camera = camera.clone();
+        camera.set_viewport(Viewport::new_at_origo(width, height));
+        let objects = objects.into_iter().collect::<Vec<_>>();
+
+        let ids_layer = self.ids_layer();
+        let linear_depth_layer = self.linear_depth_layer();
+        let targets = self.targets.as_mut().unwrap();
+        RenderTarget::new(
+            targets.beauty.as_color_target(None),
+            targets.depth.as_depth_target(),
+        )
+        .clear(ClearState {
+            depth: Some(1.0),
+            ..clear_state
+        })
+        .render(&camera, &objects, lights);
+
+        if let Some(outputs) = targets.outputs.as_mut() {
+            if self.ids {
+                outputs
+                    .as_color_target(&[ids_layer], None)
+                    .clear(ClearState::color(0.0, 0.0, 0.0, 0.0));
+            }
+            if self.linear_depth {
+                outputs
+                    .as_color_target(&[linear_depth_layer], None)
+                    .clear(ClearState::color(f32::MAX, 0.0, 0.0, 0.0));
+            }
+            let frustum = camera.frustum();
+            let layers = (0..layer_count).collect::<Vec<_>>();
+            RenderTarget::new(
+                outputs.as_color_target(&layers, None),
+                targets.depth.as_depth_target(),
+            )
+            .write::<RendererError>(|| {
+                for (index, object) in objects
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, o)| frustum.intersects_aabb(&o.aabb()))
+                {
+                    object.render_with_material(
+                        &OutputsMaterial {
+                            id: index as u32 + 1,
+                            ids: self.ids,
+                            linear_depth: self.linear_depth,
+                        },
+                        &camera,
+                        &[],
+                    );
+                }
+                Ok(())
+            })
+            .unwrap();
+        }
+    }
+
+    ///
+    /// Returns the beauty image, ie. the colors written by the materials, or `None` if nothing has been rendered.
+    ///
+    pub fn beauty(&self) -> Option<ColorTexture<'_>> {
+        self.targets
+            .as_ref()
+            .map(|targets| ColorTexture::Single(&targets.beauty))
+    }
+
+    ///
+    /// Returns the object ids in the red channel, or `None` if the ids are not enabled or nothing has been rendered.
+    ///
+    pub fn ids(&self) -> Option<ColorTexture<'_>> {
+        if !self.ids {
+            return None;
+        }
+        self.outputs().map(|texture| ColorTexture::Array {
+            texture,
+            layers: &[0],
+        })
+    }
+
+    ///
+    /// Returns the linear depth in the red channel, or `None` if the linear depth is not enabled or nothing has been rendered.
+    ///
+    pub fn linear_depth(&self) -> Option<ColorTexture<'_>> {
+        if !self.linear_depth {
+            return None;
+        }
+        self.outputs().map(|texture| ColorTexture::Array {
+            texture,
+            layers: if self.ids { &[1] } else { &[0] },
+        })
+    }
+
+    ///
+    /// Returns the depth texture which is used for depth testing, or `None` if nothing has been rendered.
+    ///
+    pub fn depth_texture(&self) -> Option<DepthTexture<'_>> {
+        self.targets
+            .as_ref()
+            .map(|targets| DepthTexture::Single(&targets.depth))
+    }
+
+    ///
+    /// Reads the index of the object at the given pixel, ie. the index in the objects given to [RenderOutputs::render], or `None` if no object covers the pixel
+    /// or the ids are not enabled. The pixel coordinate must be in physical pixels, where (viewport.x, viewport.y) indicate the bottom left corner of the viewport.
+    ///
+    /// **Note:** This stalls the CPU until the GPU has finished rendering.
+    ///
+    pub fn read_id(&mut self, pixel: impl Into<PhysicalPoint>) -> Option<usize> {
+        if !self.ids {
+            return None;
+        }
+        let layer = self.ids_layer();
+        let id = self.read_pixel(layer, pixel.into())? as usize;
+        (id > 0).then(|| id - 1)
+    }
+
+    ///
+    /// Reads the linear depth at the given pixel, or `None` if no object covers the pixel or the linear depth is not enabled.
+    /// The pixel coordinate must be in physical pixels, where (viewport.x, viewport.y) indicate the bottom left corner of the viewport.
+    ///
+    /// **Note:** This stalls the CPU until the GPU has finished rendering.
+    ///
+    pub fn read_linear_depth(&mut self, pixel: impl Into<PhysicalPoint>) -> Option<f32> {
+        if !self.linear_depth {
+            return None;
+        }
+        let layer = self.linear_depth_layer();
+        self.read_pixel(layer, pixel.into())
+            .filter(|depth| *depth < f32::MAX)
+    }
+
+    fn outputs(&self) -> Option<&Texture2DArray> {
+        self.targets
+            .as_ref()
+            .and_then(|targets| targets.outputs.as_ref())
+    }
+
+    fn ids_layer(&self) -> u32 {
+        0
+    }
+
+    fn linear_depth_layer(&self) -> u32 {
+        self.ids as u32
+    }
+
+    // Reads the red channel of the given layer at the given pixel.
+    fn read_pixel(&mut self, layer: u32, pixel: PhysicalPoint) -> Option<f32> {
+        let (x, y) = (
+            pixel.x as i32 - self.viewport.x,
+            pixel.y as i32 - self.viewport.y,
+        );
+        let outputs = self.targets.as_mut()?.outputs.as_mut()?;
+        if x < 0 || y < 0 || x >= outputs.width() as i32 || y >= outputs.height() as i32 {
+            return None;
+        }
+        let layers = [layer];
+        outputs
+            .as_color_target(&layers, None)
+            .read_partially::<[f32; 4]>(ScissorBox {
+                x,
+                y,
+                width: 1,
+                height: 1,
+            })
+            .first()
+            .map(|value| value[0])
+    }
+}
+
+// Writes the id of the object and/or the linear depth to the render targets, where the depth test makes sure that the values of the nearest surface are written.
+struct OutputsMaterial {
+    id: u32,
+    ids: bool,
+    linear_depth: bool,
+}
+
+impl Material for OutputsMaterial {
+    fn id(&self) -> u16 {
+        0b1u16 << 15 | 0b1u16 << 13 | 0b1u16 << 11
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        include_str!("material/shaders/render_outputs.frag").to_string()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes::NONE
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
+        if self.ids {
+            program.use_uniform("outputId", self.id as f32);
+        }
+        if self.linear_depth {
+            program.use_uniform(
+                "outputInverseProjection",
+                camera.projection().invert().unwrap_or(Mat4::identity()),
+            );
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::LessOrEqual,
+            cull: Cull::None,
+            ..Default::default()
+        }
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+
+    fn shader_defines(&self) -> ShaderDefines {
+        let mut defines = ShaderDefines::default();
+        if self.ids {
+            defines.insert("OUTPUT_IDS");
+        }
+        if self.linear_depth {
+            defines.insert("OUTPUT_LINEAR_DEPTH");
+        }
+        defines
+    }
+}
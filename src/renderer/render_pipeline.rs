@@ -0,0 +1,353 @@
+use crate::renderer::*;
+
+///
+/// The quality of the shadows rendered by a [RenderPipeline], see [PipelineSettings::shadow_quality].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ShadowQuality {
+    /// No shadows.
+    Off,
+    /// 512x512 shadow maps with hard shadow edges.
+    Low,
+    /// 1024x1024 shadow maps with a 3x3 filter kernel.
+    #[default]
+    Medium,
+    /// 2048x2048 shadow maps with a 5x5 filter kernel.
+    High,
+}
+
+impl ShadowQuality {
+    ///
+    /// Returns the width and height of the shadow maps or `None` if the shadows are disabled.
+    ///
+    pub fn texture_size(&self) -> Option<u32> {
+        match self {
+            Self::Off => None,
+            Self::Low => Some(512),
+            Self::Medium => Some(1024),
+            Self::High => Some(2048),
+        }
+    }
+
+    ///
+    /// Returns the size of the percentage-closer filtering kernel, see [ShadowSettings::pcf_kernel_size].
+    ///
+    pub fn pcf_kernel_size(&self) -> u32 {
+        match self {
+            Self::Off | Self::Low => 1,
+            Self::Medium => 3,
+            Self::High => 5,
+        }
+    }
+}
+
+///
+/// The graphics settings of a [RenderPipeline], for example exposed in the graphics settings menu of an application.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PipelineSettings {
+    /// Whether to render the scene into a floating point render target and apply the tone and color mapping of the camera afterwards,
+    /// instead of applying the mapping directly when rendering each object.
+    /// The format of the render target is given by [Context::color_precision], and HDR rendering is disabled if it is [ColorPrecision::Rgba8]. The default is true.
+    pub hdr: bool,
+    /// The number of samples per pixel used for multisample anti-aliasing, where 0 disables multisampling.
+    /// The number is rounded down to a power of two and limited to the maximum number of samples supported by the context. The default is 4.
+    pub msaa_samples: u32,
+    /// The quality of the shadows. The default is [ShadowQuality::Medium].
+    pub shadow_quality: ShadowQuality,
+}
+
+impl Default for PipelineSettings {
+    fn default() -> Self {
+        Self {
+            hdr: true,
+            msaa_samples: 4,
+            shadow_quality: ShadowQuality::default(),
+        }
+    }
+}
+
+///
+/// Renders a scene to a render target, usually the screen, according to a set of [PipelineSettings]
+/// and owns the intermediate render targets needed for that, ie. the multisample and HDR render targets.
+///
+/// When the settings are changed using [RenderPipeline::set_settings], all resources that depend on the changed settings are recreated:
+/// The render targets are recreated the next time [RenderPipeline::render] is called
+/// and the shadow maps are regenerated the next time [RenderPipeline::update_shadow_maps] is called.
+/// The shader programs are chosen based on the camera and lights, so they are automatically compiled for the new settings when needed.
+/// This means that an in-app graphics settings menu only has to call [RenderPipeline::set_settings].
+///
+pub struct RenderPipeline {
+    context: Context,
+    settings: PipelineSettings,
+    targets: Option<PipelineTargets>,
+    shadow_quality: Option<ShadowQuality>,
+    generation: u32,
+    /// The clear state of the intermediate render targets before the scene is rendered. The default is transparent black and a depth of one.
+    pub clear_state: ClearState,
+}
+
+impl RenderPipeline {
+    ///
+    /// Creates a new render pipeline with the given settings. The render targets are created the first time [RenderPipeline::render] is called.
+    ///
+    pub fn new(context: &Context, settings: PipelineSettings) -> Self {
+        Self {
+            context: context.clone(),
+            settings,
+            targets: None,
+            shadow_quality: None,
+            generation: 0,
+            clear_state: ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0),
+        }
+    }
+
+    ///
+    /// Returns the current settings.
+    ///
+    pub fn settings(&self) -> &PipelineSettings {
+        &self.settings
+    }
+
+    ///
+    /// Changes the settings. The resources depending on the changed settings are recreated the next time they are used, see [RenderPipeline].
+    ///
+    pub fn set_settings(&mut self, settings: PipelineSettings) {
+        self.settings = settings;
+    }
+
+    ///
+    /// Returns whether or not HDR rendering is used, which is the case if enabled in the settings and the color precision of the context supports HDR colors.
+    ///
+    pub fn is_hdr(&self) -> bool {
+        self.settings.hdr && self.context.color_precision().is_hdr()
+    }
+
+    ///
+    /// Returns the number of samples per pixel actually used for multisample anti-aliasing given the settings and the capabilities of the context.
+    ///
+    pub fn msaa_samples(&self) -> u32 {
+        let samples = self
+            .settings
+            .msaa_samples
+            .min(self.context.capabilities().max_samples);
+        if samples < 2 {
+            0
+        } else {
+            1 << (31 - samples.leading_zeros())
+        }
+    }
+
+    ///
+    /// Returns a number which is increased each time the intermediate render targets are recreated,
+    /// for example because the settings or the size of the viewport changed.
+    ///
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    ///
+    /// Returns whether or not the shadow maps were generated with another shadow quality than the current one and should be regenerated.
+    ///
+    pub fn shadow_maps_outdated(&self) -> bool {
+        self.shadow_quality != Some(self.settings.shadow_quality)
+    }
+
+    ///
+    /// Generates the shadow maps of the given lights using the given geometries as shadow casters, with the texture size and filtering given by the shadow quality,
+    /// or clears the shadow maps if the shadows are turned off.
+    /// Call this method when the shadow casters have moved, otherwise use [RenderPipeline::update_shadow_maps].
+    ///
+    pub fn generate_shadow_maps(
+        &mut self,
+        lights: &mut [&mut dyn ShadowCaster],
+        geometries: &[&dyn Geometry],
+    ) {
+        let quality = self.settings.shadow_quality;
+        for light in lights.iter_mut() {
+            light.shadow_settings_mut().pcf_kernel_size = quality.pcf_kernel_size();
+            light.update_shadow_map(quality.texture_size(), geometries);
+        }
+        self.shadow_quality = Some(quality);
+    }
+
+    ///
+    /// Generates the shadow maps as in [RenderPipeline::generate_shadow_maps], but only if the shadow quality has changed since the shadow maps were last generated
+    /// or if any of the geometries are animated (see [Geometry::is_animated]), for example a [SkinnedMesh], so the shadows follow the animation.
+    /// The shadow maps are rendered with the vertex shader of each geometry, so any vertex deformation, for example skinning, instancing or [Wind], is also applied to the shadows.
+    /// Returns whether or not the shadow maps were generated.
+    ///
+    pub fn update_shadow_maps(
+        &mut self,
+        lights: &mut [&mut dyn ShadowCaster],
+        geometries: &[&dyn Geometry],
+    ) -> bool {
+        if self.shadow_maps_outdated() || geometries.iter().any(|g| g.is_animated()) {
+            self.generate_shadow_maps(lights, geometries);
+            true
+        } else {
+            false
+        }
+    }
+
+    ///
+    /// Renders the objects using the given camera and lights into the viewport of the camera in the given render target, according to the settings.
+    /// If HDR rendering and multisampling are both disabled, the objects are rendered directly into the render target,
+    /// otherwise they are rendered into the intermediate render targets which are then copied to the render target, overwriting the color and depth inside the viewport.
+    /// The intermediate render targets are recreated if the settings or the size of the viewport have changed.
+    ///
+    /// Must not be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method, since it renders into its own render targets.
+    ///
+    pub fn render(
+        &mut self,
+        target: &RenderTarget,
+        camera: &Camera,
+        objects: impl IntoIterator<Item = impl Object>,
+        lights: &[&dyn Light],
+    ) {
+        let hdr = self.is_hdr();
+        let precision = if hdr {
+            self.context.color_precision()
+        } else {
+            ColorPrecision::Rgba8
+        };
+        let samples = self.msaa_samples();
+        if !hdr && samples == 0 {
+            self.targets = None;
+            target.render(camera, objects, lights);
+            return;
+        }
+
+        let viewport = camera.viewport();
+        let (width, height) = (viewport.width.max(1), viewport.height.max(1));
+        if !matches!(&self.targets, Some(t) if t.precision == precision && t.samples == samples && t.color.width() == width && t.color.height() == height)
+        {
+            self.targets = Some(PipelineTargets::new(
+                &self.context,
+                precision,
+                samples,
+                width,
+                height,
+            ));
+            self.generation = self.generation.wrapping_add(1);
+        }
+        let targets = self.targets.as_mut().unwrap();
+
+        let mut scene_camera = camera.clone();
+        scene_camera.set_viewport(Viewport::new_at_origo(width, height));
+        if hdr {
+            scene_camera.disable_tone_and_color_mapping();
+        }
+        match &targets.multisample {
+            Some(MultisampleTarget::Hdr(multisample)) => {
+                multisample
+                    .clear(self.clear_state)
+                    .render(&scene_camera, objects, lights);
+                multisample.resolve_color_to(&targets.color.as_color_target(None));
+                multisample.resolve_depth_to(&targets.depth.as_depth_target());
+            }
+            Some(MultisampleTarget::PackedHdr(multisample)) => {
+                multisample
+                    .clear(self.clear_state)
+                    .render(&scene_camera, objects, lights);
+                multisample.resolve_color_to(&targets.color.as_color_target(None));
+                multisample.resolve_depth_to(&targets.depth.as_depth_target());
+            }
+            Some(MultisampleTarget::Ldr(multisample)) => {
+                multisample
+                    .clear(self.clear_state)
+                    .render(&scene_camera, objects, lights);
+                multisample.resolve_color_to(&targets.color.as_color_target(None));
+                multisample.resolve_depth_to(&targets.depth.as_depth_target());
+            }
+            None => {
+                RenderTarget::new(
+                    targets.color.as_color_target(None),
+                    targets.depth.as_depth_target(),
+                )
+                .clear(self.clear_state)
+                .render(&scene_camera, objects, lights);
+            }
+        }
+
+        let color_texture = Some(ColorTexture::Single(&targets.color));
+        let depth_texture = Some(DepthTexture::Single(&targets.depth));
+        if hdr {
+            target.apply_screen_effect(
+                &ScreenEffect::default(),
+                camera,
+                &[],
+                color_texture,
+                depth_texture,
+            );
+        } else {
+            target.apply_screen_effect(
+                &CopyEffect::default(),
+                camera,
+                &[],
+                color_texture,
+                depth_texture,
+            );
+        }
+    }
+}
+
+// The intermediate render targets, which depend on the color precision, the multisample settings and the size of the viewport.
+struct PipelineTargets {
+    precision: ColorPrecision,
+    samples: u32,
+    multisample: Option<MultisampleTarget>,
+    color: Texture2D,
+    depth: DepthTexture2D,
+}
+
+enum MultisampleTarget {
+    Hdr(RenderTargetMultisample<[f16; 4], f32>),
+    PackedHdr(RenderTargetMultisample<r11g11b10f, f32>),
+    Ldr(RenderTargetMultisample<[u8; 4], f32>),
+}
+
+impl PipelineTargets {
+    fn new(
+        context: &Context,
+        precision: ColorPrecision,
+        samples: u32,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let multisample = (samples > 0).then(|| match precision {
+            ColorPrecision::Rgba16f => MultisampleTarget::Hdr(RenderTargetMultisample::new(
+                context, width, height, samples,
+            )),
+            ColorPrecision::Rg11b10f => MultisampleTarget::PackedHdr(RenderTargetMultisample::new(
+                context, width, height, samples,
+            )),
+            ColorPrecision::Rgba8 => MultisampleTarget::Ldr(RenderTargetMultisample::new(
+                context, width, height, samples,
+            )),
+        });
+        Self {
+            precision,
+            samples,
+            multisample,
+            color: Texture2D::new_empty_with_precision(
+                context,
+                precision,
+                width,
+                height,
+                Interpolation::Nearest,
+                Interpolation::Nearest,
+                None,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            ),
+            depth: DepthTexture2D::new::<f32>(
+                context,
+                width,
+                height,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            ),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::renderer::*;
+
+enum RenderItem<'a> {
+    Object(&'a dyn Object),
+    GeometryWithMaterial(&'a dyn Geometry, &'a dyn Material),
+}
+
+impl RenderItem<'_> {
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        match self {
+            Self::Object(object) => object.aabb(),
+            Self::GeometryWithMaterial(geometry, _) => geometry.aabb(),
+        }
+    }
+
+    fn material_type(&self) -> MaterialType {
+        match self {
+            Self::Object(object) => object.material_type(),
+            Self::GeometryWithMaterial(_, material) => material.material_type(),
+        }
+    }
+
+    fn render(&self, camera: &Camera, lights: &[&dyn Light], disable_depth_write: bool) {
+        match self {
+            Self::Object(object) => object.render(camera, lights),
+            Self::GeometryWithMaterial(geometry, material) => {
+                if disable_depth_write {
+                    geometry.render_with_material(&DepthReadOnly(*material), camera, lights)
+                } else {
+                    geometry.render_with_material(*material, camera, lights)
+                }
+            }
+        }
+    }
+}
+
+// Renders with the given material but without writing to the depth buffer.
+struct DepthReadOnly<'a>(&'a dyn Material);
+
+impl Material for DepthReadOnly<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        self.0.fragment_shader_source(lights)
+    }
+
+    fn id(&self) -> u16 {
+        self.0.id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.0.use_uniforms(program, camera, lights)
+    }
+
+    fn render_states(&self) -> RenderStates {
+        let mut render_states = self.0.render_states();
+        render_states.write_mask.depth = false;
+        render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        self.0.material_type()
+    }
+
+    fn shader_defines(&self) -> ShaderDefines {
+        self.0.shader_defines()
+    }
+}
+
+///
+/// A list of objects to render, which separates the opaque and transparent objects and renders them in the correct order.
+/// The opaque objects are rendered from front to back, to minimize overdraw, and then the transparent objects are rendered from back to front,
+/// based on the distance from the camera to the center of the bounding box of each object.
+/// Objects outside the camera frustum are not rendered.
+///
+/// When a geometry and a material are added separately using [RenderQueue::push_with_material], writing to the depth buffer is disabled
+/// when rendering transparent materials so that transparent objects do not hide each other.
+/// Objects added with [RenderQueue::push] are rendered with the render states defined by the object.
+///
+/// The render queue only supports forward materials, use [RenderTarget::render] for objects with a [MaterialType::Deferred] or
+/// [MaterialType::OrderIndependentTransparent] material.
+///
+#[derive(Default)]
+pub struct RenderQueue<'a> {
+    items: Vec<RenderItem<'a>>,
+}
+
+impl<'a> RenderQueue<'a> {
+    ///
+    /// Creates a new empty render queue.
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Adds the object to the queue.
+    /// The object is rendered with its own render states, so writing to the depth buffer is not disabled if the object is transparent.
+    /// Use [RenderQueue::push_with_material] or [RenderQueue::push_gm] to have the queue disable writing to the depth buffer for transparent materials.
+    ///
+    pub fn push(&mut self, object: &'a dyn Object) {
+        self.items.push(RenderItem::Object(object));
+    }
+
+    ///
+    /// Adds the geometry to the queue which is then rendered with the given material.
+    ///
+    pub fn push_with_material(&mut self, geometry: &'a dyn Geometry, material: &'a dyn Material) {
+        self.items
+            .push(RenderItem::GeometryWithMaterial(geometry, material));
+    }
+
+    ///
+    /// Adds the geometry and material of the [Gm] to the queue, which makes it possible for the queue to disable writing to the depth buffer if the material is transparent.
+    ///
+    pub fn push_gm<G: Geometry, M: Material>(&mut self, object: &'a Gm<G, M>) {
+        self.push_with_material(&object.geometry, &object.material);
+    }
+
+    ///
+    /// Returns the number of objects in the queue.
+    ///
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    ///
+    /// Returns whether or not the queue is empty.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    ///
+    /// Removes all objects from the queue, for example to reuse the queue in the next frame.
+    ///
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    ///
+    /// Renders the objects in the queue using the given camera and lights, first the opaque objects from front to back and then the transparent objects from back to front.
+    /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
+    ///
+    pub fn render(&self, camera: &Camera, lights: &[&dyn Light]) {
+        let position = camera.position();
+        let frustum = camera.frustum();
+        let (mut transparent, mut opaque): (Vec<_>, Vec<_>) = self
+            .items
+            .iter()
+            .filter(|item| frustum.intersects_aabb(&item.aabb()))
+            .map(|item| (item, position.distance2(item.aabb().center())))
+            .partition(|(item, _)| item.material_type() == MaterialType::Transparent);
+        opaque.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        transparent.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        for (item, _) in opaque {
+            item.render(camera, lights, false);
+        }
+        for (item, _) in transparent {
+            item.render(camera, lights, true);
+        }
+    }
+}
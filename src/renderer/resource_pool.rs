@@ -0,0 +1,282 @@
+use crate::renderer::*;
+use std::marker::PhantomData;
+
+///
+/// A generational handle to a resource stored in a [ResourcePool].
+/// The handle is a small copyable identifier which stays the same for the lifetime of the resource and which is never reused by another resource,
+/// so it can be stored in for example undo/redo records without keeping the resource alive.
+/// When the resource is removed, the handle becomes invalid and [ResourcePool::get] returns `None`.
+///
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "")
+)]
+pub struct Handle<T> {
+    index: u32,
+    generation: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    ///
+    /// Returns the index of the slot in the [ResourcePool] where the resource is stored.
+    ///
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    ///
+    /// Returns the generation of the handle, which separates this handle from the handles to other resources that have been stored in the same slot.
+    ///
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> std::hash::Hash for Handle<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handle")
+            .field("index", &self.index)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+///
+/// A handle to a [Mesh] stored in a [ResourcePool].
+///
+pub type MeshHandle = Handle<Mesh>;
+
+///
+/// A handle to a [Texture2D] stored in a [ResourcePool].
+///
+pub type TextureHandle = Handle<Texture2D>;
+
+struct Slot<T> {
+    value: Option<T>,
+    generation: u32,
+    next_generation: u32,
+}
+
+///
+/// Owns a set of resources, for example meshes, textures or objects, and gives access to them through generational [Handle]s.
+/// This is useful for editor style applications that create and destroy many objects and need stable identifiers for them.
+///
+/// The slots of removed resources are reused, but the generation of a handle is never reused, so a handle to a removed resource never refers to a new resource.
+/// A removed resource can be put back with the same handle using [ResourcePool::restore], for example when undoing the removal.
+///
+pub struct ResourcePool<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<T> ResourcePool<T> {
+    ///
+    /// Creates a new empty pool.
+    ///
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    ///
+    /// Inserts the resource into the pool and returns a new handle to it.
+    ///
+    pub fn insert(&mut self, value: T) -> Handle<T> {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    value: None,
+                    generation: 0,
+                    next_generation: 0,
+                });
+                self.slots.len() as u32 - 1
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.generation = slot.next_generation;
+        slot.next_generation += 1;
+        slot.value = Some(value);
+        self.len += 1;
+        Handle {
+            index,
+            generation: slot.generation,
+            _marker: PhantomData,
+        }
+    }
+
+    ///
+    /// Removes the resource with the given handle from the pool and returns it.
+    /// Returns `None` if the handle is invalid, ie. if the resource has already been removed.
+    ///
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        let slot = self.slots.get_mut(handle.index as usize)?;
+        if slot.generation != handle.generation || slot.value.is_none() {
+            return None;
+        }
+        self.free.push(handle.index);
+        self.len -= 1;
+        slot.value.take()
+    }
+
+    ///
+    /// Puts a removed resource back into the pool with the given handle, for example when undoing the removal of the resource.
+    /// Returns the resource as an error if the handle is already in use.
+    ///
+    pub fn restore(&mut self, handle: Handle<T>, value: T) -> Result<(), T> {
+        let index = handle.index as usize;
+        while self.slots.len() <= index {
+            self.free.push(self.slots.len() as u32);
+            self.slots.push(Slot {
+                value: None,
+                generation: 0,
+                next_generation: 0,
+            });
+        }
+        let slot = &mut self.slots[index];
+        if slot.value.is_some() {
+            return Err(value);
+        }
+        slot.generation = handle.generation;
+        slot.next_generation = slot.next_generation.max(handle.generation + 1);
+        slot.value = Some(value);
+        self.free.retain(|i| *i != handle.index);
+        self.len += 1;
+        Ok(())
+    }
+
+    ///
+    /// Returns the resource with the given handle or `None` if the handle is invalid.
+    ///
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    ///
+    /// Returns the resource with the given handle or `None` if the handle is invalid.
+    ///
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.value.as_mut())
+    }
+
+    ///
+    /// Returns whether or not the handle refers to a resource in this pool.
+    ///
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.get(handle).is_some()
+    }
+
+    ///
+    /// Returns the number of resources in the pool.
+    ///
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    ///
+    /// Returns whether or not the pool is empty.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///
+    /// Removes all resources from the pool. All handles to the removed resources become invalid.
+    ///
+    pub fn clear(&mut self) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.value.take().is_some() {
+                self.free.push(index as u32);
+            }
+        }
+        self.len = 0;
+    }
+
+    ///
+    /// Returns an iterator over the handles and resources in the pool.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.value.as_ref().map(|value| {
+                (
+                    Handle {
+                        index: index as u32,
+                        generation: slot.generation,
+                        _marker: PhantomData,
+                    },
+                    value,
+                )
+            })
+        })
+    }
+
+    ///
+    /// Returns an iterator over the handles and mutable resources in the pool.
+    ///
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let generation = slot.generation;
+                slot.value.as_mut().map(|value| {
+                    (
+                        Handle {
+                            index: index as u32,
+                            generation,
+                            _marker: PhantomData,
+                        },
+                        value,
+                    )
+                })
+            })
+    }
+
+    ///
+    /// Returns an iterator over the resources in the pool, for example to render all objects in a pool using [RenderTarget::render].
+    ///
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+}
+
+impl<T> Default for ResourcePool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,551 @@
+use crate::renderer::*;
+
+///
+/// The version of the scene file format written by [SceneFile::to_json].
+/// Files with a newer version than this cannot be loaded.
+///
+pub const SCENE_FILE_VERSION: u32 = 1;
+
+///
+/// A description of a scene, ie. the object transformations, material parameters, light settings, camera bookmarks and environment,
+/// which can be saved to and loaded from a versioned file format (see [SceneFile::to_json] and [SceneFile::from_json], requires the `serialize` feature).
+///
+/// The scene file only contains plain data and references to assets by their path, for example the geometry of an object or a texture,
+/// which is then loaded by the application, for example using [three_d_asset::io::load].
+/// This makes it possible to implement save and load in an editor style application.
+///
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneFile {
+    /// The version of the file format, see [SCENE_FILE_VERSION].
+    pub version: u32,
+    /// The objects in the scene.
+    pub objects: Vec<ObjectDescription>,
+    /// The lights in the scene.
+    pub lights: Vec<LightDescription>,
+    /// A list of named camera views.
+    pub cameras: Vec<CameraBookmark>,
+    /// The environment of the scene.
+    pub environment: EnvironmentDescription,
+}
+
+impl Default for SceneFile {
+    fn default() -> Self {
+        Self {
+            version: SCENE_FILE_VERSION,
+            objects: Vec::new(),
+            lights: Vec::new(),
+            cameras: Vec::new(),
+            environment: EnvironmentDescription::default(),
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl SceneFile {
+    ///
+    /// Serializes the scene to a JSON string.
+    ///
+    pub fn to_json(&self) -> Result<String, RendererError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| RendererError::SceneSerialization(e.to_string()))
+    }
+
+    ///
+    /// Deserializes a scene from a JSON string.
+    /// Returns an error if the file format version is newer than [SCENE_FILE_VERSION].
+    ///
+    pub fn from_json(json: &str) -> Result<Self, RendererError> {
+        let scene: Self = serde_json::from_str(json)
+            .map_err(|e| RendererError::SceneSerialization(e.to_string()))?;
+        if scene.version > SCENE_FILE_VERSION {
+            return Err(RendererError::UnsupportedSceneFileVersion(
+                scene.version,
+                SCENE_FILE_VERSION,
+            ));
+        }
+        Ok(Self {
+            version: SCENE_FILE_VERSION,
+            ..scene
+        })
+    }
+
+    ///
+    /// Saves the scene as JSON to the file at the given path.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), RendererError> {
+        std::fs::write(path, self.to_json()?).map_err(RendererError::SceneFileIo)
+    }
+
+    ///
+    /// Loads a scene from the JSON file at the given path.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, RendererError> {
+        let json = std::fs::read_to_string(path).map_err(RendererError::SceneFileIo)?;
+        Self::from_json(&json)
+    }
+}
+
+///
+/// Describes an object in a [SceneFile].
+///
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectDescription {
+    /// The name of the object.
+    pub name: String,
+    /// The path to the asset containing the geometry of the object.
+    pub geometry: String,
+    /// The transformation of the object given as the four columns of the transformation matrix.
+    pub transformation: [[f32; 4]; 4],
+    /// The material of the object.
+    pub material: MaterialDescription,
+}
+
+impl ObjectDescription {
+    ///
+    /// Creates a new object description with the given name, geometry asset path, transformation and material.
+    ///
+    pub fn new(
+        name: impl Into<String>,
+        geometry: impl Into<String>,
+        transformation: Mat4,
+        material: MaterialDescription,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            geometry: geometry.into(),
+            transformation: transformation.into(),
+            material,
+        }
+    }
+
+    ///
+    /// Returns the transformation of the object.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.transformation.into()
+    }
+}
+
+///
+/// Describes the parameters of a [PhysicalMaterial] in a [SceneFile], where the textures are referenced by their asset paths.
+///
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialDescription {
+    /// The name of the material.
+    pub name: String,
+    /// The albedo color in sRGBA.
+    pub albedo: [u8; 4],
+    /// The path to the albedo texture, if any.
+    pub albedo_texture: Option<String>,
+    /// The metallic factor.
+    pub metallic: f32,
+    /// The roughness factor.
+    pub roughness: f32,
+    /// The path to the metallic (blue channel) and roughness (green channel) texture, if any.
+    pub metallic_roughness_texture: Option<String>,
+    /// The occlusion strength.
+    pub occlusion_strength: f32,
+    /// The path to the occlusion texture, if any.
+    pub occlusion_texture: Option<String>,
+    /// The normal scale.
+    pub normal_scale: f32,
+    /// The path to the normal texture, if any.
+    pub normal_texture: Option<String>,
+    /// The emissive color in sRGB.
+    pub emissive: [u8; 3],
+    /// The path to the emissive texture, if any.
+    pub emissive_texture: Option<String>,
+    /// Whether or not the material is transparent.
+    pub is_transparent: bool,
+    /// The alpha cutout threshold, if any, see [PhysicalMaterial::alpha_cutout].
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub alpha_cutout: Option<f32>,
+}
+
+impl Default for MaterialDescription {
+    fn default() -> Self {
+        Self::from_physical_material(&PhysicalMaterial::default())
+    }
+}
+
+impl MaterialDescription {
+    ///
+    /// Creates a material description with the parameters of the given material.
+    /// The texture paths are not known from the material and are therefore set to `None`.
+    ///
+    pub fn from_physical_material(material: &PhysicalMaterial) -> Self {
+        Self {
+            name: material.name.clone(),
+            albedo: color_to_array(material.albedo),
+            albedo_texture: None,
+            metallic: material.metallic,
+            roughness: material.roughness,
+            metallic_roughness_texture: None,
+            occlusion_strength: material.occlusion_strength,
+            occlusion_texture: None,
+            normal_scale: material.normal_scale,
+            normal_texture: None,
+            emissive: [
+                material.emissive.r,
+                material.emissive.g,
+                material.emissive.b,
+            ],
+            emissive_texture: None,
+            is_transparent: material.is_transparent,
+            alpha_cutout: material.alpha_cutout,
+        }
+    }
+
+    ///
+    /// Applies the parameters to the given material. The textures are not changed, since they need to be loaded by the application.
+    /// If the transparency changes, only the write mask and blending of the [render states](PhysicalMaterial::render_states) are changed,
+    /// so for example the culling set by the application is kept.
+    ///
+    pub fn apply(&self, material: &mut PhysicalMaterial) {
+        material.name = self.name.clone();
+        material.albedo = color_from_array(&self.albedo);
+        material.metallic = self.metallic;
+        material.roughness = self.roughness;
+        material.occlusion_strength = self.occlusion_strength;
+        material.normal_scale = self.normal_scale;
+        material.emissive = Srgba::new(self.emissive[0], self.emissive[1], self.emissive[2], 255);
+        material.alpha_cutout = self.alpha_cutout;
+        if material.is_transparent != self.is_transparent {
+            if self.is_transparent {
+                material.render_states.write_mask = WriteMask::COLOR;
+                material.render_states.blend = Blend::TRANSPARENCY;
+            } else {
+                material.render_states.write_mask = WriteMask::default();
+                material.render_states.blend = Blend::Disabled;
+            }
+        }
+        material.is_transparent = self.is_transparent;
+    }
+}
+
+///
+/// Describes a light in a [SceneFile].
+///
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum LightDescription {
+    /// An [AmbientLight].
+    Ambient {
+        /// The intensity of the light.
+        intensity: f32,
+        /// The color of the light in sRGB.
+        color: [u8; 4],
+    },
+    /// A [DirectionalLight].
+    Directional {
+        /// The intensity of the light.
+        intensity: f32,
+        /// The color of the light in sRGB.
+        color: [u8; 4],
+        /// The direction of the light.
+        direction: [f32; 3],
+    },
+    /// A [PointLight].
+    Point {
+        /// The intensity of the light.
+        intensity: f32,
+        /// The color of the light in sRGB.
+        color: [u8; 4],
+        /// The position of the light.
+        position: [f32; 3],
+        /// The constant, linear and quadratic attenuation factors.
+        attenuation: [f32; 3],
+        /// The range of the light, see [Attenuation::range].
+        #[cfg_attr(feature = "serialize", serde(default))]
+        attenuation_range: Option<f32>,
+    },
+    /// A [SpotLight].
+    Spot {
+        /// The intensity of the light.
+        intensity: f32,
+        /// The color of the light in sRGB.
+        color: [u8; 4],
+        /// The position of the light.
+        position: [f32; 3],
+        /// The direction of the light.
+        direction: [f32; 3],
+        /// The cutoff angle in degrees.
+        cutoff: f32,
+        /// The inner cutoff angle in degrees, see [SpotLight::inner_cutoff]. If not specified, the default of the [SpotLight] is used.
+        #[cfg_attr(feature = "serialize", serde(default))]
+        inner_cutoff: Option<f32>,
+        /// The constant, linear and quadratic attenuation factors.
+        attenuation: [f32; 3],
+        /// The range of the light, see [Attenuation::range].
+        #[cfg_attr(feature = "serialize", serde(default))]
+        attenuation_range: Option<f32>,
+    },
+    /// An [AreaLight].
+    Area {
+        /// The intensity of the light.
+        intensity: f32,
+        /// The color of the light in sRGB.
+        color: [u8; 4],
+        /// The position of the center of the light.
+        position: [f32; 3],
+        /// The direction of the light.
+        direction: [f32; 3],
+        /// The up direction of the light.
+        up: [f32; 3],
+        /// The width and height of the light.
+        size: [f32; 2],
+    },
+}
+
+impl From<&AmbientLight> for LightDescription {
+    fn from(light: &AmbientLight) -> Self {
+        Self::Ambient {
+            intensity: light.intensity,
+            color: color_to_array(light.color),
+        }
+    }
+}
+
+impl From<&DirectionalLight> for LightDescription {
+    fn from(light: &DirectionalLight) -> Self {
+        Self::Directional {
+            intensity: light.intensity,
+            color: color_to_array(light.color),
+            direction: light.direction.into(),
+        }
+    }
+}
+
+impl From<&PointLight> for LightDescription {
+    fn from(light: &PointLight) -> Self {
+        Self::Point {
+            intensity: light.intensity,
+            color: color_to_array(light.color),
+            position: light.position.into(),
+            attenuation: attenuation_to_array(&light.attenuation),
+            attenuation_range: light.attenuation.range,
+        }
+    }
+}
+
+impl From<&SpotLight> for LightDescription {
+    fn from(light: &SpotLight) -> Self {
+        Self::Spot {
+            intensity: light.intensity,
+            color: color_to_array(light.color),
+            position: light.position.into(),
+            direction: light.direction.into(),
+            cutoff: Deg::from(light.cutoff).0,
+            inner_cutoff: Some(Deg::from(light.inner_cutoff).0),
+            attenuation: attenuation_to_array(&light.attenuation),
+            attenuation_range: light.attenuation.range,
+        }
+    }
+}
+
+impl From<&AreaLight> for LightDescription {
+    fn from(light: &AreaLight) -> Self {
+        Self::Area {
+            intensity: light.intensity,
+            color: color_to_array(light.color),
+            position: light.position.into(),
+            direction: light.direction.into(),
+            up: light.up.into(),
+            size: [light.width, light.height],
+        }
+    }
+}
+
+impl LightDescription {
+    ///
+    /// Creates the light described by this light description.
+    ///
+    pub fn to_light(&self, context: &Context) -> Box<dyn Light> {
+        match self {
+            Self::Ambient { intensity, color } => Box::new(AmbientLight::new(
+                context,
+                *intensity,
+                color_from_array(color),
+            )),
+            Self::Directional {
+                intensity,
+                color,
+                direction,
+            } => Box::new(DirectionalLight::new(
+                context,
+                *intensity,
+                color_from_array(color),
+                &(*direction).into(),
+            )),
+            Self::Point {
+                intensity,
+                color,
+                position,
+                attenuation,
+                attenuation_range,
+            } => Box::new(PointLight::new(
+                context,
+                *intensity,
+                color_from_array(color),
+                &(*position).into(),
+                attenuation_from_array(attenuation, *attenuation_range),
+            )),
+            Self::Spot {
+                intensity,
+                color,
+                position,
+                direction,
+                cutoff,
+                inner_cutoff,
+                attenuation,
+                attenuation_range,
+            } => {
+                let mut light = SpotLight::new(
+                    context,
+                    *intensity,
+                    color_from_array(color),
+                    &(*position).into(),
+                    &(*direction).into(),
+                    degrees(*cutoff),
+                    attenuation_from_array(attenuation, *attenuation_range),
+                );
+                if let Some(inner_cutoff) = inner_cutoff {
+                    light.inner_cutoff = degrees(*inner_cutoff).into();
+                }
+                Box::new(light)
+            }
+            Self::Area {
+                intensity,
+                color,
+                position,
+                direction,
+                up,
+                size,
+            } => Box::new(AreaLight::new(
+                context,
+                *intensity,
+                color_from_array(color),
+                &(*position).into(),
+                &(*direction).into(),
+                &(*up).into(),
+                size[0],
+                size[1],
+            )),
+        }
+    }
+}
+
+fn color_to_array(color: Srgba) -> [u8; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+fn color_from_array(color: &[u8; 4]) -> Srgba {
+    Srgba::new(color[0], color[1], color[2], color[3])
+}
+
+fn attenuation_to_array(attenuation: &Attenuation) -> [f32; 3] {
+    [
+        attenuation.constant,
+        attenuation.linear,
+        attenuation.quadratic,
+    ]
+}
+
+fn attenuation_from_array(attenuation: &[f32; 3], range: Option<f32>) -> Attenuation {
+    Attenuation {
+        constant: attenuation[0],
+        linear: attenuation[1],
+        quadratic: attenuation[2],
+        range,
+    }
+}
+
+///
+/// A named camera view in a [SceneFile].
+///
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraBookmark {
+    /// The name of the camera view.
+    pub name: String,
+    /// The position of the camera.
+    pub position: [f32; 3],
+    /// The target of the camera.
+    pub target: [f32; 3],
+    /// The up direction of the camera.
+    pub up: [f32; 3],
+    /// The vertical field of view in degrees for a perspective projection or `None` for an orthographic projection.
+    pub field_of_view: Option<f32>,
+    /// The height of the view for an orthographic projection, not used for a perspective projection.
+    pub height: f32,
+    /// The distance to the near plane.
+    pub z_near: f32,
+    /// The distance to the far plane.
+    pub z_far: f32,
+}
+
+impl CameraBookmark {
+    ///
+    /// Creates a bookmark of the current view and projection of the given camera.
+    ///
+    pub fn from_camera(name: impl Into<String>, camera: &Camera) -> Self {
+        let (field_of_view, height) = match camera.projection_type() {
+            three_d_asset::ProjectionType::Perspective { field_of_view_y } => {
+                (Some(Deg::from(*field_of_view_y).0), 0.0)
+            }
+            three_d_asset::ProjectionType::Orthographic { height } => (None, *height),
+        };
+        Self {
+            name: name.into(),
+            position: (*camera.position()).into(),
+            target: (*camera.target()).into(),
+            up: (*camera.up()).into(),
+            field_of_view,
+            height,
+            z_near: camera.z_near(),
+            z_far: camera.z_far(),
+        }
+    }
+
+    ///
+    /// Sets the view and projection of the given camera to the bookmarked view and projection. The viewport is not changed.
+    ///
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.set_view(self.position.into(), self.target.into(), self.up.into());
+        match self.field_of_view {
+            Some(field_of_view) => {
+                camera.set_perspective_projection(degrees(field_of_view), self.z_near, self.z_far)
+            }
+            None => camera.set_orthographic_projection(self.height, self.z_near, self.z_far),
+        }
+    }
+}
+
+///
+/// Describes the environment of a [SceneFile].
+///
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvironmentDescription {
+    /// The color the screen is cleared to before rendering the scene, in sRGBA.
+    pub background_color: [u8; 4],
+    /// The path to the environment map (for example an equirectangular HDR image) used for the skybox and image based lighting, if any.
+    pub environment_map: Option<String>,
+    /// The exposure of the camera in stops (EV).
+    pub exposure: f32,
+}
+
+impl Default for EnvironmentDescription {
+    fn default() -> Self {
+        Self {
+            background_color: [0, 0, 0, 255],
+            environment_map: None,
+            exposure: 0.0,
+        }
+    }
+}
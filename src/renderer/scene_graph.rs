@@ -0,0 +1,616 @@
+use crate::renderer::*;
+
+///
+/// A handle to a [Node] in a [Scene].
+///
+pub type NodeHandle = Handle<Node>;
+
+///
+/// An [Object] which can be attached to a [Node] in a [Scene], which places the object at the world transformation of the node.
+/// Implemented for [Gm] with a [Mesh], [InstancedMesh], [SkinnedMesh], [Points] or [Sprites] and any material.
+///
+pub trait NodeObject: Object {
+    ///
+    /// Sets the transformation of the object, which is the world transformation of the node the object is attached to.
+    ///
+    fn set_transformation(&mut self, transformation: Mat4);
+
+    ///
+    /// Returns this object as an [Object].
+    ///
+    fn as_object(&self) -> &dyn Object;
+
+    ///
+    /// Returns the geometry of this object.
+    ///
+    fn geometry(&self) -> &dyn Geometry;
+
+    ///
+    /// Returns the material of this object.
+    ///
+    fn material(&self) -> &dyn Material;
+}
+
+macro_rules! impl_node_object {
+    ($geometry:ty) => {
+        impl<M: Material> NodeObject for Gm<$geometry, M> {
+            fn set_transformation(&mut self, transformation: Mat4) {
+                self.geometry.set_transformation(transformation);
+            }
+
+            fn as_object(&self) -> &dyn Object {
+                self
+            }
+
+            fn geometry(&self) -> &dyn Geometry {
+                &self.geometry
+            }
+
+            fn material(&self) -> &dyn Material {
+                &self.material
+            }
+        }
+    };
+}
+
+impl_node_object!(Mesh);
+impl_node_object!(InstancedMesh);
+impl_node_object!(SkinnedMesh);
+impl_node_object!(Points);
+impl_node_object!(Sprites);
+
+///
+/// A light attached to a [Node] in a [Scene].
+/// The light is placed at the origin of the node and points along the negative z-axis of the node,
+/// so the position and direction of the light are overwritten by [Scene::update].
+///
+pub enum NodeLight {
+    /// A directional light, where only the direction is given by the node.
+    Directional(DirectionalLight),
+    /// A point light, where only the position is given by the node.
+    Point(PointLight),
+    /// A spot light, where both the position and the direction are given by the node.
+    Spot(SpotLight),
+}
+
+impl NodeLight {
+    ///
+    /// Returns the light as a trait object, for example to use it with [RenderTarget::render].
+    ///
+    pub fn as_light(&self) -> &dyn Light {
+        match self {
+            Self::Directional(light) => light,
+            Self::Point(light) => light,
+            Self::Spot(light) => light,
+        }
+    }
+
+    fn set_transformation(&mut self, transformation: Mat4) {
+        let position = (transformation * vec4(0.0, 0.0, 0.0, 1.0)).truncate();
+        // The transformation may contain scale, so the direction is normalized
+        let direction = (transformation * vec4(0.0, 0.0, -1.0, 0.0))
+            .truncate()
+            .normalize();
+        match self {
+            Self::Directional(light) => light.direction = direction,
+            Self::Point(light) => light.position = position,
+            Self::Spot(light) => {
+                light.position = position;
+                light.direction = direction;
+            }
+        }
+    }
+}
+
+///
+/// A node in a [Scene] with a transformation relative to its parent and an optional object, light and camera.
+/// The world transformation of the node, ie. the transformation of all its parents combined with its own, is applied to the object, light and camera by [Scene::update].
+///
+/// When the scene is updated at a fixed rate which is different from the frame rate, for example by a fixed timestep simulation,
+/// the transformations can be interpolated between the last two updates using [Scene::begin_tick] and [Scene::interpolate].
+///
+/// Each node has a `visible` and an `enabled` flag which propagate down the hierarchy:
+/// - A node which is not visible is not rendered, and neither are its children unless they ignore the visibility of their parent, see [Scene::set_inherit_visibility].
+///   Hidden nodes are otherwise kept up to date, so they can be shown again instantly.
+/// - A node which is not enabled is skipped entirely together with all its children, regardless of their own flags, ie. they are neither rendered nor updated.
+///
+/// The flags of a node are changed through the scene, for example [Scene::set_visible], which updates the effective flags of all nodes in the sub-tree.
+///
+pub struct Node {
+    /// The name of the node, for example used to find the node in an editor.
+    pub name: String,
+    /// The transformation of the node relative to its parent, or relative to the world if this is a root node.
+    pub transformation: Mat4,
+    /// The object of the node, if any, see [NodeObject].
+    pub object: Option<Box<dyn NodeObject>>,
+    /// The light of the node, if any, see [NodeLight].
+    pub light: Option<NodeLight>,
+    /// The camera of the node, if any, which is placed at the origin of the node and looks along the negative z-axis of the node with the y-axis as up direction.
+    pub camera: Option<Camera>,
+    world_transformation: Mat4,
+    previous_world_transformation: Option<Mat4>,
+    interpolated: bool,
+    dirty: bool,
+    parent: Option<NodeHandle>,
+    children: Vec<NodeHandle>,
+    visible: bool,
+    enabled: bool,
+    inherit_visibility: bool,
+    effective_visible: bool,
+    effective_enabled: bool,
+}
+
+impl Node {
+    ///
+    /// Returns the transformation of this node relative to the world as computed by the last call to [Scene::update].
+    ///
+    pub fn world_transformation(&self) -> Mat4 {
+        self.world_transformation
+    }
+
+    ///
+    /// Returns the world transformation of this node at the beginning of the current tick, see [Scene::begin_tick],
+    /// or `None` if the node is not interpolated, for example because it was added or [Scene::reset_interpolation] was called during the current tick.
+    ///
+    pub fn previous_world_transformation(&self) -> Option<Mat4> {
+        self.previous_world_transformation
+    }
+
+    ///
+    /// Returns the parent of this node or `None` if this is a root node.
+    ///
+    pub fn parent(&self) -> Option<NodeHandle> {
+        self.parent
+    }
+
+    ///
+    /// Returns the children of this node.
+    ///
+    pub fn children(&self) -> &[NodeHandle] {
+        &self.children
+    }
+
+    ///
+    /// Returns the visible flag of this node without taking the parents into account, see [Node::is_visible] for the effective visibility.
+    ///
+    pub fn visible_flag(&self) -> bool {
+        self.visible
+    }
+
+    ///
+    /// Returns the enabled flag of this node without taking the parents into account, see [Node::is_enabled] for the effective value.
+    ///
+    pub fn enabled_flag(&self) -> bool {
+        self.enabled
+    }
+
+    ///
+    /// Returns whether or not this node is hidden when its parent is hidden, see [Scene::set_inherit_visibility].
+    ///
+    pub fn inherits_visibility(&self) -> bool {
+        self.inherit_visibility
+    }
+
+    ///
+    /// Returns whether or not this node is visible when taking the parents into account, ie. whether the object of the node is rendered if the node is also enabled.
+    ///
+    pub fn is_visible(&self) -> bool {
+        self.effective_visible
+    }
+
+    ///
+    /// Returns whether or not this node and all its parents are enabled.
+    ///
+    pub fn is_enabled(&self) -> bool {
+        self.effective_enabled
+    }
+
+    ///
+    /// Returns whether or not the object of this node should be rendered, ie. whether the node is both visible and enabled.
+    ///
+    pub fn is_rendered(&self) -> bool {
+        self.effective_visible && self.effective_enabled
+    }
+
+    // Applies the given world transformation to the object, light and camera.
+    fn apply_transformation(&mut self, transformation: Mat4) {
+        if let Some(object) = &mut self.object {
+            object.set_transformation(transformation);
+        }
+        if let Some(light) = &mut self.light {
+            light.set_transformation(transformation);
+        }
+        if let Some(camera) = &mut self.camera {
+            let position = (transformation * vec4(0.0, 0.0, 0.0, 1.0)).truncate();
+            let direction = (transformation * vec4(0.0, 0.0, -1.0, 0.0)).truncate();
+            let up = (transformation * vec4(0.0, 1.0, 0.0, 0.0)).truncate();
+            camera.set_view(position, position + direction, up);
+        }
+    }
+}
+
+///
+/// A hierarchy of [Node]s, each with an optional object, light and camera.
+/// The nodes are identified by [NodeHandle]s which stay valid until the node is removed.
+///
+/// Call [Scene::update] after changing the nodes and before rendering to propagate the transformations down the hierarchy.
+/// Only the world transformations of the changed nodes and their children are recomputed.
+///
+/// Entire sub-assemblies can be hidden and shown cheaply using [Scene::set_visible] and [Scene::set_enabled] on their root node,
+/// instead of removing and adding the objects.
+///
+pub struct Scene {
+    nodes: ResourcePool<Node>,
+    roots: Vec<NodeHandle>,
+}
+
+impl Scene {
+    ///
+    /// Creates a new empty scene.
+    ///
+    pub fn new() -> Self {
+        Self {
+            nodes: ResourcePool::new(),
+            roots: Vec::new(),
+        }
+    }
+
+    ///
+    /// Adds a new visible and enabled node with the given name and object and an identity transformation as a child of the given parent, or as a root node if the parent is `None`.
+    ///
+    /// # Panic
+    /// Will panic if the parent is not in the scene.
+    ///
+    pub fn add(
+        &mut self,
+        parent: Option<NodeHandle>,
+        name: impl Into<String>,
+        object: Option<Box<dyn NodeObject>>,
+    ) -> NodeHandle {
+        assert!(
+            parent.map_or(true, |p| self.nodes.contains(p)),
+            "the parent must be in the scene"
+        );
+        let handle = self.nodes.insert(Node {
+            name: name.into(),
+            transformation: Mat4::identity(),
+            object,
+            light: None,
+            camera: None,
+            world_transformation: Mat4::identity(),
+            previous_world_transformation: None,
+            interpolated: false,
+            dirty: true,
+            parent: None,
+            children: Vec::new(),
+            visible: true,
+            enabled: true,
+            inherit_visibility: true,
+            effective_visible: true,
+            effective_enabled: true,
+        });
+        self.attach(handle, parent);
+        handle
+    }
+
+    ///
+    /// Removes the node and all its children from the scene and returns the removed node, or `None` if the node is not in the scene.
+    ///
+    pub fn remove(&mut self, handle: NodeHandle) -> Option<Node> {
+        if !self.nodes.contains(handle) {
+            return None;
+        }
+        self.detach(handle);
+        let mut node = self.nodes.remove(handle).unwrap();
+        let mut stack = std::mem::take(&mut node.children);
+        while let Some(child) = stack.pop() {
+            if let Some(child) = self.nodes.remove(child) {
+                stack.extend(child.children);
+            }
+        }
+        node.parent = None;
+        Some(node)
+    }
+
+    ///
+    /// Moves the node to the given parent, or makes it a root node if the parent is `None`. The node keeps its own flags,
+    /// but its effective visibility and enabled state are updated to the new parent.
+    ///
+    /// # Panic
+    /// Will panic if the node or the parent is not in the scene or if the parent is the node itself or one of its children.
+    ///
+    pub fn set_parent(&mut self, handle: NodeHandle, parent: Option<NodeHandle>) {
+        assert!(self.nodes.contains(handle), "the node must be in the scene");
+        let mut ancestor = parent;
+        while let Some(a) = ancestor {
+            assert!(a != handle, "a node cannot be moved into its own sub-tree");
+            ancestor = self.nodes.get(a).and_then(|n| n.parent);
+        }
+        self.detach(handle);
+        self.attach(handle, parent);
+    }
+
+    ///
+    /// Returns the node with the given handle or `None` if the node is not in the scene.
+    ///
+    pub fn get(&self, handle: NodeHandle) -> Option<&Node> {
+        self.nodes.get(handle)
+    }
+
+    ///
+    /// Returns the node with the given handle mutably or `None` if the node is not in the scene.
+    /// The node is marked as changed, so its world transformation is applied to its object, light and camera the next time [Scene::update] is called.
+    ///
+    pub fn get_mut(&mut self, handle: NodeHandle) -> Option<&mut Node> {
+        let node = self.nodes.get_mut(handle)?;
+        node.dirty = true;
+        Some(node)
+    }
+
+    ///
+    /// Sets the transformation of the node relative to its parent. Returns false if the node is not in the scene.
+    ///
+    pub fn set_transformation(&mut self, handle: NodeHandle, transformation: Mat4) -> bool {
+        match self.get_mut(handle) {
+            Some(node) => {
+                node.transformation = transformation;
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Computes the world transformation of the changed nodes and their children and applies it to their objects, lights and cameras.
+    /// Disabled nodes are skipped and updated when they are enabled again.
+    ///
+    pub fn update(&mut self) {
+        let mut stack: Vec<_> = self
+            .roots
+            .iter()
+            .map(|root| (*root, Mat4::identity(), false))
+            .collect();
+        while let Some((handle, parent_transformation, parent_changed)) = stack.pop() {
+            let node = self.nodes.get_mut(handle).unwrap();
+            let changed = node.dirty || parent_changed;
+            if !node.enabled {
+                node.dirty = changed;
+                continue;
+            }
+            if changed {
+                node.dirty = false;
+                node.interpolated = false;
+                node.world_transformation = parent_transformation * node.transformation;
+                node.apply_transformation(node.world_transformation);
+            }
+            let transformation = node.world_transformation;
+            stack.extend(
+                node.children
+                    .iter()
+                    .map(|child| (*child, transformation, changed)),
+            );
+        }
+    }
+
+    ///
+    /// Begins a new tick of a fixed rate update, for example a step of a fixed timestep simulation, by updating the scene (see [Scene::update])
+    /// and storing the world transformation of each enabled node as the transformation at the beginning of the tick.
+    /// Call this before changing the nodes in each tick, call [Scene::update] after the changes and then call [Scene::interpolate] before rendering each frame.
+    ///
+    pub fn begin_tick(&mut self) {
+        self.update();
+        for (_, node) in self.nodes.iter_mut() {
+            if node.effective_enabled {
+                node.previous_world_transformation = Some(node.world_transformation);
+            }
+        }
+    }
+
+    ///
+    /// Applies the transformations between the beginning of the current tick, see [Scene::begin_tick], and the last call to [Scene::update]
+    /// given by the render alpha in the range `[0, 1]` to the objects, lights and cameras, so the movement is smooth at any frame rate.
+    /// The render alpha is usually the time accumulated since the last tick divided by the duration of a tick.
+    /// The translation and scale are interpolated linearly and the rotation spherically, so the transformations must not contain shear.
+    /// Only the nodes which moved during the tick are changed and the nodes without a previous transformation are not interpolated.
+    ///
+    pub fn interpolate(&mut self, alpha: f32) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        for (_, node) in self.nodes.iter_mut() {
+            if !node.effective_enabled {
+                continue;
+            }
+            match node.previous_world_transformation {
+                Some(previous) if previous != node.world_transformation => {
+                    let transformation = JointPose::from_transformation(previous)
+                        .interpolate(
+                            &JointPose::from_transformation(node.world_transformation),
+                            alpha,
+                        )
+                        .transformation();
+                    node.apply_transformation(transformation);
+                    node.interpolated = true;
+                }
+                _ if node.interpolated => {
+                    node.apply_transformation(node.world_transformation);
+                    node.interpolated = false;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    ///
+    /// Stops interpolating the node in the current tick, so it is rendered at its current transformation, for example because it was teleported.
+    /// Returns false if the node is not in the scene.
+    ///
+    pub fn reset_interpolation(&mut self, handle: NodeHandle) -> bool {
+        match self.nodes.get_mut(handle) {
+            Some(node) => {
+                node.previous_world_transformation = None;
+                node.dirty = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Returns the root nodes, ie. the nodes without a parent.
+    ///
+    pub fn roots(&self) -> &[NodeHandle] {
+        &self.roots
+    }
+
+    ///
+    /// Returns all nodes in the scene in no particular order.
+    ///
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeHandle, &Node)> {
+        self.nodes.iter()
+    }
+
+    ///
+    /// Returns the number of nodes in the scene.
+    ///
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    ///
+    /// Returns whether or not the scene is empty.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    ///
+    /// Shows or hides the node and, unless they ignore the visibility of their parent, its children.
+    /// Returns false if the node is not in the scene.
+    ///
+    pub fn set_visible(&mut self, handle: NodeHandle, visible: bool) -> bool {
+        self.set_flags(handle, |node| node.visible = visible)
+    }
+
+    ///
+    /// Enables or disables the node and all its children. Returns false if the node is not in the scene.
+    ///
+    pub fn set_enabled(&mut self, handle: NodeHandle, enabled: bool) -> bool {
+        self.set_flags(handle, |node| node.enabled = enabled)
+    }
+
+    ///
+    /// Sets whether or not the node is hidden when its parent is hidden. The default is true.
+    /// If false, the visibility of the node only depends on its own visible flag, which makes it possible to show a part of a hidden sub-assembly.
+    /// The enabled flag is always inherited. Returns false if the node is not in the scene.
+    ///
+    pub fn set_inherit_visibility(&mut self, handle: NodeHandle, inherit: bool) -> bool {
+        self.set_flags(handle, |node| node.inherit_visibility = inherit)
+    }
+
+    ///
+    /// Returns the objects of all nodes which are both visible and enabled in depth-first order, for example to render them using [RenderTarget::render].
+    ///
+    pub fn objects(&self) -> impl Iterator<Item = &dyn Object> {
+        self.rendered_nodes()
+            .into_iter()
+            .filter_map(|node| node.object.as_ref().map(|object| object.as_object()))
+    }
+
+    ///
+    /// Returns the lights of all nodes which are both visible and enabled.
+    ///
+    pub fn lights(&self) -> Vec<&dyn Light> {
+        self.rendered_nodes()
+            .into_iter()
+            .filter_map(|node| node.light.as_ref().map(|light| light.as_light()))
+            .collect()
+    }
+
+    ///
+    /// Returns a [RenderQueue] with the objects of all nodes which are both visible and enabled.
+    /// The geometry and material of each object are added separately, so writing to the depth buffer is disabled for transparent materials,
+    /// see [RenderQueue::push_with_material].
+    ///
+    pub fn render_queue(&self) -> RenderQueue<'_> {
+        let mut queue = RenderQueue::new();
+        for node in self.rendered_nodes() {
+            if let Some(object) = node.object.as_ref() {
+                queue.push_with_material(object.geometry(), object.material());
+            }
+        }
+        queue
+    }
+
+    // Returns the visible nodes in depth-first order, skipping the disabled sub-trees.
+    fn rendered_nodes(&self) -> Vec<&Node> {
+        let mut nodes = Vec::new();
+        let mut stack: Vec<_> = self.roots.iter().rev().copied().collect();
+        while let Some(handle) = stack.pop() {
+            let node = self.nodes.get(handle).unwrap();
+            if !node.enabled {
+                continue;
+            }
+            if node.effective_visible {
+                nodes.push(node);
+            }
+            stack.extend(node.children.iter().rev().copied());
+        }
+        nodes
+    }
+
+    fn set_flags(&mut self, handle: NodeHandle, f: impl FnOnce(&mut Node)) -> bool {
+        match self.nodes.get_mut(handle) {
+            Some(node) => {
+                f(node);
+                self.update_flags(handle);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Updates the effective flags of the node and its sub-tree from the flags of its parent.
+    fn update_flags(&mut self, handle: NodeHandle) {
+        let mut stack = vec![handle];
+        while let Some(handle) = stack.pop() {
+            let (parent_visible, parent_enabled) = self
+                .nodes
+                .get(handle)
+                .and_then(|node| node.parent)
+                .and_then(|parent| self.nodes.get(parent))
+                .map(|parent| (parent.effective_visible, parent.effective_enabled))
+                .unwrap_or((true, true));
+            let node = self.nodes.get_mut(handle).unwrap();
+            node.effective_visible = node.visible && (parent_visible || !node.inherit_visibility);
+            node.effective_enabled = node.enabled && parent_enabled;
+            stack.extend(node.children.iter().copied());
+        }
+    }
+
+    fn attach(&mut self, handle: NodeHandle, parent: Option<NodeHandle>) {
+        match parent {
+            Some(parent) => self.nodes.get_mut(parent).unwrap().children.push(handle),
+            None => self.roots.push(handle),
+        }
+        let node = self.nodes.get_mut(handle).unwrap();
+        node.parent = parent;
+        node.dirty = true;
+        self.update_flags(handle);
+    }
+
+    fn detach(&mut self, handle: NodeHandle) {
+        match self.nodes.get(handle).and_then(|node| node.parent) {
+            Some(parent) => {
+                if let Some(parent) = self.nodes.get_mut(parent) {
+                    parent.children.retain(|c| *c != handle);
+                }
+            }
+            None => self.roots.retain(|r| *r != handle),
+        }
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,156 @@
+use crate::renderer::*;
+use std::collections::BTreeSet;
+
+///
+/// A set of preprocessor defines, for example `USE_UV1` or `SKINNED`, which are prepended to the shader source of a [Material] or [Geometry]
+/// as `#define NAME` so that custom shader code can enable features using `#ifdef NAME`.
+///
+/// The defines are part of the key used to cache the compiled programs, so each combination of defines is only compiled once
+/// and enabling a define on one object does not force recompilation of the programs used by other objects.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShaderDefines {
+    names: BTreeSet<String>,
+}
+
+impl ShaderDefines {
+    ///
+    /// Creates a new set of defines with the given names.
+    ///
+    pub fn new<S: Into<String>>(names: impl IntoIterator<Item = S>) -> Self {
+        let mut defines = Self::default();
+        for name in names {
+            defines.insert(name);
+        }
+        defines
+    }
+
+    ///
+    /// Adds the define with the given name. The name must be a valid preprocessor identifier.
+    ///
+    pub fn insert(&mut self, name: impl Into<String>) {
+        let name = name.into();
+        assert!(
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            "the shader define {} must only contain ascii letters, digits and underscores and must not start with a digit",
+            name
+        );
+        self.names.insert(name);
+    }
+
+    ///
+    /// Removes the define with the given name.
+    ///
+    pub fn remove(&mut self, name: &str) {
+        self.names.remove(name);
+    }
+
+    ///
+    /// Returns whether or not the define with the given name is set.
+    ///
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    ///
+    /// Returns whether or not no defines are set.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    ///
+    /// Returns an iterator over the names of the defines in alphabetical order.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(|name| name.as_str())
+    }
+
+    ///
+    /// Adds all the defines in the other set to this set.
+    ///
+    pub fn extend(&mut self, other: &ShaderDefines) {
+        self.names.extend(other.names.iter().cloned());
+    }
+
+    ///
+    /// Returns the `#define NAME` lines which are prepended to the shader source.
+    ///
+    pub fn source(&self) -> String {
+        self.names
+            .iter()
+            .map(|name| format!("#define {}\n", name))
+            .collect()
+    }
+}
+
+///
+/// Wraps a [Material] and adds the given [ShaderDefines] to the fragment shader source of the material.
+/// This makes it possible to enable a feature on a single object, for example `Gm::new(mesh, WithDefines::new(material, ["USE_UV1"]))`,
+/// without writing a separate material for each combination of features.
+///
+pub struct WithDefines<M: Material> {
+    /// The wrapped material.
+    pub material: M,
+    /// The defines added to the shader source of the wrapped material.
+    pub defines: ShaderDefines,
+}
+
+impl<M: Material> WithDefines<M> {
+    ///
+    /// Wraps the given material and adds the defines with the given names.
+    ///
+    pub fn new<S: Into<String>>(material: M, names: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            material,
+            defines: ShaderDefines::new(names),
+        }
+    }
+}
+
+impl<M: Material> std::ops::Deref for WithDefines<M> {
+    type Target = M;
+    fn deref(&self) -> &Self::Target {
+        &self.material
+    }
+}
+
+impl<M: Material> std::ops::DerefMut for WithDefines<M> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.material
+    }
+}
+
+impl<M: Material> Material for WithDefines<M> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        self.material.fragment_shader_source(lights)
+    }
+
+    fn id(&self) -> u16 {
+        self.material.id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.material.fragment_attributes()
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.material.use_uniforms(program, camera, lights)
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.material.render_states()
+    }
+
+    fn material_type(&self) -> MaterialType {
+        self.material.material_type()
+    }
+
+    fn shader_defines(&self) -> ShaderDefines {
+        let mut defines = self.material.shader_defines();
+        defines.extend(&self.defines);
+        defines
+    }
+}
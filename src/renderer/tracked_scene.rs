@@ -0,0 +1,304 @@
+use crate::renderer::*;
+
+///
+/// A reversible record of a change to a [TrackedScene].
+/// The records are stored by the scene and used to implement [TrackedScene::undo] and [TrackedScene::redo],
+/// but can also be inspected using [TrackedScene::changes], for example to show a history in an editor.
+///
+pub enum SceneChange<M: Material + Clone> {
+    /// An object was added to the scene. The object is stored in the record while the addition is undone.
+    Added {
+        /// The handle to the added object.
+        handle: Handle<Gm<Mesh, M>>,
+        /// The object if the addition has been undone, otherwise `None`.
+        object: Option<Gm<Mesh, M>>,
+    },
+    /// An object was removed from the scene. The object is stored in the record while the removal is applied.
+    Removed {
+        /// The handle to the removed object.
+        handle: Handle<Gm<Mesh, M>>,
+        /// The object if the removal is applied, otherwise `None`.
+        object: Option<Gm<Mesh, M>>,
+    },
+    /// The transformation of an object was changed.
+    Transformation {
+        /// The handle to the changed object.
+        handle: Handle<Gm<Mesh, M>>,
+        /// The transformation before the change.
+        before: Mat4,
+        /// The transformation after the change.
+        after: Mat4,
+    },
+    /// One or more material parameters of an object were changed.
+    Material {
+        /// The handle to the changed object.
+        handle: Handle<Gm<Mesh, M>>,
+        /// The material before the change.
+        before: M,
+        /// The material after the change.
+        after: M,
+    },
+}
+
+impl<M: Material + Clone> SceneChange<M> {
+    ///
+    /// Returns the handle to the object affected by this change.
+    ///
+    pub fn handle(&self) -> Handle<Gm<Mesh, M>> {
+        match self {
+            Self::Added { handle, .. }
+            | Self::Removed { handle, .. }
+            | Self::Transformation { handle, .. }
+            | Self::Material { handle, .. } => *handle,
+        }
+    }
+
+    fn undo(&mut self, objects: &mut ResourcePool<Gm<Mesh, M>>) {
+        match self {
+            Self::Added { handle, object } => *object = objects.remove(*handle),
+            Self::Removed { handle, object } => Self::restore(objects, *handle, object),
+            Self::Transformation { handle, before, .. } => {
+                if let Some(o) = objects.get_mut(*handle) {
+                    o.geometry.set_transformation(*before);
+                }
+            }
+            Self::Material { handle, before, .. } => {
+                if let Some(o) = objects.get_mut(*handle) {
+                    o.material = before.clone();
+                }
+            }
+        }
+    }
+
+    fn redo(&mut self, objects: &mut ResourcePool<Gm<Mesh, M>>) {
+        match self {
+            Self::Added { handle, object } => Self::restore(objects, *handle, object),
+            Self::Removed { handle, object } => *object = objects.remove(*handle),
+            Self::Transformation { handle, after, .. } => {
+                if let Some(o) = objects.get_mut(*handle) {
+                    o.geometry.set_transformation(*after);
+                }
+            }
+            Self::Material { handle, after, .. } => {
+                if let Some(o) = objects.get_mut(*handle) {
+                    o.material = after.clone();
+                }
+            }
+        }
+    }
+
+    fn restore(
+        objects: &mut ResourcePool<Gm<Mesh, M>>,
+        handle: Handle<Gm<Mesh, M>>,
+        object: &mut Option<Gm<Mesh, M>>,
+    ) {
+        if let Some(o) = object.take() {
+            if let Err(o) = objects.restore(handle, o) {
+                *object = Some(o);
+            }
+        }
+    }
+}
+
+///
+/// A set of objects, each consisting of a [Mesh] and a material, with an optional change-tracking layer.
+/// When tracking is enabled, each change made through the scene, ie. adding or removing an object, changing the transformation of an object
+/// or changing the material parameters of an object, is recorded as a reversible [SceneChange].
+/// This makes it possible to implement undo and redo in an editor style application using [TrackedScene::undo] and [TrackedScene::redo].
+///
+/// The objects are identified by [Handle]s which stay valid when a removal is undone.
+///
+pub struct TrackedScene<M: Material + Clone> {
+    objects: ResourcePool<Gm<Mesh, M>>,
+    undo_stack: Vec<SceneChange<M>>,
+    redo_stack: Vec<SceneChange<M>>,
+    tracking: bool,
+}
+
+impl<M: Material + Clone> TrackedScene<M> {
+    ///
+    /// Creates a new empty scene with change tracking enabled.
+    ///
+    pub fn new() -> Self {
+        Self {
+            objects: ResourcePool::new(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            tracking: true,
+        }
+    }
+
+    ///
+    /// Enables or disables the change tracking. Changes made while the tracking is disabled cannot be undone.
+    ///
+    pub fn set_tracking(&mut self, tracking: bool) {
+        self.tracking = tracking;
+    }
+
+    ///
+    /// Returns whether or not the changes to the scene are tracked.
+    ///
+    pub fn is_tracking(&self) -> bool {
+        self.tracking
+    }
+
+    ///
+    /// Returns the objects in the scene, for example to render them using [ResourcePool::values].
+    ///
+    pub fn objects(&self) -> &ResourcePool<Gm<Mesh, M>> {
+        &self.objects
+    }
+
+    ///
+    /// Returns the object with the given handle or `None` if the object is not in the scene.
+    ///
+    pub fn get(&self, handle: Handle<Gm<Mesh, M>>) -> Option<&Gm<Mesh, M>> {
+        self.objects.get(handle)
+    }
+
+    ///
+    /// Adds the object to the scene and returns a handle to it.
+    ///
+    pub fn add(&mut self, object: Gm<Mesh, M>) -> Handle<Gm<Mesh, M>> {
+        let handle = self.objects.insert(object);
+        self.record(SceneChange::Added {
+            handle,
+            object: None,
+        });
+        handle
+    }
+
+    ///
+    /// Removes the object with the given handle from the scene. Returns false if the object is not in the scene.
+    /// If the change tracking is enabled, the object is kept alive in the change record so the removal can be undone.
+    ///
+    pub fn remove(&mut self, handle: Handle<Gm<Mesh, M>>) -> bool {
+        match self.objects.remove(handle) {
+            Some(object) => {
+                self.record(SceneChange::Removed {
+                    handle,
+                    object: Some(object),
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Sets the transformation of the object with the given handle. Returns false if the object is not in the scene.
+    ///
+    pub fn set_transformation(
+        &mut self,
+        handle: Handle<Gm<Mesh, M>>,
+        transformation: Mat4,
+    ) -> bool {
+        match self.objects.get_mut(handle) {
+            Some(object) => {
+                let before = object.geometry.transformation();
+                object.geometry.set_transformation(transformation);
+                self.record(SceneChange::Transformation {
+                    handle,
+                    before,
+                    after: transformation,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Changes the material parameters of the object with the given handle using the given function. Returns false if the object is not in the scene.
+    ///
+    pub fn update_material(
+        &mut self,
+        handle: Handle<Gm<Mesh, M>>,
+        update: impl FnOnce(&mut M),
+    ) -> bool {
+        match self.objects.get_mut(handle) {
+            Some(object) => {
+                let before = object.material.clone();
+                update(&mut object.material);
+                let after = object.material.clone();
+                self.record(SceneChange::Material {
+                    handle,
+                    before,
+                    after,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Reverts the latest tracked change. Returns false if there is nothing to undo.
+    ///
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(mut change) => {
+                change.undo(&mut self.objects);
+                self.redo_stack.push(change);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Applies the latest undone change again. Returns false if there is nothing to redo.
+    ///
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(mut change) => {
+                change.redo(&mut self.objects);
+                self.undo_stack.push(change);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Returns whether or not there is a change to undo.
+    ///
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    ///
+    /// Returns whether or not there is a change to redo.
+    ///
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    ///
+    /// Returns the tracked changes that can be undone, ordered from the oldest to the latest change.
+    ///
+    pub fn changes(&self) -> &[SceneChange<M>] {
+        &self.undo_stack
+    }
+
+    ///
+    /// Removes all tracked changes, which also releases the objects kept alive by the change records.
+    ///
+    pub fn clear_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    fn record(&mut self, change: SceneChange<M>) {
+        if self.tracking {
+            self.redo_stack.clear();
+            self.undo_stack.push(change);
+        }
+    }
+}
+
+impl<M: Material + Clone> Default for TrackedScene<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,134 @@
+use crate::renderer::*;
+
+///
+/// How an [UpdateScheduler] chooses which items to update in a frame.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SchedulingPolicy {
+    /// Cycles through the items, so each item is updated every `item count / budget` frames.
+    #[default]
+    RoundRobin,
+    /// Updates the items with the highest priority, for example the screen coverage given by [Camera::screen_coverage],
+    /// multiplied by the number of frames since the item was last updated, so items with a low priority are also eventually updated.
+    Priority,
+}
+
+///
+/// Spreads expensive updates, for example re-rendering dynamic reflections or shadow maps, over several frames
+/// by choosing at most a budgeted number of items to update each frame, which keeps the frame time stable in complex scenes.
+///
+/// The scheduler does not own the items, it only keeps track of how many frames ago each item was updated.
+/// The items are identified by their index, so the same order must be used each frame; call [UpdateScheduler::reset] if the items change.
+/// Items which have never been updated or for which [UpdateScheduler::request_update] has been called are always updated first.
+///
+#[derive(Clone, Debug)]
+pub struct UpdateScheduler {
+    /// The maximum number of items to update each frame.
+    pub budget: usize,
+    /// How the items to update are chosen.
+    pub policy: SchedulingPolicy,
+    next: usize,
+    // The number of frames since each item was updated, where u32::MAX means that the item must be updated
+    ages: Vec<u32>,
+}
+
+impl UpdateScheduler {
+    ///
+    /// Creates a new scheduler which updates at most `budget` items each frame using the given policy.
+    ///
+    pub fn new(budget: usize, policy: SchedulingPolicy) -> Self {
+        Self {
+            budget,
+            policy,
+            next: 0,
+            ages: Vec::new(),
+        }
+    }
+
+    ///
+    /// Forgets when the items were updated, so all items are updated as soon as the budget allows.
+    ///
+    pub fn reset(&mut self) {
+        self.next = 0;
+        self.ages.clear();
+    }
+
+    ///
+    /// Makes sure the item with the given index is updated as soon as the budget allows, for example because it has moved.
+    ///
+    pub fn request_update(&mut self, index: usize) {
+        if index >= self.ages.len() {
+            self.ages.resize(index + 1, u32::MAX);
+        }
+        self.ages[index] = u32::MAX;
+    }
+
+    ///
+    /// Returns the indices of the items to update this frame, given the priority of each item, which is only used by [SchedulingPolicy::Priority].
+    /// Call this once per frame and update the returned items.
+    ///
+    pub fn schedule(&mut self, priorities: &[f32]) -> Vec<usize> {
+        let count = priorities.len();
+        self.ages.resize(count, u32::MAX);
+        let budget = self.budget.min(count);
+        let mut selected: Vec<usize> = (0..count)
+            .filter(|i| self.ages[*i] == u32::MAX)
+            .take(budget)
+            .collect();
+        match self.policy {
+            SchedulingPolicy::RoundRobin => {
+                while selected.len() < budget {
+                    let index = self.next % count;
+                    if !selected.contains(&index) {
+                        selected.push(index);
+                    }
+                    self.next = (index + 1) % count;
+                }
+            }
+            SchedulingPolicy::Priority => {
+                let mut candidates: Vec<(usize, f32)> = (0..count)
+                    .filter(|i| !selected.contains(i))
+                    .map(|i| (i, priorities[i].max(0.0) * (self.ages[i] as f32 + 1.0)))
+                    .collect();
+                candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+                let remaining = budget - selected.len();
+                selected.extend(candidates.into_iter().take(remaining).map(|(i, _)| i));
+            }
+        }
+        for age in self.ages.iter_mut() {
+            *age = age.saturating_add(1);
+        }
+        for i in selected.iter() {
+            self.ages[*i] = 0;
+        }
+        selected
+    }
+
+    ///
+    /// Regenerates the shadow maps of the lights chosen by this scheduler with the given texture size using the given geometries as shadow casters.
+    /// The priority of each light is the screen coverage of the volume of the light (see [Light::volume]) as seen from the given camera,
+    /// where lights without a volume, for example directional lights, cover the entire screen.
+    /// Returns the indices of the updated lights.
+    ///
+    pub fn update_shadow_maps(
+        &mut self,
+        camera: &Camera,
+        lights: &mut [&mut dyn ShadowCaster],
+        texture_size: Option<u32>,
+        geometries: &[&dyn Geometry],
+    ) -> Vec<usize> {
+        let priorities: Vec<f32> = lights
+            .iter()
+            .map(|light| {
+                light
+                    .volume()
+                    .map_or(1.0, |volume| camera.screen_coverage(&volume.aabb()))
+            })
+            .collect();
+        let indices = self.schedule(&priorities);
+        for i in indices.iter() {
+            lights[*i].update_shadow_map(texture_size, geometries);
+        }
+        indices
+    }
+}
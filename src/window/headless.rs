@@ -1,3 +1,4 @@
+use crate::renderer::*;
 use crate::{Context, CoreError};
 use glutin_029::{
     dpi::PhysicalSize, event_loop::EventLoop, ContextBuilder, ContextCurrentState, CreationError,
@@ -50,6 +51,59 @@ impl HeadlessContext {
             _glutin_context: Rc::new(glutin_context),
         })
     }
+
+    ///
+    /// Renders a single frame of the given objects with the given camera and lights into an offscreen image with the given size in pixels
+    /// and returns the result as RGBA data, for example to compare against a reference image in a golden-image test.
+    ///
+    /// The frame is rendered deterministically, so the same input gives the same result each time on the same machine:
+    /// - The viewport of the camera is set to the full image and the image is cleared to transparent black before rendering.
+    /// - Multisampling and dithering are disabled and no temporal effects, for example [TemporalUpsampler], are applied.
+    ///
+    /// Objects with their own random state, for example [GpuParticleSystem], are deterministic given the same sequence of updates.
+    ///
+    #[allow(unsafe_code)]
+    pub fn render_single_frame(
+        &self,
+        camera: &Camera,
+        objects: impl IntoIterator<Item = impl Object>,
+        lights: &[&dyn Light],
+        width: u32,
+        height: u32,
+    ) -> CpuTexture {
+        let mut camera = camera.clone();
+        camera.set_viewport(Viewport::new_at_origo(width, height));
+        let mut color = Texture2D::new_empty::<[u8; 4]>(
+            &self.context,
+            width,
+            height,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let mut depth = DepthTexture2D::new::<f32>(
+            &self.context,
+            width,
+            height,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let dither = unsafe { self.context.is_enabled(crate::context::DITHER) };
+        unsafe {
+            self.context.disable(crate::context::DITHER);
+        }
+        RenderTarget::new(color.as_color_target(None), depth.as_depth_target())
+            .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
+            .render(&camera, objects, lights);
+        if dither {
+            unsafe {
+                self.context.enable(crate::context::DITHER);
+            }
+        }
+        color.read_to_cpu_texture()
+    }
 }
 
 impl std::ops::Deref for HeadlessContext {
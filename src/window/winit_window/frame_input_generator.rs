@@ -72,6 +72,7 @@ impl FrameInputGenerator {
             duration.as_secs() as f64 * 1000.0 + duration.subsec_nanos() as f64 * 1e-6;
         self.accumulated_time += elapsed_time;
         self.last_time = now;
+        context.set_time(self.accumulated_time * 0.001);
 
         let frame_input = FrameInput {
             events: self.events.drain(..).collect(),
@@ -319,15 +320,12 @@ impl FrameInputGenerator {
                         if self.finger_id.map(|id| id == touch.id).unwrap_or(false) {
                             let last_pos = self.cursor_pos.unwrap();
                             if let Some(p) = self.secondary_cursor_pos {
-                                self.events.push(crate::Event::MouseWheel {
-                                    position: position.into(),
-                                    modifiers: self.modifiers,
-                                    handled: false,
-                                    delta: (
-                                        (position.x - p.x).abs() - (last_pos.x - p.x).abs(),
-                                        (position.y - p.y).abs() - (last_pos.y - p.y).abs(),
-                                    ),
-                                });
+                                self.events.push(pinch_event(
+                                    position,
+                                    last_pos,
+                                    p,
+                                    self.modifiers,
+                                ));
                             } else {
                                 self.events.push(crate::Event::MouseMotion {
                                     button: Some(MouseButton::Left),
@@ -345,15 +343,12 @@ impl FrameInputGenerator {
                         {
                             let last_pos = self.secondary_cursor_pos.unwrap();
                             if let Some(p) = self.cursor_pos {
-                                self.events.push(crate::Event::MouseWheel {
-                                    position: p.into(),
-                                    modifiers: self.modifiers,
-                                    handled: false,
-                                    delta: (
-                                        (position.x - p.x).abs() - (last_pos.x - p.x).abs(),
-                                        (position.y - p.y).abs() - (last_pos.y - p.y).abs(),
-                                    ),
-                                });
+                                self.events.push(pinch_event(
+                                    position,
+                                    last_pos,
+                                    p,
+                                    self.modifiers,
+                                ));
                             }
                             self.secondary_cursor_pos = Some(position);
                         }
@@ -365,6 +360,32 @@ impl FrameInputGenerator {
     }
 }
 
+// Creates a pinch event when one finger moves from the last position to the new position while the other finger stays at the given position.
+fn pinch_event(
+    position: LogicalPoint,
+    last_pos: LogicalPoint,
+    other: LogicalPoint,
+    modifiers: Modifiers,
+) -> crate::Event {
+    let distance = |p: LogicalPoint| ((p.x - other.x).powi(2) + (p.y - other.y).powi(2)).sqrt();
+    crate::Event::Pinch {
+        delta: distance(position) - distance(last_pos),
+        // The point between the two fingers moves half as much as the finger
+        translation: (
+            0.5 * (position.x - last_pos.x),
+            0.5 * (position.y - last_pos.y),
+        ),
+        position: LogicalPoint {
+            x: 0.5 * (position.x + other.x),
+            y: 0.5 * (position.y + other.y),
+            ..position
+        }
+        .into(),
+        modifiers,
+        handled: false,
+    }
+}
+
 fn is_printable_char(chr: char) -> bool {
     let is_in_private_use_area = ('\u{e000}'..='\u{f8ff}').contains(&chr)
         || ('\u{f0000}'..='\u{ffffd}').contains(&chr)
@@ -40,6 +40,11 @@ pub struct SurfaceSettings {
     /// Specify whether or not hardware acceleration is preferred, required, or
     /// off. The default is [HardwareAcceleration::Preferred].
     pub hardware_acceleration: HardwareAcceleration,
+    /// Specify whether or not to fall back to a WebGL 1 context if WebGL 2 is not supported, instead of failing.
+    /// The context then runs in a compatibility mode with reduced functionality, see [Context::is_legacy](crate::Context::is_legacy).
+    /// The default is false.
+    /// Only has an effect on web.
+    pub webgl1_fallback: bool,
 }
 
 impl Default for SurfaceSettings {
@@ -50,6 +55,7 @@ impl Default for SurfaceSettings {
             stencil_buffer: 0,
             multisamples: 4,
             hardware_acceleration: HardwareAcceleration::Preferred,
+            webgl1_fallback: false,
         }
     }
 }
@@ -35,27 +35,57 @@ mod inner {
         ) -> Result<Self, WindowError> {
             let canvas = window.canvas();
 
+            let options = serde_wasm_bindgen::to_value(&ContextOpt {
+                antialias: settings.multisamples > 0,
+                depth: settings.depth_buffer > 0,
+                stencil: settings.stencil_buffer > 0,
+                willReadFrequently: match settings.hardware_acceleration {
+                    HardwareAcceleration::Required => false,
+                    HardwareAcceleration::Preferred => false,
+                    HardwareAcceleration::Off => true,
+                },
+                alpha: false,
+            })
+            .unwrap();
+
             // get webgl context and verify extensions
-            let webgl_context = canvas
-                .get_context_with_context_options(
-                    "webgl2",
-                    &serde_wasm_bindgen::to_value(&ContextOpt {
-                        antialias: settings.multisamples > 0,
-                        depth: settings.depth_buffer > 0,
-                        stencil: settings.stencil_buffer > 0,
-                        willReadFrequently: match settings.hardware_acceleration {
-                            HardwareAcceleration::Required => false,
-                            HardwareAcceleration::Preferred => false,
-                            HardwareAcceleration::Off => true,
-                        },
-                        alpha: false,
-                    })
-                    .unwrap(),
-                )
-                .map_err(|e| WindowError::WebGL2NotSupported(format!(": {:?}", e)))?
-                .ok_or(WindowError::WebGL2NotSupported("".to_string()))?
-                .dyn_into::<web_sys::WebGl2RenderingContext>()
-                .map_err(|e| WindowError::WebGL2NotSupported(format!(": {:?}", e)))?;
+            let webgl2_context = canvas
+                .get_context_with_context_options("webgl2", &options)
+                .map_err(|e| WindowError::WebGL2NotSupported(format!(": {:?}", e)))
+                .and_then(|c| c.ok_or(WindowError::WebGL2NotSupported("".to_string())))
+                .and_then(|c| {
+                    c.dyn_into::<web_sys::WebGl2RenderingContext>()
+                        .map_err(|e| WindowError::WebGL2NotSupported(format!(": {:?}", e)))
+                });
+            let webgl_context = match webgl2_context {
+                Ok(webgl_context) => webgl_context,
+                Err(e) if settings.webgl1_fallback => {
+                    let webgl_context = canvas
+                        .get_context_with_context_options("webgl", &options)
+                        .ok()
+                        .flatten()
+                        .and_then(|c| c.dyn_into::<web_sys::WebGlRenderingContext>().ok())
+                        .ok_or(e)?;
+                    // Optional extensions, the content is expected to check the capabilities of the context
+                    for extension in [
+                        "OES_texture_float",
+                        "OES_texture_half_float",
+                        "OES_texture_float_linear",
+                        "OES_texture_half_float_linear",
+                        "OES_standard_derivatives",
+                        "EXT_shader_texture_lod",
+                        "WEBGL_depth_texture",
+                    ] {
+                        let _ = webgl_context.get_extension(extension);
+                    }
+                    return Ok(Self {
+                        context: Context::from_gl_context(Arc::new(
+                            crate::context::Context::from_webgl1_context(webgl_context),
+                        ))?,
+                    });
+                }
+                Err(e) => return Err(e),
+            };
             webgl_context
                 .get_extension("EXT_color_buffer_float")
                 .map_err(|e| WindowError::ColorBufferFloatNotSupported(format!("{:?}", e)))?;